@@ -1,7 +1,8 @@
 // Type aliases used everywhere in the engine. Always import this
 use rayna_engine::core::types::*;
 
-use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::aperture::Aperture;
+use rayna_engine::scene::camera::{Camera, Projection, Shutter};
 /// Creates a camera object, that controls where the image is rendered from.
 ///
 /// See [Camera] for documentation for the fields a camera has.
@@ -25,8 +26,17 @@ pub fn create_camera() -> Camera {
         pos,
         v_fov,
         fwd,
+        up: Vector3::Y,
         focus_dist,
         defocus_angle,
+        motion: None,
+        projection: Projection::Perspective,
+        physical: None,
+        aperture: Aperture::Circle,
+        distortion: None,
+        tilt_shift: None,
+        shutter: Shutter::INSTANT,
+        stereo: None,
     };
 
     return camera;
@@ -117,9 +127,13 @@ pub fn create_scene() -> StandardScene {
 
 // `Renderer` is the main struct that does the rendering
 use rayna_engine::render::renderer::Renderer;
-// These two control how the image is rendered
+// These control how the image is rendered
 use rand::rngs::SmallRng;
+use rayna_engine::render::filter::PixelFilter;
+use rayna_engine::render::postprocess::tone_mapping::ToneMapping;
+use rayna_engine::render::postprocess::white_balance::WhiteBalance;
 use rayna_engine::render::render_opts::{RenderMode, RenderOpts};
+use rayna_engine::shared::rng::SamplerKind;
 
 /// Here we create the renderer, using the scene and camera we created earlier.
 /// Due to future-compatibility reasons, the renderer takes ownership of them.
@@ -136,9 +150,28 @@ pub fn create_renderer(
         width: nonzero::nonzero!(200_usize),       // Image Dimensions
         height: nonzero::nonzero!(200_usize),      // Image Dimensions
         samples: nonzero::nonzero!(1_usize),       // Sample each pixel multiple times
+        target_samples: None,                      // Keep accumulating for as long as we render
+        max_time: None,                            // No matter how long that takes
+        progressive_preview: false,                // Every pass is accumulated
+        temporal_reprojection: false,              // The camera doesn't move
+        sampler: SamplerKind::Sobol,               // Spread the samples out evenly, so they converge faster
+        filter: PixelFilter::Mitchell,             // Smooth jagged edges without blurring the image
         mode: RenderMode::PBR,                     // Make normal renders
+        clay: false,                               // Keep the materials
         ray_depth: 3,                              // Bounce three times
         ray_branching: nonzero::nonzero!(1_usize), // Ignore this; advanced and probably useless
+        robust_intersections: false,               // Only needed when debugging precision issues
+        bvh_stats: false,                          // Only needed when profiling the BVHs
+        intersection_capacity: 8,                  // Ignore this; only matters for complex CSG
+        deterministic: false,                      // Only needed for reproducible renders
+        aovs: false,                               // Only needed for denoising or compositing
+        variance: false,                           // Only needed to see how converged the render is
+        transparent_sky: false,                    // Show the sky behind the objects
+        auto_exposure: None,                       // Keep the raw (linear) brightness
+        exposure: 0.,                              // Don't brighten or darken the image
+        white_balance: WhiteBalance::NEUTRAL,      // The lights are already white
+        denoise: None,                             // Keep the noise, it goes away as the frames accumulate
+        tone_mapping: ToneMapping::AcesFitted,     // Bright lights fade to white instead of clipping
     };
     return Renderer::new_from(scene, camera, render_options, 2).unwrap();
 }
@@ -0,0 +1,74 @@
+//! # Module [crate::core::capabilities]
+//!
+//! Reports what this build of the engine supports, so that frontends (the UI, command-line tools, and remote render
+//! workers) can display it, and check they are compatible before sending work. See [capabilities()]
+
+use crate::core::types::{Channel, Number};
+use crate::render::aov::Aov;
+use crate::render::bake::BakeChannel;
+use crate::render::render_opts::RenderMode;
+use serde::Serialize;
+use std::mem::size_of;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// A device that the engine can render on
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum Backend {
+    /// Multi-threaded rendering on the CPU
+    Cpu,
+}
+
+/// Information about what this build of the engine supports. See [capabilities()]
+#[derive(Clone, Debug, Valuable, Serialize)]
+pub struct Capabilities {
+    /// The version of the engine crate
+    pub version: &'static str,
+    /// The number of bits used for geometry calculations (the size of [Number])
+    pub number_bits: usize,
+    /// The number of bits used for each colour channel (the size of [Channel])
+    pub channel_bits: usize,
+    /// The widest SIMD registers that the engine was compiled to use (bits), or zero if SIMD is unavailable
+    pub simd_width: usize,
+    /// How many [Number]s fit in a SIMD register, which is the most efficient size for batched intersections
+    pub simd_lanes: usize,
+    /// Whether debug validation (see [crate::shared::validate]) is enabled.
+    ///
+    /// This is only enabled for debug builds, and makes rendering much slower
+    pub validation: bool,
+    pub backends: Vec<Backend>,
+    pub render_modes: Vec<RenderMode>,
+    pub aovs: Vec<Aov>,
+    pub bake_channels: Vec<BakeChannel>,
+}
+
+/// Returns what this build of the engine supports
+pub fn capabilities() -> Capabilities {
+    let simd_width = if cfg!(target_feature = "avx512f") {
+        512
+    } else if cfg!(target_feature = "avx") {
+        256
+    } else if cfg!(any(
+        target_feature = "sse2",
+        target_feature = "neon",
+        target_feature = "simd128"
+    )) {
+        128
+    } else {
+        0
+    };
+
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        number_bits: size_of::<Number>() * 8,
+        channel_bits: size_of::<Channel>() * 8,
+        simd_width,
+        simd_lanes: simd_width / (size_of::<Number>() * 8),
+        validation: cfg!(debug_assertions),
+        backends: Backend::iter().collect(),
+        render_modes: RenderMode::iter().collect(),
+        aovs: Aov::iter().collect(),
+        bake_channels: BakeChannel::iter().collect(),
+    }
+}
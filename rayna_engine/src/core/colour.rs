@@ -1,5 +1,6 @@
 use crate::core::types::{Channel, Number};
 use crate::impl_op_assign;
+use crate::shared::math::Lerp;
 use crate::{forward_fn, impl_op};
 use itertools::Itertools;
 use std::array;
@@ -59,6 +60,77 @@ impl Colour<3> {
 
 // endregion Known Colours
 
+// region Heatmaps
+
+impl Colour<3> {
+    /// A heatmap that goes from black (`0.0`), through blue, green and yellow, up to red (`1.0`)
+    pub fn heatmap(heat: Number) -> Self {
+        const HEAT: [Colour<3>; 5] = [
+            Colour::new([0.0, 0.0, 0.0]),
+            Colour::new([0.0, 0.0, 1.0]),
+            Colour::new([0.0, 1.0, 0.0]),
+            Colour::new([1.0, 1.0, 0.0]),
+            Colour::new([1.0, 0.0, 0.0]),
+        ];
+
+        let val = heat.clamp(0., 1.) * (HEAT.len() - 1) as Number;
+        let (floor, ceil) = (val.floor(), val.ceil());
+        Colour::lerp(HEAT[floor as usize], HEAT[ceil as usize], val - floor)
+    }
+}
+
+// endregion Heatmaps
+
+// region Colour Spaces
+
+/// Converts a channel from the (non-linear) sRGB encoding into linear light, using the exact sRGB transfer function.
+///
+/// Most image files (PNG, JPEG, etc.) are sRGB-encoded, but the renderer works with linear colours, so images must be
+/// converted when they are loaded
+pub fn srgb_to_linear(c: Channel) -> Channel {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a channel from linear light into the (non-linear) sRGB encoding, using the exact sRGB transfer function.
+/// This is the inverse of [srgb_to_linear()].
+///
+/// The value is clamped to `0.0..=1.0` first, since that's all sRGB can store. This should be done exactly once,
+/// when the image is displayed or saved
+pub fn linear_to_srgb(c: Channel) -> Channel {
+    let c = c.clamp(0., 1.);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        (1.055 * c.powf(1. / 2.4)) - 0.055
+    }
+}
+
+/// How the values of an image file are encoded
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ColourSpace {
+    /// Encoded with the sRGB transfer function, as almost all images of colours (photos, albedo textures) are. They
+    /// are converted into linear light when they are loaded
+    #[default]
+    Srgb,
+    /// Already linear, so the values are kept as they are. This is for data textures that don't hold colours, such
+    /// as normal maps, roughness maps and masks
+    Linear,
+}
+
+impl<const N: usize> Colour<N> {
+    /// Converts each channel of an sRGB-encoded colour into linear light, see [srgb_to_linear()]
+    pub fn srgb_to_linear(&self) -> Self { self.map(srgb_to_linear) }
+
+    /// Converts each channel of a linear colour into the sRGB encoding, see [linear_to_srgb()]
+    pub fn linear_to_srgb(&self) -> Self { self.map(linear_to_srgb) }
+}
+
+// endregion Colour Spaces
+
 // region To/From impls
 
 impl<const N: usize> const From<[Channel; N]> for Colour<N> {
@@ -0,0 +1,156 @@
+use crate::core::types::{Number, Point3, Vector3};
+use crate::shared::aabb::Aabb;
+use derivative::Derivative;
+use getset::{CopyGetters, Getters};
+use ndarray::{s, ArcArray, Ix3, Shape};
+use thiserror::Error;
+
+/// The error for when a [DensityGrid] would have too many voxels to allocate
+#[derive(Error, Copy, Clone, Debug)]
+#[error("grid of {dims:?} voxels is larger than the limit of {max_voxels} voxels")]
+pub struct GridTooLarge {
+    /// The number of voxels the grid would have had along each axis
+    pub dims: [usize; 3],
+    pub max_voxels: usize,
+}
+
+/// A dense 3D grid of density values (**voxels**), such as the smoke or clouds loaded from a VDB file (see
+/// [AssetResolver::load_vdb()](crate::scene::asset::AssetResolver::load_vdb)).
+///
+/// Positions are in **index space**, where each voxel is one unit across, and the centre of the voxel at index
+/// `[i, j, k]` is at `origin + (i, j, k)`. Between the voxel centres, the values are interpolated (trilinear), and
+/// outside the grid the density is zero.
+///
+/// This is cheap to clone, since the voxels are shared
+#[derive(CopyGetters, Getters, Derivative, Clone)]
+#[derivative(Debug)]
+pub struct DensityGrid {
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
+    #[get = "pub"]
+    data: ArcArray<f32, Ix3>,
+    /// The index-space position of the centre of the first voxel
+    #[get_copy = "pub"]
+    origin: Point3,
+    /// The largest value in the grid
+    #[get_copy = "pub"]
+    max_value: Number,
+}
+
+// region Constructors
+
+impl DensityGrid {
+    /// Creates a new grid from the voxel values, where the first voxel is centred on the `origin`
+    pub fn new(origin: impl Into<Point3>, data: impl Into<ArcArray<f32, Ix3>>) -> Self {
+        let data = data.into();
+        let max_value = data.iter().copied().fold(0., f32::max) as Number;
+
+        Self {
+            data,
+            origin: origin.into(),
+            max_value,
+        }
+    }
+
+    /// Creates a grid from sparse voxels, given as the index of the voxel and its value.
+    ///
+    /// The grid is just big enough to contain all the voxels, and any voxels that aren't given are zero. If a voxel
+    /// is given more than once, the last value is used
+    ///
+    /// # Panics
+    /// If the grid would have more than [usize::MAX] voxels. See [Self::from_tiles()] to limit the size instead
+    pub fn from_voxels(voxels: impl IntoIterator<Item = ([i64; 3], f32)>) -> Self {
+        let tiles = voxels
+            .into_iter()
+            .map(|(index, value)| (index, 1, value))
+            .collect::<Vec<_>>();
+        Self::from_tiles(&tiles, usize::MAX).expect("grid too large")
+    }
+
+    /// Creates a grid from sparse *tiles*: cubes of voxels that all have the same value, given as the index of the
+    /// first (smallest) voxel in the cube, the number of voxels along each side, and the value.
+    ///
+    /// This is the same as [Self::from_voxels()], with each tile expanded into its voxels, except that it fails if
+    /// the grid would have more than `max_voxels` voxels, before anything is allocated
+    pub fn from_tiles(tiles: &[([i64; 3], u32, f32)], max_voxels: usize) -> Result<Self, GridTooLarge> {
+        if tiles.is_empty() {
+            return Ok(Self::new(Point3::ZERO, ArcArray::zeros(Shape::from(Ix3(0, 0, 0)))));
+        }
+
+        let (mut min, mut max) = ([i64::MAX; 3], [i64::MIN; 3]);
+        for &(index, size, _) in tiles {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(index[axis]);
+                max[axis] = max[axis].max(index[axis] + size as i64 - 1);
+            }
+        }
+
+        let dims = [0, 1, 2].map(|axis| (max[axis] - min[axis] + 1) as usize);
+        let count = dims.iter().try_fold(1_usize, |count, &d| count.checked_mul(d));
+        if !count.is_some_and(|count| count <= max_voxels) {
+            return Err(GridTooLarge { dims, max_voxels });
+        }
+
+        let mut data = ArcArray::zeros(Shape::from(Ix3(dims[0], dims[1], dims[2])));
+        for &(index, size, value) in tiles {
+            let [i, j, k] = [0, 1, 2].map(|axis| (index[axis] - min[axis]) as usize);
+            let size = size as usize;
+            data.slice_mut(s![i..i + size, j..j + size, k..k + size]).fill(value);
+        }
+
+        Ok(Self::new(min.map(|i| i as Number), data))
+    }
+}
+
+// endregion Constructors
+
+// region Sampling
+
+impl DensityGrid {
+    /// The number of voxels along each axis
+    pub fn dims(&self) -> [usize; 3] {
+        let (x, y, z) = self.data.dim();
+        [x, y, z]
+    }
+
+    /// The bounds of the grid (in index space), including the whole of each voxel on the edges. This is [None] if the
+    /// grid is empty
+    pub fn bounds(&self) -> Option<Aabb> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let size = Vector3::from(self.dims().map(|d| d as Number));
+        let min = self.origin - Vector3::splat(0.5);
+        Some(Aabb::new(min, min + size))
+    }
+
+    /// Gets the value of the voxel at the given index, which is zero outside the grid
+    pub fn voxel(&self, index: [i64; 3]) -> Number {
+        let dims = self.dims();
+        if (0..3).any(|axis| index[axis] < 0 || index[axis] >= dims[axis] as i64) {
+            return 0.;
+        }
+        self.data[index.map(|i| i as usize)] as Number
+    }
+
+    /// Samples the density at the given (index-space) position, interpolating between the nearest voxels
+    pub fn sample(&self, point: Point3) -> Number {
+        let local = (point - self.origin).to_array();
+        let base = local.map(|x| x.floor());
+        let frac = [0, 1, 2].map(|axis| local[axis] - base[axis]);
+        let base = base.map(|x| x as i64);
+
+        let mut value = 0.;
+        for corner in 0..8 {
+            let offset = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let weight = (0..3)
+                .map(|axis| if offset[axis] == 1 { frac[axis] } else { 1. - frac[axis] })
+                .product::<Number>();
+            if weight > 0. {
+                value += weight * self.voxel([0, 1, 2].map(|axis| base[axis] + offset[axis] as i64));
+            }
+        }
+        value
+    }
+}
+
+// endregion Sampling
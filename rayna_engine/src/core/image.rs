@@ -1,18 +1,22 @@
+use crate::core::colour::ColourSpace;
 use crate::core::types::{Colour, Number};
+use crate::render::aov::{write_layers_exr, Aov, AovExportError};
 use crate::shared::math::Lerp;
 use derivative::Derivative;
 use getset::{CopyGetters, Getters};
 use ndarray::{ArcArray, Ix2, Shape};
+use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
+use std::path::Path;
 
 #[derive(CopyGetters, Getters, Derivative, Clone)]
-#[derivative(Debug)]
+#[derivative(Debug(bound = "Col: Debug + 'static"))]
 pub struct Image<Col = Colour> {
     #[get_copy = "pub"]
     width: usize,
     #[get_copy = "pub"]
     height: usize,
-    #[derivative(Debug = "ignore")]
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
     #[get = "pub"]
     data: ArcArray<Col, Ix2>,
 }
@@ -24,6 +28,14 @@ impl<Col: Clone + Default> Image<Col> {
     pub fn new_blank(width: usize, height: usize) -> Self {
         Self::new(ArcArray::from_elem(Shape::from(Ix2(width, height)), Default::default()))
     }
+
+    /// Makes sure the image has the specified dimensions, replacing it with a blank one if it doesn't. Otherwise, the
+    /// pixels are left as they are, and nothing is allocated
+    pub fn ensure_size(&mut self, width: usize, height: usize) {
+        if self.width != width || self.height != height {
+            *self = Self::new_blank(width, height);
+        }
+    }
 }
 
 impl<Col: Clone> Image<Col> {
@@ -53,13 +65,31 @@ impl<Col> Image<Col> {
 // endregion Constructors
 
 // region From<> for crate `image`
+
+/// Converts an image loaded with the `image` crate into linear colours, assuming the integer formats are sRGB-encoded.
+/// See [Image::from_dynamic()]
 impl From<image::DynamicImage> for Image<Colour> {
-    fn from(img: image::DynamicImage) -> Self {
+    fn from(img: image::DynamicImage) -> Self { Self::from_dynamic(img, ColourSpace::Srgb) }
+}
+
+impl Image<Colour> {
+    /// Converts an image loaded with the `image` crate into linear colours.
+    ///
+    /// Floating-point images (HDR, EXR) are always linear, so they are kept as-is. The integer formats are in the
+    /// `space` that is given: [ColourSpace::Srgb] images are converted to linear with
+    /// [srgb_to_linear()](crate::core::colour::srgb_to_linear), and [ColourSpace::Linear] ones are kept as-is (for
+    /// data textures, such as normal maps)
+    pub fn from_dynamic(img: image::DynamicImage, space: ColourSpace) -> Self {
+        let linear = space == ColourSpace::Linear
+            || matches!(
+                img,
+                image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+            );
         // Try convert into appropriate pixel format
         let img = img.into_rgb32f();
         let (width, height, data) = (img.width() as _, img.height() as _, img.into_raw());
         // Have to transmute the data buffer because it's flattened, which we don't want
-        let data = unsafe {
+        let mut data = unsafe {
             // SAFETY: `Colour` is a wrapper around a `[Channel; Colour::CHANNEL_COUNT]`, so we can safely transmute
             let (ptr, len, cap) = data.into_raw_parts();
             Vec::from_raw_parts(
@@ -68,6 +98,9 @@ impl From<image::DynamicImage> for Image<Colour> {
                 cap / Colour::CHANNEL_COUNT,
             )
         };
+        if !linear {
+            data.iter_mut().for_each(|c| *c = c.srgb_to_linear());
+        }
 
         Self::new(
             // `NDarray` and `image` seem to have different row/column ordering, so swap the axes to compensate
@@ -107,10 +140,40 @@ impl<Col> Image<Col> {
         let c = Col::lerp(cy1, cy2, yl);
         c
     }
+
+    /// Gets the pixel nearest to the coordinates, clamping them to the edges of the image
+    pub fn get_nearest(&self, px: Number, py: Number) -> Col
+    where
+        Col: Clone,
+    {
+        let x = px.round().clamp(0., (self.width - 1) as _) as usize;
+        let y = py.round().clamp(0., (self.height - 1) as _) as usize;
+        self[(x, y)].clone()
+    }
 }
 
 // endregion Pixel Accessors
 
+// region Resampling
+
+impl<Col> Image<Col>
+where
+    Col: Lerp<Number> + Clone,
+{
+    /// Creates a copy of the image at half the size (rounding down, but at least one pixel), where each pixel is the
+    /// average of the 2x2 pixels it covers. For odd sizes, the last row/column is repeated
+    pub fn half_size(&self) -> Self {
+        let px = |x: usize, y: usize| self[(x.min(self.width - 1), y.min(self.height - 1))].clone();
+        let half = |a: Col, b: Col| Col::lerp(a, b, 0.5);
+        Self::from_fn((self.width / 2).max(1), (self.height / 2).max(1), |x, y| {
+            let (x, y) = (x * 2, y * 2);
+            half(half(px(x, y), px(x + 1, y)), half(px(x, y + 1), px(x + 1, y + 1)))
+        })
+    }
+}
+
+// endregion Resampling
+
 // region Deref
 
 impl<Col> Deref for Image<Col> {
@@ -124,4 +187,52 @@ impl<Col> DerefMut for Image<Col> {
 
 // endregion Deref
 
+// region Saving
+
+impl Image<Colour> {
+    /// Saves the image as a 32-bit float OpenEXR file.
+    ///
+    /// Unlike 8-bit formats, this keeps the full (linear, HDR) range of the image, so it can be taken into
+    /// compositing without losing any data. The image is stored in the main layer, as `R`, `G` and `B` channels
+    pub fn save_exr(&self, path: impl AsRef<Path>) -> Result<(), AovExportError> { self.save_exr_with_aovs(&[], path) }
+
+    /// Saves the image as a 32-bit float OpenEXR file, with each of the `aovs` stored in its own layer
+    /// (see [Aov::layer_name()]). See [Self::save_exr()].
+    ///
+    /// All the AOVs must have the same dimensions as the image.
+    pub fn save_exr_with_aovs(&self, aovs: &[(Aov, Image)], path: impl AsRef<Path>) -> Result<(), AovExportError> {
+        self.write_exr(None, aovs, path)
+    }
+
+    /// Saves the image as a 32-bit float OpenEXR file, with an `A` channel in the main layer taken from the `alpha`
+    /// (which should have the alpha in all of its channels, like [Render::alpha](crate::render::render::Render::alpha)).
+    /// See [Self::save_exr_with_aovs()].
+    ///
+    /// EXR images are premultiplied, so the image should already have been multiplied by the alpha.
+    pub fn save_exr_with_alpha(
+        &self,
+        alpha: &Image,
+        aovs: &[(Aov, Image)],
+        path: impl AsRef<Path>,
+    ) -> Result<(), AovExportError> {
+        self.write_exr(Some(alpha), aovs, path)
+    }
+
+    fn write_exr(
+        &self,
+        alpha: Option<&Image>,
+        aovs: &[(Aov, Image)],
+        path: impl AsRef<Path>,
+    ) -> Result<(), AovExportError> {
+        let main = (None, Aov::Beauty.channel_names(), self);
+        let alpha = alpha.map(|alpha| (None, &["A"][..], alpha));
+        let layers = aovs
+            .iter()
+            .map(|(aov, img)| (Some(aov.layer_name()), aov.channel_names(), img));
+        write_layers_exr(std::iter::once(main).chain(alpha).chain(layers), path)
+    }
+}
+
+// endregion Saving
+
 // TODO: Parallel iteration?
@@ -0,0 +1,299 @@
+//! # Module [crate::core::job]
+//!
+//! A simple background job queue for long-running, one-off tasks, such as high-resolution snapshots and render
+//! comparisons.
+//!
+//! Work that is part of rendering each frame (such as [denoising](crate::render::postprocess::denoise)) isn't run
+//! through the queue, since the frame can't be shown until it is done. It already runs on the renderer's thread pool,
+//! and is stopped by the renderer's [CancelToken](crate::render::cancel::CancelToken) instead.
+//!
+//! Jobs are submitted to a [JobQueue], which runs them on its own worker threads, so that they don't block
+//! the render loop (or whatever else submitted them). Each job gets a [JobContext] that it can use to report
+//! its progress, and to check whether it has been cancelled. Submitting a job returns a [JobHandle], which can be used
+//! to query the job's [JobStatus] and progress, or to request cancellation.
+//!
+//! # Cancellation
+//! Cancellation is cooperative: calling [JobHandle::cancel()] only sets a flag, and it is up to the job to
+//! periodically check [JobContext::is_cancelled()] (or [JobContext::check_cancelled()]) and stop early.
+
+use crate::core::targets::JOB;
+use crate::core::types::Number;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use thiserror::Error;
+use tracing::{debug, trace, warn};
+
+/// Unique identifier for a job, within a [JobQueue]
+pub type JobId = u64;
+
+/// The current status of a job
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    /// The job is waiting for a worker to become available
+    Queued,
+    /// The job is currently being run
+    Running,
+    /// The job completed successfully
+    Completed,
+    /// The job was cancelled before it completed
+    Cancelled,
+    /// The job failed, with the given error message
+    Failed(String),
+}
+
+impl JobStatus {
+    /// Whether the job has finished (either successfully or not), and won't change status again
+    pub fn is_finished(&self) -> bool { !matches!(self, Self::Queued | Self::Running) }
+}
+
+/// Error returned by a job that did not complete successfully
+#[derive(Error, Clone, Debug)]
+pub enum JobError {
+    #[error("job was cancelled")]
+    Cancelled,
+    #[error("job failed: {0}")]
+    Failed(String),
+}
+
+/// A snapshot of the state of a job, at a given point in time
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobInfo {
+    pub id: JobId,
+    pub name: String,
+    /// Progress of the job, in the range `0.0..=1.0`
+    pub progress: Number,
+    pub status: JobStatus,
+}
+
+// region Job State
+
+/// State of a job that is shared between the worker running it and all the [JobHandle]s
+#[derive(Debug)]
+struct JobShared {
+    id: JobId,
+    name: String,
+    /// The progress, stored as the bits of a [Number]
+    progress: AtomicU64,
+    cancelled: AtomicBool,
+    status: Mutex<JobStatus>,
+}
+
+impl JobShared {
+    fn set_status(&self, status: JobStatus) { *self.status.lock().expect("job status mutex poisoned") = status; }
+
+    fn status(&self) -> JobStatus { self.status.lock().expect("job status mutex poisoned").clone() }
+}
+
+/// A handle to a job that was submitted to a [JobQueue]
+#[derive(Clone, Debug)]
+pub struct JobHandle {
+    shared: Arc<JobShared>,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> JobId { self.shared.id }
+
+    pub fn name(&self) -> &str { &self.shared.name }
+
+    /// Gets the most recently reported progress of the job, in the range `0.0..=1.0`
+    pub fn progress(&self) -> Number { Number::from_bits(self.shared.progress.load(Ordering::Relaxed)) }
+
+    pub fn status(&self) -> JobStatus { self.shared.status() }
+
+    /// Requests that the job be cancelled.
+    ///
+    /// If the job hasn't started yet, it will be skipped. If it is running, it will stop the next time it
+    /// checks for cancellation.
+    pub fn cancel(&self) { self.shared.cancelled.store(true, Ordering::Relaxed) }
+
+    /// Gets a snapshot of the job's current state
+    pub fn info(&self) -> JobInfo {
+        JobInfo {
+            id: self.id(),
+            name: self.name().to_owned(),
+            progress: self.progress(),
+            status: self.status(),
+        }
+    }
+}
+
+/// Passed to a running job, so that it can report its progress and check for cancellation
+#[derive(Debug)]
+pub struct JobContext<'a> {
+    shared: &'a JobShared,
+}
+
+impl<'a> JobContext<'a> {
+    /// Reports the job's progress. Values are clamped to `0.0..=1.0`
+    pub fn set_progress(&self, progress: Number) {
+        self.shared
+            .progress
+            .store(progress.clamp(0., 1.).to_bits(), Ordering::Relaxed)
+    }
+
+    /// Whether the job has been requested to cancel
+    pub fn is_cancelled(&self) -> bool { self.shared.cancelled.load(Ordering::Relaxed) }
+
+    /// Returns [JobError::Cancelled] if the job has been requested to cancel, so it can be used with `?`
+    pub fn check_cancelled(&self) -> Result<(), JobError> {
+        if self.is_cancelled() {
+            Err(JobError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// endregion Job State
+
+// region Queue
+
+struct QueuedJob {
+    shared: Arc<JobShared>,
+    func: Box<dyn FnOnce(&JobContext) -> Result<(), JobError> + Send + 'static>,
+}
+
+/// A queue that runs jobs on a set of background worker threads
+#[derive(derivative::Derivative)]
+#[derivative(Debug)]
+pub struct JobQueue {
+    #[derivative(Debug = "ignore")]
+    sender: Option<mpsc::Sender<QueuedJob>>,
+    workers: Vec<JoinHandle<()>>,
+    next_id: AtomicU64,
+    /// All jobs that have been submitted and not yet [cleared](Self::clear_finished)
+    jobs: Mutex<Vec<JobHandle>>,
+}
+
+impl JobQueue {
+    /// Creates a new job queue, that runs jobs on `num_workers` threads (at least one)
+    pub fn new(num_workers: usize) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<QueuedJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..num_workers.max(1))
+            .map(|i| {
+                let receiver = Arc::clone(&receiver);
+                std::thread::Builder::new()
+                    .name(format!("JobQueue::worker_{i}"))
+                    .spawn(move || Self::worker_run(receiver))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+            next_id: AtomicU64::new(0),
+            jobs: Mutex::new(vec![]),
+        })
+    }
+
+    /// Submits a job to the queue, to be run as soon as a worker is available
+    pub fn submit(
+        &self,
+        name: impl Into<String>,
+        func: impl FnOnce(&JobContext) -> Result<(), JobError> + Send + 'static,
+    ) -> JobHandle {
+        let shared = Arc::new(JobShared {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            name: name.into(),
+            progress: AtomicU64::new((0. as Number).to_bits()),
+            cancelled: AtomicBool::new(false),
+            status: Mutex::new(JobStatus::Queued),
+        });
+        let handle = JobHandle { shared: shared.clone() };
+        debug!(target: JOB, id = handle.id(), name = handle.name(), "submitting job");
+
+        let job = QueuedJob {
+            shared,
+            func: Box::new(func),
+        };
+        let sent = self.sender.as_ref().map(|s| s.send(job));
+        if !matches!(sent, Some(Ok(()))) {
+            warn!(target: JOB, id = handle.id(), "job queue disconnected, job will not run");
+            handle
+                .shared
+                .set_status(JobStatus::Failed("job queue disconnected".into()));
+        }
+
+        self.jobs.lock().expect("jobs mutex poisoned").push(handle.clone());
+        handle
+    }
+
+    /// Returns handles for all the jobs that have been submitted (and not cleared)
+    pub fn jobs(&self) -> Vec<JobHandle> { self.jobs.lock().expect("jobs mutex poisoned").clone() }
+
+    /// Finds the job with the given ID
+    pub fn get(&self, id: JobId) -> Option<JobHandle> {
+        self.jobs
+            .lock()
+            .expect("jobs mutex poisoned")
+            .iter()
+            .find(|j| j.id() == id)
+            .cloned()
+    }
+
+    /// Removes all the jobs that have finished from the list of [jobs](Self::jobs)
+    pub fn clear_finished(&self) {
+        self.jobs
+            .lock()
+            .expect("jobs mutex poisoned")
+            .retain(|j| !j.status().is_finished());
+    }
+
+    /// Requests cancellation of all jobs in the queue
+    pub fn cancel_all(&self) { self.jobs().iter().for_each(JobHandle::cancel); }
+
+    fn worker_run(receiver: Arc<Mutex<mpsc::Receiver<QueuedJob>>>) {
+        loop {
+            // Only hold the lock while receiving, so other workers can take jobs while this one is running
+            let job = match receiver.lock() {
+                Ok(rx) => rx.recv(),
+                Err(_) => return,
+            };
+            let Ok(QueuedJob { shared, func }) = job else {
+                trace!(target: JOB, "job queue closed, worker exiting");
+                return;
+            };
+
+            if shared.cancelled.load(Ordering::Relaxed) {
+                trace!(target: JOB, id = shared.id, "job cancelled before starting");
+                shared.set_status(JobStatus::Cancelled);
+                continue;
+            }
+
+            trace!(target: JOB, id = shared.id, name = %shared.name, "starting job");
+            shared.set_status(JobStatus::Running);
+            let ctx = JobContext { shared: &shared };
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| func(&ctx)));
+
+            let status = match result {
+                Ok(Ok(())) => {
+                    ctx.set_progress(1.);
+                    JobStatus::Completed
+                }
+                Ok(Err(JobError::Cancelled)) => JobStatus::Cancelled,
+                Ok(Err(JobError::Failed(err))) => JobStatus::Failed(err),
+                Err(_) => JobStatus::Failed("job panicked".into()),
+            };
+            debug!(target: JOB, id = shared.id, ?status, "job finished");
+            shared.set_status(status);
+        }
+    }
+}
+
+impl Drop for JobQueue {
+    fn drop(&mut self) {
+        self.cancel_all();
+        // Closing the channel makes the workers exit once they finish their current job
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                warn!(target: JOB, "job worker thread panicked");
+            }
+        }
+    }
+}
+
+// endregion Queue
@@ -1,5 +1,8 @@
+pub mod capabilities;
 pub mod colour;
+pub mod grid;
 pub mod image;
+pub mod job;
 pub mod macros;
 pub mod profiler;
 pub mod targets;
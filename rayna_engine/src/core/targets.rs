@@ -6,4 +6,5 @@ tracing_targets! {
     MESH = "mesh",
     MATERIAL = "material",
     OBJECT = "object",
+    JOB = "job",
 }
@@ -31,3 +31,5 @@ pub mod scene;
 pub mod shared;
 pub mod skybox;
 pub mod texture;
+
+pub use crate::core::capabilities::capabilities;
@@ -0,0 +1,163 @@
+//! # Module [crate::material::clay]
+//!
+//! *Clay* rendering: replacing the materials in the scene with a single neutral diffuse material, so that the
+//! lighting and geometry can be judged without the shading getting in the way.
+//! See [RenderOpts::clay](crate::render::render_opts::RenderOpts::clay).
+//!
+//! Nothing in the scene is changed; instead, the renderer [shades](Shading) each surface it hits with either the
+//! surface's own material, or with [ClayMaterial] in its place.
+
+use crate::core::types::{Channel, Colour, Number, Vector3};
+use crate::material::lambertian::LambertianMaterial;
+use crate::material::light::LightGroup;
+use crate::material::Material;
+use crate::shared::intersect::Intersection;
+use crate::shared::ray::Ray;
+use crate::texture::solid::SolidTexture;
+use rand_core::RngCore;
+
+/// The colour of [ClayMaterial]
+pub const CLAY_ALBEDO: Colour = Colour::new([0.5; 3]);
+
+/// A plain, light grey [Lambertian](LambertianMaterial) material, used in place of the scene's materials for clay
+/// renders
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ClayMaterial;
+
+impl ClayMaterial {
+    const INNER: LambertianMaterial<SolidTexture> = LambertianMaterial {
+        albedo: SolidTexture { albedo: CLAY_ALBEDO },
+    };
+}
+
+impl Material for ClayMaterial {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
+        Self::INNER.scatter(ray, intersection, rng)
+    }
+
+    fn reflected_light(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        future_ray: &Ray,
+        future_col: &Colour,
+        rng: &mut dyn RngCore,
+    ) -> Colour {
+        Self::INNER.reflected_light(ray, intersection, future_ray, future_col, rng)
+    }
+
+    fn scatter_probability(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        dir: Vector3,
+        rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        Self::INNER.scatter_probability(ray, intersection, dir, rng)
+    }
+}
+
+/// The material that a surface is shaded with: either its own material, or [ClayMaterial] in its place.
+///
+/// Lights and shadow catchers always keep their own material, since the scene wouldn't be lit (or composited)
+/// properly without them
+#[derive(Debug)]
+pub enum Shading<'m, Mat> {
+    Original(&'m Mat),
+    Clay(ClayMaterial),
+}
+
+// Can't be derived, since that would require `Mat: Copy`
+impl<Mat> Clone for Shading<'_, Mat> {
+    fn clone(&self) -> Self { *self }
+}
+impl<Mat> Copy for Shading<'_, Mat> {}
+
+impl<'m, Mat: Material> Shading<'m, Mat> {
+    /// Shades with the `material`, or with clay in its place if `clay` is true (and the material isn't a light or a
+    /// shadow catcher)
+    pub fn new(material: &'m Mat, clay: bool) -> Self {
+        match clay && !material.is_light() && material.shadow_catcher().is_none() {
+            true => Self::Clay(ClayMaterial),
+            false => Self::Original(material),
+        }
+    }
+}
+
+impl<Mat: Material> Material for Shading<'_, Mat> {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
+        match self {
+            Self::Original(mat) => mat.scatter(ray, intersection, rng),
+            Self::Clay(clay) => clay.scatter(ray, intersection, rng),
+        }
+    }
+
+    fn emitted_light(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        match self {
+            Self::Original(mat) => mat.emitted_light(ray, intersection, rng),
+            Self::Clay(clay) => clay.emitted_light(ray, intersection, rng),
+        }
+    }
+
+    fn reflected_light(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        future_ray: &Ray,
+        future_col: &Colour,
+        rng: &mut dyn RngCore,
+    ) -> Colour {
+        match self {
+            Self::Original(mat) => mat.reflected_light(ray, intersection, future_ray, future_col, rng),
+            Self::Clay(clay) => clay.reflected_light(ray, intersection, future_ray, future_col, rng),
+        }
+    }
+
+    fn scatter_probability(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        dir: Vector3,
+        rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        match self {
+            Self::Original(mat) => mat.scatter_probability(ray, intersection, dir, rng),
+            Self::Clay(clay) => clay.scatter_probability(ray, intersection, dir, rng),
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        match self {
+            Self::Original(mat) => mat.is_specular(),
+            Self::Clay(clay) => clay.is_specular(),
+        }
+    }
+
+    fn is_light(&self) -> bool {
+        match self {
+            Self::Original(mat) => mat.is_light(),
+            Self::Clay(clay) => clay.is_light(),
+        }
+    }
+
+    fn shadow_catcher(&self) -> Option<Channel> {
+        match self {
+            Self::Original(mat) => mat.shadow_catcher(),
+            Self::Clay(clay) => clay.shadow_catcher(),
+        }
+    }
+
+    fn light_group(&self) -> Option<&LightGroup> {
+        match self {
+            Self::Original(mat) => mat.light_group(),
+            Self::Clay(clay) => clay.light_group(),
+        }
+    }
+
+    fn albedo(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        match self {
+            Self::Original(mat) => mat.albedo(ray, intersection, rng),
+            Self::Clay(clay) => clay.albedo(ray, intersection, rng),
+        }
+    }
+}
@@ -68,6 +68,8 @@ impl<Tex: Texture> Material for DielectricMaterial<Tex> {
         // future_col * (attenuation_col.exp(transmission))
         future_col * attenuation_col * transmission.exp()
     }
+
+    fn is_specular(&self) -> bool { true }
 }
 
 impl<Tex: Texture> DielectricMaterial<Tex> {
@@ -1,15 +1,28 @@
-use crate::core::types::{Colour, Vector3};
+use crate::core::types::{Channel, Colour, Number, Vector3};
+use crate::material::light::LightGroup;
 use crate::material::Material;
 use crate::shared::intersect::Intersection;
 use crate::shared::ray::Ray;
+use crate::texture::Texture;
 use rand_core::RngCore;
 use std::sync::Arc;
 
+/// Object wrapper around a `dyn` [Material]; Delegates everything to the inner material.
+///
+/// If possible use the enum variants on [`crate::material::MaterialInstance`], so that static-dispatch is used instead of dynamic dispatch
 #[derive(Clone, Debug)]
 pub struct DynamicMaterial {
     pub inner: Arc<dyn Material>,
 }
 
+impl DynamicMaterial {
+    pub fn new(inner: impl Material + 'static) -> Self { Self { inner: Arc::new(inner) } }
+}
+
+impl<Tex: Texture> super::MaterialInstance<Tex> {
+    pub fn from_dyn(value: impl Material + 'static) -> Self { Self::from(DynamicMaterial::new(value)) }
+}
+
 impl Material for DynamicMaterial {
     fn scatter(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
         self.inner.scatter(ray, intersection, rng)
@@ -30,4 +43,26 @@ impl Material for DynamicMaterial {
         self.inner
             .reflected_light(ray, intersection, future_ray, future_col, rng)
     }
+
+    fn scatter_probability(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        dir: Vector3,
+        rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        self.inner.scatter_probability(ray, intersection, dir, rng)
+    }
+
+    fn is_specular(&self) -> bool { self.inner.is_specular() }
+
+    fn is_light(&self) -> bool { self.inner.is_light() }
+
+    fn shadow_catcher(&self) -> Option<Channel> { self.inner.shadow_catcher() }
+
+    fn light_group(&self) -> Option<&LightGroup> { self.inner.light_group() }
+
+    fn albedo(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        self.inner.albedo(ray, intersection, rng)
+    }
 }
@@ -6,6 +6,7 @@ use crate::shared::rng;
 use crate::texture::{Texture, TextureInstance};
 
 use rand_core::RngCore;
+use std::f64::consts::PI;
 
 /// A material that uniformly scatters rays in all directions
 ///
@@ -29,6 +30,16 @@ impl<Tex: Texture> Material for IsotropicMaterial<Tex> {
     fn scatter(&self, _ray: &Ray, _intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
         Some(rng::normal_on_unit_sphere(rng))
     }
+
+    fn scatter_probability(
+        &self,
+        _ray: &Ray,
+        _intersection: &Intersection,
+        _dir: Vector3,
+        _rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        Some(1. / (4. * PI))
+    }
     //TODO: Take into account distance along travelled ray (beer's law?)
     fn reflected_light(
         &self,
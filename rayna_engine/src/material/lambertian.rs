@@ -1,4 +1,4 @@
-use crate::core::types::{Colour, Vector3};
+use crate::core::types::{Colour, Number, Vector3};
 use crate::material::Material;
 use crate::shared::intersect::Intersection;
 use crate::shared::ray::Ray;
@@ -7,6 +7,7 @@ use crate::texture::Texture;
 use crate::texture::TextureInstance;
 
 use rand::RngCore;
+use std::f64::consts::PI;
 
 #[derive(Copy, Clone, Debug)]
 pub struct LambertianMaterial<Tex: Texture> {
@@ -27,14 +28,25 @@ impl<Tex: Texture> From<Tex> for LambertianMaterial<Tex> {
 
 impl<Tex: Texture> Material for LambertianMaterial<Tex> {
     fn scatter(&self, _ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
-        // Completely random scatter direction, in same hemisphere as normal
-        let rand = rng::vector_in_unit_sphere(rng);
+        // Completely random scatter direction. This has to be *on* the sphere (not in it), for the
+        // distribution to match `scatter_probability()`
+        let rand = rng::normal_on_unit_sphere(rng);
         // Bias towards the normal so we get a `cos(theta)` distribution (Lambertian scatter)
         let vec = intersection.ray_normal + rand;
         // Can't necessarily normalise, since maybe `rand + normal == 0`
         Some(vec.try_normalize().unwrap_or(intersection.ray_normal))
     }
 
+    fn scatter_probability(
+        &self,
+        _ray: &Ray,
+        intersection: &Intersection,
+        dir: Vector3,
+        _rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        Some(Vector3::dot(intersection.ray_normal, dir).max(0.) / PI)
+    }
+
     //noinspection DuplicatedCode
     fn reflected_light(
         &self,
@@ -4,6 +4,9 @@ use crate::shared::intersect::Intersection;
 use crate::shared::ray::Ray;
 use crate::texture::Texture;
 use rand_core::RngCore;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::sync::Mutex;
 
 /// A simple emissive material for turning an mesh into a light.
 ///
@@ -11,6 +14,9 @@ use rand_core::RngCore;
 #[derive(Copy, Clone, Debug)]
 pub struct LightMaterial<Tex: Texture> {
     pub emissive: Tex,
+    /// The group that this light belongs to, so that its contribution can be rendered separately.
+    /// See [crate::render::light_group]
+    pub group: Option<LightGroup>,
 }
 
 impl<Tex: Texture> Material for LightMaterial<Tex> {
@@ -30,4 +36,39 @@ impl<Tex: Texture> Material for LightMaterial<Tex> {
     ) -> Colour {
         Colour::BLACK
     }
+
+    fn is_light(&self) -> bool { true }
+
+    fn light_group(&self) -> Option<&LightGroup> { self.group.as_ref() }
+}
+
+/// The name of a group of lights (such as `"key"` or `"fill"`)
+///
+/// The names are interned (and never freed), so groups are [Copy] and cheap to compare, and the same group can be
+/// shared between many lights.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LightGroup(&'static str);
+
+impl LightGroup {
+    pub fn new(name: impl AsRef<str>) -> Self {
+        // Scenes only have a handful of groups, so leaking their names is fine
+        static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+        let name = name.as_ref();
+        let mut names = NAMES.lock().expect("light group names poisoned");
+        match names.get(name) {
+            Some(&interned) => Self(interned),
+            None => {
+                let interned: &'static str = Box::leak(name.into());
+                names.insert(interned);
+                Self(interned)
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str { self.0 }
+}
+
+impl Display for LightGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { f.write_str(self.0) }
 }
@@ -43,4 +43,7 @@ impl<Tex: Texture> Material for MetalMaterial<Tex> {
     ) -> Colour {
         future_col * self.albedo.value(intersect, rng)
     }
+
+    // Even fuzzy metals are glossy enough that they are better traced than gathered
+    fn is_specular(&self) -> bool { true }
 }
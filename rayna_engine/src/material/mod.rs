@@ -1,9 +1,11 @@
 //noinspection ALL
 use self::{
     dielectric::DielectricMaterial, dynamic::DynamicMaterial, isotropic::IsotropicMaterial,
-    lambertian::LambertianMaterial, light::LightMaterial, metal::MetalMaterial,
+    lambertian::LambertianMaterial, light::LightMaterial, metal::MetalMaterial, normal_mapped::NormalMappedMaterial,
+    shadow_catcher::ShadowCatcherMaterial,
 };
-use crate::core::types::{Colour, Vector3};
+use crate::core::types::{Channel, Colour, Number, Vector3};
+use crate::material::light::LightGroup;
 use crate::shared::intersect::Intersection;
 use crate::shared::ray::Ray;
 use crate::shared::RtRequirement;
@@ -11,12 +13,15 @@ use crate::texture::{Texture, TextureInstance};
 use enum_dispatch::enum_dispatch;
 use rand::RngCore;
 
+pub mod clay;
 pub mod dielectric;
 pub mod dynamic;
 pub mod isotropic;
 pub mod lambertian;
 pub mod light;
 pub mod metal;
+pub mod normal_mapped;
+pub mod shadow_catcher;
 
 /// The trait that defines what properties a material has
 #[enum_dispatch]
@@ -72,21 +77,37 @@ pub trait Material: RtRequirement {
     /// ```
     fn scatter(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3>;
 
-    // /// Calculates the value of the probability of the material having scattered a ray in the given direction.
-    // ///
-    // /// This is equivalent to evaluating the material's scattering **Probability Density Function** (**PDF**),
-    // /// for the given intersection and ray pair.
-    // ///
-    // /// # Arguments
-    // /// * `ray_in`: The incoming ray that resulted in the intersection
-    // /// * `intersection`: Information about the intersection with the mesh
-    // /// * `ray_out`: The outgoing ray. It is not guaranteed to have been obtained from a call to [Self::scatter()].
-    // ///
-    // /// # Return Value
-    // /// This should return the value of the material's PDF, for the given variable.
-    // /// If the given scatter direction is not possible, or invalid, for the material, this should return `0.0`,
-    // /// and not panic (i.e., a ray in a random direction, on a 'mirror' material, would return zero).
-    // fn scatter_probability(&self, ray_in: &Ray, scattered: &Ray, intersection: &Intersection) -> Number;
+    /// Calculates the value of the probability of the material having scattered a ray in the given direction.
+    ///
+    /// This is equivalent to evaluating the material's scattering **Probability Density Function** (**PDF**)
+    /// (per unit solid angle), for the given intersection and direction. The renderer uses it to also scatter rays
+    /// in other directions (such as towards the sun, see
+    /// [Skybox::sample_direction()](crate::skybox::Skybox::sample_direction)), and weigh them against the ones from
+    /// [Self::scatter()].
+    ///
+    /// For that to work, the light from [Self::reflected_light()] must not depend on where the `future_ray` goes,
+    /// other than through this density (i.e. it is the material's `BRDF * cos(theta) / pdf`).
+    ///
+    /// # Arguments
+    /// * `ray`: The incoming ray that resulted in the intersection
+    /// * `intersection`: Information about the intersection with the mesh
+    /// * `dir`: The outgoing direction. It is not guaranteed to have been obtained from a call to [Self::scatter()].
+    ///
+    /// # Return Value
+    /// This should return the value of the material's PDF, for the given direction.
+    /// If the given scatter direction is not possible for the material, this should return `0.0`, and not panic.
+    /// The default implementation returns [None], meaning that the material can't calculate it (e.g. a 'mirror'
+    /// material), so only [Self::scatter()] is used
+    #[allow(unused_variables)]
+    fn scatter_probability(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        dir: Vector3,
+        rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        None
+    }
 
     /// This function calculates the amount of light that is emitted by the material
     ///
@@ -149,6 +170,59 @@ pub trait Material: RtRequirement {
         future_col: &Colour,
         rng: &mut dyn RngCore,
     ) -> Colour;
+
+    /// Whether this material only scatters light in a single direction (or a few), like a mirror or glass, rather
+    /// than spreading it out like a diffuse surface.
+    ///
+    /// Photon mapping (see [crate::render::photon]) follows rays through specular materials, and only stores and
+    /// gathers photons on the others.
+    ///
+    /// # Return Value
+    /// The default implementation returns `false`, meaning that the material is diffuse
+    fn is_specular(&self) -> bool { false }
+
+    /// Whether this material is a light, that only emits light rather than reflecting it.
+    ///
+    /// Lights keep their own material in [clay renders](crate::material::clay), so that the scene is still lit.
+    ///
+    /// # Return Value
+    /// The default implementation returns `false`
+    fn is_light(&self) -> bool { false }
+
+    /// Whether this material is a *shadow catcher*, and if so, how strong its shadows are (`0.0..=1.0`)
+    ///
+    /// Shadow catchers are not shaded normally: instead, the renderer passes through whatever is behind the surface,
+    /// darkened by how much light is blocked by the rest of the scene. See
+    /// [ShadowCatcherMaterial](shadow_catcher::ShadowCatcherMaterial).
+    ///
+    /// # Return Value
+    /// The default implementation returns [None], meaning that this is a normal material
+    fn shadow_catcher(&self) -> Option<Channel> { None }
+
+    /// The [LightGroup] that the light emitted by this material belongs to, if any.
+    ///
+    /// Light groups are used to render the contribution of each group of lights separately.
+    /// See [crate::render::light_group].
+    ///
+    /// # Return Value
+    /// The default implementation returns [None], meaning that the light isn't part of any group
+    fn light_group(&self) -> Option<&LightGroup> { None }
+
+    /// The colour of the surface itself (its *albedo*), without any lighting. This is used for the
+    /// [albedo AOV](crate::render::aov::Aov::Albedo), which denoisers use to keep textures sharp.
+    ///
+    /// # Return Value
+    /// The default implementation is the light that the material reflects along a scattered ray, when that ray sees
+    /// pure white. If the material doesn't scatter, the emitted light is used instead
+    fn albedo(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        match self.scatter(ray, intersection, rng) {
+            Some(dir) => {
+                let future_ray = Ray::new(intersection.pos_w, dir);
+                self.reflected_light(ray, intersection, &future_ray, &Colour::WHITE, rng)
+            }
+            None => self.emitted_light(ray, intersection, rng),
+        }
+    }
 }
 
 /// An optimised implementation of [Material].
@@ -171,6 +245,8 @@ pub enum MaterialInstance<Tex: Texture> {
     DielectricMaterial(DielectricMaterial<Tex>),
     IsotropicMaterial(IsotropicMaterial<Tex>),
     LightMaterial(LightMaterial<Tex>),
+    NormalMappedMaterial(NormalMappedMaterial<DynamicMaterial, Tex>),
+    ShadowCatcherMaterial,
     DynamicMaterial,
 }
 
@@ -0,0 +1,93 @@
+use crate::core::types::{Channel, Colour, Number, Vector3};
+use crate::material::dynamic::DynamicMaterial;
+use crate::material::light::LightGroup;
+use crate::material::Material;
+use crate::shared::intersect::Intersection;
+use crate::shared::ray::Ray;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::normal_map::NormalMapTexture;
+use crate::texture::Texture;
+use rand_core::RngCore;
+
+/// A material that bends the surface normals of another material with a [normal map](NormalMapTexture), to add
+/// small bumps and dents without any extra geometry.
+///
+/// The normal map is oriented using the [tangent](Intersection::tangent) of the mesh. Meshes without one still work,
+/// but the direction of the bumps across the surface is arbitrary.
+#[derive(Clone, Debug)]
+pub struct NormalMappedMaterial<Mat: Material = DynamicMaterial, Tex: Texture = DynamicTexture> {
+    pub inner: Mat,
+    pub normal_map: NormalMapTexture<Tex>,
+}
+
+impl<Mat: Material, Tex: Texture> NormalMappedMaterial<Mat, Tex> {
+    pub fn new(inner: Mat, normal_map: impl Into<NormalMapTexture<Tex>>) -> Self {
+        Self {
+            inner,
+            normal_map: normal_map.into(),
+        }
+    }
+
+    /// Replaces the normals of the `intersection` with the ones from the normal map
+    fn perturb(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Intersection {
+        let tangent = intersection
+            .tangent
+            .unwrap_or_else(|| Vector3::any_orthonormal_pair(&intersection.normal).0);
+        let normal =
+            NormalMapTexture::<Tex>::to_world(self.normal_map.normal(intersection, rng), intersection.normal, tangent);
+        Intersection {
+            normal,
+            ray_normal: if intersection.front_face { normal } else { -normal },
+            ..*intersection
+        }
+    }
+}
+
+impl<Mat: Material, Tex: Texture> Material for NormalMappedMaterial<Mat, Tex> {
+    fn scatter(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
+        let intersection = self.perturb(intersection, rng);
+        self.inner.scatter(ray, &intersection, rng)
+    }
+
+    fn emitted_light(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let intersection = self.perturb(intersection, rng);
+        self.inner.emitted_light(ray, &intersection, rng)
+    }
+
+    fn reflected_light(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        future_ray: &Ray,
+        future_col: &Colour,
+        rng: &mut dyn RngCore,
+    ) -> Colour {
+        let intersection = self.perturb(intersection, rng);
+        self.inner
+            .reflected_light(ray, &intersection, future_ray, future_col, rng)
+    }
+
+    fn scatter_probability(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        dir: Vector3,
+        rng: &mut dyn RngCore,
+    ) -> Option<Number> {
+        let intersection = self.perturb(intersection, rng);
+        self.inner.scatter_probability(ray, &intersection, dir, rng)
+    }
+
+    fn is_specular(&self) -> bool { self.inner.is_specular() }
+
+    fn is_light(&self) -> bool { self.inner.is_light() }
+
+    fn shadow_catcher(&self) -> Option<Channel> { self.inner.shadow_catcher() }
+
+    fn light_group(&self) -> Option<&LightGroup> { self.inner.light_group() }
+
+    fn albedo(&self, ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let intersection = self.perturb(intersection, rng);
+        self.inner.albedo(ray, &intersection, rng)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::core::types::{Channel, Colour, Vector3};
+use crate::material::Material;
+use crate::shared::intersect::Intersection;
+use crate::shared::ray::Ray;
+use crate::shared::rng;
+use rand_core::RngCore;
+
+/// A material that is invisible, except for the shadows that other objects cast onto it.
+///
+/// This is mostly useful for compositing renders over photographs: place a shadow catcher where the ground
+/// is in the photo, and the rendered objects will appear to cast shadows onto it. Everything else
+/// passes the skybox straight through.
+///
+/// # Rendering
+/// Shadow catchers are handled specially by the [renderer](crate::render::renderer::Renderer), which checks
+/// [Material::shadow_catcher()]. The [RenderMode::Alpha](crate::render::render_opts::RenderMode::Alpha) mode
+/// can be used to output the matching alpha (coverage) mask.
+#[derive(Copy, Clone, Debug)]
+pub struct ShadowCatcherMaterial {
+    /// How strongly the shadows are visible, from `0.0` (invisible) to `1.0` (full strength)
+    pub strength: Channel,
+}
+
+impl Default for ShadowCatcherMaterial {
+    fn default() -> Self { Self { strength: 1.0 } }
+}
+
+impl Material for ShadowCatcherMaterial {
+    fn scatter(&self, _ray: &Ray, intersection: &Intersection, rng: &mut dyn RngCore) -> Option<Vector3> {
+        // Sample the incoming light diffusely, same as a lambertian surface would
+        let rand = rng::vector_in_unit_sphere(rng);
+        let vec = intersection.ray_normal + rand;
+        Some(vec.try_normalize().unwrap_or(intersection.ray_normal))
+    }
+
+    fn reflected_light(
+        &self,
+        _ray: &Ray,
+        _intersection: &Intersection,
+        _future_ray: &Ray,
+        future_col: &Colour,
+        _rng: &mut dyn RngCore,
+    ) -> Colour {
+        // Only used if rendered outside the renderer's special handling,
+        // in which case behave like a perfectly white diffuse surface
+        *future_col
+    }
+
+    fn shadow_catcher(&self) -> Option<Channel> { Some(self.strength.clamp(0.0, 1.0)) }
+}
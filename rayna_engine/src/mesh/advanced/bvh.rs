@@ -11,6 +11,7 @@ use std::ops::{Add, Div};
 
 use crate::mesh::{Mesh as MeshTrait, MeshProperties};
 use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::bvh_cost;
 use crate::shared::generic_bvh::{GenericBvh, GenericBvhNode};
 use crate::shared::intersect::Intersection;
 use crate::shared::interval::Interval;
@@ -49,6 +50,18 @@ impl<Mesh: MeshTrait> BvhMesh<Mesh> {
 
 // endregion Constructors
 
+// region Refitting
+
+impl<Mesh: MeshTrait> BvhMesh<Mesh> {
+    /// Iterates mutably over all the meshes in the tree. Call [Self::refit()] after changing them
+    pub fn meshes_mut(&mut self) -> impl Iterator<Item = &mut Mesh> { self.inner.objects_mut() }
+
+    /// Updates the bounds of the tree after the meshes have been changed. See [GenericBvh::refit()]
+    pub fn refit(&mut self) { self.inner.refit() }
+}
+
+// endregion Refitting
+
 // region Mesh Impl
 
 impl<Mesh: MeshTrait> BvhMesh<Mesh> {
@@ -70,6 +83,7 @@ impl<Mesh: MeshTrait> BvhMesh<Mesh> {
         return match arena.get(node).expect("node should exist in arena").get() {
             // An aabb will need to delegate to child nodes if not missed
             GenericBvhNode::Nested(aabb) => {
+                bvh_cost::visit_node();
                 if !aabb.hit(ray, interval) {
                     return None;
                 }
@@ -92,9 +106,11 @@ impl<Mesh: MeshTrait> BvhMesh<Mesh> {
             }
             // meshes can be delegated directly
             GenericBvhNode::Object(mesh) => {
+                bvh_cost::visit_node();
                 if !mesh.expect_aabb().hit(ray, interval) {
                     None
                 } else {
+                    bvh_cost::test_primitive();
                     mesh.intersect(ray, interval, rng)
                 }
             }
@@ -0,0 +1,182 @@
+use crate::core::types::{Number, Point3};
+use crate::mesh::{Mesh as MeshTrait, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::intersect_arena::IntersectionList;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use itertools::Itertools;
+use rand_core::RngCore;
+use serde::Serialize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// A boolean (set) operation used to combine two meshes
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum CsgOperation {
+    /// Everything that is inside either mesh
+    Union,
+    /// Only what is inside both meshes
+    Intersection,
+    /// Everything inside the first mesh, with the second mesh cut out of it
+    Difference,
+}
+
+impl CsgOperation {
+    /// Whether a point is inside the combined mesh, given whether it is inside each of the two meshes
+    pub fn combine(&self, inside_a: bool, inside_b: bool) -> bool {
+        match self {
+            Self::Union => inside_a || inside_b,
+            Self::Intersection => inside_a && inside_b,
+            Self::Difference => inside_a && !inside_b,
+        }
+    }
+
+    /// The bounds of the combined shape, given the bounds of the two shapes
+    pub fn bounds(&self, a: Option<&Aabb>, b: Option<&Aabb>) -> Option<Aabb> {
+        match self {
+            Self::Union => match (a, b) {
+                (Some(a), Some(b)) => Some(Aabb::encompass(a, b)),
+                _ => None,
+            },
+            // Can't be any larger than either shape
+            Self::Intersection => match (a, b) {
+                (Some(a), Some(b)) => {
+                    // If they don't overlap, this collapses to an empty box
+                    let min = a.min().max(b.min());
+                    Some(Aabb::new(min, a.max().min(b.max()).max(min)))
+                }
+                (Some(bounds), None) | (None, Some(bounds)) => Some(*bounds),
+                (None, None) => None,
+            },
+            // Cutting can only make it smaller
+            Self::Difference => a.copied(),
+        }
+    }
+
+    /// Combines all the intersections along a ray with two closed shapes, keeping only the ones that are on the
+    /// surface of the combined shape.
+    ///
+    /// The hits for each shape must be sorted by distance (see [MeshTrait::intersect_all()]), and so are the combined
+    /// hits. Surfaces from the second shape in a [CsgOperation::Difference] are turned inside out.
+    ///
+    /// # Performance
+    /// The two lists are merged as they are iterated, so nothing is buffered.
+    pub fn combine_hits<Hit: AsRef<Intersection> + AsMut<Intersection>>(
+        self,
+        hits_a: impl IntoIterator<Item = Hit>,
+        hits_b: impl IntoIterator<Item = Hit>,
+    ) -> impl Iterator<Item = Hit> {
+        let mut hits_a = hits_a.into_iter().peekable();
+        let mut hits_b = hits_b.into_iter().peekable();
+
+        // If the first intersection is leaving a shape, then the ray must have started inside it
+        let starts_inside = |hit: Option<&Hit>| hit.is_some_and(|hit| !hit.as_ref().front_face);
+        let (mut inside_a, mut inside_b) = (starts_inside(hits_a.peek()), starts_inside(hits_b.peek()));
+        let mut inside = self.combine(inside_a, inside_b);
+
+        // Merge the two (sorted) lists, tagging which shape each came from
+        let events = hits_a
+            .map(|hit| (hit, false))
+            .merge_by(hits_b.map(|hit| (hit, true)), |(a, _), (b, _)| {
+                a.as_ref().dist <= b.as_ref().dist
+            });
+
+        // Each intersection crosses the surface of one of the shapes, which may or may not also be a surface
+        // of the combined shape
+        events.filter_map(move |(mut hit, from_b)| {
+            if from_b {
+                inside_b = !inside_b;
+            } else {
+                inside_a = !inside_a;
+            }
+            let now_inside = self.combine(inside_a, inside_b);
+            if now_inside == inside {
+                return None;
+            }
+            inside = now_inside;
+
+            if from_b && self == Self::Difference {
+                let intersection = hit.as_mut();
+                intersection.normal = -intersection.normal;
+                intersection.front_face = !intersection.front_face;
+            }
+            Some(hit)
+        })
+    }
+}
+
+/// A mesh made by combining two meshes with a boolean operation (**Constructive Solid Geometry**)
+///
+/// This allows cut-outs and holes to be made, without having to do any polygon booleans. Both meshes must be closed
+/// (have a well-defined inside), since the intersections are found by tracking when the ray enters and exits each
+/// mesh (see [MeshTrait::intersect_all()]).
+///
+/// # Sides
+/// Intersections keep the [side](Intersection::side) of the mesh that was hit. For surfaces from the second mesh
+/// in a [CsgOperation::Difference], the normals are flipped, since the inside of that mesh is now outside.
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct CsgMesh<Mesh: MeshTrait> {
+    #[get = "pub"]
+    a: Box<Mesh>,
+    #[get = "pub"]
+    b: Box<Mesh>,
+    #[get_copy = "pub"]
+    operation: CsgOperation,
+    #[get_copy = "pub"]
+    centre: Point3,
+    aabb: Option<Aabb>,
+}
+
+// region Constructors
+
+impl<Mesh: MeshTrait> CsgMesh<Mesh> {
+    pub fn new(a: impl Into<Mesh>, b: impl Into<Mesh>, operation: CsgOperation) -> Self {
+        let (a, b) = (a.into(), b.into());
+
+        let aabb = operation.bounds(a.aabb(), b.aabb());
+
+        Self {
+            centre: a.centre(),
+            a: Box::new(a),
+            b: Box::new(b),
+            operation,
+            aabb,
+        }
+    }
+
+    pub fn union(a: impl Into<Mesh>, b: impl Into<Mesh>) -> Self { Self::new(a, b, CsgOperation::Union) }
+
+    pub fn intersection(a: impl Into<Mesh>, b: impl Into<Mesh>) -> Self { Self::new(a, b, CsgOperation::Intersection) }
+
+    pub fn difference(a: impl Into<Mesh>, b: impl Into<Mesh>) -> Self { Self::new(a, b, CsgOperation::Difference) }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl<Mesh: MeshTrait> HasAabb for CsgMesh<Mesh> {
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+impl<Mesh: MeshTrait> MeshProperties for CsgMesh<Mesh> {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+impl<Mesh: MeshTrait> MeshTrait for CsgMesh<Mesh> {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        self.intersect_all(ray, interval, rng).first().copied()
+    }
+
+    fn intersect_all(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> IntersectionList {
+        let hits_a = self.a.intersect_all(ray, interval, rng);
+        let hits_b = self.b.intersect_all(ray, interval, rng);
+        self.operation
+            .combine_hits(hits_a.iter().copied(), hits_b.iter().copied())
+            .collect()
+    }
+}
+
+// endregion Mesh Impl
@@ -0,0 +1,335 @@
+use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::mesh::advanced::bvh::BvhMesh;
+use crate::mesh::primitive::capsule::CapsuleMesh;
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::math::Lerp;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use serde::Serialize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// How the control points of a [CurveStrand] are joined together
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum CurveBasis {
+    /// Piecewise cubic Bézier curves, where every fourth point is shared between two spans (`3n + 1` points).
+    ///
+    /// The curve passes through the shared points, and the two points in-between control the tangents
+    Bezier,
+    /// A uniform cubic B-spline (any number of points, at least four).
+    ///
+    /// The curve doesn't pass through the points, but it is always smooth, which makes it easy to generate strands
+    BSpline,
+}
+
+/// How the surface of a [CurveMesh] is made from its strands
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum CurveShape {
+    /// A flat ribbon that always faces the ray, with a normal that bends across its width so that it shades like a
+    /// cylinder.
+    ///
+    /// This is much faster than [CurveShape::Cylinder], and looks the same for thin strands (hair, fur and grass)
+    Ribbon,
+    /// A tube swept along the curve, which looks correct up close
+    Cylinder,
+}
+
+/// A single strand of a [CurveMesh]
+#[derive(Clone, Debug)]
+pub struct CurveStrand {
+    /// The control points of the curve, see [CurveBasis]
+    pub points: Vec<Point3>,
+    /// The radius at each control point, which is interpolated along the curve in the same way as the points
+    pub radii: Vec<Number>,
+}
+
+impl CurveStrand {
+    /// Creates a new strand, with a radius at each point
+    ///
+    /// # Panics
+    /// Panics if the number of radii doesn't match the number of points
+    pub fn new(points: impl IntoIterator<Item = impl Into<Point3>>, radii: impl Into<Vec<Number>>) -> Self {
+        let points = points.into_iter().map(Into::into).collect::<Vec<_>>();
+        let radii = radii.into();
+        assert_eq!(points.len(), radii.len(), "should have a radius for each point");
+        Self { points, radii }
+    }
+
+    /// Creates a new strand which tapers linearly from the `root` radius to the `tip` radius
+    pub fn tapered(points: impl IntoIterator<Item = impl Into<Point3>>, root: Number, tip: Number) -> Self {
+        let points = points.into_iter().map(Into::into).collect::<Vec<_>>();
+        let last = points.len().saturating_sub(1).max(1) as Number;
+        let radii = (0..points.len())
+            .map(|i| Lerp::lerp(root, tip, i as Number / last))
+            .collect();
+        Self { points, radii }
+    }
+}
+
+/// A mesh made of many thin curved strands, for rendering hair, fur and grass.
+///
+/// # Strands
+/// Each strand is a smooth cubic curve (see [CurveBasis]), with a radius that varies along its length. When the mesh
+/// is created, the strands are split up into short straight segments, which are stored in a [BvhMesh]. Since the
+/// bounds are calculated per segment rather than per strand, long curly strands don't get huge boxes that overlap
+/// all their neighbours, which is what would make a strand-level BVH slow.
+///
+/// # Sides
+/// The [Intersection::side] is the index of the strand that was hit, so that it can be used to vary the appearance
+/// of each strand. The `u` coordinate goes along the strand (from `0` at the root to `1` at the tip), and `v` goes
+/// across it (or around it, for [CurveShape::Cylinder]).
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct CurveMesh {
+    #[get_copy = "pub"]
+    basis: CurveBasis,
+    #[get_copy = "pub"]
+    shape: CurveShape,
+    /// How many strands there are
+    #[get_copy = "pub"]
+    strand_count: usize,
+    /// How many straight segments the strands were split into, in total
+    #[get_copy = "pub"]
+    segment_count: usize,
+    bvh: BvhMesh<CurveSegment>,
+}
+
+// region Constructors
+
+impl CurveMesh {
+    /// Creates a new mesh from the given strands.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments_per_span`: How many straight segments each cubic span of the curve is split into. Higher values
+    ///   are smoother, but use more memory. Around `4..8` is usually enough
+    ///
+    /// # Panics
+    /// Panics if any strand has the wrong number of points for the `basis`, or there are no strands
+    pub fn new(
+        strands: impl IntoIterator<Item = CurveStrand>,
+        basis: CurveBasis,
+        shape: CurveShape,
+        segments_per_span: usize,
+    ) -> Self {
+        let segments_per_span = segments_per_span.max(1);
+        let mut strand_count = 0;
+        let mut segments = vec![];
+
+        for (index, strand) in strands.into_iter().enumerate() {
+            strand_count += 1;
+            let spans = basis.spans(strand.points.len());
+            let count = spans * segments_per_span;
+
+            // Evaluate the curve at the end of each segment
+            let samples = (0..=count)
+                .map(|i| {
+                    let t = (i as Number / segments_per_span as Number).min(spans as Number);
+                    let span = (t as usize).min(spans - 1);
+                    basis.eval(&strand, span, t - span as Number)
+                })
+                .collect::<Vec<_>>();
+
+            segments.extend(samples.windows(2).enumerate().map(|(i, pair)| {
+                let [(start, r_start), (end, r_end)] = [pair[0], pair[1]];
+                CurveSegment::new(
+                    [start, end],
+                    [r_start, r_end],
+                    [i as Number / count as Number, (i + 1) as Number / count as Number],
+                    index,
+                    shape,
+                )
+            }));
+        }
+
+        assert!(strand_count > 0, "should have at least one strand");
+        Self {
+            basis,
+            shape,
+            strand_count,
+            segment_count: segments.len(),
+            bvh: BvhMesh::new(segments),
+        }
+    }
+}
+
+impl CurveBasis {
+    /// How many cubic spans a strand with the given number of points has
+    fn spans(self, points: usize) -> usize {
+        match self {
+            Self::Bezier => {
+                assert!(
+                    points >= 4 && (points - 1) % 3 == 0,
+                    "bézier strands need 3n + 1 points, had {points}"
+                );
+                (points - 1) / 3
+            }
+            Self::BSpline => {
+                assert!(points >= 4, "b-spline strands need at least 4 points, had {points}");
+                points - 3
+            }
+        }
+    }
+
+    /// Evaluates the position and radius on the given span of a strand, at `t` in `0..=1`
+    fn eval(self, strand: &CurveStrand, span: usize, t: Number) -> (Point3, Number) {
+        let s = 1. - t;
+        let (first, weights) = match self {
+            Self::Bezier => (span * 3, [s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t]),
+            Self::BSpline => (
+                span,
+                [
+                    s * s * s / 6.,
+                    ((3. * t * t * t) - (6. * t * t) + 4.) / 6.,
+                    ((-3. * t * t * t) + (3. * t * t) + (3. * t) + 1.) / 6.,
+                    t * t * t / 6.,
+                ],
+            ),
+        };
+
+        let (pos, radius) = (0..4).fold((Vector3::ZERO, 0.), |(pos, radius), i| {
+            (
+                pos + (strand.points[first + i].to_vector() * weights[i]),
+                radius + (strand.radii[first + i] * weights[i]),
+            )
+        });
+        (pos.to_point(), radius)
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl HasAabb for CurveMesh {
+    fn aabb(&self) -> Option<&Aabb> { self.bvh.aabb() }
+}
+
+impl MeshProperties for CurveMesh {
+    fn centre(&self) -> Point3 { self.bvh.centre() }
+}
+
+impl Mesh for CurveMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        self.bvh.intersect(ray, interval, rng)
+    }
+}
+
+// endregion Mesh Impl
+
+// region Segment
+
+/// A single straight piece of a strand, which is what is actually stored in the BVH
+#[derive(Copy, Clone, Debug)]
+struct CurveSegment {
+    start: Point3,
+    end: Point3,
+    radii: [Number; 2],
+    /// The `u` coordinate at either end of the segment
+    u: [Number; 2],
+    strand: usize,
+    /// Only used for [CurveShape::Cylinder], since it also handles the joints between segments
+    capsule: Option<CapsuleMesh>,
+    centre: Point3,
+    aabb: Aabb,
+}
+
+impl CurveSegment {
+    fn new(points: [Point3; 2], radii: [Number; 2], u: [Number; 2], strand: usize, shape: CurveShape) -> Self {
+        let [start, end] = points;
+        let max_radius = radii[0].max(radii[1]);
+        let capsule = match shape {
+            CurveShape::Ribbon => None,
+            // Capsules don't taper, so use the average radius, which is close enough for short segments
+            CurveShape::Cylinder => Some(CapsuleMesh::new(start, end, (radii[0] + radii[1]) / 2.)),
+        };
+        Self {
+            start,
+            end,
+            radii,
+            u,
+            strand,
+            capsule,
+            centre: start + ((end - start) / 2.),
+            aabb: Aabb::encompass_points(
+                [start, end]
+                    .into_iter()
+                    .flat_map(|p| [p - Vector3::splat(max_radius), p + Vector3::splat(max_radius)]),
+            ),
+        }
+    }
+
+    fn intersect_ribbon(&self, ray: &Ray, interval: &Interval<Number>) -> Option<Intersection> {
+        // Find the closest points between the ray and the segment
+        let d = ray.dir();
+        let s = self.end - self.start;
+        let w = ray.pos() - self.start;
+        let (b, c) = (Vector3::dot(d, s), s.length_squared());
+        let (dw, sw) = (Vector3::dot(d, w), Vector3::dot(s, w));
+        let denominator = c - (b * b);
+        // Looking straight down the segment, so it has no width
+        if denominator <= 1e-12 * c {
+            return None;
+        }
+        let along = (((b * dw) - sw) / -denominator).clamp(0., 1.);
+        let on_segment = self.start + (s * along);
+        let dist = Vector3::dot(on_segment - ray.pos(), d);
+        if !interval.contains(&dist) {
+            return None;
+        }
+
+        let radius = Lerp::lerp(self.radii[0], self.radii[1], along);
+        let pos_w = ray.at(dist);
+        let offset = pos_w - on_segment;
+        if offset.length_squared() > radius * radius {
+            return None;
+        }
+
+        // The ribbon faces the ray, but the normal curves around the side so it looks round
+        let side = Vector3::cross(s, d).normalize();
+        let across = (Vector3::dot(offset, side) / radius).clamp(-1., 1.);
+        let normal = ((side * across) - (d * (1. - (across * across)).sqrt())).normalize();
+
+        Some(Intersection {
+            pos_w,
+            pos_l: pos_w - self.centre.to_vector(),
+            normal,
+            ray_normal: normal,
+            front_face: true,
+            dist,
+            uv: Point2::new(Lerp::lerp(self.u[0], self.u[1], along), (across + 1.) / 2.),
+            side: self.strand,
+            colour: None,
+            tangent: None,
+        })
+    }
+}
+
+impl HasAabb for CurveSegment {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for CurveSegment {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+impl Mesh for CurveSegment {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        let Some(capsule) = &self.capsule else {
+            return self.intersect_ribbon(ray, interval);
+        };
+
+        let mut intersection = capsule.intersect(ray, interval, rng)?;
+        // The capsule's `v` goes along the segment, so map it onto the whole strand
+        let along = intersection.uv.y;
+        intersection.uv = Point2::new(Lerp::lerp(self.u[0], self.u[1], along), intersection.uv.x);
+        intersection.side = self.strand;
+        intersection.pos_l = intersection.pos_w - self.centre.to_vector();
+        Some(intersection)
+    }
+}
+
+// endregion Segment
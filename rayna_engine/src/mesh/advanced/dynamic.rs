@@ -1,7 +1,9 @@
 use crate::core::types::{Number, Point3};
 use crate::mesh::{Mesh, MeshProperties};
+use crate::object::light::LightShape;
 use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::Intersection;
+use crate::shared::intersect_arena::IntersectionList;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
 use rand_core::RngCore;
@@ -32,6 +34,12 @@ impl Mesh for DynamicMesh {
     fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
         self.inner.intersect(ray, interval, rng)
     }
+
+    fn intersect_all(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> IntersectionList {
+        self.inner.intersect_all(ray, interval, rng)
+    }
+
+    fn light_shape(&self) -> Option<LightShape> { self.inner.light_shape() }
 }
 
 impl HasAabb for DynamicMesh {
@@ -0,0 +1,251 @@
+use crate::core::targets::MESH;
+use crate::core::types::{Colour, Number, Point2, Point3, Vector3};
+use crate::mesh::advanced::bvh::BvhMesh;
+use crate::mesh::primitive::triangle::Triangle;
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use derivative::Derivative;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use std::ops::{Add, Div};
+use tracing::warn;
+
+/// How the vertex normals of an [IndexedTriangleMesh] are determined
+#[derive(Clone, Debug, Default)]
+pub enum VertexNormals {
+    /// Each triangle is shaded using its geometric (face) normal, giving a faceted look
+    Flat,
+    /// Use the given normals, one per vertex
+    Provided(Vec<Vector3>),
+    /// Automatically calculate smooth normals, by averaging the normals of all the faces that share a vertex,
+    /// weighted by the angle of the face's corner at that vertex
+    #[default]
+    AngleWeighted,
+}
+
+/// Extra per-vertex attributes for an [IndexedTriangleMesh], which are interpolated across the faces of the triangles
+#[derive(Clone, Debug, Default)]
+pub struct VertexAttributes {
+    /// The UV coordinates at each vertex, used for texture mapping (see [Intersection::uv])
+    ///
+    /// If not given, the UVs of each triangle are its barycentric coordinates
+    pub uvs: Option<Vec<Point2>>,
+    /// The colours at each vertex (see [Intersection::colour])
+    pub colours: Option<Vec<Colour>>,
+}
+
+/// A mesh made from triangles that share a common list of vertices
+///
+/// Normals are interpolated across the faces of each triangle (see [VertexNormals]),
+/// so that low-poly meshes can still appear smooth.
+#[derive(CopyGetters, Getters, Derivative, Clone)]
+#[derivative(Debug)]
+pub struct IndexedTriangleMesh {
+    /// The positions of the vertices in the mesh
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
+    #[get = "pub"]
+    vertices: Vec<Point3>,
+    /// The normals at each vertex of the mesh, one per vertex.
+    ///
+    /// Will be empty if [VertexNormals::Flat] was used
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
+    #[get = "pub"]
+    normals: Vec<Vector3>,
+    /// The indices of the vertices that make up each triangle
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
+    #[get = "pub"]
+    indices: Vec<[usize; 3]>,
+    /// The UV coordinates at each vertex of the mesh, one per vertex.
+    ///
+    /// Will be empty if no UVs were given
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
+    #[get = "pub"]
+    uvs: Vec<Point2>,
+    /// The colours at each vertex of the mesh, one per vertex.
+    ///
+    /// Will be empty if no colours were given
+    #[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]
+    #[get = "pub"]
+    colours: Vec<Colour>,
+    /// How many (valid) triangles there are in this mesh
+    #[get_copy = "pub"]
+    count: usize,
+    #[get_copy = "pub"]
+    centre: Point3,
+    // Built from the vertices, so it doesn't need to be printed (or hashed) as well
+    #[derivative(Debug = "ignore")]
+    #[get = "pub"]
+    mesh: BvhMesh<Triangle>,
+}
+
+// region Constructors
+
+impl IndexedTriangleMesh {
+    /// Creates a new mesh from the given vertices, and the triangles formed by indexing into them
+    ///
+    /// # Panics
+    /// Panics if any of the indices are out of bounds, or if normals were [provided](VertexNormals::Provided)
+    /// and the number of normals doesn't match the number of vertices.
+    ///
+    /// Degenerate triangles (those with duplicate vertices) are skipped.
+    pub fn new(
+        vertices: impl Into<Vec<Point3>>,
+        indices: impl Into<Vec<[usize; 3]>>,
+        normals: impl Into<Option<VertexNormals>>,
+    ) -> Self {
+        Self::new_with_attributes(vertices, indices, normals, VertexAttributes::default())
+    }
+
+    /// Creates a new mesh, like [Self::new()], with extra per-vertex attributes (UVs and colours)
+    ///
+    /// # Panics
+    /// As well as the cases in [Self::new()], panics if any of the attributes don't have one value per vertex.
+    pub fn new_with_attributes(
+        vertices: impl Into<Vec<Point3>>,
+        indices: impl Into<Vec<[usize; 3]>>,
+        normals: impl Into<Option<VertexNormals>>,
+        attributes: VertexAttributes,
+    ) -> Self {
+        let (vertices, indices) = (vertices.into(), indices.into());
+        let uvs = attributes.uvs.unwrap_or_default();
+        let colours = attributes.colours.unwrap_or_default();
+        assert!(
+            uvs.is_empty() || uvs.len() == vertices.len(),
+            "must have one UV per vertex"
+        );
+        assert!(
+            colours.is_empty() || colours.len() == vertices.len(),
+            "must have one colour per vertex"
+        );
+
+        assert!(
+            indices.iter().flatten().all(|&i| i < vertices.len()),
+            "triangle indices must be within bounds of vertices (len {})",
+            vertices.len()
+        );
+
+        let normals = match normals.into().unwrap_or_default() {
+            VertexNormals::Flat => vec![],
+            VertexNormals::Provided(normals) => {
+                assert_eq!(normals.len(), vertices.len(), "must have one normal per vertex");
+                normals.into_iter().map(Vector3::normalize).collect()
+            }
+            VertexNormals::AngleWeighted => angle_weighted_normals(&vertices, &indices),
+        };
+
+        let triangles = indices
+            .iter()
+            .filter_map(|&tri| {
+                let verts = tri.map(|i| vertices[i]);
+                if verts[0] == verts[1] || verts[1] == verts[2] || verts[2] == verts[0] {
+                    warn!(target: MESH, "triangle with duplicate vertices; verts: {verts:?}");
+                    return None;
+                }
+
+                let normals = if normals.is_empty() {
+                    [face_normal(verts)?; 3]
+                } else {
+                    tri.map(|i| normals[i])
+                };
+                let mut triangle = Triangle::new(verts, normals);
+                if !uvs.is_empty() {
+                    triangle = triangle.with_uvs(tri.map(|i| uvs[i]));
+                }
+                if !colours.is_empty() {
+                    triangle = triangle.with_colours(tri.map(|i| colours[i]));
+                }
+                Some(triangle)
+            })
+            .collect::<Vec<_>>();
+
+        let centre = vertices
+            .iter()
+            .copied()
+            .map(Point3::to_vector)
+            .fold(Vector3::ZERO, Vector3::add)
+            .div(vertices.len().max(1) as Number)
+            .to_point();
+
+        Self {
+            count: triangles.len(),
+            mesh: BvhMesh::new(triangles),
+            vertices,
+            normals,
+            indices,
+            uvs,
+            colours,
+            centre,
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Normals
+
+/// Calculates the geometric normal of a triangle, using counter-clockwise winding.
+///
+/// Returns [None] if the triangle is degenerate (has zero area)
+pub fn face_normal([a, b, c]: [Point3; 3]) -> Option<Vector3> { Vector3::cross(b - a, c - a).try_normalize() }
+
+/// Calculates smooth normals for each of the vertices, by averaging the normals of all the faces that share a vertex,
+/// weighted by the angle of the face's corner at that vertex.
+///
+/// Vertices that aren't part of any (non-degenerate) triangle get an arbitrary normal.
+pub fn angle_weighted_normals(vertices: &[Point3], indices: &[[usize; 3]]) -> Vec<Vector3> {
+    /*
+    CREDITS:
+
+    Title: "Computing Vertex Normals from Polygonal Facets"
+    Author: Grit Thürmer, Charles A. Wüthrich
+    URL: <https://doi.org/10.1080/10867651.1998.10487487>
+    */
+    let mut accum = vec![Vector3::ZERO; vertices.len()];
+
+    for &tri in indices {
+        let verts = tri.map(|i| vertices[i]);
+        let Some(normal) = face_normal(verts) else {
+            continue;
+        };
+
+        for corner in 0..3 {
+            let p = verts[corner];
+            let (Some(e1), Some(e2)) = (
+                (verts[(corner + 1) % 3] - p).try_normalize(),
+                (verts[(corner + 2) % 3] - p).try_normalize(),
+            ) else {
+                continue;
+            };
+            let angle = Vector3::dot(e1, e2).clamp(-1., 1.).acos();
+            accum[tri[corner]] += normal * angle;
+        }
+    }
+
+    accum
+        .into_iter()
+        .map(|n| n.try_normalize().unwrap_or(Vector3::new(0., 1., 0.)))
+        .collect()
+}
+
+// endregion Normals
+
+// region Mesh Impl
+
+impl HasAabb for IndexedTriangleMesh {
+    fn aabb(&self) -> Option<&Aabb> { self.mesh.aabb() }
+}
+
+impl MeshProperties for IndexedTriangleMesh {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+impl Mesh for IndexedTriangleMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        self.mesh.intersect(ray, interval, rng)
+    }
+}
+
+// endregion Mesh Impl
@@ -1,4 +1,11 @@
 pub mod bvh;
+pub mod csg;
+pub mod curve;
 pub mod dynamic;
+pub mod indexed_triangle;
 pub mod list;
+pub mod point_cloud;
+pub mod polygon;
+pub mod subdivision;
 pub mod triangle;
+pub mod voxel_grid;
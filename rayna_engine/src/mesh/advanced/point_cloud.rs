@@ -0,0 +1,232 @@
+use crate::core::types::{Colour, Number, Point2, Point3, Vector3};
+use crate::mesh::advanced::bvh::BvhMesh;
+use crate::mesh::planar::AABB_PADDING;
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::CopyGetters;
+use rand::Rng;
+use rand_core::RngCore;
+use serde::Serialize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// How each point in a [PointCloudMesh] is drawn
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum SplatShape {
+    /// A solid disc, with a hard edge
+    Disc,
+    /// A disc that fades out from the centre with a Gaussian falloff, so that overlapping points blend smoothly
+    /// together.
+    ///
+    /// The fading is done stochastically (rays randomly pass through the splat, more often further from the centre),
+    /// so it converges over many samples, and needs no sorting
+    Gaussian,
+}
+
+/// A single point in a [PointCloudMesh]
+#[derive(Copy, Clone, Debug)]
+pub struct Splat {
+    pub pos: Point3,
+    pub radius: Number,
+    /// The direction the splat faces (e.g. the surface normal from a scan), or [None] if it should always face
+    /// the ray (e.g. for particles). Must be normalised
+    pub normal: Option<Vector3>,
+    /// The colour of the point, returned as [Intersection::colour]
+    pub colour: Option<Colour>,
+}
+
+impl Splat {
+    /// Creates a new splat that always faces the ray, and has no colour
+    pub fn new(pos: impl Into<Point3>, radius: Number) -> Self {
+        Self {
+            pos: pos.into(),
+            radius,
+            normal: None,
+            colour: None,
+        }
+    }
+
+    /// Makes the splat face in the given direction
+    ///
+    /// # Panics
+    /// Panics if the normal is zero
+    pub fn with_normal(self, normal: impl Into<Vector3>) -> Self {
+        Self {
+            normal: Some(normal.into().try_normalize().expect("splat normal must not be zero")),
+            ..self
+        }
+    }
+
+    pub fn with_colour(self, colour: impl Into<Colour>) -> Self {
+        Self {
+            colour: Some(colour.into()),
+            ..self
+        }
+    }
+}
+
+/// A large number of small discs (**splats**), for visualising point clouds such as LiDAR scans or photogrammetry,
+/// and particle effects.
+///
+/// Points are stored in a [BvhMesh], so millions of points can be rendered without needing to mesh them first.
+///
+/// # Sides
+/// The [Intersection::side] is the index of the point that was hit. The UVs are across the splat, with the centre at
+/// `(0.5, 0.5)`, like [DiskMesh](crate::mesh::planar::disk::DiskMesh). The colour of each point is returned as
+/// [Intersection::colour] (e.g. for use with a
+/// [VertexColourTexture](crate::texture::vertex_colour::VertexColourTexture)).
+#[derive(Clone, Debug, CopyGetters)]
+pub struct PointCloudMesh {
+    #[get_copy = "pub"]
+    shape: SplatShape,
+    /// How many points there are
+    #[get_copy = "pub"]
+    count: usize,
+    bvh: BvhMesh<SplatMesh>,
+}
+
+// region Constructors
+
+impl PointCloudMesh {
+    /// Creates a new point cloud from the given points
+    ///
+    /// # Panics
+    /// Panics if there are no points, or any of the radii are not positive
+    pub fn new(points: impl IntoIterator<Item = Splat>, shape: SplatShape) -> Self {
+        let splats = points
+            .into_iter()
+            .enumerate()
+            .map(|(index, splat)| {
+                assert!(splat.radius > 0., "splat radius must be positive, was {}", splat.radius);
+                SplatMesh::new(splat, index, shape)
+            })
+            .collect::<Vec<_>>();
+        assert!(!splats.is_empty(), "point cloud should have at least one point");
+
+        Self {
+            shape,
+            count: splats.len(),
+            bvh: BvhMesh::new(splats),
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl HasAabb for PointCloudMesh {
+    fn aabb(&self) -> Option<&Aabb> { self.bvh.aabb() }
+}
+
+impl MeshProperties for PointCloudMesh {
+    fn centre(&self) -> Point3 { self.bvh.centre() }
+}
+
+impl Mesh for PointCloudMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        self.bvh.intersect(ray, interval, rng)
+    }
+}
+
+// endregion Mesh Impl
+
+// region Splat
+
+/// A single splat, which is what is actually stored in the BVH
+#[derive(Copy, Clone, Debug)]
+struct SplatMesh {
+    splat: Splat,
+    index: usize,
+    shape: SplatShape,
+    aabb: Aabb,
+}
+
+impl SplatMesh {
+    fn new(splat: Splat, index: usize, shape: SplatShape) -> Self {
+        // Billboards can face any direction, so bound the whole sphere
+        let half_size = match splat.normal {
+            Some(normal) => {
+                let extent = |a: Number| (1. - (a * a)).max(0.).sqrt();
+                Vector3::new(extent(normal.x), extent(normal.y), extent(normal.z)) * splat.radius
+            }
+            None => Vector3::splat(splat.radius),
+        };
+        Self {
+            splat,
+            index,
+            shape,
+            aabb: Aabb::new(splat.pos - half_size, splat.pos + half_size).min_padded(AABB_PADDING),
+        }
+    }
+}
+
+impl HasAabb for SplatMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for SplatMesh {
+    fn centre(&self) -> Point3 { self.splat.pos }
+}
+
+impl Mesh for SplatMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        let Splat {
+            pos,
+            radius,
+            normal,
+            colour,
+        } = self.splat;
+        let normal = normal.unwrap_or(-ray.dir());
+
+        let denominator = Vector3::dot(ray.dir(), normal);
+        // Edge-on, so can't be seen
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let dist = Vector3::dot(pos - ray.pos(), normal) / denominator;
+        if !interval.contains(&dist) {
+            return None;
+        }
+
+        let pos_w = ray.at(dist);
+        let offset = pos_w - pos;
+        let r2 = offset.length_squared() / (radius * radius);
+        if r2 > 1. {
+            return None;
+        }
+        if self.shape == SplatShape::Gaussian {
+            // Standard deviation of a third of the radius, so it has (nearly) faded out by the edge
+            let opacity = Number::exp(-4.5 * r2);
+            if rng.gen::<Number>() >= opacity {
+                return None;
+            }
+        }
+
+        // UVs along any pair of axes on the plane of the splat
+        let (u, v) = Vector3::any_orthonormal_pair(&normal);
+        let uv = Point2::new(
+            0.5 + (Vector3::dot(offset, u) / (radius * 2.)),
+            0.5 + (Vector3::dot(offset, v) / (radius * 2.)),
+        );
+
+        let front_face = denominator < 0.;
+        Some(Intersection {
+            pos_w,
+            pos_l: offset.to_point(),
+            normal,
+            ray_normal: if front_face { normal } else { -normal },
+            front_face,
+            dist,
+            uv,
+            side: self.index,
+            colour,
+            tangent: Some(u),
+        })
+    }
+}
+
+// endregion Splat
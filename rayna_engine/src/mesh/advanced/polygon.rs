@@ -0,0 +1,191 @@
+use crate::core::targets::MESH;
+use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::mesh::advanced::indexed_triangle::{IndexedTriangleMesh, VertexAttributes, VertexNormals};
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::Getters;
+use rand_core::RngCore;
+use tracing::warn;
+
+/// A mesh made from polygons with any number of vertices (**n-gons**), such as the quads and n-gons found in OBJ
+/// files.
+///
+/// The polygons are split into triangles when the mesh is created (see [triangulate()]), and stored as an
+/// [IndexedTriangleMesh], so they are just as fast to render as triangles.
+///
+/// # Polygons
+/// Each polygon must be (roughly) planar and wound counter-clockwise, but can be concave. Polygons that cross over
+/// themselves can't be triangulated properly, and will be filled in as best as possible.
+#[derive(Clone, Debug, Getters)]
+#[get = "pub"]
+pub struct PolygonMesh {
+    /// The indices of the vertices that make up each polygon
+    polygons: Vec<Vec<usize>>,
+    /// The triangulated mesh
+    mesh: IndexedTriangleMesh,
+}
+
+// region Constructors
+
+impl PolygonMesh {
+    /// Creates a new mesh from the given vertices, and the polygons formed by indexing into them
+    ///
+    /// # Panics
+    /// Panics if any polygon has fewer than three vertices. Also see [IndexedTriangleMesh::new()]
+    pub fn new(
+        vertices: impl Into<Vec<Point3>>,
+        polygons: impl IntoIterator<Item = impl Into<Vec<usize>>>,
+        normals: impl Into<Option<VertexNormals>>,
+    ) -> Self {
+        Self::new_with_attributes(vertices, polygons, normals, VertexAttributes::default())
+    }
+
+    /// Creates a new mesh, like [Self::new()], with extra per-vertex attributes (UVs and colours)
+    ///
+    /// # Panics
+    /// See [Self::new()] and [IndexedTriangleMesh::new_with_attributes()]
+    pub fn new_with_attributes(
+        vertices: impl Into<Vec<Point3>>,
+        polygons: impl IntoIterator<Item = impl Into<Vec<usize>>>,
+        normals: impl Into<Option<VertexNormals>>,
+        attributes: VertexAttributes,
+    ) -> Self {
+        let vertices = vertices.into();
+        let polygons = polygons.into_iter().map(Into::into).collect::<Vec<Vec<usize>>>();
+        assert!(
+            polygons.iter().all(|polygon| polygon.len() >= 3),
+            "polygons must have at least three vertices"
+        );
+        assert!(
+            polygons.iter().flatten().all(|&i| i < vertices.len()),
+            "polygon indices must be within bounds of vertices (len {})",
+            vertices.len()
+        );
+
+        let indices = polygons
+            .iter()
+            .flat_map(|polygon| triangulate(&vertices, polygon))
+            .collect::<Vec<_>>();
+
+        Self {
+            mesh: IndexedTriangleMesh::new_with_attributes(vertices, indices, normals, attributes),
+            polygons,
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Triangulation
+
+/// Splits a planar polygon into triangles, using **ear-clipping**, which works for concave polygons too.
+///
+/// The `polygon` is the indices of its vertices (counter-clockwise), and the returned triangles index into the same
+/// `vertices`, with the same winding.
+///
+/// If the polygon can't be triangulated properly (e.g. it crosses over itself), the rest of it is filled with a
+/// triangle fan.
+pub fn triangulate(vertices: &[Point3], polygon: &[usize]) -> Vec<[usize; 3]> {
+    /*
+    CREDITS:
+
+    Title: "Triangulation by Ear Clipping"
+    Authors:
+        - David Eberly
+    URL: <https://www.geometrictools.com/Documentation/TriangulationByEarClipping.pdf>
+    Publisher: Geometric Tools
+    Version: 2002
+    */
+
+    match polygon {
+        [] | [_] | [_, _] => return vec![],
+        &[a, b, c] => return vec![[a, b, c]],
+        _ => {}
+    }
+
+    // Project onto the plane of the polygon, so we can work in 2D
+    let normal = newell_normal(polygon.iter().map(|&i| vertices[i]));
+    let (u, _) = Vector3::any_orthonormal_pair(&normal);
+    let v = Vector3::cross(normal, u);
+    let project = |i: usize| {
+        let p = vertices[i].to_vector();
+        Point2::new(Vector3::dot(p, u), Vector3::dot(p, v))
+    };
+
+    let mut remaining = polygon.iter().map(|&i| (i, project(i))).collect::<Vec<_>>();
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let [(_, a), (_, b), (_, c)] = [remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]];
+            // Reflex corners can't be ears, and neither can corners with another vertex inside them
+            cross_2d(a, b, c) > 0.
+                && !remaining
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i && j != (i + n - 1) % n && j != (i + 1) % n)
+                    .any(|(_, &(_, p))| in_triangle(p, [a, b, c]))
+        });
+
+        let Some(ear) = ear else {
+            warn!(target: MESH, ?polygon, "couldn't triangulate polygon; filling the rest with a fan");
+            break;
+        };
+        triangles.push([
+            remaining[(ear + n - 1) % n].0,
+            remaining[ear].0,
+            remaining[(ear + 1) % n].0,
+        ]);
+        remaining.remove(ear);
+    }
+
+    triangles.extend((1..remaining.len() - 1).map(|i| [remaining[0].0, remaining[i].0, remaining[i + 1].0]));
+    triangles
+}
+
+/// Calculates the normal of a (possibly concave) polygon, using Newell's method.
+///
+/// This is robust to concave corners, and to polygons that aren't quite planar
+fn newell_normal(points: impl Iterator<Item = Point3> + Clone) -> Vector3 {
+    let next = points.clone().cycle().skip(1);
+    let normal = points.zip(next).fold(Vector3::ZERO, |n, (a, b)| {
+        n + Vector3::new(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        )
+    });
+    normal.try_normalize().unwrap_or(Vector3::Z)
+}
+
+/// Twice the signed area of the 2D triangle `abc`, which is positive if it is counter-clockwise
+fn cross_2d(a: Point2, b: Point2, c: Point2) -> Number { ((b.x - a.x) * (c.y - a.y)) - ((b.y - a.y) * (c.x - a.x)) }
+
+/// Is the point `p` inside (or on the edge of) the counter-clockwise 2D triangle?
+fn in_triangle(p: Point2, [a, b, c]: [Point2; 3]) -> bool {
+    cross_2d(a, b, p) >= 0. && cross_2d(b, c, p) >= 0. && cross_2d(c, a, p) >= 0.
+}
+
+// endregion Triangulation
+
+// region Mesh Impl
+
+impl HasAabb for PolygonMesh {
+    fn aabb(&self) -> Option<&Aabb> { self.mesh.aabb() }
+}
+
+impl MeshProperties for PolygonMesh {
+    fn centre(&self) -> Point3 { self.mesh.centre() }
+}
+
+impl Mesh for PolygonMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        self.mesh.intersect(ray, interval, rng)
+    }
+}
+
+// endregion Mesh Impl
@@ -0,0 +1,282 @@
+use crate::core::types::{Number, Point3, Vector3};
+use crate::mesh::advanced::indexed_triangle::{IndexedTriangleMesh, VertexNormals};
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use smallvec::SmallVec;
+use std::collections::HashMap;
+
+/// A smooth surface, made by repeatedly subdividing a coarse polygon mesh (the **cage**) using the
+/// **Catmull-Clark** algorithm.
+///
+/// This lets low-poly models be rendered as smooth objects. The subdivision is all done up-front, and the result is
+/// stored as an [IndexedTriangleMesh] with smooth normals.
+///
+/// # Cage
+/// The faces of the cage can have any number of vertices (though quads work best), and must be wound
+/// counter-clockwise. Edges that are only used by one face are treated as boundaries, which are kept sharp
+/// (they follow a B-spline curve, instead of shrinking away).
+///
+/// # Limit Surface
+/// After the last subdivision, the vertices are moved onto the limit surface (where they would end up after
+/// infinitely many subdivisions), so even low levels give the correct shape.
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct SubdivisionMesh {
+    /// How many times the cage was subdivided
+    #[get_copy = "pub"]
+    level: usize,
+    /// The triangulated limit surface
+    #[get = "pub"]
+    mesh: IndexedTriangleMesh,
+}
+
+// region Constructors
+
+impl SubdivisionMesh {
+    /// Creates a new subdivision surface, from the vertices and faces of the cage.
+    ///
+    /// Each level of subdivision multiplies the number of faces by four (after the first level, which splits each
+    /// `n`-sided face into `n` quads), so keep it low. A level of zero just triangulates the cage.
+    ///
+    /// # Panics
+    /// Panics if any face has fewer than three vertices, or any of the indices are out of bounds
+    pub fn new(
+        vertices: impl Into<Vec<Point3>>,
+        faces: impl IntoIterator<Item = impl Into<Vec<usize>>>,
+        level: usize,
+    ) -> Self {
+        let mut vertices = vertices.into();
+        let mut faces = faces.into_iter().map(Into::into).collect::<Vec<Vec<usize>>>();
+        for face in &faces {
+            assert!(face.len() >= 3, "faces must have at least three vertices: {face:?}");
+            assert!(
+                face.iter().all(|&i| i < vertices.len()),
+                "face indices must be in bounds: {face:?}"
+            );
+        }
+
+        for _ in 0..level {
+            (vertices, faces) = subdivide(&vertices, &faces);
+        }
+        if level > 0 {
+            vertices = limit_positions(&vertices, &faces);
+        }
+
+        // Fan-triangulate each face (they're all quads after subdividing, so this is just two triangles)
+        let indices = faces
+            .iter()
+            .flat_map(|face| (1..face.len() - 1).map(|i| [face[0], face[i], face[i + 1]]))
+            .collect::<Vec<_>>();
+
+        Self {
+            level,
+            mesh: IndexedTriangleMesh::new(vertices, indices, VertexNormals::AngleWeighted),
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Subdivision
+
+/// The (sorted) vertex indices of an edge
+type EdgeKey = (usize, usize);
+
+fn edge_key(a: usize, b: usize) -> EdgeKey { (a.min(b), a.max(b)) }
+
+/// Iterates over the edges of a face, as pairs of consecutive vertices
+fn face_edges(face: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    face.iter().zip(face.iter().cycle().skip(1)).map(|(&a, &b)| (a, b))
+}
+
+/// Which faces use each edge
+fn edge_faces(faces: &[Vec<usize>]) -> HashMap<EdgeKey, SmallVec<[usize; 2]>> {
+    let mut edges = HashMap::<EdgeKey, SmallVec<[usize; 2]>>::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (a, b) in face_edges(face) {
+            edges.entry(edge_key(a, b)).or_default().push(f);
+        }
+    }
+    edges
+}
+
+fn centroid(points: impl IntoIterator<Item = Point3>) -> Point3 {
+    let (sum, count) = points
+        .into_iter()
+        .fold((Vector3::ZERO, 0), |(sum, count), p| (sum + p.to_vector(), count + 1));
+    (sum / count as Number).to_point()
+}
+
+/// Does a single level of Catmull-Clark subdivision, returning the new vertices and (quad) faces
+fn subdivide(vertices: &[Point3], faces: &[Vec<usize>]) -> (Vec<Point3>, Vec<Vec<usize>>) {
+    /*
+    CREDITS:
+
+    Title: "Recursively generated B-spline surfaces on arbitrary topological meshes"
+    Authors:
+        - Edwin Catmull
+        - Jim Clark
+    URL: <https://doi.org/10.1016/0010-4485(78)90110-0>
+    Publisher: Computer-Aided Design
+    Version: vol. 10, no. 6, 350-355, 1978
+    */
+
+    let face_points = faces
+        .iter()
+        .map(|face| centroid(face.iter().map(|&i| vertices[i])))
+        .collect::<Vec<_>>();
+    let edges = edge_faces(faces);
+
+    // New vertices are laid out as `[moved original vertices, face points, edge points]`
+    let face_offset = vertices.len();
+    let edge_offset = face_offset + faces.len();
+    let mut edge_indices = HashMap::with_capacity(edges.len());
+    let mut edge_points = Vec::with_capacity(edges.len());
+    for (&(a, b), adjacent) in &edges {
+        let point = if adjacent.len() == 2 {
+            centroid([
+                vertices[a],
+                vertices[b],
+                face_points[adjacent[0]],
+                face_points[adjacent[1]],
+            ])
+        } else {
+            // Boundary (or non-manifold), so stays on the edge
+            centroid([vertices[a], vertices[b]])
+        };
+        edge_indices.insert((a, b), edge_offset + edge_points.len());
+        edge_points.push(point);
+    }
+
+    // Move the original vertices, based on their neighbours
+    let mut vertex_faces = vec![SmallVec::<[usize; 6]>::new(); vertices.len()];
+    for (f, face) in faces.iter().enumerate() {
+        face.iter().for_each(|&v| vertex_faces[v].push(f));
+    }
+    let mut vertex_edges = vec![SmallVec::<[EdgeKey; 6]>::new(); vertices.len()];
+    for &(a, b) in edges.keys() {
+        vertex_edges[a].push((a, b));
+        vertex_edges[b].push((a, b));
+    }
+    let moved = (0..vertices.len()).map(|v| {
+        let p = vertices[v];
+        let other = |&(a, b): &EdgeKey| if a == v { b } else { a };
+        let boundary = vertex_edges[v]
+            .iter()
+            .filter(|e| edges[*e].len() != 2)
+            .map(|e| vertices[other(e)])
+            .collect::<SmallVec<[_; 2]>>();
+
+        if vertex_faces[v].is_empty() {
+            // Not part of any face, leave it alone
+            p
+        } else if let [e1, e2] = boundary[..] {
+            // Boundary vertices follow the cubic B-spline along the boundary
+            (((p.to_vector() * 6.) + e1.to_vector() + e2.to_vector()) / 8.).to_point()
+        } else if !boundary.is_empty() {
+            // Non-manifold vertices (where more than two boundaries meet) stay sharp
+            p
+        } else {
+            let n = vertex_edges[v].len() as Number;
+            let f = centroid(vertex_faces[v].iter().map(|&f| face_points[f]));
+            let r = centroid(
+                vertex_edges[v]
+                    .iter()
+                    .map(|&(a, b)| centroid([vertices[a], vertices[b]])),
+            );
+            ((f.to_vector() + (r.to_vector() * 2.) + (p.to_vector() * (n - 3.))) / n).to_point()
+        }
+    });
+
+    let new_vertices = moved.chain(face_points.iter().copied()).chain(edge_points).collect();
+    let edge_index = |a: usize, b: usize| edge_indices[&edge_key(a, b)];
+    let new_faces = faces
+        .iter()
+        .enumerate()
+        .flat_map(|(f, face)| {
+            let n = face.len();
+            (0..n).map(move |i| {
+                let (prev, curr, next) = (face[(i + n - 1) % n], face[i], face[(i + 1) % n]);
+                vec![curr, edge_index(curr, next), face_offset + f, edge_index(prev, curr)]
+            })
+        })
+        .collect();
+
+    (new_vertices, new_faces)
+}
+
+/// Moves each vertex of a (quad-only) mesh onto the Catmull-Clark limit surface
+fn limit_positions(vertices: &[Point3], faces: &[Vec<usize>]) -> Vec<Point3> {
+    /*
+    CREDITS:
+
+    Title: "Exact evaluation of Catmull-Clark subdivision surfaces at arbitrary parameter values"
+    Authors:
+        - Jos Stam
+    URL: <https://doi.org/10.1145/280814.280945>
+    Publisher: SIGGRAPH
+    Version: 1998
+    */
+
+    let edges = edge_faces(faces);
+    // Sums of the edge neighbours, and the diagonal neighbours (opposite corners of each quad)
+    let mut edge_sum = vec![Vector3::ZERO; vertices.len()];
+    let mut valence = vec![0_usize; vertices.len()];
+    let mut boundary = vec![SmallVec::<[usize; 2]>::new(); vertices.len()];
+    for (&(a, b), adjacent) in &edges {
+        edge_sum[a] += vertices[b].to_vector();
+        edge_sum[b] += vertices[a].to_vector();
+        valence[a] += 1;
+        valence[b] += 1;
+        if adjacent.len() != 2 {
+            boundary[a].push(b);
+            boundary[b].push(a);
+        }
+    }
+    let mut diagonal_sum = vec![Vector3::ZERO; vertices.len()];
+    for face in faces {
+        let n = face.len();
+        for i in 0..n {
+            diagonal_sum[face[i]] += vertices[face[(i + 2) % n]].to_vector();
+        }
+    }
+
+    (0..vertices.len())
+        .map(|v| {
+            let p = vertices[v].to_vector();
+            let limit = match boundary[v][..] {
+                [] if valence[v] > 0 => {
+                    let n = valence[v] as Number;
+                    ((p * n * n) + (edge_sum[v] * 4.) + diagonal_sum[v]) / (n * (n + 5.))
+                }
+                [e1, e2] => ((p * 4.) + vertices[e1].to_vector() + vertices[e2].to_vector()) / 6.,
+                _ => p,
+            };
+            limit.to_point()
+        })
+        .collect()
+}
+
+// endregion Subdivision
+
+// region Mesh Impl
+
+impl HasAabb for SubdivisionMesh {
+    fn aabb(&self) -> Option<&Aabb> { self.mesh.aabb() }
+}
+
+impl MeshProperties for SubdivisionMesh {
+    fn centre(&self) -> Point3 { self.mesh.centre() }
+}
+
+impl Mesh for SubdivisionMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        self.mesh.intersect(ray, interval, rng)
+    }
+}
+
+// endregion Mesh Impl
@@ -220,6 +220,9 @@ where
             side: 0,
             ray_normal: normal * -det.signum(),
             normal,
+            colour: None,
+            // `u` is the barycentric coordinate along the first edge
+            tangent: Some(Vector3::new(v0v1.0[0][tri_idx], v0v1.0[1][tri_idx], v0v1.0[2][tri_idx])),
         })
     }
 }
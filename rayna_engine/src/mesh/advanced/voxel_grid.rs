@@ -0,0 +1,285 @@
+use crate::core::types::{Colour, Number, Point2, Point3, Vector3};
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter};
+
+/// The value of a voxel. `0` is empty, and any other value is solid, with the value being its material index
+pub type Voxel = u8;
+
+/// How the voxels in a [VoxelGridMesh] are stored
+#[derive(Clone)]
+pub enum VoxelStorage {
+    /// Every voxel is stored, in `x`, then `y`, then `z` order.
+    ///
+    /// Best for grids that are mostly full, such as terrain or volumetric captures
+    Dense(Vec<Voxel>),
+    /// Only the solid voxels are stored.
+    ///
+    /// Best for large grids that are mostly empty, at the cost of slower lookups
+    Sparse(HashMap<[usize; 3], Voxel>),
+}
+
+// The `Debug` output is hashed for checkpoints (see [crate::render::checkpoint::scene_hash()]), so the sparse voxels
+// are printed in order, instead of the (random) order of the map
+impl Debug for VoxelStorage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dense(voxels) => f.debug_tuple("Dense").field(voxels).finish(),
+            Self::Sparse(voxels) => f
+                .debug_tuple("Sparse")
+                .field(&voxels.iter().collect::<BTreeMap<_, _>>())
+                .finish(),
+        }
+    }
+}
+
+/// A regular grid of cubic voxels (like Minecraft), each of which is either empty or solid.
+///
+/// This is much cheaper than making a triangle mesh (or a [BvhMesh](super::bvh::BvhMesh) of boxes) for each voxel,
+/// since rays step through the grid one voxel at a time (**3D-DDA**), which only needs a lookup per voxel and no
+/// acceleration structure.
+///
+/// # Materials
+/// Each solid voxel has a material index. Since meshes don't have materials, the index is used to look up a colour
+/// in the [palette](Self::palette()), which is returned as [Intersection::colour] (e.g. for use with a
+/// [VertexColourTexture](crate::texture::vertex_colour::VertexColourTexture)).
+///
+/// # Sides
+/// The same as [AxisBoxMesh](crate::mesh::primitive::axis_box::AxisBoxMesh): `x: 0, 1; y: 2, 3; z: 4, 5`,
+/// negative side first. The UVs are across the face of the voxel that was hit.
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct VoxelGridMesh {
+    /// The corner of the grid with the smallest coordinates
+    #[get_copy = "pub"]
+    origin: Point3,
+    /// The size of each (cubic) voxel
+    #[get_copy = "pub"]
+    voxel_size: Number,
+    /// The number of voxels along each axis
+    #[get_copy = "pub"]
+    dims: [usize; 3],
+    #[get = "pub"]
+    voxels: VoxelStorage,
+    /// The colours for each material index, see [Self]
+    #[get = "pub"]
+    palette: Vec<Colour>,
+    #[get_copy = "pub"]
+    centre: Point3,
+    aabb: Aabb,
+}
+
+// region Constructors
+
+impl VoxelGridMesh {
+    /// Creates a new grid from densely-stored voxels.
+    ///
+    /// # Panics
+    /// Panics if the number of voxels doesn't match the dimensions
+    pub fn new_dense(origin: impl Into<Point3>, voxel_size: Number, dims: [usize; 3], voxels: Vec<Voxel>) -> Self {
+        let [w, h, d] = dims;
+        assert_eq!(
+            voxels.len(),
+            w * h * d,
+            "number of voxels should match dimensions {dims:?}"
+        );
+        Self::new(origin.into(), voxel_size, dims, VoxelStorage::Dense(voxels))
+    }
+
+    /// Creates a new grid from the positions of the solid voxels. See [VoxelStorage::Sparse]
+    ///
+    /// Any empty voxels (with value `0`) are ignored.
+    ///
+    /// # Panics
+    /// Panics if any of the voxels are outside the dimensions
+    pub fn new_sparse(
+        origin: impl Into<Point3>,
+        voxel_size: Number,
+        dims: [usize; 3],
+        voxels: impl IntoIterator<Item = ([usize; 3], Voxel)>,
+    ) -> Self {
+        let voxels = voxels
+            .into_iter()
+            .filter(|&(_, voxel)| voxel != 0)
+            .inspect(|(pos, _)| {
+                assert!(
+                    (0..3).all(|i| pos[i] < dims[i]),
+                    "voxel {pos:?} should be within dimensions {dims:?}"
+                )
+            })
+            .collect();
+        Self::new(origin.into(), voxel_size, dims, VoxelStorage::Sparse(voxels))
+    }
+
+    fn new(origin: Point3, voxel_size: Number, dims: [usize; 3], voxels: VoxelStorage) -> Self {
+        assert!(voxel_size > 0., "voxel size must be positive, was {voxel_size}");
+        let size = Vector3::from(dims.map(|d| d as Number)) * voxel_size;
+        Self {
+            origin,
+            voxel_size,
+            dims,
+            voxels,
+            palette: vec![],
+            centre: origin + (size / 2.),
+            aabb: Aabb::new(origin, origin + size),
+        }
+    }
+
+    /// Sets the colour for each material index. Index `0` is never used, since those voxels are empty
+    pub fn with_palette(self, palette: impl IntoIterator<Item = impl Into<Colour>>) -> Self {
+        Self {
+            palette: palette.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Voxels
+
+impl VoxelGridMesh {
+    /// Returns the voxel at the given grid position, which is empty (`0`) if outside the grid
+    pub fn get(&self, [x, y, z]: [usize; 3]) -> Voxel {
+        let [w, h, d] = self.dims;
+        if x >= w || y >= h || z >= d {
+            return 0;
+        }
+        match &self.voxels {
+            VoxelStorage::Dense(voxels) => voxels[x + (w * (y + (h * z)))],
+            VoxelStorage::Sparse(voxels) => voxels.get(&[x, y, z]).copied().unwrap_or(0),
+        }
+    }
+}
+
+// endregion Voxels
+
+// region Mesh Impl
+
+impl Mesh for VoxelGridMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
+        /*
+        CREDITS:
+
+        Title: "A Fast Voxel Traversal Algorithm for Ray Tracing"
+        Authors:
+            - John Amanatides
+            - Andrew Woo
+        URL: <http://www.cse.yorku.ca/~amana/research/grid.pdf>
+        Publisher: Eurographics
+        Version: 1987
+        */
+
+        // Clip the ray to the bounds of the grid
+        let t1 = (self.aabb.min() - ray.pos()) * ray.inv_dir();
+        let t2 = (self.aabb.max() - ray.pos()) * ray.inv_dir();
+        let t_near = t1.min(t2);
+        let (t_enter, t_exit) = (t_near.max_element(), t1.max(t2).min_element());
+        let start = t_enter.max(interval.start.unwrap_or(Number::NEG_INFINITY));
+        let end = t_exit.min(interval.end.unwrap_or(Number::INFINITY));
+        if start > end {
+            return None;
+        }
+
+        // Find the voxel we start in, in grid coordinates
+        let dims = self.dims.map(|d| d as isize);
+        let grid_pos = (ray.at(start) - self.origin) / self.voxel_size;
+        let mut cell = [0, 1, 2].map(|i| (grid_pos.as_array()[i].floor() as isize).clamp(0, dims[i] - 1));
+        let voxel_at = |cell: [isize; 3]| self.get(cell.map(|c| c as usize));
+
+        let dir = ray.dir();
+        let step = [0, 1, 2].map(|i| dir.as_array()[i].signum() as isize);
+        // Distance along the ray to cross one voxel, and to reach the next voxel boundary, on each axis
+        let mut t_max = [Number::INFINITY; 3];
+        let mut t_delta = [Number::INFINITY; 3];
+        for i in 0..3 {
+            if dir.as_array()[i] == 0. {
+                continue;
+            }
+            let next = cell[i] + (step[i] > 0) as isize;
+            let boundary = self.origin.as_array()[i] + (next as Number * self.voxel_size);
+            t_max[i] = (boundary - ray.pos().as_array()[i]) / dir.as_array()[i];
+            t_delta[i] = self.voxel_size / dir.as_array()[i].abs();
+        }
+
+        // If the ray starts inside a solid voxel, we're looking for where it leaves instead
+        let inside = start > t_enter && voxel_at(cell) != 0;
+        // When entering from outside the grid, the first face is the one the ray entered through
+        let mut axis = t_near
+            .as_array()
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(0, |(i, _)| i);
+        let mut dist = start;
+        let mut voxel = voxel_at(cell);
+
+        // Unless we entered straight into a solid voxel, step through the grid until we cross a surface
+        if inside || voxel == 0 {
+            loop {
+                // Step into the next voxel, along whichever axis has the closest boundary
+                axis = (0..3).min_by(|&a, &b| t_max[a].total_cmp(&t_max[b])).unwrap_or(0);
+                dist = t_max[axis];
+                if dist > end {
+                    return None;
+                }
+                cell[axis] += step[axis];
+                t_max[axis] += t_delta[axis];
+
+                let in_grid = (0..dims[axis]).contains(&cell[axis]);
+                let next = if in_grid { voxel_at(cell) } else { 0 };
+                match (inside, next) {
+                    // Left the solid, keeping the material of the voxel we were in
+                    (true, 0) => break,
+                    (true, next) => voxel = next,
+                    (false, _) if !in_grid => return None,
+                    (false, 0) => {}
+                    (false, next) => {
+                        voxel = next;
+                        break;
+                    }
+                }
+            }
+        }
+
+        // The face we crossed points against the ray when entering, and with it when leaving
+        let mut normal = [0.; 3];
+        normal[axis] = (if inside { step[axis] } else { -step[axis] }) as Number;
+        let normal = Vector3::from(normal);
+        let front_face = !inside;
+
+        // UVs across the face of the voxel, from the other two axes
+        let pos_w = ray.at(dist);
+        let grid_pos = (pos_w - self.origin) / self.voxel_size;
+        let [u_axis, v_axis] = [(axis + 1) % 3, (axis + 2) % 3];
+        let uv = Point2::new(grid_pos.as_array()[u_axis].fract(), grid_pos.as_array()[v_axis].fract());
+
+        Some(Intersection {
+            pos_w,
+            pos_l: pos_w - self.centre.to_vector(),
+            normal,
+            ray_normal: if front_face { normal } else { -normal },
+            front_face,
+            dist,
+            uv,
+            side: (axis * 2) + (normal.as_array()[axis] > 0.) as usize,
+            colour: self.palette.get(voxel as usize).copied(),
+            tangent: None,
+        })
+    }
+}
+
+impl HasAabb for VoxelGridMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for VoxelGridMesh {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+// endregion Mesh Impl
@@ -3,6 +3,7 @@ use dyn_clone::DynClone;
 
 pub mod polygonised;
 pub mod raymarched;
+pub mod sdf;
 
 pub trait SdfGeneratorFunction: Fn(Point3) -> Number + Send + Sync + DynClone {}
 impl<T: Fn(Point3) -> Number + Send + Sync + Clone> SdfGeneratorFunction for T {}
@@ -1,49 +1,59 @@
 use crate::core::targets::MESH;
 use crate::core::types::{Number, Point3, Vector3};
 use crate::mesh::advanced::bvh::BvhMesh;
-//use crate::mesh::advanced::triangle::BatchTriangle;
 use crate::mesh::isosurface::SdfGeneratorFunction;
 use crate::mesh::primitive::triangle::Triangle;
 use crate::mesh::{Mesh, MeshProperties};
 use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::Intersection;
 use crate::shared::interval::Interval;
+use crate::shared::math::solve_3x3;
 use crate::shared::ray::Ray;
 use derivative::Derivative;
 use getset::{CopyGetters, Getters};
-use isosurface::distance::Signed;
-use isosurface::extractor::IndexedInterleavedNormals;
-use isosurface::math::Vec3;
-use isosurface::sampler::Sampler;
-use isosurface::source::{HermiteSource, ScalarSource};
-use isosurface::MarchingCubes;
-use itertools::Itertools;
 use rand_core::RngCore;
-use std::iter::zip;
-use tracing::warn;
-
-/// How many triangles we batch at once
-// TODO: BatchTriangle currently broken
-//const N_TRI: usize = 1;
+use std::collections::HashMap;
+use tracing::{trace, warn};
 
 /// A mesh struct that is created by creating an isosurface from a given SDF
 ///
+/// The surface is found by sampling the SDF on an adaptive octree, so that only the cells near the surface are
+/// subdivided down to the full resolution, and then meshed using **dual contouring**, which places one vertex per
+/// cell where the surface planes in that cell meet. Unlike marching cubes, this preserves sharp edges and corners
+/// of the SDF (e.g. the corners of a box), instead of chamfering them off.
+///
 /// # Transforming
 /// This mesh purposefully does not have any properties for transforming,
 /// so you must offset the resulting object using a transform
 #[derive(CopyGetters, Getters, Derivative, Clone)]
 #[derivative(Debug)]
 pub struct PolygonisedIsosurfaceMesh {
+    /// How many cells there are along each axis at the finest level of the octree.
+    /// This is the requested resolution, rounded up to a power of two
     #[get_copy = "pub"]
     resolution: usize,
     /// How many total triangles there are in this [PolygonisedIsosurfaceMesh]
     #[get_copy = "pub"]
     count: usize,
+    /// How many cells at the finest level were sampled, which is roughly proportional to the surface area, not the
+    /// volume
+    #[get_copy = "pub"]
+    sampled_cells: usize,
     #[derivative(Debug = "ignore")]
     #[get = "pub"]
     mesh: BvhMesh<Triangle>,
 }
 
+/// How much the vertex in each cell is pulled towards the average of the edge crossings.
+///
+/// This keeps the vertex stable when the planes in the cell are (nearly) parallel, without moving it noticeably
+/// when they aren't
+const QEF_BIAS: Number = 1e-2;
+
+/// If a vertex normal is further than this (cosine of the angle) from the normal of the triangle, the triangle is
+/// across a sharp edge, so it is shaded flat instead
+const SHARP_COS: Number = 0.7;
+
 // region Constructors
 
 impl PolygonisedIsosurfaceMesh {
@@ -52,82 +62,89 @@ impl PolygonisedIsosurfaceMesh {
     /// # Arguments
     ///
     /// * `resolution`: How dense the resulting mesh should be.
-    /// The resulting mesh has dimensions of a `N*N*N` grid, where `N = resolution`
+    /// The surface is sampled at most as finely as a `N*N*N` grid, where `N = resolution` (rounded up to a power
+    /// of two)
     /// * `sdf`: The **SDF** that defines the surface for the mesh.
     /// This SDF will be evaluated in local-space: `x,y,z: [0, 1]`
+    ///
+    /// # Note
+    /// The SDF must be a true distance (or underestimate it), since empty space is skipped based on the distance to
+    /// the surface. If it is not, use [Self::new_with_lipschitz()]
     pub fn new<F: SdfGeneratorFunction>(resolution: usize, sdf: F) -> Self {
-        let source = SdfWrapper {
+        Self::new_with_lipschitz(resolution, 1., sdf)
+    }
+
+    /// Creates a new mesh, like [Self::new()], for a function that is not a true distance.
+    ///
+    /// The `lipschitz` bound is the most the function can change per unit of distance (i.e. the largest length of
+    /// its gradient), which is `1.0` for a true SDF. If this is too low, parts of the surface may be missing
+    ///
+    /// # Panics
+    /// Panics if the resolution is zero, or the bound isn't positive
+    pub fn new_with_lipschitz<F: SdfGeneratorFunction>(resolution: usize, lipschitz: Number, sdf: F) -> Self {
+        assert!(resolution > 0, "resolution must be non-zero");
+        assert!(lipschitz > 0., "lipschitz bound must be positive (was {lipschitz})");
+
+        let resolution = resolution.next_power_of_two();
+        let mut sampler = Sampler {
             func: sdf,
-            epsilon: 1e-7,
+            scale: 1. / resolution as Number,
+            lipschitz,
+            values: HashMap::new(),
         };
-        // Raw coordinates for the vertices and normals
-        let mut raw_vertex_normal_coords = vec![];
-        let mut raw_indices = vec![];
-        MarchingCubes::<Signed>::new(resolution).extract(
-            &Sampler::new(&source),
-            &mut IndexedInterleavedNormals::new(&mut raw_vertex_normal_coords, &mut raw_indices, &source),
-        );
 
-        assert_eq!(
-            raw_indices.len() % 3,
-            0,
-            "`raw_indices.len` should be multiple of 3 (was {})",
-            raw_indices.len()
-        );
-        assert_eq!(
-            raw_vertex_normal_coords.len() % 6,
-            0,
-            "`raw_vertex_coords.len` should be multiple of 6 (was {})",
-            raw_vertex_normal_coords.len()
-        );
+        let mut cells = vec![];
+        sampler.collect_cells([0; 3], resolution, &mut cells);
+        let sampled_cells = cells.len();
 
-        // Group the vertex coordinates into groups of three, so we get a 3D point
-        // Interleaved with normals, so extract that out too
-        let (raw_verts, raw_normals): (Vec<_>, Vec<_>) = raw_vertex_normal_coords
-            .array_chunks::<3>()
-            .map(|vs| vs.map(|v| v as Number))
-            .array_chunks::<2>()
-            .map(|[v, n]| (Point3::from(v), Vector3::from(n)))
-            .unzip();
-
-        // Group the indices in chunks of three as well, for the three vertices of each triangle
-        let triangle_indices = raw_indices
-            .array_chunks::<3>()
-            .map(|vs| vs.map(|v| v as usize))
-            .collect_vec();
+        // Place a vertex in every cell that the surface passes through
+        let mut cell_vertices = HashMap::new();
+        let mut vertices = vec![];
+        for &cell in &cells {
+            if let Some(vertex) = sampler.cell_vertex(cell) {
+                cell_vertices.insert(cell, vertices.len());
+                vertices.push(vertex);
+            }
+        }
+        let normals = vertices.iter().map(|&v| sampler.gradient(v)).collect::<Vec<_>>();
 
+        // Each edge with a sign change is surrounded by four cells, which form a quad around it
         let mut triangles = vec![];
-
-        // Loop over all indices, map them to the vertex positions, and create a triangle
-        // TODO: I think I'm transposing twice here which is pointless. Maybe optimise that?
-        //  Not super important though since this isn't a hot path
-        let (tri_verts, tri_normals): (Vec<_>, Vec<_>) = triangle_indices
-            .into_iter()
-            // Unpack the vertices and normals for the triangle
-            .map(|vert_indices| (vert_indices.map(|i| raw_verts[i]), vert_indices.map(|i| raw_normals[i])))
-            .filter_map(|(verts, normals)| {
-                // Sometimes this generates "empty" triangles that have duplicate vertices
-                // This is invalid, so skip those. Not sure if it's a bug or intentional :(
-                if verts[0] == verts[1] || verts[1] == verts[2] || verts[2] == verts[0] {
-                    warn!(target: MESH,  "triangle with empty vertices; verts: [{verts:?}]");
-                    return None;
-                }
-                // Normals are not normalised by [SdfSource], so do that here.
-                // If for any vertex there is a zero gradient normal, skip those because
-                // I don't know a good way to handle them
-                let Some(normals) = normals.try_map(Vector3::try_normalize) else {
-                    warn!(target: MESH,  "triangle with empty normals; normals: {normals:?}");
-                    return None;
+        for &cell in cells.iter().filter(|cell| cell_vertices.contains_key(*cell)) {
+            for axis in 0..3 {
+                let Some(quad) = sampler.edge_quad(cell, axis, &cell_vertices) else {
+                    continue;
                 };
-                return Some((verts, normals));
-            })
-            .unzip();
-
-        // Now batch the triangles together
-        // TODO: Don't skip the remainder
-        // for (vertices, normals) in zip(tri_verts.chunks(N_TRI), tri_normals.chunks(N_TRI)) {
-        for (vertices, normals) in zip(tri_verts, tri_normals) {
-            triangles.push(Triangle::new(vertices, normals));
+                for tri in [[quad[0], quad[1], quad[2]], [quad[0], quad[2], quad[3]]] {
+                    // Vertices can end up in the same place (e.g. when clamped to a shared corner), so skip those
+                    let verts = tri.map(|i| vertices[i]);
+                    if verts[0] == verts[1] || verts[1] == verts[2] || verts[2] == verts[0] {
+                        continue;
+                    }
+                    let Some(face_normal) = Vector3::cross(verts[1] - verts[0], verts[2] - verts[0]).try_normalize()
+                    else {
+                        continue;
+                    };
+                    // Smooth shading, except across sharp features
+                    let normals = tri.map(|i| match normals[i] {
+                        Some(n) if Vector3::dot(n, face_normal) >= SHARP_COS => n,
+                        _ => face_normal,
+                    });
+                    triangles.push(Triangle::new(verts, normals));
+                }
+            }
+        }
+
+        trace!(
+            target: MESH,
+            resolution,
+            sampled_cells,
+            vertices = vertices.len(),
+            triangles = triangles.len(),
+            "polygonised isosurface"
+        );
+        if triangles.is_empty() {
+            warn!(target: MESH, "isosurface has no triangles; does the surface lie inside `[0, 1]`?");
         }
 
         let count = triangles.len();
@@ -136,6 +153,7 @@ impl PolygonisedIsosurfaceMesh {
         Self {
             count,
             resolution,
+            sampled_cells,
             mesh,
         }
     }
@@ -143,42 +161,161 @@ impl PolygonisedIsosurfaceMesh {
 
 // endregion Constructors
 
-// region Isosurface Helper
+// region Dual Contouring
 
-/// A custom wrapper struct around an [SdfGeneratorFunction]
-///
-/// It is used for
-struct SdfWrapper<F: SdfGeneratorFunction> {
-    pub func: F,
-    pub epsilon: Number,
+/*
+CREDITS:
+
+Title: "Dual Contouring of Hermite Data"
+Authors:
+    - Tao Ju
+    - Frank Losasso
+    - Scott Schaefer
+    - Joe Warren
+URL: <https://www.cs.rice.edu/~jwarren/papers/dualcontour.pdf>
+Publisher: SIGGRAPH
+Version: 2002
+*/
+
+/// The position of a corner on the grid (or a cell, by its lowest corner)
+type GridPos = [usize; 3];
+
+/// Samples the SDF on the grid, caching the values at each corner so that neighbouring cells share them
+struct Sampler<F: SdfGeneratorFunction> {
+    func: F,
+    /// Size of a single cell at the finest level
+    scale: Number,
+    lipschitz: Number,
+    values: HashMap<GridPos, Number>,
 }
 
-// TODO: See if we can use Numbers (f64) with [SdfWrapper],
-//  instead of converting to/from f32
-impl<F: SdfGeneratorFunction> ScalarSource for SdfWrapper<F> {
-    fn sample_scalar(&self, Vec3 { x, y, z }: Vec3) -> Signed {
-        let point = [x, y, z].map(|n| n as Number).into();
-        Signed((self.func)(point) as f32)
+impl<F: SdfGeneratorFunction> Sampler<F> {
+    fn point(&self, pos: GridPos) -> Point3 { Point3::from(pos.map(|p| p as Number * self.scale)) }
+
+    fn value(&mut self, pos: GridPos) -> Number {
+        let point = self.point(pos);
+        *self.values.entry(pos).or_insert_with(|| (self.func)(point))
     }
-}
 
-impl<F: SdfGeneratorFunction> HermiteSource for SdfWrapper<F> {
-    fn sample_normal(&self, Vec3 { x, y, z }: Vec3) -> Vec3 {
-        let p = [x, y, z].map(|n| n as Number).into();
-        let v = (self.func)(p);
-        let dx = (self.func)(p + Vector3::new(self.epsilon, 0.0, 0.0)) - v;
-        let dy = (self.func)(p + Vector3::new(0.0, self.epsilon, 0.0)) - v;
-        let dz = (self.func)(p + Vector3::new(0.0, 0.0, self.epsilon)) - v;
-
-        // NOTE: In the case that the scalar field is completely homogenous in the region,
-        //  all the values will be the same and we will have no gradient, so this will be zero.
-        //  Since this is an internal API, we can skip that check here and do it in the mesh generation part
-        let grad = Vector3::new(dx, dy, dz);
-        Vec3::new(grad.x as f32, grad.y as f32, grad.z as f32)
+    /// The (normalised) gradient of the SDF, which is the surface normal. Is [None] if the field is flat here
+    fn gradient(&self, p: Point3) -> Option<Vector3> {
+        let e = self.scale * 1e-3;
+        let d = |offset: Vector3| (self.func)(p + offset) - (self.func)(p - offset);
+        Vector3::new(
+            d(Vector3::new(e, 0., 0.)),
+            d(Vector3::new(0., e, 0.)),
+            d(Vector3::new(0., 0., e)),
+        )
+        .try_normalize()
+    }
+
+    /// Recursively subdivides the octree cell at `min` (with the given size in finest cells), and adds all the
+    /// finest-level cells that might contain the surface to `cells`
+    fn collect_cells(&mut self, min: GridPos, size: usize, cells: &mut Vec<GridPos>) {
+        // If the surface is further away than the corners of the cell, it can't be inside the cell
+        let half_size = size as Number * self.scale * 0.5;
+        let centre = self.point(min) + Vector3::splat(half_size);
+        let half_diagonal = half_size * Number::sqrt(3.);
+        if (self.func)(centre).abs() > half_diagonal * self.lipschitz {
+            return;
+        }
+
+        if size == 1 {
+            cells.push(min);
+            return;
+        }
+        let half = size / 2;
+        for child in 0..8 {
+            let child_min = [0, 1, 2].map(|axis| min[axis] + (((child >> axis) & 1) * half));
+            self.collect_cells(child_min, half, cells);
+        }
+    }
+
+    /// Calculates where the vertex for the cell should go, if the surface passes through it.
+    ///
+    /// This finds where the surface crosses each edge of the cell, and then the point closest to all the tangent
+    /// planes at those crossings (the **Quadratic Error Function**), which lies on any sharp feature in the cell
+    fn cell_vertex(&mut self, cell: GridPos) -> Option<Point3> {
+        let corners = std::array::from_fn::<_, 8, _>(|i| [0, 1, 2].map(|axis| cell[axis] + ((i >> axis) & 1)));
+        let values = corners.map(|c| self.value(c));
+        if values.iter().all(|&v| v < 0.) || values.iter().all(|&v| v >= 0.) {
+            return None;
+        }
+
+        // Hermite data: where the surface crosses each edge, and the normal there
+        let mut crossings = vec![];
+        for bit in [1, 2, 4] {
+            for a in (0..8).filter(|a| a & bit == 0) {
+                let (va, vb) = (values[a], values[a | bit]);
+                if (va < 0.) == (vb < 0.) {
+                    continue;
+                }
+                let t = va / (va - vb);
+                let (start, end) = (self.point(corners[a]), self.point(corners[a | bit]));
+                let pos = start + ((end - start) * t);
+                crossings.push((pos, self.gradient(pos)));
+            }
+        }
+
+        let mass_point =
+            crossings.iter().fold(Vector3::ZERO, |sum, (p, _)| sum + p.to_vector()) / crossings.len() as Number;
+        let mass_point = mass_point.to_point();
+
+        // Solve `(AᵀA + λI) x = Aᵀb` for the offset from the mass point, where each row of `A` is a normal, and `b`
+        // is the distance of that plane from the mass point
+        let mut ata = [Vector3::X * QEF_BIAS, Vector3::Y * QEF_BIAS, Vector3::Z * QEF_BIAS];
+        let mut atb = Vector3::ZERO;
+        for &(pos, normal) in &crossings {
+            let Some(n) = normal else { continue };
+            let dist = Vector3::dot(n, pos - mass_point);
+            for (col, n_i) in ata.iter_mut().zip(n.as_array()) {
+                *col += n * *n_i;
+            }
+            atb += n * dist;
+        }
+        let offset = solve_3x3(ata, atb).unwrap_or(Vector3::ZERO);
+
+        // Keep the vertex inside its cell, otherwise the mesh can fold over itself
+        let (lo, hi) = (self.point(cell), self.point(cell.map(|c| c + 1)));
+        let vertex = (mass_point + offset).to_array();
+        Some(Point3::from(std::array::from_fn(|i| {
+            vertex[i].clamp(lo.as_array()[i], hi.as_array()[i])
+        })))
+    }
+
+    /// If the edge going along `axis` from the lowest corner of `cell` crosses the surface, returns the vertices of
+    /// the four cells around it, wound counter-clockwise when seen from outside the surface
+    fn edge_quad(&mut self, cell: GridPos, axis: usize, cell_vertices: &HashMap<GridPos, usize>) -> Option<[usize; 4]> {
+        let mut end = cell;
+        end[axis] += 1;
+        let (inside_start, inside_end) = (self.value(cell) < 0., self.value(end) < 0.);
+        if inside_start == inside_end {
+            return None;
+        }
+
+        // The edge is on the boundary of the grid, so there aren't cells all around it
+        let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+        if cell[b] == 0 || cell[c] == 0 {
+            return None;
+        }
+        let offset = |db: usize, dc: usize| {
+            let mut pos = cell;
+            pos[b] -= db;
+            pos[c] -= dc;
+            cell_vertices.get(&pos).copied()
+        };
+        let quad = [offset(1, 1)?, offset(0, 1)?, offset(0, 0)?, offset(1, 0)?];
+
+        // The quad goes anticlockwise around `+axis`, so flip it if the outside is the other way
+        Some(if inside_start {
+            quad
+        } else {
+            [quad[3], quad[2], quad[1], quad[0]]
+        })
     }
 }
 
-// endregion Isosurface Helper
+// endregion Dual Contouring
 
 // region Mesh Impl
 
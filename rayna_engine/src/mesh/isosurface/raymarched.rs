@@ -1,4 +1,5 @@
 use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::mesh::isosurface::sdf::SdfNode;
 use crate::mesh::isosurface::SdfGeneratorFunction;
 use crate::mesh::{Mesh, MeshProperties};
 use crate::shared::aabb::{Aabb, HasAabb};
@@ -10,15 +11,33 @@ use getset::{CopyGetters, Getters};
 use rand_core::RngCore;
 
 /// A mesh struct that is created by ray-marching for a given SDF.
+///
+/// # Step Size
+/// Each step moves the ray forwards by the distance to the surface, which is only safe if the function is a true
+/// distance. Functions that change faster than that (e.g. ones that have been scaled or warped) will overshoot,
+/// missing thin features, so give a [Lipschitz bound](Self::with_lipschitz()) to scale the steps down.
 #[derive(CopyGetters, Getters, Derivative, Clone)]
 #[derivative(Debug)]
 pub struct RaymarchedIsosurfaceMesh {
     #[derivative(Debug = "ignore")]
     #[get = "pub"]
     sdf: Box<dyn SdfGeneratorFunction>,
+    /// The node tree that the SDF was built from, if it was created using [RaymarchedIsosurfaceMesh::from_node()]
+    #[get = "pub"]
+    node: Option<SdfNode>,
 
+    /// The maximum number of ray-marching steps allowed for intersections
+    #[get_copy = "pub"]
     max_iterations: usize,
+    /// The distance threshold at which a ray is considered to have intersected with the surface
+    #[get_copy = "pub"]
     epsilon: Number,
+    /// The most the SDF can change per unit of distance (`1.0` for a true SDF). Steps are divided by this
+    #[get_copy = "pub"]
+    lipschitz: Number,
+    /// The offset used to sample the gradient of the SDF when calculating normals
+    #[get_copy = "pub"]
+    normal_epsilon: Number,
 }
 
 // region Constructors
@@ -26,6 +45,9 @@ pub struct RaymarchedIsosurfaceMesh {
 impl RaymarchedIsosurfaceMesh {
     pub const DEFAULT_EPSILON: Number = 1e-7;
     pub const DEFAULT_ITERATIONS: usize = 150;
+    pub const DEFAULT_LIPSCHITZ: Number = 1.;
+    /// Larger than [Self::DEFAULT_EPSILON], so that normals aren't affected by floating-point noise in the SDF
+    pub const DEFAULT_NORMAL_EPSILON: Number = 1e-5;
 
     /// Creates a new mesh from the given isosurface, as defined by the **Signed-Distance Function** (**SDF**)
     ///
@@ -36,8 +58,11 @@ impl RaymarchedIsosurfaceMesh {
     pub fn new<F: SdfGeneratorFunction + 'static>(sdf: F) -> Self {
         Self {
             sdf: Box::new(sdf),
+            node: None,
             epsilon: Self::DEFAULT_EPSILON,
             max_iterations: Self::DEFAULT_ITERATIONS,
+            lipschitz: Self::DEFAULT_LIPSCHITZ,
+            normal_epsilon: Self::DEFAULT_NORMAL_EPSILON,
         }
     }
 
@@ -49,12 +74,47 @@ impl RaymarchedIsosurfaceMesh {
     /// * `max_iterations`: The maximum number of ray-marching steps allowed for intersections
     /// * `epsilon`: The distance threshold at which a ray is considered to have intersected with the surface
     pub fn new_custom<F: SdfGeneratorFunction + 'static>(sdf: F, max_iterations: usize, epsilon: Number) -> Self {
+        Self::new(sdf).with_max_iterations(max_iterations).with_epsilon(epsilon)
+    }
+
+    /// Creates a new mesh from an SDF node tree, see [crate::mesh::isosurface::sdf]
+    ///
+    /// Unlike with a closure, the SDF can be inspected and serialised afterwards, using [Self::node()]
+    pub fn from_node(node: SdfNode) -> Self {
+        let eval = node.clone();
         Self {
-            sdf: Box::new(sdf),
-            epsilon,
-            max_iterations,
+            node: Some(node),
+            ..Self::new(move |p| eval.eval(p))
         }
     }
+
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self { Self { max_iterations, ..self } }
+
+    /// # Panics
+    /// Panics if the epsilon isn't positive
+    pub fn with_epsilon(self, epsilon: Number) -> Self {
+        assert!(epsilon > 0., "epsilon must be positive (was {epsilon})");
+        Self { epsilon, ..self }
+    }
+
+    /// Sets the Lipschitz bound of the SDF, for functions that aren't a true distance. See [Self] for details
+    ///
+    /// # Panics
+    /// Panics if the bound isn't positive
+    pub fn with_lipschitz(self, lipschitz: Number) -> Self {
+        assert!(lipschitz > 0., "lipschitz bound must be positive (was {lipschitz})");
+        Self { lipschitz, ..self }
+    }
+
+    /// # Panics
+    /// Panics if the epsilon isn't positive
+    pub fn with_normal_epsilon(self, normal_epsilon: Number) -> Self {
+        assert!(
+            normal_epsilon > 0.,
+            "normal epsilon must be positive (was {normal_epsilon})"
+        );
+        Self { normal_epsilon, ..self }
+    }
 }
 
 // endregion Constructors
@@ -69,6 +129,21 @@ impl MeshProperties for RaymarchedIsosurfaceMesh {
     fn centre(&self) -> Point3 { Point3::ZERO }
 }
 
+impl RaymarchedIsosurfaceMesh {
+    /// Calculates the surface normal at the point, using the central-difference gradient of the SDF.
+    /// Is [None] if the field is flat there
+    fn gradient(&self, p: Point3) -> Option<Vector3> {
+        let e = self.normal_epsilon;
+        let d = |offset: Vector3| (self.sdf)(p + offset) - (self.sdf)(p - offset);
+        Vector3::new(
+            d(Vector3::new(e, 0., 0.)),
+            d(Vector3::new(0., e, 0.)),
+            d(Vector3::new(0., 0., e)),
+        )
+        .try_normalize()
+    }
+}
+
 impl Mesh for RaymarchedIsosurfaceMesh {
     fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
         let epsilon = self.epsilon;
@@ -80,40 +155,34 @@ impl Mesh for RaymarchedIsosurfaceMesh {
         loop {
             // Ray march towards surface
             let dist = (self.sdf)(point);
-            // Always step forwards
-            total_dist += dist.abs();
-            // point += dir * step; // Causes compounding floating-point errors
-            point = ray.at(total_dist);
 
             // Arbitrarily close to surface, counts as an intersection
             // Also needs to be in valid bounds
             if dist.abs() < epsilon && interval.contains(&total_dist) {
-                // let point_pos = point + Vector3::splat(EPSILON);
-                // let point_neg = point - Vector3::splat(EPSILON);
-                let p = point;
-                let normal = Vector3::normalize(
-                    [
-                        (self.sdf)((p.x + epsilon, p.y, p.z).into()) - (self.sdf)((p.x - epsilon, p.y, p.z).into()),
-                        (self.sdf)((p.x, p.y + epsilon, p.z).into()) - (self.sdf)((p.x, p.y - epsilon, p.z).into()),
-                        (self.sdf)((p.x, p.y, p.z + epsilon).into()) - (self.sdf)((p.x, p.y, p.z - epsilon).into()),
-                    ]
-                    .into(),
-                );
+                let front_face = dist.is_sign_positive();
+                let normal = self.gradient(point).unwrap_or(-ray.dir());
 
                 return Some(Intersection {
-                    pos_w: p,
-                    pos_l: p,
+                    pos_w: point,
+                    pos_l: point,
                     uv: Point2::ZERO,
                     dist: total_dist,
-                    front_face: dist.is_sign_positive(),
+                    front_face,
                     side: i,
                     normal,
-                    ray_normal: normal,
+                    ray_normal: if front_face { normal } else { -normal },
+                    colour: None,
+                    tangent: None,
                 });
             }
 
-            // Exceeded the limit
-            if i > self.max_iterations {
+            // Always step forwards, but no further than the surface could be
+            total_dist += dist.abs() / self.lipschitz;
+            // point += dir * step; // Causes compounding floating-point errors
+            point = ray.at(total_dist);
+
+            // Exceeded the limit, or gone past the end of the interval so can't hit anything valid
+            if i > self.max_iterations || interval.end.is_some_and(|end| total_dist > end) {
                 return None;
             }
 
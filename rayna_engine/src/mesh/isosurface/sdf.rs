@@ -0,0 +1,185 @@
+//! # Module [crate::mesh::isosurface::sdf]
+//!
+//! A small language for building **Signed-Distance Functions** (**SDFs**) out of nodes, instead of writing a Rust
+//! closure. Since an [SdfNode] is plain data, it can be created at runtime (e.g. from a UI or a scene file) and
+//! serialised.
+//!
+//! Nodes are either primitives ([SdfNode::Sphere], [SdfNode::Cuboid], [SdfNode::Torus]), boolean combinations of
+//! other nodes (which can be smoothed), or modifiers that warp the space an inner node is evaluated in.
+//!
+//! # Example
+//!
+//! ```
+//! # use rayna_engine::core::types::*;
+//! # use rayna_engine::mesh::isosurface::sdf::SdfNode;
+//! # use rayna_engine::mesh::isosurface::raymarched::RaymarchedIsosurfaceMesh;
+//! // A cube with the corners rounded off, and a ring cut into the top
+//! let node = SdfNode::cuboid(Point3::ZERO, Vector3::splat(1.6))
+//!     .intersect(SdfNode::sphere(Point3::ZERO, 1.1), 0.1)
+//!     .subtract(SdfNode::torus((0., 0.8, 0.), 0.5, 0.1), 0.05);
+//! let mesh = RaymarchedIsosurfaceMesh::from_node(node);
+//! ```
+//!
+//! # Modifiers
+//! The modifiers ([SdfNode::Twist] and [SdfNode::Bend]) don't preserve distances, so the resulting SDF may
+//! overestimate the distance to the surface. Keep the strength low, or ray-marching may step through the surface.
+
+use crate::core::types::{Number, Point3, Vector2, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// A node in an SDF tree. See [crate::mesh::isosurface::sdf]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SdfNode {
+    /// A sphere
+    Sphere { centre: Point3, radius: Number },
+    /// An axis-aligned box
+    Cuboid { centre: Point3, size: Vector3 },
+    /// A torus (doughnut), lying flat on the `XZ` plane
+    Torus {
+        centre: Point3,
+        /// The radius from the centre to the middle of the tube
+        major_radius: Number,
+        /// The radius of the tube itself
+        minor_radius: Number,
+    },
+    /// Everything inside either node
+    ///
+    /// The `smoothness` is the distance over which the two surfaces blend together, where `0.0` is a sharp edge
+    Union {
+        a: Box<SdfNode>,
+        b: Box<SdfNode>,
+        smoothness: Number,
+    },
+    /// Everything inside node `a`, with node `b` cut out of it
+    Subtract {
+        a: Box<SdfNode>,
+        b: Box<SdfNode>,
+        smoothness: Number,
+    },
+    /// Only what is inside both nodes
+    Intersect {
+        a: Box<SdfNode>,
+        b: Box<SdfNode>,
+        smoothness: Number,
+    },
+    /// Twists the inner node around the `Y` axis, by `strength` radians per unit of height
+    Twist { inner: Box<SdfNode>, strength: Number },
+    /// Bends the inner node around the `Z` axis, by `strength` radians per unit along the `X` axis
+    Bend { inner: Box<SdfNode>, strength: Number },
+}
+
+// region Constructors
+
+impl SdfNode {
+    pub fn sphere(centre: impl Into<Point3>, radius: Number) -> Self {
+        Self::Sphere {
+            centre: centre.into(),
+            radius,
+        }
+    }
+
+    pub fn cuboid(centre: impl Into<Point3>, size: impl Into<Vector3>) -> Self {
+        Self::Cuboid {
+            centre: centre.into(),
+            size: size.into(),
+        }
+    }
+
+    pub fn torus(centre: impl Into<Point3>, major_radius: Number, minor_radius: Number) -> Self {
+        Self::Torus {
+            centre: centre.into(),
+            major_radius,
+            minor_radius,
+        }
+    }
+
+    pub fn union(self, other: Self, smoothness: Number) -> Self {
+        Self::Union {
+            a: Box::new(self),
+            b: Box::new(other),
+            smoothness,
+        }
+    }
+
+    pub fn subtract(self, other: Self, smoothness: Number) -> Self {
+        Self::Subtract {
+            a: Box::new(self),
+            b: Box::new(other),
+            smoothness,
+        }
+    }
+
+    pub fn intersect(self, other: Self, smoothness: Number) -> Self {
+        Self::Intersect {
+            a: Box::new(self),
+            b: Box::new(other),
+            smoothness,
+        }
+    }
+
+    pub fn twist(self, strength: Number) -> Self {
+        Self::Twist {
+            inner: Box::new(self),
+            strength,
+        }
+    }
+
+    pub fn bend(self, strength: Number) -> Self {
+        Self::Bend {
+            inner: Box::new(self),
+            strength,
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Evaluation
+
+impl SdfNode {
+    /// Evaluates the SDF at the given point, returning the (approximate) signed distance to the surface
+    pub fn eval(&self, p: Point3) -> Number {
+        /* CREDITS: Distance functions/Inigo Quilez/https://iquilezles.org/articles/distfunctions/ */
+        match self {
+            Self::Sphere { centre, radius } => (p - *centre).length() - radius,
+            Self::Cuboid { centre, size } => {
+                let q = (p - *centre).abs() - (*size / 2.);
+                q.max(Vector3::ZERO).length() + q.max_element().min(0.)
+            }
+            Self::Torus {
+                centre,
+                major_radius,
+                minor_radius,
+            } => {
+                let p = p - *centre;
+                let q = Vector2::new(Vector2::new(p.x, p.z).length() - major_radius, p.y);
+                q.length() - minor_radius
+            }
+            Self::Union { a, b, smoothness } => smooth_min(a.eval(p), b.eval(p), *smoothness),
+            Self::Subtract { a, b, smoothness } => -smooth_min(-a.eval(p), b.eval(p), *smoothness),
+            Self::Intersect { a, b, smoothness } => -smooth_min(-a.eval(p), -b.eval(p), *smoothness),
+            Self::Twist { inner, strength } => {
+                let (s, c) = (strength * p.y).sin_cos();
+                inner.eval(Point3::new((c * p.x) - (s * p.z), p.y, (s * p.x) + (c * p.z)))
+            }
+            Self::Bend { inner, strength } => {
+                let (s, c) = (strength * p.x).sin_cos();
+                inner.eval(Point3::new((c * p.x) - (s * p.y), (s * p.x) + (c * p.y), p.z))
+            }
+        }
+    }
+}
+
+/// Polynomial smooth minimum of two distances, blending over the distance `k`.
+///
+/// Falls back to [Number::min()] when `k` is zero
+fn smooth_min(a: Number, b: Number, k: Number) -> Number {
+    /* CREDITS: Smooth minimum/Inigo Quilez/https://iquilezles.org/articles/smin/ */
+    if k <= 0. {
+        return a.min(b);
+    }
+    let h = (0.5 + (0.5 * (b - a) / k)).clamp(0., 1.);
+    (a * h) + (b * (1. - h)) - (k * h * (1. - h))
+}
+
+// endregion Evaluation
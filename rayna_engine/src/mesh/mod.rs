@@ -25,29 +25,44 @@
 //! - See [`self::primitive::sphere`] for an example
 
 use crate::core::types::{Number, Point3};
+use crate::object::light::LightShape;
 use crate::shared::aabb::HasAabb;
 use crate::shared::intersect::Intersection;
+use crate::shared::intersect_arena::IntersectionList;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
 use crate::shared::RtRequirement;
 use enum_dispatch::enum_dispatch;
 use rand_core::RngCore;
+use std::simd::{LaneCount, SupportedLaneCount};
 // noinspection ALL - Used by enum_dispatch macro
 #[allow(unused_imports)]
 use self::{
-    advanced::{bvh::BvhMesh, dynamic::DynamicMesh, list::MeshList, triangle::BatchTriangle},
+    advanced::{
+        bvh::BvhMesh, csg::CsgMesh, curve::CurveMesh, dynamic::DynamicMesh, indexed_triangle::IndexedTriangleMesh,
+        list::MeshList, point_cloud::PointCloudMesh, polygon::PolygonMesh, subdivision::SubdivisionMesh,
+        triangle::BatchTriangle, voxel_grid::VoxelGridMesh,
+    },
     isosurface::{polygonised::PolygonisedIsosurfaceMesh, raymarched::RaymarchedIsosurfaceMesh},
-    planar::{infinite_plane::InfinitePlaneMesh, parallelogram::ParallelogramMesh},
-    primitive::{axis_box::AxisBoxMesh, cylinder::CylinderMesh, sphere::SphereMesh},
+    planar::{disk::DiskMesh, infinite_plane::InfinitePlaneMesh, parallelogram::ParallelogramMesh},
+    primitive::{
+        axis_box::AxisBoxMesh, capsule::CapsuleMesh, cone::ConeMesh, cylinder::CylinderMesh, ellipsoid::EllipsoidMesh,
+        rounded_box::RoundedBoxMesh, sphere::SphereMesh,
+    },
 };
 
 pub mod advanced;
 pub mod isosurface;
+pub mod ops;
 pub mod planar;
 pub mod primitive;
 
 // region Object traits
 
+/// The maximum number of intersections returned by [Mesh::intersect_all()]
+pub const MAX_INTERSECTIONS: usize = 32;
+
 #[enum_dispatch]
 #[doc(notable_trait)]
 pub trait Mesh: MeshProperties + RtRequirement {
@@ -57,6 +72,68 @@ pub trait Mesh: MeshProperties + RtRequirement {
     /// This should return the *first* intersection that is within the given range, else [None]
     fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection>;
 
+    /// Finds all the intersections between the given ray and the mesh, within the given range.
+    ///
+    /// This is used where the inside of a mesh matters, such as for [CsgMesh], so the mesh should be closed.
+    ///
+    /// # Return Value
+    /// The intersections, sorted by distance (nearest first), in a buffer from the
+    /// [per-thread arena](crate::shared::intersect_arena).
+    /// At most [MAX_INTERSECTIONS] are returned, to guard against meshes that never stop being intersected
+    ///
+    /// # Default Implementation
+    /// Repeatedly calls [Mesh::intersect()], starting just past the previous intersection each time.
+    /// Meshes that can find all their intersections at once should override this.
+    fn intersect_all(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> IntersectionList {
+        let mut hits = IntersectionList::new();
+        let mut interval = *interval;
+        while hits.len() < MAX_INTERSECTIONS {
+            let Some(hit) = self.intersect(ray, &interval, rng) else {
+                break;
+            };
+            // Nudge the start forwards, so we don't find the same intersection again
+            interval.start = Some(hit.dist + (hit.dist.abs().max(1.) * 1e-9));
+            hits.push(hit);
+        }
+        hits
+    }
+
+    /// Intersects a whole [RayPacket] with the mesh at once, each ray with its own interval.
+    ///
+    /// # Return Value
+    /// The *first* intersection for each ray (as with [Mesh::intersect()]), or [None] if it missed.
+    /// Rays that aren't in the `active` mask are skipped, and always return [None]
+    ///
+    /// # Default Implementation
+    /// Calls [Mesh::intersect()] for each ray in turn. Meshes that can intersect several rays at once with SIMD
+    /// should override this.
+    fn intersect_packet<const L: usize>(
+        &self,
+        packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<Intersection>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+        Self: Sized,
+    {
+        std::array::from_fn(|i| match active.test(i) {
+            true => self.intersect(&packet.rays()[i], &intervals[i], rng),
+            false => None,
+        })
+    }
+
+    /// The mesh as a [LightShape], if it is one of the simple shapes that lights can have.
+    ///
+    /// Objects that hold a mesh (rather than other objects) use this to
+    /// [collect their lights](crate::object::Object::collect_lights()), when their material
+    /// [is a light](crate::material::Material::is_light()).
+    ///
+    /// # Default Implementation
+    /// Returns [None], meaning that the mesh can't be sampled as a light
+    fn light_shape(&self) -> Option<LightShape> { None }
+
     // TODO: A fast method that simply checks if an intersection occurred at all, with no more info (shadow checks)
 }
 
@@ -67,9 +144,14 @@ pub trait Mesh: MeshProperties + RtRequirement {
 #[derive(Clone, Debug)]
 pub enum MeshInstance {
     SphereMesh,
+    EllipsoidMesh,
     CylinderMesh,
+    ConeMesh,
+    CapsuleMesh,
     AxisBoxMesh,
+    RoundedBoxMesh,
     ParallelogramMesh,
+    DiskMesh,
     InfinitePlaneMesh,
     RaymarchedIsosurfaceMesh,
     PolygonisedIsosurfaceMesh,
@@ -79,8 +161,15 @@ pub enum MeshInstance {
     BatchTriangle8(BatchTriangle<8>),
     BatchTriangle16(BatchTriangle<16>),
     TriangleMesh(primitive::triangle::Triangle),
+    IndexedTriangleMesh,
+    PolygonMesh,
     BvhMesh(BvhMesh<MeshInstance>),
     MeshList(MeshList<MeshInstance>),
+    CsgMesh(CsgMesh<MeshInstance>),
+    VoxelGridMesh,
+    SubdivisionMesh,
+    CurveMesh,
+    PointCloudMesh,
     DynamicMesh,
 }
 
@@ -0,0 +1,456 @@
+//! # Module [crate::mesh::ops]
+//!
+//! Operations that process whole meshes, such as [simplifying](simplify()) them, or cleaning up imported triangle
+//! soup ([weld()], [fix_winding()] and [recompute_normals()]).
+//!
+//! These are intended to be run once when a scene is loaded (e.g. on imported models), not during rendering.
+
+use crate::core::targets::MESH;
+use crate::core::types::{Number, Point3, Vector3};
+use crate::mesh::advanced::indexed_triangle::{face_normal, IndexedTriangleMesh, VertexAttributes, VertexNormals};
+use crate::shared::math::solve_3x3;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::ops::Add;
+use tracing::debug;
+
+// region Simplification
+
+/// How strongly the edges of open meshes are kept in place, compared to the surface itself
+const BOUNDARY_WEIGHT: Number = 1000.;
+
+/// Collapses are rejected if they would turn a face by more than this (cosine of the angle), which would fold the
+/// mesh over itself
+const MAX_FLIP_COS: Number = 0.2;
+
+/// Simplifies (**decimates**) the mesh, until it has at most `target_tris` triangles.
+///
+/// This repeatedly collapses the edge that changes the shape of the mesh the least (measured with **quadric error
+/// metrics**), so flat areas are simplified much more than detailed ones. The edges of open meshes are preserved.
+///
+/// UVs and colours are kept from the surviving vertices. If the mesh had smooth normals, they are recalculated
+/// ([VertexNormals::AngleWeighted]) for the new shape, otherwise the result is [flat](VertexNormals::Flat).
+///
+/// # Note
+/// The simplification can stop early (with more than `target_tris` triangles), if every remaining collapse would fold
+/// the mesh over itself
+pub fn simplify(mesh: &IndexedTriangleMesh, target_tris: usize) -> IndexedTriangleMesh {
+    /*
+    CREDITS:
+
+    Title: "Surface Simplification Using Quadric Error Metrics"
+    Authors:
+        - Michael Garland
+        - Paul S. Heckbert
+    URL: <https://www.cs.cmu.edu/~garland/Papers/quadrics.pdf>
+    Publisher: SIGGRAPH
+    Version: 1997
+    */
+
+    let mut positions = mesh.vertices().clone();
+    let mut faces = mesh
+        .indices()
+        .iter()
+        .map(|&tri| face_normal(tri.map(|i| positions[i])).map(|_| tri))
+        .collect::<Vec<_>>();
+    let mut live_faces = faces.iter().flatten().count();
+
+    let mut vertex_faces = vec![vec![]; positions.len()];
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edge_faces = HashMap::<(usize, usize), Vec<usize>>::new();
+    for (f, tri) in faces.iter().enumerate() {
+        let Some(tri) = tri else { continue };
+        let verts = tri.map(|i| positions[i]);
+        let plane = Quadric::plane(face_normal(verts).expect("degenerate faces were removed"), verts[0]);
+        for (corner, &v) in tri.iter().enumerate() {
+            vertex_faces[v].push(f);
+            quadrics[v] = quadrics[v] + plane;
+            let next = tri[(corner + 1) % 3];
+            edge_faces.entry((v.min(next), v.max(next))).or_default().push(f);
+        }
+    }
+
+    // Edges with only one face are on the boundary, so add a plane perpendicular to the face along the edge, which
+    // stops the boundary from moving
+    for (&(a, b), faces_on_edge) in &edge_faces {
+        let &[f] = faces_on_edge.as_slice() else { continue };
+        let normal = face_normal(faces[f].expect("face is live").map(|i| positions[i])).expect("face is live");
+        let Some(perpendicular) = Vector3::cross(positions[b] - positions[a], normal).try_normalize() else {
+            continue;
+        };
+        let plane = Quadric::plane(perpendicular, positions[a]).scale(BOUNDARY_WEIGHT);
+        quadrics[a] = quadrics[a] + plane;
+        quadrics[b] = quadrics[b] + plane;
+    }
+
+    let mut versions = vec![0usize; positions.len()];
+    let mut heap = edge_faces
+        .keys()
+        .map(|&(a, b)| Collapse::new(a, b, &positions, &quadrics, &versions))
+        .collect::<BinaryHeap<_>>();
+
+    while live_faces > target_tris {
+        let Some(collapse) = heap.pop() else { break };
+        let Collapse { keep, remove, pos, .. } = collapse;
+        // One of the vertices has changed since this was queued, so it's out of date
+        if [versions[keep], versions[remove]] != collapse.versions {
+            continue;
+        }
+        if would_flip(keep, remove, pos, &positions, &faces, &vertex_faces) {
+            continue;
+        }
+
+        positions[keep] = pos;
+        quadrics[keep] = quadrics[keep] + quadrics[remove];
+        for f in std::mem::take(&mut vertex_faces[remove]) {
+            let Some(tri) = &mut faces[f] else { continue };
+            tri.iter_mut().filter(|v| **v == remove).for_each(|v| *v = keep);
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+                faces[f] = None;
+                live_faces -= 1;
+            } else {
+                vertex_faces[keep].push(f);
+            }
+        }
+        vertex_faces[keep].retain(|&f| faces[f].is_some());
+        versions[keep] += 1;
+        versions[remove] += 1;
+
+        let neighbours = vertex_faces[keep]
+            .iter()
+            .filter_map(|&f| faces[f])
+            .flatten()
+            .filter(|&v| v != keep)
+            .collect::<HashSet<_>>();
+        heap.extend(
+            neighbours
+                .into_iter()
+                .map(|n| Collapse::new(keep, n, &positions, &quadrics, &versions)),
+        );
+    }
+
+    debug!(target: MESH, from = mesh.indices().len(), to = live_faces, target = target_tris, "simplified mesh");
+
+    // Only keep the vertices that are still used, in their original order
+    let mut remap = vec![None; positions.len()];
+    let mut kept = vec![];
+    for v in faces.iter().flatten().flatten().copied() {
+        remap[v].get_or_insert_with(|| {
+            kept.push(v);
+            kept.len() - 1
+        });
+    }
+    let indices = faces
+        .iter()
+        .flatten()
+        .map(|tri| tri.map(|v| remap[v].expect("vertex is used")))
+        .collect::<Vec<_>>();
+
+    rebuild(mesh, &positions, &kept, indices, same_normals(mesh))
+}
+
+/// Would moving the vertices `keep` and `remove` to `pos` fold over any of the faces around them?
+fn would_flip(
+    keep: usize,
+    remove: usize,
+    pos: Point3,
+    positions: &[Point3],
+    faces: &[Option<[usize; 3]>],
+    vertex_faces: &[Vec<usize>],
+) -> bool {
+    vertex_faces[keep]
+        .iter()
+        .chain(&vertex_faces[remove])
+        .filter_map(|&f| faces[f])
+        // Faces with both vertices are removed by the collapse
+        .filter(|tri| !(tri.contains(&keep) && tri.contains(&remove)))
+        .any(|tri| {
+            let old = tri.map(|v| positions[v]);
+            let new = tri.map(|v| if v == keep || v == remove { pos } else { positions[v] });
+            match (face_normal(old), face_normal(new)) {
+                (Some(old), Some(new)) => Vector3::dot(old, new) < MAX_FLIP_COS,
+                _ => true,
+            }
+        })
+}
+
+/// A possible edge collapse, ordered so that the cheapest is at the top of a [BinaryHeap]
+#[derive(Copy, Clone, Debug)]
+struct Collapse {
+    cost: Number,
+    keep: usize,
+    remove: usize,
+    /// Where the merged vertex will go
+    pos: Point3,
+    /// The versions of `[keep, remove]` when this was calculated, so that stale collapses can be skipped
+    versions: [usize; 2],
+}
+
+impl Collapse {
+    fn new(keep: usize, remove: usize, positions: &[Point3], quadrics: &[Quadric], versions: &[usize]) -> Self {
+        let quadric = quadrics[keep] + quadrics[remove];
+        let (a, b) = (positions[keep], positions[remove]);
+        let mid = a + ((b - a) * 0.5);
+
+        // The optimal position can be far away if the quadric is nearly singular (e.g. on flat areas), so only use
+        // it if it's close to the edge
+        let optimal = quadric.optimal().filter(|&p| (p - mid).length() <= (b - a).length());
+        let pos = optimal.unwrap_or_else(|| {
+            [a, b, mid]
+                .into_iter()
+                .min_by(|&p, &q| quadric.error(p).total_cmp(&quadric.error(q)))
+                .expect("candidates not empty")
+        });
+
+        Self {
+            cost: quadric.error(pos),
+            keep,
+            remove,
+            pos,
+            versions: [versions[keep], versions[remove]],
+        }
+    }
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for Collapse {
+    // Reversed, so the heap gives the lowest cost first
+    fn cmp(&self, other: &Self) -> Ordering { other.cost.total_cmp(&self.cost) }
+}
+
+/// A symmetric 4x4 matrix `Q`, where `[p, 1]ᵀ Q [p, 1]` is the sum of the squared distances from `p` to a set of
+/// planes.
+///
+/// Stores the upper triangle: `[aa, ab, ac, ad, bb, bc, bd, cc, cd, dd]`
+#[derive(Copy, Clone, Debug, Default)]
+struct Quadric([Number; 10]);
+
+impl Quadric {
+    /// The quadric for the plane with the given normal, going through `point`
+    fn plane(normal: Vector3, point: Point3) -> Self {
+        let [a, b, c] = normal.to_array();
+        let d = -Vector3::dot(normal, point.to_vector());
+        Self([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d])
+    }
+
+    fn scale(self, factor: Number) -> Self { Self(self.0.map(|q| q * factor)) }
+
+    /// The squared distance error of the point
+    fn error(&self, p: Point3) -> Number {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.0;
+        let [x, y, z] = p.to_array();
+        (aa * x * x)
+            + (2. * ab * x * y)
+            + (2. * ac * x * z)
+            + (2. * ad * x)
+            + (bb * y * y)
+            + (2. * bc * y * z)
+            + (2. * bd * y)
+            + (cc * z * z)
+            + (2. * cd * z)
+            + dd
+    }
+
+    /// The point with the lowest error, if there is a single one
+    fn optimal(&self) -> Option<Point3> {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, _] = self.0;
+        solve_3x3(
+            [
+                Vector3::new(aa, ab, ac),
+                Vector3::new(ab, bb, bc),
+                Vector3::new(ac, bc, cc),
+            ],
+            -Vector3::new(ad, bd, cd),
+        )
+        .map(Vector3::to_point)
+    }
+}
+
+impl Add for Quadric {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self { Self(std::array::from_fn(|i| self.0[i] + rhs.0[i])) }
+}
+
+// endregion Simplification
+
+// region Cleanup
+
+/// Merges vertices that are within `tolerance` of each other, so that triangles that were separate (e.g. from an STL
+/// file, where every triangle has its own vertices) become connected, and can be smoothly shaded.
+///
+/// Attributes are kept from the first of each set of merged vertices. Triangles that collapse because of the merging
+/// are removed. Smooth normals are recalculated ([VertexNormals::AngleWeighted]), and flat ones are kept flat; use
+/// [recompute_normals()] afterwards to make a flat mesh smooth.
+///
+/// # Panics
+/// Panics if the tolerance is negative
+pub fn weld(mesh: &IndexedTriangleMesh, tolerance: Number) -> IndexedTriangleMesh {
+    assert!(tolerance >= 0., "tolerance must not be negative (was {tolerance})");
+
+    // Bucket the vertices into a grid, so only the neighbouring cells need to be searched
+    let cell_size = tolerance.max(1e-12);
+    let cell = |p: Point3| p.to_array().map(|c| (c / cell_size).floor() as i64);
+    let mut grid = HashMap::<[i64; 3], Vec<usize>>::new();
+
+    let vertices = mesh.vertices();
+    let mut kept = vec![];
+    let mut remap = Vec::with_capacity(vertices.len());
+    for (v, &p) in vertices.iter().enumerate() {
+        let [x, y, z] = cell(p);
+        let existing = itertools::iproduct!(-1..=1, -1..=1, -1..=1)
+            .filter_map(|(dx, dy, dz)| grid.get(&[x + dx, y + dy, z + dz]))
+            .flatten()
+            .copied()
+            .find(|&k| (vertices[kept[k]] - p).length() <= tolerance);
+        remap.push(existing.unwrap_or_else(|| {
+            kept.push(v);
+            grid.entry([x, y, z]).or_default().push(kept.len() - 1);
+            kept.len() - 1
+        }));
+    }
+
+    let indices = mesh
+        .indices()
+        .iter()
+        .map(|tri| tri.map(|v| remap[v]))
+        .filter(|&[a, b, c]| a != b && b != c && c != a)
+        .collect::<Vec<_>>();
+
+    debug!(target: MESH, from = vertices.len(), to = kept.len(), tolerance, "welded mesh");
+    rebuild(mesh, vertices, &kept, indices, same_normals(mesh))
+}
+
+/// Makes the winding of the triangles consistent, so that neighbouring triangles face the same way. Parts of the
+/// mesh that are closed are then turned to face outwards.
+///
+/// Triangles are only connected if they share vertices, so meshes that are triangle soup should be [welded](weld())
+/// first.
+pub fn fix_winding(mesh: &IndexedTriangleMesh) -> IndexedTriangleMesh {
+    let mut indices = mesh.indices().clone();
+    let mut edge_faces = HashMap::<(usize, usize), Vec<usize>>::new();
+    for (f, tri) in indices.iter().enumerate() {
+        for (a, b) in tri_edges(*tri) {
+            edge_faces.entry((a.min(b), a.max(b))).or_default().push(f);
+        }
+    }
+
+    let mut visited = vec![false; indices.len()];
+    let mut flipped = 0;
+    for start in 0..indices.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+
+        // Flood-fill the connected triangles, flipping them to match the ones they were reached from
+        let mut component = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(f) = queue.pop_front() {
+            for (a, b) in tri_edges(indices[f]) {
+                for &g in &edge_faces[&(a.min(b), a.max(b))] {
+                    if visited[g] {
+                        continue;
+                    }
+                    // Neighbours that agree go along the shared edge in the opposite direction
+                    if tri_edges(indices[g]).contains(&(a, b)) {
+                        indices[g].swap(1, 2);
+                        flipped += 1;
+                    }
+                    visited[g] = true;
+                    component.push(g);
+                    queue.push_back(g);
+                }
+            }
+        }
+
+        // Only closed parts have an inside and outside
+        let closed = component.iter().all(|&f| {
+            tri_edges(indices[f])
+                .into_iter()
+                .all(|(a, b)| edge_faces[&(a.min(b), a.max(b))].len() == 2)
+        });
+        let volume = component
+            .iter()
+            .map(|&f| {
+                let [a, b, c] = indices[f].map(|v| mesh.vertices()[v].to_vector());
+                Vector3::dot(a, Vector3::cross(b, c))
+            })
+            .sum::<Number>();
+        if closed && volume < 0. {
+            component.iter().for_each(|&f| indices[f].swap(1, 2));
+            flipped += component.len();
+        }
+    }
+
+    debug!(target: MESH, flipped, "fixed mesh winding");
+    let all = (0..mesh.vertices().len()).collect::<Vec<_>>();
+    rebuild(mesh, mesh.vertices(), &all, indices, same_normals(mesh))
+}
+
+/// Recalculates smooth normals for the mesh ([VertexNormals::AngleWeighted]), replacing any normals it already had
+pub fn recompute_normals(mesh: &IndexedTriangleMesh) -> IndexedTriangleMesh {
+    let all = (0..mesh.vertices().len()).collect::<Vec<_>>();
+    rebuild(
+        mesh,
+        mesh.vertices(),
+        &all,
+        mesh.indices().clone(),
+        VertexNormals::AngleWeighted,
+    )
+}
+
+/// The directed edges of a triangle
+fn tri_edges([a, b, c]: [usize; 3]) -> [(usize, usize); 3] { [(a, b), (b, c), (c, a)] }
+
+// endregion Cleanup
+
+// region Helpers
+
+/// Normals of the same kind as the mesh already has, which will need recalculating if the mesh is changed
+fn same_normals(mesh: &IndexedTriangleMesh) -> VertexNormals {
+    if mesh.normals().is_empty() {
+        VertexNormals::Flat
+    } else {
+        VertexNormals::AngleWeighted
+    }
+}
+
+/// Creates a new mesh from a subset of the vertices of the original mesh, keeping their attributes.
+///
+/// `kept` are the indices of the vertices to keep (into `positions` and the original mesh's attributes), and
+/// `indices` index into `kept`
+fn rebuild(
+    mesh: &IndexedTriangleMesh,
+    positions: &[Point3],
+    kept: &[usize],
+    indices: Vec<[usize; 3]>,
+    normals: VertexNormals,
+) -> IndexedTriangleMesh {
+    let attributes = VertexAttributes {
+        uvs: keep_attribute(mesh.uvs(), kept),
+        colours: keep_attribute(mesh.colours(), kept),
+    };
+    IndexedTriangleMesh::new_with_attributes(
+        kept.iter().map(|&v| positions[v]).collect::<Vec<_>>(),
+        indices,
+        normals,
+        attributes,
+    )
+}
+
+/// Picks out the values of a per-vertex attribute for the `kept` vertices, or [None] if the mesh doesn't have it
+fn keep_attribute<T: Clone>(attribute: &[T], kept: &[usize]) -> Option<Vec<T>> {
+    (!attribute.is_empty()).then(|| kept.iter().map(|&v| attribute[v].clone()).collect())
+}
+
+// endregion Helpers
@@ -0,0 +1,101 @@
+use getset::CopyGetters;
+use rand_core::RngCore;
+
+use crate::core::types::{Number, Point2, Point3, Vector3};
+
+use crate::mesh::planar::Planar;
+use crate::mesh::{Mesh, MeshProperties};
+use crate::object::light::LightShape;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+
+/// A flat, circular disk. Can optionally have a hole in the middle, making it an annulus (ring)
+///
+/// The UV coordinates are mapped so the disk fills the `0.0..=1.0` square, with the centre at `(0.5, 0.5)`
+#[derive(Copy, Clone, Debug, CopyGetters)]
+#[get_copy = "pub"]
+pub struct DiskMesh {
+    /// The plane that this mesh sits upon. The origin is the centre of the disk,
+    /// and `u, v` are normalised so the UVs are in world units
+    plane: Planar,
+    radius: Number,
+    /// The radius of the hole in the centre, zero for a solid disk
+    inner_radius: Number,
+    aabb: Aabb,
+}
+
+// region Constructors
+
+impl DiskMesh {
+    /// Creates a new solid disk, facing in the direction of the `normal`
+    pub fn new(centre: impl Into<Point3>, normal: impl Into<Vector3>, radius: Number) -> Self {
+        Self::new_annulus(centre, normal, radius, 0.)
+    }
+
+    /// Creates a new annulus (a disk with a hole in the middle), facing in the direction of the `normal`
+    ///
+    /// # Panics
+    /// Panics if `inner_radius` is not in the range `0.0..radius`, or the normal is zero
+    pub fn new_annulus(
+        centre: impl Into<Point3>,
+        normal: impl Into<Vector3>,
+        radius: Number,
+        inner_radius: Number,
+    ) -> Self {
+        assert!(
+            (0.0..radius).contains(&inner_radius),
+            "inner radius must be in the range `0.0..radius` (radius: {radius}, inner radius: {inner_radius})"
+        );
+        let (centre, normal) = (centre.into(), normal.into());
+        let normal = normal.try_normalize().expect("disk normal must not be zero");
+
+        // Make sure `cross(u, v) == normal`, so the plane faces the right way
+        let (u, _) = Vector3::any_orthonormal_pair(&normal);
+        let v = Vector3::cross(normal, u);
+        let plane = Planar::new(centre, u, v);
+
+        // Bounds of a disk are `radius * sqrt(1 - normal^2)` along each axis
+        let extent = |a: Number| (1. - (a * a)).max(0.).sqrt();
+        let half_size = Vector3::new(extent(normal.x), extent(normal.y), extent(normal.z)) * radius;
+        let aabb = Aabb::new(centre - half_size, centre + half_size).min_padded(super::AABB_PADDING);
+
+        Self {
+            plane,
+            radius,
+            inner_radius,
+            aabb,
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl Mesh for DiskMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
+        let mut i = self.plane.intersect_bounded(ray, interval)?;
+        // Check in interval for our segment of the plane: `inner^2 <= u^2 + v^2 <= radius^2`
+        let dist_sqr = i.uv.to_vector().length_squared();
+        if dist_sqr > self.radius * self.radius || dist_sqr < self.inner_radius * self.inner_radius {
+            return None;
+        }
+
+        // Remap from `-radius..=radius` to `0..=1`
+        i.uv = Point2::new(i.uv.x / self.radius / 2. + 0.5, i.uv.y / self.radius / 2. + 0.5);
+        Some(i)
+    }
+
+    fn light_shape(&self) -> Option<LightShape> { Some(LightShape::Disk(*self)) }
+}
+
+impl HasAabb for DiskMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+impl MeshProperties for DiskMesh {
+    fn centre(&self) -> Point3 { self.plane.p() }
+}
+
+// endregion Mesh Impl
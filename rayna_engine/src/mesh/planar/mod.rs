@@ -14,6 +14,7 @@ use crate::shared::ray::Ray;
 use getset::CopyGetters;
 use num_traits::Zero;
 
+pub mod disk;
 pub mod infinite_plane;
 pub mod parallelogram;
 
@@ -190,6 +191,8 @@ impl Planar {
             ray_normal: -self.n * denominator.signum(),
             uv: Point2::new(alpha, beta),
             side: 0,
+            colour: None,
+            tangent: Some(self.u),
         })
     }
 }
@@ -5,6 +5,7 @@ use crate::core::types::{Number, Point2, Point3};
 
 use crate::mesh::planar::Planar;
 use crate::mesh::{Mesh, MeshProperties};
+use crate::object::light::LightShape;
 use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::Intersection;
 use crate::shared::interval::Interval;
@@ -55,6 +56,8 @@ impl Mesh for ParallelogramMesh {
             None
         }
     }
+
+    fn light_shape(&self) -> Option<LightShape> { Some(LightShape::Parallelogram(*self)) }
 }
 
 impl HasAabb for ParallelogramMesh {
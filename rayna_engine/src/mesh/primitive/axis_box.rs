@@ -3,6 +3,8 @@ use glam::swizzles::*;
 use glamour::FromRaw;
 use glamour::ToRaw;
 use rand_core::RngCore;
+use std::simd::prelude::*;
+use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 use crate::core::types::{Number, Point3, Size3, Vector2, Vector3};
 
@@ -11,6 +13,8 @@ use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::Intersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
+use crate::shared::simd_math::{SimdConstants, SimdVector};
 use crate::shared::validate;
 
 /// Built instance of a box mesh
@@ -95,6 +99,58 @@ impl Mesh for AxisBoxMesh {
         let mut plane_dist = (self.radius * winding * sgn) - ro;
         plane_dist *= ray.inv_dir();
 
+        self.intersect_faces(ray, interval, ro, winding, sgn, plane_dist)
+    }
+
+    fn intersect_packet<const L: usize>(
+        &self,
+        packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        _rng: &mut dyn RngCore,
+    ) -> [Option<Intersection>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        // Find the planes for all the rays at once (the same as the scalar version), and then only test the faces
+        // separately, since that branches too much to be worth vectorising
+        let splat = |v: Vector3| SimdVector(v.as_array().map(Simd::<Number, L>::splat));
+        let ro = packet.pos() - splat(self.centre.to_vector());
+        let [rx, ry, rz] = ro.0.map(|r| r.abs());
+        let [ix, iy, iz] = self.inv_radius.as_array().map(Simd::<Number, L>::splat);
+        let winding = ((rx * ix).simd_max(ry * iy).simd_max(rz * iz) - SimdConstants::<L>::ONE).signum();
+        let sgn = SimdVector(packet.dir().0.map(|d| -d.signum()));
+        let plane_dist = ((splat(self.radius) * SimdVector([winding; 3]) * sgn) - ro) * packet.inv_dir();
+
+        let unpack = |v: SimdVector<L, 3>, i: usize| Vector3::new(v.0[0][i], v.0[1][i], v.0[2][i]);
+        std::array::from_fn(|i| match active.test(i) {
+            true => self.intersect_faces(
+                &packet.rays()[i],
+                &intervals[i],
+                unpack(ro, i),
+                winding[i],
+                unpack(sgn, i),
+                unpack(plane_dist, i),
+            ),
+            false => None,
+        })
+    }
+}
+
+impl AxisBoxMesh {
+    /// The second half of the intersection, which finds which face of the box (if any) the ray hits, from the
+    /// distances to the planes of the faces that the ray could hit
+    fn intersect_faces(
+        &self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        ro: Vector3,
+        winding: Number,
+        sgn: Vector3,
+        plane_dist: Vector3,
+    ) -> Option<Intersection> {
+        let rd = ray.dir();
+
         // Perform all three ray-box tests on each axis.
         // Use a macro to eliminate the redundant code (no efficiency boost from doing so, of course!)
         macro_rules! test {
@@ -127,6 +183,8 @@ impl Mesh for AxisBoxMesh {
                             uv: uvs.to_point(),
                             // x: 0,1; y: 2,3; z: 4,5; -ve sign first then positive sign
                             side: ((glam::uvec3(1, 5, 9).$u + sgn.$u as u32) / 2) as usize,
+                            colour: None,
+                            tangent: None,
                         });
                     }
                 }
@@ -0,0 +1,162 @@
+use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::CopyGetters;
+use glamour::AngleConsts;
+use rand_core::RngCore;
+use smallvec::SmallVec;
+
+/// A capsule: all the points within a certain radius of a line segment.
+///
+/// This is a cylinder with a hemisphere on each end, and is much cheaper than raymarching the equivalent SDF.
+///
+/// # Sides
+/// - `0`: The cylindrical body of the capsule
+/// - `1`: The hemisphere at the start of the segment
+/// - `2`: The hemisphere at the end of the segment
+#[derive(Copy, Clone, Debug, CopyGetters)]
+#[get_copy = "pub"]
+pub struct CapsuleMesh {
+    centre: Point3,
+    /// The start of the line segment (the centre of the first hemisphere)
+    start: Point3,
+    /// The end of the line segment (the centre of the second hemisphere)
+    end: Point3,
+    /// The normalised direction from the start to the end of the segment
+    axis: Vector3,
+    /// The length of the line segment (not including the hemispheres)
+    length: Number,
+    radius: Number,
+    /// Two arbitrary directions that are orthogonal to the axis (and each other),
+    /// used as a reference frame
+    orthogonals: (Vector3, Vector3),
+    aabb: Aabb,
+}
+
+// region Constructors
+
+impl CapsuleMesh {
+    /// Creates a new capsule around the line segment from `start` to `end`.
+    ///
+    /// If the two points are the same, the capsule is a sphere
+    pub fn new(start: impl Into<Point3>, end: impl Into<Point3>, radius: Number) -> Self {
+        let (start, end) = (start.into(), end.into());
+        let length = (end - start).length();
+        // Any axis works for a sphere
+        let axis = (end - start).try_normalize().unwrap_or(Vector3::Y);
+        let aabb = Aabb::new(
+            Point3::min(start, end) - Vector3::splat(radius),
+            Point3::max(start, end) + Vector3::splat(radius),
+        );
+
+        Self {
+            centre: ((start.to_vector() + end.to_vector()) / 2.).to_point(),
+            start,
+            end,
+            axis,
+            length,
+            radius,
+            orthogonals: Vector3::any_orthonormal_pair(&axis),
+            aabb,
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl Mesh for CapsuleMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
+        let d = ray.dir();
+        let o = ray.pos() - self.start;
+        let r2 = self.radius * self.radius;
+        // How far along the axis a point is, relative to the start
+        let along = |t: Number| Vector3::dot(o + (d * t), self.axis);
+
+        // (dist, side)
+        let mut hits = SmallVec::<[(Number, usize); 6]>::new();
+
+        // Body: an infinite cylinder around the axis, limited to the length of the segment
+        {
+            let d_perp = d - (self.axis * Vector3::dot(d, self.axis));
+            let o_perp = o - (self.axis * Vector3::dot(o, self.axis));
+            let a = d_perp.length_squared();
+            let half_b = Vector3::dot(o_perp, d_perp);
+            let c = o_perp.length_squared() - r2;
+            let discriminant = (half_b * half_b) - (a * c);
+
+            // If the ray is parallel to the axis, it can only hit the hemispheres
+            if a > 1e-12 && discriminant >= 0. {
+                let sqrt_d = discriminant.sqrt();
+                for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a] {
+                    if (0.0..=self.length).contains(&along(t)) {
+                        hits.push((t, 0));
+                    }
+                }
+            }
+        }
+
+        // Hemispheres: only the halves of each sphere that are past the ends of the segment
+        for (centre, side) in [(self.start, 1), (self.end, 2)] {
+            let oc = ray.pos() - centre;
+            let half_b = Vector3::dot(oc, d);
+            let c = oc.length_squared() - r2;
+            let discriminant = (half_b * half_b) - c;
+            if discriminant < 0. {
+                continue;
+            }
+
+            let sqrt_d = discriminant.sqrt();
+            for t in [-half_b - sqrt_d, -half_b + sqrt_d] {
+                let y = along(t);
+                if (side == 1 && y <= 0.) || (side == 2 && y >= self.length) {
+                    hits.push((t, side));
+                }
+            }
+        }
+
+        let (dist, side) = hits
+            .into_iter()
+            .filter(|(t, _)| interval.contains(t))
+            .min_by(|(a, _), (b, _)| Number::total_cmp(a, b))?;
+
+        let pos_w = ray.at(dist);
+        // The normal points away from the closest point on the segment
+        let y = along(dist);
+        let closest = self.start + (self.axis * y.clamp(0., self.length));
+        let normal = (pos_w - closest) / self.radius;
+
+        // Wrap `u` around the axis, and `v` along the whole length (including the hemispheres)
+        let (ox, oy) = self.orthogonals;
+        let u = (Number::atan2(Vector3::dot(normal, oy), Vector3::dot(normal, ox)) / Number::PI / 2.) + 0.5;
+        let v = (y + self.radius) / (self.length + (2. * self.radius));
+
+        let front_face = Vector3::dot(d, normal) <= 0.;
+        Some(Intersection {
+            pos_w,
+            pos_l: (pos_w - self.centre).to_point(),
+            normal,
+            ray_normal: if front_face { normal } else { -normal },
+            front_face,
+            dist,
+            uv: Point2::new(u, v.clamp(0., 1.)),
+            side,
+            colour: None,
+            tangent: None,
+        })
+    }
+}
+
+impl HasAabb for CapsuleMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for CapsuleMesh {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+// endregion Mesh Impl
@@ -0,0 +1,217 @@
+use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::CopyGetters;
+use glamour::AngleConsts;
+use rand_core::RngCore;
+use smallvec::SmallVec;
+
+/// A cone (or a frustum, if truncated), with a circular base.
+///
+/// The cone is closed, with a cap on the base (and on the top, for a frustum).
+///
+/// # Sides
+/// - `0`: The sloped body of the cone
+/// - `1`: The base cap
+/// - `2`: The top cap (only for a frustum)
+#[derive(Copy, Clone, Debug, CopyGetters)]
+#[get_copy = "pub"]
+pub struct ConeMesh {
+    centre: Point3,
+    /// The centre of the base of the cone
+    base: Point3,
+    /// The tip of the (untruncated) cone
+    apex: Point3,
+    /// The normalised direction from the base to the apex
+    axis: Vector3,
+    /// The distance from the base to the apex
+    height: Number,
+    /// The radius of the base of the cone
+    radius: Number,
+    /// How far along the axis the cone is truncated, from the base
+    ///
+    /// Equal to [Self::height] for an untruncated cone
+    top_height: Number,
+    /// The radius of the top cap, zero for an untruncated cone
+    top_radius: Number,
+    /// The radius of the cone shrinks by this much, for each unit moved along the axis
+    slope: Number,
+    /// Two arbitrary directions that are orthogonal to the axis (and each other),
+    /// used as a reference frame
+    orthogonals: (Vector3, Vector3),
+    aabb: Aabb,
+}
+
+// region Constructors
+
+impl ConeMesh {
+    /// Creates a new cone, with the given base centre and radius, that comes to a point at the apex
+    pub fn new(base: impl Into<Point3>, apex: impl Into<Point3>, radius: Number) -> Self {
+        Self::new_frustum(base, apex, radius, 0.)
+    }
+
+    /// Creates a new frustum: a cone with the top cut off.
+    ///
+    /// The cone is created like [Self::new()], and then `truncation` is the fraction of the height that is
+    /// cut off at the apex end, in the range `0.0..1.0`. A truncation of `0.0` is a normal cone.
+    ///
+    /// # Panics
+    /// Panics if the `truncation` is outside the range `0.0..1.0`
+    pub fn new_frustum(base: impl Into<Point3>, apex: impl Into<Point3>, radius: Number, truncation: Number) -> Self {
+        assert!(
+            (0.0..1.0).contains(&truncation),
+            "truncation must be in the range `0.0..1.0`, was {truncation}"
+        );
+        let (base, apex) = (base.into(), apex.into());
+
+        let height = (apex - base).length();
+        let axis = (apex - base) / height;
+        let top_height = height * (1. - truncation);
+        let top_radius = radius * truncation;
+        let top = base + (axis * top_height);
+
+        // Bounds of a disk are `radius * sqrt(1 - axis^2)` along each axis
+        let extent = |a: Number| (1. - (a * a)).max(0.).sqrt();
+        let disk_extent = |r: Number| Vector3::new(extent(axis.x), extent(axis.y), extent(axis.z)) * r;
+        let aabb = Aabb::encompass(
+            Aabb::new(base - disk_extent(radius), base + disk_extent(radius)),
+            Aabb::new(top - disk_extent(top_radius), top + disk_extent(top_radius)),
+        );
+
+        Self {
+            centre: ((base.to_vector() + top.to_vector()) / 2.).to_point(),
+            base,
+            apex,
+            axis,
+            height,
+            radius,
+            top_height,
+            top_radius,
+            slope: radius / height,
+            orthogonals: Vector3::any_orthonormal_pair(&axis),
+            aabb,
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl Mesh for ConeMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
+        // Transform the ray into the cone's local frame, where `z` is along the axis, and the base is at the origin
+        let (ox, oy) = self.orthogonals;
+        let to_local = |v: Vector3| Vector3::new(v.dot(ox), v.dot(oy), v.dot(self.axis));
+        let o = to_local(ray.pos() - self.base);
+        let d = to_local(ray.dir());
+
+        // (dist, local pos, side)
+        let mut hits = SmallVec::<[(Number, Vector3, usize); 4]>::new();
+
+        // Body: `x^2 + y^2 = (slope * (height - z))^2`
+        {
+            let k2 = self.slope * self.slope;
+            let h = self.height - o.z;
+            let a = (d.x * d.x) + (d.y * d.y) - (k2 * d.z * d.z);
+            let half_b = (o.x * d.x) + (o.y * d.y) + (k2 * h * d.z);
+            let c = (o.x * o.x) + (o.y * o.y) - (k2 * h * h);
+
+            let roots: SmallVec<[Number; 2]> = if a.abs() < 1e-12 {
+                // Ray is parallel to the slope, so only one intersection
+                if half_b == 0. {
+                    SmallVec::new()
+                } else {
+                    SmallVec::from_slice(&[-c / (2. * half_b)])
+                }
+            } else {
+                let discriminant = (half_b * half_b) - (a * c);
+                if discriminant < 0. {
+                    SmallVec::new()
+                } else {
+                    let sqrt_d = discriminant.sqrt();
+                    SmallVec::from_slice(&[(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a])
+                }
+            };
+
+            for t in roots {
+                let p = o + (d * t);
+                // Also rejects the "shadow" cone, that extends past the apex
+                if (0.0..=self.top_height).contains(&p.z) {
+                    hits.push((t, p, 0));
+                }
+            }
+        }
+
+        // End caps
+        if d.z != 0. {
+            let caps = [(0., self.radius, 1), (self.top_height, self.top_radius, 2)];
+            for (z, r, side) in caps.into_iter().filter(|&(_, r, _)| r > 0.) {
+                let t = (z - o.z) / d.z;
+                let p = o + (d * t);
+                if (p.x * p.x) + (p.y * p.y) <= r * r {
+                    hits.push((t, p, side));
+                }
+            }
+        }
+
+        let (dist, p, side) = hits
+            .into_iter()
+            .filter(|(t, ..)| interval.contains(t))
+            .min_by(|(a, ..), (b, ..)| Number::total_cmp(a, b))?;
+
+        let to_world = |v: Vector3| (ox * v.x) + (oy * v.y) + (self.axis * v.z);
+        let (normal, uv) = match side {
+            0 => {
+                // The gradient of the surface, simplified using `sqrt(x^2 + y^2) = slope * (height - z)`
+                let radial = Vector3::new(p.x, p.y, 0.);
+                let normal = to_world(Vector3::new(p.x, p.y, self.slope * radial.length()))
+                    .try_normalize()
+                    // Right at the apex, there is no well-defined normal
+                    .unwrap_or(self.axis);
+                // Remap angle from `-pi..pi`to `0..1`
+                let u = (Number::atan2(p.y, p.x) / Number::PI / 2.) + 0.5;
+                let v = p.z / self.top_height;
+                (normal, Point2::new(u, v))
+            }
+            side => {
+                let (normal, r) = if side == 1 {
+                    (-self.axis, self.radius)
+                } else {
+                    (self.axis, self.top_radius)
+                };
+                let uv = Point2::new(p.x / r / 2. + 0.5, p.y / r / 2. + 0.5);
+                (normal, uv)
+            }
+        };
+
+        let pos_w = ray.at(dist);
+        let pos_l = (pos_w - self.centre).to_point();
+        let front_face = Vector3::dot(ray.dir(), normal) <= 0.;
+        Some(Intersection {
+            pos_w,
+            pos_l,
+            normal,
+            ray_normal: if front_face { normal } else { -normal },
+            front_face,
+            dist,
+            uv,
+            side,
+            colour: None,
+            tangent: None,
+        })
+    }
+}
+
+impl HasAabb for ConeMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for ConeMesh {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+// endregion Mesh Impl
@@ -179,6 +179,8 @@ impl Mesh for CylinderMesh {
             dist,
             uv,
             side: face,
+            colour: None,
+            tangent: None,
         });
     }
 }
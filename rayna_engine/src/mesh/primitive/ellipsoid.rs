@@ -0,0 +1,103 @@
+use crate::core::types::{Number, Point3, Vector3};
+use crate::mesh::primitive::sphere::sphere_uv;
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::CopyGetters;
+use rand_core::RngCore;
+
+/// An axis-aligned ellipsoid: a sphere that has been stretched by a different amount along each axis.
+///
+/// Prefer this over non-uniformly scaling a [SphereMesh](super::sphere::SphereMesh) with an object transform,
+/// as the normals are calculated exactly here. Rotating the ellipsoid with an object transform is fine.
+#[derive(Copy, Clone, Debug, CopyGetters)]
+#[get_copy = "pub"]
+pub struct EllipsoidMesh {
+    centre: Point3,
+    /// The radius of the ellipsoid along each axis
+    radii: Vector3,
+    /// `1 / radii`, precomputed to avoid divisions
+    inv_radii: Vector3,
+    aabb: Aabb,
+}
+
+// region Constructors
+
+impl EllipsoidMesh {
+    /// Creates a new ellipsoid, with the given radius along each of the axes
+    ///
+    /// # Panics
+    /// Panics if any of the radii are not positive
+    pub fn new(centre: impl Into<Point3>, radii: impl Into<Vector3>) -> Self {
+        let (centre, radii) = (centre.into(), radii.into());
+        assert!(
+            radii.x > 0. && radii.y > 0. && radii.z > 0.,
+            "radii must be positive, were {radii:?}"
+        );
+
+        Self {
+            centre,
+            radii,
+            inv_radii: radii.recip(),
+            aabb: Aabb::new(centre - radii, centre + radii),
+        }
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl Mesh for EllipsoidMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
+        // Squash the ray into the space where the ellipsoid is a unit sphere.
+        // Scaling keeps the distances along the ray the same, as long as we don't normalise the direction
+        let o = (ray.pos() - self.centre) * self.inv_radii;
+        let d = ray.dir() * self.inv_radii;
+
+        let a = d.length_squared();
+        let half_b = Vector3::dot(o, d);
+        let c = o.length_squared() - 1.;
+        let discriminant = (half_b * half_b) - (a * c);
+        if discriminant < 0. {
+            return None;
+        }
+
+        let sqrt_d = discriminant.sqrt();
+        let dist = [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a]
+            .into_iter()
+            .find(|t| interval.contains(t))?;
+
+        let pos_w = ray.at(dist);
+        // Point on the unit sphere
+        let local = o + (d * dist);
+        // The gradient of `(x/rx)^2 + (y/ry)^2 + (z/rz)^2`
+        let normal = (local * self.inv_radii).normalize();
+        let front_face = Vector3::dot(ray.dir(), normal) <= 0.;
+
+        Some(Intersection {
+            pos_w,
+            pos_l: local.to_point(),
+            normal,
+            ray_normal: if front_face { normal } else { -normal },
+            front_face,
+            dist,
+            uv: sphere_uv(local),
+            side: 0,
+            colour: None,
+            tangent: None,
+        })
+    }
+}
+
+impl HasAabb for EllipsoidMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for EllipsoidMesh {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+// endregion Mesh Impl
@@ -1,4 +1,8 @@
 pub mod axis_box;
+pub mod capsule;
+pub mod cone;
 pub mod cylinder;
+pub mod ellipsoid;
+pub mod rounded_box;
 pub mod sphere;
 pub mod triangle;
@@ -0,0 +1,190 @@
+use crate::core::types::{Number, Point2, Point3, Size3, Vector3};
+use crate::mesh::{Mesh, MeshProperties};
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::Intersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::CopyGetters;
+use rand_core::RngCore;
+
+/// An axis-aligned box, with rounded edges and corners.
+///
+/// # Intersection
+/// Intersections are found using a hybrid approach: the ray is first clipped to the bounds of the box analytically,
+/// and hits on the flat parts of the faces are returned exactly. Only rays that enter near the rounded edges are
+/// ray-marched (using the exact SDF), and only within the (short) span inside the bounds.
+///
+/// # Sides
+/// The same as [AxisBoxMesh](super::axis_box::AxisBoxMesh): `x: 0, 1; y: 2, 3; z: 4, 5`, negative side first.
+/// For the rounded parts, the side is that of the closest face.
+#[derive(Copy, Clone, Debug, CopyGetters)]
+#[get_copy = "pub"]
+pub struct RoundedBoxMesh {
+    centre: Point3,
+    /// Half the size of the box, along each axis
+    radius: Vector3,
+    /// Half the size of the inner (sharp-cornered) box, that is rounded to get the outer shape
+    core: Vector3,
+    /// The radius of the rounding on the edges and corners
+    corner_radius: Number,
+    aabb: Aabb,
+}
+
+// region Constructors
+
+impl RoundedBoxMesh {
+    /// The maximum number of ray-marching steps, for rays that pass near the rounded edges
+    pub const MAX_ITERATIONS: usize = 128;
+    /// The distance threshold at which a ray-marched ray is considered to have hit the surface
+    pub const EPSILON: Number = 1e-7;
+
+    /// Creates a new rounded box, with the given opposite corners
+    ///
+    /// # Panics
+    /// Panics if the `corner_radius` is negative, or larger than half the smallest side of the box
+    pub fn new(a: impl Into<Point3>, b: impl Into<Point3>, corner_radius: Number) -> Self {
+        let aabb = Aabb::new(a, b);
+        let radius = aabb.size() / 2.;
+        let max_corner = radius.min_element();
+        assert!(
+            (0.0..=max_corner).contains(&corner_radius),
+            "corner radius must be in the range `0.0..={max_corner}`, was {corner_radius}"
+        );
+
+        Self {
+            centre: Point3::from((aabb.min().to_vector() + aabb.max().to_vector()) / 2.),
+            radius,
+            core: radius - Vector3::splat(corner_radius),
+            corner_radius,
+            aabb,
+        }
+    }
+
+    /// Creates a new rounded box, with the given centre and dimensions
+    pub fn new_centred(centre: impl Into<Point3>, size: impl Into<Size3>, corner_radius: Number) -> Self {
+        let (centre, size) = (centre.into(), size.into().to_vector());
+        Self::new(centre + size / 2., centre - size / 2., corner_radius)
+    }
+}
+
+// endregion Constructors
+
+// region Mesh Impl
+
+impl RoundedBoxMesh {
+    /// The signed distance from the surface, for a point relative to the centre
+    fn sdf(&self, p: Vector3) -> Number {
+        let q = p.abs() - self.core;
+        q.max(Vector3::ZERO).length() + q.max_element().min(0.) - self.corner_radius
+    }
+
+    /// The (outward) normal of the surface, for a point relative to the centre
+    fn normal(&self, p: Vector3) -> Vector3 {
+        let q = p.abs() - self.core;
+        let outside = q.max(Vector3::ZERO);
+        if outside != Vector3::ZERO {
+            // Near the rounded parts, points away from the closest point on the core
+            (outside * p.signum()).normalize()
+        } else {
+            // Inside the core, so points out of the closest face
+            let axis = dominant_axis(q);
+            let mut n = [0.; 3];
+            n[axis] = p.as_array()[axis].signum();
+            Vector3::from(n)
+        }
+    }
+}
+
+impl Mesh for RoundedBoxMesh {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, _rng: &mut dyn RngCore) -> Option<Intersection> {
+        let ro = ray.pos() - self.centre;
+        let rd = ray.dir();
+
+        // Analytically clip the ray to the bounds of the box (slab test)
+        let t1 = (-self.radius - ro) * ray.inv_dir();
+        let t2 = (self.radius - ro) * ray.inv_dir();
+        let (t_near, t_far) = (t1.min(t2), t1.max(t2));
+        let (t_enter, t_exit) = (t_near.max_element(), t_far.min_element());
+        if t_enter > t_exit {
+            return None;
+        }
+        let start = t_enter.max(interval.start.unwrap_or(Number::NEG_INFINITY));
+        let end = t_exit.min(interval.end.unwrap_or(Number::INFINITY));
+        if start > end {
+            return None;
+        }
+
+        // Entering on a flat part of a face, so the hit is exactly on the bounds
+        let on_flat_face = start == t_enter && {
+            let q = (ro + (rd * t_enter)).abs() - self.core;
+            let axis = dominant_axis(t_near);
+            (0..3).all(|i| i == axis || q.as_array()[i] <= 0.)
+        };
+
+        let dist = if on_flat_face {
+            t_enter
+        } else {
+            // Ray-march the rest, stepping towards the surface from whichever side the ray starts on
+            let inside = self.sdf(ro + (rd * start)) < 0.;
+            let mut t = start;
+            let mut hit = None;
+            for _ in 0..Self::MAX_ITERATIONS {
+                let d = self.sdf(ro + (rd * t));
+                let d = if inside { -d } else { d };
+                if d < Self::EPSILON {
+                    hit = Some(t);
+                    break;
+                }
+                t += d;
+                if t > end {
+                    break;
+                }
+            }
+            hit.filter(|t| interval.contains(t))?
+        };
+
+        let pos_l = ro + (rd * dist);
+        let normal = self.normal(pos_l);
+        let front_face = Vector3::dot(rd, normal) <= 0.;
+
+        // Project onto the closest face for the UVs, remapping from `-radius..radius` to `0..1`
+        let axis = dominant_axis(normal.abs());
+        let (p, r) = (pos_l.as_array(), self.radius.as_array());
+        let [u_axis, v_axis] = [(axis + 1) % 3, (axis + 2) % 3];
+        let uv = Point2::new((p[u_axis] / r[u_axis] + 1.) / 2., (p[v_axis] / r[v_axis] + 1.) / 2.);
+
+        Some(Intersection {
+            pos_w: ray.at(dist),
+            pos_l: pos_l.to_point(),
+            normal,
+            ray_normal: if front_face { normal } else { -normal },
+            front_face,
+            dist,
+            uv,
+            side: (axis * 2) + (normal.as_array()[axis] > 0.) as usize,
+            colour: None,
+            tangent: None,
+        })
+    }
+}
+
+impl HasAabb for RoundedBoxMesh {
+    fn aabb(&self) -> Option<&Aabb> { Some(&self.aabb) }
+}
+
+impl MeshProperties for RoundedBoxMesh {
+    fn centre(&self) -> Point3 { self.centre }
+}
+
+// endregion Mesh Impl
+
+/// The index of the largest component of the vector
+fn dominant_axis(v: Vector3) -> usize {
+    if v.x >= v.y && v.x >= v.z {
+        0
+    } else if v.y >= v.z {
+        1
+    } else {
+        2
+    }
+}
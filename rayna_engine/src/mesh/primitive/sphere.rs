@@ -1,13 +1,19 @@
 use crate::core::types::{Number, Point2, Point3, Vector3};
 use crate::mesh::{Mesh, MeshProperties};
+use crate::object::light::LightShape;
 use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::Intersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
+use crate::shared::robust;
+use crate::shared::simd_math::{SimdConstants, SimdVector};
 use crate::shared::validate;
 use getset::CopyGetters;
 use glamour::AngleConsts;
 use rand_core::RngCore;
+use std::simd::prelude::*;
+use std::simd::{LaneCount, Simd, StdFloat, SupportedLaneCount};
 
 /// The actual instance of a sphere that can be rendered.
 /// Has precomputed values and therefore cannot be mutated
@@ -51,9 +57,21 @@ impl Mesh for SphereMesh {
         // NOTE: Normally `a = ray_dir.length_squared()`. Since the contract of `Ray` is that the direction is
         //  normalised, this means that `a = 1`, and we can simplify the equations a bit
         validate::normal3(ray_dir);
-        let half_b = Vector3::dot(ray_rel_pos, ray_dir);
-        let c = ray_rel_pos.length_squared() - self.radius_sqr;
-        let discriminant = (half_b * half_b) - (c);
+        let robust = robust::enabled();
+        let (half_b, c, discriminant) = if robust {
+            let half_b = robust::dot(ray_rel_pos, ray_dir);
+            let c = robust::length_squared(ray_rel_pos) - self.radius_sqr;
+            // Calculating `b^2 - c` directly loses most of the precision when the ray is far from the sphere,
+            // so instead use the (equivalent) distance from the sphere's centre to the ray's line
+            // See "Precision Improvements for Ray/Sphere Intersection" (Ray Tracing Gems, ch. 7)
+            let perp = ray_rel_pos - (ray_dir * half_b);
+            let discriminant = self.radius_sqr - robust::length_squared(perp);
+            (half_b, c, discriminant)
+        } else {
+            let half_b = Vector3::dot(ray_rel_pos, ray_dir);
+            let c = ray_rel_pos.length_squared() - self.radius_sqr;
+            (half_b, c, (half_b * half_b) - (c))
+        };
 
         //No solutions to where ray intersects with sphere because of negative square root
         if discriminant < 0. {
@@ -62,22 +80,83 @@ impl Mesh for SphereMesh {
 
         let sqrt_d = discriminant.sqrt();
 
+        let (near, far) = if robust {
+            // Numerically stable form of the quadratic formula, avoids subtracting two similar numbers
+            let q = -half_b - (half_b.signum() * sqrt_d);
+            if q == 0. {
+                (q, q)
+            } else {
+                let (r0, r1) = (c / q, q);
+                (r0.min(r1), r0.max(r1))
+            }
+        } else {
+            (-half_b - sqrt_d, -half_b + sqrt_d)
+        };
+
         // Find the nearest root that lies in the acceptable range.
         //This way we do a double check on both, prioritizing the less-positive root (as it's closer)
         //And we only return null if neither is valid
-        let mut root = -half_b - sqrt_d;
+        let mut root = near;
         if !interval.contains(&root) {
-            root = -half_b + sqrt_d;
+            root = far;
             if !interval.contains(&root) {
                 return None;
             }
         }
 
-        let dist = root;
+        return Some(self.intersection_at(ray, root));
+    }
+
+    fn intersect_packet<const L: usize>(
+        &self,
+        packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<Intersection>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        // The compensated maths isn't vectorised, so robust mode has to go through the scalar version
+        if robust::enabled() {
+            return std::array::from_fn(|i| match active.test(i) {
+                true => self.intersect(&packet.rays()[i], &intervals[i], rng),
+                false => None,
+            });
+        }
+
+        // The same as the non-robust scalar version, for all the rays at once
+        let centre = SimdVector(self.pos.as_array().map(Simd::<Number, L>::splat));
+        let ray_rel_pos = packet.pos() - centre;
+        let half_b = SimdVector::dot(ray_rel_pos, packet.dir());
+        let c = SimdVector::dot(ray_rel_pos, ray_rel_pos) - Simd::splat(self.radius_sqr);
+        let discriminant = (half_b * half_b) - c;
+        let hit = active & discriminant.simd_ge(SimdConstants::<L>::ZERO);
+        if !hit.any() {
+            return [None; L];
+        }
+
+        let sqrt_d = discriminant.simd_max(SimdConstants::<L>::ZERO).sqrt();
+        let (near, far) = ((-half_b - sqrt_d).to_array(), (-half_b + sqrt_d).to_array());
+        std::array::from_fn(|i| {
+            if !hit.test(i) {
+                return None;
+            }
+            let root = [near[i], far[i]].into_iter().find(|root| intervals[i].contains(root))?;
+            Some(self.intersection_at(&packet.rays()[i], root))
+        })
+    }
+
+    fn light_shape(&self) -> Option<LightShape> { Some(LightShape::Sphere(*self)) }
+}
+
+impl SphereMesh {
+    /// Creates the intersection for where the `ray` hits the surface, at the distance `dist`
+    fn intersection_at(&self, ray: &Ray, dist: Number) -> Intersection {
         let world_point = ray.at(dist);
         let local_point = (world_point - self.pos) / self.radius;
         let outward_normal = local_point;
-        let ray_pos_inside = Vector3::dot(ray_dir, outward_normal) > 0.;
+        let ray_pos_inside = Vector3::dot(ray.dir(), outward_normal) > 0.;
         //This flips the normal if the ray is inside the sphere
         //This forces the normal to always be going against the ray
         let ray_normal = if ray_pos_inside {
@@ -86,7 +165,7 @@ impl Mesh for SphereMesh {
             outward_normal
         };
 
-        return Some(Intersection {
+        Intersection {
             pos_w: world_point,
             pos_l: local_point.to_point(),
             dist,
@@ -95,7 +174,9 @@ impl Mesh for SphereMesh {
             front_face: !ray_pos_inside,
             uv: sphere_uv(local_point),
             side: 0,
-        });
+            colour: None,
+            tangent: Some(sphere_tangent(local_point)),
+        }
     }
 }
 
@@ -120,4 +201,12 @@ pub fn sphere_uv(p: Vector3) -> Point2 {
     return Point2::new(u, v);
 }
 
+/// The direction that the `u` coordinate of [sphere_uv()] increases along, for a point on a sphere.
+///
+/// This is zero at the poles, where `u` is undefined
+pub fn sphere_tangent(p: Vector3) -> Vector3 {
+    // `u` follows `atan2(-z, x)`, so moves around the `Y` axis
+    Vector3::new(p.z, 0., -p.x)
+}
+
 //endregion Helper
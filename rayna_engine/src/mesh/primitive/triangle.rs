@@ -1,9 +1,10 @@
-use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::core::types::{Colour, Number, Point2, Point3, Vector2, Vector3};
 use crate::mesh::{Mesh, MeshProperties};
 use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::Intersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::robust;
 use num_traits::Zero;
 use rand_core::RngCore;
 use std::fmt::Debug;
@@ -15,9 +16,21 @@ pub struct Triangle {
     vertices: [Point3; 3],
     /// The corresponding normal vectors at the vertices
     normals: [Vector3; 3],
+    /// The UV coordinates at the vertices
+    uvs: [Point2; 3],
+    /// The (optional) colours at the vertices
+    colours: Option<[Colour; 3]>,
     aabb: Aabb,
 }
 
+/// The UV coordinates a [Triangle] uses by default, which map the UVs directly to the barycentric coordinates
+/// of the intersection
+pub const DEFAULT_UVS: [Point2; 3] = [
+    Point2 { x: 0., y: 0. },
+    Point2 { x: 1., y: 0. },
+    Point2 { x: 0., y: 1. },
+];
+
 impl Triangle {
     pub fn new(vertices: impl Into<[Point3; 3]>, normals: impl Into<[Vector3; 3]>) -> Self {
         let (vertices, normals) = (vertices.into(), normals.into());
@@ -31,9 +44,23 @@ impl Triangle {
         Self {
             vertices,
             normals,
+            uvs: DEFAULT_UVS,
+            colours: None,
             aabb: Aabb::encompass_points(vertices),
         }
     }
+
+    /// Sets the UV coordinates at each of the vertices, which are interpolated across the face of the triangle
+    pub fn with_uvs(mut self, uvs: impl Into<[Point2; 3]>) -> Self {
+        self.uvs = uvs.into();
+        self
+    }
+
+    /// Sets the colours at each of the vertices, which are interpolated across the face of the triangle
+    pub fn with_colours(mut self, colours: impl Into<[Colour; 3]>) -> Self {
+        self.colours = Some(colours.into());
+        self
+    }
 }
 
 // region Mesh Impl
@@ -61,10 +88,17 @@ impl Mesh for Triangle {
 
         let [v0, v1, v2] = self.vertices;
 
+        // Swap to the error-compensated maths if we need the extra precision
+        let (cross, dot): (fn(Vector3, Vector3) -> Vector3, fn(Vector3, Vector3) -> Number) = if robust::enabled() {
+            (robust::cross, robust::dot)
+        } else {
+            (Vector3::cross, Vector3::dot)
+        };
+
         let v0v1 = v1 - v0;
         let v0v2 = v2 - v0;
-        let p_vec = Vector3::cross(ray.dir(), v0v2);
-        let det = v0v1.dot(p_vec);
+        let p_vec = cross(ray.dir(), v0v2);
+        let det = dot(v0v1, p_vec);
 
         // ray and triangle are parallel
         if det.is_zero() {
@@ -74,17 +108,17 @@ impl Mesh for Triangle {
         let inv_det = 1. / det;
 
         let t_vec = ray.pos() - v0;
-        let u = Vector3::dot(t_vec, p_vec) * inv_det;
+        let u = dot(t_vec, p_vec) * inv_det;
         if u < 0. || u > 1. {
             return None;
         }
 
-        let q_vec = Vector3::cross(t_vec, v0v1);
-        let v = Vector3::dot(ray.dir(), q_vec) * inv_det;
+        let q_vec = cross(t_vec, v0v1);
+        let v = dot(ray.dir(), q_vec) * inv_det;
         if v < 0. || u + v > 1. {
             return None;
         }
-        let t = Vector3::dot(v0v2, q_vec) * inv_det;
+        let t = dot(v0v2, q_vec) * inv_det;
 
         if !interval.contains(&t) {
             return None;
@@ -101,10 +135,12 @@ impl Mesh for Triangle {
             pos_l: bary_coords.to_point(),
             front_face: det.is_sign_negative(),
             dist: t,
-            uv: Point2::new(u, v),
+            uv: Self::interpolate_uvs(self.uvs, bary_coords),
             side: 0,
             ray_normal: normal * -det.signum(),
             normal,
+            colour: self.colours.map(|c| Self::interpolate_colours(c, bary_coords)),
+            tangent: Self::uv_tangent([v0v1, v0v2], self.uvs),
         })
     }
 }
@@ -117,6 +153,32 @@ impl Triangle {
             .fold(Vector3::ZERO, Vector3::add)
             .try_normalize()
     }
+
+    /// Interpolates across the vertex UVs for a given point in barycentric coordinates
+    fn interpolate_uvs(uvs: [Point2; 3], bary_coords: Vector3) -> Point2 {
+        std::iter::zip(uvs, bary_coords)
+            .map(|(uv, u)| uv.to_vector() * u)
+            .fold(Vector2::ZERO, Add::add)
+            .to_point()
+    }
+
+    /// Calculates the direction that the `u` texture coordinate increases along, from the triangle's edges and the
+    /// UVs at its vertices. Returns [None] if the UVs are degenerate (all in a line)
+    fn uv_tangent([e1, e2]: [Vector3; 2], [uv0, uv1, uv2]: [Point2; 3]) -> Option<Vector3> {
+        let (d1, d2) = (uv1 - uv0, uv2 - uv0);
+        let det = (d1.x * d2.y) - (d2.x * d1.y);
+        if det.is_zero() {
+            return None;
+        }
+        Some(((e1 * d2.y) - (e2 * d1.y)) / det)
+    }
+
+    /// Interpolates across the vertex colours for a given point in barycentric coordinates
+    fn interpolate_colours(colours: [Colour; 3], bary_coords: Vector3) -> Colour {
+        std::iter::zip(colours, bary_coords)
+            .map(|(c, u)| c * u)
+            .fold(Colour::BLACK, Add::add)
+    }
 }
 
 // endregion Mesh Impl
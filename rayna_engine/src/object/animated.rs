@@ -0,0 +1,243 @@
+use crate::core::types::{Number, Point3, Transform3};
+use crate::object::light::{transform_lights, SceneLight};
+use crate::object::transform::{decompose, interpolate, ObjectTransform, TransformParts};
+use crate::object::Object;
+use crate::scene::camera::Shutter;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::FullIntersection;
+use crate::shared::interval::Interval;
+use crate::shared::math::Lerp;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use serde::Serialize;
+use smallvec::SmallVec;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// How a [KeyframeTrack] moves between its keyframes
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum Interpolation {
+    /// Jumps to the next keyframe when it is reached
+    Step,
+    /// Moves at a constant speed between keyframes
+    Linear,
+    /// Eases in and out of each keyframe (smoothstep)
+    Smooth,
+}
+
+/// A sequence of transforms at different times, which are interpolated between to animate an object.
+///
+/// The translation, rotation and scale of each transform are interpolated separately, with the rotation taking the
+/// shortest path. This means that a full turn needs at least three keyframes, otherwise the start and end rotations
+/// are the same.
+///
+/// Before the first keyframe and after the last, the transform stays at that keyframe
+#[derive(Clone, Debug, CopyGetters)]
+pub struct KeyframeTrack {
+    keyframes: Vec<(Number, TransformParts)>,
+    #[get_copy = "pub"]
+    interpolation: Interpolation,
+}
+
+impl KeyframeTrack {
+    /// Creates a new track from the `(time, transform)` keyframes, which don't need to be in order.
+    ///
+    /// The transforms should only contain scaling, rotation and translation (no shearing)
+    ///
+    /// # Panics
+    /// Panics if there are no keyframes, or any of the times are not finite
+    pub fn new(keyframes: impl IntoIterator<Item = (Number, Transform3)>, interpolation: Interpolation) -> Self {
+        let mut keyframes = keyframes
+            .into_iter()
+            .map(|(time, transform)| {
+                assert!(time.is_finite(), "keyframe time must be finite (was {time})");
+                (time, decompose(transform))
+            })
+            .collect::<Vec<_>>();
+        assert!(!keyframes.is_empty(), "track must have at least one keyframe");
+        keyframes.sort_by(|(a, _), (b, _)| Number::total_cmp(a, b));
+
+        Self {
+            keyframes,
+            interpolation,
+        }
+    }
+
+    /// The times of the first and last keyframes
+    pub fn time_range(&self) -> (Number, Number) { (self.keyframes[0].0, self.keyframes[self.keyframes.len() - 1].0) }
+
+    /// The times of all the keyframes, in order
+    pub fn times(&self) -> impl Iterator<Item = Number> + '_ { self.keyframes.iter().map(|(time, _)| *time) }
+
+    /// Calculates the (uncorrected) transform at the given time
+    pub fn transform_at(&self, time: Number) -> Transform3 {
+        // Index of the first keyframe after the time
+        let next = self.keyframes.partition_point(|(t, _)| *t <= time);
+        if next == 0 {
+            return interpolate(self.keyframes[0].1, self.keyframes[0].1, 0.);
+        }
+        let (start_time, start) = self.keyframes[next - 1];
+        let Some(&(end_time, end)) = self.keyframes.get(next) else {
+            return interpolate(start, start, 0.);
+        };
+
+        let t = (time - start_time) / (end_time - start_time);
+        let t = match self.interpolation {
+            Interpolation::Step => 0.,
+            Interpolation::Linear => t,
+            Interpolation::Smooth => t * t * (3. - (2. * t)),
+        };
+        interpolate(start, end, t)
+    }
+}
+
+/// How many times the track is sampled between each pair of keyframes, when calculating the bounds
+const ANIMATION_AABB_STEPS: usize = 8;
+
+/// An object that moves over time, by applying an animated transform (see [KeyframeTrack]) to another object.
+///
+/// The time is set for the whole scene at once, using [Scene::set_time()](crate::scene::Scene::set_time), so that
+/// a sequence of frames can be rendered (e.g. for a turntable). The bounds of the object cover the whole animation,
+/// so that changing the time doesn't require the acceleration structures in the scene to be rebuilt.
+///
+/// While the camera's [shutter](Shutter) is open, the object moves from where it is when the shutter opens to where
+/// it is when the shutter closes, so that fast animations are motion blurred. The animation is followed in a straight
+/// line over the shutter interval, which is only noticeable for very long shutters.
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct AnimatedObject<Obj: Object> {
+    #[get = "pub"]
+    object: Box<Obj>,
+    #[get = "pub"]
+    track: KeyframeTrack,
+    /// The current time of the animation
+    #[get_copy = "pub"]
+    time: Number,
+    /// When the shutter is open, relative to the time
+    #[get_copy = "pub"]
+    shutter: Shutter,
+    /// The centre that the transforms are corrected around
+    centre: Point3,
+    /// The transform at the current time, moving over the shutter interval
+    transform: ObjectTransform,
+    aabb: Option<Aabb>,
+}
+
+// region Constructors
+
+impl<Obj: Object> AnimatedObject<Obj> {
+    /// Creates a new animated object, where the transforms are corrected to be around the centre of the object's
+    /// bounds (see [ObjectTransform::new_corrected()]). The animation starts at time `0.0`
+    pub fn new(object: impl Into<Obj>, track: KeyframeTrack) -> Self {
+        let object = object.into();
+        let centre = object
+            .aabb()
+            .map_or(Point3::ZERO, |aabb| aabb.min() + (aabb.size() / 2.));
+        Self::new_around(object, track, centre)
+    }
+
+    /// Creates a new animated object, without correcting the transforms. See [Self::new()]
+    pub fn new_uncorrected(object: impl Into<Obj>, track: KeyframeTrack) -> Self {
+        Self::new_around(object.into(), track, Point3::ZERO)
+    }
+
+    fn new_around(object: Obj, track: KeyframeTrack, centre: Point3) -> Self {
+        let transform_at = |time: Number| ObjectTransform::new_corrected(track.transform_at(time), centre);
+
+        // Sample between each pair of keyframes, since the rotation can swing outside the bounds at the keyframes
+        let times = track.times().collect::<Vec<_>>();
+        let steps = times.windows(2).flat_map(|pair| {
+            (0..ANIMATION_AABB_STEPS)
+                .map(|i| Lerp::lerp(pair[0], pair[1], i as Number / ANIMATION_AABB_STEPS as Number))
+        });
+        let aabb = steps
+            .chain(times.last().copied())
+            .map(|time| transform_at(time).calculate_aabb(object.aabb()))
+            .reduce(|a, b| Option::zip(a, b).map(|(a, b)| Aabb::encompass(a, b)))
+            .flatten();
+
+        Self {
+            transform: transform_at(0.),
+            object: Box::new(object),
+            track,
+            time: 0.,
+            shutter: Shutter::INSTANT,
+            centre,
+            aabb,
+        }
+    }
+
+    /// Calculates the transform for the current time and shutter
+    fn update_transform(&mut self) {
+        let start = self.track.transform_at(self.shutter.time_at(self.time, 0.));
+        let end = self.track.transform_at(self.shutter.time_at(self.time, 1.));
+        self.transform = if start == end {
+            ObjectTransform::new_corrected(start, self.centre)
+        } else {
+            ObjectTransform::new_moving_corrected(start, end, self.centre)
+        };
+    }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Obj: Object> Object for AnimatedObject<Obj> {
+    type Mesh = Obj::Mesh;
+    type Mat = Obj::Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Self::Mat>> {
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        let mut inner = self.object.full_intersect(&trans_ray, interval, rng)?;
+        inner.intersection = self.transform.outgoing_intersection(orig_ray, inner.intersection);
+        Some(inner)
+    }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Self::Mat>; 4]> {
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        let mut hits = self.object.full_intersect_all(&trans_ray, interval, rng);
+        for hit in &mut hits {
+            hit.intersection = self.transform.outgoing_intersection(orig_ray, hit.intersection);
+        }
+        hits
+    }
+
+    fn prepare(&mut self) { self.object.prepare() }
+
+    fn set_time(&mut self, time: Number) {
+        self.time = time;
+        self.update_transform();
+        self.object.set_time(time);
+    }
+
+    fn set_shutter(&mut self, shutter: Shutter) {
+        self.shutter = shutter;
+        self.update_transform();
+        self.object.set_shutter(shutter);
+    }
+
+    fn set_lod_quality(&mut self, quality: Number) { self.object.set_lod_quality(quality) }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Self::Mat>>) {
+        let start = lights.len();
+        self.object.collect_lights(lights);
+        transform_lights(&mut lights[start..], &self.transform);
+    }
+}
+
+impl<Obj: Object> HasAabb for AnimatedObject<Obj> {
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+// endregion Object Impl
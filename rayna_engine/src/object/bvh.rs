@@ -7,14 +7,20 @@ use crate::core::types::{Number, Point3};
 use getset::Getters;
 use indextree::{Arena, NodeId};
 use rand_core::RngCore;
+use rayon::prelude::*;
+use std::simd::{LaneCount, SupportedLaneCount};
 
+use crate::object::light::{transform_lights, SceneLight};
 use crate::object::transform::ObjectTransform;
 use crate::object::Object;
+use crate::scene::camera::Shutter;
 use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::bvh_cost;
 use crate::shared::generic_bvh::{GenericBvh, GenericBvhNode};
 use crate::shared::intersect::FullIntersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
 use crate::shared::validate;
 
 #[derive(Getters, Clone, Debug)]
@@ -49,15 +55,40 @@ impl<Obj: Object> BvhObject<Obj> {
     pub fn new_uncorrected(objects: impl IntoIterator<Item = Obj>, transform: impl Into<ObjectTransform>) -> Self {
         let transform = transform.into();
         let inner = GenericBvh::new(objects);
-        let aabb = inner.root_id().map(|root| match inner.arena()[root].get() {
+        let aabb = Self::root_aabb(&inner);
+
+        Self { inner, transform, aabb }
+    }
+
+    fn root_aabb(inner: &GenericBvh<Obj>) -> Option<Aabb> {
+        inner.root_id().map(|root| match inner.arena()[root].get() {
             GenericBvhNode::Nested(aabb) => *aabb,
             GenericBvhNode::Object(o) => *o.expect_aabb(),
-        });
+        })
+    }
+}
 
-        Self { inner, transform, aabb }
+// region Refitting
+
+impl<Obj: Object> BvhObject<Obj> {
+    /// Iterates over all the objects in the tree
+    pub fn objects(&self) -> impl Iterator<Item = &Obj> { self.inner.objects() }
+
+    /// Iterates mutably over all the objects in the tree. Call [Self::refit()] after changing them
+    pub fn objects_mut(&mut self) -> impl Iterator<Item = &mut Obj> { self.inner.objects_mut() }
+
+    /// Updates the bounds of the tree after the objects have been changed (e.g. animated). See [GenericBvh::refit()]
+    ///
+    /// # Note
+    /// If this object is inside another [BvhObject], the outer one must be refitted afterwards as well
+    pub fn refit(&mut self) {
+        self.inner.refit();
+        self.aabb = Self::root_aabb(&self.inner);
     }
 }
 
+// endregion Refitting
+
 impl<Obj: Object> BvhObject<Obj> {
     /// Given a [NodeId] on the [Arena] tree, calculates the nearest intersection for the given `ray` and `interval`
     ///
@@ -77,6 +108,7 @@ impl<Obj: Object> BvhObject<Obj> {
         return match arena.get(node).expect("node should exist in arena").get() {
             // An aabb will need to delegate to child nodes if not missed
             GenericBvhNode::Nested(aabb) => {
+                bvh_cost::visit_node();
                 if !aabb.hit(ray, interval) {
                     return None;
                 }
@@ -98,14 +130,63 @@ impl<Obj: Object> BvhObject<Obj> {
             }
             // Objects can be delegated directly
             GenericBvhNode::Object(obj) => {
+                bvh_cost::visit_node();
                 if !obj.expect_aabb().hit(ray, interval) {
                     None
                 } else {
+                    bvh_cost::test_primitive();
                     obj.full_intersect(ray, interval, rng)
                 }
             }
         };
     }
+
+    /// [Self::bvh_node_intersect()] for a whole packet of rays at once.
+    ///
+    /// The rays go down the tree together, with the `active` mask keeping track of which of them are still inside
+    /// the current node. Each ray's interval is shrunk as it hits things, and its nearest intersection is stored
+    /// in `hits`
+    fn bvh_node_intersect_packet<'o, const L: usize>(
+        packet: &RayPacket<L>,
+        intervals: &mut [Interval<Number>; L],
+        active: PacketMask<L>,
+        node: NodeId,
+        arena: &'o Arena<GenericBvhNode<Obj>>,
+        hits: &mut [Option<FullIntersection<'o, Obj::Mat>>; L],
+        rng: &mut dyn RngCore,
+    ) where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        match arena.get(node).expect("node should exist in arena").get() {
+            GenericBvhNode::Nested(aabb) => {
+                bvh_cost::visit_node();
+                let active = active & aabb.hit_packet(packet, intervals);
+                if !active.any() {
+                    return;
+                }
+                for child in node.children(arena) {
+                    Self::bvh_node_intersect_packet(packet, intervals, active, child, arena, hits, rng);
+                }
+            }
+            GenericBvhNode::Object(obj) => {
+                bvh_cost::visit_node();
+                let active = active & obj.expect_aabb().hit_packet(packet, intervals);
+                if !active.any() {
+                    return;
+                }
+                bvh_cost::test_primitive();
+                let obj_hits = obj.full_intersect_packet(packet, intervals, active, rng);
+                for (i, hit) in obj_hits.into_iter().enumerate() {
+                    let Some(hit) = hit else {
+                        continue;
+                    };
+                    validate::intersection(packet.rays()[i], &hit.intersection, &intervals[i]);
+                    intervals[i] = intervals[i].with_some_end(hit.intersection.dist);
+                    hits[i] = Some(hit);
+                }
+            }
+        }
+    }
 }
 
 impl<Obj: Object> Object for BvhObject<Obj> {
@@ -125,6 +206,62 @@ impl<Obj: Object> Object for BvhObject<Obj> {
         inner.intersection = self.transform.outgoing_intersection(orig_ray, inner.intersection);
         Some(inner)
     }
+
+    fn full_intersect_packet<'o, const L: usize>(
+        &'o self,
+        orig_packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<FullIntersection<'o, Obj::Mat>>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        // Rays heading in different directions would go down different branches, so they might as well be separate
+        let root = self.inner.root_id();
+        let Some(root) = root.filter(|_| orig_packet.is_coherent()) else {
+            return std::array::from_fn(|i| match active.test(i) {
+                true => self.full_intersect(&orig_packet.rays()[i], &intervals[i], rng),
+                false => None,
+            });
+        };
+
+        let trans_packet = orig_packet.map(|ray| self.transform.incoming_ray(ray));
+        let mut intervals = *intervals;
+        let mut hits = std::array::from_fn(|_| None);
+        Self::bvh_node_intersect_packet(
+            &trans_packet,
+            &mut intervals,
+            active,
+            root,
+            self.inner.arena(),
+            &mut hits,
+            rng,
+        );
+        for (hit, orig_ray) in std::iter::zip(&mut hits, orig_packet.rays()) {
+            if let Some(hit) = hit {
+                hit.intersection = self.transform.outgoing_intersection(orig_ray, hit.intersection);
+            }
+        }
+        hits
+    }
+
+    fn prepare(&mut self) {
+        let objects = self.objects_mut().collect::<Vec<_>>();
+        objects.into_par_iter().for_each(|obj| obj.prepare());
+    }
+
+    fn set_time(&mut self, time: Number) { self.objects_mut().for_each(|obj| obj.set_time(time)) }
+
+    fn set_shutter(&mut self, shutter: Shutter) { self.objects_mut().for_each(|obj| obj.set_shutter(shutter)) }
+
+    fn set_lod_quality(&mut self, quality: Number) { self.objects_mut().for_each(|obj| obj.set_lod_quality(quality)) }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Obj::Mat>>) {
+        let start = lights.len();
+        self.objects().for_each(|obj| obj.collect_lights(lights));
+        transform_lights(&mut lights[start..], &self.transform);
+    }
 }
 
 impl<Obj: Object> HasAabb for BvhObject<Obj> {
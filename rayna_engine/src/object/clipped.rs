@@ -0,0 +1,200 @@
+use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::object::light::SceneLight;
+use crate::object::Object;
+use crate::scene::camera::Shutter;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::{FullIntersection, Intersection};
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use derivative::Derivative;
+use getset::Getters;
+use rand_core::RngCore;
+
+/// A plane that cuts away part of an object, see [ClippedObject]
+///
+/// Everything on the side of the plane that the normal points towards is removed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ClipPlane {
+    pub point: Point3,
+    /// The normal of the plane, pointing towards the side that is removed. This is normalised
+    pub normal: Vector3,
+}
+
+impl ClipPlane {
+    /// Creates a new plane through the `point`, which removes everything in the direction of the `normal`
+    ///
+    /// # Panics
+    /// Panics if the normal is zero
+    pub fn new(point: impl Into<Point3>, normal: impl Into<Vector3>) -> Self {
+        Self {
+            point: point.into(),
+            normal: normal
+                .into()
+                .try_normalize()
+                .expect("clip plane normal must not be zero"),
+        }
+    }
+}
+
+/// An object with parts of it cut away by one or more [ClipPlane]s, for showing section (cut-away) views of objects.
+///
+/// Only the part of the object that is behind all of the planes is kept. Without a cap material the object is left
+/// open, so the inside of it can be seen through the cut. With a cap material, the cut is filled in, as if the
+/// object was solid; this needs the object to be closed (have a well-defined inside).
+#[derive(Getters, Derivative)]
+#[derivative(Clone(bound = "Obj: Clone, Obj::Mat: Clone"), Debug)]
+#[get = "pub"]
+pub struct ClippedObject<Obj: Object> {
+    object: Box<Obj>,
+    planes: Vec<ClipPlane>,
+    /// The material the cut surfaces are filled in with, if any
+    cap_material: Option<Obj::Mat>,
+}
+
+// region Constructors
+
+impl<Obj: Object> ClippedObject<Obj> {
+    /// Creates a new clipped object, where the cuts are left open
+    pub fn new(object: impl Into<Obj>, planes: impl IntoIterator<Item = ClipPlane>) -> Self {
+        Self {
+            object: Box::new(object.into()),
+            planes: planes.into_iter().collect(),
+            cap_material: None,
+        }
+    }
+
+    /// Fills in the cut surfaces with the given material
+    pub fn with_cap_material(mut self, material: impl Into<Obj::Mat>) -> Self {
+        self.cap_material = Some(material.into());
+        self
+    }
+
+    /// Finds the range of distances along the ray that are behind all of the planes, if any
+    fn kept_range(&self, ray: &Ray) -> Option<(Number, Number)> {
+        let (mut enter, mut exit) = (Number::NEG_INFINITY, Number::INFINITY);
+        for plane in &self.planes {
+            let offset = Vector3::dot(ray.pos() - plane.point, plane.normal);
+            let rate = Vector3::dot(ray.dir(), plane.normal);
+            if rate == 0. {
+                // Parallel to the plane, so it's either always kept or never
+                if offset > 0. {
+                    return None;
+                }
+                continue;
+            }
+            let dist = -offset / rate;
+            if rate < 0. {
+                enter = enter.max(dist);
+            } else {
+                exit = exit.min(dist);
+            }
+        }
+        (enter <= exit).then_some((enter, exit))
+    }
+
+    /// Is the given point along the ray inside the object? Found by checking if the next surface is facing away
+    fn is_inside(&self, ray: &Ray, dist: Number, rng: &mut dyn RngCore) -> bool {
+        self.object
+            .full_intersect(ray, &Interval::from(dist..), rng)
+            .is_some_and(|hit| !hit.intersection.front_face)
+    }
+
+    /// Creates an intersection with the cap on the plane that the ray crosses at the given distance
+    fn cap_intersection<'o>(
+        &'o self,
+        ray: &Ray,
+        dist: Number,
+        material: &'o Obj::Mat,
+    ) -> Option<FullIntersection<'o, Obj::Mat>> {
+        let pos = ray.at(dist);
+        // The plane the ray crosses is the one closest to the point
+        let plane = self.planes.iter().min_by(|a, b| {
+            let dist = |plane: &ClipPlane| Vector3::dot(pos - plane.point, plane.normal).abs();
+            Number::total_cmp(&dist(a), &dist(b))
+        })?;
+
+        // The cap faces out of the kept part of the object, the same way as the plane's normal
+        let front_face = Vector3::dot(ray.dir(), plane.normal) < 0.;
+        let (u, v) = Vector3::any_orthonormal_pair(&plane.normal);
+        let local = pos - plane.point;
+        let intersection = Intersection {
+            dist,
+            pos_w: pos,
+            pos_l: pos,
+            normal: plane.normal,
+            ray_normal: if front_face { plane.normal } else { -plane.normal },
+            front_face,
+            uv: Point2::new(Vector3::dot(local, u), Vector3::dot(local, v)),
+            side: 0,
+            colour: None,
+            tangent: Some(u),
+        };
+        Some(intersection.make_full(material))
+    }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Obj: Object> Object for ClippedObject<Obj> {
+    type Mesh = Obj::Mesh;
+    type Mat = Obj::Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Self::Mat>> {
+        let (enter, exit) = self.kept_range(ray)?;
+        let start = interval.start.map_or(enter, |start| start.max(enter));
+        let end = interval.end.map_or(exit, |end| end.min(exit));
+        if start > end {
+            return None;
+        }
+
+        // Where the ray crosses into the kept part while inside the object, it hits the cap
+        if let Some(cap) = &self.cap_material {
+            let entering_cap = enter.is_finite() && interval.contains(&enter);
+            if entering_cap && self.is_inside(ray, enter, rng) {
+                return self.cap_intersection(ray, enter, cap);
+            }
+        }
+
+        // Infinite bounds mean there aren't any planes on that side
+        let kept = Interval {
+            start: start.is_finite().then_some(start),
+            end: end.is_finite().then_some(end),
+        };
+        if let Some(hit) = self.object.full_intersect(ray, &kept, rng) {
+            return Some(hit);
+        }
+
+        // Likewise when it crosses out of the kept part, while inside
+        if let Some(cap) = &self.cap_material {
+            let exiting_cap = exit.is_finite() && interval.contains(&exit);
+            if exiting_cap && self.is_inside(ray, exit, rng) {
+                return self.cap_intersection(ray, exit, cap);
+            }
+        }
+        None
+    }
+
+    fn prepare(&mut self) { self.object.prepare() }
+
+    fn set_time(&mut self, time: Number) { self.object.set_time(time) }
+
+    fn set_shutter(&mut self, shutter: Shutter) { self.object.set_shutter(shutter) }
+
+    fn set_lod_quality(&mut self, quality: Number) { self.object.set_lod_quality(quality) }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Self::Mat>>) { self.object.collect_lights(lights) }
+}
+
+impl<Obj: Object> HasAabb for ClippedObject<Obj> {
+    /// The bounds of the whole object, since the planes only make it smaller
+    fn aabb(&self) -> Option<&Aabb> { self.object.aabb() }
+}
+
+// endregion Object Impl
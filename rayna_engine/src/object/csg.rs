@@ -0,0 +1,116 @@
+use crate::core::types::Number;
+use crate::mesh::advanced::csg::CsgOperation;
+use crate::object::light::SceneLight;
+use crate::object::Object;
+use crate::scene::camera::Shutter;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::FullIntersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use smallvec::SmallVec;
+
+/// An object made by combining two objects with a boolean operation (**Constructive Solid Geometry**)
+///
+/// This works like [CsgMesh](crate::mesh::advanced::csg::CsgMesh), except that each part keeps its own material and
+/// transform, so differently-materialed objects can be combined. Surfaces keep the material of the object they came
+/// from, so the faces cut by the second object in a [CsgOperation::Difference] have that object's material (e.g.
+/// cutting a metal sphere out of a wooden box leaves a metal-lined hole).
+///
+/// Both objects must be closed (have a well-defined inside), see [Object::full_intersect_all()]
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct CsgObject<Obj: Object> {
+    #[get = "pub"]
+    a: Box<Obj>,
+    #[get = "pub"]
+    b: Box<Obj>,
+    #[get_copy = "pub"]
+    operation: CsgOperation,
+    aabb: Option<Aabb>,
+}
+
+// region Constructors
+
+impl<Obj: Object> CsgObject<Obj> {
+    pub fn new(a: impl Into<Obj>, b: impl Into<Obj>, operation: CsgOperation) -> Self {
+        let (a, b) = (a.into(), b.into());
+        Self {
+            aabb: operation.bounds(a.aabb(), b.aabb()),
+            a: Box::new(a),
+            b: Box::new(b),
+            operation,
+        }
+    }
+
+    pub fn union(a: impl Into<Obj>, b: impl Into<Obj>) -> Self { Self::new(a, b, CsgOperation::Union) }
+
+    pub fn intersection(a: impl Into<Obj>, b: impl Into<Obj>) -> Self { Self::new(a, b, CsgOperation::Intersection) }
+
+    pub fn difference(a: impl Into<Obj>, b: impl Into<Obj>) -> Self { Self::new(a, b, CsgOperation::Difference) }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Obj: Object> Object for CsgObject<Obj> {
+    type Mesh = Obj::Mesh;
+    type Mat = Obj::Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Self::Mat>> {
+        self.full_intersect_all(ray, interval, rng).into_iter().next()
+    }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Self::Mat>; 4]> {
+        let hits_a = self.a.full_intersect_all(ray, interval, rng);
+        let hits_b = self.b.full_intersect_all(ray, interval, rng);
+        self.operation.combine_hits(hits_a, hits_b).collect()
+    }
+
+    fn prepare(&mut self) {
+        self.a.prepare();
+        self.b.prepare();
+    }
+
+    fn set_time(&mut self, time: Number) {
+        self.a.set_time(time);
+        self.b.set_time(time);
+    }
+
+    fn set_shutter(&mut self, shutter: Shutter) {
+        self.a.set_shutter(shutter);
+        self.b.set_shutter(shutter);
+    }
+
+    fn set_lod_quality(&mut self, quality: Number) {
+        self.a.set_lod_quality(quality);
+        self.b.set_lod_quality(quality);
+    }
+
+    /// The lights of both objects are kept whole, even where parts of them are cut away. The exception is the second
+    /// object of a [difference](CsgOperation::Difference), which only ever shows on the inside of the first
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Self::Mat>>) {
+        self.a.collect_lights(lights);
+        match self.operation {
+            CsgOperation::Union | CsgOperation::Intersection => self.b.collect_lights(lights),
+            CsgOperation::Difference => {}
+        }
+    }
+}
+
+impl<Obj: Object> HasAabb for CsgObject<Obj> {
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+// endregion Object Impl
@@ -0,0 +1,132 @@
+use crate::core::types::{Number, Point3, Transform3};
+use crate::object::light::{transform_lights, SceneLight};
+use crate::object::list::ObjectList;
+use crate::object::transform::ObjectTransform;
+use crate::object::Object;
+use crate::scene::camera::Shutter;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::FullIntersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use smallvec::SmallVec;
+
+/// A group of objects that share a parent transform, which is applied on top of each child's own transform.
+///
+/// This is useful for assemblies (e.g. a car made from a body and wheels, or a rig of lights) which should be moved
+/// around as a whole. The transform can be changed with [Self::set_transform()] at any time, which is cheap because
+/// none of the children have to be changed. Groups can be nested inside each other, and the transforms combine.
+///
+/// # Note
+/// Changing the transform changes the bounds of the group, so any [BvhObject](crate::object::bvh::BvhObject) that
+/// contains the group must be [refitted](crate::object::bvh::BvhObject::refit) afterwards
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct GroupObject<Obj: Object> {
+    #[get = "pub"]
+    children: ObjectList<Obj>,
+    #[get = "pub"]
+    transform: ObjectTransform,
+    /// The point that the transform is corrected around (see [ObjectTransform::new_corrected()])
+    #[get_copy = "pub"]
+    centre: Point3,
+    aabb: Option<Aabb>,
+}
+
+// region Constructors
+
+impl<Obj: Object> GroupObject<Obj> {
+    /// Creates a new group, where the transform is corrected to be around the centre of the children's bounds (see
+    /// [ObjectTransform::new_corrected()]). Unbounded groups are corrected around the origin
+    pub fn new(children: impl IntoIterator<Item = Obj>, transform: Transform3) -> Self {
+        let children = ObjectList::new_uncorrected(children, None);
+        let centre = children
+            .aabb()
+            .map_or(Point3::ZERO, |aabb| aabb.min() + (aabb.size() / 2.));
+        Self::new_around(children, transform, centre)
+    }
+
+    /// Creates a new group, without correcting the transform. See [Self::new()]
+    pub fn new_uncorrected(children: impl IntoIterator<Item = Obj>, transform: Transform3) -> Self {
+        Self::new_around(ObjectList::new_uncorrected(children, None), transform, Point3::ZERO)
+    }
+
+    fn new_around(children: ObjectList<Obj>, transform: Transform3, centre: Point3) -> Self {
+        let mut group = Self {
+            children,
+            transform: ObjectTransform::IDENTITY,
+            centre,
+            aabb: None,
+        };
+        group.set_transform(transform);
+        group
+    }
+
+    /// Changes the parent transform of the group, which is corrected around the [centre](Self::centre) the same way as
+    /// when the group was created
+    pub fn set_transform(&mut self, transform: Transform3) {
+        self.transform = ObjectTransform::new_corrected(transform, self.centre);
+        self.aabb = self.transform.calculate_aabb(self.children.aabb());
+    }
+
+    /// See [Self::set_transform()]
+    pub fn with_transform(mut self, transform: Transform3) -> Self {
+        self.set_transform(transform);
+        self
+    }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Obj: Object> Object for GroupObject<Obj> {
+    type Mesh = Obj::Mesh;
+    type Mat = Obj::Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Self::Mat>> {
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        let mut inner = self.children.full_intersect(&trans_ray, interval, rng)?;
+        inner.intersection = self.transform.outgoing_intersection(orig_ray, inner.intersection);
+        Some(inner)
+    }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Self::Mat>; 4]> {
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        let mut hits = self.children.full_intersect_all(&trans_ray, interval, rng);
+        for hit in &mut hits {
+            hit.intersection = self.transform.outgoing_intersection(orig_ray, hit.intersection);
+        }
+        hits
+    }
+
+    fn prepare(&mut self) { self.children.prepare() }
+
+    fn set_time(&mut self, time: Number) { self.children.set_time(time) }
+
+    fn set_shutter(&mut self, shutter: Shutter) { self.children.set_shutter(shutter) }
+
+    fn set_lod_quality(&mut self, quality: Number) { self.children.set_lod_quality(quality) }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Self::Mat>>) {
+        let start = lights.len();
+        self.children.collect_lights(lights);
+        transform_lights(&mut lights[start..], &self.transform);
+    }
+}
+
+impl<Obj: Object> HasAabb for GroupObject<Obj> {
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+// endregion Object Impl
@@ -0,0 +1,245 @@
+use crate::core::types::Number;
+use crate::material::Material;
+use crate::mesh::Mesh as MeshTrait;
+use crate::object::light::SceneLight;
+use crate::object::transform::ObjectTransform;
+use crate::object::Object;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::bvh_cost;
+use crate::shared::generic_bvh::{GenericBvh, GenericBvhNode};
+use crate::shared::intersect::FullIntersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use crate::shared::validate;
+use getset::Getters;
+use indextree::{Arena, NodeId};
+use rand_core::RngCore;
+use std::sync::Arc;
+
+/// A single copy of the mesh in an [InstancedObject]
+#[derive(Clone, Debug)]
+pub struct Instance<Mat: Material> {
+    pub transform: ObjectTransform,
+    /// The material to use for this instance, instead of the object's default material
+    pub material: Option<Mat>,
+}
+
+impl<Mat: Material> Instance<Mat> {
+    pub fn new(transform: impl Into<ObjectTransform>) -> Self {
+        Self {
+            transform: transform.into(),
+            material: None,
+        }
+    }
+
+    /// Overrides the material for this instance
+    pub fn with_material(self, material: impl Into<Mat>) -> Self {
+        Self {
+            material: Some(material.into()),
+            ..self
+        }
+    }
+}
+
+/// An object that draws the same mesh many times, each with a different transform (and optionally material).
+///
+/// The mesh is only stored once (and can be shared with other objects, since it's in an [Arc]), and so is any
+/// acceleration structure inside it (e.g. a [BvhMesh](crate::mesh::advanced::bvh::BvhMesh)). Rays are transformed
+/// into the space of each instance they might hit, which is found using a BVH over the instances.
+///
+/// This is much cheaper than making a [SimpleObject](super::simple::SimpleObject) for each copy, both in memory
+/// and in building the BVH, for scenes with lots of repeated geometry (forests, crowds, grids of spheres, etc).
+///
+/// # Note
+/// The mesh must be bounded (i.e. [`HasAabb::aabb()`] returns [`Some(_)`])
+#[derive(Getters, Clone, Debug)]
+#[get = "pub"]
+pub struct InstancedObject<Mesh: MeshTrait, Mat: Material> {
+    mesh: Arc<Mesh>,
+    /// The material used by instances that don't override it
+    material: Mat,
+    #[get(skip)]
+    instances: GenericBvh<InstanceNode<Mat>>,
+    #[get(skip)]
+    aabb: Option<Aabb>,
+}
+
+/// An [Instance] in the BVH, with its bounds
+#[derive(Clone, Debug)]
+struct InstanceNode<Mat: Material> {
+    instance: Instance<Mat>,
+    aabb: Option<Aabb>,
+}
+
+impl<Mat: Material> HasAabb for InstanceNode<Mat> {
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+// region Constructors
+
+impl<Mesh, Mat> InstancedObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    /// Creates a new instanced object, where each instance's transform is corrected to be around the centre of the
+    /// mesh. See [super::simple::SimpleObject::new()]
+    ///
+    /// # Panics
+    /// See [Self::new_uncorrected()]
+    pub fn new(
+        mesh: impl Into<Arc<Mesh>>,
+        material: impl Into<Mat>,
+        instances: impl IntoIterator<Item = Instance<Mat>>,
+    ) -> Self {
+        let mesh = mesh.into();
+        let centre = mesh.centre();
+        let instances = instances.into_iter().map(|instance| Instance {
+            transform: instance.transform.with_correction(centre),
+            ..instance
+        });
+        Self::new_uncorrected(mesh, material, instances)
+    }
+
+    /// Creates a new instanced object, without correcting the transforms of the instances.
+    /// See [super::simple::SimpleObject::new_uncorrected()]
+    ///
+    /// # Panics
+    /// Panics if the mesh is unbounded
+    pub fn new_uncorrected(
+        mesh: impl Into<Arc<Mesh>>,
+        material: impl Into<Mat>,
+        instances: impl IntoIterator<Item = Instance<Mat>>,
+    ) -> Self {
+        let (mesh, material) = (mesh.into(), material.into());
+        assert!(mesh.aabb().is_some(), "instanced meshes must be bounded");
+
+        let nodes = instances.into_iter().map(|instance| InstanceNode {
+            aabb: instance.transform.calculate_aabb(mesh.aabb()),
+            instance,
+        });
+        let instances = GenericBvh::new(nodes);
+        let aabb = instances.root_id().map(|root| match instances.arena()[root].get() {
+            GenericBvhNode::Nested(aabb) => *aabb,
+            GenericBvhNode::Object(o) => *o.expect_aabb(),
+        });
+
+        Self {
+            mesh,
+            material,
+            instances,
+            aabb,
+        }
+    }
+
+    /// How many instances there are
+    pub fn count(&self) -> usize {
+        self.instances
+            .arena()
+            .iter()
+            .filter(|node| matches!(node.get(), GenericBvhNode::Object(_)))
+            .count()
+    }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Mesh, Mat> InstancedObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    /// Given a [NodeId] on the [Arena] tree, calculates the nearest intersection for the given `ray` and `interval`.
+    ///
+    /// Same as [BvhObject](super::bvh::BvhObject), except that the leaves are instances of our mesh
+    fn bvh_node_intersect<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        node: NodeId,
+        arena: &'o Arena<GenericBvhNode<InstanceNode<Mat>>>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Mat>> {
+        match arena.get(node).expect("node should exist in arena").get() {
+            GenericBvhNode::Nested(aabb) => {
+                bvh_cost::visit_node();
+                if !aabb.hit(ray, interval) {
+                    return None;
+                }
+
+                let mut shrunk_interval = *interval;
+                let mut closest_intersect = None;
+                for child in node.children(arena) {
+                    let Some(intersect) = self.bvh_node_intersect(ray, &shrunk_interval, child, arena, rng) else {
+                        continue;
+                    };
+
+                    validate::intersection(ray, &intersect.intersection, &shrunk_interval);
+                    shrunk_interval = shrunk_interval.with_some_end(intersect.intersection.dist);
+                    closest_intersect = Some(intersect)
+                }
+
+                closest_intersect
+            }
+            GenericBvhNode::Object(InstanceNode { instance, aabb }) => {
+                bvh_cost::visit_node();
+                if !aabb.as_ref().is_some_and(|aabb| aabb.hit(ray, interval)) {
+                    return None;
+                }
+                bvh_cost::test_primitive();
+
+                let trans_ray = instance.transform.incoming_ray(ray);
+                let inner = self.mesh.intersect(&trans_ray, interval, rng)?;
+                let intersection = instance.transform.outgoing_intersection(ray, inner);
+                Some(intersection.make_full(instance.material.as_ref().unwrap_or(&self.material)))
+            }
+        }
+    }
+}
+
+impl<Mesh, Mat> Object for InstancedObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    type Mesh = Mesh;
+    type Mat = Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Mat>> {
+        self.bvh_node_intersect(ray, interval, self.instances.root_id()?, self.instances.arena(), rng)
+    }
+
+    /// Each instance with a [light](Material::is_light()) material is a light, if the mesh has a
+    /// [light shape](MeshTrait::light_shape())
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Mat>>) {
+        let Some(shape) = self.mesh.light_shape() else {
+            return;
+        };
+        for InstanceNode { instance, .. } in self.instances.objects() {
+            let material = instance.material.as_ref().unwrap_or(&self.material);
+            if material.is_light() {
+                lights.push(SceneLight {
+                    shape: shape.transformed(&instance.transform),
+                    material,
+                });
+            }
+        }
+    }
+}
+
+impl<Mesh, Mat> HasAabb for InstancedObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+// endregion Object Impl
@@ -0,0 +1,265 @@
+use crate::core::types::{Number, Point3, Vector3};
+use crate::material::Material;
+use crate::mesh::planar::disk::DiskMesh;
+use crate::mesh::planar::parallelogram::ParallelogramMesh;
+use crate::mesh::planar::Planar;
+use crate::mesh::primitive::sphere::SphereMesh;
+use crate::mesh::{Mesh, MeshProperties};
+use crate::object::transform::ObjectTransform;
+use crate::object::Object;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::{FullIntersection, Intersection};
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use crate::shared::rng;
+use getset::Getters;
+use glamour::AngleConsts;
+use rand_core::RngCore;
+use smallvec::SmallVec;
+
+/// The shape of a [LightObject]. Only simple shapes are supported, since they need to be sampled uniformly by area
+#[derive(Copy, Clone, Debug)]
+pub enum LightShape {
+    Parallelogram(ParallelogramMesh),
+    Sphere(SphereMesh),
+    Disk(DiskMesh),
+}
+
+/// A point on the surface of a light, see [LightShape::sample_surface()]
+#[derive(Copy, Clone, Debug)]
+pub struct LightSample {
+    pub pos: Point3,
+    /// The outwards normal of the surface at the point
+    pub normal: Vector3,
+}
+
+impl LightShape {
+    /// The surface area of the light
+    pub fn area(&self) -> Number {
+        match self {
+            Self::Parallelogram(mesh) => Vector3::cross(mesh.plane().u(), mesh.plane().v()).length(),
+            Self::Sphere(mesh) => 4. * Number::PI * mesh.radius() * mesh.radius(),
+            Self::Disk(mesh) => {
+                Number::PI * ((mesh.radius() * mesh.radius()) - (mesh.inner_radius() * mesh.inner_radius()))
+            }
+        }
+    }
+
+    /// The probability density (per unit area) of [Self::sample_surface()] choosing any given point, which is the
+    /// same everywhere on the surface
+    pub fn pdf_area(&self) -> Number { 1. / self.area() }
+
+    /// Chooses a random point on the surface of the light, uniformly by area
+    pub fn sample_surface(&self, rng: &mut dyn RngCore) -> LightSample {
+        match self {
+            Self::Parallelogram(mesh) => {
+                let plane = mesh.plane();
+                let (a, b) = (rng::number_in_unit_line_01(rng), rng::number_in_unit_line_01(rng));
+                LightSample {
+                    pos: plane.p() + (plane.u() * a) + (plane.v() * b),
+                    normal: plane.n(),
+                }
+            }
+            Self::Sphere(mesh) => {
+                let normal = rng::normal_on_unit_sphere(rng);
+                LightSample {
+                    pos: mesh.pos() + (normal * mesh.radius()),
+                    normal,
+                }
+            }
+            Self::Disk(mesh) => {
+                // Square root, so the points aren't bunched up in the middle
+                let (inner, outer) = (mesh.inner_radius() * mesh.inner_radius(), mesh.radius() * mesh.radius());
+                let radius = (inner + ((outer - inner) * rng::number_in_unit_line_01(rng))).sqrt();
+                let dir = rng::normal_on_unit_circle(rng) * radius;
+                let plane = mesh.plane();
+                LightSample {
+                    pos: plane.p() + (plane.u() * dir.x) + (plane.v() * dir.y),
+                    normal: plane.n(),
+                }
+            }
+        }
+    }
+
+    /// Moves the light into the space of the given transform (e.g. from an object's space to world-space).
+    ///
+    /// Spheres and disks can only be scaled evenly, so if the transform stretches them, they are scaled by the average
+    /// amount instead. For moving transforms, the light is placed where it is at the start of the shutter interval
+    pub fn transformed(&self, transform: &ObjectTransform) -> Self {
+        if *transform.is_identity() {
+            return *self;
+        }
+        let transform = transform.transform();
+
+        match self {
+            Self::Parallelogram(mesh) => {
+                let plane = mesh.plane();
+                Self::Parallelogram(ParallelogramMesh::new(Planar::new(
+                    transform.map_point(plane.p()),
+                    transform.map_vector(plane.u()),
+                    transform.map_vector(plane.v()),
+                )))
+            }
+            Self::Sphere(mesh) => {
+                let [x, y, z] = [Vector3::X, Vector3::Y, Vector3::Z].map(|axis| transform.map_vector(axis));
+                let scale = Vector3::dot(x, Vector3::cross(y, z)).abs().cbrt();
+                Self::Sphere(SphereMesh::new(transform.map_point(mesh.pos()), mesh.radius() * scale))
+            }
+            Self::Disk(mesh) => {
+                let plane = mesh.plane();
+                // `u` and `v` are normalised, so the length of the normal is how much the area is scaled by
+                let normal = Vector3::cross(transform.map_vector(plane.u()), transform.map_vector(plane.v()));
+                let scale = normal.length().sqrt();
+                Self::Disk(DiskMesh::new_annulus(
+                    transform.map_point(plane.p()),
+                    normal,
+                    mesh.radius() * scale,
+                    mesh.inner_radius() * scale,
+                ))
+            }
+        }
+    }
+}
+
+impl From<ParallelogramMesh> for LightShape {
+    fn from(value: ParallelogramMesh) -> Self { Self::Parallelogram(value) }
+}
+impl From<SphereMesh> for LightShape {
+    fn from(value: SphereMesh) -> Self { Self::Sphere(value) }
+}
+impl From<DiskMesh> for LightShape {
+    fn from(value: DiskMesh) -> Self { Self::Disk(value) }
+}
+
+// region Mesh Impl
+
+impl Mesh for LightShape {
+    fn intersect(&self, ray: &Ray, interval: &Interval<Number>, rng: &mut dyn RngCore) -> Option<Intersection> {
+        match self {
+            Self::Parallelogram(mesh) => mesh.intersect(ray, interval, rng),
+            Self::Sphere(mesh) => mesh.intersect(ray, interval, rng),
+            Self::Disk(mesh) => mesh.intersect(ray, interval, rng),
+        }
+    }
+}
+
+impl HasAabb for LightShape {
+    fn aabb(&self) -> Option<&Aabb> {
+        match self {
+            Self::Parallelogram(mesh) => mesh.aabb(),
+            Self::Sphere(mesh) => mesh.aabb(),
+            Self::Disk(mesh) => mesh.aabb(),
+        }
+    }
+}
+
+impl MeshProperties for LightShape {
+    fn centre(&self) -> Point3 {
+        match self {
+            Self::Parallelogram(mesh) => mesh.centre(),
+            Self::Sphere(mesh) => mesh.centre(),
+            Self::Disk(mesh) => mesh.centre(),
+        }
+    }
+}
+
+// endregion Mesh Impl
+
+/// An area light, which can be sampled directly for direct illumination (instead of only being found by rays that
+/// happen to hit it).
+///
+/// The light is drawn like any other object, and emits light through its material, which should be a
+/// [LightMaterial](crate::material::light::LightMaterial). All the lights in a scene are found with
+/// [Scene::lights()](crate::scene::Scene::lights).
+///
+/// The shape is already in world-space, so there is no transform; use [LightShape::transformed()] to move it.
+#[derive(Getters, Clone, Debug)]
+#[get = "pub"]
+pub struct LightObject<Mat: Material> {
+    shape: LightShape,
+    material: Mat,
+}
+
+/// A light in the scene, with its shape in world-space. See [Object::collect_lights()]
+#[derive(Debug)]
+pub struct SceneLight<'o, Mat: Material> {
+    pub shape: LightShape,
+    /// The material the light emits through
+    pub material: &'o Mat,
+}
+
+// Can't derive these since they would require `Mat: Clone`
+impl<Mat: Material> Clone for SceneLight<'_, Mat> {
+    fn clone(&self) -> Self { *self }
+}
+impl<Mat: Material> Copy for SceneLight<'_, Mat> {}
+
+// region Constructors
+
+impl<Mat: Material> LightObject<Mat> {
+    pub fn new(shape: impl Into<LightShape>, material: impl Into<Mat>) -> Self {
+        Self {
+            shape: shape.into(),
+            material: material.into(),
+        }
+    }
+
+    /// See [LightShape::sample_surface()]
+    pub fn sample_surface(&self, rng: &mut dyn RngCore) -> LightSample { self.shape.sample_surface(rng) }
+
+    /// See [LightShape::pdf_area()]
+    pub fn pdf_area(&self) -> Number { self.shape.pdf_area() }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Mat: Material> Object for LightObject<Mat> {
+    type Mesh = LightShape;
+    type Mat = Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Mat>> {
+        let intersect = self.shape.intersect(ray, interval, rng)?;
+        Some(intersect.make_full(&self.material))
+    }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Mat>; 4]> {
+        self.shape
+            .intersect_all(ray, interval, rng)
+            .iter()
+            .map(|&intersect| intersect.make_full(&self.material))
+            .collect()
+    }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Mat>>) {
+        lights.push(SceneLight {
+            shape: self.shape,
+            material: &self.material,
+        });
+    }
+}
+
+impl<Mat: Material> HasAabb for LightObject<Mat> {
+    fn aabb(&self) -> Option<&Aabb> { self.shape.aabb() }
+}
+
+// endregion Object Impl
+
+/// Moves the lights that were collected by an object with the given transform into world-space. Used when forwarding
+/// [Object::collect_lights()]
+pub(crate) fn transform_lights<Mat: Material>(lights: &mut [SceneLight<'_, Mat>], transform: &ObjectTransform) {
+    lights
+        .iter_mut()
+        .for_each(|light| light.shape = light.shape.transformed(transform));
+}
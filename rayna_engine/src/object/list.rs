@@ -1,16 +1,21 @@
 use getset::Getters;
 use rand_core::RngCore;
+use rayon::prelude::*;
 
 use super::transform::ObjectTransform;
 use crate::core::types::{Number, Point3};
 use crate::material::Material;
 use crate::mesh;
 use crate::object::bvh::BvhObject;
+use crate::object::light::{transform_lights, SceneLight};
 use crate::object::{Object, ObjectInstance};
+use crate::scene::camera::Shutter;
 use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::FullIntersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
+use std::simd::{LaneCount, SupportedLaneCount};
 
 #[derive(Getters, Clone, Debug)]
 #[get = "pub"]
@@ -127,6 +132,67 @@ impl<Obj: Object> Object for ObjectList<Obj> {
         intersect.intersection = self.transform.outgoing_intersection(orig_ray, intersect.intersection);
         Some(intersect)
     }
+
+    fn full_intersect_packet<'o, const L: usize>(
+        &'o self,
+        orig_packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<FullIntersection<'o, Obj::Mat>>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let trans_packet = orig_packet.map(|ray| self.transform.incoming_ray(ray));
+
+        let mut hits = self.bvh.full_intersect_packet(&trans_packet, intervals, active, rng);
+        for obj in &self.unbounded {
+            let obj_hits = obj.full_intersect_packet(&trans_packet, intervals, active, rng);
+            for (hit, obj_hit) in std::iter::zip(&mut hits, obj_hits) {
+                *hit = match (hit.take(), obj_hit) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+            }
+        }
+
+        let mut hits = hits.into_iter();
+        orig_packet.rays().each_ref().map(|orig_ray| {
+            let mut intersect = hits.next().expect("one hit per ray")?;
+            intersect.intersection = self.transform.outgoing_intersection(orig_ray, intersect.intersection);
+            Some(intersect)
+        })
+    }
+
+    fn prepare(&mut self) {
+        let (bvh, unbounded) = (&mut self.bvh, &mut self.unbounded);
+        rayon::join(
+            || bvh.prepare(),
+            || unbounded.par_iter_mut().for_each(|obj| obj.prepare()),
+        );
+    }
+
+    fn set_time(&mut self, time: Number) {
+        self.bvh.set_time(time);
+        self.unbounded.iter_mut().for_each(|obj| obj.set_time(time));
+    }
+
+    fn set_shutter(&mut self, shutter: Shutter) {
+        self.bvh.set_shutter(shutter);
+        self.unbounded.iter_mut().for_each(|obj| obj.set_shutter(shutter));
+    }
+
+    fn set_lod_quality(&mut self, quality: Number) {
+        self.bvh.set_lod_quality(quality);
+        self.unbounded.iter_mut().for_each(|obj| obj.set_lod_quality(quality));
+    }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Obj::Mat>>) {
+        let start = lights.len();
+        self.bvh.collect_lights(lights);
+        self.unbounded.iter().for_each(|obj| obj.collect_lights(lights));
+        transform_lights(&mut lights[start..], &self.transform);
+    }
 }
 impl<Obj: Object> HasAabb for ObjectList<Obj> {
     fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
@@ -0,0 +1,200 @@
+use crate::core::types::{Number, Point3};
+use crate::material::Material;
+use crate::mesh::Mesh as MeshTrait;
+use crate::object::light::SceneLight;
+use crate::object::transform::ObjectTransform;
+use crate::object::Object;
+use crate::shared::aabb::{Aabb, HasAabb};
+use crate::shared::intersect::FullIntersection;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+use smallvec::SmallVec;
+
+/// An object that switches between several versions of a mesh (**levels of detail**), depending on how far away it
+/// is from the origin of each ray.
+///
+/// Distant objects cover only a few pixels, so a much simpler mesh looks the same but is far cheaper to intersect
+/// (e.g. a mesh [simplified](crate::mesh::ops::simplify()) to a fraction of its triangles).
+///
+/// # Levels
+/// Each level has a distance from which it is used, and the level with the largest distance that is still closer than
+/// the ray origin is chosen. The [quality](Self::quality) scales the distances, and can be set for the whole scene
+/// with [Scene::set_lod_quality()](crate::scene::Scene::set_lod_quality).
+///
+/// All the levels should be roughly the same shape and position, and they share one material and transform.
+#[derive(Getters, CopyGetters, Clone, Debug)]
+pub struct LodObject<Mesh: MeshTrait, Mat: Material> {
+    /// The `(distance, mesh)` for each level, sorted by distance
+    #[get = "pub"]
+    levels: Vec<(Number, Mesh)>,
+    #[get = "pub"]
+    material: Mat,
+    #[get = "pub"]
+    transform: ObjectTransform,
+    /// How far the detailed levels are used out to; `2.0` would use each level until twice as far away.
+    /// Defaults to `1.0`
+    #[get_copy = "pub"]
+    quality: Number,
+    /// The centre of the object in world-space, which the distance to the ray is measured from
+    #[get_copy = "pub"]
+    centre: Point3,
+    aabb: Option<Aabb>,
+}
+
+// region Constructors
+
+impl<Mesh, Mat> LodObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    /// Creates a new object from the `(distance, mesh)` levels, which don't need to be in order.
+    /// The transform is corrected around the centre of the most detailed mesh (see
+    /// [super::simple::SimpleObject::new()])
+    ///
+    /// # Panics
+    /// Panics if there are no levels, or any of the distances are negative or NaN
+    pub fn new(
+        levels: impl IntoIterator<Item = (Number, impl Into<Mesh>)>,
+        material: impl Into<Mat>,
+        transform: impl Into<ObjectTransform>,
+    ) -> Self {
+        let levels = Self::sort_levels(levels);
+        let transform = transform.into().with_correction(levels[0].1.centre());
+        Self::new_from_sorted(levels, material.into(), transform)
+    }
+
+    /// Creates a new object, without correcting the transform. See [Self::new()]
+    ///
+    /// # Panics
+    /// See [Self::new()]
+    pub fn new_uncorrected(
+        levels: impl IntoIterator<Item = (Number, impl Into<Mesh>)>,
+        material: impl Into<Mat>,
+        transform: impl Into<ObjectTransform>,
+    ) -> Self {
+        Self::new_from_sorted(Self::sort_levels(levels), material.into(), transform.into())
+    }
+
+    fn sort_levels(levels: impl IntoIterator<Item = (Number, impl Into<Mesh>)>) -> Vec<(Number, Mesh)> {
+        let mut levels = levels
+            .into_iter()
+            .map(|(dist, mesh)| {
+                assert!(dist >= 0., "level distance must be non-negative (was {dist})");
+                (dist, mesh.into())
+            })
+            .collect::<Vec<_>>();
+        assert!(!levels.is_empty(), "must have at least one level of detail");
+        levels.sort_by(|(a, _), (b, _)| Number::total_cmp(a, b));
+        levels
+    }
+
+    fn new_from_sorted(levels: Vec<(Number, Mesh)>, material: Mat, transform: ObjectTransform) -> Self {
+        // Any of the levels could be hit, so the bounds need to cover all of them
+        let aabb = levels
+            .iter()
+            .map(|(_, mesh)| transform.calculate_aabb(mesh.aabb()))
+            .reduce(|a, b| Option::zip(a, b).map(|(a, b)| Aabb::encompass(a, b)))
+            .flatten();
+        let centre = transform.transform().map_point(levels[0].1.centre());
+
+        Self {
+            levels,
+            material,
+            transform,
+            quality: 1.,
+            centre,
+            aabb,
+        }
+    }
+
+    /// Sets the [quality](Self::quality) of this object
+    ///
+    /// # Panics
+    /// Panics if the quality isn't positive
+    pub fn with_quality(mut self, quality: Number) -> Self {
+        self.set_lod_quality(quality);
+        self
+    }
+
+    /// Chooses which level to use, for a ray starting at the given (world-space) position
+    pub fn level_for(&self, ray_pos: Point3) -> usize {
+        let dist = (ray_pos - self.centre).length() / self.quality;
+        // The first level is used even if the ray is closer than its distance
+        self.levels
+            .partition_point(|(start, _)| *start <= dist)
+            .saturating_sub(1)
+    }
+}
+
+// endregion Constructors
+
+// region Object Impl
+
+impl<Mesh, Mat> Object for LodObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    type Mesh = Mesh;
+    type Mat = Mat;
+
+    fn full_intersect<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> Option<FullIntersection<'o, Mat>> {
+        let (_, mesh) = &self.levels[self.level_for(orig_ray.pos())];
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        let inner = mesh.intersect(&trans_ray, interval, rng)?;
+        let intersect = self.transform.outgoing_intersection(orig_ray, inner);
+        Some(intersect.make_full(&self.material))
+    }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Mat>; 4]> {
+        let (_, mesh) = &self.levels[self.level_for(orig_ray.pos())];
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        mesh.intersect_all(&trans_ray, interval, rng)
+            .iter()
+            .map(|&inner| {
+                self.transform
+                    .outgoing_intersection(orig_ray, inner)
+                    .make_full(&self.material)
+            })
+            .collect()
+    }
+
+    fn set_lod_quality(&mut self, quality: Number) {
+        assert!(quality > 0., "quality must be positive (was {quality})");
+        self.quality = quality;
+    }
+
+    /// Lights use the most detailed level, since they are seen from everywhere in the scene
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Mat>>) {
+        let (_, mesh) = &self.levels[0];
+        if let Some(shape) = mesh.light_shape().filter(|_| self.material.is_light()) {
+            lights.push(SceneLight {
+                shape: shape.transformed(&self.transform),
+                material: &self.material,
+            });
+        }
+    }
+}
+
+impl<Mesh, Mat> HasAabb for LodObject<Mesh, Mat>
+where
+    Mesh: MeshTrait,
+    Mat: Material,
+{
+    fn aabb(&self) -> Option<&Aabb> { self.aabb.as_ref() }
+}
+
+// endregion Object Impl
@@ -1,22 +1,45 @@
+pub mod animated;
 pub mod bvh;
+pub mod clipped;
+pub mod csg;
+pub mod group;
+pub mod instanced;
+pub mod light;
 pub mod list;
+pub mod lod;
 pub mod simple;
 pub mod transform;
 pub mod volumetric;
 
 use crate::core::types::Number;
 use crate::material::Material;
-use crate::mesh::Mesh as MeshTrait;
+use crate::mesh::{Mesh as MeshTrait, MAX_INTERSECTIONS};
+use crate::scene::camera::Shutter;
 use crate::shared::aabb::Aabb;
 use crate::shared::aabb::HasAabb;
 use crate::shared::intersect::FullIntersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
 use crate::shared::RtRequirement;
 use rand_core::RngCore;
+use smallvec::SmallVec;
+use std::simd::{LaneCount, SupportedLaneCount};
 
 // noinspection ALL
-use self::{bvh::BvhObject, list::ObjectList, simple::SimpleObject, volumetric::VolumetricObject};
+use self::{
+    animated::AnimatedObject,
+    bvh::BvhObject,
+    clipped::ClippedObject,
+    csg::CsgObject,
+    group::GroupObject,
+    instanced::InstancedObject,
+    light::{LightObject, SceneLight},
+    list::ObjectList,
+    lod::LodObject,
+    simple::SimpleObject,
+    volumetric::VolumetricObject,
+};
 
 // TODO: Should objects (as well as other traits) have some sort of identifier?
 
@@ -39,6 +62,97 @@ pub trait Object: RtRequirement + HasAabb {
         interval: &Interval<Number>,
         rng: &mut dyn RngCore,
     ) -> Option<FullIntersection<'o, Self::Mat>>;
+
+    /// Finds all the intersections between the given ray and the object, within the given range.
+    ///
+    /// This is used where the inside of an object matters, such as for [CsgObject], so the object should be closed.
+    ///
+    /// # Return Value
+    /// The intersections, sorted by distance (nearest first). At most [MAX_INTERSECTIONS] are returned
+    ///
+    /// # Default Implementation
+    /// Repeatedly calls [Object::full_intersect()], like [MeshTrait::intersect_all()]
+    fn full_intersect_all<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Self::Mat>; 4]> {
+        let mut hits = SmallVec::new();
+        let mut interval = *interval;
+        while hits.len() < MAX_INTERSECTIONS {
+            let Some(hit) = self.full_intersect(ray, &interval, rng) else {
+                break;
+            };
+            // Nudge the start forwards, so we don't find the same intersection again
+            let dist = hit.intersection.dist;
+            interval.start = Some(dist + (dist.abs().max(1.) * 1e-9));
+            hits.push(hit);
+        }
+        hits
+    }
+
+    /// Intersects a whole [RayPacket] with the object at once, each ray with its own interval.
+    /// See [MeshTrait::intersect_packet()]
+    ///
+    /// # Return Value
+    /// The *first* intersection for each ray (as with [Object::full_intersect()]), or [None] if it missed.
+    /// Rays that aren't in the `active` mask are skipped, and always return [None]
+    ///
+    /// # Default Implementation
+    /// Calls [Object::full_intersect()] for each ray in turn. Only objects that can do better with SIMD (such as
+    /// [SimpleObject] and [BvhObject]) override this.
+    fn full_intersect_packet<'o, const L: usize>(
+        &'o self,
+        packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<FullIntersection<'o, Self::Mat>>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        std::array::from_fn(|i| match active.test(i) {
+            true => self.full_intersect(&packet.rays()[i], &intervals[i], rng),
+            false => None,
+        })
+    }
+
+    /// Does any expensive pre-processing the object needs, before it is rendered.
+    ///
+    /// This is called once by the renderer, before the first frame is rendered with the scene (see
+    /// [Scene::prepare()](crate::scene::Scene::prepare)), so that the cost isn't paid in the middle of a frame.
+    /// Objects that contain other objects should forward this to their children (in parallel if possible).
+    ///
+    /// This must not change the bounds of the object.
+    fn prepare(&mut self) {}
+
+    /// Sets the current time of the scene (e.g. the frame of an animation), see
+    /// [Scene::set_time()](crate::scene::Scene::set_time). This is used by [AnimatedObject].
+    ///
+    /// Objects that contain other objects should forward this to their children.
+    /// This must not change the bounds of the object.
+    fn set_time(&mut self, _time: Number) {}
+
+    /// Sets when the camera's shutter is open, relative to the [time](Self::set_time), see
+    /// [Scene::set_shutter()](crate::scene::Scene::set_shutter). This is used by [AnimatedObject], to blur the
+    /// animation over the shutter interval.
+    ///
+    /// Objects that contain other objects should forward this to their children.
+    /// This must not change the bounds of the object.
+    fn set_shutter(&mut self, _shutter: Shutter) {}
+
+    /// Sets the quality of any levels of detail, see [Scene::set_lod_quality()](crate::scene::Scene::set_lod_quality)
+    /// and [LodObject].
+    ///
+    /// Objects that contain other objects should forward this to their children.
+    fn set_lod_quality(&mut self, _quality: Number) {}
+
+    /// Adds all the lights in the object (see [LightObject]) to the list, in world-space, so that they can be sampled
+    /// directly. See [Scene::lights()](crate::scene::Scene::lights)
+    ///
+    /// Objects that contain other objects should forward this to their children, and transform the lights they add.
+    fn collect_lights<'o>(&'o self, _lights: &mut Vec<SceneLight<'o, Self::Mat>>) {}
 }
 
 // region Static dispatch
@@ -49,6 +163,13 @@ pub enum ObjectInstance<Mesh: MeshTrait + Clone, Mat: Material + Clone> {
     VolumetricObject(VolumetricObject<Mesh, Mat>),
     ObjectList(ObjectList<ObjectInstance<Mesh, Mat>>),
     Bvh(BvhObject<ObjectInstance<Mesh, Mat>>),
+    Instanced(InstancedObject<Mesh, Mat>),
+    Csg(CsgObject<ObjectInstance<Mesh, Mat>>),
+    Animated(AnimatedObject<ObjectInstance<Mesh, Mat>>),
+    Lod(LodObject<Mesh, Mat>),
+    Light(LightObject<Mat>),
+    Group(GroupObject<ObjectInstance<Mesh, Mat>>),
+    Clipped(ClippedObject<ObjectInstance<Mesh, Mat>>),
 }
 
 // `enum_dispatch` doesn't support associated type interval, so we have to do manual impl
@@ -67,6 +188,139 @@ impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> Object for ObjectInstance<M
             Self::SimpleObject(v) => v.full_intersect(ray, interval, rng),
             Self::VolumetricObject(v) => v.full_intersect(ray, interval, rng),
             Self::ObjectList(v) => v.full_intersect(ray, interval, rng),
+            Self::Instanced(v) => v.full_intersect(ray, interval, rng),
+            Self::Csg(v) => v.full_intersect(ray, interval, rng),
+            Self::Animated(v) => v.full_intersect(ray, interval, rng),
+            Self::Lod(v) => v.full_intersect(ray, interval, rng),
+            Self::Light(v) => v.full_intersect(ray, interval, rng),
+            Self::Group(v) => v.full_intersect(ray, interval, rng),
+            Self::Clipped(v) => v.full_intersect(ray, interval, rng),
+        }
+    }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Self::Mat>; 4]> {
+        match self {
+            Self::Bvh(v) => v.full_intersect_all(ray, interval, rng),
+            Self::SimpleObject(v) => v.full_intersect_all(ray, interval, rng),
+            Self::VolumetricObject(v) => v.full_intersect_all(ray, interval, rng),
+            Self::ObjectList(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Instanced(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Csg(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Animated(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Lod(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Light(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Group(v) => v.full_intersect_all(ray, interval, rng),
+            Self::Clipped(v) => v.full_intersect_all(ray, interval, rng),
+        }
+    }
+
+    fn full_intersect_packet<'o, const L: usize>(
+        &'o self,
+        packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<FullIntersection<'o, Self::Mat>>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        match self {
+            Self::Bvh(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::SimpleObject(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::VolumetricObject(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::ObjectList(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Instanced(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Csg(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Animated(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Lod(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Light(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Group(v) => v.full_intersect_packet(packet, intervals, active, rng),
+            Self::Clipped(v) => v.full_intersect_packet(packet, intervals, active, rng),
+        }
+    }
+
+    fn prepare(&mut self) {
+        match self {
+            Self::Bvh(v) => v.prepare(),
+            Self::SimpleObject(v) => v.prepare(),
+            Self::VolumetricObject(v) => v.prepare(),
+            Self::ObjectList(v) => v.prepare(),
+            Self::Instanced(v) => v.prepare(),
+            Self::Csg(v) => v.prepare(),
+            Self::Animated(v) => v.prepare(),
+            Self::Lod(v) => v.prepare(),
+            Self::Light(v) => v.prepare(),
+            Self::Group(v) => v.prepare(),
+            Self::Clipped(v) => v.prepare(),
+        }
+    }
+
+    fn set_time(&mut self, time: Number) {
+        match self {
+            Self::Bvh(v) => v.set_time(time),
+            Self::SimpleObject(v) => v.set_time(time),
+            Self::VolumetricObject(v) => v.set_time(time),
+            Self::ObjectList(v) => v.set_time(time),
+            Self::Instanced(v) => v.set_time(time),
+            Self::Csg(v) => v.set_time(time),
+            Self::Animated(v) => v.set_time(time),
+            Self::Lod(v) => v.set_time(time),
+            Self::Light(v) => v.set_time(time),
+            Self::Group(v) => v.set_time(time),
+            Self::Clipped(v) => v.set_time(time),
+        }
+    }
+
+    fn set_shutter(&mut self, shutter: Shutter) {
+        match self {
+            Self::Bvh(v) => v.set_shutter(shutter),
+            Self::SimpleObject(v) => v.set_shutter(shutter),
+            Self::VolumetricObject(v) => v.set_shutter(shutter),
+            Self::ObjectList(v) => v.set_shutter(shutter),
+            Self::Instanced(v) => v.set_shutter(shutter),
+            Self::Csg(v) => v.set_shutter(shutter),
+            Self::Animated(v) => v.set_shutter(shutter),
+            Self::Lod(v) => v.set_shutter(shutter),
+            Self::Light(v) => v.set_shutter(shutter),
+            Self::Group(v) => v.set_shutter(shutter),
+            Self::Clipped(v) => v.set_shutter(shutter),
+        }
+    }
+
+    fn set_lod_quality(&mut self, quality: Number) {
+        match self {
+            Self::Bvh(v) => v.set_lod_quality(quality),
+            Self::SimpleObject(v) => v.set_lod_quality(quality),
+            Self::VolumetricObject(v) => v.set_lod_quality(quality),
+            Self::ObjectList(v) => v.set_lod_quality(quality),
+            Self::Instanced(v) => v.set_lod_quality(quality),
+            Self::Csg(v) => v.set_lod_quality(quality),
+            Self::Animated(v) => v.set_lod_quality(quality),
+            Self::Lod(v) => v.set_lod_quality(quality),
+            Self::Light(v) => v.set_lod_quality(quality),
+            Self::Group(v) => v.set_lod_quality(quality),
+            Self::Clipped(v) => v.set_lod_quality(quality),
+        }
+    }
+
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Self::Mat>>) {
+        match self {
+            Self::Bvh(v) => v.collect_lights(lights),
+            Self::SimpleObject(v) => v.collect_lights(lights),
+            Self::VolumetricObject(v) => v.collect_lights(lights),
+            Self::ObjectList(v) => v.collect_lights(lights),
+            Self::Instanced(v) => v.collect_lights(lights),
+            Self::Csg(v) => v.collect_lights(lights),
+            Self::Animated(v) => v.collect_lights(lights),
+            Self::Lod(v) => v.collect_lights(lights),
+            Self::Light(v) => v.collect_lights(lights),
+            Self::Group(v) => v.collect_lights(lights),
+            Self::Clipped(v) => v.collect_lights(lights),
         }
     }
 }
@@ -78,6 +332,13 @@ impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> HasAabb for ObjectInstance<
             Self::SimpleObject(v) => v.aabb(),
             Self::VolumetricObject(v) => v.aabb(),
             Self::ObjectList(v) => v.aabb(),
+            Self::Instanced(v) => v.aabb(),
+            Self::Csg(v) => v.aabb(),
+            Self::Animated(v) => v.aabb(),
+            Self::Lod(v) => v.aabb(),
+            Self::Light(v) => v.aabb(),
+            Self::Group(v) => v.aabb(),
+            Self::Clipped(v) => v.aabb(),
         }
     }
 }
@@ -104,5 +365,34 @@ impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<BvhObject<ObjectInstan
 {
     fn from(value: BvhObject<ObjectInstance<Mesh, Mat>>) -> Self { Self::Bvh(value) }
 }
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<InstancedObject<Mesh, Mat>> for ObjectInstance<Mesh, Mat> {
+    fn from(value: InstancedObject<Mesh, Mat>) -> Self { Self::Instanced(value) }
+}
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<CsgObject<ObjectInstance<Mesh, Mat>>>
+    for ObjectInstance<Mesh, Mat>
+{
+    fn from(value: CsgObject<ObjectInstance<Mesh, Mat>>) -> Self { Self::Csg(value) }
+}
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<AnimatedObject<ObjectInstance<Mesh, Mat>>>
+    for ObjectInstance<Mesh, Mat>
+{
+    fn from(value: AnimatedObject<ObjectInstance<Mesh, Mat>>) -> Self { Self::Animated(value) }
+}
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<LodObject<Mesh, Mat>> for ObjectInstance<Mesh, Mat> {
+    fn from(value: LodObject<Mesh, Mat>) -> Self { Self::Lod(value) }
+}
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<LightObject<Mat>> for ObjectInstance<Mesh, Mat> {
+    fn from(value: LightObject<Mat>) -> Self { Self::Light(value) }
+}
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<GroupObject<ObjectInstance<Mesh, Mat>>>
+    for ObjectInstance<Mesh, Mat>
+{
+    fn from(value: GroupObject<ObjectInstance<Mesh, Mat>>) -> Self { Self::Group(value) }
+}
+impl<Mesh: MeshTrait + Clone, Mat: Material + Clone> From<ClippedObject<ObjectInstance<Mesh, Mat>>>
+    for ObjectInstance<Mesh, Mat>
+{
+    fn from(value: ClippedObject<ObjectInstance<Mesh, Mat>>) -> Self { Self::Clipped(value) }
+}
 
 // endregion impl From<_> for ObjectInstance
@@ -7,8 +7,11 @@ use crate::shared::aabb::{Aabb, HasAabb};
 use crate::shared::intersect::FullIntersection;
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{PacketMask, RayPacket};
 use getset::Getters;
 use rand_core::RngCore;
+use smallvec::SmallVec;
+use std::simd::{LaneCount, SupportedLaneCount};
 
 /// The main struct that encapsulates all the different "components" that make up an mesh
 ///
@@ -115,6 +118,42 @@ where
         let intersect = self.transform.outgoing_intersection(orig_ray, inner);
         Some(intersect.make_full(&self.material))
     }
+
+    fn full_intersect_all<'o>(
+        &'o self,
+        orig_ray: &Ray,
+        interval: &Interval<Number>,
+        rng: &mut dyn RngCore,
+    ) -> SmallVec<[FullIntersection<'o, Mat>; 4]> {
+        let trans_ray = self.transform.incoming_ray(orig_ray);
+        self.mesh
+            .intersect_all(&trans_ray, interval, rng)
+            .iter()
+            .map(|&inner| {
+                self.transform
+                    .outgoing_intersection(orig_ray, inner)
+                    .make_full(&self.material)
+            })
+            .collect()
+    }
+
+    fn full_intersect_packet<'o, const L: usize>(
+        &'o self,
+        orig_packet: &RayPacket<L>,
+        intervals: &[Interval<Number>; L],
+        active: PacketMask<L>,
+        rng: &mut dyn RngCore,
+    ) -> [Option<FullIntersection<'o, Mat>>; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let trans_packet = orig_packet.map(|ray| self.transform.incoming_ray(ray));
+        let inner = self.mesh.intersect_packet(&trans_packet, intervals, active, rng);
+        std::array::from_fn(|i| {
+            let intersect = self.transform.outgoing_intersection(&orig_packet.rays()[i], inner[i]?);
+            Some(intersect.make_full(&self.material))
+        })
+    }
 }
 
 impl<Mesh, Mat> HasAabb for SimpleObject<Mesh, Mat>
@@ -10,12 +10,19 @@
 //! ## Inverse Transform
 //! The matrix inverse of `transform`. This is the matrix corresponding to the transformation from
 //! mesh-space to world-space
+//!
+//! ## Motion
+//! A transform can also move over the shutter interval, for motion blur (see [ObjectTransform::new_moving()]).
+//! The start and end transforms are split into their scale, rotation and translation, which are interpolated
+//! separately (with the rotation being spherically interpolated), using the [time](Ray::time) of each ray.
 
-use crate::core::types::{Point3, Transform3, Vector3};
+use crate::core::types::{Matrix4, Number, Point3, Transform3, Vector3};
 use crate::shared::aabb::Aabb;
 use crate::shared::intersect::Intersection;
 use crate::shared::ray::Ray;
 use getset::Getters;
+use glam::{DMat4, DQuat, DVec3};
+use glamour::{FromRaw, ToRaw};
 
 /// A struct that holds both a [Transform3] and it's inverse.
 #[derive(Copy, Clone, Debug, Getters)]
@@ -29,9 +36,29 @@ pub struct ObjectTransform {
     /// World to object transform
     inv_transform: Transform3,
     /// Is this transform the identity transform?
+    ///
+    /// Moving transforms are never the identity
     is_identity: bool,
+    /// How the transform moves over the shutter interval, if at all
+    #[get(skip)]
+    motion: Option<Motion>,
+}
+
+/// The keyframes of a moving [ObjectTransform]
+#[derive(Copy, Clone, Debug)]
+struct Motion {
+    start: TransformParts,
+    end: TransformParts,
+    /// The centre that the transforms are corrected around, see [ObjectTransform::new_corrected()]
+    centre: Point3,
 }
 
+/// A transform, split into `(scale, rotation, translation)`, so that it can be interpolated
+pub(crate) type TransformParts = (DVec3, DQuat, DVec3);
+
+/// How many times a moving transform is sampled when calculating its bounds
+const MOTION_AABB_STEPS: usize = 8;
+
 // region Creating
 
 impl ObjectTransform {
@@ -39,6 +66,7 @@ impl ObjectTransform {
         transform: Transform3::IDENTITY,
         inv_transform: Transform3::IDENTITY,
         is_identity: true,
+        motion: None,
     };
 
     /// Creates a new (uncorrected) transform object
@@ -47,6 +75,7 @@ impl ObjectTransform {
             transform,
             inv_transform: transform.inverse(),
             is_identity: transform == Transform3::IDENTITY,
+            motion: None,
         }
     }
 
@@ -64,10 +93,66 @@ impl ObjectTransform {
         Self::new(correct_transform)
     }
 
+    /// Creates a new (uncorrected) transform, which moves from `start` to `end` over the shutter interval.
+    ///
+    /// The transforms should only contain scaling, rotation and translation (no shearing), otherwise they can't be
+    /// interpolated correctly
+    pub fn new_moving(start: Transform3, end: Transform3) -> Self {
+        Self::new_moving_corrected(start, end, Point3::ZERO)
+    }
+
+    /// Creates a new transform which moves from `start` to `end` over the shutter interval, accounting for the mesh's
+    /// translation from the origin. See [Self::new_corrected()] and [Self::new_moving()]
+    pub fn new_moving_corrected(start: Transform3, end: Transform3, obj_centre: impl Into<Point3>) -> Self {
+        let motion = Motion {
+            start: decompose(start),
+            end: decompose(end),
+            centre: obj_centre.into(),
+        };
+
+        Self {
+            is_identity: false,
+            motion: Some(motion),
+            ..Self::new_corrected(start, motion.centre)
+        }
+    }
+
     /// Applies transform correction to the `self` transform
     pub fn with_correction(&self, obj_centre: impl Into<Point3>) -> Self {
-        Self::new_corrected(self.transform, obj_centre)
+        match &self.motion {
+            None => Self::new_corrected(self.transform, obj_centre),
+            Some(motion) => Self::new_moving_corrected(motion.transform_at(0.), motion.transform_at(1.), obj_centre),
+        }
     }
+
+    /// Returns the (static) transform at the given time through the shutter interval. See [Ray::time]
+    ///
+    /// If the transform isn't moving, this is the same as `self`
+    pub fn at_time(&self, time: Number) -> Self {
+        match &self.motion {
+            None => *self,
+            Some(motion) => Self::new_corrected(motion.transform_at(time), motion.centre),
+        }
+    }
+
+    /// Is this transform moving over the shutter interval?
+    pub fn is_moving(&self) -> bool { self.motion.is_some() }
+}
+
+impl Motion {
+    /// Interpolates the (uncorrected) transform at the given time
+    fn transform_at(&self, time: Number) -> Transform3 { interpolate(self.start, self.end, time) }
+}
+
+/// Splits a transform into its parts. The transform should not contain any shearing
+pub(crate) fn decompose(transform: Transform3) -> TransformParts {
+    transform.matrix.to_raw().to_scale_rotation_translation()
+}
+
+/// Interpolates between two transforms, with the rotation being spherically interpolated
+pub(crate) fn interpolate((s0, r0, t0): TransformParts, (s1, r1, t1): TransformParts, t: Number) -> Transform3 {
+    let matrix = DMat4::from_scale_rotation_translation(s0.lerp(s1, t), r0.slerp(r1, t), t0.lerp(t1, t));
+    Transform3::from_matrix_unchecked(Matrix4::from_raw(matrix))
 }
 
 impl From<Transform3> for ObjectTransform {
@@ -93,9 +178,12 @@ impl ObjectTransform {
         if self.is_identity {
             return *incoming_ray;
         }
+        if self.motion.is_some() {
+            return self.at_time(incoming_ray.time()).incoming_ray(incoming_ray);
+        }
 
         let (pos, dir) = incoming_ray.into();
-        Ray::new(self.inv_transform.map_point(pos), self.inv_transform.map_vector(dir))
+        Ray::new(self.inv_transform.map_point(pos), self.inv_transform.map_vector(dir)).with_time(incoming_ray.time())
     }
 
     /// Transforms the outgoing intersection from mesh-space to world-space
@@ -103,6 +191,11 @@ impl ObjectTransform {
         if self.is_identity {
             return intersection;
         }
+        if self.motion.is_some() {
+            return self
+                .at_time(original_ray.time())
+                .outgoing_intersection(original_ray, intersection);
+        }
 
         // PANICS:
         // We use `.unwrap()` on the results of the transformations
@@ -120,6 +213,8 @@ impl ObjectTransform {
 
         normal(&mut intersection.normal);
         normal(&mut intersection.ray_normal);
+        // Tangents don't have to be normalised (and might be zero), so don't go through `normal()`
+        intersection.tangent = intersection.tangent.map(|t| self.transform.map_vector(t));
         point(&mut intersection.pos_l);
         point(&mut intersection.pos_w);
 
@@ -131,7 +226,17 @@ impl ObjectTransform {
     }
 
     /// Given a transform and (optional) AABB, calculates the new AABB given that transform
+    ///
+    /// For moving transforms, this encompasses the AABB over the whole shutter interval
     pub fn calculate_aabb(&self, aabb: Option<&Aabb>) -> Option<Aabb> {
+        if self.motion.is_some() {
+            let steps = (0..=MOTION_AABB_STEPS).map(|i| {
+                self.at_time(i as Number / MOTION_AABB_STEPS as Number)
+                    .calculate_aabb(aabb)
+            });
+            return steps.collect::<Option<Vec<_>>>().map(Aabb::encompass_iter);
+        }
+
         if self.is_identity {
             aabb.copied()
         } else {
@@ -1,6 +1,7 @@
-use crate::core::types::Number;
+use crate::core::types::{Channel, Number, Point2, Point3, Vector3};
 use crate::material::Material;
 use crate::mesh::Mesh as MeshTrait;
+use crate::object::light::SceneLight;
 use crate::object::transform::ObjectTransform;
 use crate::object::Object;
 use crate::shared::aabb::{Aabb, HasAabb};
@@ -8,13 +9,15 @@ use crate::shared::intersect::{FullIntersection, Intersection};
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
 use crate::shared::rng;
+use crate::texture::{Texture, TextureInstance};
 use getset::{CopyGetters, Getters};
 use rand::Rng;
 use rand_core::RngCore;
 
-/// An mesh wrapper that treats the wrapped mesh as a constant-density volume
+/// An mesh wrapper that treats the wrapped mesh as a volume
 ///
-/// The volume has the same shape as the wrapped `mesh`, and a constant density at all points in the volume
+/// The volume has the same shape as the wrapped `mesh`, and a constant density at all points in the volume, unless a
+/// [density texture](Self::with_density_texture()) is given (for clouds, smoke, etc).
 /// You are strongly recommended to use an instance of [`crate::material::isotropic::IsotropicMaterial`]
 #[derive(Getters, CopyGetters, Clone, Debug)]
 pub struct VolumetricObject<Mesh: MeshTrait, Mat: Material> {
@@ -28,6 +31,9 @@ pub struct VolumetricObject<Mesh: MeshTrait, Mat: Material> {
     density: Number,
     #[get_copy = "pub"]
     neg_inv_density: Number,
+    /// Scales the density at each point in the volume, see [Self::with_density_texture()]
+    #[get = "pub"]
+    density_texture: Option<TextureInstance>,
     aabb: Option<Aabb>,
 }
 
@@ -67,8 +73,43 @@ where
             transform,
             density,
             neg_inv_density: -1. / density,
+            density_texture: None,
         }
     }
+
+    /// Makes the density vary throughout the volume, by scaling it by the texture at each point (the average of its
+    /// channels, clamped to `0.0..=1.0`). The [density](Self::density) is then the densest that the volume can be.
+    ///
+    /// The texture is looked up at points inside the volume, so it should be a 3D texture, such as a
+    /// [WorldNoiseTexture](crate::texture::noise::WorldNoiseTexture) or a
+    /// [LocalNoiseTexture](crate::texture::noise::LocalNoiseTexture).
+    pub fn with_density_texture(mut self, texture: impl Into<TextureInstance>) -> Self {
+        self.density_texture = Some(texture.into());
+        self
+    }
+
+    /// Calculates the fraction of the maximum density at the given (mesh-space) point in the volume
+    fn density_fraction(&self, orig_ray: &Ray, pos_l: Point3, rng: &mut dyn RngCore) -> Number {
+        let Some(texture) = &self.density_texture else {
+            return 1.;
+        };
+        let pos_w = self.transform.at_time(orig_ray.time()).transform().map_point(pos_l);
+        let point = Intersection {
+            dist: (pos_w - orig_ray.pos()).length(),
+            pos_w,
+            pos_l,
+            // Only the position matters for the density
+            normal: Vector3::Z,
+            ray_normal: Vector3::Z,
+            uv: Point2::ZERO,
+            side: 0,
+            front_face: true,
+            colour: None,
+            tangent: None,
+        };
+        let value = texture.value(&point, rng);
+        (value.into_iter().sum::<Channel>() / 3.).clamp(0., 1.) as Number
+    }
 }
 
 // endregion Constructors
@@ -122,21 +163,38 @@ where
             }
         };
 
-        // Distance between entry and exit of mesh along ray
-        let dist_inside = exiting_dist - entering_dist;
-        // Random distance at which we will hit
-        let hit_dist = self.neg_inv_density * Number::ln(rng.gen());
-        // Actual distance along the ray of the volume intersection that we'll use
-        let dist = entering_dist + hit_dist;
-
         // NOTE: We don't do normal interval checks on intersections here, due to concavity issues given above.
-        // Also, even if `exiting_dist` is outside of the range, the value `hit_dist` might be inside
-        // And `hit_dist` is the one we actually use, so check that instead
+        // Also, even if `exiting_dist` is outside of the range, the distance we hit at might be inside
+        // And that distance is the one we actually use, so check that instead
         // We don't need to check `if !interval.contains(&dist)`, it's guaranteed to be inside `interval`
         // Since we clamped the entry/exit distances to the interval already
 
-        if hit_dist > dist_inside {
-            return None;
+        // Delta (Woodcock) tracking: take steps as if the volume had the maximum density everywhere, and at each step
+        // only hit with the chance of the actual density there. Otherwise, it's a 'null' collision and we keep going.
+        // For a constant density, every step hits so this is just one step
+        /*
+        CREDITS:
+
+        Title: "Techniques for Efficient Monte Carlo Simulation"
+        Authors:
+            - E. R. Woodcock
+            - T. Murphy
+            - P. J. Hemmings
+            - T. C. Longworth
+        Publisher: Proceedings of the Conference on the Application of Computing Methods to Reactor Problems
+        Version: 1965
+        */
+        let mut dist = entering_dist;
+        loop {
+            dist += self.neg_inv_density * Number::ln(rng.gen());
+            if dist > exiting_dist {
+                return None;
+            }
+            if self.density_texture.is_none()
+                || rng.gen::<Number>() < self.density_fraction(orig_ray, ray.at(dist), rng)
+            {
+                break;
+            }
         }
 
         let pos_w = ray.at(dist);
@@ -153,11 +211,24 @@ where
             uv: rng::vector_in_unit_square_01(rng).to_point(),
             side: 0,
             front_face: true,
+            colour: None,
+            tangent: None,
         };
 
         let intersect = self.transform.outgoing_intersection(orig_ray, inter);
         Some(intersect.make_full(&self.material))
     }
+
+    /// Glowing volumes are sampled as lights on their boundary, if the mesh has a
+    /// [light shape](MeshTrait::light_shape())
+    fn collect_lights<'o>(&'o self, lights: &mut Vec<SceneLight<'o, Mat>>) {
+        if let Some(shape) = self.mesh.light_shape().filter(|_| self.material.is_light()) {
+            lights.push(SceneLight {
+                shape: shape.transformed(&self.transform),
+                material: &self.material,
+            });
+        }
+    }
 }
 
 impl<Mesh: MeshTrait, Mat: Material> HasAabb for VolumetricObject<Mesh, Mat> {
@@ -1,15 +1,24 @@
+use derivative::Derivative;
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
 use std::ops::{Add, Div};
 
-use crate::core::{colour::ColourRgb, image::Image, types::Number};
+use crate::core::{
+    colour::ColourRgb,
+    image::Image,
+    types::{Channel, Number},
+};
 
 /// A wrapper around an [`Image`] that stores [`AccumulationValue`]s instead of pixels
 ///
 /// Has convenience methods for working with accumulated samples easier. Not all pixels
 /// need to be sampled evenly - sample counts can be unique per pixel.
-#[derive(Debug, Clone, Default)]
+#[derive(Derivative, Clone, Default)]
+#[derivative(Debug(bound = "C: Debug + 'static"))]
 pub struct AccumulationBuffer<C = ColourRgb> {
     inner: Option<Image<AccumulationValue<C>>>,
     counter: usize,
+    samples: usize,
 }
 
 /// Wrapper struct storing the accumulated colour value for a single pixel
@@ -43,14 +52,33 @@ impl<C: Add<Output = C> + Div<Number, Output = C> + Clone> AccumulationValue<C>
 
     /// Gets the overall accumulated colour value
     pub fn get(&self) -> C { self.mean.clone() }
+
+    /// Gets the total weight of the samples that have been accumulated
+    pub fn weight(&self) -> Number { self.accum }
+
+    /// Limits the total weight of the samples to `max`, keeping the same mean, so that new samples have more of an
+    /// effect
+    fn limit_weight(&self, max: Number) -> Self {
+        if self.accum <= max {
+            return self.clone();
+        }
+        Self {
+            sum: self.sum.clone() / (self.accum / max),
+            mean: self.mean.clone(),
+            accum: max,
+        }
+    }
 }
 
 impl<C: Default + Clone> AccumulationBuffer<C> {
     ///
     /// This ensures the given image exists and has correct dimensions. If the image dimensions changed
     /// then the image is cleared.
-    pub fn new_frame(&mut self, [w, h]: [usize; 2]) -> &mut Image<AccumulationValue<C>> {
+    ///
+    /// The `samples` are how many samples each pixel will be given in this frame
+    pub fn new_frame(&mut self, [w, h]: [usize; 2], samples: usize) -> &mut Image<AccumulationValue<C>> {
         self.counter += 1;
+        self.samples += samples;
         // Doesn't exist
         if self.inner.is_none() {
             return self.inner.insert(Image::new_blank(w, h));
@@ -68,6 +96,7 @@ impl<C: Default + Clone> AccumulationBuffer<C> {
     pub fn clear(&mut self) {
         self.inner.as_mut().map(|img| img.fill(AccumulationValue::default()));
         self.counter = 0;
+        self.samples = 0;
     }
 
     /// Returns the number of frames that make up this buffer.
@@ -75,4 +104,112 @@ impl<C: Default + Clone> AccumulationBuffer<C> {
     /// This is the number of times that [`Self::new_frame`] has been called, so it
     /// might be different to the per-pixel accumulation counters.
     pub fn frame_count(&self) -> usize { self.counter }
+
+    /// Returns the total number of samples per pixel that make up this buffer, over all the frames
+    pub fn sample_count(&self) -> usize { self.samples }
+
+    /// The accumulated value of each pixel, or [None] if nothing has been accumulated yet
+    pub fn values(&self) -> Option<&Image<AccumulationValue<C>>> { self.inner.as_ref() }
+}
+
+impl<C: Add<Output = C> + Div<Number, Output = C> + Default + Clone> AccumulationBuffer<C> {
+    /// Copies the accumulated value of each pixel into the `target` image, resizing it to match if needed (so that the
+    /// same image can be reused for every frame without allocating).
+    ///
+    /// Returns `false` (leaving the target as it was) if nothing has been accumulated yet
+    pub fn read_into(&self, target: &mut Image<C>) -> bool {
+        let Some(img) = &self.inner else {
+            return false;
+        };
+        target.ensure_size(img.width(), img.height());
+        target.zip_mut_with(img.data(), |px, value| *px = value.get());
+        true
+    }
+
+    /// Moves the accumulated pixels around, for [temporal reprojection](crate::render::reproject). Each pixel takes
+    /// the samples of the pixel that `source` returns for it (limited to `max_weight`), or starts again from nothing
+    /// if it returns [None].
+    ///
+    /// The frame and sample counters start again from zero, since the pixels no longer have the same samples
+    pub fn reproject(&mut self, source: impl Fn(usize, usize) -> Option<(usize, usize)>, max_weight: Number) {
+        self.counter = 0;
+        self.samples = 0;
+        let Some(img) = &mut self.inner else {
+            return;
+        };
+        let old = img.clone();
+        *img = Image::from_fn(old.width(), old.height(), |x, y| match source(x, y) {
+            Some(src) => old[src].limit_weight(max_weight),
+            None => AccumulationValue::default(),
+        });
+    }
 }
+
+// region Serialisation
+
+impl AccumulationBuffer<ColourRgb> {
+    /// Writes the buffer (including the frame and sample counters), in a simple little-endian binary format that can
+    /// be read back with [Self::read_from()]
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let (w, h) = self.inner.as_ref().map_or((0, 0), |img| (img.width(), img.height()));
+        for n in [self.counter, self.samples, w, h] {
+            writer.write_all(&(n as u64).to_le_bytes())?;
+        }
+        let Some(img) = &self.inner else {
+            return Ok(());
+        };
+        for y in 0..h {
+            for x in 0..w {
+                let AccumulationValue { sum, accum, .. } = img[(x, y)];
+                for c in sum {
+                    writer.write_all(&c.to_le_bytes())?;
+                }
+                writer.write_all(&accum.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a buffer that was written with [Self::write_to()], which must have the given dimensions (or be empty).
+    ///
+    /// The dimensions are checked before anything is allocated, so that a corrupted file can't make it allocate
+    /// arbitrarily much
+    pub fn read_from(reader: &mut impl Read, [expected_w, expected_h]: [usize; 2]) -> io::Result<Self> {
+        let [counter, samples, w, h] = [(); 4].try_map(|()| read_bytes(reader).map(u64::from_le_bytes))?;
+        let [counter, samples, w, h] = [counter, samples, w, h].map(|n| n as usize);
+        let valid_size = [w, h] == [0, 0] || [w, h] == [expected_w, expected_h];
+        if !valid_size || w.checked_mul(h).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid accumulation buffer size",
+            ));
+        }
+
+        let inner = match w {
+            0 => None,
+            _ => {
+                let mut values = Vec::with_capacity(w * h);
+                for _ in 0..(w * h) {
+                    let sum = ColourRgb::from([(); 3].try_map(|()| read_bytes(reader).map(Channel::from_le_bytes))?);
+                    let accum = Number::from_le_bytes(read_bytes(reader)?);
+                    let mean = if accum > 0. { sum / accum } else { ColourRgb::default() };
+                    values.push(AccumulationValue { sum, mean, accum });
+                }
+                Some(Image::from_fn(w, h, |x, y| values[x + (y * w)]))
+            }
+        };
+        Ok(Self {
+            inner,
+            counter,
+            samples,
+        })
+    }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+// endregion Serialisation
@@ -0,0 +1,336 @@
+//! # Module [crate::render::animation]
+//!
+//! Rendering a sequence of frames (an animation), with [render_sequence()] or [save_sequence()].
+//!
+//! The camera follows a [CameraPath] (such as a [turntable](CameraPath::turntable) around an object), and the scene's
+//! time is set for each frame (see [Scene::set_time()](crate::scene::Scene::set_time)), so that any
+//! [animated objects](crate::object::animated::AnimatedObject) move along with it, as does the sun of a
+//! [time-of-day sky](crate::skybox::time_of_day::TimeOfDaySkybox). The times of the frames are
+//! spread evenly over the [time range](CameraPath::time_range) of the path.
+//!
+//! Each frame is rendered from nothing, until the renderer is [finished](Renderer::is_finished) (see
+//! [RenderOpts::target_samples](crate::render::render_opts::RenderOpts::target_samples)), or for a single pass if the
+//! renderer would never finish. The renderer is left with the camera and time of the last frame.
+
+use crate::core::targets::RENDERER;
+use crate::core::types::{Angle, Image, Number, Point3};
+use crate::object::animated::Interpolation;
+use crate::object::Object;
+use crate::render::output::{self, OutputError, OutputFormat};
+use crate::render::render::Render;
+use crate::render::renderer::Renderer;
+use crate::scene::camera::{CamInvalidError, Camera, CameraMotion};
+use crate::shared::math::Lerp;
+use crate::skybox::Skybox;
+use rand_core::{RngCore, SeedableRng};
+use std::convert::identity;
+use std::f64::consts::TAU;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// The path that the camera follows through an animation
+#[derive(Clone, Debug)]
+pub struct CameraPath {
+    kind: PathKind,
+}
+
+#[derive(Clone, Debug)]
+enum PathKind {
+    Keyframes {
+        keyframes: Vec<(Number, Camera)>,
+        interpolation: Interpolation,
+    },
+    Turntable {
+        camera: Camera,
+        target: Point3,
+        radius: Number,
+        pitch: Angle,
+        duration: Number,
+    },
+}
+
+impl CameraPath {
+    /// Creates a path that moves between the `(time, camera)` keyframes, which don't need to be in order.
+    ///
+    /// The position, direction, FOV, focus distance and defocus angle of the cameras are interpolated, and the rest
+    /// of the settings are taken from the keyframe before. Before the first keyframe and after the last, the camera
+    /// stays at that keyframe
+    ///
+    /// # Panics
+    /// Panics if there are no keyframes, or any of the times are not finite
+    pub fn keyframes(keyframes: impl IntoIterator<Item = (Number, Camera)>, interpolation: Interpolation) -> Self {
+        let mut keyframes = keyframes.into_iter().collect::<Vec<_>>();
+        assert!(!keyframes.is_empty(), "path must have at least one keyframe");
+        for (time, _) in &keyframes {
+            assert!(time.is_finite(), "keyframe time must be finite (was {time})");
+        }
+        keyframes.sort_by(|(a, _), (b, _)| Number::total_cmp(a, b));
+        Self {
+            kind: PathKind::Keyframes {
+                keyframes,
+                interpolation,
+            },
+        }
+    }
+
+    /// Creates a path that orbits once around the `target` over the `duration`, at a distance of `radius` and raised
+    /// by the `pitch` (see [Camera::orbit()]). The rest of the settings are taken from the `camera`.
+    ///
+    /// The path loops, so the last frame of the sequence is just before the first one again
+    pub fn turntable(camera: Camera, target: Point3, radius: Number, pitch: Angle, duration: Number) -> Self {
+        Self {
+            kind: PathKind::Turntable {
+                camera,
+                target,
+                radius,
+                pitch,
+                duration,
+            },
+        }
+    }
+
+    /// The times that the path starts and ends at
+    pub fn time_range(&self) -> (Number, Number) {
+        match &self.kind {
+            PathKind::Keyframes { keyframes, .. } => (keyframes[0].0, keyframes[keyframes.len() - 1].0),
+            PathKind::Turntable { duration, .. } => (0., *duration),
+        }
+    }
+
+    /// Whether the path ends where it starts, so that the sequence shouldn't have the same frame at both ends
+    pub fn is_loop(&self) -> bool { matches!(self.kind, PathKind::Turntable { .. }) }
+
+    /// The time of a frame in a sequence of `frame_count` frames, spread evenly over the [Self::time_range()]
+    pub fn frame_time(&self, frame: usize, frame_count: usize) -> Number {
+        let (start, end) = self.time_range();
+        let steps = if self.is_loop() {
+            frame_count
+        } else {
+            frame_count.saturating_sub(1)
+        };
+        if steps == 0 {
+            return start;
+        }
+        Lerp::lerp(start, end, frame as Number / steps as Number)
+    }
+
+    /// The camera at the given time along the path
+    pub fn camera_at(&self, time: Number) -> Result<Camera, CamInvalidError> {
+        match &self.kind {
+            PathKind::Keyframes {
+                keyframes,
+                interpolation,
+            } => Ok(interpolate_keyframes(keyframes, *interpolation, time)),
+            PathKind::Turntable {
+                camera,
+                target,
+                radius,
+                pitch,
+                duration,
+            } => {
+                let yaw = Angle {
+                    radians: TAU * (time / duration),
+                };
+                let orbit = Camera::orbit(*target, *radius, yaw, *pitch)?;
+                Ok(Camera {
+                    pos: orbit.pos,
+                    fwd: orbit.fwd,
+                    up: orbit.up,
+                    focus_dist: orbit.focus_dist,
+                    ..*camera
+                })
+            }
+        }
+    }
+
+    /// The camera for a frame at `time`, moving along the path while its [shutter](Camera::shutter) is open
+    fn frame_camera(&self, time: Number) -> Result<Camera, CamInvalidError> {
+        let camera = self.camera_at(time)?;
+        let open = self.camera_at(camera.shutter.time_at(time, 0.))?;
+        if camera.shutter.duration() == 0. {
+            return Ok(Camera { motion: None, ..open });
+        }
+        let close = self.camera_at(camera.shutter.time_at(time, 1.))?;
+        Ok(Camera {
+            motion: Some(CameraMotion {
+                pos: close.pos,
+                fwd: close.fwd,
+            }),
+            ..open
+        })
+    }
+}
+
+/// Interpolates between the cameras of the two keyframes around the `time`
+fn interpolate_keyframes(keyframes: &[(Number, Camera)], interpolation: Interpolation, time: Number) -> Camera {
+    // Index of the first keyframe after the time
+    let next = keyframes.partition_point(|(t, _)| *t <= time);
+    if next == 0 {
+        return keyframes[0].1;
+    }
+    let (start_time, start) = keyframes[next - 1];
+    let Some(&(end_time, end)) = keyframes.get(next) else {
+        return start;
+    };
+
+    let t = (time - start_time) / (end_time - start_time);
+    let t = match interpolation {
+        Interpolation::Step => 0.,
+        Interpolation::Linear => t,
+        Interpolation::Smooth => t * t * (3. - (2. * t)),
+    };
+    let angle = |a: Angle, b: Angle| Angle {
+        radians: Lerp::lerp(a.radians, b.radians, t),
+    };
+    Camera {
+        pos: Lerp::lerp(start.pos.to_vector(), end.pos.to_vector(), t).to_point(),
+        // Directions that are opposite can't be blended, so they jump half way instead
+        fwd: Lerp::lerp(start.fwd, end.fwd, t)
+            .try_normalize()
+            .unwrap_or(if t < 0.5 { start.fwd } else { end.fwd }),
+        up: Lerp::lerp(start.up, end.up, t)
+            .try_normalize()
+            .unwrap_or(if t < 0.5 { start.up } else { end.up }),
+        v_fov: angle(start.v_fov, end.v_fov),
+        focus_dist: Lerp::lerp(start.focus_dist, end.focus_dist, t),
+        defocus_angle: angle(start.defocus_angle, end.defocus_angle),
+        ..start
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AnimationError {
+    #[error("the camera for frame {frame} was not valid")]
+    InvalidCamera {
+        frame: usize,
+        #[backtrace]
+        #[source]
+        source: CamInvalidError,
+    },
+    #[error("the render of frame {frame} was cancelled")]
+    Cancelled { frame: usize },
+    #[error("failed to save frame")]
+    SaveError {
+        #[backtrace]
+        #[from]
+        source: OutputError,
+    },
+}
+
+/// Renders each of the frames of the sequence, calling `each_frame` with the frame number and its render as it
+/// finishes. See the [module docs](self)
+pub fn render_each_frame<Obj, Sky, Rng>(
+    renderer: &mut Renderer<Obj, Sky, Rng>,
+    camera_path: &CameraPath,
+    frame_count: usize,
+    mut each_frame: impl FnMut(usize, Render<Image>) -> Result<(), AnimationError>,
+) -> Result<(), AnimationError>
+where
+    Obj: Object,
+    Sky: Skybox,
+    Rng: RngCore + Send + SeedableRng,
+{
+    info!(target: RENDERER, frame_count, "rendering sequence");
+    for frame in 0..frame_count {
+        let time = camera_path.frame_time(frame, frame_count);
+        let camera = camera_path
+            .frame_camera(time)
+            .map_err(|source| AnimationError::InvalidCamera { frame, source })?;
+        debug!(target: RENDERER, frame, time, "rendering frame");
+
+        renderer.set_camera(camera);
+        renderer.set_time(time);
+        // Without a target, the renderer never finishes, so only one pass is rendered
+        let has_target = renderer.options().target_samples.is_some() || renderer.options().max_time.is_some();
+        let render = loop {
+            let render = renderer.render();
+            if render.stats.cancelled {
+                return Err(AnimationError::Cancelled { frame });
+            }
+            let preview = render.stats.preview_scale.is_some();
+            if !preview && (!has_target || renderer.is_finished()) {
+                break render;
+            }
+        };
+        each_frame(frame, render)?;
+    }
+    Ok(())
+}
+
+/// Renders the frames of the sequence into images. See the [module docs](self)
+pub fn render_sequence<Obj, Sky, Rng>(
+    renderer: &mut Renderer<Obj, Sky, Rng>,
+    camera_path: &CameraPath,
+    frame_count: usize,
+) -> Result<Vec<Image>, AnimationError>
+where
+    Obj: Object,
+    Sky: Skybox,
+    Rng: RngCore + Send + SeedableRng,
+{
+    let mut images = Vec::with_capacity(frame_count);
+    render_each_frame(renderer, camera_path, frame_count, |_, render| {
+        images.push(render.img);
+        Ok(())
+    })?;
+    Ok(images)
+}
+
+/// Renders the frames of the sequence, saving each one to a numbered file (see [frame_path()]) as it finishes, in the
+/// format given by the extension of the `pattern`. Returns the paths that the frames were saved to.
+///
+/// Like a snapshot, the frames are saved with their [alpha](crate::render::render::Render::alpha) if they have it
+/// and the format supports it. See the [module docs](self)
+pub fn save_sequence<Obj, Sky, Rng>(
+    renderer: &mut Renderer<Obj, Sky, Rng>,
+    camera_path: &CameraPath,
+    frame_count: usize,
+    pattern: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>, AnimationError>
+where
+    Obj: Object,
+    Sky: Skybox,
+    Rng: RngCore + Send + SeedableRng,
+{
+    let pattern = pattern.as_ref();
+    let format = OutputFormat::from_path(pattern)?;
+    let mut paths = Vec::with_capacity(frame_count);
+    render_each_frame(renderer, camera_path, frame_count, |frame, render| {
+        let path = frame_path(pattern, frame);
+        match &render.alpha {
+            Some(alpha) if format.supports_alpha() => {
+                output::save_with_alpha(&render.img, alpha, &path, format, identity)?
+            }
+            _ => output::save(&render.img, &path, format, identity)?,
+        }
+        paths.push(path);
+        Ok(())
+    })?;
+    Ok(paths)
+}
+
+/// The path for a frame of a sequence, replacing the last run of `#` in the `pattern` with the frame number, padded
+/// with zeroes to the same width (e.g. `frame_####.png` gives `frame_0012.png`).
+///
+/// If there aren't any `#`, the frame number is added to the end of the file name, padded to 4 digits
+pub fn frame_path(pattern: &Path, frame: usize) -> PathBuf {
+    let pattern = pattern.to_string_lossy();
+    match pattern.rfind('#') {
+        Some(last) => {
+            let end = last + 1;
+            let start = pattern[..end].trim_end_matches('#').len();
+            let width = end - start;
+            PathBuf::from(format!("{}{frame:0width$}{}", &pattern[..start], &pattern[end..]))
+        }
+        None => {
+            let path = Path::new(&*pattern);
+            let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+            let name = match path.extension() {
+                Some(ext) => format!("{stem}_{frame:04}.{}", ext.to_string_lossy()),
+                None => format!("{stem}_{frame:04}"),
+            };
+            path.with_file_name(name)
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! # Module [crate::render::aov]
+//!
+//! **Arbitrary Output Variables** (**AOVs**): extra images rendered alongside the final (beauty) image,
+//! that contain information about the scene, such as surface normals or depth. These are normally used for
+//! compositing, and for denoising.
+//!
+//! Any set of AOVs can be rendered and exported into a single multi-layer EXR file in one call,
+//! using [save_aovs_exr()]. Each AOV is stored as its own layer, named after the AOV (see [Aov::layer_name()]).
+
+use crate::core::targets::RENDERER;
+use crate::core::types::{Channel, Colour, Image};
+use crate::material::Material;
+use crate::object::Object;
+use crate::render::renderer::Renderer;
+use crate::shared::intersect::FullIntersection;
+use crate::shared::ray::Ray;
+use crate::shared::rng;
+use crate::skybox::Skybox;
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, ImageAttributes, IntegerBounds, Layer, LayerAttributes, Layers,
+    WritableImage,
+};
+use rand_core::{RngCore, SeedableRng};
+use serde::Serialize;
+use smallvec::SmallVec;
+use std::path::Path;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use thiserror::Error;
+use tracing::debug;
+use valuable::Valuable;
+
+/// A single output variable that can be rendered
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum Aov {
+    /// The final, physically-based rendered image
+    Beauty,
+    /// The (outward) world-space surface normal
+    Normal,
+    /// The distance along the camera ray to the surface
+    Depth,
+    /// The numeric ID of the surface that was hit (currently the [side](crate::shared::intersect::Intersection::side) of the mesh)
+    Id,
+    /// The colour of the surface that was first hit, without any lighting (see [Material::albedo()])
+    Albedo,
+    /// A value in the range `0.0..1.0` that identifies the material that was hit, for making selection masks.
+    ///
+    /// This is a hash of where the material is stored, so it is only stable while the scene is unchanged. Objects
+    /// that have their own (cloned) copy of a material get different IDs
+    MaterialId,
+}
+
+impl Aov {
+    /// The AOVs that are rendered alongside the beauty image when
+    /// [RenderOpts::aovs](crate::render::render_opts::RenderOpts::aovs) is enabled, see
+    /// [Render::aovs](crate::render::render::Render::aovs). This is every AOV apart from [Aov::Beauty]
+    pub const AUXILIARY: [Aov; 5] = [Aov::Normal, Aov::Depth, Aov::Id, Aov::Albedo, Aov::MaterialId];
+
+    /// The name of the layer this AOV is stored in, when exported
+    pub fn layer_name(&self) -> &'static str {
+        match self {
+            Self::Beauty => "beauty",
+            Self::Normal => "normal",
+            Self::Depth => "depth",
+            Self::Id => "id",
+            Self::Albedo => "albedo",
+            Self::MaterialId => "material_id",
+        }
+    }
+
+    /// The names of the channels this AOV uses. Each channel is taken from the corresponding channel of the
+    /// rendered [Image] (so single-channel AOVs only use the first channel)
+    pub fn channel_names(&self) -> &'static [&'static str] {
+        match self {
+            Self::Beauty => &["R", "G", "B"],
+            Self::Normal => &["X", "Y", "Z"],
+            Self::Depth => &["Z"],
+            Self::Id => &["id"],
+            Self::Albedo => &["R", "G", "B"],
+            Self::MaterialId => &["id"],
+        }
+    }
+
+    /// Calculates the value of this AOV for the intersection of a camera ray.
+    ///
+    /// Not valid for [Aov::Beauty], which can't be calculated from a single intersection.
+    pub(crate) fn value<Mat: Material>(&self, ray: &Ray, hit: &FullIntersection<Mat>, rng: &mut dyn RngCore) -> Colour {
+        let FullIntersection { intersection, material } = hit;
+        match self {
+            Self::Beauty => unreachable!("beauty AOV can't be calculated from an intersection"),
+            Self::Normal => Colour::from(intersection.normal.as_array().map(|n| n as Channel)),
+            Self::Depth => Colour::from([intersection.dist as Channel; 3]),
+            Self::Id => Colour::from([intersection.side as Channel; 3]),
+            Self::Albedo => material.albedo(ray, intersection, rng),
+            Self::MaterialId => {
+                let hash = rng::hash_seed([std::ptr::from_ref(*material).addr() as u64]);
+                // Top 24 bits, so that the value is exact as a float
+                Colour::from([(hash >> 40) as Channel / (1_u64 << 24) as Channel; 3])
+            }
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AovExportError {
+    #[error("no layers were given to export")]
+    Empty,
+    #[error("layer {layer} has dimensions {actual:?}, but expected {expected:?}")]
+    DimensionMismatch {
+        layer: String,
+        expected: [usize; 2],
+        actual: [usize; 2],
+    },
+    #[error("failed to write EXR file")]
+    Exr {
+        #[backtrace]
+        #[from]
+        source: exr::error::Error,
+    },
+}
+
+/// Renders each of the `aovs` with the given renderer, and saves them all as layers of a single EXR file.
+///
+/// See [Renderer::render_aov()] for how each AOV is rendered.
+pub fn save_aovs_exr<Obj, Sky, Rng>(
+    renderer: &mut Renderer<Obj, Sky, Rng>,
+    aovs: &[Aov],
+    path: impl AsRef<Path>,
+) -> Result<(), AovExportError>
+where
+    Obj: Object,
+    Sky: Skybox,
+    Rng: RngCore + Send + SeedableRng,
+{
+    let images = aovs
+        .iter()
+        .map(|&aov| (aov, renderer.render_aov(aov)))
+        .collect::<Vec<_>>();
+    write_aovs_exr(&images, path)
+}
+
+/// Saves already-rendered AOVs as layers of a single EXR file.
+///
+/// All the images must have the same dimensions.
+pub fn write_aovs_exr(images: &[(Aov, Image)], path: impl AsRef<Path>) -> Result<(), AovExportError> {
+    write_layers_exr(
+        images
+            .iter()
+            .map(|(aov, img)| (Some(aov.layer_name()), aov.channel_names(), img)),
+        path,
+    )
+}
+
+/// Saves images as layers of a single EXR file.
+///
+/// Each layer is given as `(name, channel names, image)`, where each channel is taken from the corresponding
+/// channel of the image. Layers without a name are stored as the main layer. Layers with the same name are merged
+/// into one, so that extra channels (such as alpha) can be added to a layer from another image.
+/// All the images must have the same dimensions.
+pub(crate) fn write_layers_exr<'a>(
+    layers: impl IntoIterator<Item = (Option<&'a str>, &'a [&'a str], &'a Image)>,
+    path: impl AsRef<Path>,
+) -> Result<(), AovExportError> {
+    let path = path.as_ref();
+    let mut layers = layers.into_iter().peekable();
+    let Some((_, _, first)) = layers.peek() else {
+        return Err(AovExportError::Empty);
+    };
+    let (w, h) = (first.width(), first.height());
+
+    let mut merged = Vec::<(Option<&str>, SmallVec<_>)>::new();
+    for (name, channel_names, img) in layers {
+        let dims = [img.width(), img.height()];
+        if dims != [w, h] {
+            return Err(AovExportError::DimensionMismatch {
+                layer: name.unwrap_or("main").to_string(),
+                expected: [w, h],
+                actual: dims,
+            });
+        }
+
+        let channels = channel_names
+            .iter()
+            .enumerate()
+            .map(|(c, &channel)| {
+                // EXR stores samples row-by-row
+                let samples = (0..h).flat_map(|y| (0..w).map(move |x| img[(x, y)][c])).collect();
+                AnyChannel::new(channel, FlatSamples::F32(samples))
+            })
+            .collect::<SmallVec<_>>();
+
+        match merged.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, existing)) => existing.extend(channels),
+            None => merged.push((name, channels)),
+        }
+    }
+
+    let mut exr_layers = Layers::new();
+    for (name, channels) in merged {
+        exr_layers.push(Layer::new(
+            (w, h),
+            name.map_or_else(LayerAttributes::default, LayerAttributes::named),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(channels),
+        ));
+    }
+
+    debug!(target: RENDERER, ?path, count = exr_layers.len(), "writing layers to EXR");
+    let exr = exr::image::Image::from_layers(ImageAttributes::new(IntegerBounds::from_dimensions((w, h))), exr_layers);
+    exr.write().to_file(path)?;
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! # Module [crate::render::bake]
+//!
+//! **Baking**: rendering into the UV space of a mesh, instead of from a camera.
+//!
+//! Each texel of the output image corresponds to a point on the surface of the mesh (found using the mesh's UVs),
+//! and the value of the texel is calculated at that point in the scene. This can be used to create lightmaps and
+//! ambient occlusion maps for other renderers (such as game engines), or to bake expensive lighting into a texture.
+//!
+//! See [Renderer::bake()](crate::render::renderer::Renderer::bake) for the actual rendering, and [BakeChannel] for
+//! what can be baked.
+//!
+//! # Texels
+//! The image is flipped vertically from UV space, so that `v = 0` is the bottom row of the image (the same as
+//! [ImageTexture](crate::texture::image::ImageTexture)). Texels that aren't covered by any triangle are left black,
+//! and no padding (dilation) is added around the edges of the UV islands.
+//!
+//! # Transforms
+//! The mesh is baked where its object puts it in the world, using the object's [ObjectTransform] (the same one that
+//! the object in the scene has, see [SimpleObject::transform()](crate::object::simple::SimpleObject::transform)).
+//! Moving transforms are baked where they are at the start of the shutter interval, which is when the rays that are
+//! traced for baking are fired.
+
+use crate::core::image::Image;
+use crate::core::types::{Number, Point3, Vector2, Vector3};
+use crate::mesh::advanced::indexed_triangle::{face_normal, IndexedTriangleMesh};
+use crate::object::transform::ObjectTransform;
+use nonzero::nonzero;
+use serde::Serialize;
+use std::num::NonZeroUsize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use thiserror::Error;
+use valuable::Valuable;
+
+/// What is baked into each texel
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum BakeChannel {
+    /// The world-space position of the surface
+    Position,
+    /// The (interpolated) world-space normal of the surface
+    Normal,
+    /// How much of the hemisphere above the surface is unoccluded, within [BakeOpts::ao_distance]
+    ///
+    /// `1.0` is completely unoccluded, `0.0` is completely occluded
+    AmbientOcclusion,
+    /// The incoming light at the surface (cosine-weighted), calculated using the normal integrator.
+    ///
+    /// This is the colour that a white, perfectly diffuse surface would have, so multiply by the albedo
+    /// to get the final colour.
+    Lighting,
+}
+
+/// Options for baking. See [crate::render::bake]
+#[derive(Copy, Clone, Debug, Valuable, Serialize)]
+pub struct BakeOpts {
+    /// The width of the baked image (texels)
+    pub width: NonZeroUsize,
+    /// The height of the baked image (texels)
+    pub height: NonZeroUsize,
+    /// How many rays are traced for each texel, for [BakeChannel::AmbientOcclusion] and [BakeChannel::Lighting]
+    pub samples: NonZeroUsize,
+    /// How far away an object can be and still occlude the surface, for [BakeChannel::AmbientOcclusion]
+    pub ao_distance: Number,
+}
+
+impl Default for BakeOpts {
+    fn default() -> Self {
+        Self {
+            width: nonzero!(512_usize),
+            height: nonzero!(512_usize),
+            samples: nonzero!(64_usize),
+            ao_distance: 1.,
+        }
+    }
+}
+
+impl BakeOpts {
+    pub fn dims(&self) -> [usize; 2] { [self.width.get(), self.height.get()] }
+}
+
+#[derive(Error, Debug)]
+pub enum BakeError {
+    #[error("mesh has no UVs, so can't be baked")]
+    MissingUvs,
+}
+
+/// A point on the surface of a mesh, that a texel maps to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SurfacePoint {
+    pub pos: Point3,
+    pub normal: Vector3,
+}
+
+/// Rasterises the UV layout of the mesh, finding the point on the surface of the mesh that each texel maps to.
+/// The points are in world-space, after the object-to-world `transform` has been applied.
+///
+/// Texels are sampled at their centres. If several triangles overlap a texel, the last one wins.
+pub fn rasterise_uvs(
+    mesh: &IndexedTriangleMesh,
+    transform: &ObjectTransform,
+    [w, h]: [usize; 2],
+) -> Result<Image<Option<SurfacePoint>>, BakeError> {
+    let uvs = mesh.uvs();
+    if uvs.is_empty() {
+        return Err(BakeError::MissingUvs);
+    }
+    // Bake rays are all fired at the start of the shutter interval
+    let transform = transform.at_time(0.);
+
+    let mut texels = Image::<Option<SurfacePoint>>::new_blank(w, h);
+    for &tri in mesh.indices() {
+        let verts = tri.map(|i| mesh.vertices()[i]);
+        let Some(flat_normal) = face_normal(verts) else {
+            continue;
+        };
+        let normals = if mesh.normals().is_empty() {
+            [flat_normal; 3]
+        } else {
+            tri.map(|i| mesh.normals()[i])
+        };
+        // Into texel space, flipping `v`
+        let [a, b, c] = tri.map(|i| Vector2::new(uvs[i].x * w as Number, (1. - uvs[i].y) * h as Number));
+
+        let area = cross(b - a, c - a);
+        if area == 0. {
+            continue;
+        }
+
+        // Only check the texels in the bounds of the triangle
+        let min = a.min(b).min(c).floor().max(Vector2::ZERO);
+        let max = a.max(b).max(c).ceil().min(Vector2::new(w as Number, h as Number));
+        for y in (min.y as usize)..(max.y as usize) {
+            for x in (min.x as usize)..(max.x as usize) {
+                let p = Vector2::new(x as Number + 0.5, y as Number + 0.5);
+                // Barycentric coordinates (works for either winding, since we divide by the signed area)
+                let wa = cross(c - b, p - b) / area;
+                let wb = cross(a - c, p - c) / area;
+                let wc = 1. - wa - wb;
+                if wa < 0. || wb < 0. || wc < 0. {
+                    continue;
+                }
+
+                let pos = (verts[0].to_vector() * wa) + (verts[1].to_vector() * wb) + (verts[2].to_vector() * wc);
+                let normal = ((normals[0] * wa) + (normals[1] * wb) + (normals[2] * wc))
+                    .try_normalize()
+                    .unwrap_or(flat_normal);
+                texels[(x, y)] = Some(to_world(&transform, pos.to_point(), normal));
+            }
+        }
+    }
+
+    Ok(texels)
+}
+
+/// Transforms a point on the surface of the mesh from mesh-space to world-space, the same way as
+/// [ObjectTransform::outgoing_intersection()]
+fn to_world(transform: &ObjectTransform, pos: Point3, normal: Vector3) -> SurfacePoint {
+    if *transform.is_identity() {
+        return SurfacePoint { pos, normal };
+    }
+    let object_to_world = transform.transform();
+    SurfacePoint {
+        pos: object_to_world.matrix.transform_point(pos),
+        // Transforms are invertible, so the normal can't collapse to zero
+        normal: object_to_world.map_vector(normal).normalize(),
+    }
+}
+
+/// The 2D cross product (the `z` component of the 3D cross product)
+fn cross(a: Vector2, b: Vector2) -> Number { (a.x * b.y) - (a.y * b.x) }
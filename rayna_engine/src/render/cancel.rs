@@ -0,0 +1,39 @@
+//! # Module [crate::render::cancel]
+//!
+//! Cancelling a [render](crate::render::renderer::Renderer::render()) while it is still in progress, so that a frame
+//! which is already out of date (because the camera moved, for example) doesn't hold everything up until it finishes.
+//!
+//! # Cancellation
+//! As with [jobs](crate::core::job), cancellation is cooperative: calling [CancelToken::cancel()] only sets a flag,
+//! which the renderer checks before each tile. The tiles that were already started are finished, and the rest are
+//! skipped.
+//!
+//! A cancelled pass has only been added to some of the pixels, so its samples are discarded, leaving the passes that
+//! were accumulated before it. The render is returned with
+//! [RenderStats::cancelled](crate::render::render::RenderStats::cancelled) set, and should be thrown away.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag that can be shared with other threads, to cancel the pass that a
+/// [renderer](crate::render::renderer::Renderer) is in the middle of. See the [module docs](self)
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self { Self::default() }
+
+    /// Requests that the current pass be cancelled.
+    ///
+    /// This only affects a pass that is being rendered: cancelling in between passes does nothing, since the flag is
+    /// [reset](Self::reset()) at the start of each one
+    pub fn cancel(&self) { self.cancelled.store(true, Ordering::Relaxed) }
+
+    /// Whether the pass has been requested to cancel
+    pub fn is_cancelled(&self) -> bool { self.cancelled.load(Ordering::Relaxed) }
+
+    /// Clears the flag, ready for the next pass
+    pub fn reset(&self) { self.cancelled.store(false, Ordering::Relaxed) }
+}
@@ -0,0 +1,243 @@
+//! # Module [crate::render::checkpoint]
+//!
+//! **Checkpoints**: saving the accumulated samples of a render to a file, so that a long render can be carried on
+//! later (e.g. after the application is restarted), instead of starting again from scratch.
+//! See [Renderer::save_checkpoint()](crate::render::renderer::Renderer::save_checkpoint) and
+//! [Renderer::resume_from()](crate::render::renderer::Renderer::resume_from).
+//!
+//! Each checkpoint stores a hash of the render options and camera that produced it (see [settings_hash()]), and can
+//! only be resumed by a renderer with the same ones. Only the options that change the accumulated samples are hashed,
+//! so the post-processing (exposure, tone mapping, etc.) can still be changed in between. It also stores a hash of
+//! the scene (see [scene_hash()]), so that a checkpoint isn't resumed after the scene has been edited (including its
+//! assets, such as the pixels of a texture).
+
+use crate::core::targets::RENDERER;
+use crate::core::types::{Channel, Colour, Number, Point2, Point3, Vector3};
+use crate::render::accum_buffer::AccumulationBuffer;
+use crate::render::postprocess::tone_mapping::ToneMapping;
+use crate::render::postprocess::white_balance::WhiteBalance;
+use crate::render::render_opts::RenderOpts;
+use crate::scene::camera::Camera;
+use crate::shared::rng;
+use ndarray::{ArrayBase, Data, Dimension};
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt::{self, Debug, Write as _};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::debug;
+
+/// The start of every checkpoint file
+const MAGIC: [u8; 8] = *b"RAYNACKP";
+/// The version of the file format, which is increased whenever it changes
+const VERSION: u32 = 2;
+
+#[derive(Error, Debug)]
+pub enum CheckpointError {
+    #[error("failed to access checkpoint file {path:?}")]
+    IoError {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: io::Error,
+    },
+    #[error("{path:?} isn't a checkpoint file, or is from an incompatible version")]
+    InvalidFile { path: PathBuf },
+    #[error("checkpoint {path:?} was rendered with different render options or a different camera")]
+    SettingsMismatch { path: PathBuf },
+    #[error("checkpoint {path:?} was rendered with a different scene")]
+    SceneMismatch { path: PathBuf },
+}
+
+/// Hashes the parts of the render options and camera that affect the accumulated samples
+pub fn settings_hash(opts: &RenderOpts, camera: &Camera) -> u64 {
+    let opts = RenderOpts {
+        // These only change how the samples are split into passes, or the post-processing
+        samples: NonZeroUsize::MIN,
+        target_samples: None,
+        max_time: None,
+        progressive_preview: false,
+        temporal_reprojection: false,
+        aovs: false,
+        variance: false,
+        bvh_stats: false,
+        intersection_capacity: 0,
+        auto_exposure: None,
+        exposure: 0.,
+        white_balance: WhiteBalance::NEUTRAL,
+        denoise: None,
+        tone_mapping: ToneMapping::Linear,
+        ..*opts
+    };
+    // The `Debug` output has every field, and prints the floats exactly
+    let text = format!("{opts:?}{camera:?}");
+    rng::hash_seed(words(text.as_bytes()))
+}
+
+thread_local! {
+    /// Whether [scene_hash()] is hashing a scene on this thread, so that [fmt_data()] should hash the data
+    static HASHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Hashes the scene, from its `Debug` output (which has every field, like with [settings_hash()]). The bulk data that
+/// is left out of the `Debug` output (such as the pixels of images and the vertices of meshes) is hashed as well, see
+/// [fmt_data()].
+///
+/// The scene should be hashed as it was given to the renderer, before it changes anything (such as the shutter).
+///
+/// # Performance
+/// The output isn't buffered, but large scenes still take a while to print, so this should only be done once for
+/// each scene
+pub fn scene_hash(scene: &impl Debug) -> u64 {
+    /// Hashes everything written to it
+    struct Hasher(u64);
+    impl fmt::Write for Hasher {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 = rng::hash_seed(std::iter::once(self.0).chain(words(s.as_bytes())));
+            Ok(())
+        }
+    }
+    /// Stops hashing the data once the scene is hashed, even if printing it panics
+    struct Guard;
+    impl Drop for Guard {
+        fn drop(&mut self) { HASHING.set(false); }
+    }
+
+    HASHING.set(true);
+    let _guard = Guard;
+    let mut hasher = Hasher(0);
+    write!(hasher, "{scene:?}").expect("hashing can't fail");
+    hasher.0
+}
+
+/// Formats bulk data (such as the pixels of an image) for a `Debug` implementation, with
+/// `#[derivative(Debug(format_with = "crate::render::checkpoint::fmt_data"))]`.
+///
+/// Normally the data is too big to be worth printing, so it is left out. But while [scene_hash()] is hashing a scene,
+/// a hash of the data is printed instead, so that changing any of it changes the scene's hash
+pub fn fmt_data(data: &impl DataBuffer, f: &mut fmt::Formatter) -> fmt::Result {
+    if !HASHING.get() {
+        return f.write_str("..");
+    }
+    write!(f, "{:016x}", data.data_hash())
+}
+
+/// Bulk data in the scene, that is hashed by [fmt_data()]
+pub trait DataBuffer {
+    /// Hashes every element of the data. The common types of elements (floats, colours, vectors and indices) are
+    /// hashed from their bits, and anything else from its `Debug` output
+    fn data_hash(&self) -> u64;
+}
+
+impl<T: Debug + 'static> DataBuffer for Vec<T> {
+    fn data_hash(&self) -> u64 { rng::hash_seed(self.iter().flat_map(element_words)) }
+}
+
+impl<T: Debug + 'static, S: Data<Elem = T>, D: Dimension> DataBuffer for ArrayBase<S, D> {
+    fn data_hash(&self) -> u64 { rng::hash_seed(self.iter().flat_map(element_words)) }
+}
+
+impl<B: DataBuffer + ?Sized> DataBuffer for &B {
+    fn data_hash(&self) -> u64 { B::data_hash(self) }
+}
+
+/// The words to hash for a single element of a [DataBuffer]
+fn element_words(value: &(impl Debug + 'static)) -> [u64; 3] {
+    let any = value as &dyn Any;
+    let float = |n: Number| n.to_bits();
+    if let Some(&n) = any.downcast_ref::<Channel>() {
+        [n.to_bits() as u64, 0, 0]
+    } else if let Some(&n) = any.downcast_ref::<Number>() {
+        [float(n), 0, 0]
+    } else if let Some(c) = any.downcast_ref::<Colour>() {
+        c.0.map(|c| c.to_bits() as u64)
+    } else if let Some(p) = any.downcast_ref::<Point3>() {
+        [float(p.x), float(p.y), float(p.z)]
+    } else if let Some(v) = any.downcast_ref::<Vector3>() {
+        [float(v.x), float(v.y), float(v.z)]
+    } else if let Some(p) = any.downcast_ref::<Point2>() {
+        [float(p.x), float(p.y), 0]
+    } else if let Some(indices) = any.downcast_ref::<[usize; 3]>() {
+        indices.map(|i| i as u64)
+    } else {
+        [rng::hash_seed(words(format!("{value:?}").as_bytes())), 0, 0]
+    }
+}
+
+/// Splits the bytes into words for [rng::hash_seed()], padding the last one with zeroes
+fn words(bytes: &[u8]) -> impl Iterator<Item = u64> + '_ {
+    bytes.chunks(8).map(|chunk| {
+        let mut word = [0; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u64::from_le_bytes(word)
+    })
+}
+
+/// Writes the accumulation buffer to a checkpoint file
+pub(crate) fn save(
+    path: &Path,
+    hash: u64,
+    scene_hash: u64,
+    buffer: &AccumulationBuffer,
+) -> Result<(), CheckpointError> {
+    debug!(target: RENDERER, ?path, hash, scene_hash, "saving checkpoint");
+    let io_err = |source| CheckpointError::IoError {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let mut writer = BufWriter::new(File::create(path).map_err(io_err)?);
+    writer.write_all(&MAGIC).map_err(io_err)?;
+    writer.write_all(&VERSION.to_le_bytes()).map_err(io_err)?;
+    writer.write_all(&hash.to_le_bytes()).map_err(io_err)?;
+    writer.write_all(&scene_hash.to_le_bytes()).map_err(io_err)?;
+    buffer.write_to(&mut writer).map_err(io_err)?;
+    writer.flush().map_err(io_err)
+}
+
+/// Reads the accumulation buffer from a checkpoint file, checking that it was saved with the same settings and scene,
+/// and that the buffer has the given dimensions
+pub(crate) fn load(
+    path: &Path,
+    hash: u64,
+    scene_hash: u64,
+    dims: [usize; 2],
+) -> Result<AccumulationBuffer, CheckpointError> {
+    debug!(target: RENDERER, ?path, hash, scene_hash, "loading checkpoint");
+    let io_err = |source: io::Error| match source.kind() {
+        // The file is truncated or corrupted
+        io::ErrorKind::UnexpectedEof | io::ErrorKind::InvalidData => CheckpointError::InvalidFile {
+            path: path.to_path_buf(),
+        },
+        _ => CheckpointError::IoError {
+            path: path.to_path_buf(),
+            source,
+        },
+    };
+
+    let mut reader = BufReader::new(File::open(path).map_err(io_err)?);
+    let mut header = [0; MAGIC.len() + 4 + 8 + 8];
+    reader.read_exact(&mut header).map_err(io_err)?;
+    let (magic, rest) = header.split_at(MAGIC.len());
+    let (version, rest) = rest.split_at(4);
+    let (saved_hash, saved_scene_hash) = rest.split_at(8);
+    if magic != MAGIC || version != VERSION.to_le_bytes() {
+        return Err(CheckpointError::InvalidFile {
+            path: path.to_path_buf(),
+        });
+    }
+    if saved_hash != hash.to_le_bytes() {
+        return Err(CheckpointError::SettingsMismatch {
+            path: path.to_path_buf(),
+        });
+    }
+    if saved_scene_hash != scene_hash.to_le_bytes() {
+        return Err(CheckpointError::SceneMismatch {
+            path: path.to_path_buf(),
+        });
+    }
+    AccumulationBuffer::read_from(&mut reader, dims).map_err(io_err)
+}
@@ -0,0 +1,214 @@
+//! # Module [crate::render::compare]
+//!
+//! Tools for comparing two renders, for example to see how much a change in the render options
+//! (a different integrator, sampler, sample count, etc.) affects the final image.
+//!
+//! The difference between the two images is summarised using two metrics:
+//!
+//! - **RMSE** (*root mean squared error*), which is simple and objective,
+//!   but doesn't match how noticeable the error is to a person
+//! - **FLIP** (see [flip_colour_error()]), which approximates how noticeable the difference is when flipping
+//!   between the two images
+//!
+//! # Noise
+//! Unless [RenderOpts::deterministic] is enabled (for both sets of options), two renders with identical options will
+//! still differ slightly due to noise. Accumulate more frames to reduce its effect on the metrics, or enable it so
+//! that only the options being compared make a difference.
+
+use crate::core::types::{Channel, Colour, Image, Number};
+use crate::object::Object;
+use crate::render::render::Render;
+use crate::render::render_opts::RenderOpts;
+use crate::render::renderer::Renderer;
+use crate::skybox::Skybox;
+use ndarray::Zip;
+use rand_core::{RngCore, SeedableRng};
+use std::num::NonZeroUsize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CompareError {
+    #[error("cannot compare images of different dimensions ({a:?} and {b:?})")]
+    DimensionMismatch { a: [usize; 2], b: [usize; 2] },
+}
+
+/// Metrics that summarise the difference between two images
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComparisonMetrics {
+    /// Root mean squared error, across all the pixels and channels
+    pub rmse: Number,
+    /// Mean of the per-pixel FLIP error, in the range `0.0..=1.0`
+    pub mean_flip: Number,
+    /// Largest per-pixel FLIP error, in the range `0.0..=1.0`
+    pub max_flip: Number,
+}
+
+/// The result of comparing two images
+#[derive(Clone, Debug)]
+pub struct ImageComparison {
+    /// The absolute difference between the two images, per-channel
+    pub difference: Image,
+    /// The per-pixel FLIP error between the two images, as a greyscale image
+    pub flip_map: Image,
+    pub metrics: ComparisonMetrics,
+}
+
+/// The result of rendering a scene with two different sets of options, and comparing the renders
+#[derive(Clone, Debug)]
+pub struct RenderComparison {
+    pub a: Render<Image>,
+    pub b: Render<Image>,
+    pub comparison: ImageComparison,
+}
+
+// region Comparisons
+
+/// Compares two images, calculating the difference image and [metrics](ComparisonMetrics) between them
+pub fn compare_images(a: &Image, b: &Image) -> Result<ImageComparison, CompareError> {
+    let (dims_a, dims_b) = ([a.width(), a.height()], [b.width(), b.height()]);
+    if dims_a != dims_b {
+        return Err(CompareError::DimensionMismatch { a: dims_a, b: dims_b });
+    }
+
+    let flip_max = flip_max_error();
+    let difference = Zip::from(a.data())
+        .and(b.data())
+        .map_collect(|a, b| a.map2(b, |a, b| (a - b).abs()));
+    let flip = Zip::from(a.data())
+        .and(b.data())
+        .map_collect(|a, b| flip_error_with_max(*a, *b, flip_max));
+
+    let num_pixels = (a.width() * a.height()).max(1) as Number;
+    let squared_error = difference
+        .iter()
+        .flat_map(|d| d.0)
+        .map(|d| (d as Number).powi(2))
+        .sum::<Number>();
+    let metrics = ComparisonMetrics {
+        rmse: (squared_error / (num_pixels * Colour::CHANNEL_COUNT as Number)).sqrt(),
+        mean_flip: flip.iter().sum::<Number>() / num_pixels,
+        max_flip: flip.iter().copied().fold(0., Number::max),
+    };
+
+    Ok(ImageComparison {
+        difference: Image::new(difference.into_shared()),
+        flip_map: Image::new(flip.map(|&f| Colour::from([f as Channel; 3])).into_shared()),
+        metrics,
+    })
+}
+
+/// Renders the renderer's scene and camera with two different sets of render options, and compares the results.
+///
+/// Each render accumulates `frames` frames, using a fresh copy of the `renderer`,
+/// so the renderer's own accumulation buffer is left untouched.
+/// [Previews](RenderOpts::progressive_preview) are turned off, so that every frame is a full pass.
+///
+/// Both sets of options must have the same dimensions, otherwise the renders can't be compared.
+pub fn compare_renders<Obj, Sky, Rng>(
+    renderer: &Renderer<Obj, Sky, Rng>,
+    opts_a: RenderOpts,
+    opts_b: RenderOpts,
+    frames: NonZeroUsize,
+) -> Result<RenderComparison, CompareError>
+where
+    Obj: Object + Clone,
+    Sky: Skybox + Clone,
+    Rng: RngCore + Send + SeedableRng,
+{
+    let (dims_a, dims_b) = (opts_a.dims(), opts_b.dims());
+    if dims_a != dims_b {
+        return Err(CompareError::DimensionMismatch { a: dims_a, b: dims_b });
+    }
+
+    let render_with = |opts: RenderOpts| {
+        let mut renderer = renderer.clone();
+        renderer.set_options(RenderOpts {
+            progressive_preview: false,
+            ..opts
+        });
+        for _ in 1..frames.get() {
+            renderer.render();
+        }
+        renderer.render()
+    };
+
+    let (a, b) = (render_with(opts_a), render_with(opts_b));
+    let comparison = compare_images(&a.img, &b.img)?;
+    Ok(RenderComparison { a, b, comparison })
+}
+
+// endregion Comparisons
+
+// region FLIP
+
+/// Calculates the FLIP error between two (linear RGB) colours, in the range `0.0..=1.0`.
+///
+/// This is the *colour* part of the FLIP metric only. The spatial filtering (which depends on the viewing distance)
+/// and the edge/point feature detection are skipped, which makes it a per-pixel metric.
+/// Colours are clamped to the `0.0..=1.0` range first, as FLIP is designed for LDR images.
+pub fn flip_colour_error(a: Colour, b: Colour) -> Number { flip_error_with_max(a, b, flip_max_error()) }
+
+/// The exponent applied to the raw colour distance in FLIP
+const FLIP_Q_C: Number = 0.7;
+
+/// The FLIP error between pure green and pure blue, which is the largest possible error
+fn flip_max_error() -> Number {
+    hyab(
+        hunt(linear_rgb_to_lab(Colour::GREEN)),
+        hunt(linear_rgb_to_lab(Colour::BLUE)),
+    )
+    .powf(FLIP_Q_C)
+}
+
+fn flip_error_with_max(a: Colour, b: Colour, max: Number) -> Number {
+    /*
+    CREDITS:
+
+    Title: "FLIP: A Difference Evaluator for Alternating Images"
+    Author: Pontus Andersson, Jim Nilsson, Tomas Akenine-Möller, Magnus Oskarsson, Kalle Åström, Mark D. Fairchild
+    URL: <https://research.nvidia.com/publication/2020-07_flip-difference-evaluator-alternating-images>
+    */
+    const P_C: Number = 0.4;
+    const P_T: Number = 0.95;
+
+    let [a, b] = [a, b].map(|c| hunt(linear_rgb_to_lab(c.map(|c| c.clamp(0., 1.)))));
+    let err = hyab(a, b).powf(FLIP_Q_C);
+
+    // Compress the error into `0..=1`, giving more of the range to smaller (more common) errors
+    let err = if err < P_C * max {
+        err * P_T / (P_C * max)
+    } else {
+        P_T + ((err - P_C * max) / (max - P_C * max)) * (1. - P_T)
+    };
+    err.clamp(0., 1.)
+}
+
+/// Converts a linear (sRGB primaries) colour into CIE L\*a\*b\* space, using a D65 white point
+fn linear_rgb_to_lab(c: Colour) -> [Number; 3] {
+    let [r, g, b] = c.0.map(|c| c as Number);
+    let x = 0.4124564 * r + 0.3575761 * g + 0.1804375 * b;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = 0.0193339 * r + 0.1191920 * g + 0.9503041 * b;
+
+    const DELTA: Number = 6. / 29.;
+    let f = |t: Number| {
+        if t > DELTA.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3. * DELTA.powi(2)) + 4. / 29.
+        }
+    };
+    let (fx, fy, fz) = (f(x / 0.950489), f(y), f(z / 1.088840));
+
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
+
+/// Applies the Hunt effect to a L\*a\*b\* colour, which reduces the chroma of dark colours
+fn hunt([l, a, b]: [Number; 3]) -> [Number; 3] { [l, 0.01 * l * a, 0.01 * l * b] }
+
+/// The HyAB colour distance, which works better than euclidean distance for large colour differences
+fn hyab([l1, a1, b1]: [Number; 3], [l2, a2, b2]: [Number; 3]) -> Number {
+    (l1 - l2).abs() + Number::hypot(a1 - a2, b1 - b2)
+}
+
+// endregion FLIP
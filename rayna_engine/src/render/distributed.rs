@@ -0,0 +1,304 @@
+//! # Module [crate::render::distributed]
+//!
+//! **Distributed rendering**: spreading the tiles of each pass over other machines, for final-quality renders of heavy
+//! scenes.
+//!
+//! One renderer is the *coordinator*, which
+//! [connects](crate::render::renderer::Renderer::connect_worker) to any number of *workers*: other processes running
+//! the engine, that [serve](crate::render::renderer::Renderer::serve) their own renderer over TCP. While it renders a
+//! pass, the coordinator hands out the [tiles](crate::render::tile) to its own threads and to the workers alike
+//! (whichever is free takes the next one), and adds the tiles that come back to its accumulation buffer, as if it had
+//! rendered them itself. Everything else (the AOVs, alpha, denoising and post-processing) is still done by the
+//! coordinator.
+//!
+//! # Setting Up
+//! Only the tiles are sent over the network, so each worker needs its own copy of the scene, camera and render
+//! options, the same as the coordinator's. As with [checkpoints](crate::render::checkpoint), the options and camera
+//! are checked using [settings_hash()](crate::render::checkpoint::settings_hash), and the scene (at the time it is
+//! set to) using [scene_hash()](crate::render::checkpoint::scene_hash). They are checked for every tile, so that a
+//! worker is dropped as soon as the coordinator's settings or scene change, instead of blending tiles of the wrong
+//! scene into the accumulation.
+//!
+//! When rendering deterministically, the tiles rendered by the workers are identical to the ones the coordinator would
+//! have rendered itself.
+//!
+//! # Limitations
+//! - [Photon mapping](crate::render::render_opts::RenderMode::PhotonMapping) is rendered entirely by the coordinator,
+//!   since the photons are traced for the whole pass at once, and the workers don't have them.
+//! - If a worker fails or disconnects, the tile it was rendering is rendered by the coordinator instead, and the worker
+//!   is dropped.
+//!
+//! # Protocol
+//! Everything is little-endian. The coordinator starts with a magic number, the protocol version (`u32`), its
+//! settings hash and its scene hash (`u64`s), and the worker replies with a status byte. Then for each tile, the
+//! coordinator sends both hashes again, the pass (the frame, first sample, and number of samples) and the tile (`x`, `y`, `w` and
+//! `h`), all as `u64`s. The worker replies with a status byte, followed by the colour of each pixel of the tile (in the
+//! order of [Tile::pixels()]) as three `f32`s. The coordinator closes the connection once it is done with the worker.
+//!
+//! There is no authentication, so workers should only be reachable by trusted coordinators. Even so, a worker refuses
+//! (and disconnects) any request for a tile that is bigger than [TILE_SIZE], outside the image, or has more samples
+//! than the worker's own [RenderOpts::samples](crate::render::render_opts::RenderOpts::samples), so that it can't be
+//! made to allocate (or render) arbitrarily much.
+
+use crate::core::targets::RENDERER;
+use crate::core::types::{Channel, Colour};
+use crate::render::tile::{Tile, TILE_SIZE};
+use crate::scene::camera::CamInvalidError;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::num::NonZeroUsize;
+use thiserror::Error;
+use tracing::debug;
+
+/// The start of every connection
+const MAGIC: [u8; 8] = *b"RAYNADST";
+/// The version of the protocol, which is increased whenever it changes
+const VERSION: u32 = 2;
+
+/// The worker accepted the handshake, or rendered the tile
+const STATUS_OK: u8 = 0;
+/// The worker has different settings to the coordinator
+const STATUS_MISMATCH: u8 = 1;
+/// The worker has a different scene to the coordinator
+const STATUS_SCENE_MISMATCH: u8 = 2;
+
+#[derive(Error, Debug)]
+pub enum DistributedError {
+    #[error("network error while rendering")]
+    IoError {
+        #[backtrace]
+        #[from]
+        source: io::Error,
+    },
+    #[error("peer isn't a rayna renderer, or is from an incompatible version")]
+    InvalidPeer,
+    #[error("the worker has different render options or a different camera to the coordinator")]
+    SettingsMismatch,
+    #[error("the worker has a different scene to the coordinator")]
+    SceneMismatch,
+    #[error("the worker returned a colour that is negative or isn't finite")]
+    InvalidColour,
+    #[error("the coordinator requested a tile outside the image, or with more samples than a pass has")]
+    InvalidRequest,
+    #[error("the worker's camera is invalid")]
+    InvalidCamera {
+        #[source]
+        source: CamInvalidError,
+    },
+}
+
+/// A request from the coordinator for a worker to render a tile
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct TileRequest {
+    /// The coordinator's [settings hash](crate::render::checkpoint::settings_hash)
+    pub hash: u64,
+    /// The coordinator's [scene hash](crate::render::renderer::Renderer::scene_hash)
+    pub scene_hash: u64,
+    /// The index of the pass, for seeding deterministic renders
+    pub frame: u64,
+    /// The index of the first sample in the pass
+    pub first_sample: u64,
+    /// How many samples each pixel is given in the pass
+    pub samples: NonZeroUsize,
+    pub tile: Tile,
+}
+
+impl TileRequest {
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let Tile { x, y, w, h } = self.tile;
+        let numbers = [
+            self.hash,
+            self.scene_hash,
+            self.frame,
+            self.first_sample,
+            self.samples.get() as u64,
+        ]
+        .into_iter()
+        .chain([x, y, w, h].map(|n| n as u64));
+        for n in numbers {
+            writer.write_all(&n.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let [hash, scene_hash, frame, first_sample, samples, x, y, w, h] = [(); 9].try_map(|()| read_u64(reader))?;
+        let samples = NonZeroUsize::new(samples as usize)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "tile requested with zero samples"))?;
+        let [x, y, w, h] = [x, y, w, h].map(|n| n as usize);
+        // Checked before anything is allocated for the tile, since anyone can connect to a worker
+        if !(1..=TILE_SIZE).contains(&w) || !(1..=TILE_SIZE).contains(&h) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "tile requested with an invalid size",
+            ));
+        }
+        Ok(Self {
+            hash,
+            scene_hash,
+            frame,
+            first_sample,
+            samples,
+            tile: Tile { x, y, w, h },
+        })
+    }
+}
+
+/// A TCP connection between a coordinator and a worker, buffered in both directions
+#[derive(Debug)]
+pub(crate) struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: BufWriter<TcpStream>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        // The requests and replies are small, and each side waits on the other
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: BufWriter::new(stream),
+        })
+    }
+
+    /// Accepts a connection from a coordinator (on the worker's side), checking that it has the same settings `hash`
+    /// and `scene_hash`
+    pub fn accept(stream: TcpStream, hash: u64, scene_hash: u64) -> Result<Self, DistributedError> {
+        let mut conn = Self::new(stream)?;
+        let mut magic = [0; MAGIC.len()];
+        conn.reader.read_exact(&mut magic)?;
+        if magic != MAGIC || read_u32(&mut conn.reader)? != VERSION {
+            return Err(DistributedError::InvalidPeer);
+        }
+        let [their_hash, their_scene_hash] = [(); 2].try_map(|()| read_u64(&mut conn.reader))?;
+        if their_hash != hash {
+            conn.write_status(STATUS_MISMATCH)?;
+            return Err(DistributedError::SettingsMismatch);
+        }
+        if their_scene_hash != scene_hash {
+            conn.write_status(STATUS_SCENE_MISMATCH)?;
+            return Err(DistributedError::SceneMismatch);
+        }
+        conn.write_status(STATUS_OK)?;
+        Ok(conn)
+    }
+
+    /// Reads the next tile request from the coordinator, or [None] if it has closed the connection
+    pub fn read_request(&mut self) -> Result<Option<TileRequest>, DistributedError> {
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(TileRequest::read_from(&mut self.reader)?))
+    }
+
+    /// Tells the coordinator that its settings don't match any more
+    pub fn write_mismatch(&mut self) -> Result<(), DistributedError> { Ok(self.write_status(STATUS_MISMATCH)?) }
+
+    /// Tells the coordinator that its scene doesn't match any more
+    pub fn write_scene_mismatch(&mut self) -> Result<(), DistributedError> {
+        Ok(self.write_status(STATUS_SCENE_MISMATCH)?)
+    }
+
+    /// Sends the colours of a rendered tile back to the coordinator
+    pub fn write_tile(&mut self, colours: &[Colour]) -> Result<(), DistributedError> {
+        self.writer.write_all(&[STATUS_OK])?;
+        for c in colours.iter().flat_map(|c| c.0) {
+            self.writer.write_all(&c.to_le_bytes())?;
+        }
+        Ok(self.writer.flush()?)
+    }
+
+    fn write_status(&mut self, status: u8) -> io::Result<()> {
+        self.writer.write_all(&[status])?;
+        self.writer.flush()
+    }
+
+    /// Reads a status byte, turning anything but [STATUS_OK] into an error
+    fn read_status(&mut self) -> Result<(), DistributedError> {
+        let mut status = [0];
+        self.reader.read_exact(&mut status)?;
+        match status[0] {
+            STATUS_OK => Ok(()),
+            STATUS_MISMATCH => Err(DistributedError::SettingsMismatch),
+            STATUS_SCENE_MISMATCH => Err(DistributedError::SceneMismatch),
+            _ => Err(DistributedError::InvalidPeer),
+        }
+    }
+}
+
+/// A worker that a coordinator is connected to
+#[derive(Debug)]
+pub(crate) struct RemoteWorker {
+    addr: SocketAddr,
+    conn: Connection,
+    /// Whether anything has gone wrong with the worker, so that it shouldn't be used any more
+    failed: bool,
+}
+
+impl RemoteWorker {
+    /// Connects to a worker (on the coordinator's side), checking that it has the same settings `hash` and
+    /// `scene_hash`
+    pub fn connect(addr: impl ToSocketAddrs, hash: u64, scene_hash: u64) -> Result<Self, DistributedError> {
+        let stream = TcpStream::connect(addr)?;
+        let addr = stream.peer_addr()?;
+        let mut conn = Connection::new(stream)?;
+        conn.writer.write_all(&MAGIC)?;
+        conn.writer.write_all(&VERSION.to_le_bytes())?;
+        conn.writer.write_all(&hash.to_le_bytes())?;
+        conn.writer.write_all(&scene_hash.to_le_bytes())?;
+        conn.writer.flush()?;
+        conn.read_status().map_err(|err| match err {
+            // Anything that isn't a worker is unlikely to reply at all
+            DistributedError::IoError { source } if source.kind() == io::ErrorKind::UnexpectedEof => {
+                DistributedError::InvalidPeer
+            }
+            err => err,
+        })?;
+        debug!(target: RENDERER, ?addr, "connected to worker");
+        Ok(Self {
+            addr,
+            conn,
+            failed: false,
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr { self.addr }
+
+    pub fn failed(&self) -> bool { self.failed }
+
+    /// Has the worker render a tile, returning the colour of each pixel (in the order of [Tile::pixels()]).
+    ///
+    /// If this fails (including if the worker returns a colour that is negative or isn't finite), the worker is
+    /// marked as [failed](Self::failed())
+    pub fn render_tile(&mut self, request: &TileRequest) -> Result<Vec<Colour>, DistributedError> {
+        let result = self.render_tile_inner(request);
+        self.failed |= result.is_err();
+        result
+    }
+
+    fn render_tile_inner(&mut self, request: &TileRequest) -> Result<Vec<Colour>, DistributedError> {
+        request.write_to(&mut self.conn.writer)?;
+        self.conn.writer.flush()?;
+        self.conn.read_status()?;
+        (0..request.tile.area())
+            .map(|_| {
+                let channels = [(); 3].try_map(|()| read_bytes(&mut self.conn.reader).map(Channel::from_le_bytes))?;
+                // Added to the accumulation as-is, where a single bad colour would never average out
+                if !channels.iter().all(|c| c.is_finite() && *c >= 0.) {
+                    return Err(DistributedError::InvalidColour);
+                }
+                Ok(Colour::from(channels))
+            })
+            .collect()
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> { read_bytes(reader).map(u64::from_le_bytes) }
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> { read_bytes(reader).map(u32::from_le_bytes) }
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
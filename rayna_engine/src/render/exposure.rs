@@ -0,0 +1,128 @@
+//! # Module [crate::render::exposure]
+//!
+//! Automatic exposure (also known as *eye adaptation*).
+//!
+//! Each frame, the log-average luminance of the accumulated image is measured, and the exposure is adjusted
+//! so that the average brightness maps to a middle grey [key](AutoExposure::key). The exposure doesn't jump
+//! straight to the new value, but adapts towards it over time (at [AutoExposure::speed]), like an eye adjusting
+//! to a change in brightness.
+//!
+//! Exposure is measured in *stops* (EV), where each stop doubles the brightness of the image.
+
+use crate::core::types::{Colour, Image, Number};
+use serde::Serialize;
+use std::time::Duration;
+use valuable::Valuable;
+
+/// Settings for the automatic exposure. See the [module docs](self) for details.
+#[derive(Copy, Clone, Debug, PartialEq, Valuable, Serialize)]
+pub struct AutoExposure {
+    /// The lowest exposure that can be applied (stops)
+    pub min_ev: Number,
+    /// The highest exposure that can be applied (stops)
+    pub max_ev: Number,
+    /// How quickly the exposure adapts to changes in the scene's brightness.
+    ///
+    /// Roughly the inverse of the number of seconds it takes to adapt. Use [Number::INFINITY] to adapt instantly.
+    pub speed: Number,
+    /// The brightness that the average luminance of the image is mapped to, normally middle grey (`0.18`)
+    pub key: Number,
+}
+
+impl Default for AutoExposure {
+    fn default() -> Self {
+        Self {
+            min_ev: -10.,
+            max_ev: 10.,
+            speed: 2.,
+            key: 0.18,
+        }
+    }
+}
+
+/// The current (adapted) exposure of a renderer, which carries across frames
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExposureState {
+    /// The current exposure (stops), or [None] if nothing has been measured yet
+    ev: Option<Number>,
+}
+
+impl ExposureState {
+    /// Measures the luminance of the image, and adapts the exposure towards the target for that luminance.
+    ///
+    /// `elapsed` is how long it has been since the last adaptation. The first measurement always adapts instantly,
+    /// so that the first frame isn't wildly over- or under-exposed.
+    ///
+    /// Returns the new exposure (stops)
+    pub fn adapt(&mut self, settings: &AutoExposure, img: &Image, elapsed: Duration) -> Number {
+        let target = target_exposure(settings, log_average_luminance(img));
+        let ev = match self.ev {
+            None => target,
+            Some(current) => {
+                // Exponential decay towards the target, so the adaptation is independent of the frame rate
+                let t = 1. - (-settings.speed * elapsed.as_secs_f64()).exp();
+                current + ((target - current) * t.clamp(0., 1.))
+            }
+        };
+        // Clamp again in case the settings changed since the last frame
+        let ev = ev.clamp(settings.min_ev, settings.max_ev);
+        self.ev = Some(ev);
+        ev
+    }
+
+    /// Forgets the current exposure, so that the next measurement adapts instantly
+    pub fn reset(&mut self) { self.ev = None; }
+
+    /// The current exposure (stops), or [None] if nothing has been measured yet
+    pub fn ev(&self) -> Option<Number> { self.ev }
+}
+
+/// The relative luminance of a linear (sRGB primaries) colour
+pub fn luminance(c: Colour) -> Number {
+    let [r, g, b] = c.0.map(|c| c as Number);
+    (0.2126 * r) + (0.7152 * g) + (0.0722 * b)
+}
+
+/// Calculates the log-average (geometric mean) luminance of an image.
+///
+/// This is much less sensitive to small, very bright areas (like light sources) than the arithmetic mean.
+/// Invalid (NaN or negative) pixels are ignored.
+pub fn log_average_luminance(img: &Image) -> Number {
+    /*
+    CREDITS:
+
+    Title: "Photographic Tone Reproduction for Digital Images"
+    Author: Erik Reinhard, Michael Stark, Peter Shirley, James Ferwerda
+    URL: <https://doi.org/10.1145/566654.566575>
+    */
+    // Avoids the singularity for black pixels
+    const DELTA: Number = 1e-4;
+
+    let (sum, count) = img
+        .iter()
+        .map(|&c| luminance(c))
+        .filter(|l| *l >= 0.)
+        .fold((0., 0_usize), |(sum, count), l| (sum + (DELTA + l).ln(), count + 1));
+
+    if count == 0 {
+        return 0.;
+    }
+    (sum / count as Number).exp()
+}
+
+/// The exposure (stops) needed to map the given average luminance to the [key](AutoExposure::key),
+/// clamped to the allowed range
+pub fn target_exposure(settings: &AutoExposure, avg_luminance: Number) -> Number {
+    let ev = if avg_luminance > 0. {
+        (settings.key / avg_luminance).log2()
+    } else {
+        settings.max_ev
+    };
+    ev.clamp(settings.min_ev, settings.max_ev)
+}
+
+/// Scales the image by the given exposure (stops)
+pub fn apply_exposure(img: &mut Image, ev: Number) {
+    let scale = ev.exp2();
+    img.mapv_inplace(|c| c * scale);
+}
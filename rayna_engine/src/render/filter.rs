@@ -0,0 +1,169 @@
+//! # Module [crate::render::filter]
+//!
+//! **Reconstruction filters**: how the samples taken in and around each pixel are combined into the colour of that
+//! pixel. A wider filter blends in samples from the neighbouring pixels, which reduces aliasing on fine geometry, at
+//! the cost of a slightly softer image.
+//!
+//! Rather than spreading the samples evenly and weighting them by the filter (or splatting each sample into several
+//! pixels), the samples are placed with *filter importance sampling*: the offset of each sample from the centre of
+//! the pixel is drawn in proportion to the filter. So each pixel is still independent, every sample has the same
+//! weight (apart from the negative lobes of [PixelFilter::Mitchell]), and the accumulation works unchanged.
+//!
+//! The filter is chosen with [RenderOpts::filter](crate::render::render_opts::RenderOpts::filter).
+
+use crate::core::types::{Number, Vector2};
+use crate::shared::validate;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// The reconstruction filters that can be used for the pixels. See the [module docs](self) for details.
+///
+/// All the filters are separable, so the 2D filter is the product of the 1D filter in each axis
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum PixelFilter {
+    /// Every sample inside the pixel counts equally, and nothing outside it does. Sharp, but aliases the most
+    #[default]
+    Box,
+    /// Falls off linearly to zero, one pixel away from the centre
+    Tent,
+    /// A gaussian with a standard deviation of half a pixel, cut off smoothly at one and a half pixels. Soft, with
+    /// very little aliasing
+    Gaussian,
+    /// The Mitchell-Netravali cubic (with `B = C = 1/3`), which has small negative lobes that keep the image sharp,
+    /// while still reducing aliasing well
+    Mitchell,
+}
+
+impl PixelFilter {
+    /// How far the filter extends from the centre of the pixel (in pixels), in each axis
+    pub fn radius(&self) -> Number {
+        match self {
+            Self::Box => 0.5,
+            Self::Tent => 1.,
+            Self::Gaussian => 1.5,
+            Self::Mitchell => 2.,
+        }
+    }
+
+    /// The (unnormalised) value of the 1D filter, at the offset `x` from the centre of the pixel
+    pub fn evaluate(&self, x: Number) -> Number {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.;
+        }
+        match self {
+            Self::Box => 1.,
+            Self::Tent => 1. - x,
+            Self::Gaussian => {
+                const SIGMA: Number = 0.5;
+                let gaussian = |x: Number| Number::exp(-(x * x) / (2. * SIGMA * SIGMA));
+                // Subtract the value at the edge, so there's no discontinuity where it is cut off
+                gaussian(x) - gaussian(self.radius())
+            }
+            Self::Mitchell => mitchell(x),
+        }
+    }
+
+    /// Chooses where a sample should go, relative to the centre of the pixel, from the uniform numbers `u`
+    /// (each in the range `0.0..1.0`).
+    ///
+    /// Returns the offset from the centre of the pixel, and the weight that the sample's colour should be multiplied
+    /// by. The weights are normalised, so the filtered colour is the *mean* of the weighted samples
+    pub fn sample(&self, u: Vector2) -> (Vector2, Number) {
+        let (x, weight_x) = self.sample_1d(u.x);
+        let (y, weight_y) = self.sample_1d(u.y);
+        let offset = Vector2::new(x, y);
+        validate::vector2(&offset);
+        (offset, weight_x * weight_y)
+    }
+
+    fn sample_1d(&self, u: Number) -> (Number, Number) {
+        match self {
+            Self::Box => (u - 0.5, 1.),
+            // Inverse of the CDF of the triangle
+            Self::Tent => match u < 0.5 {
+                true => ((2. * u).sqrt() - 1., 1.),
+                false => (1. - (2. - (2. * u)).sqrt(), 1.),
+            },
+            Self::Gaussian => GAUSSIAN_TABLE.sample(u),
+            Self::Mitchell => MITCHELL_TABLE.sample(u),
+        }
+    }
+}
+
+fn mitchell(x: Number) -> Number {
+    /*
+    CREDITS:
+
+    Title: "Reconstruction Filters in Computer Graphics"
+    Author: Don P. Mitchell, Arun N. Netravali
+    URL: <https://doi.org/10.1145/378456.378514>
+    */
+    const B: Number = 1. / 3.;
+    const C: Number = 1. / 3.;
+
+    let (x2, x3) = (x * x, x * x * x);
+    let v = if x < 1. {
+        ((12. - (9. * B) - (6. * C)) * x3) + ((-18. + (12. * B) + (6. * C)) * x2) + (6. - (2. * B))
+    } else {
+        ((-B - (6. * C)) * x3) + (((6. * B) + (30. * C)) * x2) + (((-12. * B) - (48. * C)) * x) + ((8. * B) + (24. * C))
+    };
+    v / 6.
+}
+
+static GAUSSIAN_TABLE: Lazy<FilterTable> = Lazy::new(|| FilterTable::new(PixelFilter::Gaussian));
+static MITCHELL_TABLE: Lazy<FilterTable> = Lazy::new(|| FilterTable::new(PixelFilter::Mitchell));
+
+/// A tabulated 1D filter, so that filters without an analytic inverse CDF can still be importance-sampled.
+///
+/// The samples are drawn from a piecewise-constant approximation of the absolute value of the filter, and weighted
+/// by the ratio of the real filter to that approximation, so there is no bias
+struct FilterTable {
+    filter: PixelFilter,
+    /// The CDF at the edges of each bin, normalised to `0.0..=1.0`
+    cdf: Vec<Number>,
+    /// The (signed) integral of the filter
+    integral: Number,
+}
+
+impl FilterTable {
+    const BINS: usize = 256;
+
+    fn new(filter: PixelFilter) -> Self {
+        let bin_width = (2. * filter.radius()) / Self::BINS as Number;
+        let values = (0..Self::BINS)
+            .map(|i| filter.evaluate(-filter.radius() + ((i as Number + 0.5) * bin_width)))
+            .collect::<Vec<_>>();
+
+        let mut cdf = Vec::with_capacity(Self::BINS + 1);
+        cdf.push(0.);
+        for v in &values {
+            cdf.push(cdf.last().expect("cdf isn't empty") + (v.abs() * bin_width));
+        }
+        let total = cdf[Self::BINS];
+        cdf.iter_mut().for_each(|c| *c /= total);
+
+        Self {
+            filter,
+            cdf,
+            integral: values.iter().sum::<Number>() * bin_width,
+        }
+    }
+
+    fn sample(&self, u: Number) -> (Number, Number) {
+        let bin = (self.cdf.partition_point(|&c| c <= u) - 1).min(Self::BINS - 1);
+        let (start, end) = (self.cdf[bin], self.cdf[bin + 1]);
+        let bin_width = (2. * self.filter.radius()) / Self::BINS as Number;
+
+        let t = if end > start { (u - start) / (end - start) } else { 0.5 };
+        let x = -self.filter.radius() + ((bin as Number + t) * bin_width);
+        let pdf = (end - start) / bin_width;
+        let weight = match pdf > 0. {
+            true => self.filter.evaluate(x) / (pdf * self.integral),
+            false => 0.,
+        };
+        (x, weight)
+    }
+}
@@ -0,0 +1,69 @@
+//! # Module [crate::render::light_group]
+//!
+//! **Light groups**: rendering the contribution of each group of lights as a separate image.
+//!
+//! Lights (emissive materials) can be assigned to a named [LightGroup]
+//! (see [Material::light_group()](crate::material::Material::light_group)).
+//! Each group can then be rendered on its own (see [Renderer::render_light_group()]), and since light is additive,
+//! the final image is simply the sum of all the groups. This means the lighting can be rebalanced afterwards
+//! (dimming the key light, tinting the fill, etc.) by scaling each group before summing them, without having to
+//! re-render the scene.
+//!
+//! The skybox, and any lights that aren't in a group, are part of the *ungrouped* contribution
+//! (the group [None]), so that nothing is lost when summing the groups.
+//!
+//! All the groups can be exported as layers of a single EXR file, using [save_light_groups_exr()].
+
+use crate::material::light::LightGroup;
+use crate::object::Object;
+use crate::render::aov::{write_layers_exr, AovExportError};
+use crate::render::renderer::Renderer;
+use crate::skybox::Skybox;
+use rand_core::{RngCore, SeedableRng};
+use std::path::Path;
+
+/// The name of the layer the ungrouped lights are stored in, when exported
+pub const UNGROUPED_LAYER_NAME: &str = "ungrouped";
+
+/// Which lights contribute to a render
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum LightFilter<'a> {
+    /// All the lights contribute, as in a normal render
+    All,
+    /// Only the lights in the given group contribute ([None] being the ungrouped lights and the skybox)
+    Only(Option<&'a LightGroup>),
+}
+
+impl LightFilter<'_> {
+    /// Whether light from the given group should contribute to the render
+    pub fn includes(&self, group: Option<&LightGroup>) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(only) => *only == group,
+        }
+    }
+}
+
+/// Renders each of the light `groups` with the given renderer, and saves them all as layers of a single EXR file.
+///
+/// Each layer is named after its group, with the ungrouped lights named [UNGROUPED_LAYER_NAME].
+pub fn save_light_groups_exr<Obj, Sky, Rng>(
+    renderer: &mut Renderer<Obj, Sky, Rng>,
+    groups: &[Option<LightGroup>],
+    path: impl AsRef<Path>,
+) -> Result<(), AovExportError>
+where
+    Obj: Object,
+    Sky: Skybox,
+    Rng: RngCore + Send + SeedableRng,
+{
+    let images = groups
+        .iter()
+        .map(|group| (group, renderer.render_light_group(group.as_ref())))
+        .collect::<Vec<_>>();
+    let layers = images.iter().map(|(group, img)| {
+        let name = group.as_ref().map_or(UNGROUPED_LAYER_NAME, LightGroup::name);
+        (Some(name), &["R", "G", "B"][..], img)
+    });
+    write_layers_exr(layers, path)
+}
@@ -1,4 +1,22 @@
 pub mod accum_buffer;
+pub mod animation;
+pub mod aov;
+pub mod bake;
+pub mod cancel;
+pub mod checkpoint;
+pub mod compare;
+pub mod distributed;
+pub mod exposure;
+pub mod filter;
+pub mod light_group;
+pub mod output;
+pub mod photon;
+pub mod postprocess;
+pub mod progress;
+pub mod ray_stats;
 pub mod render;
 pub mod render_opts;
 pub mod renderer;
+pub mod reproject;
+pub mod tile;
+pub mod variance;
@@ -0,0 +1,214 @@
+//! # Module [crate::render::output]
+//!
+//! Saving rendered images to files, with [save()].
+//!
+//! The renderer outputs linear, unbounded (HDR) colours. [OutputFormat::Exr] keeps them as they are, but the other
+//! (8 and 16-bit) formats can only store colours in the range `0.0..=1.0`, and expect them to be sRGB-encoded.
+//! For those, each pixel is tone-mapped, clamped, converted from linear to sRGB, and then quantised to the bit depth
+//! of the format (in that order).
+//!
+//! Renders with a [transparent sky](crate::render::render_opts::RenderOpts::transparent_sky) can be saved with their
+//! alpha channel using [save_with_alpha()]. The renderer's images are premultiplied by the alpha, which is what EXR
+//! expects, but PNG stores straight alpha, so for PNGs the colours are divided by the alpha before anything else is
+//! done to them. The alpha itself is quantised linearly, without being sRGB-encoded.
+
+use crate::core::colour::linear_to_srgb;
+use crate::core::targets::RENDERER;
+use crate::core::types::{Channel, Colour, Image};
+use crate::render::aov::AovExportError;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ImageBuffer, ImageFormat, Rgb, Rgba};
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::debug;
+use valuable::Valuable;
+
+/// The quality that JPEG files are saved with, when it isn't given (see [OutputFormat::from_path()])
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// The file formats that images can be saved in
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize)]
+pub enum OutputFormat {
+    /// 8 bits per channel PNG
+    Png,
+    /// 16 bits per channel PNG, which avoids banding in smooth gradients
+    Png16,
+    /// 8 bits per channel JPEG, with a quality of `1..=100`
+    Jpeg { quality: u8 },
+    /// 32-bit float OpenEXR, which keeps the linear HDR data. See [Image::save_exr()]
+    Exr,
+}
+
+impl OutputFormat {
+    /// Guesses the format from the extension of the path. PNGs are 8-bit, and JPEGs use [DEFAULT_JPEG_QUALITY]
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, OutputError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png") => Ok(Self::Png),
+            Some("jpg" | "jpeg") => Ok(Self::Jpeg {
+                quality: DEFAULT_JPEG_QUALITY,
+            }),
+            Some("exr") => Ok(Self::Exr),
+            _ => Err(OutputError::UnknownFormat {
+                path: path.to_path_buf(),
+            }),
+        }
+    }
+
+    /// Whether the format can store an alpha channel (see [save_with_alpha()])
+    pub fn supports_alpha(&self) -> bool { !matches!(self, Self::Jpeg { .. }) }
+}
+
+#[derive(Error, Debug)]
+pub enum OutputError {
+    #[error("couldn't tell the image format from the extension of {path:?}")]
+    UnknownFormat { path: PathBuf },
+    #[error("failed to create image file {path:?}")]
+    IoError {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to encode image {path:?}")]
+    ImageError {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("{format:?} images can't store an alpha channel")]
+    NoAlpha { format: OutputFormat },
+    #[error("alpha has dimensions {actual:?}, but the image has dimensions {expected:?}")]
+    AlphaDimensionMismatch { expected: [usize; 2], actual: [usize; 2] },
+    #[error("failed to save EXR image")]
+    ExrError {
+        #[backtrace]
+        #[from]
+        source: AovExportError,
+    },
+}
+
+/// Saves the image to a file, in the given format.
+///
+/// The `tonemap` function maps the linear HDR colours into the displayable range, before they are clamped and
+/// sRGB-encoded (use [std::convert::identity] to just clamp them). It isn't used for [OutputFormat::Exr], which is
+/// saved as-is. See the [module docs](self) for details
+pub fn save(
+    image: &Image,
+    path: impl AsRef<Path>,
+    format: OutputFormat,
+    tonemap: impl Fn(Colour) -> Colour,
+) -> Result<(), OutputError> {
+    write(image, None, path.as_ref(), format, &tonemap)
+}
+
+/// Saves the image to a file with an alpha channel, in the given format. See [save()].
+///
+/// The `alpha` should have the alpha in all of its channels, and the image should already be premultiplied by it
+/// (like [Render::alpha](crate::render::render::Render::alpha)). Formats that can't store alpha (see
+/// [OutputFormat::supports_alpha()]) return [OutputError::NoAlpha]. See the [module docs](self) for details
+pub fn save_with_alpha(
+    image: &Image,
+    alpha: &Image,
+    path: impl AsRef<Path>,
+    format: OutputFormat,
+    tonemap: impl Fn(Colour) -> Colour,
+) -> Result<(), OutputError> {
+    if !format.supports_alpha() {
+        return Err(OutputError::NoAlpha { format });
+    }
+    let (expected, actual) = ([image.width(), image.height()], [alpha.width(), alpha.height()]);
+    if expected != actual {
+        return Err(OutputError::AlphaDimensionMismatch { expected, actual });
+    }
+    write(image, Some(alpha), path.as_ref(), format, &tonemap)
+}
+
+fn write(
+    image: &Image,
+    alpha: Option<&Image>,
+    path: &Path,
+    format: OutputFormat,
+    tonemap: &impl Fn(Colour) -> Colour,
+) -> Result<(), OutputError> {
+    debug!(target: RENDERER, ?path, ?format, alpha = alpha.is_some(), "saving image");
+
+    let encode_err = |source| OutputError::ImageError {
+        path: path.to_path_buf(),
+        source,
+    };
+    match (format, alpha) {
+        (OutputFormat::Exr, None) => image.save_exr(path)?,
+        (OutputFormat::Exr, Some(alpha)) => image.save_exr_with_alpha(alpha, &[], path)?,
+        (OutputFormat::Png, None) => quantise::<u8>(image, tonemap)
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(encode_err)?,
+        (OutputFormat::Png, Some(alpha)) => quantise_alpha::<u8>(image, alpha, tonemap)
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(encode_err)?,
+        (OutputFormat::Png16, None) => quantise::<u16>(image, tonemap)
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(encode_err)?,
+        (OutputFormat::Png16, Some(alpha)) => quantise_alpha::<u16>(image, alpha, tonemap)
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(encode_err)?,
+        (OutputFormat::Jpeg { .. }, Some(_)) => return Err(OutputError::NoAlpha { format }),
+        (OutputFormat::Jpeg { quality }, None) => {
+            let file = File::create(path).map_err(|source| OutputError::IoError {
+                path: path.to_path_buf(),
+                source,
+            })?;
+            let mut encoder = JpegEncoder::new_with_quality(BufWriter::new(file), quality.clamp(1, 100));
+            encoder
+                .encode_image(&quantise::<u8>(image, tonemap))
+                .map_err(encode_err)?
+        }
+    }
+    Ok(())
+}
+
+/// An integer channel type that images can be quantised to
+trait Quantised: image::Primitive {
+    /// Converts a channel in the range `0.0..=1.0` to the nearest integer value
+    fn from_channel(c: Channel) -> Self;
+}
+
+impl Quantised for u8 {
+    fn from_channel(c: Channel) -> Self { (c * Self::MAX as Channel).round() as Self }
+}
+
+impl Quantised for u16 {
+    fn from_channel(c: Channel) -> Self { (c * Self::MAX as Channel).round() as Self }
+}
+
+/// Tone-maps, sRGB-encodes and quantises each pixel of the image
+fn quantise<T: Quantised>(image: &Image, tonemap: &impl Fn(Colour) -> Colour) -> ImageBuffer<Rgb<T>, Vec<T>> {
+    ImageBuffer::from_fn(image.width() as u32, image.height() as u32, |x, y| {
+        let colour = tonemap(image[(x as usize, y as usize)]);
+        Rgb(colour.0.map(|c| T::from_channel(linear_to_srgb(c))))
+    })
+}
+
+/// Un-premultiplies, tone-maps, sRGB-encodes and quantises each pixel of the image, along with its alpha
+fn quantise_alpha<T: Quantised>(
+    image: &Image,
+    alpha: &Image,
+    tonemap: &impl Fn(Colour) -> Colour,
+) -> ImageBuffer<Rgba<T>, Vec<T>> {
+    ImageBuffer::from_fn(image.width() as u32, image.height() as u32, |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let a = alpha[(x, y)][0].clamp(0., 1.);
+        // Nothing is there when the alpha is zero, so the colour doesn't matter
+        let straight = if a > 0. { image[(x, y)] / a } else { Colour::BLACK };
+        let [r, g, b] = tonemap(straight).0.map(|c| T::from_channel(linear_to_srgb(c)));
+        Rgba([r, g, b, T::from_channel(a)])
+    })
+}
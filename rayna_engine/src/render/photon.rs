@@ -0,0 +1,210 @@
+//! # Module [crate::render::photon]
+//!
+//! **Photon mapping**: rendering light that has been focused by mirrors and glass (*caustics*), which the normal path
+//! tracer can barely find. See [RenderMode::PhotonMapping](crate::render::render_opts::RenderMode::PhotonMapping).
+//!
+//! Each pass, photons are traced outwards from the lights in the scene (see
+//! [LightObject](crate::object::light::LightObject)), and stored in a [PhotonMap] wherever they land on a diffuse
+//! surface. The camera rays are then followed through any [specular](Material::is_specular) surfaces, until they hit a
+//! diffuse one, where the light is estimated from the photons that landed close by.
+//!
+//! This is *stochastic progressive photon mapping*, using the probabilistic formulation: every pass is an independent
+//! estimate (so the passes can be accumulated like normal samples), but the radius that photons are gathered from
+//! shrinks a little with each pass (see [pass_radius()]), so that the blurring of the lighting goes away as the render
+//! converges.
+//!
+//! Only the lights emit photons. The skybox is still seen directly, and through specular surfaces, but it doesn't
+//! light the diffuse surfaces in this mode.
+
+/*
+CREDITS:
+
+Title: "Progressive Photon Mapping: A Probabilistic Approach"
+Authors:
+    - Claude Knaus
+    - Matthias Zwicker
+URL: <https://doi.org/10.1145/1966394.1966404>
+Publisher: ACM Transactions on Graphics
+Version: vol. 30, no. 3, 2011
+*/
+
+use crate::core::types::{Channel, Colour, Number, Point3, Vector3};
+use crate::material::clay::Shading;
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::object::light::{LightShape, SceneLight};
+use crate::object::Object;
+use crate::render::render_opts::RenderOpts;
+use crate::scene::Scene;
+use crate::shared::intersect::{FullIntersection, Intersection};
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use crate::shared::rng;
+use glamour::AngleConsts;
+use rand::Rng;
+use rand_core::RngCore;
+use std::collections::HashMap;
+
+/// How quickly the gathering radius shrinks between passes (`0.0..1.0`). Smaller values shrink faster, which reduces
+/// the blurring sooner, but leaves more noise
+pub const RADIUS_ALPHA: Number = 2. / 3.;
+
+/// The radius that photons are gathered from in the first pass, in pixels (at the camera's focus distance)
+pub const INITIAL_RADIUS_PIXELS: Number = 4.;
+
+/// A packet of light that has been traced from a light, and landed on a diffuse surface
+#[derive(Copy, Clone, Debug)]
+pub struct Photon {
+    pub pos: Point3,
+    /// The direction the photon was travelling in when it landed
+    pub dir: Vector3,
+    /// How much light the photon carries (flux)
+    pub power: Colour,
+}
+
+/// The photons from a pass, sorted into a grid of cells so that the ones near a point can be found quickly.
+///
+/// The cells are as large as the gathering radius, so only the neighbouring cells need to be searched
+#[derive(Clone, Debug)]
+pub struct PhotonMap {
+    cells: HashMap<[i64; 3], Vec<Photon>>,
+    radius: Number,
+}
+
+impl PhotonMap {
+    pub fn new(photons: impl IntoIterator<Item = Photon>, radius: Number) -> Self {
+        let mut cells = HashMap::<[i64; 3], Vec<Photon>>::new();
+        for photon in photons {
+            cells.entry(Self::cell(photon.pos, radius)).or_default().push(photon);
+        }
+        Self { cells, radius }
+    }
+
+    /// The radius that photons are gathered from
+    pub fn radius(&self) -> Number { self.radius }
+
+    pub fn len(&self) -> usize { self.cells.values().map(Vec::len).sum() }
+
+    pub fn is_empty(&self) -> bool { self.cells.is_empty() }
+
+    fn cell(pos: Point3, radius: Number) -> [i64; 3] { pos.to_array().map(|c| (c / radius).floor() as i64) }
+
+    /// Finds the photons within the gathering radius of the given point
+    pub fn photons_near(&self, pos: Point3) -> impl Iterator<Item = &Photon> {
+        let [x, y, z] = Self::cell(pos, self.radius);
+        let radius_sq = self.radius * self.radius;
+        itertools::iproduct!(-1..=1, -1..=1, -1..=1)
+            .filter_map(move |(dx, dy, dz)| self.cells.get(&[x + dx, y + dy, z + dz]))
+            .flatten()
+            .filter(move |photon| (photon.pos - pos).length_squared() <= radius_sq)
+    }
+
+    /// Estimates the light that is reflected back along the `ray` by a diffuse surface, from the photons that landed
+    /// around the intersection
+    pub fn gather<Mat: Material>(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        material: &Mat,
+        rng: &mut dyn RngCore,
+    ) -> Colour {
+        let mut sum = Colour::BLACK;
+        for photon in self.photons_near(intersection.pos_w) {
+            // Photons that landed on the other side of the surface can't be seen from this side
+            if Vector3::dot(photon.dir, intersection.ray_normal) >= 0. {
+                continue;
+            }
+            // The reflected light is weighted by the material's scattering PDF, which for a diffuse surface is
+            // `cos / PI`, so the `albedo * power` that it returns still needs dividing by PI to be the BRDF
+            let incoming = Ray::new(intersection.pos_w, -photon.dir).with_time(ray.time());
+            sum += material.reflected_light(ray, intersection, &incoming, &photon.power, rng);
+        }
+        // Spread over the area of the disk that the photons were gathered from
+        sum / (Number::PI * Number::PI * self.radius * self.radius) as Channel
+    }
+}
+
+/// The radius to gather photons from in a given pass (starting from `0`), which shrinks a little every pass so that
+/// the estimate converges
+pub fn pass_radius(initial: Number, pass: u64) -> Number {
+    // The area shrinks by `(i + alpha) / (i + 1)` each pass
+    let area_scale = (1..=pass)
+        .map(|i| (i as Number + RADIUS_ALPHA) / (i as Number + 1.))
+        .product::<Number>();
+    initial * area_scale.sqrt()
+}
+
+/// Emits a single photon from one of the `lights` (chosen at random), and traces it through the scene, storing it in
+/// `photons` wherever it lands on a diffuse surface.
+///
+/// The power of the photon is divided between all of the `photon_count` photons that are emitted in the pass. The
+/// photon bounces at most [RenderOpts::ray_depth] times, off surfaces [shaded](Shading) as they would be for the camera
+pub fn trace_photon<Obj: Object, Sky>(
+    scene: &Scene<Obj, Sky>,
+    lights: &[SceneLight<'_, Obj::Mat>],
+    photon_count: usize,
+    opts: &RenderOpts,
+    interval: &Interval<Number>,
+    rng: &mut dyn RngCore,
+    photons: &mut Vec<Photon>,
+) {
+    if lights.is_empty() {
+        return;
+    }
+    let light = &lights[rng.gen_range(0..lights.len())];
+
+    // Planar lights emit from both sides, and spheres only outwards
+    let sample = light.shape.sample_surface(rng);
+    let (normal, sides) = match light.shape {
+        LightShape::Sphere(_) => (sample.normal, 1.),
+        LightShape::Parallelogram(_) | LightShape::Disk(_) => match rng.gen::<bool>() {
+            true => (sample.normal, 2.),
+            false => (-sample.normal, 2.),
+        },
+    };
+
+    // Look back at the light from just in front of the point, to find out how much it emits there
+    let probe = Ray::new(sample.pos + (normal * 1e-2), -normal);
+    let Some(surface) = light.shape.intersect(&probe, &Interval::from(0. ..1.), rng) else {
+        return;
+    };
+    let emitted = light.material.emitted_light(&probe, &surface, rng);
+    // Emitting with a cosine distribution, the flux of the whole light is `PI * area * radiance`
+    let flux_scale = Number::PI * light.shape.area() * sides * lights.len() as Number / photon_count as Number;
+    let mut power = emitted * flux_scale as Channel;
+
+    let dir = (normal + rng::normal_on_unit_sphere(rng))
+        .try_normalize()
+        .unwrap_or(normal);
+    let mut ray = Ray::new(sample.pos, dir);
+    for _ in 0..=opts.ray_depth {
+        let Some(FullIntersection { intersection, material }) = scene.objects.full_intersect(&ray, interval, rng)
+        else {
+            return;
+        };
+        let material = Shading::new(material, opts.clay);
+        if !material.is_specular() {
+            photons.push(Photon {
+                pos: intersection.pos_w,
+                dir: ray.dir(),
+                power,
+            });
+        }
+
+        let Some(scatter) = material.scatter(&ray, &intersection, rng) else {
+            return;
+        };
+        let future_ray = Ray::new(intersection.pos_w, scatter);
+        let reflected = material.reflected_light(&ray, &intersection, &future_ray, &power, rng);
+
+        // Russian roulette, so that photons which have lost most of their power stop being traced, without the
+        // photons that do survive being biased
+        let brightest = |c: Colour| c.into_iter().fold(0., Channel::max);
+        let survival = (brightest(reflected) / brightest(power)).min(1.);
+        if brightest(reflected) <= 0. || rng.gen::<Channel>() >= survival {
+            return;
+        }
+        power = reflected / survival;
+        ray = future_ray;
+    }
+}
@@ -0,0 +1,169 @@
+//! # Module [crate::render::postprocess::denoise]
+//!
+//! A lightweight, built-in denoiser, for when a proper (machine-learning) denoiser isn't available.
+//!
+//! This is an *edge-avoiding À-Trous* filter: a small blur that is repeated several times, with the samples spread
+//! twice as far apart each time (the kernel has "holes" in it), so that it covers a large area cheaply. Each sample
+//! is weighted by how similar it is to the centre pixel, both in colour and in the [AOVs](crate::render::aov) of the
+//! surface (normal, depth and albedo). This keeps the edges of objects and textures sharp, while the noise on flat
+//! areas is smoothed away.
+//!
+//! The denoiser is enabled with [RenderOpts::denoise](crate::render::render_opts::RenderOpts::denoise), and is only
+//! applied to the output of each frame (not the accumulation), so it can be toggled on any frame.
+
+use crate::core::types::{Channel, Colour, Image};
+use crate::render::aov::Aov;
+use ndarray::Zip;
+use serde::Serialize;
+use std::ops::DerefMut as _;
+use valuable::Valuable;
+
+/// Settings for the denoiser. See the [module docs](self) for details.
+///
+/// Each of the `sigma`s controls how different a sample can be from the centre pixel before it stops being blended
+/// in: smaller values keep more detail (and more noise). Use [Channel::INFINITY] to ignore that property entirely
+#[derive(Copy, Clone, Debug, PartialEq, Valuable, Serialize)]
+pub struct Denoise {
+    /// How many times the filter is applied. Each iteration doubles the spacing of the samples, so the filter covers
+    /// about `2^(iterations + 2)` pixels across
+    pub iterations: usize,
+    /// How different the colours can be. This is halved each iteration, since there is less noise left to remove
+    pub colour_sigma: Channel,
+    /// How different the surface normals can be
+    pub normal_sigma: Channel,
+    /// How different the depths can be, relative to the depth of the centre pixel
+    pub depth_sigma: Channel,
+    /// How different the albedos (surface colours) can be
+    pub albedo_sigma: Channel,
+}
+
+impl Default for Denoise {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            colour_sigma: 1.,
+            normal_sigma: 0.3,
+            depth_sigma: 0.05,
+            albedo_sigma: 0.1,
+        }
+    }
+}
+
+/// The [AOVs](Aov) that guide the denoiser, so it can tell where the edges in the scene are
+#[derive(Copy, Clone, Debug)]
+pub struct DenoiseGuides<'a> {
+    /// The [Aov::Normal] image
+    pub normal: &'a Image,
+    /// The [Aov::Depth] image
+    pub depth: &'a Image,
+    /// The [Aov::Albedo] image
+    pub albedo: &'a Image,
+}
+
+impl<'a> DenoiseGuides<'a> {
+    /// Finds the guides in a set of rendered AOVs (such as [Render::aovs](crate::render::render::Render::aovs)),
+    /// if they are all there
+    pub fn from_aovs(aovs: &'a [(Aov, Image)]) -> Option<Self> {
+        let find = |wanted: Aov| aovs.iter().find(|(aov, _)| *aov == wanted).map(|(_, img)| img);
+        Some(Self {
+            normal: find(Aov::Normal)?,
+            depth: find(Aov::Depth)?,
+            albedo: find(Aov::Albedo)?,
+        })
+    }
+}
+
+/// Denoises the image, using the guides to keep the edges sharp.
+///
+/// This uses [rayon] to filter the pixels in parallel, so should be called inside the thread pool that it should use.
+///
+/// # Panics
+/// Panics if the guides don't have the same dimensions as the image
+pub fn denoise(img: &Image, guides: &DenoiseGuides, settings: &Denoise) -> Image {
+    /*
+    CREDITS:
+
+    Title: "Edge-Avoiding À-Trous Wavelet Transform for fast Global Illumination Filtering"
+    Author: Holger Dammertz, Daniel Sewtz, Johannes Hanika, Hendrik P. A. Lensch
+    URL: <https://doi.org/10.2312/EGGH/HPG10/067-075>
+    */
+    let (w, h) = (img.width(), img.height());
+    for guide in [guides.normal, guides.depth, guides.albedo] {
+        assert_eq!(
+            [guide.width(), guide.height()],
+            [w, h],
+            "denoise guides must have the same dimensions as the image"
+        );
+    }
+
+    let mut current = img.clone();
+    let mut colour_sigma = settings.colour_sigma;
+    for iteration in 0..settings.iterations {
+        let step = 1 << iteration;
+        let mut next = Image::new_blank(w, h);
+        Zip::indexed(next.deref_mut()).par_for_each(|(x, y), px| {
+            *px = filter_px(&current, guides, settings, colour_sigma, step, x, y);
+        });
+        current = next;
+        colour_sigma /= 2.;
+    }
+    current
+}
+
+/// Filters a single pixel with the (sparse) 5x5 kernel, where the samples are `step` pixels apart
+fn filter_px(
+    img: &Image,
+    guides: &DenoiseGuides,
+    settings: &Denoise,
+    colour_sigma: Channel,
+    step: usize,
+    x: usize,
+    y: usize,
+) -> Colour {
+    // B3-spline, which is close to a gaussian
+    const KERNEL: [Channel; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+    let centre = (x, y);
+    let depth = guides.depth[centre][0];
+    let (mut sum, mut total_weight) = (Colour::BLACK, 0.);
+
+    for (i, kx) in KERNEL.iter().enumerate() {
+        for (j, ky) in KERNEL.iter().enumerate() {
+            // Skip samples that fall outside of the image
+            let (Some(qx), Some(qy)) = (
+                (x + (i * step)).checked_sub(2 * step).filter(|&qx| qx < img.width()),
+                (y + (j * step)).checked_sub(2 * step).filter(|&qy| qy < img.height()),
+            ) else {
+                continue;
+            };
+            let sample = (qx, qy);
+
+            let sample_depth = guides.depth[sample][0];
+            let depth_diff = (depth - sample_depth).abs() / depth.max(sample_depth).max(Channel::EPSILON);
+
+            let weight = kx
+                * ky
+                * similarity(distance_sq(img[centre], img[sample]), colour_sigma)
+                * similarity(distance_sq(guides.normal[centre], guides.normal[sample]), settings.normal_sigma)
+                * similarity(depth_diff * depth_diff, settings.depth_sigma)
+                * similarity(distance_sq(guides.albedo[centre], guides.albedo[sample]), settings.albedo_sigma);
+            sum += img[sample] * weight;
+            total_weight += weight;
+        }
+    }
+
+    // The centre pixel is always fully similar to itself, so the weight is never zero
+    sum / total_weight
+}
+
+/// The squared (euclidean) distance between two colours
+fn distance_sq(a: Colour, b: Colour) -> Channel { (a - b).into_iter().map(|c| c * c).sum() }
+
+/// How similar two values are (`0.0..=1.0`), given the squared distance between them
+fn similarity(distance_sq: Channel, sigma: Channel) -> Channel {
+    // Identical values are always similar, even when the sigma is zero
+    if distance_sq == 0. {
+        return 1.;
+    }
+    (-distance_sq / (sigma * sigma)).exp()
+}
@@ -0,0 +1,10 @@
+//! # Module [crate::render::postprocess]
+//!
+//! Filters that are applied to the rendered image once it has been accumulated, before it is returned.
+//!
+//! These only change the output of each frame, and not the accumulation buffer, so they can be switched on and off
+//! (or have their settings changed) without restarting the accumulation.
+
+pub mod denoise;
+pub mod tone_mapping;
+pub mod white_balance;
@@ -0,0 +1,133 @@
+//! # Module [crate::render::postprocess::tone_mapping]
+//!
+//! **Tone mapping**: compressing the unbounded (HDR) colours that the renderer outputs into the `0.0..=1.0` range
+//! that can be displayed, so that bright areas (like light sources) fade smoothly to white instead of clipping.
+//!
+//! The operator is chosen with [RenderOpts::tone_mapping](crate::render::render_opts::RenderOpts::tone_mapping),
+//! and is applied to the output image after the [exposure](crate::render::exposure). The result is still linear,
+//! and still needs to be sRGB-encoded to be displayed (see [crate::render::output]).
+
+use crate::core::types::{Channel, Colour, Image};
+use crate::render::exposure::luminance;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::ops::DerefMut as _;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+/// The tone mapping operators that can be applied to an image. See the [module docs](self) for details.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum ToneMapping {
+    /// No tone mapping: the colours are left as-is, so anything brighter than white is clipped when displayed
+    #[default]
+    Linear,
+    /// The simple Reinhard operator, applied to the luminance so that the hue is kept.
+    /// Very bright, saturated colours can still be brighter than white in some channels
+    Reinhard,
+    /// Krzysztof Narkowicz's fast curve fit of the ACES filmic tone mapping, which slightly over-saturates bright
+    /// colours
+    AcesFitted,
+    /// Stephen Hill's more accurate fit of the ACES reference and output transforms, which desaturates bright
+    /// colours towards white like film does
+    AcesHill,
+    /// John Hable's filmic curve from *Uncharted 2*, which has a softer toe and shoulder than ACES
+    Filmic,
+}
+
+impl ToneMapping {
+    /// Applies the tone mapping operator to a single (linear) colour
+    pub fn apply(&self, c: Colour) -> Colour {
+        match self {
+            Self::Linear => c,
+            Self::Reinhard => reinhard(c),
+            Self::AcesFitted => aces_fitted(c),
+            Self::AcesHill => aces_hill(c),
+            Self::Filmic => filmic(c),
+        }
+    }
+}
+
+/// Applies the tone mapping operator to every pixel of the image, in-place.
+///
+/// This uses [rayon], so should be called inside the thread pool that it should use.
+pub fn apply_tone_mapping(img: &mut Image, mapping: ToneMapping) {
+    if mapping == ToneMapping::Linear {
+        return;
+    }
+    img.deref_mut().into_par_iter().for_each(|c| *c = mapping.apply(*c));
+}
+
+fn reinhard(c: Colour) -> Colour {
+    /*
+    CREDITS:
+
+    Title: "Photographic Tone Reproduction for Digital Images"
+    Author: Erik Reinhard, Michael Stark, Peter Shirley, James Ferwerda
+    URL: <https://doi.org/10.1145/566654.566575>
+    */
+    let lum = luminance(c) as Channel;
+    if lum <= 0. {
+        return Colour::BLACK;
+    }
+    c * (1. / (1. + lum))
+}
+
+fn aces_fitted(c: Colour) -> Colour {
+    /* CREDITS: Krzysztof Narkowicz/"ACES Filmic Tone Mapping Curve"/https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/ */
+    const A: Channel = 2.51;
+    const B: Channel = 0.03;
+    const C: Channel = 2.43;
+    const D: Channel = 0.59;
+    const E: Channel = 0.14;
+
+    c.map(|x| {
+        // The curve was fitted to an image that was exposed slightly darker
+        let x = x.max(0.) * 0.6;
+        ((x * ((A * x) + B)) / ((x * ((C * x) + D)) + E)).clamp(0., 1.)
+    })
+}
+
+fn aces_hill(c: Colour) -> Colour {
+    /* CREDITS: Stephen Hill/"BakingLab"/https://github.com/TheRealMJP/BakingLab/blob/master/BakingLab/ACES.hlsl */
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: [[Channel; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: [[Channel; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let mul = |m: &[[Channel; 3]; 3], c: Colour| {
+        Colour::from(m.map(|row| row.iter().zip(c.0).map(|(m, c)| m * c).sum::<Channel>()))
+    };
+
+    let c = mul(&INPUT, c.map(|x| x.max(0.)));
+    // The reference rendering transform and output device transform, combined
+    let c = c.map(|v| {
+        let a = (v * (v + 0.024_578_6)) - 0.000_090_537;
+        let b = (v * ((0.983_729 * v) + 0.432_951)) + 0.238_081;
+        a / b
+    });
+    mul(&OUTPUT, c).map(|x| x.clamp(0., 1.))
+}
+
+fn filmic(c: Colour) -> Colour {
+    /* CREDITS: John Hable/"Filmic Tonemapping Operators"/http://filmicworlds.com/blog/filmic-tonemapping-operators/ */
+    const A: Channel = 0.15; // Shoulder strength
+    const B: Channel = 0.50; // Linear strength
+    const C: Channel = 0.10; // Linear angle
+    const D: Channel = 0.20; // Toe strength
+    const E: Channel = 0.02; // Toe numerator
+    const F: Channel = 0.30; // Toe denominator
+    // The linear value that is mapped to white
+    const WHITE: Channel = 11.2;
+    const EXPOSURE_BIAS: Channel = 2.;
+
+    let curve = |x: Channel| (((x * ((A * x) + (C * B))) + (D * E)) / ((x * ((A * x) + B)) + (D * F))) - (E / F);
+    let white_scale = 1. / curve(WHITE);
+    c.map(|x| (curve(x.max(0.) * EXPOSURE_BIAS) * white_scale).clamp(0., 1.))
+}
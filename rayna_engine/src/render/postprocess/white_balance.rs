@@ -0,0 +1,149 @@
+//! # Module [crate::render::postprocess::white_balance]
+//!
+//! **White balance**: correcting the colour cast of the lighting in a scene, like the white balance setting of a
+//! camera. The [temperature](WhiteBalance::temperature) is the colour of the light that should appear white, so
+//! setting it lower (warmer) makes the image bluer, and setting it higher (cooler) makes the image more orange.
+//! The [tint](WhiteBalance::tint) corrects for a green or magenta cast.
+//!
+//! The colours are adapted in LMS (cone response) space, using the Bradford transform, which keeps the colours more
+//! natural than simply scaling the RGB channels.
+//!
+//! White balance is set with [RenderOpts::white_balance](crate::render::render_opts::RenderOpts::white_balance),
+//! and is applied to the output image after the exposure but before tone mapping, so it can be changed without
+//! re-tracing the scene.
+
+use crate::core::types::{Channel, Colour, Image, Number};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::ops::DerefMut as _;
+use valuable::Valuable;
+
+type Matrix = [[Number; 3]; 3];
+
+/// White balance settings. See the [module docs](self) for details.
+#[derive(Copy, Clone, Debug, PartialEq, Valuable, Serialize)]
+pub struct WhiteBalance {
+    /// The colour temperature (in Kelvin) of the light that should appear white. This is clamped to
+    /// [Self::TEMPERATURE_RANGE]. `6500` (daylight) leaves the image unchanged
+    pub temperature: Number,
+    /// Corrects for a green (positive) or magenta (negative) cast in the light, normally in the range `-1.0..=1.0`.
+    /// `0.0` leaves the image unchanged
+    pub tint: Number,
+}
+
+impl WhiteBalance {
+    /// The settings that leave the image unchanged
+    pub const NEUTRAL: Self = Self {
+        temperature: 6500.,
+        tint: 0.,
+    };
+    /// The range of temperatures (Kelvin) that can be corrected for
+    pub const TEMPERATURE_RANGE: std::ops::RangeInclusive<Number> = 1667.0..=25000.0;
+
+    /// Whether these settings leave the image unchanged
+    pub fn is_neutral(&self) -> bool { *self == Self::NEUTRAL }
+
+    /// Calculates the matrix that converts a linear (sRGB primaries) colour lit by the light with these settings,
+    /// into one that is lit by the neutral light
+    pub fn matrix(&self) -> Matrix {
+        /*
+        CREDITS:
+
+        Title: "Chromatic Adaptation"
+        Author: Bruce Lindbloom
+        URL: <http://www.brucelindbloom.com/index.html?Eqn_ChromAdapt.html>
+        */
+        const XYZ_FROM_RGB: Matrix = [
+            [0.4124564, 0.3575761, 0.1804375],
+            [0.2126729, 0.7151522, 0.0721750],
+            [0.0193339, 0.1191920, 0.9503041],
+        ];
+        const RGB_FROM_XYZ: Matrix = [
+            [3.2404542, -1.5371385, -0.4985314],
+            [-0.9692660, 1.8760108, 0.0415560],
+            [0.0556434, -0.2040259, 1.0572252],
+        ];
+        const LMS_FROM_XYZ: Matrix = [
+            [0.8951, 0.2664, -0.1614],
+            [-0.7502, 1.7135, 0.0367],
+            [0.0389, -0.0685, 1.0296],
+        ];
+        const XYZ_FROM_LMS: Matrix = [
+            [0.9869929, -0.1470543, 0.1599627],
+            [0.4323053, 0.5183603, 0.0492912],
+            [-0.0085287, 0.0400428, 0.9684867],
+        ];
+
+        let white_lms = |wb: &Self| mul_vec(&LMS_FROM_XYZ, illuminant_xyz(wb.temperature, wb.tint));
+        let (source, target) = (white_lms(self), white_lms(&Self::NEUTRAL));
+        // Von Kries: scale each cone response so the source white becomes the target white
+        let scale = [0, 1, 2].map(|i| {
+            let mut row = [0.; 3];
+            row[i] = target[i] / source[i];
+            row
+        });
+
+        [RGB_FROM_XYZ, XYZ_FROM_LMS, scale, LMS_FROM_XYZ, XYZ_FROM_RGB]
+            .into_iter()
+            .reduce(|a, b| mul(&a, &b))
+            .expect("there are matrices")
+    }
+}
+
+impl Default for WhiteBalance {
+    fn default() -> Self { Self::NEUTRAL }
+}
+
+/// Applies the white balance to every pixel of the image, in-place.
+///
+/// This uses [rayon], so should be called inside the thread pool that it should use.
+pub fn apply_white_balance(img: &mut Image, settings: &WhiteBalance) {
+    if settings.is_neutral() {
+        return;
+    }
+    let matrix = settings.matrix();
+    img.deref_mut().into_par_iter().for_each(|c| {
+        let rgb = mul_vec(&matrix, c.0.map(|c| c as Number));
+        *c = Colour::from(rgb.map(|c| c as Channel));
+    });
+}
+
+/// The XYZ colour (with `Y = 1`) of a black-body light at the given temperature, shifted by the tint
+fn illuminant_xyz(temperature: Number, tint: Number) -> [Number; 3] {
+    /*
+    CREDITS:
+
+    Title: "Design of Advanced Color Temperature Control System for HDTV Applications"
+    Author: Bongsoon Kang, Ohak Moon, Changhee Hong, Honam Lee, Bonghwan Cho, Youngsun Kim
+    URL: <https://doi.org/10.3938/jkps.41.865>
+    */
+    let t = temperature.clamp(
+        *WhiteBalance::TEMPERATURE_RANGE.start(),
+        *WhiteBalance::TEMPERATURE_RANGE.end(),
+    );
+    let (t1, t2, t3) = (1e3 / t, 1e6 / (t * t), 1e9 / (t * t * t));
+
+    let x = if t <= 4000. {
+        (-0.2661239 * t3) - (0.2343589 * t2) + (0.8776956 * t1) + 0.179910
+    } else {
+        (-3.0258469 * t3) + (2.1070379 * t2) + (0.2226347 * t1) + 0.240390
+    };
+    let (x2, x3) = (x * x, x * x * x);
+    let y = if t <= 2222. {
+        (-1.1063814 * x3) - (1.34811020 * x2) + (2.18555832 * x) - 0.20219683
+    } else if t <= 4000. {
+        (-0.9549476 * x3) - (1.37418593 * x2) + (2.09137015 * x) - 0.16748867
+    } else {
+        (3.0817580 * x3) - (5.87338670 * x2) + (3.75112997 * x) - 0.37001483
+    };
+    // A positive tint means the light is greener
+    let y = y + (tint * 0.05);
+
+    [x / y, 1., (1. - x - y) / y]
+}
+
+fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    [0, 1, 2].map(|i| [0, 1, 2].map(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn mul_vec(m: &Matrix, v: [Number; 3]) -> [Number; 3] { m.map(|row| row.iter().zip(v).map(|(m, v)| m * v).sum()) }
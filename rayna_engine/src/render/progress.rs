@@ -0,0 +1,66 @@
+//! # Module [crate::render::progress]
+//!
+//! Reporting how far through a pass the [renderer](crate::render::renderer::Renderer) is, while it is rendering, so
+//! that slow passes don't look like nothing is happening until they land.
+//!
+//! Progress is measured in [tiles](crate::render::tile), and is reported to a [ProgressSink] each time a tile is
+//! finished. Any `Fn(RenderProgress) + Sync` closure can be used as a sink, or `()` to ignore the progress.
+//!
+//! # Threading
+//! The tiles are rendered in parallel, so the sink is called from the renderer's worker threads, possibly at the same
+//! time. It is called once for every tile, so it should be quick, and throttle anything expensive (such as sending
+//! the progress somewhere else).
+
+use crate::core::types::Number;
+use std::time::Duration;
+
+/// How far through a pass the renderer is
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RenderProgress {
+    /// How many tiles have been rendered so far
+    pub tiles_done: usize,
+    /// How many tiles there are in the pass
+    pub tiles_total: usize,
+    /// How long the pass has been rendering for
+    pub elapsed: Duration,
+}
+
+impl RenderProgress {
+    /// The fraction of the pass that has been rendered, in the range `0.0..=1.0`
+    pub fn fraction(&self) -> Number {
+        match self.tiles_total {
+            0 => 1.,
+            total => self.tiles_done as Number / total as Number,
+        }
+    }
+
+    /// Whether every tile has been rendered
+    pub fn is_done(&self) -> bool { self.tiles_done >= self.tiles_total }
+
+    /// An estimate of how much longer the pass will take, assuming the rest of the tiles take as long as the ones so
+    /// far did. This is [None] until the first tile is done, since there is nothing to estimate from
+    pub fn remaining(&self) -> Option<Duration> {
+        if self.tiles_done == 0 {
+            return None;
+        }
+        let per_tile = self.elapsed.as_secs_f64() / self.tiles_done as Number;
+        Some(Duration::from_secs_f64(
+            per_tile * self.tiles_total.saturating_sub(self.tiles_done) as Number,
+        ))
+    }
+}
+
+/// Something that the progress of a pass is reported to. See the [module docs](self)
+pub trait ProgressSink: Sync {
+    /// Called each time a tile is finished, with the progress so far
+    fn report(&self, progress: RenderProgress);
+}
+
+impl<F: Fn(RenderProgress) + Sync> ProgressSink for F {
+    fn report(&self, progress: RenderProgress) { self(progress) }
+}
+
+/// Ignores the progress
+impl ProgressSink for () {
+    fn report(&self, _progress: RenderProgress) {}
+}
@@ -0,0 +1,99 @@
+//! # Module [crate::render::ray_stats]
+//!
+//! Counting the rays that are traced in each pass, for [RenderStats::rays](crate::render::render::RenderStats::rays).
+//!
+//! Rays are counted by [kind](RayKind), as they are traced. The BVH nodes that they visit are counted separately, by
+//! [crate::shared::bvh_cost], and included when the counts are [taken](take()).
+//!
+//! # Global State
+//! Counting has to be as cheap as possible, since it happens for every ray, so the counts are kept per-thread and
+//! never synchronised. At the end of each pass, the [renderer](crate::render::renderer::Renderer) takes the counts
+//! from every thread in its pool, and adds them up.
+
+use crate::shared::bvh_cost;
+use std::cell::Cell;
+use std::iter::Sum;
+use std::ops::Add;
+
+thread_local! {
+    static COUNTS: Cell<RayStats> = const {
+        Cell::new(RayStats {
+            primary: 0,
+            bounce: 0,
+            shadow: 0,
+            bvh_nodes: 0,
+        })
+    };
+}
+
+/// What a ray is being traced for
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum RayKind {
+    /// Straight from the camera (including straight through
+    /// [shadow catchers](crate::material::shadow_catcher::ShadowCatcherMaterial))
+    Primary,
+    /// Scattered from a surface, to find the light reflected by it
+    Bounce,
+    /// Scattered from a shadow catcher, to find how much of the sky's light is blocked from reaching it
+    Shadow,
+}
+
+/// How many rays were traced, and how much work it took. See the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct RayStats {
+    /// See [RayKind::Primary]
+    pub primary: u64,
+    /// See [RayKind::Bounce]
+    pub bounce: u64,
+    /// See [RayKind::Shadow]
+    pub shadow: u64,
+    /// How many BVH nodes the rays visited, see [crate::shared::bvh_cost].
+    ///
+    /// Only counted if [RenderOpts::bvh_stats](crate::render::render_opts::RenderOpts::bvh_stats) is enabled,
+    /// otherwise it's always zero
+    pub bvh_nodes: u64,
+}
+
+impl RayStats {
+    /// The total number of rays traced (of all kinds)
+    pub fn total(&self) -> u64 { self.primary + self.bounce + self.shadow }
+}
+
+impl Add for RayStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            primary: self.primary + rhs.primary,
+            bounce: self.bounce + rhs.bounce,
+            shadow: self.shadow + rhs.shadow,
+            bvh_nodes: self.bvh_nodes + rhs.bvh_nodes,
+        }
+    }
+}
+
+impl Sum for RayStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::default(), Self::add) }
+}
+
+/// Counts a ray of the given kind being traced
+#[inline(always)]
+pub fn count(kind: RayKind) {
+    COUNTS.with(|counts| {
+        let mut stats = counts.get();
+        match kind {
+            RayKind::Primary => stats.primary += 1,
+            RayKind::Bounce => stats.bounce += 1,
+            RayKind::Shadow => stats.shadow += 1,
+        }
+        counts.set(stats);
+    });
+}
+
+/// Returns the rays counted on this thread so far (along with the BVH nodes visited), and resets them to zero
+pub fn take() -> RayStats {
+    RayStats {
+        bvh_nodes: bvh_cost::take().nodes,
+        ..COUNTS.replace(RayStats::default())
+    }
+}
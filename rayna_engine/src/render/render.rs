@@ -1,4 +1,8 @@
+use crate::core::types::{Image, Number};
+use crate::render::aov::{Aov, AovExportError};
+use crate::render::ray_stats::RayStats;
 use crate::render::render_opts::RenderOpts;
+use std::path::Path;
 use std::time::Duration;
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -9,12 +13,58 @@ pub struct RenderStats {
     pub num_threads: usize,
     /// The render options that were used to make the render
     pub opts: RenderOpts,
-    /// Number of frames that were accumulated so far
+    /// Number of frames (passes) that were accumulated so far.
+    ///
+    /// Once the [target](RenderOpts::target_samples) is reached, no more passes are made, so this stops increasing
     pub accum_frames: usize,
+    /// Total number of samples per pixel that were accumulated so far, over all the passes
+    pub accum_samples: usize,
+    /// Whether the [target samples](RenderOpts::target_samples) or [time limit](RenderOpts::max_time) has been
+    /// reached, so that no more passes will be rendered until the accumulation is cleared
+    pub finished: bool,
+    /// Whether the pass was [cancelled](crate::render::cancel) before it finished. If so, the accumulation was cleared,
+    /// and the render shouldn't be used
+    pub cancelled: bool,
+    /// If the pass was a [progressive preview](RenderOpts::progressive_preview), how many pixels across each of its
+    /// blocks were (e.g. `Some(4)` for a quarter resolution preview). Previews aren't accumulated
+    pub preview_scale: Option<usize>,
+    /// The automatic exposure (stops) that was applied to the image, if [auto exposure](RenderOpts::auto_exposure) is
+    /// enabled. This doesn't include the [manual exposure](RenderOpts::exposure)
+    pub exposure: Option<Number>,
+    /// How many rays were traced for the render (only for the last pass, not all the accumulated ones). This is zero
+    /// if nothing had to be rendered, because the render is [finished](Self::finished)
+    pub rays: RayStats,
+}
+
+impl RenderStats {
+    /// How many rays were traced each second, over the whole [duration](Self::duration) of the render
+    pub fn rays_per_sec(&self) -> Number {
+        match self.duration.is_zero() {
+            true => 0.,
+            false => self.rays.total() as Number / self.duration.as_secs_f64(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Render<T> {
     pub img: T,
     pub stats: RenderStats,
+    /// The auxiliary AOVs that were rendered alongside the image, if [RenderOpts::aovs] is enabled (otherwise this is
+    /// empty). These aren't accumulated, see [Renderer::render_aov()](crate::render::renderer::Renderer::render_aov)
+    pub aovs: Vec<(Aov, T)>,
+    /// The coverage of each pixel (in all the channels), if [RenderOpts::transparent_sky] is enabled. The image is
+    /// premultiplied by it, so that the sky is black
+    pub alpha: Option<T>,
+}
+
+impl Render<Image> {
+    /// Saves the image, any [AOVs](Self::aovs) and the [alpha](Self::alpha) as a single OpenEXR file, see
+    /// [Image::save_exr_with_alpha()]
+    pub fn save_exr(&self, path: impl AsRef<Path>) -> Result<(), AovExportError> {
+        match &self.alpha {
+            Some(alpha) => self.img.save_exr_with_alpha(alpha, &self.aovs, path),
+            None => self.img.save_exr_with_aovs(&self.aovs, path),
+        }
+    }
 }
@@ -1,7 +1,15 @@
 use crate::core::types::Number;
+use crate::render::exposure::AutoExposure;
+use crate::render::filter::PixelFilter;
+use crate::render::postprocess::denoise::Denoise;
+use crate::render::postprocess::tone_mapping::ToneMapping;
+use crate::render::postprocess::white_balance::WhiteBalance;
+use crate::shared::intersect_arena;
+use crate::shared::rng::SamplerKind;
 use nonzero::nonzero;
 use serde::Serialize;
 use std::num::NonZeroUsize;
+use std::time::Duration;
 use strum_macros::{Display, EnumIter, IntoStaticStr};
 use valuable::Valuable;
 
@@ -11,11 +19,50 @@ pub struct RenderOpts {
     pub width: NonZeroUsize,
     /// The target height of the render (pixels)
     pub height: NonZeroUsize,
-    /// A scalar to increase the number of samples taken for each pixel.
-    /// Probably keep this at one and prefer accumulation instead.
+    /// How many samples are taken for each pixel, in each pass (each call to
+    /// [Renderer::render()](crate::render::renderer::Renderer::render())). The passes are accumulated, so this only
+    /// trades off how long each pass takes against how many passes are needed.
+    /// Probably keep this low, so that interactive renders stay responsive.
     pub samples: NonZeroUsize,
+    /// The total number of samples per pixel to accumulate, over as many passes as it takes.
+    ///
+    /// Once it has been reached, further passes don't trace any more rays, and just return the accumulated image
+    /// again. The last pass only takes as many samples as are left, so the total is exact.
+    /// If [None], the passes keep accumulating forever (unless [Self::max_time] is reached)
+    pub target_samples: Option<NonZeroUsize>,
+    /// The longest time to spend rendering passes for the accumulation.
+    ///
+    /// Like [Self::target_samples], once it has been spent no more rays are traced, and the accumulated image is
+    /// returned again. The pass that goes over the limit is still finished, so the total can be a little longer.
+    /// If [None], there is no time limit
+    pub max_time: Option<Duration>,
+    /// Start each render with a few quick, low resolution previews (at 1/8, 1/4 and then 1/2 of the resolution)
+    /// before accumulating at the full resolution, so that the image keeps up when moving the camera around.
+    ///
+    /// The previews take one sample per block of pixels, and aren't accumulated. They are rendered whenever the
+    /// accumulation is cleared (e.g. when the camera, scene or options change). See
+    /// [RenderStats::preview_scale](crate::render::render::RenderStats::preview_scale)
+    pub progressive_preview: bool,
+    /// When the camera moves, reproject the accumulated samples to where the new camera sees them, instead of clearing
+    /// the accumulation, so that the image doesn't go back to being noisy every time the camera is moved a little.
+    ///
+    /// Samples that can't be reprojected (for things the old camera couldn't see) start again from nothing, and the
+    /// rest are limited in weight so that view-dependent shading doesn't smear for long. See
+    /// [crate::render::reproject]
+    pub temporal_reprojection: bool,
+    /// The sequence that the samples are drawn from. See [SamplerKind]
+    ///
+    /// The low-discrepancy sequences spread the samples out more evenly than random ones, so they converge faster
+    /// at low sample counts. They are used for the position in the pixel, the lens, and the materials
+    pub sampler: SamplerKind,
+    /// The reconstruction filter that the samples of each pixel are combined with. See [crate::render::filter]
+    pub filter: PixelFilter,
     /// The way in which the render is visuaised. See [RenderMode]
     pub mode: RenderMode,
+    /// Shade every surface with the same plain grey material, except for the lights (and shadow catchers), so that
+    /// the lighting and geometry of the scene can be judged without the materials getting in the way.
+    /// See [crate::material::clay]
+    pub clay: bool,
     /// How many times a ray can bounce
     pub ray_depth: usize,
     /// (Advanced) How many sub-rays each ray should split into, each time it bounces
@@ -26,6 +73,74 @@ pub struct RenderOpts {
     /// Note that this causes an exponential increase in the number of rays. It is advisable to keep this very low.
     /// This is mostly only effective in highly diffuse scenes.
     pub ray_branching: NonZeroUsize,
+    /// (Debug) Use error-compensated arithmetic inside the intersection routines
+    ///
+    /// This is useful for tracking down precision artefacts (speckles, holes, surface acne)
+    /// without having to rebuild the engine. See [crate::shared::robust].
+    ///
+    /// # Performance
+    /// The compensated maths is noticeably slower, so keep this off unless you need it.
+    pub robust_intersections: bool,
+    /// (Debug) Count the BVH nodes that each ray visits, for
+    /// [RayStats::bvh_nodes](crate::render::ray_stats::RayStats::bvh_nodes). See [crate::shared::bvh_cost]
+    ///
+    /// They are always counted in [RenderMode::BvhCost], since that's what it shows.
+    ///
+    /// # Performance
+    /// Counting happens for every node of every BVH, so this slows down the traversal a little.
+    pub bvh_stats: bool,
+    /// How many intersections the buffers for all-intersection queries (e.g. for CSG) have room for when they're
+    /// created. See [crate::shared::intersect_arena]
+    ///
+    /// # Performance
+    /// The buffers are reused, so this only matters for the first few rays on each thread. Raise it for scenes with
+    /// complex CSG, so the buffers don't have to grow.
+    pub intersection_capacity: usize,
+    /// (Debug) Make renders reproducible, regardless of the number of threads.
+    ///
+    /// Normally each thread has its own random number streams, so which pixels a thread happens to render changes
+    /// the output. In this mode the streams are reseeded for each pixel (from the pixel coordinates and the frame
+    /// number), so the same scene always gives bit-identical output. Useful for golden-image tests, and for checking
+    /// whether an artefact is caused by a race condition.
+    ///
+    /// # Performance
+    /// Reseeding is cheap for most generators, but not free.
+    pub deterministic: bool,
+    /// Also render the auxiliary [AOVs](crate::render::aov::Aov::AUXILIARY) (normals, depth, albedo, etc.) in the same
+    /// pass as the beauty image, for denoising and compositing. See [Render::aovs](crate::render::render::Render::aovs)
+    ///
+    /// # Performance
+    /// This traces an extra camera ray for each pixel, and uses a lot more memory for large images.
+    pub aovs: bool,
+    /// Keep track of how much the passes of each pixel vary, to estimate how converged it is. See
+    /// [crate::render::variance]
+    pub variance: bool,
+    /// Make the sky transparent, so that the render can be composited over another background.
+    ///
+    /// Camera rays that don't hit anything (including the ones that pass through
+    /// [shadow catchers](crate::material::shadow_catcher::ShadowCatcherMaterial)) are black instead of showing the
+    /// sky, and the coverage of each pixel is rendered alongside the image, as its
+    /// [alpha](crate::render::render::Render::alpha). The sky still lights the scene as normal.
+    ///
+    /// # Performance
+    /// This traces an extra camera ray for each sample, to find the coverage.
+    pub transparent_sky: bool,
+    /// Automatically adjust the exposure of the image, based on how bright it is. See [crate::render::exposure]
+    ///
+    /// If [None], the image is left as-is (no exposure is applied)
+    pub auto_exposure: Option<AutoExposure>,
+    /// Extra exposure (stops) applied to the image, on top of the [automatic exposure](Self::auto_exposure) if it is
+    /// enabled. Like exposure compensation on a camera, each stop doubles the brightness of the image
+    pub exposure: Number,
+    /// Corrects the colour cast of the lighting. See [crate::render::postprocess::white_balance]
+    pub white_balance: WhiteBalance,
+    /// Denoise the image with the built-in denoiser. See [crate::render::postprocess::denoise]
+    ///
+    /// The denoiser is guided by the [AOVs](Self::aovs), which are rendered for it even if they aren't enabled.
+    /// If [None], the image is left as-is
+    pub denoise: Option<Denoise>,
+    /// How the HDR colours are compressed into the displayable range. See [crate::render::postprocess::tone_mapping]
+    pub tone_mapping: ToneMapping,
 }
 
 #[derive(
@@ -49,6 +164,27 @@ pub enum RenderMode {
     Uv,
     /// Visualise which side of the object was hit
     Side,
+    /// Output the alpha (coverage) mask, for compositing.
+    ///
+    /// Opaque objects are white, the sky is black, and
+    /// [shadow catchers](crate::material::shadow_catcher::ShadowCatcherMaterial) show the density of their shadows
+    Alpha,
+    /// Physically-based rendering using photon mapping, which can render caustics (light focused by mirrors and
+    /// glass) that [RenderMode::PBR] would barely ever find.
+    ///
+    /// Only the [lights](crate::object::light::LightObject) in the scene light the diffuse surfaces.
+    /// See [crate::render::photon]
+    PhotonMapping,
+    /// Visualise how much work it took to find what each camera ray hit, as a heatmap of the number of BVH nodes
+    /// visited and primitives tested (see [crate::shared::bvh_cost]). Useful for finding the parts of a scene that
+    /// are slow to render, and checking that changes to the BVHs actually help
+    BvhCost,
+    /// Visualise how many times the path from each pixel bounced before it ended (by escaping to the sky, or being
+    /// absorbed), as a heatmap from black (no bounces) up to red ([RenderOpts::ray_depth] bounces). Paths that were
+    /// cut off by the depth limit are white, so large white areas mean that the limit is too low for the scene.
+    ///
+    /// Only a single ray is followed from each surface, so [RenderOpts::ray_branching] is ignored
+    PathDepth,
 }
 
 impl RenderOpts {
@@ -64,9 +200,28 @@ impl Default for RenderOpts {
             width: nonzero!(740_usize),
             height: nonzero!(480_usize),
             samples: nonzero!(1_usize),
+            target_samples: None,
+            max_time: None,
+            progressive_preview: false,
+            temporal_reprojection: false,
+            sampler: SamplerKind::Random,
+            filter: PixelFilter::Box,
             mode: Default::default(),
+            clay: false,
             ray_depth: 5,
             ray_branching: nonzero!(1_usize),
+            robust_intersections: false,
+            bvh_stats: false,
+            intersection_capacity: intersect_arena::DEFAULT_CAPACITY,
+            deterministic: false,
+            aovs: false,
+            variance: false,
+            transparent_sky: false,
+            auto_exposure: None,
+            exposure: 0.,
+            white_balance: WhiteBalance::NEUTRAL,
+            denoise: None,
+            tone_mapping: ToneMapping::Linear,
         }
     }
 }
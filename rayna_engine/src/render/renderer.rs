@@ -1,20 +1,47 @@
 use crate::core::profiler;
 use crate::core::targets::*;
-use crate::core::types::{Channel, Colour, Image, Number, Vector2};
+use crate::core::types::{Channel, Colour, Image, Number, Vector2, Vector3};
+use crate::material::clay::Shading;
+use crate::material::light::LightGroup;
 use crate::material::Material;
+use crate::mesh::advanced::indexed_triangle::IndexedTriangleMesh;
+use crate::object::transform::ObjectTransform;
 use crate::object::Object;
+use crate::render::aov::Aov;
+use crate::render::bake::{rasterise_uvs, BakeChannel, BakeError, BakeOpts, SurfacePoint};
+use crate::render::cancel::CancelToken;
+use crate::render::checkpoint::{self, CheckpointError};
+use crate::render::distributed::{Connection, DistributedError, RemoteWorker, TileRequest};
+use crate::render::exposure::{apply_exposure, ExposureState};
+use crate::render::light_group::LightFilter;
+use crate::render::photon::{self, PhotonMap};
+use crate::render::postprocess::denoise::{denoise, DenoiseGuides};
+use crate::render::postprocess::tone_mapping::apply_tone_mapping;
+use crate::render::postprocess::white_balance::apply_white_balance;
+use crate::render::progress::{ProgressSink, RenderProgress};
+use crate::render::ray_stats::{self, RayKind, RayStats};
 use crate::render::render::{Render, RenderStats};
 use crate::render::render_opts::{RenderMode, RenderOpts};
-use crate::scene::camera::Camera;
+use crate::render::reproject::{self, PixelHit};
+use crate::render::tile::{split_tiles, Tile, TILE_SIZE};
+use crate::render::variance::{self, Moments};
 use crate::scene::camera::Viewport;
-use crate::scene::Scene;
-use crate::shared::intersect::FullIntersection;
+use crate::scene::camera::{Camera, Shutter};
+use crate::scene::lens;
+use crate::scene::{PrepareStage, Scene};
+use crate::shared::bvh_cost::{self, BvhCost};
+use crate::shared::intersect::{FullIntersection, Intersection};
+use crate::shared::intersect_arena;
 use crate::shared::interval::Interval;
 use crate::shared::math::Lerp;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{RayPacket, PACKET_SIZE, WIDE_PACKET_SIZE};
+use crate::shared::rng::{self, Sampler, SamplerKind};
+use crate::shared::robust;
 use crate::shared::validate;
 use crate::skybox::Skybox;
-use ndarray::Zip;
+use itertools::izip;
+use ndarray::{Array2, ArrayViewMut2, Zip};
 use num_integer::Roots as _;
 use puffin::profile_function;
 use rand::distributions::Distribution;
@@ -23,12 +50,19 @@ use rand_core::{RngCore, SeedableRng};
 use rayon::prelude::*;
 use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
 use smallvec::SmallVec;
+use std::fmt::Debug;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::num::NonZeroUsize;
 use std::ops::DerefMut as _;
+use std::path::Path;
+use std::simd::{LaneCount, Mask, SupportedLaneCount};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 use thiserror::Error;
-use tracing::{error, trace};
+use tracing::{debug, error, trace, warn};
 
-use super::accum_buffer::AccumulationBuffer;
+use super::accum_buffer::{AccumulationBuffer, AccumulationValue};
 
 /// The main struct that does the rendering of scenes
 ///
@@ -41,6 +75,35 @@ pub struct Renderer<Obj, Sky, Rng> {
     data_pool: opool::Pool<PooledDataAllocator, PooledData<Rng>>,
     /// Accumulation buffer storing the [accumulated] result of previous renders.
     accum_buffer: AccumulationBuffer,
+    /// Accumulation buffer for the coverage of each pixel, when [RenderOpts::transparent_sky] is enabled
+    alpha_buffer: AccumulationBuffer<Number>,
+    /// Accumulation buffer for the luminance of each pixel's passes, when [RenderOpts::variance] is enabled
+    variance_buffer: AccumulationBuffer<Moments>,
+    /// The current (adapted) exposure, used for [RenderOpts::auto_exposure]
+    exposure: ExposureState,
+    /// Whether [Scene::prepare()] has been called on the current scene
+    scene_prepared: bool,
+    /// The shutter that the objects in the current scene were last given (see [Scene::set_shutter()])
+    scene_shutter: Option<Shutter>,
+    /// The [hash](checkpoint::scene_hash()) of the scene as it was given, and the time it was last set to, so that a
+    /// checkpoint isn't resumed with a different scene
+    scene_hash: u64,
+    scene_time: Option<Number>,
+    /// How long has been spent rendering the passes in the accumulation buffer, for [RenderOpts::max_time]
+    accum_time: Duration,
+    /// The scale of the next [progressive preview](RenderOpts::progressive_preview), which is halved after each
+    /// preview. The passes are accumulated once it reaches 1
+    preview_scale: usize,
+    /// The camera that the accumulated samples were rendered with, if the camera has moved since, so that they can be
+    /// [reprojected](crate::render::reproject) before the next pass
+    reproject_from: Option<Camera>,
+    /// The image, AOVs and alpha from the last pass, once the render is [finished](Self::is_finished()), so that they
+    /// can be returned again without rendering anything
+    finished: Option<(Image, Vec<(Aov, Image)>, Option<Image>)>,
+    /// Checked while rendering each pass, so that it can be cancelled from another thread. See [Self::cancel_token()]
+    cancel: CancelToken,
+    /// The workers that the tiles of each pass are shared with. See [crate::render::distributed]
+    workers: Vec<RemoteWorker>,
     // Purposefully storing these in the render (though not really required)
     // for future compatibility with GPU renderer
     #[getset(get = "pub")]
@@ -51,6 +114,13 @@ pub struct Renderer<Obj, Sky, Rng> {
     options: RenderOpts,
 }
 
+/// The scale of the first [progressive preview](RenderOpts::progressive_preview) after the accumulation is cleared
+const PREVIEW_START_SCALE: usize = 8;
+
+/// How often the rays scattered from diffuse surfaces are sent towards the bright parts of the sky, instead of where
+/// the material scatters them. See [Renderer::sample_scatter()]
+const SKY_SAMPLE_PROBABILITY: Number = 0.5;
+
 #[derive(Error, Debug)]
 pub enum RendererCreateError {
     #[error("failed to create worker thread pool")]
@@ -67,8 +137,8 @@ impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
     /// Creates a new renderer instance, using default values for the scene, camera, and render options
     pub fn new_default() -> Result<Self, RendererCreateError>
     where
-        Obj: Default,
-        Sky: Default,
+        Obj: Default + Debug,
+        Sky: Default + Debug,
         Rng: SeedableRng,
     {
         Self::new_from(
@@ -90,6 +160,8 @@ impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
         num_threads: usize,
     ) -> Result<Self, RendererCreateError>
     where
+        Obj: Debug,
+        Sky: Debug,
         Rng: SeedableRng,
     {
         let thread_pool = Self::create_thread_pool(num_threads).map_err(RendererCreateError::from)?;
@@ -100,6 +172,19 @@ impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
             thread_pool,
             data_pool,
             accum_buffer,
+            alpha_buffer: AccumulationBuffer::default(),
+            variance_buffer: AccumulationBuffer::default(),
+            exposure: ExposureState::default(),
+            scene_prepared: false,
+            scene_shutter: None,
+            scene_hash: checkpoint::scene_hash(&scene),
+            scene_time: None,
+            accum_time: Duration::ZERO,
+            preview_scale: PREVIEW_START_SCALE,
+            reproject_from: None,
+            finished: None,
+            cancel: CancelToken::new(),
+            workers: vec![],
             scene,
             camera,
             options,
@@ -119,6 +204,17 @@ impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
             .build()
     }
 
+    /// Sets the per-thread switches (see [crate::shared::robust] and [crate::shared::intersect_arena]) from the render
+    /// options, on every thread in the pool
+    fn apply_thread_options(&self) {
+        let robust = self.options.robust_intersections;
+        let intersection_capacity = self.options.intersection_capacity;
+        self.thread_pool.broadcast(|_| {
+            robust::set_enabled(robust);
+            intersect_arena::set_capacity(intersection_capacity);
+        });
+    }
+
     /// Helper method to create the data pool
     fn create_data_pool() -> opool::Pool<PooledDataAllocator, PooledData<Rng>>
     where
@@ -131,16 +227,22 @@ impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
 }
 
 /// Clone Renderer
-impl<Obj: Clone, Sky: Clone, Rng: SeedableRng> Clone for Renderer<Obj, Sky, Rng> {
+impl<Obj: Clone + Debug, Sky: Clone + Debug, Rng: SeedableRng> Clone for Renderer<Obj, Sky, Rng> {
     fn clone(&self) -> Self {
         // No good way to clone thread pool or data pool
-        Self::new_from(
+        let renderer = Self::new_from(
             self.scene.clone(),
             self.camera.clone(),
             self.options.clone(),
             self.thread_pool.current_num_threads(),
         )
-        .expect("could not clone: couldn't create renderer")
+        .expect("could not clone: couldn't create renderer");
+        // The scene may have been changed by rendering (e.g. the shutter), so it shouldn't be hashed again
+        Self {
+            scene_hash: self.scene_hash,
+            scene_time: self.scene_time,
+            ..renderer
+        }
     }
 }
 
@@ -150,20 +252,74 @@ impl<Obj: Clone, Sky: Clone, Rng: SeedableRng> Clone for Renderer<Obj, Sky, Rng>
 
 impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
     /// Clears the accumulation buffer, removing all previous renderer frames
-    pub fn clear_accumulation(&mut self) { self.accum_buffer.clear(); }
+    pub fn clear_accumulation(&mut self) {
+        self.accum_buffer.clear();
+        self.alpha_buffer.clear();
+        self.variance_buffer.clear();
+        self.accum_time = Duration::ZERO;
+        self.preview_scale = PREVIEW_START_SCALE;
+        self.reproject_from = None;
+        self.finished = None;
+    }
+
+    /// A token that can be used (from any thread) to cancel the pass that is currently being rendered. See
+    /// [crate::render::cancel]
+    pub fn cancel_token(&self) -> CancelToken { self.cancel.clone() }
+
+    /// Whether the render is finished, because [RenderOpts::target_samples] or [RenderOpts::max_time] has been
+    /// reached. Once finished, no more passes are rendered until the accumulation is cleared
+    pub fn is_finished(&self) -> bool {
+        let samples_reached = self
+            .options
+            .target_samples
+            .is_some_and(|t| self.accum_buffer.sample_count() >= t.get());
+        let time_reached = self.options.max_time.is_some_and(|t| self.accum_time >= t);
+        samples_reached || time_reached
+    }
 
     /// Sets the camera.
     ///
-    /// Also clears the accumulation buffer
+    /// Also clears the accumulation buffer, unless [RenderOpts::temporal_reprojection] is enabled, in which case the
+    /// accumulated samples are reprojected to the new camera before the next pass
     pub fn set_camera(&mut self, camera: Camera) {
-        self.camera = camera;
-        self.clear_accumulation();
+        let previous = std::mem::replace(&mut self.camera, camera);
+        if !self.options.temporal_reprojection || self.accum_buffer.frame_count() == 0 {
+            self.clear_accumulation();
+            return;
+        }
+        // The samples are still where the camera was when they were rendered, however many times it has moved since
+        self.reproject_from.get_or_insert(previous);
+        self.accum_time = Duration::ZERO;
+        self.finished = None;
     }
     /// Sets the scene to be rendered.
     ///
-    /// Also clears the accumulation buffer
-    pub fn set_scene(&mut self, scene: Scene<Obj, Sky>) {
+    /// Also clears the accumulation buffer. The scene will be [prepared](Scene::prepare()) before the next render,
+    /// or call [Self::prepare_scene()] to do it now.
+    pub fn set_scene(&mut self, scene: Scene<Obj, Sky>)
+    where
+        Obj: Debug,
+        Sky: Debug,
+    {
+        self.scene_hash = checkpoint::scene_hash(&scene);
+        self.scene_time = None;
         self.scene = scene;
+        self.scene_prepared = false;
+        self.scene_shutter = None;
+        self.clear_accumulation();
+    }
+
+    /// Sets the time of the scene's animations (see [Scene::set_time()]).
+    ///
+    /// Also clears the accumulation buffer. The scene doesn't need to be prepared again, since animated objects
+    /// already cover their whole animation
+    pub fn set_time(&mut self, time: Number)
+    where
+        Obj: Object,
+        Sky: Skybox,
+    {
+        self.scene.set_time(time);
+        self.scene_time = Some(time);
         self.clear_accumulation();
     }
 
@@ -184,6 +340,143 @@ impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
 
 // endregion Properties
 
+// region Checkpoints
+
+impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
+    /// Saves the samples that have been accumulated so far to a checkpoint file, so that the render can be carried on
+    /// later with [Self::resume_from()]. See [crate::render::checkpoint]
+    ///
+    /// Only the colour is saved, so the [alpha](RenderOpts::transparent_sky) starts accumulating again on resume
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let hash = checkpoint::settings_hash(&self.options, &self.camera);
+        checkpoint::save(path.as_ref(), hash, self.scene_hash(), &self.accum_buffer)
+    }
+
+    /// Replaces the accumulated samples with the ones from a checkpoint file (see [Self::save_checkpoint()]), so that
+    /// the next render carries on from where the checkpoint was saved.
+    ///
+    /// The checkpoint must have been saved with the same render options and camera, otherwise
+    /// [CheckpointError::SettingsMismatch] is returned and the accumulation is left as it was. Likewise, it must be of
+    /// the same scene (at the same [time](Self::set_time())), otherwise [CheckpointError::SceneMismatch] is returned
+    pub fn resume_from(&mut self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let hash = checkpoint::settings_hash(&self.options, &self.camera);
+        let dims = self.options.dims();
+        self.accum_buffer = checkpoint::load(path.as_ref(), hash, self.scene_hash(), dims)?;
+        self.alpha_buffer.clear();
+        self.variance_buffer.clear();
+        // The time that was spent on the checkpoint isn't known, so the time limit starts again
+        self.accum_time = Duration::ZERO;
+        // There is already something better to show than a preview
+        self.preview_scale = 1;
+        self.reproject_from = None;
+        self.finished = None;
+        Ok(())
+    }
+
+    /// The [hash](checkpoint::scene_hash()) of the scene, including the time it was set to. This is stored in
+    /// checkpoints, and checked by [workers](crate::render::distributed)
+    pub fn scene_hash(&self) -> u64 {
+        rng::hash_seed([self.scene_hash, self.scene_time.map_or(u64::MAX, Number::to_bits)])
+    }
+}
+
+// endregion Checkpoints
+
+// region Distributed
+
+impl<Obj, Sky, Rng> Renderer<Obj, Sky, Rng> {
+    /// Connects to a worker that is [serving](Self::serve()) its own renderer, so that it renders some of the tiles of
+    /// each pass. See [crate::render::distributed]
+    ///
+    /// The worker must have the same render options and camera, otherwise [DistributedError::SettingsMismatch] is
+    /// returned. Likewise, it must have the same [scene](Self::scene_hash()), otherwise
+    /// [DistributedError::SceneMismatch] is returned
+    pub fn connect_worker(&mut self, addr: impl ToSocketAddrs) -> Result<(), DistributedError> {
+        let hash = checkpoint::settings_hash(&self.options, &self.camera);
+        self.workers.push(RemoteWorker::connect(addr, hash, self.scene_hash())?);
+        Ok(())
+    }
+
+    /// How many workers are connected. Workers that fail are disconnected as soon as they do
+    pub fn worker_count(&self) -> usize { self.workers.len() }
+
+    /// Disconnects from all the workers, so that the passes are rendered locally again
+    pub fn disconnect_workers(&mut self) { self.workers.clear(); }
+}
+
+impl<Obj: Object, Sky: Skybox, Rng: RngCore + Send + SeedableRng> Renderer<Obj, Sky, Rng> {
+    /// Serves this renderer as a worker for a coordinator that has [connected](Self::connect_worker()) to it over
+    /// `stream`, rendering the tiles that it asks for until it disconnects. See [crate::render::distributed]
+    ///
+    /// The renderer should have the same scene, camera and render options as the coordinator. Nothing is
+    /// accumulated, so the renderer can be reused to serve the next coordinator.
+    ///
+    /// Returns [DistributedError::SettingsMismatch] or [DistributedError::SceneMismatch] if the coordinator's settings
+    /// or scene don't match (or stop matching), after telling it so, and [DistributedError::InvalidRequest] if it asks for a tile that the pass couldn't have
+    pub fn serve(&mut self, stream: TcpStream) -> Result<(), DistributedError> {
+        profile_function!();
+        self.ensure_scene_prepared();
+
+        let viewport = self
+            .camera
+            .calculate_viewport()
+            .map_err(|source| DistributedError::InvalidCamera { source })?;
+        let hash = checkpoint::settings_hash(&self.options, &self.camera);
+        let scene_hash = self.scene_hash();
+        let mut conn = Connection::accept(stream, hash, scene_hash)?;
+        self.apply_thread_options();
+        let interval = Interval::from(1e-3..Number::MAX);
+
+        while let Some(request) = conn.read_request()? {
+            if request.hash != hash {
+                conn.write_mismatch()?;
+                return Err(DistributedError::SettingsMismatch);
+            }
+            if request.scene_hash != scene_hash {
+                conn.write_scene_mismatch()?;
+                return Err(DistributedError::SceneMismatch);
+            }
+            let Tile { x, y, w, h } = request.tile;
+            let [width, height] = self.options.dims();
+            let inside =
+                x.checked_add(w).is_some_and(|end| end <= width) && y.checked_add(h).is_some_and(|end| end <= height);
+            if !inside || request.samples > self.options.samples {
+                return Err(DistributedError::InvalidRequest);
+            }
+            let opts = RenderOpts {
+                samples: request.samples,
+                ..self.options
+            };
+            let pass = &PassParams {
+                scene: &self.scene,
+                opts: &opts,
+                viewport: &viewport,
+                interval: &interval,
+                photons: None,
+                frame: request.frame,
+                first_sample: request.first_sample,
+            };
+            let data_pool = &self.data_pool;
+            let pixels = request.tile.pixels().collect::<Vec<_>>();
+            let colours = self.thread_pool.install(|| {
+                pixels
+                    .into_par_iter()
+                    .map_init(
+                        || data_pool.get(),
+                        |pooled, (x, y)| Self::render_px(pass, [x, y], pooled),
+                    )
+                    .collect::<Vec<_>>()
+            });
+            conn.write_tile(&colours)?;
+        }
+
+        debug!(target: RENDERER, "coordinator disconnected");
+        Ok(())
+    }
+}
+
+// endregion Distributed
+
 // region Pooled/Cached Data
 
 /// A helper struct that holds data we want to be pooled
@@ -195,7 +488,7 @@ struct PooledData<Rng> {
     pub px_coords: Vec<Vector2>,
     /// Buffer of [Colour] values
     pub px_samples: Vec<Colour>,
-    /// The [Uniform] number distribution for creating MSAA values
+    /// The [Uniform] number distribution (`0.0..1.0`) for creating MSAA values
     pub msaa_distr: Uniform<Number>,
 }
 
@@ -205,7 +498,7 @@ impl<Rng: SeedableRng> opool::PoolAllocator<PooledData<Rng>> for PooledDataAlloc
     fn allocate(&self) -> PooledData<Rng> {
         // I will admit I have no idea if you can fill an array from a function like this
         let rngs = [(); 2].map(|()| Rng::from_entropy());
-        let msaa_dist = Uniform::new_inclusive(-0.5, 0.5);
+        let msaa_dist = Uniform::new(0., 1.);
         PooledData {
             rngs,
             px_coords: vec![],
@@ -217,50 +510,699 @@ impl<Rng: SeedableRng> opool::PoolAllocator<PooledData<Rng>> for PooledDataAlloc
 
 // endregion Pooled/Cached Data
 
+// region Pass State
+
+/// What [Renderer::render_actual()] checks and reports to as it renders each tile of a pass
+struct PassHooks<'a> {
+    /// Checked before each tile is started, see [crate::render::cancel]
+    cancel: &'a CancelToken,
+    /// Told about each tile once it's finished, see [crate::render::progress]
+    progress: &'a dyn ProgressSink,
+    /// The workers to share the tiles with (see [crate::render::distributed]), and the settings and scene hashes to
+    /// send them
+    workers: &'a mut [RemoteWorker],
+    settings_hash: u64,
+    scene_hash: u64,
+}
+
+/// The accumulation buffers that [Renderer::render_actual()] adds each pass to
+struct PassBuffers<'a> {
+    colour: &'a mut AccumulationBuffer,
+    /// See [RenderOpts::variance]
+    variance: Option<&'a mut AccumulationBuffer<Moments>>,
+}
+
+/// A tile of a pass, and its views into each of the buffers that the pass is rendered into
+struct TileViews<'a> {
+    tile: Tile,
+    accum: ArrayViewMut2<'a, AccumulationValue>,
+    aux: Option<ArrayViewMut2<'a, [Colour; Aov::AUXILIARY.len()]>>,
+    variance: Option<ArrayViewMut2<'a, AccumulationValue<Moments>>>,
+}
+
+/// Everything that is needed to render the pixels of a pass
+struct PassParams<'a, Obj, Sky> {
+    scene: &'a Scene<Obj, Sky>,
+    opts: &'a RenderOpts,
+    viewport: &'a Viewport,
+    interval: &'a Interval<Number>,
+    /// The photons traced for the pass, in [RenderMode::PhotonMapping]
+    photons: Option<&'a PhotonMap>,
+    /// The index of the pass, for seeding deterministic renders
+    frame: u64,
+    /// The index of the first sample in the pass, for the [Sampler]
+    first_sample: u64,
+}
+
+// endregion Pass State
+
+// region Path State
+
+/// A ray that is being traced along a path, and what it needs to know about the rest of the path
+#[derive(Copy, Clone, Debug)]
+struct PathRay<'l> {
+    ray: Ray,
+    /// Which lights contribute to the path
+    lights: LightFilter<'l>,
+    /// How many times the path has bounced before this ray
+    depth: usize,
+    /// What the ray is being traced for. Only [primary](RayKind::Primary) rays see the sky itself
+    kind: RayKind,
+}
+
+/// Where a ray hit the scene, and the material that the surface is [shaded](Shading) with
+#[derive(Debug)]
+struct Hit<'o, Mat> {
+    intersection: Intersection,
+    material: Shading<'o, Mat>,
+}
+
+impl<'o, Mat: Material> Hit<'o, Mat> {
+    /// Shades the intersection, with clay in place of its material if [RenderOpts::clay] is enabled
+    fn shade(hit: FullIntersection<'o, Mat>, opts: &RenderOpts) -> Self {
+        Self {
+            intersection: hit.intersection,
+            material: Shading::new(hit.material, opts.clay),
+        }
+    }
+
+    /// Borrows the hit as a [FullIntersection], for the code outside of the renderer
+    fn full(&self) -> FullIntersection<'_, Shading<'o, Mat>> {
+        FullIntersection {
+            intersection: self.intersection,
+            material: &self.material,
+        }
+    }
+}
+
+/// A point where a path hit something, that is waiting on the rays scattered from it to be traced.
+///
+/// These are kept on an explicit stack by [Renderer::ray_colour()], rather than on the call stack
+struct PathVertex<'o, 'l, Mat: Material> {
+    /// The ray that hit the surface
+    incoming: PathRay<'l>,
+    hit: Hit<'o, Mat>,
+    /// How many more rays are still to be scattered from the surface. See [RenderOpts::ray_branching]
+    branches_left: usize,
+    state: VertexState,
+}
+
+/// What a [PathVertex] is doing with the rays it scatters
+enum VertexState {
+    /// A normal surface, adding up the light reflected along each scattered ray.
+    /// `outgoing` is the scattered ray that is currently being traced, and the weight of the light along it (see
+    /// [Renderer::sample_scatter()])
+    Surface {
+        emitted: Colour,
+        reflected_sum: Colour,
+        outgoing: Option<(Ray, Channel)>,
+    },
+    /// A shadow catcher that is finding out how much of the sky's light is blocked
+    Occlusion { received: Colour, unblocked: Colour },
+    /// A shadow catcher that knows how much light is blocked, and is tracing what's behind it
+    Behind { occlusion: Colour },
+}
+
+/// What should happen after a [PathVertex] is moved along
+enum VertexStep<'l> {
+    /// Another ray should be traced from the vertex
+    Trace(PathRay<'l>),
+    /// The vertex is finished, with the given colour
+    Done(Colour),
+}
+
+// endregion Path State
+
 // region High-level Rendering
 
 impl<Obj: Object, Sky: Skybox, Rng: RngCore + Send + SeedableRng> Renderer<Obj, Sky, Rng> {
+    /// Pre-processes the scene (see [Scene::prepare()]) on the render threads, calling `progress` as each stage
+    /// finishes.
+    ///
+    /// This is done automatically before the first render of each scene, but can be called beforehand to
+    /// control when the cost is paid, or to show progress for large scenes.
+    pub fn prepare_scene(&mut self, progress: impl Fn(PrepareStage) + Sync) {
+        profile_function!();
+
+        let scene = &mut self.scene;
+        self.thread_pool.install(|| {
+            scene.prepare(|stage| {
+                debug!(target: RENDERER, %stage, "prepared scene stage");
+                progress(stage);
+            })
+        });
+        self.scene_prepared = true;
+    }
+
+    /// Prepares the scene, if it hasn't been already, and gives it the camera's [shutter](Camera::shutter)
+    fn ensure_scene_prepared(&mut self) {
+        if !self.scene_prepared {
+            self.prepare_scene(|_| ());
+        }
+        if self.scene_shutter != Some(self.camera.shutter) {
+            self.scene.set_shutter(self.camera.shutter);
+            self.scene_shutter = Some(self.camera.shutter);
+        }
+    }
+
     // TODO: Should `render()` be fallible?
     pub fn render(&mut self) -> Render<Image> {
+        let mut img = Image::new_blank(0, 0);
+        let (stats, aovs, alpha) = self.render_pass(&mut img, &());
+        Render {
+            img,
+            stats,
+            aovs,
+            alpha,
+        }
+    }
+
+    /// Renders the next pass the same as [Self::render()], but writes the image into `target` instead of allocating a
+    /// new one. The target is resized to match the render if it needs to be, so the same image can be passed in every
+    /// frame.
+    ///
+    /// The `progress` of the pass is reported as it is rendered (see [crate::render::progress]). Pass `()` to ignore
+    /// it.
+    ///
+    /// The [AOVs](RenderOpts::aovs) and [alpha](RenderOpts::transparent_sky) are still rendered if they are enabled
+    /// (so that they keep accumulating alongside the image), but aren't returned. Use [Self::render()] to get them
+    pub fn render_into(&mut self, target: &mut Image, progress: impl ProgressSink) -> RenderStats {
+        self.render_pass(target, &progress).0
+    }
+
+    /// Copies the accumulated image into `target`, without rendering anything. This is the raw average of all the
+    /// passes so far, before it is denoised, exposed or tone mapped.
+    ///
+    /// Returns `false` (leaving the target as it was) if nothing has been rendered since the accumulation was cleared
+    pub fn read_accumulation(&self, target: &mut Image) -> bool { self.accum_buffer.read_into(target) }
+
+    /// Copies the estimated variance of each pixel into `target` (in all of its channels), without rendering anything.
+    /// See [crate::render::variance]
+    ///
+    /// Returns `false` (leaving the target as it was) if [RenderOpts::variance] isn't enabled, or nothing has been
+    /// rendered with it since the accumulation was cleared
+    pub fn read_variance(&self, target: &mut Image) -> bool {
+        let Some(values) = self.variance_buffer.values() else {
+            return false;
+        };
+        if self.variance_buffer.frame_count() == 0 {
+            return false;
+        }
+        target.ensure_size(values.width(), values.height());
+        target.zip_mut_with(values.data(), |px, value| {
+            *px = Colour::from([variance::variance_of_mean(value) as Channel; 3])
+        });
+        true
+    }
+
+    /// Renders the next pass into `target`, returning the stats, AOVs and alpha along with it
+    fn render_pass(
+        &mut self,
+        target: &mut Image,
+        progress: &dyn ProgressSink,
+    ) -> (RenderStats, Vec<(Aov, Image)>, Option<Image>) {
         profile_function!();
+        self.ensure_scene_prepared();
+        if let Some(previous) = self.reproject_from.take() {
+            self.reproject_accumulation(&previous);
+        }
 
         // Render image, and collect stats
 
         let start = puffin::now_ns();
         let num_threads = self.thread_pool.current_num_threads();
+        self.apply_thread_options();
+        let count_bvh = self.options.mode == RenderMode::BvhCost || self.options.bvh_stats;
+        // Throw away anything that was counted outside of a render
+        let _ = self.thread_pool.broadcast(|_| {
+            bvh_cost::set_enabled(count_bvh);
+            ray_stats::take()
+        });
+        // Only this pass can be cancelled, not one that was cancelled before it started
+        self.cancel.reset();
+        let mut cancelled = false;
+
+        // The last pass might need fewer samples to reach the target exactly
+        let remaining_samples = match self.options.target_samples {
+            Some(target) => target.get().saturating_sub(self.accum_buffer.sample_count()),
+            None => usize::MAX,
+        };
+        let pass_opts = RenderOpts {
+            samples: NonZeroUsize::new(remaining_samples.min(self.options.samples.get())).unwrap_or(NonZeroUsize::MIN),
+            ..self.options
+        };
 
-        let image = match self.camera.calculate_viewport() {
-            Err(err) => {
+        let finished = self.finished.as_ref().filter(|_| self.is_finished()).cloned();
+        let preview_scale = (self.options.progressive_preview && self.preview_scale > 1).then_some(self.preview_scale);
+
+        let (aovs, alpha) = match (self.camera.calculate_viewport(), finished, preview_scale) {
+            (Err(err), _, _) => {
                 trace!(target: RENDERER, ?err, "couldn't calculate viewport");
                 let [w, h] = self.options.dims();
-                Self::render_failed(w, h)
+                *target = Self::render_failed(w, h);
+                (vec![], None)
             }
-            Ok(viewport) => {
-                let interval = Interval::from(1e-3..Number::MAX);
-                Self::render_actual(
+            (Ok(_), Some((image, aovs, alpha)), _) => {
+                trace!(target: RENDERER, "render finished, reusing last pass");
+                // Copied rather than shared, so that post-processing the target doesn't have to copy it anyway
+                target.ensure_size(image.width(), image.height());
+                target.assign(image.data());
+                (aovs, alpha)
+            }
+            (Ok(viewport), None, Some(scale)) => {
+                trace!(target: RENDERER, scale, "rendering progressive preview");
+                Self::render_preview(
                     &self.thread_pool,
                     &self.data_pool,
-                    &mut self.accum_buffer,
                     &self.scene,
                     &self.options,
                     &viewport,
-                    &interval,
-                )
+                    scale,
+                    target,
+                );
+                self.preview_scale = scale / 2;
+                (vec![], None)
+            }
+            (Ok(viewport), None, None) => {
+                let interval = Interval::from(1e-3..Number::MAX);
+                let pass_start = puffin::now_ns();
+                let settings_hash = checkpoint::settings_hash(&self.options, &self.camera);
+                // Kept so that a cancelled pass can be taken back out. The buffers are shared until the pass writes to
+                // them, so this costs a single copy of each one
+                let before = (
+                    self.accum_buffer.clone(),
+                    self.options.variance.then(|| self.variance_buffer.clone()),
+                );
+                let aovs = Self::render_actual(
+                    &self.thread_pool,
+                    &self.data_pool,
+                    PassBuffers {
+                        colour: &mut self.accum_buffer,
+                        variance: self.options.variance.then_some(&mut self.variance_buffer),
+                    },
+                    &self.scene,
+                    &pass_opts,
+                    &viewport,
+                    PassHooks {
+                        cancel: &self.cancel,
+                        progress,
+                        workers: &mut self.workers,
+                        settings_hash,
+                        scene_hash: self.scene_hash(),
+                    },
+                );
+                // The tiles that failed workers were given have been rendered locally instead
+                self.workers.retain(|worker| !worker.failed());
+                if self.cancel.is_cancelled() {
+                    debug!(target: RENDERER, "pass cancelled, discarding its samples");
+                    // Only some of the pixels had the pass added to them, so go back to how they were before it
+                    let (accum, variance) = before;
+                    self.accum_buffer = accum;
+                    if let Some(variance) = variance {
+                        self.variance_buffer = variance;
+                    }
+                    cancelled = true;
+                    (vec![], None)
+                } else {
+                    let alpha = self.options.transparent_sky.then(|| {
+                        Self::render_alpha(
+                            &self.thread_pool,
+                            &self.data_pool,
+                            &mut self.alpha_buffer,
+                            &self.scene,
+                            &pass_opts,
+                            &viewport,
+                            &interval,
+                        )
+                    });
+                    self.accum_buffer.read_into(target);
+                    self.accum_time += Duration::from_nanos(puffin::now_ns().abs_diff(pass_start));
+                    if self.is_finished() {
+                        self.finished = Some((target.clone(), aovs.clone(), alpha.clone()));
+                    }
+                    (aovs, alpha)
+                }
             }
         };
 
-        let end = puffin::now_ns();
-        let duration = Duration::from_nanos(end.abs_diff(start));
+        let rays = self
+            .thread_pool
+            .broadcast(|_| ray_stats::take())
+            .into_iter()
+            .sum::<RayStats>();
 
-        Render {
-            img: image,
-            stats: RenderStats {
-                duration,
+        // The target is left as it was, since there is nothing to show
+        if cancelled {
+            let stats = RenderStats {
+                duration: Duration::from_nanos(puffin::now_ns().abs_diff(start)),
                 num_threads,
                 opts: self.options,
-                accum_frames: self.accum_buffer.frame_count(),
-            },
+                cancelled: true,
+                rays,
+                ..RenderStats::default()
+            };
+            return (stats, vec![], None);
         }
+
+        // Denoise before measuring the exposure, so the noise doesn't skew it
+        if let Some(settings) = &self.options.denoise {
+            if let Some(guides) = DenoiseGuides::from_aovs(&aovs) {
+                *target = self.thread_pool.install(|| denoise(target, &guides, settings));
+            }
+        }
+        // The AOVs might only have been rendered for the denoiser
+        let aovs = if self.options.aovs { aovs } else { vec![] };
+
+        let end = puffin::now_ns();
+        let duration = Duration::from_nanos(end.abs_diff(start));
+
+        // Measure the accumulated image, and expose it accordingly
+        let exposure = match &self.options.auto_exposure {
+            Some(settings) => Some(self.exposure.adapt(settings, target, duration)),
+            None => {
+                self.exposure.reset();
+                None
+            }
+        };
+        // A physical camera's settings are only used when the exposure isn't automatic, like on a real camera
+        let ev = exposure.unwrap_or_else(|| self.camera.exposure()) + self.options.exposure;
+        if ev != 0. {
+            apply_exposure(target, ev);
+        }
+
+        // Compress the HDR colours into the displayable range, now that they have been exposed and balanced
+        self.thread_pool.install(|| {
+            apply_white_balance(target, &self.options.white_balance);
+            apply_tone_mapping(target, self.options.tone_mapping);
+        });
+
+        let stats = RenderStats {
+            duration,
+            num_threads,
+            opts: self.options,
+            accum_frames: self.accum_buffer.frame_count(),
+            accum_samples: self.accum_buffer.sample_count(),
+            finished: self.is_finished(),
+            cancelled: false,
+            preview_scale,
+            exposure,
+            rays,
+        };
+        (stats, aovs, alpha)
+    }
+
+    /// Reprojects the accumulated samples from where the `previous` camera saw them to where the current camera sees
+    /// them. See [crate::render::reproject]
+    fn reproject_accumulation(&mut self, previous: &Camera) {
+        profile_function!();
+
+        let (Ok(previous), Ok(current)) = (previous.calculate_viewport(), self.camera.calculate_viewport()) else {
+            self.clear_accumulation();
+            return;
+        };
+        self.apply_thread_options();
+        let old_hits = self.centre_hits(&previous);
+        let new_hits = self.centre_hits(&current);
+        let sources = self.thread_pool.install(|| {
+            Zip::from(&new_hits).par_map_collect(|&hit| reproject::find_previous(hit, &previous, &old_hits))
+        });
+        trace!(
+            target: RENDERER,
+            kept = sources.iter().flatten().count(),
+            total = sources.len(),
+            "reprojected accumulation"
+        );
+
+        let source = |x, y| sources[(x, y)];
+        self.accum_buffer.reproject(source, reproject::MAX_HISTORY);
+        self.alpha_buffer.reproject(source, reproject::MAX_HISTORY);
+        self.variance_buffer.reproject(source, reproject::MAX_HISTORY);
+    }
+
+    /// Finds what the ray through the centre of each pixel hits, for [Self::reproject_accumulation()]
+    fn centre_hits(&self, viewport: &Viewport) -> Array2<PixelHit> {
+        let [w, h] = self.options.dims();
+        let interval = Interval::from(1e-3..Number::MAX);
+        let (scene, opts, data_pool) = (&self.scene, &self.options, &self.data_pool);
+        let mut hits = Array2::from_elem((w, h), PixelHit::Sky(Vector3::ZERO));
+
+        self.thread_pool.install(|| {
+            Zip::indexed(&mut hits).into_par_iter().panic_fuse().for_each_init(
+                || data_pool.get(),
+                |pooled, ((x, y), hit)| {
+                    let ray = viewport.centre_ray(x as Number, y as Number, w as Number, h as Number);
+                    let rng = &mut pooled.deref_mut().rngs[1];
+                    *hit = match Self::calculate_intersection(scene, &ray, opts, &interval, rng) {
+                        Some(hit) => PixelHit::Surface(hit.intersection.pos_w),
+                        None => PixelHit::Sky(ray.dir()),
+                    };
+                },
+            );
+        });
+
+        hits
+    }
+
+    /// Renders a single [Aov] of the current scene.
+    ///
+    /// [Aov::Beauty] is the same as a normal [render](Self::render()), and is accumulated as normal.
+    /// The other AOVs are calculated from a single camera ray through the centre of each pixel, so that their values
+    /// aren't blended across the edges of objects. Pixels where nothing was hit are zero.
+    ///
+    /// To get all the AOVs at once, without tracing the scene again for each one, enable [RenderOpts::aovs] instead.
+    pub fn render_aov(&mut self, aov: Aov) -> Image {
+        profile_function!();
+
+        if aov == Aov::Beauty {
+            return self.render().img;
+        }
+        self.ensure_scene_prepared();
+
+        let [w, h] = self.options.dims();
+        let mut img = Image::new_blank(w, h);
+        let viewport = match self.camera.calculate_viewport() {
+            Ok(viewport) => viewport,
+            Err(err) => {
+                trace!(target: RENDERER, ?err, "couldn't calculate viewport");
+                return img;
+            }
+        };
+        self.apply_thread_options();
+        let interval = Interval::from(1e-3..Number::MAX);
+        let (scene, opts, data_pool) = (&self.scene, &self.options, &self.data_pool);
+
+        self.thread_pool.install(|| {
+            Zip::indexed(img.deref_mut())
+                .into_par_iter()
+                .panic_fuse()
+                .for_each_init(
+                    || data_pool.get(),
+                    |pooled, ((x, y), px)| {
+                        let rng = &mut pooled.deref_mut().rngs[1];
+                        let ray = viewport.calc_ray(x as Number, y as Number, w as Number, h as Number, rng);
+                        *px = match Self::calculate_intersection(scene, &ray, opts, &interval, rng) {
+                            Some(hit) => aov.value(&ray, &hit.full(), rng),
+                            None => Colour::BLACK,
+                        };
+                    },
+                );
+        });
+
+        img
+    }
+
+    /// Renders the light contributed by a single [LightGroup], or the ungrouped lights (and skybox) if [None].
+    ///
+    /// Each pixel takes [RenderOpts::samples] samples, but the result is not accumulated (and doesn't affect
+    /// the accumulation of normal renders). See [crate::render::light_group] for details.
+    pub fn render_light_group(&mut self, group: Option<&LightGroup>) -> Image {
+        profile_function!();
+        self.ensure_scene_prepared();
+
+        let [w, h] = self.options.dims();
+        let mut img = Image::new_blank(w, h);
+        let viewport = match self.camera.calculate_viewport() {
+            Ok(viewport) => viewport,
+            Err(err) => {
+                trace!(target: RENDERER, ?err, "couldn't calculate viewport");
+                return img;
+            }
+        };
+        self.apply_thread_options();
+        let interval = Interval::from(1e-3..Number::MAX);
+        let (scene, opts, data_pool) = (&self.scene, &self.options, &self.data_pool);
+        let lights = LightFilter::Only(group);
+
+        self.thread_pool.install(|| {
+            Zip::indexed(img.deref_mut())
+                .into_par_iter()
+                .panic_fuse()
+                .for_each_init(
+                    || data_pool.get(),
+                    |pooled, ((x, y), px)| {
+                        let PooledData {
+                            msaa_distr,
+                            rngs: [rng_sample, rng_render],
+                            ..
+                        } = pooled.deref_mut();
+                        let samples = opts.samples.get();
+                        let mut sum = Colour::BLACK;
+                        for _ in 0..samples {
+                            let px_x = x as Number + msaa_distr.sample(rng_sample);
+                            let px_y = y as Number + msaa_distr.sample(rng_sample);
+                            let channel = viewport.pick_channel(rng_render);
+                            let ray =
+                                viewport.calc_ray_channel(px_x, px_y, w as Number, h as Number, channel, rng_render);
+                            let ray = PathRay {
+                                ray,
+                                lights,
+                                depth: 0,
+                                kind: RayKind::Primary,
+                            };
+                            let colour = Self::ray_colour(scene, ray, opts, &interval, rng_render);
+                            sum += lens::isolate_channel(colour, channel);
+                        }
+                        *px = sum / samples as Channel;
+                    },
+                );
+        });
+
+        img
+    }
+
+    /// Bakes a single [BakeChannel] into the UV space of the given mesh, placed in the world by the object-to-world
+    /// `transform`. See [crate::render::bake] for details.
+    ///
+    /// The mesh should also be part of the scene (with the same transform), so that it can occlude and
+    /// reflect light onto itself.
+    ///
+    /// The current render options are still used for tracing rays (e.g. [RenderOpts::ray_depth]),
+    /// but the dimensions and sample count are taken from the `bake_opts`.
+    pub fn bake(
+        &mut self,
+        mesh: &IndexedTriangleMesh,
+        transform: impl Into<ObjectTransform>,
+        channel: BakeChannel,
+        bake_opts: &BakeOpts,
+    ) -> Result<Image, BakeError> {
+        profile_function!();
+        self.ensure_scene_prepared();
+
+        let [w, h] = bake_opts.dims();
+        let texels = rasterise_uvs(mesh, &transform.into(), [w, h])?;
+        let mut img = Image::new_blank(w, h);
+
+        self.apply_thread_options();
+        let interval = Interval::from(1e-3..Number::MAX);
+        let ao_interval = Interval::from(1e-3..bake_opts.ao_distance);
+        let (scene, opts, data_pool) = (&self.scene, &self.options, &self.data_pool);
+        let samples = bake_opts.samples.get();
+
+        self.thread_pool.install(|| {
+            Zip::from(img.deref_mut())
+                .and(texels.data())
+                .into_par_iter()
+                .panic_fuse()
+                .for_each_init(
+                    || data_pool.get(),
+                    |pooled, (px, texel)| {
+                        let Some(SurfacePoint { pos, normal }) = *texel else {
+                            return;
+                        };
+                        let rng = &mut pooled.deref_mut().rngs[1];
+                        // Cosine-weighted directions over the hemisphere, like a perfectly diffuse surface
+                        let sample_ray = |rng: &mut Rng| {
+                            let dir = (normal + rng::normal_on_unit_sphere(rng))
+                                .try_normalize()
+                                .unwrap_or(normal);
+                            Ray::new(pos, dir)
+                        };
+
+                        *px = match channel {
+                            BakeChannel::Position => Colour::from(pos.as_array().map(|p| p as Channel)),
+                            BakeChannel::Normal => Colour::from(normal.as_array().map(|n| n as Channel)),
+                            BakeChannel::AmbientOcclusion => {
+                                let unoccluded = (0..samples)
+                                    .filter(|_| {
+                                        let ray = sample_ray(rng);
+                                        Self::calculate_intersection(scene, &ray, opts, &ao_interval, rng).is_none()
+                                    })
+                                    .count();
+                                Colour::from([(unoccluded as Number / samples as Number) as Channel; 3])
+                            }
+                            BakeChannel::Lighting => {
+                                let mut sum = Colour::BLACK;
+                                for _ in 0..samples {
+                                    let ray = sample_ray(rng);
+                                    // The first bounce is the one from the surface
+                                    let ray = PathRay {
+                                        ray,
+                                        lights: LightFilter::All,
+                                        depth: 1,
+                                        kind: RayKind::Bounce,
+                                    };
+                                    sum += Self::ray_colour(scene, ray, opts, &interval, rng);
+                                }
+                                sum / samples as Channel
+                            }
+                        };
+                    },
+                );
+        });
+
+        Ok(img)
+    }
+
+    /// Renders a [progressive preview](RenderOpts::progressive_preview) into `target`, at `1/scale` of the resolution.
+    ///
+    /// Each block of `scale` pixels across takes a single sample through its centre, which the whole block is filled
+    /// with. Nothing is accumulated
+    fn render_preview(
+        thread_pool: &ThreadPool,
+        data_pool: &opool::Pool<PooledDataAllocator, PooledData<Rng>>,
+        scene: &Scene<Obj, Sky>,
+        render_opts: &RenderOpts,
+        viewport: &Viewport,
+        scale: usize,
+        target: &mut Image,
+    ) {
+        profile_function!();
+
+        let [w, h] = render_opts.dims();
+        let mut blocks = Image::new_blank(w.div_ceil(scale), h.div_ceil(scale));
+        let interval = Interval::from(1e-3..Number::MAX);
+        let opts = &RenderOpts {
+            // The photons would take longer to trace than the whole preview
+            mode: match render_opts.mode {
+                RenderMode::PhotonMapping => RenderMode::PBR,
+                mode => mode,
+            },
+            ..*render_opts
+        };
+
+        thread_pool.install(|| {
+            Zip::indexed(blocks.deref_mut())
+                .into_par_iter()
+                .panic_fuse()
+                .for_each_init(
+                    || data_pool.get(),
+                    |pooled, ((x, y), px)| {
+                        let rng = &mut pooled.deref_mut().rngs[1];
+                        if opts.deterministic {
+                            *rng = Rng::seed_from_u64(rng::hash_seed([scale as u64, x as u64, y as u64]));
+                        }
+                        let centre = |b: usize| (b * scale) as Number + (scale - 1) as Number / 2.;
+                        let sample = Self::render_px_once(scene, viewport, opts, &interval, centre(x), centre(y), rng);
+                        validate::colour(&sample);
+                        *px = sample;
+                    },
+                );
+
+            target.ensure_size(w, h);
+            Zip::indexed(target.deref_mut())
+                .into_par_iter()
+                .for_each(|((x, y), px)| *px = blocks[(x / scale, y / scale)]);
+        });
     }
 
     /// Helper function for returning a render in case of a failure
@@ -291,13 +1233,256 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore + Send + SeedableRng> Renderer<Obj,
         return img;
     }
 
-    /// Does the actual rendering
+    /// Does the actual rendering, accumulating the pass in the `buffers` (which the image is then read from), and
+    /// returning the auxiliary AOVs (if enabled, or needed for denoising)
     ///
-    /// This is only called when the viewport is valid, and therefore an image can be rendered
+    /// This is only called when the viewport is valid, and therefore an image can be rendered. If the pass is
+    /// [cancelled](CancelToken), the tiles that haven't been started yet are skipped, and the progress is reported as
+    /// each of the others is finished. Any [workers](crate::render::distributed) take tiles from the same queue as the
+    /// local threads
     fn render_actual(
         thread_pool: &ThreadPool,
         data_pool: &opool::Pool<PooledDataAllocator, PooledData<Rng>>,
-        accum_buffer: &mut AccumulationBuffer,
+        buffers: PassBuffers,
+        scene: &Scene<Obj, Sky>,
+        render_opts: &RenderOpts,
+        viewport: &Viewport,
+        hooks: PassHooks,
+    ) -> Vec<(Aov, Image)> {
+        profile_function!();
+
+        let PassBuffers {
+            colour: accum_buffer,
+            variance: variance_buffer,
+        } = buffers;
+        let PassHooks {
+            cancel,
+            progress,
+            workers,
+            settings_hash,
+            scene_hash,
+        } = hooks;
+        let [w, h] = render_opts.dims();
+        let interval = &Interval::from(1e-3..Number::MAX);
+
+        let frame = accum_buffer.frame_count() as u64;
+        let first_sample = accum_buffer.sample_count() as u64;
+        let accum = accum_buffer.new_frame([w, h], render_opts.samples.get());
+        // Each pass is a single sample of the variance, however many samples it has
+        let variance = variance_buffer.map(|buffer| buffer.new_frame([w, h], 1));
+
+        // All the photons for the pass have to be traced before any of the pixels can gather them
+        let photons = (render_opts.mode == RenderMode::PhotonMapping)
+            .then(|| thread_pool.install(|| Self::trace_photons(scene, render_opts, viewport, interval, frame)));
+        // The workers don't have the photons, so they can't help with photon mapping
+        let workers: &mut [RemoteWorker] = match photons {
+            Some(_) => &mut [],
+            None => workers,
+        };
+        let pass = &PassParams {
+            scene,
+            opts: render_opts,
+            viewport,
+            interval,
+            photons: photons.as_ref(),
+            frame,
+            first_sample,
+        };
+
+        let tiles = split_tiles(accum.view_mut(), TILE_SIZE);
+        let (tiles_total, tiles_done, start) = (tiles.len(), AtomicUsize::new(0), puffin::now_ns());
+        let report_tile = || {
+            progress.report(RenderProgress {
+                tiles_done: tiles_done.fetch_add(1, Ordering::Relaxed) + 1,
+                tiles_total,
+                elapsed: Duration::from_nanos(puffin::now_ns().abs_diff(start)),
+            })
+        };
+
+        // The auxiliary AOVs are stored together for each pixel, and only split into separate images at the end
+        let mut aux_img = (render_opts.aovs || render_opts.denoise.is_some())
+            .then(|| Array2::<[Colour; Aov::AUXILIARY.len()]>::default((w, h)));
+        let aux_tiles = match &mut aux_img {
+            Some(aux) => split_tiles(aux.view_mut(), TILE_SIZE)
+                .into_iter()
+                .map(|(_, view)| Some(view))
+                .collect::<Vec<_>>(),
+            None => std::iter::repeat_with(|| None).take(tiles.len()).collect(),
+        };
+        let variance_tiles = match variance {
+            Some(variance) => split_tiles(variance.view_mut(), TILE_SIZE)
+                .into_iter()
+                .map(|(_, view)| Some(view))
+                .collect::<Vec<_>>(),
+            None => std::iter::repeat_with(|| None).take(tiles.len()).collect(),
+        };
+
+        // The tiles are handed out one at a time, to whichever thread (or worker) is free next.
+        // Once the pass is cancelled, the rest of the tiles are skipped, without stopping the ones already in progress
+        let queue = Mutex::new(
+            izip!(tiles, aux_tiles, variance_tiles).map(|((tile, accum), aux, variance)| TileViews {
+                tile,
+                accum,
+                aux,
+                variance,
+            }),
+        );
+        let next_tile = || match cancel.is_cancelled() {
+            true => None,
+            false => queue.lock().expect("tile queue mutex poisoned").next(),
+        };
+
+        std::thread::scope(|scope| {
+            for worker in workers.iter_mut() {
+                scope.spawn(move || {
+                    while let Some(mut views) = next_tile() {
+                        let request = TileRequest {
+                            hash: settings_hash,
+                            scene_hash,
+                            frame,
+                            first_sample,
+                            samples: render_opts.samples,
+                            tile: views.tile,
+                        };
+                        let rendered = match worker.render_tile(&request) {
+                            Ok(rendered) => Some(rendered),
+                            Err(err) => {
+                                let addr = worker.addr();
+                                warn!(target: RENDERER, ?err, %addr, "worker failed, rendering its tile locally");
+                                None
+                            }
+                        };
+                        // The rest of the tile (the auxiliary AOVs, or the whole tile if the worker failed) is
+                        // rendered by the thread pool, so that it has the same thread options as the other tiles, and
+                        // its ray stats are counted
+                        thread_pool
+                            .install(|| Self::render_tile(pass, &mut views, rendered.as_deref(), &mut data_pool.get()));
+                        report_tile();
+                        if worker.failed() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            thread_pool.install(|| {
+                std::iter::from_fn(next_tile)
+                    .par_bridge()
+                    // Return on panic as fast as possible; don't keep processing all the tiles on panic
+                    // Otherwise we get (literally) millions of panics (1 per pixel) which just hangs the renderer as it prints
+                    .panic_fuse()
+                    .for_each_init(
+                        || {
+                            let profiler_scope = puffin::profile_scope_custom!("inner");
+
+                            // Pull values from our thread pool
+                            // We hold them for the duration of each work segment, so we don't pull/push each tile
+                            (profiler_scope, data_pool.get())
+                        },
+                        // Process each tile
+                        |(_scope, pooled), mut views| {
+                            Self::render_tile(pass, &mut views, None, pooled);
+                            report_tile();
+                        },
+                    );
+            });
+        });
+
+        match aux_img {
+            Some(aux) => Aov::AUXILIARY
+                .into_iter()
+                .enumerate()
+                .map(|(i, aov)| (aov, Image::from_fn(w, h, |x, y| aux[(x, y)][i])))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Renders a single tile of a pass, adding it to the tile's views of the accumulation buffers (and rendering its
+    /// auxiliary AOVs, if there are any).
+    ///
+    /// If the tile has already been `rendered` by a [worker](crate::render::distributed), the colours it returned are
+    /// added instead of rendering the tile again
+    fn render_tile(
+        pass: &PassParams<Obj, Sky>,
+        views: &mut TileViews,
+        rendered: Option<&[Colour]>,
+        pooled: &mut PooledData<Rng>,
+    ) {
+        let TileViews {
+            tile,
+            accum,
+            aux,
+            variance,
+        } = views;
+        let tile = *tile;
+        let sample_count = pass.opts.samples.get();
+        Zip::indexed(accum).for_each(|(tile_x, tile_y), accum| {
+            let (x, y) = (tile.x + tile_x, tile.y + tile_y);
+            let sample = match rendered {
+                // In the same order as `Tile::pixels()`
+                Some(rendered) => rendered[tile_x * tile.h + tile_y],
+                None => Self::render_px(pass, [x, y], pooled),
+            };
+            // Weighted by the samples, since the passes can have different numbers of them.
+            // The buffer sums the samples as they are given, so the mean has to be scaled up
+            accum.insert_sample_weighted(sample * sample_count as Channel, sample_count as Number);
+            if let Some(variance) = variance.as_mut() {
+                variance[(tile_x, tile_y)].insert_sample(variance::moments(sample));
+            }
+        });
+
+        if let Some(aux) = aux {
+            Zip::indexed(aux).for_each(|(tile_x, tile_y), aux| {
+                let (x, y) = (tile.x + tile_x, tile.y + tile_y);
+                let rng = &mut pooled.rngs[1];
+                if pass.opts.deterministic {
+                    // A different stream to the ones used for the colour and alpha, so that it doesn't matter whether
+                    // the colour was rendered here or by a worker
+                    *rng = Rng::seed_from_u64(rng::hash_seed([pass.frame, x as u64, y as u64, 3]));
+                }
+                *aux = Self::render_px_aovs(pass.scene, pass.opts, pass.viewport, pass.interval, x, y, rng);
+            });
+        }
+    }
+
+    /// Renders a single pixel of a pass, with whichever method the pass uses, and returns the colour
+    fn render_px(pass: &PassParams<Obj, Sky>, [x, y]: [usize; 2], pooled: &mut PooledData<Rng>) -> Colour {
+        let PassParams {
+            scene,
+            opts,
+            viewport,
+            interval,
+            photons,
+            frame,
+            first_sample,
+        } = *pass;
+        if opts.deterministic {
+            // Independent of which thread (or machine) renders the pixel, or what it rendered beforehand
+            for (stream, rng) in pooled.rngs.iter_mut().enumerate() {
+                *rng = Rng::seed_from_u64(rng::hash_seed([frame, x as u64, y as u64, stream as u64]));
+            }
+        }
+        if let Some(photons) = photons {
+            Self::render_px_photons(scene, opts, viewport, interval, photons, [x, y], pooled)
+        } else if opts.sampler == SamplerKind::Random {
+            Self::render_px_msaa(scene, opts, viewport, interval, x, y, pooled)
+        } else {
+            // Each pixel scrambles the sequence differently, and the sample indices carry on across the frames, so
+            // that accumulating keeps filling in the gaps
+            let seed = rng::hash_seed([x as u64, y as u64]);
+            let mut sampler = Sampler::new(opts.sampler, seed, &mut pooled.rngs[1]).with_pixel(x, y);
+            sampler.start_sample(first_sample);
+            Self::render_px_sampled(scene, opts, viewport, interval, x, y, &mut sampler)
+        }
+    }
+
+    /// Renders the coverage of each pixel for [RenderOpts::transparent_sky], accumulating it in the `alpha_buffer`
+    /// (alongside the colour in the main accumulation buffer). The returned image has the alpha in all of its channels
+    fn render_alpha(
+        thread_pool: &ThreadPool,
+        data_pool: &opool::Pool<PooledDataAllocator, PooledData<Rng>>,
+        alpha_buffer: &mut AccumulationBuffer<Number>,
         scene: &Scene<Obj, Sky>,
         render_opts: &RenderOpts,
         viewport: &Viewport,
@@ -306,36 +1491,73 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore + Send + SeedableRng> Renderer<Obj,
         profile_function!();
 
         let [w, h] = render_opts.dims();
-
-        let mut dest_img = Image::new_blank(w, h); // Output image
-        let accum = accum_buffer.new_frame([w, h]);
+        let mut dest_img = Image::new_blank(w, h);
+        let frame = alpha_buffer.frame_count() as u64;
+        let sample_count = render_opts.samples.get();
+        let accum = alpha_buffer.new_frame([w, h], sample_count);
 
         thread_pool.install(|| {
-            let pixels = Zip::indexed(accum.deref_mut())
+            Zip::indexed(accum.deref_mut())
                 .and(dest_img.deref_mut())
                 .into_par_iter()
-                // Return on panic as fast as possible; don't keep processing all the pixels on panic
-                // Otherwise we get (literally) millions of panics (1 per pixel) which just hangs the renderer as it prints
-                .panic_fuse();
+                .panic_fuse()
+                .for_each_init(
+                    || data_pool.get(),
+                    |pooled, ((x, y), accum, dest)| {
+                        let rng = &mut pooled.deref_mut().rngs[1];
+                        if render_opts.deterministic {
+                            // A different stream to the ones used for the colour
+                            *rng = Rng::seed_from_u64(rng::hash_seed([frame, x as u64, y as u64, 2]));
+                        }
+                        let alpha = Self::render_px_alpha(scene, render_opts, viewport, interval, x, y, rng);
+                        let alpha =
+                            accum.insert_sample_weighted(alpha * sample_count as Number, sample_count as Number);
+                        *dest = Colour::from([alpha as Channel; 3]);
+                    },
+                );
+        });
 
-            pixels.for_each_init(
-                || {
-                    let profiler_scope = puffin::profile_scope_custom!("inner");
+        dest_img
+    }
 
-                    // Pull values from our thread pool
-                    // We hold them for the duration of each work segment, so we don't pull/push each pixel
-                    (profiler_scope, data_pool.get())
-                },
-                // Process each pixel
-                |(_scope, pooled), ((x, y), accum, dest)| {
-                    let sample = Self::render_px_msaa(scene, render_opts, viewport, interval, x, y, pooled.deref_mut());
-                    accum.insert_sample(sample);
-                    *dest = accum.get();
-                },
-            );
-        });
+    /// Traces the photons for a pass of [RenderMode::PhotonMapping] (one for each pixel), and sorts them into a
+    /// [PhotonMap]. See [crate::render::photon]
+    fn trace_photons(
+        scene: &Scene<Obj, Sky>,
+        opts: &RenderOpts,
+        viewport: &Viewport,
+        interval: &Interval<Number>,
+        frame: u64,
+    ) -> PhotonMap {
+        profile_function!();
+
+        // Traced in batches, so that each batch can be seeded separately when rendering deterministically
+        const BATCH_SIZE: usize = 1024;
+        let [w, h] = opts.dims();
+        let batches = (w * h).div_ceil(BATCH_SIZE);
+        let photon_count = batches * BATCH_SIZE;
+        let lights = scene.lights();
+
+        let photons = (0..batches)
+            .into_par_iter()
+            .flat_map_iter(|batch| {
+                let mut rng = match opts.deterministic {
+                    true => Rng::seed_from_u64(rng::hash_seed([frame, batch as u64])),
+                    false => Rng::from_entropy(),
+                };
+                let mut photons = vec![];
+                for _ in 0..BATCH_SIZE {
+                    photon::trace_photon(scene, &lights, photon_count, opts, interval, &mut rng, &mut photons);
+                }
+                photons
+            })
+            .collect::<Vec<_>>();
 
-        return dest_img;
+        // The radius starts off a few pixels wide where the camera is focused
+        let pixel_size = viewport.viewport_v.length() / h as Number;
+        let radius = photon::pass_radius(pixel_size * photon::INITIAL_RADIUS_PIXELS, frame);
+        trace!(target: RENDERER, count = photons.len(), radius, "traced photons");
+        PhotonMap::new(photons, radius)
     }
 }
 
@@ -346,7 +1568,8 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore + Send + SeedableRng> Renderer<Obj,
 impl<Obj: Object, Sky: Skybox, Rng: RngCore> Renderer<Obj, Sky, Rng> {
     /// Renders a single pixel in the scene, and returns the colour
     ///
-    /// Takes into account [`RenderOpts::msaa`]
+    /// Takes into account [RenderOpts::samples], which are stratified randomly and then placed by the
+    /// [filter](RenderOpts::filter)
     fn render_px_msaa(
         scene: &Scene<Obj, Sky>,
         opts: &RenderOpts,
@@ -365,7 +1588,7 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore> Renderer<Obj, Sky, Rng> {
             rngs: [rng_sample, rng_render],
         } = pooled_data;
 
-        // Samples are chosen stratified within the area of the pixel.
+        // Samples are chosen stratified within the unit square, and then placed around the pixel by the filter.
         // To keep things O(Samples) not O(Samples^2), we might have to skip stratifying some samples
         sample_coords.resize(sample_count, Vector2::ZERO);
         let px_centre = Vector2::new(x as Number, y as Number);
@@ -377,34 +1600,261 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore> Renderer<Obj, Sky, Rng> {
                 let rand: Vector2 = [msaa_distr.sample(rng_sample), msaa_distr.sample(rng_sample)].into();
                 let stratify_coord: Vector2 = [i as Number, j as Number].into();
                 // Make sure to divide `randomness` and `stratify_coord`
-                // so that it doesn't spill out across the stratified sub-pixels
-                let coord: Vector2 = px_centre + (rand * stratify_dim_inv) + (stratify_coord * stratify_dim_inv);
+                // so that it doesn't spill out across the stratified sub-squares
+                let coord: Vector2 = (rand * stratify_dim_inv) + (stratify_coord * stratify_dim_inv);
                 sample_coords[i + (stratify_dim * j)] = coord;
             }
         }
         // The remainder are fully random
         for i in (stratify_dim * stratify_dim)..sample_count {
-            sample_coords[i] =
-                px_centre + Vector2::from([msaa_distr.sample(rng_sample), msaa_distr.sample(rng_sample)]);
+            sample_coords[i] = Vector2::from([msaa_distr.sample(rng_sample), msaa_distr.sample(rng_sample)]);
         }
 
         samples.clear();
-        sample_coords
+        // The camera rays for a pixel all start close together and point the same way, so they can be traced as
+        // packets. This only changes how the first intersection is found, so only the PBR mode bothers.
+        // As many samples as possible go in wide packets, then narrow ones, and the rest are traced one at a time
+        let (wide, narrow) = match opts.mode {
+            RenderMode::PBR => (
+                sample_count - (sample_count % WIDE_PACKET_SIZE),
+                (sample_count % WIDE_PACKET_SIZE) - (sample_count % PACKET_SIZE),
+            ),
+            _ => (0, 0),
+        };
+        let (wide_coords, rest) = sample_coords.split_at(wide);
+        let (narrow_coords, single_coords) = rest.split_at(narrow);
+        for chunk in wide_coords.chunks_exact(WIDE_PACKET_SIZE) {
+            let chunk = chunk.try_into().expect("chunk is a wide packet");
+            let colours = Self::render_px_packet::<WIDE_PACKET_SIZE>(
+                scene, viewport, opts, interval, px_centre, chunk, rng_render,
+            );
+            samples.extend(colours);
+        }
+        for chunk in narrow_coords.chunks_exact(PACKET_SIZE) {
+            let chunk = chunk.try_into().expect("chunk is a packet");
+            let colours =
+                Self::render_px_packet::<PACKET_SIZE>(scene, viewport, opts, interval, px_centre, chunk, rng_render);
+            samples.extend(colours);
+        }
+        single_coords
             .iter()
-            .map(|&Vector2 { x, y }| Self::render_px_once(scene, viewport, opts, interval, x, y, rng_render))
-            .inspect(|p| validate::colour(p))
+            .map(|&u| {
+                let (offset, weight) = opts.filter.sample(u);
+                let Vector2 { x, y } = px_centre + offset;
+                let sample = Self::render_px_once(scene, viewport, opts, interval, x, y, rng_render);
+                validate::colour(&sample);
+                sample * weight as Channel
+            })
             .collect_into(samples);
 
         let overall_colour = {
             let accum: Colour = samples.iter().copied().sum();
             let count = samples.len() as Channel;
-            accum / count // Mean
+            // The negative lobes of some filters can make the mean slightly negative, which can't be displayed
+            (accum / count).map(|c| c.max(0.)) // Mean
         };
 
         validate::colour(overall_colour);
         overall_colour
     }
 
+    /// Renders a single pixel in the scene with a low-discrepancy [Sampler], and returns the colour
+    ///
+    /// The samples start from the sampler's current [index](Sampler::index), and the sampler is used for all the
+    /// random numbers of each sample (the position in the pixel, the lens, the materials, etc.)
+    fn render_px_sampled(
+        scene: &Scene<Obj, Sky>,
+        opts: &RenderOpts,
+        viewport: &Viewport,
+        interval: &Interval<Number>,
+        x: usize,
+        y: usize,
+        sampler: &mut Sampler<impl RngCore>,
+    ) -> Colour {
+        let sample_count = opts.samples.get();
+        let first_index = sampler.index();
+
+        let mut accum = Colour::BLACK;
+        for i in 0..sample_count {
+            sampler.start_sample(first_index + i as u64);
+            let (offset, weight) = opts.filter.sample(sampler.next_2d());
+            let (px_x, px_y) = (x as Number + offset.x, y as Number + offset.y);
+            let sample = Self::render_px_once(scene, viewport, opts, interval, px_x, px_y, sampler);
+            validate::colour(&sample);
+            accum += sample * weight as Channel;
+        }
+
+        // The negative lobes of some filters can make the mean slightly negative, which can't be displayed
+        let overall_colour = (accum / sample_count as Channel).map(|c| c.max(0.)); // Mean
+        validate::colour(overall_colour);
+        overall_colour
+    }
+
+    /// Renders a single pixel in [RenderMode::PhotonMapping], gathering the light from the photons traced for the pass
+    ///
+    /// The samples are placed randomly in the pixel by the [filter](RenderOpts::filter)
+    fn render_px_photons(
+        scene: &Scene<Obj, Sky>,
+        opts: &RenderOpts,
+        viewport: &Viewport,
+        interval: &Interval<Number>,
+        photons: &PhotonMap,
+        [x, y]: [usize; 2],
+        pooled_data: &mut PooledData<Rng>,
+    ) -> Colour {
+        let PooledData {
+            msaa_distr,
+            rngs: [rng_sample, rng_render],
+            ..
+        } = pooled_data;
+        let [w, h] = opts.dims().map(|d| d as Number);
+        let sample_count = opts.samples.get();
+
+        let mut accum = Colour::BLACK;
+        for _ in 0..sample_count {
+            let u = Vector2::from([msaa_distr.sample(rng_sample), msaa_distr.sample(rng_sample)]);
+            let (offset, weight) = opts.filter.sample(u);
+            let channel = viewport.pick_channel(rng_render);
+            let ray = viewport.calc_ray_channel(
+                x as Number + offset.x,
+                y as Number + offset.y,
+                w,
+                h,
+                channel,
+                rng_render,
+            );
+            validate::ray(ray);
+            let sample = Self::photon_colour(scene, ray, photons, opts, interval, rng_render);
+            let sample = lens::isolate_channel(sample, channel);
+            validate::colour(&sample);
+            accum += sample * weight as Channel;
+        }
+
+        // The negative lobes of some filters can make the mean slightly negative, which can't be displayed
+        let overall_colour = (accum / sample_count as Channel).map(|c| c.max(0.)); // Mean
+        validate::colour(overall_colour);
+        overall_colour
+    }
+
+    /// Renders the coverage of a single pixel for [RenderOpts::transparent_sky], from `0.0` (only the sky) to `1.0`
+    /// (completely covered). This is the mean of [RenderMode::Alpha] over the pixel, with the samples placed randomly
+    /// by the [filter](RenderOpts::filter)
+    fn render_px_alpha(
+        scene: &Scene<Obj, Sky>,
+        opts: &RenderOpts,
+        viewport: &Viewport,
+        interval: &Interval<Number>,
+        x: usize,
+        y: usize,
+        rng: &mut impl RngCore,
+    ) -> Number {
+        let alpha_opts = RenderOpts {
+            mode: RenderMode::Alpha,
+            ..*opts
+        };
+        let sample_count = opts.samples.get();
+
+        let mut accum = 0.;
+        for _ in 0..sample_count {
+            let (offset, weight) = opts.filter.sample(rng::vector_in_unit_square_01(rng));
+            let (px_x, px_y) = (x as Number + offset.x, y as Number + offset.y);
+            let sample = Self::render_px_once(scene, viewport, &alpha_opts, interval, px_x, px_y, rng);
+            validate::colour(&sample);
+            let alpha = sample.into_iter().sum::<Channel>() / Colour::CHANNEL_COUNT as Channel;
+            accum += alpha as Number * weight;
+        }
+
+        // Unlike the colour, the coverage can't go above one either
+        (accum / sample_count as Number).clamp(0., 1.)
+    }
+
+    /// Calculates the colour for a camera ray in [RenderMode::PhotonMapping].
+    ///
+    /// The ray is followed through any [specular](Material::is_specular) surfaces, picking up the light they emit,
+    /// until it reaches a diffuse surface, where the light is gathered from the `photons`
+    fn photon_colour(
+        scene: &Scene<Obj, Sky>,
+        mut ray: Ray,
+        photons: &PhotonMap,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Colour {
+        let mut colour = Colour::BLACK;
+        // How much of the light coming back along the ray makes it to the camera
+        let mut throughput = Colour::WHITE;
+        for bounce in 0..=opts.ray_depth {
+            ray_stats::count(if bounce == 0 { RayKind::Primary } else { RayKind::Bounce });
+            let Some(Hit { intersection, material }) = Self::calculate_intersection(scene, &ray, opts, interval, rng)
+            else {
+                if bounce == 0 && opts.transparent_sky {
+                    return colour;
+                }
+                return colour + (throughput * scene.skybox.sky_colour(&ray));
+            };
+            colour += throughput * material.emitted_light(&ray, &intersection, rng);
+            if !material.is_specular() {
+                return colour + (throughput * photons.gather(&ray, &intersection, &material, rng));
+            }
+
+            let Some(dir) = material.scatter(&ray, &intersection, rng) else {
+                break;
+            };
+            let future_ray = Ray::new(intersection.pos_w, dir).with_time(ray.time());
+            throughput = material.reflected_light(&ray, &intersection, &future_ray, &throughput, rng);
+            ray = future_ray;
+        }
+        colour
+    }
+
+    /// Renders `L` samples of the pixel at `px_centre` at once (in [RenderMode::PBR]), placing each of the `coords`
+    /// (in the unit square) around the pixel with the [filter](RenderOpts::filter). The returned samples are already
+    /// weighted by the filter
+    ///
+    /// The camera rays are intersected with the scene together as a [RayPacket], and then the rest of each path is
+    /// traced separately, the same as [Self::render_px_once()]
+    fn render_px_packet<const L: usize>(
+        scene: &Scene<Obj, Sky>,
+        viewport: &Viewport,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        px_centre: Vector2,
+        coords: &[Vector2; L],
+        rng: &mut impl RngCore,
+    ) -> [Colour; L]
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        let filtered = coords.map(|u| opts.filter.sample(u));
+        let positions = filtered.map(|(offset, _)| px_centre + offset);
+        let [w, h] = opts.dims().map(|d| d as Number);
+        let channels = positions.map(|_| viewport.pick_channel(rng));
+        let rays = std::array::from_fn(|i| {
+            let Vector2 { x, y } = positions[i];
+            viewport.calc_ray_channel(x, y, w, h, channels[i], rng)
+        });
+        rays.iter().for_each(validate::ray);
+
+        let packet = RayPacket::new(rays);
+        let hits = scene
+            .objects
+            .full_intersect_packet(&packet, &[*interval; L], Mask::splat(true), rng);
+        let mut hits = izip!(hits, channels, filtered);
+        rays.map(|ray| {
+            let (hit, channel, (_, weight)) = hits.next().expect("one hit per ray");
+            let hit = hit.map(|hit| Hit::shade(hit, opts));
+            let ray = PathRay {
+                ray,
+                lights: LightFilter::All,
+                depth: 0,
+                kind: RayKind::Primary,
+            };
+            let sample = lens::isolate_channel(Self::ray_colour_hit(scene, ray, hit, opts, interval, rng), channel);
+            validate::colour(&sample);
+            sample * weight as Channel
+        })
+    }
+
     /// Renders a given pixel a single time
     ///
     /// This handles the switching between render modes
@@ -415,22 +1865,55 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore> Renderer<Obj, Sky, Rng> {
         interval: &Interval<Number>,
         x: Number,
         y: Number,
-        rng: &mut Rng,
+        rng: &mut impl RngCore,
     ) -> Colour {
-        let ray = viewport.calc_ray(x, y, opts.width.get() as Number, opts.height.get() as Number, rng);
+        let [w, h] = opts.dims().map(|d| d as Number);
+        let channel = viewport.pick_channel(rng);
+        let ray = viewport.calc_ray_channel(x, y, w, h, channel, rng);
         validate::ray(ray);
+        lens::isolate_channel(Self::render_ray_once(scene, ray, opts, interval, rng), channel)
+    }
+
+    /// Renders a single camera ray, for [Self::render_px_once()]
+    fn render_ray_once(
+        scene: &Scene<Obj, Sky>,
+        ray: Ray,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Colour {
         let mode = opts.mode;
 
         if mode == RenderMode::PBR {
-            return Self::ray_colour_recursive(scene, &ray, opts, interval, 0, rng);
+            let ray = PathRay {
+                ray,
+                lights: LightFilter::All,
+                depth: 0,
+                kind: RayKind::Primary,
+            };
+            return Self::ray_colour(scene, ray, opts, interval, rng);
         }
 
-        let Some(FullIntersection {
+        ray_stats::count(RayKind::Primary);
+        if mode == RenderMode::BvhCost {
+            // Only what was counted for this ray
+            let before = bvh_cost::current();
+            let _ = Self::calculate_intersection(scene, &ray, opts, interval, rng);
+            return Self::bvh_cost_colour(bvh_cost::current() - before);
+        }
+
+        let Some(Hit {
             intersection: intersect,
             material,
-        }) = Self::calculate_intersection(scene, &ray, interval, rng)
+        }) = Self::calculate_intersection(scene, &ray, opts, interval, rng)
         else {
-            return scene.skybox.sky_colour(&ray);
+            return match mode {
+                RenderMode::Alpha => Colour::BLACK,
+                // Escaped straight away
+                RenderMode::PathDepth => Self::path_depth_colour(Some(0), opts),
+                _ if opts.transparent_sky => Colour::BLACK,
+                _ => scene.skybox.sky_colour(&ray),
+            };
         };
         validate::intersection(ray, &intersect, interval);
 
@@ -454,6 +1937,8 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore> Renderer<Obj, Sky, Rng> {
 
         return match mode {
             RenderMode::PBR => unreachable!("mode == RenderMode::PBR already checked"),
+            RenderMode::PhotonMapping => unreachable!("photon mapping is rendered by Self::render_px_photons()"),
+            RenderMode::BvhCost => unreachable!("mode == RenderMode::BvhCost already checked"),
             RenderMode::OutwardNormal => Colour::from(intersect.normal.as_array().map(|f| (f / 2.) as Channel + 0.5)),
             RenderMode::RayNormal => Colour::from(intersect.ray_normal.as_array().map(|f| (f / 2.) as Channel + 0.5)),
             RenderMode::Scatter => Colour::from(
@@ -487,89 +1972,454 @@ impl<Obj: Object, Sky: Skybox, Rng: RngCore> Renderer<Obj, Sky, Rng> {
                 let b = COLOURS[ceil as usize];
                 Colour::lerp(a, b, frac)
             }
+            RenderMode::PathDepth => {
+                let hit = Hit {
+                    intersection: intersect,
+                    material,
+                };
+                Self::path_depth_colour(Self::path_depth(scene, ray, hit, opts, interval, rng), opts)
+            }
+            RenderMode::Alpha => match material.shadow_catcher() {
+                // Regular objects are always opaque
+                None => Colour::WHITE,
+                Some(_) => {
+                    let hit = Hit {
+                        intersection: intersect,
+                        material,
+                    };
+                    let occlusion = Self::shadow_catcher_occlusion(scene, &ray, hit, opts, interval, rng);
+                    let alpha = occlusion.into_iter().sum::<Channel>() / Colour::CHANNEL_COUNT as Channel;
+                    Colour::from([alpha; 3])
+                }
+            },
         };
     }
 
-    /// Calculates the nearest intersection in the scene for the given ray
+    /// The colour of a pixel in [RenderMode::BvhCost]. This is a [heatmap](Colour::heatmap()) of the number of
+    /// tests, up to `MAX_COST` tests or more, on a logarithmic scale
+    fn bvh_cost_colour(cost: BvhCost) -> Colour {
+        const MAX_COST: u64 = 256;
+        let heat = ((cost.total() as Number).ln_1p() / (MAX_COST as Number).ln_1p()).min(1.);
+        Colour::heatmap(heat)
+    }
+
+    /// Follows the path of a camera `ray` (which has already hit something) through the scene, taking a single
+    /// scattered ray from each surface, and returns how many times it bounced before it ended. Returns [None] if it
+    /// was cut off by [RenderOpts::ray_depth] first. See [RenderMode::PathDepth]
+    fn path_depth<'o>(
+        scene: &'o Scene<Obj, Sky>,
+        mut ray: Ray,
+        mut hit: Hit<'o, Obj::Mat>,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Option<usize> {
+        let mut depth = 0;
+        loop {
+            let Some(dir) = hit.material.scatter(&ray, &hit.intersection, rng) else {
+                // Absorbed
+                return Some(depth);
+            };
+            if depth == opts.ray_depth {
+                return None;
+            }
+            depth += 1;
+
+            validate::normal3(&dir);
+            ray = Ray::new(hit.intersection.pos_w, dir).with_time(ray.time());
+            ray_stats::count(RayKind::Bounce);
+            let Some(next) = Self::calculate_intersection(scene, &ray, opts, interval, rng) else {
+                // Escaped to the sky
+                return Some(depth);
+            };
+            hit = next;
+        }
+    }
+
+    /// The colour of a pixel in [RenderMode::PathDepth], for a path that bounced `depth` times (or was cut off, if
+    /// [None])
+    fn path_depth_colour(depth: Option<usize>, opts: &RenderOpts) -> Colour {
+        match depth {
+            Some(depth) => Colour::heatmap(depth as Number / opts.ray_depth.max(1) as Number),
+            None => Colour::WHITE,
+        }
+    }
+
+    /// Calculates the auxiliary AOVs ([Aov::AUXILIARY]) for a pixel, from a single camera ray through its centre.
+    /// Pixels where nothing was hit are zero, the same as [Self::render_aov()]
+    fn render_px_aovs(
+        scene: &Scene<Obj, Sky>,
+        opts: &RenderOpts,
+        viewport: &Viewport,
+        interval: &Interval<Number>,
+        x: usize,
+        y: usize,
+        rng: &mut Rng,
+    ) -> [Colour; Aov::AUXILIARY.len()] {
+        let [w, h] = opts.dims();
+        let ray = viewport.calc_ray(x as Number, y as Number, w as Number, h as Number, rng);
+        ray_stats::count(RayKind::Primary);
+        match Self::calculate_intersection(scene, &ray, opts, interval, rng) {
+            Some(hit) => Aov::AUXILIARY.map(|aov| aov.value(&ray, &hit.full(), rng)),
+            None => [Colour::BLACK; Aov::AUXILIARY.len()],
+        }
+    }
+
+    /// Calculates the nearest intersection in the scene for the given ray, and the material to shade it with
+    /// (see [RenderOpts::clay])
     fn calculate_intersection<'o>(
         scene: &'o Scene<Obj, Sky>,
         ray: &Ray,
+        opts: &RenderOpts,
         interval: &Interval<Number>,
-        rng: &mut Rng,
-    ) -> Option<FullIntersection<'o, Obj::Mat>> {
-        scene.objects.full_intersect(ray, interval, rng)
+        rng: &mut impl RngCore,
+    ) -> Option<Hit<'o, Obj::Mat>> {
+        let hit = scene.objects.full_intersect(ray, interval, rng)?;
+        Some(Hit::shade(hit, opts))
     }
 
-    /// Recursive function that calculates the colour in the scene for a given ray.
+    /// Calculates the colour in the scene for a given ray.
     ///
-    /// # Recursion
-    /// This will recurse each time the ray scatters off an object in the scene, up to a limit imposed by [RenderOpts::bounces].
-    /// It should be fine for all *reasonable* bounce limits (~200), but will most likely overflow the stack past that.
-    fn ray_colour_recursive(
+    /// # Paths
+    /// The path is followed each time the ray scatters off an object in the scene, up to a limit imposed by
+    /// [RenderOpts::ray_depth]. The surfaces along the path are kept on an explicit stack (see [PathVertex]) instead
+    /// of recursing, so there is no risk of overflowing the stack, no matter how deep the paths are.
+    ///
+    /// # Light Groups
+    /// Only light that passes the ray's `lights` filter is counted, so that each [LightGroup] can be rendered
+    /// separately.
+    fn ray_colour(
         scene: &Scene<Obj, Sky>,
-        in_ray: &Ray,
+        ray: PathRay,
         opts: &RenderOpts,
         interval: &Interval<Number>,
-        depth: usize,
-        rng: &mut Rng,
+        rng: &mut impl RngCore,
     ) -> Colour {
-        if depth > opts.ray_depth {
-            return Colour::from([0.; 3]);
+        if ray.depth > opts.ray_depth {
+            return Colour::BLACK;
         }
 
-        // Intersect
-        let Some(FullIntersection { intersection, material }) =
-            Self::calculate_intersection(scene, in_ray, interval, rng)
-        else {
-            return scene.skybox.sky_colour(in_ray);
+        let hit = Self::calculate_intersection(scene, &ray.ray, opts, interval, rng);
+        Self::ray_colour_hit(scene, ray, hit, opts, interval, rng)
+    }
+
+    /// [Self::ray_colour()], once the ray has already been intersected with the scene (giving `hit`)
+    fn ray_colour_hit(
+        scene: &Scene<Obj, Sky>,
+        ray: PathRay,
+        hit: Option<Hit<Obj::Mat>>,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Colour {
+        // The first ray has already been traced, by the caller
+        ray_stats::count(ray.kind);
+        // Most paths are short, so they never need to allocate
+        let mut stack = SmallVec::<[PathVertex<Obj::Mat>; 8]>::new();
+        // The colour of the ray that was finished last, which is handed back to the vertex it was scattered from
+        let mut returned = Self::path_vertex_start(scene, &mut stack, ray, hit, opts, interval, rng);
+
+        loop {
+            let Some(vertex) = stack.last_mut() else {
+                return returned.expect("the first ray should have finished");
+            };
+
+            let step = Self::path_vertex_step(scene, vertex, returned.take(), opts, rng);
+            returned = Self::path_follow(scene, &mut stack, step, opts, interval, rng);
+        }
+    }
+
+    /// Does what the top [PathVertex] on the `stack` asked for with its last `step`: tracing another ray (and pushing
+    /// a vertex where it hits), or popping the vertex once it's done.
+    ///
+    /// Returns the colour to hand back to the (new) top vertex, if a ray was finished
+    fn path_follow<'o, 'l>(
+        scene: &'o Scene<Obj, Sky>,
+        stack: &mut SmallVec<[PathVertex<'o, 'l, Obj::Mat>; 8]>,
+        step: VertexStep<'l>,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Option<Colour> {
+        match step {
+            VertexStep::Trace(next) if next.depth > opts.ray_depth => Some(Colour::BLACK),
+            VertexStep::Trace(next) => {
+                ray_stats::count(next.kind);
+                let hit = Self::calculate_intersection(scene, &next.ray, opts, interval, rng);
+                Self::path_vertex_start(scene, stack, next, hit, opts, interval, rng)
+            }
+            VertexStep::Done(colour) => {
+                validate::colour(&colour);
+                stack.pop();
+                Some(colour)
+            }
+        }
+    }
+
+    /// Starts a new [PathVertex] where the `ray` hit the scene, and pushes it onto the `stack`.
+    ///
+    /// If nothing was hit then there's no vertex, and the colour of the ray is returned straight away
+    fn path_vertex_start<'o, 'l>(
+        scene: &'o Scene<Obj, Sky>,
+        stack: &mut SmallVec<[PathVertex<'o, 'l, Obj::Mat>; 8]>,
+        ray: PathRay<'l>,
+        hit: Option<Hit<'o, Obj::Mat>>,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Option<Colour> {
+        let Some(hit) = hit else {
+            // The skybox isn't part of any light group, and can't be seen directly when it's transparent
+            let transparent = ray.kind == RayKind::Primary && opts.transparent_sky;
+            return Some(if ray.lights.includes(None) && !transparent {
+                scene.skybox.sky_colour(&ray.ray)
+            } else {
+                Colour::BLACK
+            });
         };
-        validate::intersection(in_ray, &intersection, interval);
+        validate::intersection(ray.ray, &hit.intersection, interval);
 
-        let col_emitted = {
-            let col = material.emitted_light(in_ray, &intersection, rng);
-            validate::colour(&col);
-            col
+        let state = if hit.material.shadow_catcher().is_some() {
+            VertexState::Occlusion {
+                received: Colour::BLACK,
+                unblocked: Colour::BLACK,
+            }
+        } else {
+            let emitted = if ray.lights.includes(hit.material.light_group()) {
+                let col = hit.material.emitted_light(&ray.ray, &hit.intersection, rng);
+                validate::colour(&col);
+                col
+            } else {
+                Colour::BLACK
+            };
+            VertexState::Surface {
+                emitted,
+                reflected_sum: Colour::BLACK,
+                outgoing: None,
+            }
         };
 
-        // PERF: Chose num samples as a tradeoff between not allocating on heap, and wasting stack space
-        //  If we go above 8 branches, the sheer amount of intersections will have a much bigger perf impact
-        //  than any heap allocations. Also we want to make sure we don't overflow the stack with high depths
-        let mut scatter_samples = SmallVec::<[Colour; 8]>::new();
+        stack.push(PathVertex {
+            incoming: ray,
+            hit,
+            branches_left: opts.ray_branching.get(),
+            state,
+        });
+        None
+    }
+
+    /// Moves a [PathVertex] along, given the colour of the last ray it asked to be traced (if any), and returns
+    /// what should happen next
+    fn path_vertex_step<'l>(
+        scene: &Scene<Obj, Sky>,
+        vertex: &mut PathVertex<'_, 'l, Obj::Mat>,
+        returned: Option<Colour>,
+        opts: &RenderOpts,
+        rng: &mut impl RngCore,
+    ) -> VertexStep<'l> {
+        let PathVertex {
+            incoming,
+            hit: Hit { intersection, material },
+            branches_left,
+            state,
+        } = vertex;
+        let in_ray = &incoming.ray;
 
         // NOTE: The number of rays increases almost exponentially, with the number of branches and bounce depth
         //  For a given `d: depth, b: branches`, we check `(b^(d+1) - 1) / (b - 1)` rays, per pixel
         //  Normally, any more than 4 branches is visually indistinguishable, as well as crazy slow
 
-        // Calculate the lighting samples for the scattered ray
-        for _ in 0..opts.ray_branching.get() {
-            let scatter_ray = {
-                let Some(future_ray_dir) = material.scatter(in_ray, &intersection, rng) else {
-                    scatter_samples.push(Colour::BLACK);
-                    continue;
-                };
-                validate::normal3(&future_ray_dir);
-                let future_ray = Ray::new(intersection.pos_w, future_ray_dir);
-                validate::ray(future_ray);
-                future_ray
-            };
+        match state {
+            VertexState::Surface {
+                emitted,
+                reflected_sum,
+                outgoing,
+            } => {
+                // PERF: Only the mean of the scattered samples is needed, so keep a running sum instead of
+                //  buffering them
+                if let Some(col_future) = returned {
+                    let (scatter_ray, weight) = outgoing.take().expect("the colour should be for the outgoing ray");
+                    let col_scattered = material.reflected_light(in_ray, intersection, &scatter_ray, &col_future, rng);
+                    validate::colour(&col_scattered);
+                    *reflected_sum += col_scattered * weight;
+                }
+
+                while *branches_left > 0 {
+                    *branches_left -= 1;
+                    let Some(scattered) = material.scatter(in_ray, intersection, rng) else {
+                        // Absorbed, so contributes black
+                        continue;
+                    };
+                    validate::normal3(&scattered);
+                    let (future_ray_dir, weight) =
+                        Self::sample_scatter(scene, &*material, in_ray, intersection, scattered, rng);
+                    if weight <= 0. {
+                        // Sent somewhere the material never scatters to, so contributes black
+                        continue;
+                    }
+                    let future_ray = Ray::new(intersection.pos_w, future_ray_dir).with_time(in_ray.time());
+                    validate::ray(future_ray);
+                    *outgoing = Some((future_ray, weight));
+                    return VertexStep::Trace(PathRay {
+                        ray: future_ray,
+                        depth: incoming.depth + 1,
+                        kind: RayKind::Bounce,
+                        ..*incoming
+                    });
+                }
+
+                let col_scattered = *reflected_sum / opts.ray_branching.get() as Channel;
+                VertexStep::Done(*emitted + col_scattered)
+            }
+            VertexState::Occlusion { received, unblocked } => {
+                if let Some(col) = returned {
+                    *received += col;
+                }
+
+                while *branches_left > 0 {
+                    *branches_left -= 1;
+                    let Some(dir) = material.scatter(in_ray, intersection, rng) else {
+                        continue;
+                    };
+                    validate::normal3(&dir);
+                    let light_ray = Ray::new(intersection.pos_w, dir).with_time(in_ray.time());
+                    *unblocked += scene.skybox.sky_colour(&light_ray);
+                    // Occlusion depends on the geometry, not which lights are being rendered, so always use all the
+                    // lights
+                    return VertexStep::Trace(PathRay {
+                        ray: light_ray,
+                        lights: LightFilter::All,
+                        depth: incoming.depth + 1,
+                        kind: RayKind::Shadow,
+                    });
+                }
+
+                let strength = material
+                    .shadow_catcher()
+                    .expect("only shadow catchers find their occlusion");
+                let occlusion = occlusion_from_light(*received, *unblocked, strength);
+                *state = VertexState::Behind { occlusion };
+                // Pass through whatever is behind the catcher, darkened by the shadows
+                let behind_ray = Ray::new(intersection.pos_w, in_ray.dir()).with_time(in_ray.time());
+                VertexStep::Trace(PathRay {
+                    ray: behind_ray,
+                    depth: incoming.depth + 1,
+                    ..*incoming
+                })
+            }
+            VertexState::Behind { occlusion } => {
+                let col_behind = returned.expect("the colour should be for the ray behind the catcher");
+                VertexStep::Done(col_behind * (Colour::WHITE - *occlusion))
+            }
+        }
+    }
 
-            // Follow ray and calculate future bounces
-            let scatter_col = {
-                let col_future = Self::ray_colour_recursive(scene, &scatter_ray, opts, interval, depth + 1, rng);
-                validate::colour(&col_future);
-                let col_scattered = material.reflected_light(in_ray, &intersection, &scatter_ray, &col_future, rng);
-                validate::colour(&col_scattered);
-                col_scattered
-            };
+    /// Picks the direction to scatter a ray in from a surface: either where the material scattered it (`scattered`), or
+    /// towards the bright parts of the sky (see [Skybox::sample_direction()]). Returns the direction, and the weight
+    /// of the light that the material reflects along it.
+    ///
+    /// The two are combined with the balance heuristic (one-sample multiple importance sampling), so that a small,
+    /// bright sun doesn't have to be found by chance, without making the rest of the lighting any noisier. This only
+    /// works for materials that can tell how likely they are to scatter in a direction (see
+    /// [Material::scatter_probability()]); the others always use `scattered`.
+    fn sample_scatter(
+        scene: &Scene<Obj, Sky>,
+        material: &impl Material,
+        in_ray: &Ray,
+        intersection: &Intersection,
+        scattered: Vector3,
+        rng: &mut impl RngCore,
+    ) -> (Vector3, Channel) {
+        if material.is_specular() {
+            return (scattered, 1.);
+        }
+        let Some(sky_dir) = scene.skybox.sample_direction(rng) else {
+            return (scattered, 1.);
+        };
+        let dir = match rng::number_in_unit_line_01(rng) < SKY_SAMPLE_PROBABILITY {
+            true => sky_dir,
+            false => scattered,
+        };
+        let Some(material_pdf) = material.scatter_probability(in_ray, intersection, dir, rng) else {
+            return (scattered, 1.);
+        };
+        let sky_pdf = scene.skybox.direction_probability(dir);
+        let pdf = Lerp::lerp(material_pdf, sky_pdf, SKY_SAMPLE_PROBABILITY);
+        if pdf <= 0. {
+            return (dir, 0.);
+        }
+        (dir, (material_pdf / pdf) as Channel)
+    }
 
-            scatter_samples.push(scatter_col);
+    /// Calculates how much of the incoming light is blocked from reaching a shadow catcher, per channel, where a camera
+    /// ray hit it
+    ///
+    /// This compares the light actually arriving at the surface, to the light that would arrive from the skybox alone
+    /// if nothing was in the way. The result ranges from `0.0` (fully lit) to the catcher's strength (fully shadowed).
+    /// Materials that aren't shadow catchers never have any occlusion.
+    ///
+    /// The catcher is traced as the first [PathVertex] of a path, the same as in [Self::ray_colour()], but only until
+    /// it knows its occlusion, instead of carrying on to whatever is behind it
+    fn shadow_catcher_occlusion(
+        scene: &Scene<Obj, Sky>,
+        ray: &Ray,
+        hit: Hit<Obj::Mat>,
+        opts: &RenderOpts,
+        interval: &Interval<Number>,
+        rng: &mut impl RngCore,
+    ) -> Colour {
+        if hit.material.shadow_catcher().is_none() {
+            return Colour::BLACK;
         }
 
-        let col_scatter_sum = scatter_samples.iter().copied().sum::<Colour>();
-        let col_scattered = col_scatter_sum / scatter_samples.len() as Channel;
+        let mut stack = SmallVec::<[PathVertex<Obj::Mat>; 8]>::new();
+        stack.push(PathVertex {
+            incoming: PathRay {
+                ray: *ray,
+                lights: LightFilter::All,
+                depth: 0,
+                kind: RayKind::Primary,
+            },
+            hit,
+            branches_left: opts.ray_branching.get(),
+            state: VertexState::Occlusion {
+                received: Colour::BLACK,
+                unblocked: Colour::BLACK,
+            },
+        });
 
-        col_emitted + col_scattered
+        let mut returned = None;
+        loop {
+            let vertex = stack
+                .last_mut()
+                .expect("the catcher should find its occlusion before it's done");
+            let step = Self::path_vertex_step(scene, vertex, returned.take(), opts, rng);
+            if let [PathVertex {
+                state: VertexState::Behind { occlusion },
+                ..
+            }] = stack.as_slice()
+            {
+                return *occlusion;
+            }
+            returned = Self::path_follow(scene, &mut stack, step, opts, interval, rng);
+        }
     }
 }
 
+/// Calculates the occlusion of a shadow catcher with the given `strength`, from the light it `received` and the light
+/// that would have reached it if it was `unblocked`. See [Renderer::shadow_catcher_occlusion()]
+fn occlusion_from_light(received: Colour, unblocked: Colour, strength: Channel) -> Colour {
+    // Shadow catchers can only darken, never brighten, so clamp the visibility
+    let visibility = Colour::map2(&received, &unblocked, |received, unblocked| {
+        if unblocked > 0. {
+            (received / unblocked).clamp(0., 1.)
+        } else {
+            1.
+        }
+    });
+    let occlusion = (Colour::WHITE - visibility) * strength;
+    validate::colour(&occlusion);
+    occlusion
+}
+
 // endregion Low-level Rendering
@@ -0,0 +1,69 @@
+//! # Module [crate::render::reproject]
+//!
+//! **Temporal reprojection**: keeping the accumulated samples when the camera moves, instead of starting again from
+//! nothing. See [RenderOpts::temporal_reprojection](crate::render::render_opts::RenderOpts::temporal_reprojection).
+//!
+//! Before the first pass after the camera moves, the renderer finds what each pixel sees through the new camera (the
+//! surface hit by a ray through the centre of the pixel, or the sky), and which pixel of the old camera's image that
+//! was seen at. If the old camera saw the same thing there, the pixel takes the samples that were accumulated for that
+//! pixel. Otherwise (such as for something that was hidden behind an object before, or outside of the image), the pixel
+//! starts again from nothing.
+//!
+//! # Heuristics
+//! Whether the old camera saw the same thing is decided by:
+//! - The point being in front of the old camera, and inside of its image
+//! - The old camera seeing a surface at the same distance (within [DEPTH_TOLERANCE]) in that pixel, rather than
+//!   something in front of or behind it. The sky only ever matches the sky
+//!
+//! Only the centres of the pixels are compared, so the edges of objects can bleed by a pixel or so.
+//!
+//! Shading that depends on the direction it's seen from (like reflections) doesn't move with the surfaces, so it
+//! smears as the camera moves. To limit this, the reprojected pixels keep at most [MAX_HISTORY] samples worth of
+//! weight, so that the new samples soon take over.
+
+use crate::core::types::{Number, Point3, Vector3};
+use crate::scene::camera::Viewport;
+use ndarray::Array2;
+
+/// How far the distance to a reprojected point can be from the distance that the old camera saw in its pixel, as a
+/// fraction of the distance, for the samples to be kept
+pub const DEPTH_TOLERANCE: Number = 0.02;
+
+/// The most samples that a reprojected pixel keeps the weight of
+pub const MAX_HISTORY: Number = 16.;
+
+/// What the ray through the centre of a pixel saw
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum PixelHit {
+    /// A surface, at the given point
+    Surface(Point3),
+    /// The sky, in the given direction
+    Sky(Vector3),
+}
+
+/// Finds the pixel of the old camera's image that saw the same thing as a pixel of the new image (see the
+/// [module docs](self)), given what the `old_hits` were for each pixel of the old image
+pub(crate) fn find_previous(hit: PixelHit, old: &Viewport, old_hits: &Array2<PixelHit>) -> Option<(usize, usize)> {
+    let (w, h) = old_hits.dim();
+    let point = match hit {
+        PixelHit::Surface(point) => point,
+        // The sky is infinitely far away, so only the direction matters
+        PixelHit::Sky(dir) => old.pos + dir,
+    };
+    let (px, py) = old.project(point, w as Number, h as Number)?;
+    let (px, py) = (px.round(), py.round());
+    if !(0. ..w as Number).contains(&px) || !(0. ..h as Number).contains(&py) {
+        return None;
+    }
+    let (px, py) = (px as usize, py as usize);
+
+    let same = match (hit, old_hits[(px, py)]) {
+        (PixelHit::Surface(new), PixelHit::Surface(seen)) => {
+            let (new_dist, seen_dist) = ((new - old.pos).length(), (seen - old.pos).length());
+            (new_dist - seen_dist).abs() <= new_dist * DEPTH_TOLERANCE
+        }
+        (PixelHit::Sky(_), PixelHit::Sky(_)) => true,
+        _ => false,
+    };
+    same.then_some((px, py))
+}
@@ -0,0 +1,91 @@
+//! # Module [crate::render::tile]
+//!
+//! Splitting images into **tiles** (small rectangles of pixels) for rendering.
+//!
+//! The renderer hands out whole tiles to its worker threads (which steal tiles from each other when they run out),
+//! instead of single pixels. Neighbouring pixels normally hit the same objects, so rendering them together on the
+//! same thread keeps the scene data hot in that thread's cache. Tiles are also the unit of work for progress reporting
+//! and cancellation.
+
+use ndarray::{ArrayViewMut2, Axis};
+
+/// The width and height of the tiles used by the renderer, in pixels
+pub const TILE_SIZE: usize = 16;
+
+/// A rectangle of pixels in an image
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Tile {
+    /// The position of the first (top-left) pixel of the tile
+    pub x: usize,
+    pub y: usize,
+    /// The width of the tile, which is smaller than the tile size at the edges of the image
+    pub w: usize,
+    /// The height of the tile, which is smaller than the tile size at the edges of the image
+    pub h: usize,
+}
+
+impl Tile {
+    /// The number of pixels in the tile
+    pub fn area(&self) -> usize { self.w * self.h }
+
+    /// Iterates over the `(x, y)` coordinates (in the image) of every pixel in the tile
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> {
+        let Self { x, y, w, h } = *self;
+        (x..x + w).flat_map(move |px| (y..y + h).map(move |py| (px, py)))
+    }
+}
+
+/// Splits an image with the given dimensions into tiles of (at most) `size` pixels across, which cover every pixel
+/// exactly once. The tiles are in column order, the same as [split_tiles()]
+///
+/// # Panics
+/// Panics if the size is zero
+pub fn tiles([w, h]: [usize; 2], size: usize) -> Vec<Tile> {
+    assert_ne!(size, 0, "tile size must not be zero");
+    (0..w)
+        .step_by(size)
+        .flat_map(|x| {
+            (0..h).step_by(size).map(move |y| Tile {
+                x,
+                y,
+                w: size.min(w - x),
+                h: size.min(h - y),
+            })
+        })
+        .collect()
+}
+
+/// Splits a (mutable) view of an image into separate views for each tile, so that they can be written to in parallel.
+///
+/// The view should be indexed `[x, y]`, like [Image](crate::core::image::Image). The tiles are the same as (and in
+/// the same order as) [tiles()]
+///
+/// # Panics
+/// Panics if the size is zero
+pub fn split_tiles<T>(view: ArrayViewMut2<'_, T>, size: usize) -> Vec<(Tile, ArrayViewMut2<'_, T>)> {
+    assert_ne!(size, 0, "tile size must not be zero");
+    let (w, h) = view.dim();
+    let mut split = Vec::with_capacity(w.div_ceil(size) * h.div_ceil(size));
+
+    let mut rest = view;
+    for x in (0..w).step_by(size) {
+        let (mut column, remaining) = rest.split_at(Axis(0), size.min(w - x));
+        rest = remaining;
+        for y in (0..h).step_by(size) {
+            let (tile, remaining) = column.split_at(Axis(1), size.min(h - y));
+            column = remaining;
+            let (tile_w, tile_h) = tile.dim();
+            split.push((
+                Tile {
+                    x,
+                    y,
+                    w: tile_w,
+                    h: tile_h,
+                },
+                tile,
+            ));
+        }
+    }
+
+    split
+}
@@ -0,0 +1,54 @@
+//! # Module [crate::render::variance]
+//!
+//! Estimating how **converged** each pixel is, from the variance of its passes. See [RenderOpts::variance].
+//!
+//! For each pixel, the renderer accumulates the luminance of every pass (and its square) alongside the colour. The
+//! spread of the passes gives the variance of a single pass, and dividing that by the number of passes gives the
+//! variance of the accumulated mean: how far the pixel might still be from its converged value. This assumes that the
+//! passes all have the same number of samples, which is true for all but the last pass with
+//! [RenderOpts::target_samples].
+//!
+//! The variance is read with [Renderer::read_variance()], and can be shown with [heatmap()].
+//!
+//! [RenderOpts::variance]: crate::render::render_opts::RenderOpts::variance
+//! [RenderOpts::target_samples]: crate::render::render_opts::RenderOpts::target_samples
+//! [Renderer::read_variance()]: crate::render::renderer::Renderer::read_variance
+
+use crate::core::types::{Channel, Colour, Image, Number};
+use crate::render::accum_buffer::AccumulationValue;
+use crate::render::exposure::luminance;
+
+/// The luminance and squared luminance of a pass, accumulated for each pixel
+pub(crate) type Moments = crate::core::colour::Colour<2>;
+
+/// The variance (in [heatmap()]) that is shown as fully converged, and anything below it
+pub const HEATMAP_MIN: Number = 1e-6;
+/// The variance (in [heatmap()]) that is shown as not converged at all, and anything above it
+pub const HEATMAP_MAX: Number = 1.;
+
+/// The moments of a pass's colour, for accumulating
+pub(crate) fn moments(colour: Colour) -> Moments {
+    let l = luminance(colour);
+    Moments::new([l as Channel, (l * l) as Channel])
+}
+
+/// The variance of the mean of the accumulated passes, or infinite if there aren't at least two passes to compare
+pub(crate) fn variance_of_mean(value: &AccumulationValue<Moments>) -> Number {
+    let passes = value.weight();
+    if passes < 2. {
+        return Number::INFINITY;
+    }
+    let [mean, mean_sq] = value.get().0.map(|m| m as Number);
+    // Rounding can make it slightly negative when the passes are all (nearly) the same
+    ((mean_sq - (mean * mean)) / (passes - 1.)).max(0.)
+}
+
+/// Turns the variance of each pixel into a [heatmap](Colour::heatmap()), on a logarithmic scale from [HEATMAP_MIN]
+/// (black) to [HEATMAP_MAX] (red). Pixels without enough passes to know the variance are red as well
+pub fn heatmap(variance: &Image) -> Image {
+    let (min, max) = (HEATMAP_MIN.log10(), HEATMAP_MAX.log10());
+    Image::from_fn(variance.width(), variance.height(), |x, y| {
+        let log = (variance[(x, y)].0[0] as Number).max(HEATMAP_MIN).log10();
+        Colour::heatmap((log - min) / (max - min))
+    })
+}
@@ -0,0 +1,126 @@
+//! # Module [crate::scene::aperture]
+//!
+//! The shape of a [Camera](crate::scene::camera::Camera)'s aperture, which decides the shape of its *bokeh* (how
+//! out-of-focus points of light are blurred).
+//!
+//! Rays are fired from random points in the aperture, which is scaled to the size of the camera's defocus disk. Real
+//! lenses have an aperture made of a few straight blades, giving [polygonal](Aperture::Polygon) bokeh. Any other
+//! shape (such as a star, or the ovals of an anamorphic lens) can be made with an [ApertureMask].
+
+use crate::core::types::{Angle, Image, Number, Vector2};
+use crate::render::exposure::luminance;
+use crate::shared::rng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+
+/// The shape of an aperture. See the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Aperture {
+    /// A perfectly round aperture
+    #[default]
+    Circle,
+    /// A regular polygon, with a corner for each of the aperture's blades, inside the circle
+    Polygon {
+        /// How many blades (and so sides) the aperture has. Less than `3` blades is the same as [Aperture::Circle]
+        blades: usize,
+        /// How far the polygon is rotated (anticlockwise), from having a corner at the top
+        rotation: Angle,
+    },
+    /// An arbitrary shape, from a mask
+    Mask(ApertureMask),
+}
+
+/// A mask of an arbitrary aperture shape, for [Aperture::Mask].
+///
+/// The mask is stored as a small bitmap ([Self::SIZE] pixels square, which is plenty for the size bokeh is in an
+/// image), so that the camera can still be copied around cheaply
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApertureMask {
+    /// Each row of the mask, top to bottom, with a bit for each pixel (LSB on the left)
+    rows: [u32; ApertureMask::SIZE],
+}
+
+impl ApertureMask {
+    /// The width and height of the mask
+    pub const SIZE: usize = 32;
+    /// How many random points are tried before giving up on finding one inside of the mask
+    const MAX_TRIES: usize = 64;
+
+    /// Creates a mask from a function that is `true` inside of the aperture, taking the position inside of the unit
+    /// square (`-1..=1`, with `+y` up)
+    pub fn from_fn(mut inside: impl FnMut(Vector2) -> bool) -> Self {
+        let mut rows = [0; Self::SIZE];
+        for (y, row) in rows.iter_mut().enumerate() {
+            for x in 0..Self::SIZE {
+                if inside(Self::cell_centre(x, y)) {
+                    *row |= 1 << x;
+                }
+            }
+        }
+        Self { rows }
+    }
+
+    /// Creates a mask from an image, where the aperture is open wherever the image is brighter than `0.5`.
+    ///
+    /// The image is stretched to fill the mask, so it should normally be square
+    pub fn from_image(img: &Image) -> Self {
+        let (w, h) = (img.width(), img.height());
+        if w == 0 || h == 0 {
+            return Self::from_fn(|_| false);
+        }
+        Self::from_fn(|pos| {
+            // Back from the unit square to the pixel in the image
+            let x = (((pos.x + 1.) / 2.) * w as Number) as usize;
+            let y = (((1. - pos.y) / 2.) * h as Number) as usize;
+            luminance(img[(x.min(w - 1), y.min(h - 1))]) > 0.5
+        })
+    }
+
+    /// Whether the aperture is open at a position in the unit square (`-1..=1`, with `+y` up)
+    pub fn contains(&self, pos: Vector2) -> bool {
+        let cell = |c: Number| (((c + 1.) / 2.) * Self::SIZE as Number).clamp(0., (Self::SIZE - 1) as Number) as usize;
+        let (x, y) = (cell(pos.x), cell(-pos.y));
+        self.rows[y] & (1 << x) != 0
+    }
+
+    /// The position of the centre of a pixel in the mask, inside of the unit square
+    fn cell_centre(x: usize, y: usize) -> Vector2 {
+        let c = |i: usize| (((i as Number + 0.5) / Self::SIZE as Number) * 2.) - 1.;
+        Vector2::new(c(x), -c(y))
+    }
+}
+
+impl Aperture {
+    /// Returns a random point inside of the aperture, in the unit square (`-1..=1`, with `+y` up)
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vector2 {
+        match self {
+            Self::Polygon { blades, rotation } if *blades >= 3 => {
+                // Pick one of the triangles between the centre and each of the sides, then a point in it
+                let side = rng.gen_range(0..*blades) as Number;
+                let corner = |i: Number| {
+                    let angle = rotation.radians + (TAU * i / *blades as Number);
+                    Vector2::new(-angle.sin(), angle.cos())
+                };
+                let (a, b) = (corner(side), corner(side + 1.));
+                let (mut u, mut v) = (rng.gen::<Number>(), rng.gen::<Number>());
+                // Folds the square in half, so the point is in the triangle
+                if u + v > 1. {
+                    (u, v) = (1. - u, 1. - v);
+                }
+                (a * u) + (b * v)
+            }
+            Self::Mask(mask) => {
+                for _ in 0..ApertureMask::MAX_TRIES {
+                    let pos = rng::vector_in_unit_square(rng);
+                    if mask.contains(pos) {
+                        return pos;
+                    }
+                }
+                // The mask is (almost) all closed, so fire through the middle like a pinhole
+                Vector2::ZERO
+            }
+            Self::Circle | Self::Polygon { .. } => rng::vector_in_unit_circle(rng),
+        }
+    }
+}
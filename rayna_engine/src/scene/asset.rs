@@ -0,0 +1,324 @@
+//! # Module [crate::scene::asset]
+//!
+//! Resolution of asset paths (textures, HDRIs, etc.) to files on disk.
+//!
+//! Scenes should refer to their assets using *relative* paths, so that they can be moved between machines.
+//! An [AssetResolver] turns those relative paths into actual files, by checking (in order):
+//!
+//! 1. Absolute paths are used as-is
+//! 2. Relative to the scene's [base directory](AssetResolver::base_dir), normally the folder the scene file is in
+//! 3. Each of the [search paths](AssetResolver::search_paths), in the order they were added
+//! 4. Each of the paths in the [`ASSET_PATH_ENV_VAR`] environment variable
+//!
+//! Images are cached by the path they resolve to (and the colour space they're loaded in), so that scenes that use the
+//! same texture in many places only load it once. Clones of a resolver share the same cache.
+//!
+//! All file-loading components (such as [ImageTexture](crate::texture::image::ImageTexture) and
+//! [HdrImageSkybox](crate::skybox::hdri::HdrImageSkybox)) should load their files through a resolver.
+
+use crate::core::colour::ColourSpace;
+use crate::core::grid::{DensityGrid, GridTooLarge};
+use crate::core::targets::MAIN;
+use crate::core::types::{Colour, Image};
+use crate::scene::validation::{SceneValidation, SceneWarning};
+use getset::Getters;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::{trace, warn};
+
+/// The environment variable that holds extra asset search paths.
+///
+/// Uses the platform's normal path separator (`:` on unix, `;` on windows), like `PATH` does.
+pub const ASSET_PATH_ENV_VAR: &str = "RAYNA_ASSET_PATH";
+
+/// The most voxels that a [VDB grid](AssetResolver::load_vdb) can have once it's loaded (`512^3`, or 512MiB of
+/// densities)
+pub const MAX_VDB_VOXELS: usize = 512 * 512 * 512;
+
+#[derive(Error, Debug)]
+pub enum AssetError {
+    #[error("asset {path:?} could not be found (searched {searched:?})")]
+    NotFound { path: PathBuf, searched: Vec<PathBuf> },
+    #[error("failed to load image asset {path:?}")]
+    ImageError {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: image::ImageError,
+    },
+    #[error("failed to open asset {path:?}")]
+    IoError {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to load VDB asset {path:?}")]
+    VdbError {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: vdb_rs::ParseError,
+    },
+    #[error("VDB asset {path:?} is too large to load")]
+    VdbTooLarge {
+        path: PathBuf,
+        #[backtrace]
+        #[source]
+        source: GridTooLarge,
+    },
+    #[error("failed to load font asset {path:?}: {reason}")]
+    FontError { path: PathBuf, reason: &'static str },
+    #[error("VDB asset {path:?} has no grid named {name:?} (available: {available:?})")]
+    VdbGridNotFound {
+        path: PathBuf,
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+/// Resolves relative asset paths into files on disk. See the [module docs](self) for details.
+#[derive(Clone, Debug, Default, Getters)]
+#[get = "pub"]
+pub struct AssetResolver {
+    /// The directory that relative paths are resolved against first.
+    /// Normally the directory that contains the scene file
+    base_dir: Option<PathBuf>,
+    /// Extra directories that are searched, in order
+    search_paths: Vec<PathBuf>,
+    /// The images that have already been loaded, by their resolved path
+    #[get(skip)]
+    images: Arc<Mutex<HashMap<(PathBuf, ColourSpace), Image>>>,
+}
+
+// region Constructors
+
+impl AssetResolver {
+    /// Creates a new resolver, with no base directory and no search paths
+    pub fn new() -> Self { Self::default() }
+
+    /// Creates a resolver for a scene that was loaded from the given file.
+    ///
+    /// Relative paths will be resolved relative to the directory the scene file is in
+    pub fn for_scene_file(scene_file: impl AsRef<Path>) -> Self {
+        Self {
+            base_dir: scene_file.as_ref().parent().map(Path::to_path_buf),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the base directory that relative paths are resolved against
+    pub fn with_base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    /// Adds a directory to the end of the list of search paths
+    pub fn with_search_path(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.add_search_path(dir);
+        self
+    }
+
+    /// Adds a directory to the end of the list of search paths
+    pub fn add_search_path(&mut self, dir: impl Into<PathBuf>) { self.search_paths.push(dir.into()) }
+}
+
+// endregion Constructors
+
+// region Resolution
+
+impl AssetResolver {
+    /// Returns all the directories that will be searched for relative paths, in order
+    pub fn candidate_dirs(&self) -> Vec<PathBuf> {
+        let env_paths = std::env::var_os(ASSET_PATH_ENV_VAR)
+            .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        self.base_dir
+            .iter()
+            .cloned()
+            .chain(self.search_paths.iter().cloned())
+            .chain(env_paths)
+            .collect()
+    }
+
+    /// Resolves the path of an asset to a file that exists on disk
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, AssetError> {
+        let path = path.as_ref();
+
+        if path.is_absolute() {
+            return if path.is_file() {
+                Ok(path.to_path_buf())
+            } else {
+                Err(AssetError::NotFound {
+                    path: path.to_path_buf(),
+                    searched: vec![],
+                })
+            };
+        }
+
+        let searched = self.candidate_dirs();
+        if let Some(found) = searched.iter().map(|dir| dir.join(path)).find(|p| p.is_file()) {
+            trace!(target: MAIN, ?path, ?found, "resolved asset path");
+            return Ok(found);
+        }
+
+        Err(AssetError::NotFound {
+            path: path.to_path_buf(),
+            searched,
+        })
+    }
+
+    /// Resolves and loads an image asset, which is assumed to be sRGB-encoded (see [Self::load_image_as()])
+    pub fn load_image(&self, path: impl AsRef<Path>) -> Result<Image, AssetError> {
+        self.load_image_as(path, ColourSpace::Srgb)
+    }
+
+    /// Resolves and loads an image asset, whose integer formats are in the given colour `space` (see
+    /// [Image::from_dynamic()]). Data textures (normal maps, roughness, masks) should be loaded as
+    /// [ColourSpace::Linear].
+    ///
+    /// Images that have already been loaded are returned from the cache, without touching the file again
+    pub fn load_image_as(&self, path: impl AsRef<Path>, space: ColourSpace) -> Result<Image, AssetError> {
+        let path = self.resolve(path)?;
+        let key = (path, space);
+        if let Some(img) = self.images.lock().expect("image cache poisoned").get(&key) {
+            trace!(target: MAIN, path = ?key.0, "image asset was cached");
+            return Ok(img.clone());
+        }
+
+        let img = image::open(&key.0).map_err(|source| AssetError::ImageError {
+            path: key.0.clone(),
+            source,
+        })?;
+        let img = Image::from_dynamic(img, space);
+        self.images
+            .lock()
+            .expect("image cache poisoned")
+            .insert(key, img.clone());
+        Ok(img)
+    }
+
+    /// Resolves and loads a font (`.ttf` or `.otf`) asset, such as for
+    /// [SdfMask::from_text()](crate::texture::sdf::SdfMask::from_text)
+    pub fn load_font(&self, path: impl AsRef<Path>) -> Result<fontdue::Font, AssetError> {
+        let path = self.resolve(path)?;
+        let bytes = std::fs::read(&path).map_err(|source| AssetError::IoError {
+            path: path.clone(),
+            source,
+        })?;
+        fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+            .map_err(|reason| AssetError::FontError { path, reason })
+    }
+
+    /// Resolves and loads a grid from a sparse volume (`.vdb`) asset, such as the density of smoke or a cloud.
+    ///
+    /// If no `grid_name` is given, the `"density"` grid is used if there is one, otherwise the first grid in the file.
+    /// The grid must contain floating-point values.
+    ///
+    /// The voxels are stored densely (see [DensityGrid]), so the whole of the bounds of the active voxels is
+    /// allocated. Grids that would need more than [MAX_VDB_VOXELS] voxels are rejected with
+    /// [AssetError::VdbTooLarge]. The grid stays in index space, where each voxel is one unit across, so it should be scaled to the
+    /// voxel size (with the transform of the object it's used by)
+    pub fn load_vdb(&self, path: impl AsRef<Path>, grid_name: Option<&str>) -> Result<DensityGrid, AssetError> {
+        let path = self.resolve(path)?;
+        let vdb_err = |source| AssetError::VdbError {
+            path: path.clone(),
+            source,
+        };
+
+        let file = File::open(&path).map_err(|source| AssetError::IoError {
+            path: path.clone(),
+            source,
+        })?;
+        let mut reader = vdb_rs::VdbReader::new(BufReader::new(file)).map_err(vdb_err)?;
+
+        let available = reader.available_grids();
+        let name = match grid_name {
+            Some(name) => available.iter().find(|grid| *grid == name),
+            None => available.iter().find(|grid| *grid == "density").or(available.first()),
+        };
+        let Some(name) = name.cloned() else {
+            return Err(AssetError::VdbGridNotFound {
+                path: path.clone(),
+                name: grid_name.unwrap_or("density").to_owned(),
+                available,
+            });
+        };
+        let grid = reader.read_grid::<f32>(&name).map_err(vdb_err)?;
+
+        // Tiles are single values that fill a whole (cube-shaped) node of the tree. They're kept as tiles until the
+        // grid is allocated, so that huge tiles are rejected instead of being expanded into billions of voxels
+        let tiles = grid
+            .iter()
+            // Empty tiles are just the background, so don't need to be stored
+            .filter(|&(_, value, _)| value != 0.)
+            .map(|(pos, value, level)| {
+                let size = match level {
+                    vdb_rs::VdbLevel::Voxel => 1,
+                    vdb_rs::VdbLevel::Node3 => 8,
+                    vdb_rs::VdbLevel::Node4 => 8 * 16,
+                    vdb_rs::VdbLevel::Node5 => 8 * 16 * 32,
+                };
+                ([pos.x, pos.y, pos.z].map(|x| x.round() as i64), size, value)
+            })
+            .collect::<Vec<_>>();
+        let grid = DensityGrid::from_tiles(&tiles, MAX_VDB_VOXELS).map_err(|source| AssetError::VdbTooLarge {
+            path: path.clone(),
+            source,
+        })?;
+        trace!(target: MAIN, ?path, ?name, dims = ?grid.dims(), "loaded vdb grid");
+        Ok(grid)
+    }
+
+    /// Resolves and loads an image asset.
+    ///
+    /// If it couldn't be loaded, a [placeholder](placeholder_image) is returned instead,
+    /// and a [SceneWarning::MissingAsset] is recorded in `validation`
+    pub fn load_image_or_placeholder(&self, path: impl AsRef<Path>, validation: &mut SceneValidation) -> Image {
+        self.load_image_or_placeholder_as(path, ColourSpace::Srgb, validation)
+    }
+
+    /// Resolves and loads an image asset in the given colour `space` (see [Self::load_image_as()]), substituting a
+    /// placeholder if it couldn't be loaded (see [Self::load_image_or_placeholder()])
+    pub fn load_image_or_placeholder_as(
+        &self,
+        path: impl AsRef<Path>,
+        space: ColourSpace,
+        validation: &mut SceneValidation,
+    ) -> Image {
+        let path = path.as_ref();
+        self.load_image_as(path, space).unwrap_or_else(|err| {
+            warn!(target: MAIN, ?err, "couldn't load image asset, using placeholder");
+            validation.warn(SceneWarning::MissingAsset {
+                path: path.to_path_buf(),
+                reason: err.to_string(),
+            });
+            placeholder_image()
+        })
+    }
+}
+
+// endregion Resolution
+
+// region Placeholders
+
+/// Creates a placeholder image for assets that couldn't be loaded.
+///
+/// This is a small black and magenta checkerboard, which should be obvious in a render
+/// (similar in spirit to [texture_error_value()](crate::texture::texture_error_value)).
+pub fn placeholder_image() -> Image {
+    const SIZE: usize = 8;
+    const MAGENTA: Colour = Colour::new([1., 0., 1.]);
+    Image::from_fn(
+        SIZE,
+        SIZE,
+        |x, y| if (x + y) % 2 == 0 { Colour::BLACK } else { MAGENTA },
+    )
+}
+
+// endregion Placeholders
@@ -1,9 +1,15 @@
-use crate::core::types::{Angle, Number, Point3, Transform3, Vector3};
+use crate::core::types::{Angle, Colour, Number, Point3, Transform3, Vector2, Vector3};
+use crate::scene::aperture::Aperture;
+use crate::scene::lens::{LensDistortion, TiltShift};
+use crate::scene::stereo::StereoCamera;
+use crate::shared::math::Lerp;
 use crate::shared::ray::Ray;
-use crate::shared::{rng, validate};
+use crate::shared::validate;
 use puffin::profile_function;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+use strum_macros::{EnumIter, IntoStaticStr};
 use thiserror::Error;
 use valuable::Valuable;
 
@@ -11,28 +17,224 @@ use valuable::Valuable;
 pub struct Camera {
     /// Position the camera is located at
     pub pos: Point3,
-    /// Vertical FOV
+    /// Vertical FOV.
+    ///
+    /// For the [fisheye](Projection::FisheyeEquidistant) projections, this is the angle across the image circle, which
+    /// can be wider than 180°. It is ignored by the [Projection::Equirectangular] projection, which always sees
+    /// everything
     pub v_fov: Angle,
     /// Direction the camera is looking in
     // TODO: Refactor this to store a quaternion for the rotation instead,
     //  and calculate fwd/up/right by multiplying basis vectors by rotation
     pub fwd: Vector3,
+    /// The direction that is up for the camera, which the image is kept level with (normally [Vector3::Y]). This
+    /// doesn't need to be perpendicular to [Self::fwd], but can't be parallel to it
+    #[serde(default = "default_up")]
+    pub up: Vector3,
     /// Distance at which the camera is focused at
     pub focus_dist: Number,
     /// How large the defocus cone for each ray is.
     ///
     /// Larger angles increase defocus blur, zero gives perfect focus.
     pub defocus_angle: Angle,
+    /// Where the camera moves to by the end of the shutter interval, for motion blur.
+    ///
+    /// [None] if the camera stays still
+    #[serde(default)]
+    pub motion: Option<CameraMotion>,
+    /// How the directions around the camera are mapped onto the image
+    #[serde(default)]
+    pub projection: Projection,
+    /// The settings of a real camera to derive the FOV, defocus, and exposure from.
+    ///
+    /// If [Some], the [Self::v_fov] and [Self::defocus_angle] are ignored
+    #[serde(default)]
+    pub physical: Option<PhysicalCamera>,
+    /// The shape of the aperture, which is the shape that out-of-focus points are blurred into (the bokeh)
+    #[serde(default)]
+    pub aperture: Aperture,
+    /// The distortion of the lens, to match a real camera. See [crate::scene::lens]
+    #[serde(default)]
+    pub distortion: Option<LensDistortion>,
+    /// The shift and tilt of the lens, for the [perspective](Projection::Perspective) projection. See
+    /// [crate::scene::lens#tilt-shift]
+    #[serde(default)]
+    pub tilt_shift: Option<TiltShift>,
+    /// When the shutter is open, which is how far animated objects move while the frame is captured (for motion
+    /// blur). The camera moves to its [motion](Self::motion) over the same interval
+    #[serde(default)]
+    pub shutter: Shutter,
+    /// Renders an image for each eye, packed into one image. See [crate::scene::stereo]
+    #[serde(default)]
+    pub stereo: Option<StereoCamera>,
+}
+
+/// The settings of a real camera, for a [Camera] to be set up like. See [Camera::physical].
+///
+/// Lengths on the camera are in millimetres, and the scene is assumed to be in metres
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhysicalCamera {
+    /// The focal length of the lens (mm). Longer lenses have a narrower FOV, and more defocus blur
+    pub focal_length: Number,
+    /// The height of the sensor (mm), such as `24` for a full-frame sensor
+    pub sensor_height: Number,
+    /// The f-number of the aperture (the focal length divided by the diameter). Smaller numbers give more defocus
+    /// blur, and a brighter image
+    pub f_number: Number,
+    /// How long the shutter is open for (seconds). Longer times give a brighter image.
+    ///
+    /// This doesn't change how far [motion blur](Camera::motion) goes, that is always the whole shutter interval
+    pub shutter_time: Number,
+    /// The sensitivity of the sensor (ISO). Higher sensitivities give a brighter image
+    pub iso: Number,
+}
+
+impl Default for PhysicalCamera {
+    /// A 50mm lens on a full-frame sensor, exposed so that the scene's brightness is roughly unchanged
+    fn default() -> Self {
+        Self {
+            focal_length: 50.,
+            sensor_height: 24.,
+            f_number: 2.,
+            shutter_time: 1. / 4.,
+            iso: 1600.,
+        }
+    }
+}
+
+impl PhysicalCamera {
+    /// The vertical FOV of the lens, for a given projection (fisheye lenses map angles onto the sensor differently)
+    pub fn v_fov(&self, projection: Projection) -> Angle {
+        let half_height = self.sensor_height / 2.;
+        let half_fov = match projection {
+            Projection::Perspective | Projection::Equirectangular => (half_height / self.focal_length).atan(),
+            Projection::FisheyeEquidistant => half_height / self.focal_length,
+            Projection::FisheyeEquisolid => 2. * (half_height / (2. * self.focal_length)).clamp(-1., 1.).asin(),
+        };
+        Angle { radians: half_fov * 2. }
+    }
+
+    /// The angle of the defocus cone, for the aperture focused at the given distance
+    pub fn defocus_angle(&self, focus_dist: Number) -> Angle {
+        // Millimetres to metres
+        let aperture_radius = (self.focal_length / self.f_number) / 2. / 1000.;
+        Angle {
+            radians: 2. * (aperture_radius / focus_dist).atan(),
+        }
+    }
+
+    /// The exposure value of the settings at ISO 100 (EV100), where each stop halves the light that is captured
+    pub fn ev100(&self) -> Number {
+        ((self.f_number * self.f_number) / self.shutter_time).log2() - (self.iso / 100.).log2()
+    }
+
+    /// The exposure (stops) to apply to the image, so that a luminance of `1` is exposed like a real camera with
+    /// these settings would. This is replaced by the
+    /// [automatic exposure](crate::render::render_opts::RenderOpts::auto_exposure) if it's enabled
+    pub fn exposure(&self) -> Number {
+        /*
+        CREDITS:
+
+        Title: "Moving Frostbite to Physically Based Rendering"
+        Author: Sébastien Lagarde, Charles de Rousiers
+        URL: <https://seblagarde.wordpress.com/wp-content/uploads/2015/07/course_notes_moving_frostbite_to_pbr_v32.pdf>
+        */
+        // The saturation based sensitivity (the `1.2`), where the brightest luminance the sensor captures is just white
+        -(1.2 as Number).log2() - self.ev100()
+    }
+}
+
+/// How a [Camera] maps the directions around it onto the image
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum Projection {
+    /// A normal (rectilinear) camera, where straight lines stay straight
+    #[default]
+    Perspective,
+    /// A 360° panorama, with the longitude across the image and the latitude down it. This is the layout used for
+    /// environment maps (see [HdrImageSkybox](crate::skybox::hdri::HdrImageSkybox)) and VR panoramas, and should be
+    /// rendered at a 2:1 aspect ratio.
+    ///
+    /// The middle of the image is [Camera::fwd], and there is no defocus blur
+    Equirectangular,
+    /// A fisheye lens where the distance from the centre of the image is proportional to the angle from
+    /// [Camera::fwd]. The image circle fits the height of the image, and past it the projection carries on to angles
+    /// wider than the FOV. There is no defocus blur
+    FisheyeEquidistant,
+    /// A fisheye lens where the area on the image is proportional to the solid angle, like most real fisheye lenses.
+    /// Otherwise the same as [Projection::FisheyeEquidistant], but the FOV has to be less than 360°
+    FisheyeEquisolid,
+}
+
+impl Projection {
+    /// The widest FOV that this projection can have
+    pub fn max_fov(self) -> Angle {
+        match self {
+            Self::Perspective => Angle::from_degrees(180.),
+            Self::Equirectangular | Self::FisheyeEquidistant | Self::FisheyeEquisolid => Angle::from_degrees(360.),
+        }
+    }
+}
+
+/// When a [Camera]'s shutter is open, relative to the time of the frame (see
+/// [Scene::set_time()](crate::scene::Scene::set_time)), in the same units as the time.
+///
+/// Each camera ray is fired at a random point through the interval (see [Ray::time]), so anything that moves while
+/// the shutter is open is blurred
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Shutter {
+    pub open: Number,
+    pub close: Number,
+}
+
+impl Shutter {
+    /// A shutter that is only open for an instant, so animated objects aren't blurred
+    pub const INSTANT: Self = Self { open: 0., close: 0. };
+
+    /// A shutter that opens at the time of the frame, and stays open for the `angle` of a film camera's rotary
+    /// shutter, where `360°` is the whole `frame_duration`. Film is normally shot with a `180°` shutter
+    pub fn from_angle(frame_duration: Number, angle: Angle) -> Self {
+        Self {
+            open: 0.,
+            close: frame_duration * (angle.radians / TAU),
+        }
+    }
+
+    /// How long the shutter is open for
+    pub fn duration(&self) -> Number { self.close - self.open }
+
+    /// The time of the scene at a [time](Ray::time) through the shutter interval, for a frame at `frame_time`
+    pub fn time_at(&self, frame_time: Number, time: Number) -> Number {
+        frame_time + Lerp::lerp(self.open, self.close, time)
+    }
 }
 
+/// The position and direction of a moving [Camera] at the end of the shutter interval
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraMotion {
+    pub pos: Point3,
+    pub fwd: Vector3,
+}
+
+/// The [Camera::up] for cameras that were saved before it existed
+fn default_up() -> Vector3 { Vector3::Y }
+
 impl Default for Camera {
     fn default() -> Self {
         Self {
             pos: Point3::ZERO,
             v_fov: Angle::from_degrees(45.0),
             fwd: Vector3::Z,
+            up: Vector3::Y,
             focus_dist: 1.0,
             defocus_angle: Angle::from_degrees(0.0),
+            motion: None,
+            projection: Projection::Perspective,
+            physical: None,
+            aperture: Aperture::Circle,
+            distortion: None,
+            tilt_shift: None,
+            shutter: Shutter::INSTANT,
+            stereo: None,
         }
     }
 }
@@ -51,12 +253,85 @@ pub enum CamInvalidError {
     /// The calculated focal length was not valid. Try checking the focus distance is `> 0`
     #[error("the provided focal length was not valid")]
     FocalLengthInvalid,
+    /// The [tilt-shift](Camera::tilt_shift) settings were not valid. Try checking the tilts are less than 90°
+    #[error("the provided tilt-shift settings were not valid")]
+    TiltShiftInvalid,
+    /// The [stereo](Camera::stereo) settings were not valid. Try checking the convergence distance is `> 0`
+    #[error("the provided stereo settings were not valid")]
+    StereoInvalid,
 }
 
 impl Camera {
+    /// The vertical FOV and defocus angle of the camera, from its [PhysicalCamera] settings if it has them
+    pub fn lens(&self) -> (Angle, Angle) {
+        match &self.physical {
+            Some(physical) => (physical.v_fov(self.projection), physical.defocus_angle(self.focus_dist)),
+            None => (self.v_fov, self.defocus_angle),
+        }
+    }
+
+    /// The exposure (stops) from the [PhysicalCamera] settings, or `0` if there aren't any
+    pub fn exposure(&self) -> Number { self.physical.as_ref().map_or(0., PhysicalCamera::exposure) }
+
+    /// Creates a camera at `pos`, looking towards (and focused on) the `target`, with the rest of the settings left
+    /// as the [default](Camera::default)
+    pub fn look_at(pos: Point3, target: Point3, up: Vector3) -> Result<Self, CamInvalidError> {
+        let offset = target - pos;
+        let fwd = offset.try_normalize().ok_or(CamInvalidError::ForwardVectorInvalid)?;
+        let up = up.try_normalize().ok_or(CamInvalidError::UpVectorInvalid)?;
+        let camera = Self {
+            pos,
+            fwd,
+            up,
+            focus_dist: offset.length(),
+            ..Self::default()
+        };
+        // Make sure that `up` isn't parallel to `fwd`
+        camera.right_dir()?;
+        Ok(camera)
+    }
+
+    /// Creates a camera orbiting around the `target`, at a distance of `radius`, and looking at (and focused on) it.
+    ///
+    /// At a `yaw` and `pitch` of zero, the camera is on the `+Z` side of the target. Increasing the `yaw` moves the
+    /// camera anticlockwise around the target (when seen from above), and increasing the `pitch` moves it upwards
+    pub fn orbit(target: Point3, radius: Number, yaw: Angle, pitch: Angle) -> Result<Self, CamInvalidError> {
+        let (yaw, pitch) = (yaw.radians, pitch.radians);
+        let offset = Vector3::new(yaw.sin() * pitch.cos(), pitch.sin(), yaw.cos() * pitch.cos()) * radius;
+        Self::look_at(target + offset, target, Vector3::Y)
+    }
+
+    /// Moves the camera `distance` forwards (or backwards, if negative), keeping the same point in focus
+    pub fn dolly(&mut self, distance: Number) -> Result<(), CamInvalidError> {
+        let focus_dist = self.focus_dist - distance;
+        if focus_dist <= 0. || !focus_dist.is_finite() {
+            return Err(CamInvalidError::FocalLengthInvalid);
+        }
+        self.pos += self.fwd * distance;
+        self.focus_dist = focus_dist;
+        Ok(())
+    }
+
+    /// Zooms the camera in by a `factor` (or out, if less than `1`), without moving it. This narrows the
+    /// [FOV](Self::v_fov), or lengthens the [focal length](PhysicalCamera::focal_length) for a physical camera
+    pub fn zoom(&mut self, factor: Number) -> Result<(), CamInvalidError> {
+        if factor <= 0. || !factor.is_finite() {
+            return Err(CamInvalidError::FovInvalid);
+        }
+        match &mut self.physical {
+            Some(physical) => physical.focal_length *= factor,
+            None => {
+                self.v_fov = Angle {
+                    radians: 2. * ((self.v_fov.radians / 2.).tan() / factor).atan(),
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Helper function to calculate the right vector
     fn right_dir(&self) -> Result<Vector3, CamInvalidError> {
-        Vector3::cross(self.fwd, Vector3::Y)
+        Vector3::cross(self.fwd, self.up)
             .try_normalize()
             .ok_or(CamInvalidError::ForwardVectorInvalid)
     }
@@ -71,11 +346,10 @@ impl Camera {
         right_left: Number,
         up_down: Number,
     ) -> Result<(), CamInvalidError> {
-        let right_dir = Vector3::cross(self.fwd, Vector3::Y)
-            .try_normalize()
-            .ok_or(CamInvalidError::ForwardVectorInvalid)?;
+        let right_dir = self.right_dir()?;
+        let up_dir = self.up.try_normalize().ok_or(CamInvalidError::UpVectorInvalid)?;
 
-        self.pos += Vector3::Y * up_down;
+        self.pos += up_dir * up_down;
         self.pos += self.fwd * fwd_back;
         self.pos += right_dir * right_left;
 
@@ -91,7 +365,8 @@ impl Camera {
 
         let right_dir = self.right_dir()?;
 
-        let yaw_quat = Transform3::from_axis_angle(Vector3::Y, yaw);
+        let up_dir = self.up.try_normalize().ok_or(CamInvalidError::UpVectorInvalid)?;
+        let yaw_quat = Transform3::from_axis_angle(up_dir, yaw);
         let pitch_quat = Transform3::from_axis_angle(right_dir, pitch);
         // TODO: Implement roll (rotation around `fwd` axis)
         self.fwd = (yaw_quat * pitch_quat)
@@ -114,35 +389,56 @@ impl Camera {
     pub fn calculate_viewport(&self) -> Result<Viewport, CamInvalidError> {
         profile_function!();
 
+        let mut viewport = self.calculate_viewport_at(self.pos, self.fwd)?;
+        if let Some(CameraMotion { pos, fwd }) = self.motion {
+            viewport.end = Some(Box::new(self.calculate_viewport_at(pos, fwd)?));
+        }
+        Ok(viewport)
+    }
+
+    /// Calculates the (still) viewport, if the camera was at the given position and direction
+    fn calculate_viewport_at(&self, pos: Point3, fwd: Vector3) -> Result<Viewport, CamInvalidError> {
         // Not normally same in real cameras, but in our fake cam it is
         // Also seems to always be off by one
         let focal_length = self.focus_dist;
+        let (v_fov, defocus_angle) = self.lens();
 
-        if self.v_fov.radians == 0. {
+        if v_fov.radians == 0. || !v_fov.radians.is_finite() {
+            return Err(CamInvalidError::FovInvalid);
+        }
+        // The edge of the image circle would be at a single point, directly behind the camera
+        if self.projection == Projection::FisheyeEquisolid && v_fov.radians.abs() >= TAU {
             return Err(CamInvalidError::FovInvalid);
         }
         if focal_length == 0. {
             return Err(CamInvalidError::FocalLengthInvalid);
         }
+        if self.tilt_shift.is_some_and(|tilt_shift| !tilt_shift.is_valid()) {
+            return Err(CamInvalidError::TiltShiftInvalid);
+        }
+        if let Some(stereo) = &self.stereo {
+            if stereo.convergence <= 0. || !stereo.convergence.is_finite() || !stereo.ipd.is_finite() {
+                return Err(CamInvalidError::StereoInvalid);
+            }
+        }
 
         // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
-        let w = -self.fwd.try_normalize().ok_or(CamInvalidError::ForwardVectorInvalid)?;
-        let u = Vector3::cross(Vector3::Y, w)
+        let w = -fwd.try_normalize().ok_or(CamInvalidError::ForwardVectorInvalid)?;
+        let up = self.up.try_normalize().ok_or(CamInvalidError::UpVectorInvalid)?;
+        let u = Vector3::cross(up, w)
             .try_normalize()
             .ok_or(CamInvalidError::ForwardVectorInvalid)?;
         let v = Vector3::cross(w, u);
 
-        let pos = self.pos;
-
         // Calculate the location of the central pixel
         let pixel_center = pos - (w * focal_length);
 
         // Calculate the camera defocus disk basis vectors.
-        let defocus_radius = focal_length * (self.defocus_angle / 2.).tan();
+        let defocus_radius = focal_length * (defocus_angle / 2.).tan();
         let defocus_disk_u = u * defocus_radius;
         let defocus_disk_v = v * defocus_radius;
 
-        let theta = self.v_fov;
+        let theta = v_fov;
         let h = (theta / 2.).tan();
         let viewport_size = 2. * h * focal_length;
         // Calculate the vectors across the horizontal and down the vertical viewport edges.
@@ -163,11 +459,18 @@ impl Camera {
             viewport_v,
             defocus_disk_u,
             defocus_disk_v,
+            projection: self.projection,
+            fov: v_fov,
+            aperture: self.aperture,
+            distortion: self.distortion,
+            tilt_shift: self.tilt_shift,
+            stereo: self.stereo,
+            end: None,
         })
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Viewport {
     pub pos: Point3,
     pub pixel_center: Point3,
@@ -175,22 +478,139 @@ pub struct Viewport {
     pub viewport_v: Vector3,
     pub defocus_disk_u: Vector3,
     pub defocus_disk_v: Vector3,
+    pub projection: Projection,
+    /// The [Camera::v_fov], needed by the panoramic projections
+    pub fov: Angle,
+    /// The shape that the [defocus disk](Self::defocus_disk_u) is sampled in
+    pub aperture: Aperture,
+    pub distortion: Option<LensDistortion>,
+    pub tilt_shift: Option<TiltShift>,
+    pub stereo: Option<StereoCamera>,
+    /// The viewport at the end of the shutter interval, if the camera is moving
+    pub end: Option<Box<Viewport>>,
 }
 
+/// The furthest that [Viewport::tilted_focus()] moves the focus, as a multiple of the focus distance
+const MAX_TILTED_FOCUS: Number = 1e6;
+
 impl Viewport {
     /// Calculates the view ray for a given pixel at the coords `(px, py)`
     /// (screen-space, top-left to bot-right)
     ///
+    /// The ray is fired at a random [time](Ray::time) through the shutter interval, for motion blur.
+    ///
     /// # Parameters
     /// - `px`, `py`: Normalised pixel coordinates
-    /// - `defocus_rng`: RNG to generate a random sample in the focus disk with
+    /// - `rng`: RNG to generate a random sample in the [aperture](Self::aperture), and the time, with
     ///
     /// # Note
     /// The values `px` and `py` should already have an appropriate pixel shift (+-0.5) applied,
     /// if MSAA is desired.
-    pub fn calc_ray(&self, px: Number, py: Number, w: Number, h: Number, defocus_rng: &mut impl Rng) -> Ray {
+    pub fn calc_ray(&self, px: Number, py: Number, w: Number, h: Number, rng: &mut impl Rng) -> Ray {
+        self.calc_ray_channel(px, py, w, h, None, rng)
+    }
+
+    /// Picks which colour channel a camera ray is fired for (with [Self::calc_ray_channel()]), if the lens has
+    /// [chromatic aberration](crate::scene::lens#chromatic-aberration). The colour found by the ray should then be
+    /// passed through [isolate_channel()](crate::scene::lens::isolate_channel)
+    pub fn pick_channel(&self, rng: &mut impl Rng) -> Option<usize> {
+        let chromatic =
+            self.projection == Projection::Perspective && self.distortion.is_some_and(|lens| lens.is_chromatic());
+        chromatic.then(|| rng.gen_range(0..Colour::CHANNEL_COUNT))
+    }
+
+    /// The same as [Self::calc_ray()], but for one colour channel of a lens with chromatic aberration. See
+    /// [Self::pick_channel()]
+    pub fn calc_ray_channel(
+        &self,
+        px: Number,
+        py: Number,
+        w: Number,
+        h: Number,
+        channel: Option<usize>,
+        rng: &mut impl Rng,
+    ) -> Ray {
         // FIXME: This function is a rendering hotspot
 
+        let defocus_rand = self.aperture.sample(rng);
+        let time = rng.gen::<Number>();
+        let (ray_pos, ray_dir) = self.ray_at(px, py, w, h, channel, defocus_rand);
+
+        // Blend between where the camera was at the start and the end of the shutter interval
+        let Some(end) = &self.end else {
+            return Ray::new(ray_pos, ray_dir).with_time(time);
+        };
+        let (end_pos, end_dir) = end.ray_at(px, py, w, h, channel, defocus_rand);
+        Ray::new(
+            Lerp::lerp(ray_pos.to_vector(), end_pos.to_vector(), time).to_point(),
+            Lerp::lerp(ray_dir.normalize(), end_dir.normalize(), time),
+        )
+        .with_time(time)
+    }
+
+    /// Calculates the ray through the centre of the lens for a given pixel at the coords `(px, py)`, at the start of
+    /// the shutter interval. Unlike [Self::calc_ray()], this isn't random at all
+    pub fn centre_ray(&self, px: Number, py: Number, w: Number, h: Number) -> Ray {
+        let (ray_pos, ray_dir) = self.ray_at(px, py, w, h, None, Vector2::ZERO);
+        Ray::new(ray_pos, ray_dir)
+    }
+
+    /// Projects a point in the world onto the viewport, returning the pixel coords `(px, py)` that the point is seen
+    /// at (the inverse of [Self::centre_ray()]), or [None] if it is behind the camera.
+    ///
+    /// The coords are outside of the image if the point is outside of the camera's view. A [stereo](Self::stereo)
+    /// camera sees each point twice, so always returns [None]
+    pub fn project(&self, point: Point3, w: Number, h: Number) -> Option<(Number, Number)> {
+        if self.stereo.is_some() {
+            return None;
+        }
+        let fwd = self.pixel_center - self.pos;
+        let dir = point - self.pos;
+        if self.projection != Projection::Perspective {
+            return self.project_panoramic(dir, w, h);
+        }
+        let depth = dir.dot(fwd);
+        if depth <= 0. {
+            return None;
+        }
+        // Where the point is seen on the plane of the viewport, relative to its centre
+        let on_plane = dir * (fwd.length_squared() / depth) - fwd;
+        let u = on_plane.dot(self.viewport_u) / self.viewport_u.length_squared();
+        let v = on_plane.dot(self.viewport_v) / self.viewport_v.length_squared();
+        let (u, v) = match &self.distortion {
+            Some(lens) => {
+                let scale = self.lens_scale();
+                let (x, y) = lens.distort(u * scale, v * scale);
+                (x / scale, y / scale)
+            }
+            None => (u, v),
+        };
+        let (u, v) = match &self.tilt_shift {
+            Some(tilt_shift) => (u - tilt_shift.shift[0], v + tilt_shift.shift[1]),
+            None => (u, v),
+        };
+        Some(((u * h) + (w / 2.), (v * h) + (h / 2.)))
+    }
+
+    /// Calculates the (unnormalised) ray for the pixel coords `(px, py)`, and sample on the focus disk
+    fn ray_at(
+        &self,
+        px: Number,
+        py: Number,
+        w: Number,
+        h: Number,
+        channel: Option<usize>,
+        defocus_rand: Vector2,
+    ) -> (Point3, Vector3) {
+        // Each eye of a stereo camera is rendered like a whole image, into its part of the image
+        let (eye_offset, px, py, w, h) = match &self.stereo {
+            Some(stereo) => {
+                let (eye, px, py, w, h) = stereo.split(px, py, w, h);
+                (stereo.eye_offset(eye), px, py, w, h)
+            }
+            None => (0., px, py, w, h),
+        };
+
         // Normalise over the size of one dimension, so aspect is preserved
         // One dimension will be `-0.5..0.5`, other will have different magnitude
         // Also shift so `(0, 0)` is center
@@ -201,14 +621,134 @@ impl Viewport {
         let u = (px - (w / 2.)) / norm_dim;
         let v = (py - (h / 2.)) / norm_dim;
 
+        if self.projection != Projection::Perspective {
+            let dir = self.panoramic_dir(u, v, px / w, py / h);
+            return match &self.stereo {
+                Some(stereo) => self.stereo_panoramic(dir, eye_offset, stereo.convergence),
+                None => (self.pos, dir),
+            };
+        }
+        // Shifting the lens (up) moves the whole image (down) relative to the sensor
+        let (u, v) = match &self.tilt_shift {
+            Some(tilt_shift) => (u + tilt_shift.shift[0], v - tilt_shift.shift[1]),
+            None => (u, v),
+        };
+        let (u, v) = match &self.distortion {
+            Some(lens) => {
+                let scale = self.lens_scale();
+                let (x, y) = lens.undistort(u * scale, v * scale, channel);
+                (x / scale, y / scale)
+            }
+            None => (u, v),
+        };
+
         // Pixel position
         let pixel_sample = self.pixel_center + (self.viewport_u * u) + (self.viewport_v * v);
+        let pixel_sample = match &self.tilt_shift {
+            Some(tilt_shift) => self.tilted_focus(tilt_shift, pixel_sample),
+            None => pixel_sample,
+        };
 
         // Ray starts off on the focus disk, and then goes through the pixel position
-        let defocus_rand = rng::vector_in_unit_circle(defocus_rng);
         let ray_pos = self.pos + (self.defocus_disk_u * defocus_rand.x) + (self.defocus_disk_v * defocus_rand.y);
-        let ray_dir = pixel_sample - ray_pos;
+        let Some(stereo) = &self.stereo else {
+            return (ray_pos, pixel_sample - ray_pos);
+        };
+
+        // Moves the eye sideways, and shears the view so that the convergence plane stays where it was (off-axis)
+        let [right, _, fwd] = self.basis();
+        let eye = right * eye_offset;
+        let depth = (pixel_sample - self.pos).dot(fwd);
+        let pixel_sample = pixel_sample + (eye * (1. - (depth / stereo.convergence)));
+        let ray_pos = ray_pos + eye;
+        (ray_pos, pixel_sample - ray_pos)
+    }
+
+    /// Moves a point on the plane of focus along the ray through the centre of the lens, onto the plane of focus tilted
+    /// by the [TiltShift::tilt]
+    fn tilted_focus(&self, tilt_shift: &TiltShift, pixel_sample: Point3) -> Point3 {
+        let [right, down, fwd] = self.basis();
+        let [horizontal, vertical] = tilt_shift.tilt;
+        let normal = fwd + (right * horizontal.radians.tan()) + (down * vertical.radians.tan());
+        let dir = pixel_sample - self.pos;
+        let across = dir.dot(normal);
+        // Rays past the horizon of the tilted plane never reach it, so focus them far away instead
+        let t = if across > 0. {
+            (self.pixel_center - self.pos).dot(normal) / across
+        } else {
+            Number::INFINITY
+        };
+        self.pos + (dir * t.min(MAX_TILTED_FOCUS))
+    }
+
+    /// Moves a panoramic ray in the direction `dir` onto the circle that the eye is on, when looking in that direction
+    /// (omni-directional stereo), converging at the `convergence` distance
+    fn stereo_panoramic(&self, dir: Vector3, eye_offset: Number, convergence: Number) -> (Point3, Vector3) {
+        let [_, down, _] = self.basis();
+        let dir = dir.normalize();
+        // Straight up or down the eyes can't be on either side, so this shrinks to nothing there
+        let eye = Vector3::cross(dir, -down) * eye_offset;
+        (self.pos + eye, (dir * convergence) - eye)
+    }
+
+    /// The size of the viewport coordinates on the image plane that [LensDistortion] uses, at a distance of `1`
+    fn lens_scale(&self) -> Number { self.viewport_u.length() / (self.pixel_center - self.pos).length() }
+
+    /// The unit vectors to the right, down, and forwards from the camera
+    fn basis(&self) -> [Vector3; 3] {
+        [
+            self.viewport_u.normalize(),
+            self.viewport_v.normalize(),
+            (self.pixel_center - self.pos).normalize(),
+        ]
+    }
+
+    /// The direction for one of the panoramic projections, given the viewport coordinates `(u, v)`, and the fraction
+    /// of the way across and down the image `(x, y)`
+    fn panoramic_dir(&self, u: Number, v: Number, x: Number, y: Number) -> Vector3 {
+        let [right, down, fwd] = self.basis();
+        let half_fov = self.fov.radians / 2.;
+        let theta = match self.projection {
+            Projection::Perspective => unreachable!("perspective isn't panoramic"),
+            Projection::Equirectangular => {
+                let (lon, lat) = ((x - 0.5) * TAU, (y - 0.5) * PI);
+                return (fwd * lat.cos() * lon.cos()) + (right * lat.cos() * lon.sin()) + (down * lat.sin());
+            }
+            // The image circle has a radius of `0.5`, so that it fits the height
+            Projection::FisheyeEquidistant => Number::hypot(u, v) * 2. * half_fov,
+            Projection::FisheyeEquisolid => {
+                2. * (Number::hypot(u, v) * 2. * (half_fov / 2.).sin()).clamp(-1., 1.).asin()
+            }
+        };
+        let Some(across) = (right * u + down * v).try_normalize() else {
+            return fwd;
+        };
+        (fwd * theta.cos()) + (across * theta.sin())
+    }
 
-        return Ray::new(ray_pos, ray_dir);
+    /// The inverse of [Self::panoramic_dir()], for [Self::project()]
+    fn project_panoramic(&self, dir: Vector3, w: Number, h: Number) -> Option<(Number, Number)> {
+        let [right, down, fwd] = self.basis();
+        let dir = dir.try_normalize()?;
+        let (x, y, z) = (dir.dot(right), dir.dot(down), dir.dot(fwd));
+        let half_fov = self.fov.radians / 2.;
+        let theta = z.clamp(-1., 1.).acos();
+        let r = match self.projection {
+            Projection::Perspective => unreachable!("perspective isn't panoramic"),
+            Projection::Equirectangular => {
+                let (lon, lat) = (Number::atan2(x, z), y.clamp(-1., 1.).asin());
+                return Some((((lon / TAU) + 0.5) * w, ((lat / PI) + 0.5) * h));
+            }
+            Projection::FisheyeEquidistant => theta / half_fov / 2.,
+            Projection::FisheyeEquisolid => (theta / 2.).sin() / (half_fov / 2.).sin() / 2.,
+        };
+        let across = Number::hypot(x, y);
+        // Straight ahead, or straight behind (where the direction around the circle doesn't matter)
+        let (u, v) = if across == 0. {
+            (if theta > FRAC_PI_2 { r } else { 0. }, 0.)
+        } else {
+            (x / across * r, y / across * r)
+        };
+        Some(((u * h) + (w / 2.), (v * h) + (h / 2.)))
     }
 }
@@ -0,0 +1,107 @@
+//! # Module [crate::scene::lens]
+//!
+//! Distortion of a [Camera](crate::scene::camera::Camera)'s lens, so that renders can be matched up with footage from
+//! a real camera (such as for compositing).
+//!
+//! The distortion follows the Brown-Conrady model, as used by OpenCV and most camera calibration software, so that the
+//! coefficients from calibrating a real camera can be used as-is. The model maps where a point would be seen by a
+//! perfect lens to where it is actually seen in the image. Since rays are fired from the image, the inverse of the
+//! model is found numerically for each ray.
+//!
+//! The coordinates used by the model are on the image plane at a distance of `1` in front of the camera, with `+y`
+//! down. Only the [perspective](crate::scene::camera::Projection::Perspective) projection is distorted.
+//!
+//! # Chromatic Aberration
+//! Each colour channel can also have a slightly different focal length, which gives coloured fringes towards the
+//! edges of the image. Each camera ray is fired for only one of the channels (picked by
+//! [Viewport::pick_channel()](crate::scene::camera::Viewport::pick_channel)), and [isolate_channel()] keeps that
+//! channel of the colour it finds.
+//!
+//! # Tilt-Shift
+//! A [TiltShift] lens can be moved parallel to the sensor (shifted), and angled to it (tilted):
+//! - Shifting the lens looks up/down or across without turning the camera, so that vertical lines stay parallel in
+//!   architectural renders, instead of converging towards the top of the image
+//! - Tilting the lens tilts the plane that is in focus (the Scheimpflug principle), so that a whole receding plane
+//!   (like the ground) can be in focus, or a narrow band across the image for the miniature-faking effect. This only
+//!   has any effect when the camera has some defocus blur
+
+use crate::core::types::{Angle, Channel, Colour, Number};
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_2;
+
+/// How many iterations are used to invert the distortion, which is plenty for any realistic lens
+const UNDISTORT_ITERATIONS: usize = 10;
+
+/// The distortion coefficients of a lens. See the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LensDistortion {
+    /// The radial distortion coefficients (`k1`, `k2`, `k3`). Positive values give pincushion distortion, negative
+    /// values give barrel distortion
+    pub radial: [Number; 3],
+    /// The tangential distortion coefficients (`p1`, `p2`), from the lens not being parallel to the sensor
+    pub tangential: [Number; 2],
+    /// How much longer the focal length is for each of the colour channels, as a fraction of the focal length (such
+    /// as `[0.002, 0., -0.002]`). All zeroes means there is no chromatic aberration
+    pub chromatic: [Number; Colour::CHANNEL_COUNT],
+}
+
+impl LensDistortion {
+    /// Whether the lens has any chromatic aberration, so the channels need to be rendered separately
+    pub fn is_chromatic(&self) -> bool { self.chromatic.iter().any(|&c| c != 0.) }
+
+    /// Distorts a point on the image plane, from where a perfect lens would see it to where this lens sees it
+    pub fn distort(&self, x: Number, y: Number) -> (Number, Number) {
+        let [k1, k2, k3] = self.radial;
+        let [p1, p2] = self.tangential;
+        let r2 = (x * x) + (y * y);
+        let radial = 1. + (r2 * (k1 + (r2 * (k2 + (r2 * k3)))));
+        (
+            (x * radial) + (2. * p1 * x * y) + (p2 * (r2 + (2. * x * x))),
+            (y * radial) + (p1 * (r2 + (2. * y * y))) + (2. * p2 * x * y),
+        )
+    }
+
+    /// The inverse of [Self::distort()], finding where a perfect lens would see a point that this lens sees at
+    /// `(x, y)`, for the given colour channel (or the nominal focal length for [None])
+    pub fn undistort(&self, x: Number, y: Number, channel: Option<usize>) -> (Number, Number) {
+        // Fixed-point iteration, starting from the distorted point, which converges quickly for the small distortions
+        // of real lenses
+        let (mut ux, mut uy) = (x, y);
+        for _ in 0..UNDISTORT_ITERATIONS {
+            let (dx, dy) = self.distort(ux, uy);
+            (ux, uy) = (ux + (x - dx), uy + (y - dy));
+        }
+        // A longer focal length sees a smaller part of the scene
+        let focal = 1. + channel.map_or(0., |c| self.chromatic[c]);
+        (ux / focal, uy / focal)
+    }
+}
+
+/// The movements of a tilt-shift lens. See the [module docs](self#tilt-shift)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TiltShift {
+    /// How far the lens is shifted to the right and up, as a fraction of the height of the image
+    pub shift: [Number; 2],
+    /// How far the plane of focus is tilted, horizontally and vertically. Positive angles bring the plane of focus
+    /// nearer on the right and the bottom of the image respectively, and they have to be less than 90°
+    pub tilt: [Angle; 2],
+}
+
+impl TiltShift {
+    /// Whether the settings are valid for a camera to have
+    pub fn is_valid(&self) -> bool {
+        self.shift.iter().all(|s| s.is_finite()) && self.tilt.iter().all(|t| t.radians.abs() < FRAC_PI_2)
+    }
+}
+
+/// Keeps only the colour `channel` that a camera ray was fired for (see
+/// [Viewport::pick_channel()](crate::scene::camera::Viewport::pick_channel)), scaled up so that the average over all
+/// the channels is unchanged. Does nothing for [None]
+pub fn isolate_channel(colour: Colour, channel: Option<usize>) -> Colour {
+    let Some(channel) = channel else {
+        return colour;
+    };
+    let mut isolated = Colour::BLACK;
+    isolated[channel] = colour[channel] * Colour::CHANNEL_COUNT as Channel;
+    isolated
+}
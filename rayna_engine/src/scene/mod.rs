@@ -1,5 +1,21 @@
+use crate::core::types::Number;
+use crate::object::light::SceneLight;
+use crate::object::Object;
+use crate::scene::camera::Shutter;
+use crate::shared::noise::BlueNoise;
+use crate::shared::rng::SamplerKind;
+use crate::skybox::Skybox;
+use serde::Serialize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
+
+pub mod aperture;
+pub mod asset;
 pub mod camera;
+pub mod lens;
 pub mod preset;
+pub mod stereo;
+pub mod validation;
 
 /// Represents the environment, containing the objects in a scene along with the skybox.
 ///
@@ -13,6 +29,78 @@ pub struct Scene<Obj, Sky> {
     pub skybox: Sky,
 }
 
+/// A part of the scene that is pre-processed by [Scene::prepare()]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum PrepareStage {
+    /// See [Object::prepare()]
+    Objects,
+    /// See [Skybox::prepare()]
+    Skybox,
+    /// The [shared blue noise](BlueNoise::shared), which is used by [SamplerKind::BlueNoise]. It is the same for every
+    /// scene, so it is only generated the first time
+    BlueNoise,
+}
+
+impl<Obj: Object, Sky: Skybox> Scene<Obj, Sky> {
+    /// Pre-processes all the components of the scene in parallel (see [Object::prepare()] and
+    /// [Skybox::prepare()]), along with the data shared by all scenes (see [PrepareStage]), so that they are ready to
+    /// be rendered.
+    ///
+    /// The `progress` callback is called (from any thread) as each stage finishes.
+    pub fn prepare(&mut self, progress: impl Fn(PrepareStage) + Sync) {
+        let (objects, skybox, progress) = (&mut self.objects, &mut self.skybox, &progress);
+        rayon::scope(|scope| {
+            scope.spawn(|_| {
+                objects.prepare();
+                progress(PrepareStage::Objects);
+            });
+            scope.spawn(|_| {
+                skybox.prepare();
+                progress(PrepareStage::Skybox);
+            });
+            scope.spawn(|_| {
+                BlueNoise::shared();
+                progress(PrepareStage::BlueNoise);
+            });
+        });
+    }
+
+    /// Sets the current time of the scene, which moves any animated objects (see
+    /// [AnimatedObject](crate::object::animated::AnimatedObject)), and the sun of an animated sky (see
+    /// [TimeOfDaySkybox](crate::skybox::time_of_day::TimeOfDaySkybox)).
+    ///
+    /// This can be used to render an animation, one frame at a time. The units are up to the animation, but seconds
+    /// or frames are normal
+    pub fn set_time(&mut self, time: Number) {
+        self.objects.set_time(time);
+        self.skybox.set_time(time);
+    }
+
+    /// Sets when the camera's shutter is open, relative to the [time](Self::set_time), so that animated objects are
+    /// blurred by how far they move while it's open.
+    ///
+    /// The renderer does this automatically with the [camera's shutter](crate::scene::camera::Camera::shutter), so this only needs to be
+    /// called when rendering the scene some other way
+    pub fn set_shutter(&mut self, shutter: Shutter) { self.objects.set_shutter(shutter) }
+
+    /// Sets how far away the detailed versions of objects with levels of detail are used (see
+    /// [LodObject](crate::object::lod::LodObject)). Higher values look better, lower values render faster
+    ///
+    /// # Panics
+    /// Panics if the quality isn't positive
+    pub fn set_lod_quality(&mut self, quality: Number) { self.objects.set_lod_quality(quality) }
+
+    /// Finds all the lights in the scene (see [LightObject](crate::object::light::LightObject)), in world-space, so
+    /// that they can be sampled for direct illumination.
+    ///
+    /// The lights borrow from the scene, so this should be called again whenever the scene changes (e.g. the time)
+    pub fn lights(&self) -> Vec<SceneLight<'_, Obj::Mat>> {
+        let mut lights = vec![];
+        self.objects.collect_lights(&mut lights);
+        lights
+    }
+}
+
 /// Standard definition of [`Scene`], with all the default type parameters that are commonly used
 /// This is the specific form of [`Scene`] you want, almost all of the time.
 pub type StandardScene = Scene<
@@ -9,8 +9,9 @@
 
 use crate::core::types::{Angle, Channel, Colour, Image, Number, Point3, Size3, Transform3, Vector3};
 use crate::object::simple::SimpleObject;
+use crate::object::transform::ObjectTransform;
 use crate::skybox::none::NoSkybox;
-use crate::skybox::simple::SimpleSkybox;
+use crate::skybox::simple::{SimpleSkybox, WhiteSkybox};
 use image::ImageFormat;
 use noise::*;
 use rand::{thread_rng, Rng};
@@ -29,11 +30,13 @@ use crate::mesh::planar::infinite_plane::{InfinitePlaneMesh, UvWrappingMode};
 use crate::mesh::planar::parallelogram::ParallelogramMesh;
 use crate::mesh::planar::Planar;
 use crate::mesh::primitive::axis_box::AxisBoxMesh;
+use crate::mesh::primitive::cylinder::CylinderMesh;
 use crate::mesh::primitive::sphere::SphereMesh;
 use crate::mesh::MeshInstance;
 use crate::object::volumetric::VolumetricObject;
 use crate::object::ObjectInstance;
-use crate::scene::camera::Camera;
+use crate::scene::aperture::Aperture;
+use crate::scene::camera::{Camera, Projection, Shutter};
 use crate::shared::math::Lerp;
 use crate::shared::rng;
 use crate::skybox::hdri::HdrImageSkybox;
@@ -54,6 +57,87 @@ pub struct PresetScene {
     pub scene: StandardScene,
 }
 
+impl PresetScene {
+    /// Creates a look-dev scene, for previewing a material.
+    ///
+    /// The material is applied to a shader ball sitting on a pedestal, on a neutral grey stage. To either side of the
+    /// ball are a mid-grey (18%) diffuse sphere and a chrome sphere, which can be used as references for the lighting.
+    ///
+    /// This uses a uniform white environment, see [Self::lookdev_with_skybox()] to light it with an HDRI instead.
+    pub fn lookdev(material: impl Into<MaterialInstance<TextureInstance>>) -> Self {
+        Self::lookdev_with_skybox(material, WhiteSkybox)
+    }
+
+    /// Creates a [look-dev](Self::lookdev) scene, using the given skybox (normally an [HdrImageSkybox])
+    /// to light the scene
+    pub fn lookdev_with_skybox(
+        material: impl Into<MaterialInstance<TextureInstance>>,
+        skybox: impl Into<SkyboxInstance>,
+    ) -> Self {
+        let mut objects: Vec<SimpleObject<MeshInstance, MaterialInstance<TextureInstance>>> = Vec::new();
+
+        // Stage
+        objects.push(SimpleObject::new(
+            InfinitePlaneMesh::new(Planar::new(Point3::ZERO, Vector3::X, Vector3::Z), UvWrappingMode::Wrap),
+            LambertianMaterial {
+                albedo: [0.5; 3].into(),
+            },
+            None,
+        ));
+
+        // Shader ball, on a pedestal
+        objects.push(SimpleObject::new(
+            CylinderMesh::new((0., 0., 0.), (0., 0.15, 0.), 0.35),
+            LambertianMaterial {
+                albedo: [0.1; 3].into(),
+            },
+            None,
+        ));
+        objects.push(SimpleObject::new(SphereMesh::new((0., 0.65, 0.), 0.5), material, None));
+
+        // Reference spheres
+        objects.push(SimpleObject::new(
+            SphereMesh::new((-1.1, 0.25, 0.3), 0.25),
+            LambertianMaterial {
+                albedo: [0.18; 3].into(),
+            },
+            None,
+        ));
+        objects.push(SimpleObject::new(
+            SphereMesh::new((1.1, 0.25, 0.3), 0.25),
+            MetalMaterial {
+                albedo: [0.95; 3].into(),
+                fuzz: 0.,
+            },
+            None,
+        ));
+
+        PresetScene {
+            name: "Look-Dev",
+            camera: Camera {
+                pos: Point3::new(0., 0.9, 3.2),
+                fwd: Vector3::new(0., -0.15, -1.).normalize(),
+                up: Vector3::Y,
+                v_fov: Angle::from_degrees(40.),
+                focus_dist: 3.2,
+                defocus_angle: Angle::from_degrees(0.),
+                motion: None,
+                projection: Projection::Perspective,
+                physical: None,
+                aperture: Aperture::Circle,
+                distortion: None,
+                tilt_shift: None,
+                shutter: Shutter::INSTANT,
+                stereo: None,
+            },
+            scene: Scene {
+                objects: objects.into(),
+                skybox: skybox.into(),
+            },
+        }
+    }
+}
+
 // FIXME: Calling these presets is extremely slow.
 //  `RTTNW_DEMO()` takes ~1.4 sec, `ALL()` takes ~4.1 sec
 
@@ -62,7 +146,16 @@ pub struct PresetScene {
 /// # Warning
 /// Currently all scenes are re-created each time this is called.
 /// You will want to cache this value somewhere
-pub fn ALL() -> [PresetScene; 5] { [TESTING(), RTIAW_DEMO(), RTIAW_DEMO_DARK(), RTTNW_DEMO(), CORNELL()] }
+pub fn ALL() -> [PresetScene; 6] {
+    [
+        TESTING(),
+        RTIAW_DEMO(),
+        RTIAW_DEMO_DARK(),
+        RTTNW_DEMO(),
+        CORNELL(),
+        LOOKDEV(),
+    ]
+}
 
 /// A testing scene used only during development
 pub fn TESTING() -> PresetScene {
@@ -75,7 +168,8 @@ pub fn TESTING() -> PresetScene {
             refractive_index: 1.335,
         };
         objects.push(SimpleObject::new(
-            PolygonisedIsosurfaceMesh::new(64, |p_raw| {
+            // Not a true SDF, so needs a looser bound
+            PolygonisedIsosurfaceMesh::new_with_lipschitz(64, 1.5, |p_raw| {
                 let [x, y, z] = p_raw.into();
 
                 // NOTE: Point is given to us inside range `0.0..=1.0`
@@ -142,9 +236,18 @@ pub fn TESTING() -> PresetScene {
         camera: Camera {
             pos: Point3::new(0.5, 0.1, 0.7),
             fwd: Vector3::new(0., 0., -1.).normalize(),
+            up: Vector3::Y,
             v_fov: Angle::from_degrees(40.),
             focus_dist: 1.,
             defocus_angle: Angle::from_degrees(0.),
+            motion: None,
+            projection: Projection::Perspective,
+            physical: None,
+            aperture: Aperture::Circle,
+            distortion: None,
+            tilt_shift: None,
+            shutter: Shutter::INSTANT,
+            stereo: None,
         },
         scene: Scene {
             objects: objects.into(),
@@ -242,9 +345,18 @@ pub fn RTIAW_DEMO() -> PresetScene {
         camera: Camera {
             pos: Point3::new(13., 2., 3.),
             fwd: Vector3::new(-13., -2., -3.).normalize(),
+            up: Vector3::Y,
             v_fov: Angle::from_degrees(20.),
             focus_dist: 10.,
             defocus_angle: Angle::from_degrees(0.6),
+            motion: None,
+            projection: Projection::Perspective,
+            physical: None,
+            aperture: Aperture::Circle,
+            distortion: None,
+            tilt_shift: None,
+            shutter: Shutter::INSTANT,
+            stereo: None,
         },
         scene: Scene {
             objects: objects.into(),
@@ -294,6 +406,7 @@ pub fn RTIAW_DEMO_DARK() -> PresetScene {
             } else {
                 LightMaterial {
                     emissive: rng::colour_rgb_range(rng, 0.0..0.8).into(),
+                    group: None,
                 }
                 .into()
             };
@@ -323,6 +436,7 @@ pub fn RTIAW_DEMO_DARK() -> PresetScene {
             let material_choice = rng.gen::<Number>();
             let material: MaterialInstance<TextureInstance> = LightMaterial {
                 emissive: rng::colour_rgb_range(rng, 10.0..50.0).into(),
+                group: None,
             }
             .into();
 
@@ -379,9 +493,18 @@ pub fn RTIAW_DEMO_DARK() -> PresetScene {
         camera: Camera {
             pos: Point3::new(13., 2., 3.),
             fwd: Vector3::new(-13., -2., -3.).normalize(),
+            up: Vector3::Y,
             v_fov: Angle::from_degrees(20.),
             focus_dist: 10.,
             defocus_angle: Angle::from_degrees(0.6),
+            motion: None,
+            projection: Projection::Perspective,
+            physical: None,
+            aperture: Aperture::Circle,
+            distortion: None,
+            tilt_shift: None,
+            shutter: Shutter::INSTANT,
+            stereo: None,
         },
         scene: Scene {
             objects: objects.into(),
@@ -439,6 +562,7 @@ pub fn RTTNW_DEMO() -> PresetScene {
                 ParallelogramMesh::new(Planar::new((1.23, 5.54, 1.47), (3., 0., 0.), (0., 0., 2.65))),
                 LightMaterial {
                     emissive: solid_texture([7.; 3]),
+                    group: None,
                 },
                 None,
             )
@@ -447,14 +571,17 @@ pub fn RTTNW_DEMO() -> PresetScene {
     }
 
     {
-        // BROWN SPHERE
+        // BROWN SPHERE (MOVING)
         objects.push(
             SimpleObject::new(
                 SphereMesh::new((4., 4., 2.), 0.5),
                 LambertianMaterial {
                     albedo: solid_texture([0.7, 0.3, 0.1]),
                 },
-                None,
+                ObjectTransform::new_moving(
+                    Transform3::IDENTITY,
+                    Transform3::from_translation(Vector3::new(0.3, 0., 0.)),
+                ),
             )
             .into(),
         );
@@ -593,9 +720,18 @@ pub fn RTTNW_DEMO() -> PresetScene {
         camera: Camera {
             pos: Point3::new(4.78, 2.78, -6.0),
             fwd: Vector3::new(-1., 0., 3.).normalize(),
+            up: Vector3::Y,
             v_fov: Angle::from_degrees(40.),
             focus_dist: 1.,
             defocus_angle: Angle::from_degrees(0.0),
+            motion: None,
+            projection: Projection::Perspective,
+            physical: None,
+            aperture: Aperture::Circle,
+            distortion: None,
+            tilt_shift: None,
+            shutter: Shutter::INSTANT,
+            stereo: None,
         },
         scene: Scene {
             objects: objects.into(),
@@ -640,7 +776,10 @@ pub fn CORNELL() -> PresetScene {
 
         o.push(SimpleObject::new(
             ParallelogramMesh::new(Planar::new((0.4, 0.9999, 0.4), (0.2, 0., 0.), (0., 0., 0.2))),
-            LightMaterial { emissive: light.into() },
+            LightMaterial {
+                emissive: light.into(),
+                group: None,
+            },
             None,
         ));
     }
@@ -671,9 +810,18 @@ pub fn CORNELL() -> PresetScene {
         camera: Camera {
             pos: Point3::new(0.5, 0.5, 2.3),
             fwd: Vector3::new(0., 0., -1.).normalize(),
+            up: Vector3::Y,
             v_fov: Angle::from_degrees(40.),
             focus_dist: 1.,
             defocus_angle: Angle::from_degrees(0.),
+            motion: None,
+            projection: Projection::Perspective,
+            physical: None,
+            aperture: Aperture::Circle,
+            distortion: None,
+            tilt_shift: None,
+            shutter: Shutter::INSTANT,
+            stereo: None,
         },
         scene: Scene {
             objects: objects.into(),
@@ -681,3 +829,11 @@ pub fn CORNELL() -> PresetScene {
         },
     }
 }
+
+/// A [look-dev](PresetScene::lookdev) scene, showing off a simple glossy material
+pub fn LOOKDEV() -> PresetScene {
+    PresetScene::lookdev(MetalMaterial {
+        albedo: [0.8, 0.3, 0.2].into(),
+        fuzz: 0.25,
+    })
+}
@@ -0,0 +1,92 @@
+//! # Module [crate::scene::stereo]
+//!
+//! Stereo rendering, where a [Camera](crate::scene::camera::Camera) renders an image for each eye (such as for VR
+//! headsets), packed into one image.
+//!
+//! The eyes are either side of the camera's position, [StereoCamera::ipd] apart, and are converged so that things at
+//! [StereoCamera::convergence] are seen at the same place by both eyes (they appear to be at the depth of the screen).
+//! For the [perspective](crate::scene::camera::Projection::Perspective) projection, the eyes look in parallel and
+//! their images are shifted (*off-axis* stereo), which avoids the vertical parallax of turning the eyes inwards.
+//!
+//! For the panoramic projections (such as a 360° [equirectangular](crate::scene::camera::Projection::Equirectangular)
+//! panorama), each ray is fired from its own position on a circle around the camera, as if the viewer had turned
+//! their head to look in that direction (*omni-directional stereo*, or ODS). The circle shrinks towards looking
+//! straight up or down, where the eyes can't be on either side of the view, so the stereo effect fades out there.
+
+use crate::core::types::Number;
+use serde::{Deserialize, Serialize};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+/// The settings for stereo rendering. See the [module docs](self)
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StereoCamera {
+    /// The interpupillary distance (IPD), how far apart the eyes are
+    pub ipd: Number,
+    /// The distance that the eyes converge at. Things closer than this appear to come out of the screen, and things
+    /// further away appear behind it
+    pub convergence: Number,
+    /// How the images for the eyes are packed into the image
+    pub layout: StereoLayout,
+}
+
+impl Default for StereoCamera {
+    /// The average IPD of an adult (assuming the scene is in metres), converged a few metres away
+    fn default() -> Self {
+        Self {
+            ipd: 0.064,
+            convergence: 3.,
+            layout: StereoLayout::SideBySide,
+        }
+    }
+}
+
+/// How the images for the two eyes are packed into one image
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum StereoLayout {
+    /// The left eye is in the left half of the image, and the right eye in the right half
+    #[default]
+    SideBySide,
+    /// The left eye is in the top half of the image, and the right eye in the bottom half. This is the usual layout
+    /// for 360° panoramas
+    TopBottom,
+}
+
+/// One of the eyes of a [StereoCamera]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+impl StereoCamera {
+    /// Finds which eye a pixel `(px, py)` of the whole image is for, returning the eye and the pixel's coords and the
+    /// size `(w, h)` of that eye's image
+    pub fn split(&self, px: Number, py: Number, w: Number, h: Number) -> (Eye, Number, Number, Number, Number) {
+        match self.layout {
+            StereoLayout::SideBySide => {
+                let half = w / 2.;
+                if px < half {
+                    (Eye::Left, px, py, half, h)
+                } else {
+                    (Eye::Right, px - half, py, half, h)
+                }
+            }
+            StereoLayout::TopBottom => {
+                let half = h / 2.;
+                if py < half {
+                    (Eye::Left, px, py, w, half)
+                } else {
+                    (Eye::Right, px, py - half, w, half)
+                }
+            }
+        }
+    }
+
+    /// How far an eye is to the right of the camera (negative for the left eye)
+    pub fn eye_offset(&self, eye: Eye) -> Number {
+        match eye {
+            Eye::Left => -self.ipd / 2.,
+            Eye::Right => self.ipd / 2.,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! # Module [crate::scene::validation]
+//!
+//! Structured reporting of problems that were found while building or loading a scene.
+//!
+//! Problems that can be recovered from (such as a missing texture, which can be replaced with a placeholder)
+//! shouldn't fail the whole scene load. Instead they are recorded in a [SceneValidation] as a [SceneWarning],
+//! so that they can be shown to the user afterwards.
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+/// A (non-fatal) problem with a scene
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneWarning {
+    /// An asset couldn't be loaded, and a placeholder was substituted in its place
+    MissingAsset {
+        /// The path of the asset, as it was requested
+        path: PathBuf,
+        /// Why the asset couldn't be loaded
+        reason: String,
+    },
+}
+
+impl Display for SceneWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingAsset { path, reason } => {
+                write!(f, "missing asset {path:?} (replaced with placeholder): {reason}")
+            }
+        }
+    }
+}
+
+/// Collects the [SceneWarning]s found while building or loading a scene
+#[derive(Clone, Debug, Default)]
+pub struct SceneValidation {
+    warnings: Vec<SceneWarning>,
+}
+
+impl SceneValidation {
+    pub fn new() -> Self { Self::default() }
+
+    /// Records a new warning
+    pub fn warn(&mut self, warning: SceneWarning) { self.warnings.push(warning) }
+
+    /// All the warnings recorded so far, in the order they occurred
+    pub fn warnings(&self) -> &[SceneWarning] { &self.warnings }
+
+    /// Whether the scene is free of any warnings
+    pub fn is_ok(&self) -> bool { self.warnings.is_empty() }
+}
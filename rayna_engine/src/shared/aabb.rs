@@ -8,6 +8,9 @@ use crate::core::types::{Number, Point3, Vector3};
 
 use crate::shared::interval::Interval;
 use crate::shared::ray::Ray;
+use crate::shared::ray_packet::{self, PacketMask, RayPacket};
+use std::simd::prelude::*;
+use std::simd::{LaneCount, Simd, SupportedLaneCount};
 
 /// An **Axis-Aligned Bounding Box** (AABB)
 ///
@@ -151,6 +154,27 @@ impl Aabb {
 
         return interval.range_overlaps(&tmin, &tmax);
     }
+
+    /// [Self::hit()] for a whole packet of rays at once, each with its own interval.
+    ///
+    /// Returns the mask of which rays intersect the AABB
+    pub fn hit_packet<const L: usize>(&self, packet: &RayPacket<L>, intervals: &[Interval<Number>; L]) -> PacketMask<L>
+    where
+        LaneCount<L>: SupportedLaneCount,
+    {
+        // Same as the scalar version, but for all the rays at once
+        let (mut tmin, mut tmax) = ray_packet::unpack_intervals(intervals);
+        let (min, max) = (self.min.as_array(), self.max.as_array());
+        let (pos, inv_dir) = (packet.pos().0, packet.inv_dir().0);
+        for axis in 0..3 {
+            let t1 = (Simd::splat(min[axis]) - pos[axis]) * inv_dir[axis];
+            let t2 = (Simd::splat(max[axis]) - pos[axis]) * inv_dir[axis];
+            tmin = tmin.simd_max(t1.simd_min(t2));
+            tmax = tmax.simd_min(t1.simd_max(t2));
+        }
+
+        tmin.simd_le(tmax)
+    }
 }
 
 // endregion Impl
@@ -0,0 +1,92 @@
+//! # Module [crate::shared::bvh_cost]
+//!
+//! Counting how much work it takes to trace rays through the BVHs in the scene, for the
+//! [RenderMode::BvhCost](crate::render::render_opts::RenderMode::BvhCost) render mode, and the
+//! [ray statistics](crate::render::ray_stats).
+//!
+//! Two things are counted (see [BvhCost]): each node of a BVH that a ray visits (testing its bounding box), and each
+//! primitive at the leaves (an object, mesh or instance) that has to be intersected because its bounding box was hit.
+//! This covers every kind of BVH ([BvhObject](crate::object::bvh::BvhObject),
+//! [BvhMesh](crate::mesh::advanced::bvh::BvhMesh) and [InstancedObject](crate::object::instanced::InstancedObject)),
+//! including BVHs nested inside each other.
+//!
+//! # Thread-Local State
+//! The counting has to happen deep inside the intersection code, which doesn't have access to the render options.
+//! So the counts are kept per-thread, and are only counted while [enabled()] (which is also per-thread). The
+//! [renderer](crate::render::renderer::Renderer) sets it on each of the threads in its own pool at the start of each
+//! render, so renderers with different options don't interfere with each other. It only enables it for renders that
+//! need the counts (in
+//! [RenderMode::BvhCost](crate::render::render_opts::RenderMode::BvhCost), or with
+//! [RenderOpts::bvh_stats](crate::render::render_opts::RenderOpts::bvh_stats)), since counting isn't free.
+
+use std::cell::Cell;
+use std::ops::Sub;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static COST: Cell<BvhCost> = const { Cell::new(BvhCost { nodes: 0, primitives: 0 }) };
+}
+
+/// How much work was done traversing BVHs, on the current thread. See the [module docs](self)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BvhCost {
+    /// How many BVH nodes had their bounding boxes tested
+    pub nodes: u64,
+    /// How many primitives had to be intersected, after their bounding boxes were hit
+    pub primitives: u64,
+}
+
+impl BvhCost {
+    /// The total number of tests (of nodes and primitives)
+    pub fn total(&self) -> u64 { self.nodes + self.primitives }
+}
+
+impl Sub for BvhCost {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            nodes: self.nodes - rhs.nodes,
+            primitives: self.primitives - rhs.primitives,
+        }
+    }
+}
+
+/// Returns whether the BVH costs are currently being counted on this thread
+#[inline(always)]
+pub fn enabled() -> bool { COUNTING.get() }
+
+/// Enables or disables counting the BVH costs on this thread
+pub fn set_enabled(enabled: bool) { COUNTING.set(enabled) }
+
+/// Counts a BVH node being visited
+#[inline(always)]
+pub fn visit_node() {
+    if enabled() {
+        COST.with(|cost| {
+            cost.set(BvhCost {
+                nodes: cost.get().nodes + 1,
+                ..cost.get()
+            })
+        });
+    }
+}
+
+/// Counts a primitive being intersected
+#[inline(always)]
+pub fn test_primitive() {
+    if enabled() {
+        COST.with(|cost| {
+            cost.set(BvhCost {
+                primitives: cost.get().primitives + 1,
+                ..cost.get()
+            })
+        });
+    }
+}
+
+/// Returns the costs counted on this thread so far, without resetting them
+pub fn current() -> BvhCost { COST.get() }
+
+/// Returns the costs counted on this thread so far, and resets them to zero
+pub fn take() -> BvhCost { COST.replace(BvhCost::default()) }
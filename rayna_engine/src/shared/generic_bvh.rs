@@ -62,6 +62,55 @@ impl<BNode: HasAabb> GenericBvh<BNode> {
         Self { arena, root_id }
     }
 
+    /// Iterates over all the objects in the tree, in no particular order
+    pub fn objects(&self) -> impl Iterator<Item = &BNode> {
+        self.arena.iter().filter_map(|node| match node.get() {
+            GenericBvhNode::Object(obj) => Some(obj),
+            GenericBvhNode::Nested(_) => None,
+        })
+    }
+
+    /// Iterates mutably over all the objects in the tree, in no particular order.
+    ///
+    /// The hierarchy was built from the bounds of the objects, so if they are changed, [Self::refit()] must be called
+    /// afterwards.
+    pub fn objects_mut(&mut self) -> impl Iterator<Item = &mut BNode> {
+        self.arena.iter_mut().filter_map(|node| match node.get_mut() {
+            GenericBvhNode::Object(obj) => Some(obj),
+            GenericBvhNode::Nested(_) => None,
+        })
+    }
+
+    /// Updates the bounds of all the branches in the tree, after the objects have been changed (e.g. moved by an
+    /// animation). See [Self::objects_mut()]
+    ///
+    /// This keeps the same hierarchy, so it is much faster than building a new tree, but the tree gets worse the
+    /// further the objects move from where they were when it was built. It works best for small movements (such as
+    /// between frames), with the tree being rebuilt every so often.
+    ///
+    /// # Panics
+    /// As with [Self::new()], all the objects must still be bounded
+    pub fn refit(&mut self) {
+        let Some(root) = self.root_id else {
+            return;
+        };
+
+        // Parents always come before their children, so going backwards updates the children first
+        let nodes = root.descendants(&self.arena).collect::<Vec<_>>();
+        for node in nodes.into_iter().rev() {
+            if !matches!(self.arena[node].get(), GenericBvhNode::Nested(_)) {
+                continue;
+            }
+
+            let children = node.children(&self.arena).map(|child| match self.arena[child].get() {
+                GenericBvhNode::Nested(aabb) => *aabb,
+                GenericBvhNode::Object(obj) => *obj.expect_aabb(),
+            });
+            let aabb = Aabb::encompass_iter(children);
+            *self.arena[node].get_mut() = GenericBvhNode::Nested(aabb);
+        }
+    }
+
     /// Sorts the given slice of objects along the chosen `axis`
     /// This sort is *unstable* (see [sort_unstable_by](https://doc.rust-lang.org/std/primitive.slice.html#method.sort_unstable_by))
     fn sort_along_aabb_axis(axis: SplitAxis, objects: &mut [BNode]) {
@@ -1,4 +1,4 @@
-use crate::core::types::{Number, Point2, Point3, Vector3};
+use crate::core::types::{Colour, Number, Point2, Point3, Vector3};
 use crate::material::Material;
 use derivative::Derivative;
 use std::cmp::Ordering;
@@ -40,6 +40,16 @@ pub struct Intersection {
     /// For objects with a single 'surface' (like a [sphere](crate::mesh::primitive::sphere::SphereMesh), this would be always zero.
     /// For an mesh that may have multiple faces (like a [box](`crate::mesh::primitive::axis_box::AxisBoxMesh`), this would unique per-side.
     pub side: usize,
+    /// The (interpolated) vertex colour at the intersection, if the mesh has vertex colours.
+    ///
+    /// Most meshes don't, so this will normally be [None].
+    /// See [VertexColourTexture](crate::texture::vertex_colour::VertexColourTexture)
+    pub colour: Option<Colour>,
+    /// The direction that the `u` texture coordinate increases along at the intersection (the *tangent*), if the mesh
+    /// has one. Used for [normal mapping](crate::material::normal_mapped::NormalMappedMaterial).
+    ///
+    /// This doesn't need to be normalised, or exactly perpendicular to [Self::normal].
+    pub tangent: Option<Vector3>,
 }
 
 impl Eq for Intersection {}
@@ -72,6 +82,24 @@ impl<'mat, Mat: Material + 'mat> From<(&'mat Mat, Intersection)> for FullInterse
     fn from((material, intersection): (&'mat Mat, Intersection)) -> Self { Self { intersection, material } }
 }
 
+// Allows code to work with both kinds of intersection (e.g. [CsgOperation::combine_hits()](crate::mesh::advanced::csg::CsgOperation::combine_hits))
+
+impl AsRef<Intersection> for Intersection {
+    fn as_ref(&self) -> &Intersection { self }
+}
+
+impl AsMut<Intersection> for Intersection {
+    fn as_mut(&mut self) -> &mut Intersection { self }
+}
+
+impl<'mat, Mat: Material + 'mat> AsRef<Intersection> for FullIntersection<'mat, Mat> {
+    fn as_ref(&self) -> &Intersection { &self.intersection }
+}
+
+impl<'mat, Mat: Material + 'mat> AsMut<Intersection> for FullIntersection<'mat, Mat> {
+    fn as_mut(&mut self) -> &mut Intersection { &mut self.intersection }
+}
+
 impl Intersection {
     /// Converts a partial [`Intersection`] into a [`FullIntersection<Mat>`]
     pub fn make_full<Mat: Material>(self, material: &Mat) -> FullIntersection<Mat> {
@@ -0,0 +1,91 @@
+//! # Module [crate::shared::intersect_arena]
+//!
+//! A per-thread arena of buffers for lists of intersections, as returned by
+//! [`Mesh::intersect_all()`](crate::mesh::Mesh::intersect_all).
+//!
+//! Queries for all the intersections along a ray (such as for CSG) happen for every ray that hits those meshes, and
+//! can return many intersections. Instead of allocating a new list each time, an [IntersectionList] takes a buffer
+//! from the arena of the current thread, and gives it back once it's dropped, so that after the first few rays there
+//! is no heap traffic at all. Nested queries (e.g. CSG inside CSG) each take their own buffer.
+//!
+//! # Thread-Local State
+//! The arena is kept per-thread, since the meshes don't have access to anything else that's per-thread (such as the
+//! renderer's pooled data). The [renderer](crate::render::renderer::Renderer) sets the capacity of new buffers from
+//! [`RenderOpts::intersection_capacity`](crate::render::render_opts::RenderOpts::intersection_capacity) on each of
+//! the threads in its own pool, at the start of each render.
+
+use crate::shared::intersect::Intersection;
+use std::cell::{Cell, RefCell};
+use std::ops::{Deref, DerefMut};
+
+/// The default capacity of new buffers, which is enough for most CSG meshes without having to grow
+pub const DEFAULT_CAPACITY: usize = 8;
+
+thread_local! {
+    static CAPACITY: Cell<usize> = const { Cell::new(DEFAULT_CAPACITY) };
+    static FREE_BUFFERS: RefCell<Vec<Vec<Intersection>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Returns the capacity that new buffers are created with on this thread
+pub fn capacity() -> usize { CAPACITY.get() }
+
+/// Sets the capacity that new buffers are created with on this thread.
+///
+/// If the capacity changed, the buffers already in the arena are freed, so that the new capacity takes effect.
+pub fn set_capacity(capacity: usize) {
+    if CAPACITY.replace(capacity) != capacity {
+        FREE_BUFFERS.take();
+    }
+}
+
+/// Returns how many free buffers there are in the arena of this thread
+pub fn free_buffers() -> usize { FREE_BUFFERS.with_borrow(Vec::len) }
+
+/// A list of intersections, that reuses a buffer from the [arena](self) of the current thread.
+///
+/// It can be used just like a [Vec], and the buffer is given back (cleared) when the list is dropped.
+#[derive(Debug)]
+pub struct IntersectionList {
+    hits: Vec<Intersection>,
+}
+
+impl IntersectionList {
+    /// Creates a new (empty) list, taking a buffer from the arena, or allocating one if it's empty
+    pub fn new() -> Self {
+        let hits = FREE_BUFFERS
+            .with_borrow_mut(Vec::pop)
+            .unwrap_or_else(|| Vec::with_capacity(capacity()));
+        Self { hits }
+    }
+}
+
+impl Default for IntersectionList {
+    fn default() -> Self { Self::new() }
+}
+
+impl Drop for IntersectionList {
+    fn drop(&mut self) {
+        let mut hits = std::mem::take(&mut self.hits);
+        hits.clear();
+        // Might be dropped while the thread is exiting, in which case the buffer is freed normally
+        let _ = FREE_BUFFERS.try_with(|free| free.borrow_mut().push(hits));
+    }
+}
+
+impl Deref for IntersectionList {
+    type Target = Vec<Intersection>;
+
+    fn deref(&self) -> &Self::Target { &self.hits }
+}
+
+impl DerefMut for IntersectionList {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.hits }
+}
+
+impl FromIterator<Intersection> for IntersectionList {
+    fn from_iter<T: IntoIterator<Item = Intersection>>(iter: T) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
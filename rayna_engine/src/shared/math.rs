@@ -24,4 +24,21 @@ pub fn refract(vec: Vector3, n: Vector3, ir_ratio: Number) -> Vector3 {
     return r_out_perp + r_out_parallel;
 }
 
+/// Solves the 3x3 linear system `Mx = b`, where `M` has the given columns, using Cramer's rule.
+///
+/// Returns [None] if the matrix is (nearly) singular
+pub fn solve_3x3([c0, c1, c2]: [Vector3; 3], b: Vector3) -> Option<Vector3> {
+    let det = Vector3::dot(c0, Vector3::cross(c1, c2));
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    Some(
+        Vector3::new(
+            Vector3::dot(b, Vector3::cross(c1, c2)),
+            Vector3::dot(c0, Vector3::cross(b, c2)),
+            Vector3::dot(c0, Vector3::cross(c1, b)),
+        ) / det,
+    )
+}
+
 // endregion Vector Math
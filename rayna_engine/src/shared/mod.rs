@@ -1,12 +1,17 @@
 use std::fmt::Debug;
 
 pub mod aabb;
+pub mod bvh_cost;
 pub mod generic_bvh;
 pub mod intersect;
+pub mod intersect_arena;
 pub mod interval;
 pub mod math;
+pub mod noise;
 pub mod ray;
+pub mod ray_packet;
 pub mod rng;
+pub mod robust;
 pub mod simd_math;
 pub mod validate;
 
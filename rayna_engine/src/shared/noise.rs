@@ -0,0 +1,289 @@
+//! # Module [crate::shared::noise]
+//!
+//! Noise functions that aren't in the `noise` crate. They implement [NoiseFn], so they can be used anywhere that the
+//! noise crate's functions can, such as in the [noise textures](crate::texture::noise), and combined with the noise
+//! crate's modifiers.
+
+use crate::core::types::Number;
+use crate::shared::rng;
+use ::noise::NoiseFn;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use strum_macros::{EnumIter, IntoStaticStr};
+
+// region Worley
+
+/// Worley (cellular, or Voronoi) noise, from the distances to a random point in each cell of a grid.
+///
+/// The distances are in units of the cells, and are mapped so that `0` is `-1.0` and a distance of one cell is `1.0`
+/// (like the other noise functions, which are roughly `-1.0..=1.0`). It can be used in up to 3 dimensions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorleyNoise {
+    pub seed: u32,
+    /// How many cells there are per unit
+    pub frequency: Number,
+    pub mode: WorleyMode,
+    pub metric: DistanceMetric,
+}
+
+/// Which of the distances [WorleyNoise] outputs
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum WorleyMode {
+    /// The distance to the nearest point (`F1`), which looks like cells or scales
+    #[default]
+    F1,
+    /// The distance to the second nearest point (`F2`)
+    F2,
+    /// The difference between the second nearest and nearest points (`F2 - F1`), which is zero along the edges
+    /// between the cells, like cracks or cracked mud
+    F2MinusF1,
+}
+
+/// How the distances to the points of [WorleyNoise] are measured
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum DistanceMetric {
+    /// The straight line distance, giving round cells
+    #[default]
+    Euclidean,
+    /// The distance along each axis, added up, giving diamond-shaped cells
+    Manhattan,
+    /// The furthest distance along any of the axes, giving square cells
+    Chebyshev,
+}
+
+impl DistanceMetric {
+    /// The distance between two points
+    pub fn distance<const D: usize>(self, a: [Number; D], b: [Number; D]) -> Number {
+        let deltas = a.into_iter().zip(b).map(|(a, b)| (a - b).abs());
+        match self {
+            Self::Euclidean => deltas.map(|d| d * d).sum::<Number>().sqrt(),
+            Self::Manhattan => deltas.sum(),
+            Self::Chebyshev => deltas.fold(0., Number::max),
+        }
+    }
+}
+
+impl WorleyNoise {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            frequency: 1.,
+            mode: WorleyMode::F1,
+            metric: DistanceMetric::Euclidean,
+        }
+    }
+
+    /// The random point inside of a cell of the grid
+    fn cell_point<const D: usize>(&self, cell: [i64; D]) -> [Number; D] {
+        let hash = rng::hash_seed(std::iter::once(self.seed as u64).chain(cell.map(|c| c as u64)));
+        // 21 bits for each axis
+        std::array::from_fn(|i| cell[i] as Number + ((hash >> (21 * i)) & 0x1F_FFFF) as Number / (1 << 21) as Number)
+    }
+}
+
+impl<const D: usize> NoiseFn<Number, D> for WorleyNoise {
+    fn get(&self, point: [Number; D]) -> Number {
+        let point = point.map(|p| p * self.frequency);
+        let base = point.map(|p| p.floor() as i64);
+        // The nearest points can be up to two cells away
+        let radius = 2;
+        let width: usize = (2 * radius) + 1;
+
+        let (mut f1, mut f2) = (Number::INFINITY, Number::INFINITY);
+        for index in 0..width.pow(D as u32) {
+            let mut rest = index;
+            let cell = std::array::from_fn(|i| {
+                let offset = (rest % width) as i64 - radius as i64;
+                rest /= width;
+                base[i] + offset
+            });
+            let dist = self.metric.distance(point, self.cell_point(cell));
+            if dist < f1 {
+                (f1, f2) = (dist, f1);
+            } else if dist < f2 {
+                f2 = dist;
+            }
+        }
+
+        let dist = match self.mode {
+            WorleyMode::F1 => f1,
+            WorleyMode::F2 => f2,
+            WorleyMode::F2MinusF1 => f2 - f1,
+        };
+        (dist * 2.) - 1.
+    }
+}
+
+// endregion Worley
+
+// region Blue Noise
+
+/// A tileable square of blue noise, made with the void-and-cluster algorithm.
+///
+/// Each pixel has a different value, spread evenly over `0.0..1.0`, and pixels with similar values are spread out
+/// as far as possible from each other. Thresholding it at any level gives an even scattering of dots with no clumps
+/// or gaps, which makes it good for dithering, and for offsetting the samples of neighbouring pixels (see
+/// [SamplerKind::BlueNoise](crate::shared::rng::SamplerKind::BlueNoise)) so their errors look like fine grain instead of
+/// blotches.
+///
+/// As a [NoiseFn], the points are in pixels of the square (which repeats), and the values are mapped to `-1.0..1.0`
+#[derive(Clone, Debug)]
+pub struct BlueNoise {
+    size: usize,
+    values: Arc<[Number]>,
+}
+
+impl BlueNoise {
+    /// The size of the [shared](Self::shared) blue noise
+    pub const DEFAULT_SIZE: usize = 64;
+    /// The standard deviation of the gaussian that the "energy" of the pixels is spread out by, in pixels
+    const SIGMA: Number = 1.5;
+    /// The fraction of the pixels in the initial pattern
+    const INITIAL_FRACTION: Number = 0.1;
+
+    /// Generates a new `size * size` square of blue noise. This takes `O(size^4)` time, so should be done once and
+    /// reused (see [Self::shared()])
+    ///
+    /// # Panics
+    /// Panics if the size is zero
+    pub fn new(size: usize, seed: u64) -> Self {
+        /*
+        CREDITS:
+
+        Title: "The void-and-cluster method for dither array generation"
+        Author: Robert Ulichney
+        URL: <https://doi.org/10.1117/12.152707>
+        */
+        assert!(size > 0, "blue noise must have at least one pixel");
+        let count = size * size;
+        let mut energy = Energy::new(size);
+
+        // Start with some random pixels, and spread them out by moving the most clustered one into the biggest gap
+        // until that doesn't move it anywhere
+        let initial = ((count as Number * Self::INITIAL_FRACTION) as usize).max(1);
+        let mut pattern = vec![false; count];
+        for i in 0_u64.. {
+            if pattern.iter().filter(|&&p| p).count() == initial {
+                break;
+            }
+            let index = (rng::hash_seed([seed, i]) % count as u64) as usize;
+            if !pattern[index] {
+                pattern[index] = true;
+                energy.update(index, 1.);
+            }
+        }
+        for _ in 0..count {
+            let cluster = energy.tightest_cluster(&pattern);
+            pattern[cluster] = false;
+            energy.update(cluster, -1.);
+            let void = energy.largest_void(&pattern);
+            pattern[void] = true;
+            energy.update(void, 1.);
+            if void == cluster {
+                break;
+            }
+        }
+
+        // The pixels of the initial pattern are ranked by removing them from the most clustered, and the rest by
+        // filling in the biggest gaps
+        let mut ranks = vec![0; count];
+        let (mut removing, mut removing_energy) = (pattern.clone(), energy.clone());
+        for rank in (0..initial).rev() {
+            let cluster = removing_energy.tightest_cluster(&removing);
+            removing[cluster] = false;
+            removing_energy.update(cluster, -1.);
+            ranks[cluster] = rank;
+        }
+        for rank in initial..count {
+            let void = energy.largest_void(&pattern);
+            pattern[void] = true;
+            energy.update(void, 1.);
+            ranks[void] = rank;
+        }
+
+        Self {
+            size,
+            values: ranks
+                .into_iter()
+                .map(|rank| (rank as Number + 0.5) / count as Number)
+                .collect(),
+        }
+    }
+
+    /// Blue noise of the [default size](Self::DEFAULT_SIZE), which is only generated the first time it is used (which
+    /// is normally when the first scene is [prepared](crate::scene::Scene::prepare), instead of in the middle of a
+    /// frame)
+    pub fn shared() -> &'static Self {
+        static SHARED: OnceLock<BlueNoise> = OnceLock::new();
+        SHARED.get_or_init(|| Self::new(Self::DEFAULT_SIZE, 0))
+    }
+
+    /// The width and height of the square
+    pub fn size(&self) -> usize { self.size }
+
+    /// The value of a pixel, in the range `0.0..1.0`. The square repeats, so any pixel can be used
+    pub fn get_pixel(&self, x: usize, y: usize) -> Number {
+        self.values[(x % self.size) + ((y % self.size) * self.size)]
+    }
+}
+
+impl NoiseFn<Number, 2> for BlueNoise {
+    fn get(&self, [x, y]: [Number; 2]) -> Number {
+        let wrap = |p: Number| p.floor().rem_euclid(self.size as Number) as usize;
+        (self.get_pixel(wrap(x), wrap(y)) * 2.) - 1.
+    }
+}
+
+/// How clustered each pixel of a pattern is (the sum of a gaussian around each of the set pixels), for [BlueNoise]
+#[derive(Clone, Debug)]
+struct Energy {
+    size: usize,
+    /// The gaussian, by the offset between the pixels (wrapping around)
+    kernel: Vec<Number>,
+    energy: Vec<Number>,
+}
+
+impl Energy {
+    fn new(size: usize) -> Self {
+        let offset = |d: usize| d.min(size - d) as Number;
+        let kernel = (0..size * size)
+            .map(|i| {
+                let (dx, dy) = (offset(i % size), offset(i / size));
+                (-((dx * dx) + (dy * dy)) / (2. * BlueNoise::SIGMA * BlueNoise::SIGMA)).exp()
+            })
+            .collect();
+        Self {
+            size,
+            kernel,
+            energy: vec![0.; size * size],
+        }
+    }
+
+    /// Adds the gaussian around a pixel that was set (or removes it, with a `sign` of `-1`)
+    fn update(&mut self, index: usize, sign: Number) {
+        let size = self.size;
+        let (x, y) = (index % size, index / size);
+        for (i, energy) in self.energy.iter_mut().enumerate() {
+            let (dx, dy) = (((i % size) + size - x) % size, ((i / size) + size - y) % size);
+            *energy += sign * self.kernel[dx + (dy * size)];
+        }
+    }
+
+    /// The set pixel with the most energy
+    fn tightest_cluster(&self, pattern: &[bool]) -> usize { self.extreme(pattern, true, Number::gt) }
+
+    /// The unset pixel with the least energy
+    fn largest_void(&self, pattern: &[bool]) -> usize { self.extreme(pattern, false, Number::lt) }
+
+    fn extreme(&self, pattern: &[bool], set: bool, better: impl Fn(&Number, &Number) -> bool) -> usize {
+        let mut best: Option<usize> = None;
+        for (i, energy) in self.energy.iter().enumerate() {
+            if pattern[i] == set && best.map_or(true, |b| better(energy, &self.energy[b])) {
+                best = Some(i);
+            }
+        }
+        best.expect("pattern shouldn't be all set or unset")
+    }
+}
+
+// endregion Blue Noise
@@ -8,6 +8,11 @@ pub struct Ray {
     pos: Point3,
     dir: Vector3,
     inv_dir: Vector3,
+    /// When the ray was fired, as a fraction of the way through the shutter interval (`0.0..=1.0`).
+    ///
+    /// Used for motion blur, so that moving objects can be intersected where they were at that time. Rays start at
+    /// time `0.0`, use [Self::with_time()] to change it
+    time: Number,
 }
 
 impl Ray {
@@ -19,6 +24,7 @@ impl Ray {
             pos,
             dir,
             inv_dir: dir.recip(),
+            time: 0.,
         }
     }
 
@@ -35,9 +41,13 @@ impl Ray {
             pos,
             dir,
             inv_dir: dir.recip(),
+            time: 0.,
         }
     }
 
+    /// Returns the same ray, fired at a different time. See [Self::time]
+    pub fn with_time(self, time: Number) -> Self { Self { time, ..self } }
+
     /// Gets the position at a given distance along the ray
     ///
     /// `pos + (t * dir)`
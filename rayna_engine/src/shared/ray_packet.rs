@@ -0,0 +1,84 @@
+//! # Module [crate::shared::ray_packet]
+//!
+//! **Ray packets**: several rays that are intersected together, using SIMD to test all of them against the same
+//! box or mesh at once.
+//!
+//! This only pays off when the rays are *coherent* (they start close together and point in similar directions), since
+//! then they mostly visit the same nodes of a BVH and hit the same objects. The camera rays for the samples of a pixel
+//! are like this, so the renderer traces those in packets (see [Object::full_intersect_packet()]), and everything
+//! after the first bounce is traced one ray at a time.
+//!
+//! [Object::full_intersect_packet()]: crate::object::Object::full_intersect_packet
+
+use crate::core::types::Number;
+use crate::shared::interval::Interval;
+use crate::shared::ray::Ray;
+use crate::shared::simd_math::{SimdConstants, SimdVector};
+use std::simd::prelude::*;
+use std::simd::{LaneCount, Mask, Simd, SimdElement, SupportedLaneCount};
+
+/// How many rays the renderer puts in each packet
+pub const PACKET_SIZE: usize = 4;
+
+/// How many rays the renderer puts in each *wide* packet. When there are enough samples in a pixel, these are used
+/// first, so that CPUs with wider SIMD registers (e.g. AVX-512) can test more rays at once
+pub const WIDE_PACKET_SIZE: usize = 8;
+
+/// The mask type for a packet, which has one lane per ray
+pub type PacketMask<const L: usize> = Mask<<Number as SimdElement>::Mask, L>;
+
+/// A packet of `L` rays. See the [module docs](self)
+///
+/// The rays are kept as they are, as well as unpacked into an SoA (**Struct of Arrays**), with all the `X`
+/// components, then the `Y` components, etc.
+#[derive(Copy, Clone, Debug)]
+pub struct RayPacket<const L: usize>
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    rays: [Ray; L],
+    pos: SimdVector<L, 3>,
+    dir: SimdVector<L, 3>,
+    inv_dir: SimdVector<L, 3>,
+}
+
+impl<const L: usize> RayPacket<L>
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    pub fn new(rays: [Ray; L]) -> Self {
+        Self {
+            rays,
+            pos: SimdVector([0, 1, 2].map(|i| Simd::from_array(rays.map(|r| r.pos().as_array()[i])))),
+            dir: SimdVector([0, 1, 2].map(|i| Simd::from_array(rays.map(|r| r.dir().as_array()[i])))),
+            inv_dir: SimdVector([0, 1, 2].map(|i| Simd::from_array(rays.map(|r| r.inv_dir().as_array()[i])))),
+        }
+    }
+
+    /// Creates a new packet, by changing each of the rays in this one (e.g. to move them into an object's local space)
+    pub fn map(&self, f: impl FnMut(&Ray) -> Ray) -> Self { Self::new(self.rays.each_ref().map(f)) }
+
+    pub fn rays(&self) -> &[Ray; L] { &self.rays }
+    pub fn pos(&self) -> SimdVector<L, 3> { self.pos }
+    pub fn dir(&self) -> SimdVector<L, 3> { self.dir }
+    pub fn inv_dir(&self) -> SimdVector<L, 3> { self.inv_dir }
+
+    /// Whether the rays are all heading into the same octant (the signs of their directions match on every axis), so
+    /// that they should visit the nodes of a BVH in a similar order
+    pub fn is_coherent(&self) -> bool {
+        self.dir.0.iter().all(|d| {
+            let negative = d.simd_lt(SimdConstants::<L>::ZERO);
+            negative.all() || !negative.any()
+        })
+    }
+}
+
+/// Unpacks the start and end of each ray's interval into SIMD vectors, with missing bounds being infinite
+pub fn unpack_intervals<const L: usize>(intervals: &[Interval<Number>; L]) -> (Simd<Number, L>, Simd<Number, L>)
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    let start = Simd::from_array(intervals.map(|i| i.start.unwrap_or(Number::NEG_INFINITY)));
+    let end = Simd::from_array(intervals.map(|i| i.end.unwrap_or(Number::INFINITY)));
+    (start, end)
+}
@@ -3,10 +3,14 @@
 use crate::core::types::{Channel, Colour, Number, Vector2, Vector3};
 use glamour::AngleConsts;
 
+use crate::shared::noise::BlueNoise;
 use crate::shared::validate;
 use rand::distributions::uniform::SampleRange;
 use rand::Rng;
-use rand_core::SeedableRng;
+use rand_core::{RngCore, SeedableRng};
+use serde::Serialize;
+use strum_macros::{Display, EnumIter, IntoStaticStr};
+use valuable::Valuable;
 
 const PI: Number = <Number as AngleConsts>::PI;
 
@@ -22,6 +26,19 @@ impl<R: SeedableRng> opool::PoolAllocator<R> for RngPoolAllocator {
     fn allocate(&self) -> R { R::from_entropy() }
 }
 
+/// Creates a seed from the given values, such that any change to any of the values gives an unrelated seed.
+///
+/// Used to create independent (but reproducible) RNG streams, e.g. one for each pixel
+pub fn hash_seed(values: impl IntoIterator<Item = u64>) -> u64 {
+    /* CREDITS: SplitMix64/Sebastiano Vigna/https://prng.di.unimi.it/splitmix64.c */
+    values.into_iter().fold(0x9E37_79B9_7F4A_7C15, |state, value| {
+        let mut z = (state ^ value).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    })
+}
+
 // region 1D
 
 /// Returns a number in the range `-1.0..1.0`
@@ -212,3 +229,214 @@ pub fn colour_bw_range<R: Rng + ?Sized, Ra: SampleRange<Channel>>(rng: &mut R, r
 }
 
 // endregion
+
+// region Samplers
+
+/// The sequences that a [Sampler] can draw its numbers from
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Valuable, Serialize, EnumIter, IntoStaticStr, Display)]
+pub enum SamplerKind {
+    /// Independent (pseudo-)random numbers, straight from the RNG. This converges the slowest, but has no structure
+    /// that could show up as artefacts
+    #[default]
+    Random,
+    /// The Halton sequence, with Owen scrambling. Only the first [HALTON_DIMENSIONS] dimensions are low-discrepancy,
+    /// any past that are random
+    Halton,
+    /// The Sobol sequence, with Owen scrambling. Each pair of dimensions is an independently shuffled 2D Sobol
+    /// sequence, so there is no limit on the number of dimensions
+    Sobol,
+    /// The Sobol sequence, scrambled the same way for every pixel, and then shifted in each pixel by a tileable
+    /// [BlueNoise] mask (a different part of it for each dimension). The errors of neighbouring pixels are different in
+    /// a way that looks like fine, even grain instead of blotches, which is less noticeable at low sample counts (and
+    /// easier to denoise). Needs the [pixel](Sampler::with_pixel) that the samples are for
+    BlueNoise,
+}
+
+/// How many dimensions of the Halton sequence are low-discrepancy (one for each prime base)
+pub const HALTON_DIMENSIONS: usize = HALTON_PRIMES.len();
+const HALTON_PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109,
+    113, 127, 131,
+];
+
+/// The largest [Number] that is less than `1.0`
+const ONE_MINUS_EPSILON: Number = 1. - (Number::EPSILON / 2.);
+
+/// Generates the numbers for the samples of a pixel.
+///
+/// With the low-discrepancy [kinds](SamplerKind), each sample is a point in a many-dimensional sequence, and each
+/// number that is drawn comes from the next dimension of that point. The points are spread out much more evenly
+/// than random ones, so the first few dimensions (the pixel jitter, lens and the first bounce) converge much faster
+/// at low sample counts.
+///
+/// The sampler implements [RngCore], so it can be passed to anything that takes an RNG (like the camera and the
+/// materials), which then automatically draw from successive dimensions. Each pixel should use a different `seed`,
+/// so that the scrambling hides the structure of the sequence.
+#[derive(Clone, Debug)]
+pub struct Sampler<R> {
+    kind: SamplerKind,
+    seed: u64,
+    pixel: [usize; 2],
+    rng: R,
+    index: u64,
+    dimension: usize,
+}
+
+impl<R: RngCore> Sampler<R> {
+    /// Creates a new sampler, at the first dimension of the first sample.
+    ///
+    /// The `rng` is used for the [SamplerKind::Random] kind, and for anything that can't be low-discrepancy
+    pub fn new(kind: SamplerKind, seed: u64, rng: R) -> Self {
+        Self {
+            kind,
+            seed,
+            pixel: [0, 0],
+            rng,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Sets the pixel that the samples are for, which picks the shift for [SamplerKind::BlueNoise] (the `seed` isn't
+    /// used for it, since the pixels have to share the same points)
+    pub fn with_pixel(self, x: usize, y: usize) -> Self { Self { pixel: [x, y], ..self } }
+
+    pub fn kind(&self) -> SamplerKind { self.kind }
+
+    /// The index of the current sample
+    pub fn index(&self) -> u64 { self.index }
+
+    /// Moves to the sample with the given index, so that the next number is from its first dimension.
+    ///
+    /// The indices should carry on across frames (e.g. `frame * samples + sample`), so that accumulating frames keeps
+    /// filling in the gaps between the earlier samples
+    pub fn start_sample(&mut self, index: u64) {
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    /// Returns the next dimension of the current sample, in the range `0.0..1.0`
+    pub fn next_1d(&mut self) -> Number {
+        let dimension = self.dimension;
+        self.dimension += 1;
+
+        let n = match self.kind {
+            SamplerKind::Random => self.rng.gen(),
+            SamplerKind::Halton => match HALTON_PRIMES.get(dimension) {
+                Some(&base) => scrambled_radical_inverse(base, self.index, hash_seed([self.seed, dimension as u64])),
+                None => self.rng.gen(),
+            },
+            SamplerKind::Sobol => scrambled_sobol(self.index, dimension, self.seed),
+            SamplerKind::BlueNoise => {
+                // Each dimension is shifted by a different (unrelated) part of the mask, so that they aren't correlated
+                let offset = hash_seed([dimension as u64]);
+                let [x, y] = self.pixel;
+                let [dx, dy] = [offset as u32 as usize, (offset >> 32) as usize];
+                let shift = BlueNoise::shared().get_pixel(x.wrapping_add(dx), y.wrapping_add(dy));
+                (scrambled_sobol(self.index, dimension, 0) + shift)
+                    .fract()
+                    .min(ONE_MINUS_EPSILON)
+            }
+        };
+        validate::number(&n);
+        n
+    }
+
+    /// Returns the next two dimensions of the current sample, in the range `0.0..1.0`
+    pub fn next_2d(&mut self) -> Vector2 {
+        let v = Vector2::new(self.next_1d(), self.next_1d());
+        validate::vector2(&v);
+        v
+    }
+}
+
+impl<R: RngCore> RngCore for Sampler<R> {
+    // The samples are in the most significant bits, since that is where `rand` takes its floats from
+
+    fn next_u32(&mut self) -> u32 {
+        match self.kind {
+            SamplerKind::Random => self.rng.next_u32(),
+            _ => (self.next_1d() * (1_u64 << 32) as Number) as u32,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self.kind {
+            SamplerKind::Random => self.rng.next_u64(),
+            _ => (self.next_1d() * Number::powi(2., 64)) as u64,
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) { self.rng.fill_bytes(dest) }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> { self.rng.try_fill_bytes(dest) }
+}
+
+/// The radical inverse of the index in the given base, where each digit is randomly shifted depending on the digits
+/// before it (a cheap form of Owen scrambling)
+fn scrambled_radical_inverse(base: u64, mut index: u64, seed: u64) -> Number {
+    /* CREDITS: Matt Pharr, Wenzel Jakob, Greg Humphreys/"Physically Based Rendering: From Theory to Implementation" (4th ed.), section 8.6.2/https://pbr-book.org/4ed/Sampling_and_Reconstruction/Halton_Sampler */
+    let inv_base = 1. / base as Number;
+    let mut scale = inv_base;
+    let mut prefix = seed;
+    let mut n = 0.;
+    // Keep going past the last digit of the index, so that the trailing zeroes are scrambled as well
+    while scale > Number::EPSILON {
+        let digit = index % base;
+        index /= base;
+        n += ((digit + (prefix % base)) % base) as Number * scale;
+        prefix = hash_seed([prefix, digit]);
+        scale *= inv_base;
+    }
+    n.min(ONE_MINUS_EPSILON)
+}
+
+/// A dimension of the Sobol sequence, with Owen scrambling
+fn scrambled_sobol(index: u64, dimension: usize, seed: u64) -> Number {
+    // The pairs are shuffled separately, so that the dimensions of different pairs aren't correlated
+    let pair_seed = hash_seed([seed, (dimension / 2) as u64]);
+    let index = nested_uniform_scramble(index as u32, pair_seed as u32);
+    let component = dimension % 2;
+    let scramble = hash_seed([pair_seed, component as u64]) as u32;
+    nested_uniform_scramble(sobol(index, component), scramble) as Number / (1_u64 << 32) as Number
+}
+
+/// The first two dimensions of the Sobol sequence, as 32-bit fixed-point numbers
+fn sobol(index: u32, dimension: usize) -> u32 {
+    match dimension {
+        // The van der Corput sequence
+        0 => index.reverse_bits(),
+        // The direction numbers for the primitive polynomial `x + 1`
+        _ => {
+            let (mut n, mut direction) = (0, 1 << 31);
+            for bit in 0..u32::BITS {
+                if index & (1 << bit) != 0 {
+                    n ^= direction;
+                }
+                direction ^= direction >> 1;
+            }
+            n
+        }
+    }
+}
+
+/// Owen-scrambles a 32-bit fixed-point number, by flipping each bit depending on the bits above it
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    /*
+    CREDITS:
+
+    Title: "Practical Hash-based Owen Scrambling"
+    Author: Brent Burley
+    URL: <https://jcgt.org/published/0009/04/01/>
+    */
+    let mut x = x.reverse_bits();
+    // Laine-Karras permutation
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x.reverse_bits()
+}
+
+// endregion Samplers
@@ -0,0 +1,109 @@
+//! # Module [crate::shared::robust]
+//!
+//! Error-compensated arithmetic, used by the intersection routines when *robust mode* is enabled.
+//!
+//! Plain floating-point arithmetic can suffer from catastrophic cancellation in the intersection code,
+//! most noticeably with huge spheres, grazing rays, or small triangles that are far away from the origin.
+//! This shows up as speckles, holes, or "acne" on surfaces. Switching the whole engine to a wider float type
+//! would require a rebuild, so instead the meshes that support it check [enabled()] at runtime, and
+//! swap to the compensated versions of their maths.
+//!
+//! The compensated operations are built on top of fused multiply-add ([Number::mul_add]), and are roughly
+//! twice as precise as the naive versions, at the cost of being slower.
+//!
+//! # Thread-Local State
+//! The switch has to be visible from deep inside [`Mesh::intersect()`](crate::mesh::Mesh::intersect), which doesn't
+//! have access to the render options, so it is kept per-thread. The [renderer](crate::render::renderer::Renderer) sets
+//! it from [`RenderOpts::robust_intersections`](crate::render::render_opts::RenderOpts::robust_intersections) on each
+//! of the threads in its own pool, at the start of each render, so renderers with different options don't interfere
+//! with each other.
+
+use crate::core::types::{Number, Vector3};
+use std::cell::Cell;
+
+thread_local! {
+    static ROBUST_INTERSECTIONS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Returns whether robust (error-compensated) intersections are currently enabled on this thread
+#[inline(always)]
+pub fn enabled() -> bool { ROBUST_INTERSECTIONS.get() }
+
+/// Enables or disables robust (error-compensated) intersections on this thread
+pub fn set_enabled(enabled: bool) { ROBUST_INTERSECTIONS.set(enabled) }
+
+// region Error-free transformations
+
+/// Calculates `a * b`, returning the rounded product and the rounding error,
+/// such that `product + error == a * b` exactly
+#[inline(always)]
+pub fn two_product(a: Number, b: Number) -> (Number, Number) {
+    let product = a * b;
+    (product, Number::mul_add(a, b, -product))
+}
+
+/// Calculates `a + b`, returning the rounded sum and the rounding error,
+/// such that `sum + error == a + b` exactly
+#[inline(always)]
+pub fn two_sum(a: Number, b: Number) -> (Number, Number) {
+    let sum = a + b;
+    let b_virt = sum - a;
+    let a_virt = sum - b_virt;
+    (sum, (a - a_virt) + (b - b_virt))
+}
+
+// endregion Error-free transformations
+
+// region Compensated operations
+
+/// Calculates `(a * b) - (c * d)` without catastrophic cancellation
+#[inline(always)]
+pub fn difference_of_products(a: Number, b: Number, c: Number, d: Number) -> Number {
+    /*
+    CREDITS:
+
+    Title: "Further analysis of Kahan's algorithm for the accurate computation of 2x2 determinants"
+    Author: Claude-Pierre Jeannerod, Nicolas Louvet, Jean-Michel Muller
+    URL: <https://doi.org/10.1090/S0025-5718-2013-02679-8>
+    */
+    let cd = c * d;
+    let err = Number::mul_add(-c, d, cd);
+    let dop = Number::mul_add(a, b, -cd);
+    dop + err
+}
+
+/// Compensated dot product of two vectors
+#[inline(always)]
+pub fn dot(a: Vector3, b: Vector3) -> Number {
+    /*
+    CREDITS:
+
+    Title: "Accurate Sum and Dot Product" (Algorithm `Dot2`)
+    Author: Takeshi Ogita, Siegfried M. Rump, Shin'ichi Oishi
+    URL: <https://doi.org/10.1137/030601818>
+    */
+    let (mut sum, mut comp) = two_product(a.x, b.x);
+    for (x, y) in [(a.y, b.y), (a.z, b.z)] {
+        let (prod, prod_err) = two_product(x, y);
+        let (new_sum, sum_err) = two_sum(sum, prod);
+        sum = new_sum;
+        comp += prod_err + sum_err;
+    }
+    sum + comp
+}
+
+/// Compensated cross product of two vectors
+#[inline(always)]
+pub fn cross(a: Vector3, b: Vector3) -> Vector3 {
+    Vector3::new(
+        difference_of_products(a.y, b.z, a.z, b.y),
+        difference_of_products(a.z, b.x, a.x, b.z),
+        difference_of_products(a.x, b.y, a.y, b.x),
+    )
+}
+
+/// Compensated squared length of a vector
+#[inline(always)]
+pub fn length_squared(v: Vector3) -> Number { dot(v, v) }
+
+// endregion Compensated operations
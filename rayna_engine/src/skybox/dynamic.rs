@@ -1,13 +1,43 @@
-use crate::core::types::Colour;
+use crate::core::types::{Colour, Number, Vector3};
 use crate::shared::ray::Ray;
 use crate::skybox::Skybox;
+use rand_core::RngCore;
 use std::sync::Arc;
 
+/// Object wrapper around a `dyn` [Skybox]; Delegates everything to the inner skybox.
+///
+/// If possible use the enum variants on [`crate::skybox::SkyboxInstance`], so that static-dispatch is used instead of dynamic dispatch
 #[derive(Clone, Debug)]
 pub struct DynamicSkybox {
     pub inner: Arc<dyn Skybox>,
 }
 
+impl DynamicSkybox {
+    pub fn new(inner: impl Skybox + 'static) -> Self { Self { inner: Arc::new(inner) } }
+}
+
+impl super::SkyboxInstance {
+    pub fn from_dyn(value: impl Skybox + 'static) -> Self { Self::from(DynamicSkybox::new(value)) }
+}
+
 impl Skybox for DynamicSkybox {
     fn sky_colour(&self, ray: &Ray) -> Colour { self.inner.sky_colour(ray) }
+
+    fn sample_direction(&self, rng: &mut dyn RngCore) -> Option<Vector3> { self.inner.sample_direction(rng) }
+
+    fn direction_probability(&self, dir: Vector3) -> Number { self.inner.direction_probability(dir) }
+
+    fn set_time(&mut self, time: Number) {
+        // Can only change the time if we aren't sharing the skybox
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.set_time(time)
+        }
+    }
+
+    fn prepare(&mut self) {
+        // Can only prepare if we aren't sharing the skybox
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.prepare()
+        }
+    }
 }
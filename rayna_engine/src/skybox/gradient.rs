@@ -0,0 +1,147 @@
+use crate::core::types::{Angle, Channel, Colour, Number, Vector3};
+use crate::shared::math::Lerp;
+use crate::shared::ray::Ray;
+use crate::shared::rng;
+use crate::skybox::Skybox;
+use rand_core::RngCore;
+use std::f64::consts::TAU;
+
+/// A procedural skybox with a three-colour gradient (sky, horizon and ground), and a sun.
+///
+/// This gives pleasant studio-like lighting without needing any HDRI files, which is useful for look-dev.
+///
+/// # Sun
+/// The sun is a disk of uniform brightness, with a slightly softened edge. It is sampled directly (see
+/// [Skybox::sample_direction()]), so the diffuse surfaces it lights aren't noisy, even when it's small. The shiny
+/// surfaces that reflect it still only find it by chance, so a tiny sun gives noisy highlights.
+#[derive(Copy, Clone, Debug)]
+pub struct GradientSkybox {
+    /// The colour directly above
+    pub sky: Colour,
+    /// The colour at the horizon
+    pub horizon: Colour,
+    /// The colour directly below
+    pub ground: Colour,
+    /// The direction *towards* the sun. Must be normalised
+    pub sun_dir: Vector3,
+    /// How bright the sun is, added on top of the gradient
+    pub sun_intensity: Channel,
+    /// The angular radius of the sun
+    pub sun_size: Angle,
+}
+
+impl GradientSkybox {
+    /// The default angular radius of the sun
+    pub const DEFAULT_SUN_SIZE: Angle = Angle { radians: 0.1 };
+
+    pub fn new(
+        sky: impl Into<Colour>,
+        horizon: impl Into<Colour>,
+        ground: impl Into<Colour>,
+        sun_dir: impl Into<Vector3>,
+        sun_intensity: Channel,
+    ) -> Self {
+        Self {
+            sky: sky.into(),
+            horizon: horizon.into(),
+            ground: ground.into(),
+            sun_dir: sun_dir.into().normalize(),
+            sun_intensity,
+            sun_size: Self::DEFAULT_SUN_SIZE,
+        }
+    }
+}
+
+// region Sun Position
+
+impl GradientSkybox {
+    /// Moves the sun to the given angles.
+    ///
+    /// # Arguments
+    ///
+    /// * `elevation`: The angle above the horizon, where negative angles are below the horizon
+    /// * `azimuth`: The angle around the `Y` axis, measured from `+X` towards `+Z`
+    pub fn with_sun_angles(self, elevation: Angle, azimuth: Angle) -> Self {
+        let (sin_el, cos_el) = elevation.radians.sin_cos();
+        let (sin_az, cos_az) = azimuth.radians.sin_cos();
+        Self {
+            sun_dir: Vector3::new(cos_el * cos_az, sin_el, cos_el * sin_az),
+            ..self
+        }
+    }
+
+    /// Moves the sun along a simple daily arc, so that the time can be animated (see
+    /// [TimeOfDaySkybox](crate::skybox::time_of_day::TimeOfDaySkybox)).
+    ///
+    /// The sun rises at `+X` at 06:00, is at its highest (`max_elevation`) at 12:00 towards `+Z`, sets at `-X`
+    /// at 18:00, and is below the horizon overnight.
+    ///
+    /// # Arguments
+    ///
+    /// * `hours`: The time of day, in hours since midnight. Wraps around every 24 hours
+    /// * `max_elevation`: How high the sun gets at noon
+    pub fn with_time_of_day(self, hours: Number, max_elevation: Angle) -> Self {
+        // Fraction of a full circle, with sunrise at zero
+        let theta = ((hours - 6.) / 24.).rem_euclid(1.) * TAU;
+        let elevation = Angle {
+            radians: theta.sin() * max_elevation.radians,
+        };
+        self.with_sun_angles(elevation, Angle { radians: theta })
+    }
+}
+
+// endregion Sun Position
+
+impl Skybox for GradientSkybox {
+    fn sky_colour(&self, ray: &Ray) -> Colour {
+        let y = ray.dir().y;
+        // The ground fades in much quicker than the sky, so it looks like there's a floor
+        let gradient = if y >= 0. {
+            Colour::lerp(self.horizon, self.sky, y.sqrt() as Channel)
+        } else {
+            Colour::lerp(self.horizon, self.ground, (-y * 8.).min(1.) as Channel)
+        };
+
+        // Soften the edge over the outer 10% of the disk
+        let cos_angle = Vector3::dot(ray.dir(), self.sun_dir);
+        let cos_edge = self.sun_size.radians.cos();
+        let cos_inner = (self.sun_size.radians * 0.9).cos();
+        let sun = ((cos_angle - cos_edge) / (cos_inner - cos_edge)).clamp(0., 1.) as Channel;
+
+        gradient + (Colour::WHITE * (sun * self.sun_intensity))
+    }
+
+    fn sample_direction(&self, rng: &mut dyn RngCore) -> Option<Vector3> {
+        if self.sun_intensity <= 0. {
+            return None;
+        }
+        // Uniformly over the cone of directions that the disk covers
+        let cos_edge = self.sun_size.radians.cos();
+        let cos_theta = Lerp::lerp(1., cos_edge, rng::number_in_unit_line_01(rng));
+        let sin_theta = (1. - (cos_theta * cos_theta)).max(0.).sqrt();
+        let (sin_phi, cos_phi) = (rng::number_in_unit_line_01(rng) * TAU).sin_cos();
+        let (u, v) = Vector3::any_orthonormal_pair(&self.sun_dir);
+        let dir = (u * (sin_theta * cos_phi)) + (v * (sin_theta * sin_phi)) + (self.sun_dir * cos_theta);
+        Some(dir.normalize())
+    }
+
+    fn direction_probability(&self, dir: Vector3) -> Number {
+        let cos_edge = self.sun_size.radians.cos();
+        if self.sun_intensity <= 0. || Vector3::dot(dir, self.sun_dir) < cos_edge {
+            return 0.;
+        }
+        1. / (TAU * (1. - cos_edge))
+    }
+}
+
+impl Default for GradientSkybox {
+    fn default() -> Self {
+        Self::new(
+            Colour::from([0.3, 0.5, 0.9]),
+            Colour::from([0.9, 0.9, 0.95]),
+            Colour::from([0.25, 0.22, 0.2]),
+            Vector3::new(1., 2., 1.),
+            20.,
+        )
+    }
+}
@@ -1,7 +1,10 @@
 use crate::core::types::{Colour, Image, Number};
 use crate::mesh::primitive::sphere;
+use crate::scene::asset::{AssetError, AssetResolver};
+use crate::scene::validation::SceneValidation;
 use crate::shared::ray::Ray;
 use crate::skybox::Skybox;
+use std::path::Path;
 use std::sync::Arc;
 
 /// A skybox that uses a **High Dynamic Range Image** (**HDRI**) as the skybox
@@ -14,6 +17,23 @@ impl From<Image> for HdrImageSkybox {
     fn from(image: Image) -> Self { Self { image: Arc::new(image) } }
 }
 
+impl HdrImageSkybox {
+    /// Loads an HDRI skybox from an asset file, see [AssetResolver::load_image()]
+    pub fn load(resolver: &AssetResolver, path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        resolver.load_image(path).map(Self::from)
+    }
+
+    /// Loads an HDRI skybox from an asset file, substituting a placeholder if it couldn't be loaded.
+    /// See [AssetResolver::load_image_or_placeholder()]
+    pub fn load_or_placeholder(
+        resolver: &AssetResolver,
+        path: impl AsRef<Path>,
+        validation: &mut SceneValidation,
+    ) -> Self {
+        Self::from(resolver.load_image_or_placeholder(path, validation))
+    }
+}
+
 impl Skybox for HdrImageSkybox {
     fn sky_colour(&self, ray: &Ray) -> Colour {
         // Kinda cheating here, using the `sphere_uv()` function
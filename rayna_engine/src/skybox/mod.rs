@@ -1,18 +1,23 @@
 pub mod dynamic;
+pub mod gradient;
 pub mod hdri;
 pub mod none;
 pub mod simple;
+pub mod time_of_day;
 
 use self::{
     dynamic::DynamicSkybox,
+    gradient::GradientSkybox,
     hdri::HdrImageSkybox,
     none::NoSkybox,
     simple::{SimpleSkybox, WhiteSkybox},
+    time_of_day::TimeOfDaySkybox,
 };
-use crate::core::types::Colour;
+use crate::core::types::{Channel, Colour, Number, Vector3};
 use crate::shared::ray::Ray;
 use crate::shared::RtRequirement;
 use enum_dispatch::enum_dispatch;
+use rand_core::RngCore;
 
 /// The main trait for implementing a skybox
 ///
@@ -21,6 +26,29 @@ use enum_dispatch::enum_dispatch;
 #[doc(notable_trait)]
 pub trait Skybox: RtRequirement {
     fn sky_colour(&self, ray: &Ray) -> Colour;
+
+    /// Picks a direction towards the bright parts of the sky (such as the sun), so that the light from them can be
+    /// found without waiting for a scattered ray to happen to hit them. See [Self::direction_probability()]
+    ///
+    /// # Return Value
+    /// The direction (normalised), or [None] if the skybox isn't importance sampled, which is the default
+    #[allow(unused_variables)]
+    fn sample_direction(&self, rng: &mut dyn RngCore) -> Option<Vector3> { None }
+
+    /// The probability density (per unit solid angle) of [Self::sample_direction()] picking the direction `dir`.
+    ///
+    /// This must be zero wherever no directions are picked, and is only called if directions are picked at all
+    #[allow(unused_variables)]
+    fn direction_probability(&self, dir: Vector3) -> Number { 0. }
+
+    /// Sets the current time of the scene, for skyboxes that change over time (such as the
+    /// [TimeOfDaySkybox]). See [Object::set_time()](crate::object::Object::set_time)
+    fn set_time(&mut self, _time: Number) {}
+
+    /// Does any expensive pre-processing the skybox needs, before it is rendered.
+    ///
+    /// See [Object::prepare()](crate::object::Object::prepare)
+    fn prepare(&mut self) {}
 }
 
 #[enum_dispatch(Skybox)]
@@ -31,6 +59,21 @@ pub enum SkyboxInstance {
     NoSkybox,
     DynamicSkybox,
     HdrImageSkybox,
+    GradientSkybox,
+    TimeOfDaySkybox,
+}
+
+impl SkyboxInstance {
+    /// A quick-start skybox, with a gradient between three colours and a sun. See [GradientSkybox]
+    pub fn three_point(
+        sky: impl Into<Colour>,
+        horizon: impl Into<Colour>,
+        ground: impl Into<Colour>,
+        sun_dir: impl Into<Vector3>,
+        sun_intensity: Channel,
+    ) -> Self {
+        GradientSkybox::new(sky, horizon, ground, sun_dir, sun_intensity).into()
+    }
 }
 
 impl Default for SkyboxInstance {
@@ -0,0 +1,98 @@
+use crate::core::types::{Angle, Colour, Number, Vector3};
+use crate::object::animated::Interpolation;
+use crate::shared::math::Lerp;
+use crate::shared::ray::Ray;
+use crate::skybox::gradient::GradientSkybox;
+use crate::skybox::Skybox;
+use getset::{CopyGetters, Getters};
+use rand_core::RngCore;
+
+/// A [GradientSkybox] whose sun follows the time of day, animated with keyframes.
+///
+/// The time is set for the whole scene at once, using [Scene::set_time()](crate::scene::Scene::set_time), so that a
+/// day-night time-lapse can be rendered as a sequence (see [crate::render::animation]). Each keyframe gives the time
+/// of day (see [GradientSkybox::with_time_of_day()]) at a time in the scene.
+///
+/// The hours are interpolated as they are, and wrap around every 24 hours. So to carry on past midnight, keep counting
+/// up (e.g. `22.0` to `26.0`), instead of going back to `0.0`.
+///
+/// Before the first keyframe and after the last, the time of day stays at that keyframe
+#[derive(Clone, Debug, Getters, CopyGetters)]
+pub struct TimeOfDaySkybox {
+    /// The skybox, with the sun at the current time of day
+    #[get = "pub"]
+    sky: GradientSkybox,
+    /// The `(time, hours)` keyframes, in order
+    keyframes: Vec<(Number, Number)>,
+    #[get_copy = "pub"]
+    interpolation: Interpolation,
+    /// How high the sun gets at noon
+    #[get_copy = "pub"]
+    max_elevation: Angle,
+}
+
+impl TimeOfDaySkybox {
+    /// Creates a new skybox from the `(time, hours)` keyframes, which don't need to be in order. The sun starts at the
+    /// time of day at time `0.0`
+    ///
+    /// # Panics
+    /// Panics if there are no keyframes, or any of the times or hours are not finite
+    pub fn new(
+        sky: GradientSkybox,
+        keyframes: impl IntoIterator<Item = (Number, Number)>,
+        interpolation: Interpolation,
+        max_elevation: Angle,
+    ) -> Self {
+        let mut keyframes = keyframes.into_iter().collect::<Vec<_>>();
+        for &(time, hours) in &keyframes {
+            assert!(
+                time.is_finite() && hours.is_finite(),
+                "keyframe must be finite (was {hours}h at {time})"
+            );
+        }
+        assert!(!keyframes.is_empty(), "track must have at least one keyframe");
+        keyframes.sort_by(|(a, _), (b, _)| Number::total_cmp(a, b));
+
+        let mut skybox = Self {
+            sky,
+            keyframes,
+            interpolation,
+            max_elevation,
+        };
+        skybox.set_time(0.);
+        skybox
+    }
+
+    /// Calculates the time of day (in hours) at the given time
+    pub fn hours_at(&self, time: Number) -> Number {
+        // Index of the first keyframe after the time
+        let next = self.keyframes.partition_point(|(t, _)| *t <= time);
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+        let (start_time, start) = self.keyframes[next - 1];
+        let Some(&(end_time, end)) = self.keyframes.get(next) else {
+            return start;
+        };
+
+        let t = (time - start_time) / (end_time - start_time);
+        let t = match self.interpolation {
+            Interpolation::Step => 0.,
+            Interpolation::Linear => t,
+            Interpolation::Smooth => t * t * (3. - (2. * t)),
+        };
+        Lerp::lerp(start, end, t)
+    }
+}
+
+impl Skybox for TimeOfDaySkybox {
+    fn sky_colour(&self, ray: &Ray) -> Colour { self.sky.sky_colour(ray) }
+
+    fn sample_direction(&self, rng: &mut dyn RngCore) -> Option<Vector3> { self.sky.sample_direction(rng) }
+
+    fn direction_probability(&self, dir: Vector3) -> Number { self.sky.direction_probability(dir) }
+
+    fn set_time(&mut self, time: Number) {
+        self.sky = self.sky.with_time_of_day(self.hours_at(time), self.max_elevation);
+    }
+}
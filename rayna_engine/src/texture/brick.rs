@@ -0,0 +1,129 @@
+use crate::core::types::{Channel, Colour, Number, Vector2};
+use crate::shared::intersect::Intersection;
+use crate::shared::rng;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::Texture;
+use rand_core::RngCore;
+
+/// The layout of the bricks of a brick (or tile) texture.
+///
+/// The bricks are laid in rows, with each row shifted along by the [Self::row_offset], and the mortar is centred on
+/// the edges between the bricks. The rows run along the `u`/`x` axis, and stack up along `v`/`y`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BrickPattern {
+    /// The width and height of each brick, not including the mortar. In world space, the bricks are as deep as they are
+    /// wide
+    pub brick_size: Vector2,
+    /// The width of the mortar between the bricks
+    pub mortar_width: Number,
+    /// How far each row is shifted along from the one below, as a fraction of a brick (plus the mortar). `0.5` is the
+    /// usual brick wall (a *running bond*), and `0.0` gives a grid of tiles
+    pub row_offset: Number,
+    /// How much the colour of each brick is randomly brightened or darkened by, from `0.0` (all the same) to `1.0`
+    pub colour_jitter: Channel,
+    /// The seed for the colour jitter of the bricks
+    pub seed: u64,
+}
+
+impl Default for BrickPattern {
+    /// A brick wall with the proportions of a standard (UK) brick in metres, with 10mm of mortar
+    fn default() -> Self {
+        Self {
+            brick_size: Vector2::new(0.215, 0.065),
+            mortar_width: 0.01,
+            row_offset: 0.5,
+            colour_jitter: 0.2,
+            seed: 0,
+        }
+    }
+}
+
+impl BrickPattern {
+    /// Finds which brick a point is in, returning the indices of the brick along each axis (where axis `1` is the
+    /// rows), or [None] if the point is in the mortar
+    pub fn locate<const D: usize>(&self, pos: [Number; D]) -> Option<[i64; D]> {
+        let period = |axis: usize| {
+            let size = if axis == 1 {
+                self.brick_size.y
+            } else {
+                self.brick_size.x
+            };
+            size + self.mortar_width
+        };
+        let row = if D > 1 { (pos[1] / period(1)).floor() } else { 0. };
+
+        let mut brick = [0; D];
+        for (axis, &p) in pos.iter().enumerate() {
+            let period = period(axis);
+            // The rows are shifted along, but the stacking of the rows isn't
+            let p = if axis == 1 {
+                p
+            } else {
+                p - (row * self.row_offset * period)
+            };
+            let cell = (p / period).floor();
+            // Mortar is half on each side of the brick
+            let local = p - (cell * period);
+            let half_mortar = self.mortar_width / 2.;
+            if local < half_mortar || local > period - half_mortar {
+                return None;
+            }
+            brick[axis] = cell as i64;
+        }
+        Some(brick)
+    }
+
+    /// The brightness multiplier of a brick, from the [Self::colour_jitter]
+    pub fn brick_brightness<const D: usize>(&self, brick: [i64; D]) -> Channel {
+        let hash = rng::hash_seed(std::iter::once(self.seed).chain(brick.map(|b| b as u64)));
+        // Top 24 bits, in the range `-1.0..1.0`
+        let random = ((hash >> 40) as Channel / (1 << 24) as Channel * 2.) - 1.;
+        1. + (self.colour_jitter * random)
+    }
+
+    /// Evaluates the brick pattern at a point, using the `brick` texture (with the jitter) or the `mortar` texture
+    pub fn value<const D: usize>(
+        &self,
+        pos: [Number; D],
+        brick: &impl Texture,
+        mortar: &impl Texture,
+        intersection: &Intersection,
+        rng: &mut dyn RngCore,
+    ) -> Colour {
+        match self.locate(pos) {
+            Some(index) => brick.value(intersection, rng) * self.brick_brightness(index),
+            None => mortar.value(intersection, rng),
+        }
+    }
+}
+
+/// A brick texture in UV space, so the bricks follow the surface. See [BrickPattern]
+#[derive(Clone, Debug)]
+pub struct UvBrickTexture<Brick: Texture = DynamicTexture, Mortar: Texture = DynamicTexture> {
+    pub pattern: BrickPattern,
+    pub brick: Brick,
+    pub mortar: Mortar,
+}
+
+impl<Brick: Texture, Mortar: Texture> Texture for UvBrickTexture<Brick, Mortar> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let pos = intersection.uv.to_array();
+        self.pattern.value(pos, &self.brick, &self.mortar, intersection, rng)
+    }
+}
+
+/// A brick texture in world space, where the bricks are blocks stacked up along the `Y` axis. Any surface shows the
+/// bricks that it cuts through, so objects look as if they were built out of bricks. See [BrickPattern]
+#[derive(Clone, Debug)]
+pub struct WorldBrickTexture<Brick: Texture = DynamicTexture, Mortar: Texture = DynamicTexture> {
+    pub pattern: BrickPattern,
+    pub brick: Brick,
+    pub mortar: Mortar,
+}
+
+impl<Brick: Texture, Mortar: Texture> Texture for WorldBrickTexture<Brick, Mortar> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let pos = intersection.pos_w.to_array();
+        self.pattern.value(pos, &self.brick, &self.mortar, intersection, rng)
+    }
+}
@@ -4,6 +4,9 @@ use crate::texture::Texture;
 use rand_core::RngCore;
 use std::sync::Arc;
 
+/// Object wrapper around a `dyn` [Texture]; Delegates everything to the inner texture.
+///
+/// If possible use the enum variants on [`crate::texture::TextureInstance`], so that static-dispatch is used instead of dynamic dispatch
 #[derive(Clone, Debug)]
 pub struct DynamicTexture {
     pub inner: Arc<dyn Texture>,
@@ -13,6 +16,10 @@ impl DynamicTexture {
     pub fn new(inner: impl Texture + 'static) -> Self { Self { inner: Arc::new(inner) } }
 }
 
+impl super::TextureInstance {
+    pub fn from_dyn(value: impl Texture + 'static) -> Self { Self::from(DynamicTexture::new(value)) }
+}
+
 impl Texture for DynamicTexture {
     fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
         self.inner.value(intersection, rng)
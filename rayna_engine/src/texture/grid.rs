@@ -0,0 +1,40 @@
+use crate::core::grid::DensityGrid;
+use crate::core::types::{Channel, Colour};
+use crate::scene::asset::{AssetError, AssetResolver};
+use crate::shared::intersect::Intersection;
+use crate::texture::Texture;
+use rand_core::RngCore;
+use std::path::Path;
+
+/// A 3D texture that looks up a [DensityGrid] at the local position of the intersection (so the grid moves with the
+/// object). This is mainly used as the [density texture](crate::object::volumetric::VolumetricObject::with_density_texture)
+/// of a volume, for smoke and clouds.
+///
+/// The values are divided by the [largest value](DensityGrid::max_value) in the grid, so they are all in the range
+/// `0.0..=1.0`, and the value is the same in all the channels
+#[derive(Clone, Debug)]
+pub struct GridTexture {
+    pub grid: DensityGrid,
+}
+
+impl From<DensityGrid> for GridTexture {
+    fn from(grid: DensityGrid) -> Self { Self { grid } }
+}
+
+impl GridTexture {
+    /// Loads a grid texture from a VDB file, see [AssetResolver::load_vdb()]
+    pub fn load(resolver: &AssetResolver, path: impl AsRef<Path>, grid_name: Option<&str>) -> Result<Self, AssetError> {
+        resolver.load_vdb(path, grid_name).map(Self::from)
+    }
+}
+
+impl Texture for GridTexture {
+    fn value(&self, intersection: &Intersection, _rng: &mut dyn RngCore) -> Colour {
+        let max = self.grid.max_value();
+        if max <= 0. {
+            return Colour::BLACK;
+        }
+        let value = self.grid.sample(intersection.pos_l) / max;
+        Colour::from([value as Channel; 3])
+    }
+}
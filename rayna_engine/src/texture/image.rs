@@ -1,31 +1,128 @@
-use crate::core::types::{Colour, Image, Number, Size2, Vector2};
+use crate::core::colour::ColourSpace;
+use crate::core::types::{Channel, Colour, Image, Number, Size2, Vector2};
+use crate::scene::asset::{AssetError, AssetResolver};
+use crate::scene::validation::SceneValidation;
 use crate::shared::intersect::Intersection;
+use crate::shared::math::Lerp;
 use crate::texture::Texture;
 use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
+use strum_macros::{EnumIter, IntoStaticStr};
 
+/// A texture that maps an image onto the UV coordinates of the surface.
+///
+/// The image is stored as a mip-map: a chain of copies of it, each half the size of the one before (down to a single
+/// pixel), which are generated when the texture is created. Sampling a smaller level averages out the detail that
+/// would otherwise shimmer and alias when the image is squashed into fewer pixels (such as a checkerboard or photo
+/// seen at a glancing angle, or far away). The level is picked by the [Self::mip_bias] (and ray differentials, once
+/// the renderer tracks them), and the pixels of the level are blended according to the [Self::filter].
+///
+/// The pixels are linear floating-point colours, so HDR images (`.hdr` and `.exr`) keep their values above `1.0`, and
+/// can be used for bright emissive textures (see [AssetResolver::load_image()], which is shared with the
+/// [HDRI skybox](crate::skybox::hdri::HdrImageSkybox))
 #[derive(Clone, Debug)]
 pub struct ImageTexture {
-    pub image: Arc<Image>,
+    /// The levels of the mip-map, starting with the full-size image
+    levels: Arc<[Image]>,
     pub scale: Size2,
     pub offset: Vector2,
+    /// How the pixels (and levels) of the image are blended
+    pub filter: TextureFilter,
+    /// Which level of the mip-map to sample, where `0.0` is the full-size image, `1.0` is half the size, and so on
+    /// (fractional levels blend between the two levels with [TextureFilter::Trilinear]). The level is clamped to the
+    /// levels that there are
+    pub mip_bias: Number,
 }
 
-impl From<Image> for ImageTexture {
-    fn from(value: Image) -> Self { Self::from(Arc::new(value)) }
+/// How an [ImageTexture] blends between the pixels of its image
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, EnumIter, IntoStaticStr)]
+pub enum TextureFilter {
+    /// The nearest pixel of the nearest level, with no blending, giving sharp pixelated edges
+    Nearest,
+    /// Blends the four nearest pixels of the nearest level
+    #[default]
+    Bilinear,
+    /// Blends the four nearest pixels of both of the levels either side of the [ImageTexture::mip_bias], and then
+    /// between the levels, so that there isn't a jump between them
+    Trilinear,
 }
 
-impl From<Arc<Image>> for ImageTexture {
-    fn from(value: Arc<Image>) -> Self {
+impl From<Image> for ImageTexture {
+    fn from(value: Image) -> Self {
+        let mut levels = vec![value];
+        while let Some(last) = levels.last().filter(|last| last.width() > 1 || last.height() > 1) {
+            let next = last.half_size();
+            levels.push(next);
+        }
+
         Self {
             offset: Vector2::ZERO,
             scale: Size2::splat(1.),
-            image: value,
+            filter: TextureFilter::default(),
+            mip_bias: 0.,
+            levels: levels.into(),
+        }
+    }
+}
+
+impl From<Arc<Image>> for ImageTexture {
+    // Images share their pixels when cloned, so this doesn't copy them
+    fn from(value: Arc<Image>) -> Self { Self::from(Image::clone(&value)) }
+}
+
+impl ImageTexture {
+    /// Loads an image texture from an asset file, see [AssetResolver::load_image()]
+    pub fn load(resolver: &AssetResolver, path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        resolver.load_image(path).map(Self::from)
+    }
+
+    /// Loads an image texture from an asset file, substituting a placeholder if it couldn't be loaded.
+    /// See [AssetResolver::load_image_or_placeholder()]
+    pub fn load_or_placeholder(
+        resolver: &AssetResolver,
+        path: impl AsRef<Path>,
+        validation: &mut SceneValidation,
+    ) -> Self {
+        Self::from(resolver.load_image_or_placeholder(path, validation))
+    }
+
+    /// Loads an image texture that holds data instead of colours (such as a normal map, roughness map or mask), so
+    /// its values are kept as they are instead of being decoded from sRGB. See [AssetResolver::load_image_as()]
+    pub fn load_linear(resolver: &AssetResolver, path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        resolver.load_image_as(path, ColourSpace::Linear).map(Self::from)
+    }
+
+    /// Loads a data texture like [Self::load_linear()], substituting a placeholder if it couldn't be loaded.
+    /// See [AssetResolver::load_image_or_placeholder_as()]
+    pub fn load_linear_or_placeholder(
+        resolver: &AssetResolver,
+        path: impl AsRef<Path>,
+        validation: &mut SceneValidation,
+    ) -> Self {
+        Self::from(resolver.load_image_or_placeholder_as(path, ColourSpace::Linear, validation))
+    }
+
+    /// The full-size image
+    pub fn image(&self) -> &Image { &self.levels[0] }
+
+    /// The levels of the mip-map, starting with the full-size image, each half the size of the one before
+    pub fn mip_levels(&self) -> &[Image] { &self.levels }
+
+    /// Samples a level of the mip-map at the UV coordinates (where `v` is already flipped to image coords)
+    fn sample_level(&self, level: usize, u: Number, v: Number) -> Colour {
+        let image = &self.levels[level];
+        // The pixel centres are at the middle of the pixels, so that the levels line up with each other
+        let x = (u * image.width() as Number) - 0.5;
+        let y = (v * image.height() as Number) - 0.5;
+        match self.filter {
+            TextureFilter::Nearest => image.get_nearest(x, y),
+            TextureFilter::Bilinear | TextureFilter::Trilinear => image.get_bilinear(x, y),
         }
     }
 }
 
-// TODO: Implement some sort of texture filtering and stuff
 impl Texture for ImageTexture {
     fn value(&self, intersection: &Intersection, _rng: &mut dyn RngCore) -> Colour {
         // Calculate pixel positions after scale and offset
@@ -33,8 +130,25 @@ impl Texture for ImageTexture {
         // Flip y-axis to image coords
         let (u, v) = (translated.x, 1. - translated.y);
 
-        let i = u * self.image.width() as Number;
-        let j = v * self.image.height() as Number;
-        self.image.get_bilinear(i, j)
+        let max_level = (self.levels.len() - 1) as Number;
+        // NaN would be clamped to NaN, so it's treated as the full-size image
+        let level = if self.mip_bias.is_nan() {
+            0.
+        } else {
+            self.mip_bias.clamp(0., max_level)
+        };
+        match self.filter {
+            TextureFilter::Nearest | TextureFilter::Bilinear => self.sample_level(level.round() as usize, u, v),
+            TextureFilter::Trilinear => {
+                let lower = level.floor();
+                let upper = level.ceil();
+                let a = self.sample_level(lower as usize, u, v);
+                if upper == lower {
+                    return a;
+                }
+                let b = self.sample_level(upper as usize, u, v);
+                Colour::lerp(a, b, (level - lower) as Channel)
+            }
+        }
     }
 }
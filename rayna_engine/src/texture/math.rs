@@ -0,0 +1,48 @@
+use crate::core::types::{Channel, Colour};
+use crate::shared::intersect::Intersection;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::Texture;
+use rand_core::RngCore;
+
+/// A texture that applies a [MathOp] to each channel of another texture. These can be chained together (and combined
+/// with [MixTexture](crate::texture::mix::MixTexture)) to build up simple shading networks
+#[derive(Clone, Debug)]
+pub struct MathTexture<Tex: Texture = DynamicTexture> {
+    pub texture: Tex,
+    pub op: MathOp,
+}
+
+/// An operation applied to each channel of a [MathTexture]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MathOp {
+    /// `1 - c`, turning black into white
+    Invert,
+    /// Limits the channels to be between `min` and `max`
+    Clamp { min: Channel, max: Channel },
+    /// `c ^ gamma`. Values above `1.0` darken the texture, and below brighten it. Negative channels are treated as zero
+    Gamma(Channel),
+    /// Scales the channels away from (or towards, if less than `1.0`) middle grey
+    Contrast(Channel),
+    /// Scales the channels by a constant
+    Multiply(Channel),
+}
+
+impl MathOp {
+    /// Applies the operation to a single channel
+    pub fn apply(self, c: Channel) -> Channel {
+        match self {
+            Self::Invert => 1. - c,
+            // Not `clamp()`, which panics if the limits are the wrong way around
+            Self::Clamp { min, max } => c.max(min).min(max),
+            Self::Gamma(gamma) => c.max(0.).powf(gamma),
+            Self::Contrast(contrast) => ((c - 0.5) * contrast) + 0.5,
+            Self::Multiply(factor) => c * factor,
+        }
+    }
+}
+
+impl<Tex: Texture> Texture for MathTexture<Tex> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        self.texture.value(intersection, rng).map(|c| self.op.apply(c))
+    }
+}
@@ -0,0 +1,25 @@
+use crate::core::types::Colour;
+use crate::shared::intersect::Intersection;
+use crate::shared::math::Lerp;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::Texture;
+use rand_core::RngCore;
+
+/// A texture that blends between two textures, using a third texture as the factor.
+///
+/// Each channel is blended separately, so where the `factor` is black the texture is `a`, where it is white the
+/// texture is `b`, and coloured factors blend the channels by different amounts. Factors outside of `0.0..=1.0`
+/// extrapolate past the textures
+#[derive(Clone, Debug)]
+pub struct MixTexture<A: Texture = DynamicTexture, B: Texture = DynamicTexture, Factor: Texture = DynamicTexture> {
+    pub a: A,
+    pub b: B,
+    pub factor: Factor,
+}
+
+impl<A: Texture, B: Texture, Factor: Texture> Texture for MixTexture<A, B, Factor> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let factor = self.factor.value(intersection, rng);
+        Colour::lerp(self.a.value(intersection, rng), self.b.value(intersection, rng), factor)
+    }
+}
@@ -1,8 +1,16 @@
+pub mod brick;
 pub mod checker;
 pub mod dynamic;
+pub mod grid;
 pub mod image;
+pub mod math;
+pub mod mix;
 pub mod noise;
+pub mod normal_map;
+pub mod pattern;
+pub mod sdf;
 pub mod solid;
+pub mod vertex_colour;
 
 use crate::core::types::Colour;
 use crate::shared::intersect::Intersection;
@@ -12,11 +20,18 @@ use rand::thread_rng;
 use rand_core::RngCore;
 //noinspection ALL
 use self::{
+    brick::{UvBrickTexture, WorldBrickTexture},
     checker::{UvCheckerTexture, WorldCheckerTexture},
     dynamic::DynamicTexture,
+    grid::GridTexture,
     image::ImageTexture,
+    math::MathTexture,
+    mix::MixTexture,
     noise::{LocalNoiseTexture, UvNoiseTexture, WorldNoiseTexture},
+    pattern::{UvPatternTexture, WorldPatternTexture},
+    sdf::SdfTexture,
     solid::SolidTexture,
+    vertex_colour::VertexColourTexture,
 };
 
 /// The trait that defines what properties a texture has
@@ -33,10 +48,19 @@ pub enum TextureInstance {
     SolidTexture,
     WorldCheckerTexture(WorldCheckerTexture<DynamicTexture, DynamicTexture>),
     UvCheckerTexture(UvCheckerTexture<DynamicTexture, DynamicTexture>),
+    UvBrickTexture(UvBrickTexture<DynamicTexture, DynamicTexture>),
+    WorldBrickTexture(WorldBrickTexture<DynamicTexture, DynamicTexture>),
+    UvPatternTexture(UvPatternTexture<DynamicTexture, DynamicTexture>),
+    WorldPatternTexture(WorldPatternTexture<DynamicTexture, DynamicTexture>),
     ImageTexture,
     UvNoiseTexture(UvNoiseTexture<Box<dyn noise::RtNoiseFn<2>>>),
     LocalNoiseTexture(LocalNoiseTexture<Box<dyn noise::RtNoiseFn<3>>>),
     WorldNoiseTexture(WorldNoiseTexture<Box<dyn noise::RtNoiseFn<3>>>),
+    VertexColourTexture,
+    GridTexture,
+    MixTexture(MixTexture<DynamicTexture, DynamicTexture, DynamicTexture>),
+    MathTexture(MathTexture<DynamicTexture>),
+    SdfTexture(SdfTexture<DynamicTexture, DynamicTexture>),
     DynamicTexture,
 }
 
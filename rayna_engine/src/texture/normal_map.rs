@@ -0,0 +1,80 @@
+use crate::core::types::{Number, Vector3};
+use crate::scene::asset::{AssetError, AssetResolver};
+use crate::shared::intersect::Intersection;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::image::ImageTexture;
+use crate::texture::Texture;
+use rand_core::RngCore;
+use std::path::Path;
+
+/// A texture of surface normals, encoded as colours (a *normal map*). Unlike the other textures, this gives vectors
+/// instead of colours (see [Self::normal()]), so it isn't a [Texture] itself, but wraps one that holds the encoded
+/// normals (usually an [ImageTexture](crate::texture::image::ImageTexture)).
+///
+/// The normals are in tangent space, where `+Z` is the surface normal, `+X` is the tangent (along the `u` texture
+/// coordinate) and `+Y` is the bitangent (along `v`). Each channel maps `0.0..=1.0` to `-1.0..=1.0`, which is why
+/// flat areas of normal maps are a light blue (`[0.5, 0.5, 1.0]`).
+///
+/// The encoded values are data, not colours, so image textures must be loaded *linearly*, without decoding sRGB (see
+/// [Self::load()] and [ImageTexture::load_linear()]). Otherwise, flat areas decode to tilted normals.
+///
+/// To use it in a scene, wrap a material in a
+/// [NormalMappedMaterial](crate::material::normal_mapped::NormalMappedMaterial).
+#[derive(Clone, Debug)]
+pub struct NormalMapTexture<Tex: Texture = DynamicTexture> {
+    pub texture: Tex,
+    /// Flips the green (`Y`) channel, for normal maps made with `-Y` bitangents ("DirectX" normal maps, as opposed to
+    /// the "OpenGL" convention that this uses by default)
+    pub flip_green: bool,
+    /// How much the normals are tilted away from the surface normal: `0.0` is flat, `1.0` is as in the map, and
+    /// larger values exaggerate the bumps
+    pub strength: Number,
+}
+
+impl<Tex: Texture> From<Tex> for NormalMapTexture<Tex> {
+    fn from(texture: Tex) -> Self {
+        Self {
+            texture,
+            flip_green: false,
+            strength: 1.,
+        }
+    }
+}
+
+impl NormalMapTexture<ImageTexture> {
+    /// Loads a normal map from an image asset. The image is loaded linearly (see [ImageTexture::load_linear()])
+    pub fn load(resolver: &AssetResolver, path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        ImageTexture::load_linear(resolver, path).map(Self::from)
+    }
+}
+
+impl<Tex: Texture> NormalMapTexture<Tex> {
+    /// The (normalised) normal at the intersection, in tangent space. See [Self::to_world()] to bring it into world
+    /// space
+    pub fn normal(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Vector3 {
+        let colour = self.texture.value(intersection, rng);
+        let decode = |c: usize| (colour[c] as Number * 2.) - 1.;
+        let y = if self.flip_green { -decode(1) } else { decode(1) };
+        let normal = Vector3::new(decode(0) * self.strength, y * self.strength, decode(2));
+        // Encoded normals that point into the surface (or are black) are treated as flat
+        if normal.z <= 0. {
+            return Vector3::Z;
+        }
+        normal.try_normalize().unwrap_or(Vector3::Z)
+    }
+
+    /// Transforms a tangent-space `normal` (see [Self::normal()]) into world space, given the (outwards) surface
+    /// `surface_normal` and the `tangent` along the `u` texture coordinate.
+    ///
+    /// The tangent doesn't need to be normalised, or exactly perpendicular to the surface normal. If it is parallel to
+    /// it (or zero), the surface normal is returned unchanged
+    pub fn to_world(normal: Vector3, surface_normal: Vector3, tangent: Vector3) -> Vector3 {
+        let Some(tangent) = (tangent - (surface_normal * tangent.dot(surface_normal))).try_normalize() else {
+            return surface_normal;
+        };
+        let bitangent = surface_normal.cross(tangent);
+        ((tangent * normal.x) + (bitangent * normal.y) + (surface_normal * normal.z))
+            .try_normalize()
+            .unwrap_or(surface_normal)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::core::types::{Angle, Colour, Number, Vector2, Vector3};
+use crate::shared::intersect::Intersection;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::Texture;
+use rand_core::RngCore;
+
+/// A simple repeating pattern, which is either *on* (in a stripe, dot or line) or *off* at each point.
+///
+/// In UV space the patterns are 2D, and in world space they are 3D (so any surface shows the pattern where it cuts
+/// through it)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// Parallel stripes `width` wide, with gaps of the same width between them. The stripes run across the `u`
+    /// (or `x`) axis, turned by the `angle` towards the `v` (or `z`) axis, so in world space they are vertical
+    Stripes { angle: Angle, width: Number },
+    /// Dots (spheres in world space) of the `radius`, on a grid `spacing` apart
+    Dots { spacing: Number, radius: Number },
+    /// Lines (planes in world space) `line_width` wide, on a grid `spacing` apart
+    Grid { spacing: Number, line_width: Number },
+}
+
+impl Pattern {
+    /// Whether the pattern is on at a point
+    pub fn is_on<const D: usize>(&self, pos: [Number; D]) -> bool {
+        match *self {
+            Self::Stripes { angle, width } => {
+                let across = pos.first().copied().unwrap_or(0.);
+                let along = if D > 1 { pos[D - 1] } else { 0. };
+                let t = ((across * angle.cos()) + (along * angle.sin())) / width;
+                (t.floor() as i64).rem_euclid(2) == 0
+            }
+            Self::Dots { spacing, radius } => {
+                // The dots are in the middle of the cells
+                let dist_sqr = pos
+                    .iter()
+                    .map(|&p| (p.rem_euclid(spacing) - (spacing / 2.)).powi(2))
+                    .sum::<Number>();
+                dist_sqr < radius * radius
+            }
+            Self::Grid { spacing, line_width } => pos.iter().any(|&p| {
+                let offset = p.rem_euclid(spacing);
+                offset.min(spacing - offset) < line_width / 2.
+            }),
+        }
+    }
+
+    /// Evaluates the pattern at a point, using the `on` or `off` texture
+    pub fn value<const D: usize>(
+        &self,
+        pos: [Number; D],
+        on: &impl Texture,
+        off: &impl Texture,
+        intersection: &Intersection,
+        rng: &mut dyn RngCore,
+    ) -> Colour {
+        if self.is_on(pos) {
+            on.value(intersection, rng)
+        } else {
+            off.value(intersection, rng)
+        }
+    }
+}
+
+/// A [Pattern] in UV space, so it follows the surface
+#[derive(Clone, Debug)]
+pub struct UvPatternTexture<On: Texture = DynamicTexture, Off: Texture = DynamicTexture> {
+    pub pattern: Pattern,
+    pub offset: Vector2,
+    pub on: On,
+    pub off: Off,
+}
+
+impl<On: Texture, Off: Texture> Texture for UvPatternTexture<On, Off> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let pos = intersection.uv.to_vector() + self.offset;
+        self.pattern
+            .value(pos.to_array(), &self.on, &self.off, intersection, rng)
+    }
+}
+
+/// A [Pattern] in world space
+#[derive(Clone, Debug)]
+pub struct WorldPatternTexture<On: Texture = DynamicTexture, Off: Texture = DynamicTexture> {
+    pub pattern: Pattern,
+    pub offset: Vector3,
+    pub on: On,
+    pub off: Off,
+}
+
+impl<On: Texture, Off: Texture> Texture for WorldPatternTexture<On, Off> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        let pos = intersection.pos_w.to_vector() + self.offset;
+        self.pattern
+            .value(pos.to_array(), &self.on, &self.off, intersection, rng)
+    }
+}
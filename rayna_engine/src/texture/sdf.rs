@@ -0,0 +1,244 @@
+use crate::core::types::{Channel, Colour, Image, Number, Size2, Vector2};
+use crate::shared::intersect::Intersection;
+use crate::shared::math::Lerp;
+use crate::texture::dynamic::DynamicTexture;
+use crate::texture::Texture;
+use fontdue::Font;
+use rand_core::RngCore;
+
+/// A shape (such as text or a logo), stored as a *signed distance field* (SDF): the distance from each pixel to the
+/// nearest edge of the shape, in pixels, which is positive inside the shape and negative outside.
+///
+/// Unlike the coverage of each pixel, the distances can be interpolated between the pixels without blurring, so the
+/// edges stay sharp when the shape is magnified (see [SdfTexture])
+#[derive(Clone, Debug)]
+pub struct SdfMask {
+    field: Image<Number>,
+}
+
+impl SdfMask {
+    /// How big the text of [Self::from_text()] is (the size of the font's em square, roughly the height of a line), in
+    /// pixels of the field
+    pub const TEXT_PIXELS_PER_EM: f32 = 64.;
+    /// How many pixels of empty space are left around text, so that the edges of the field are outside of the shape
+    pub const TEXT_PADDING: usize = 8;
+
+    /// Creates a mask from the coverage of each pixel (in the range `0.0..=1.0`), where the pixels that are at least
+    /// half covered are inside the shape
+    pub fn from_coverage(coverage: &Image<Number>) -> Self {
+        let (width, height) = (coverage.width(), coverage.height());
+        let inside = (0..width * height)
+            .map(|i| coverage[(i % width, i / width)] >= 0.5)
+            .collect::<Vec<_>>();
+        let outside = inside.iter().map(|inside| !inside).collect::<Vec<_>>();
+        let to_inside = distance_transform(&inside, width, height);
+        let to_outside = distance_transform(&outside, width, height);
+
+        // The edge is half way between the pixels that are inside and outside
+        let field = Image::from_fn(width, height, |x, y| {
+            let i = x + (y * width);
+            if inside[i] {
+                to_outside[i] - 0.5
+            } else {
+                0.5 - to_inside[i]
+            }
+        });
+        Self { field }
+    }
+
+    /// Renders a string of text with a font, which can have multiple lines (separated by `\n`). The lines are left
+    /// aligned, and there is [Self::TEXT_PADDING] around the text. Fonts can be loaded with
+    /// [AssetResolver::load_font()](crate::scene::asset::AssetResolver::load_font)
+    pub fn from_text(font: &Font, text: &str) -> Self {
+        let px = Self::TEXT_PIXELS_PER_EM;
+        let (ascent, line_height) = match font.horizontal_line_metrics(px) {
+            Some(metrics) => (metrics.ascent, metrics.new_line_size),
+            None => (px, px * 1.2),
+        };
+
+        // Lay out the glyphs first, to find out how big the image needs to be
+        let mut glyphs = vec![];
+        let mut width: f32 = 0.;
+        let lines = text.split('\n').collect::<Vec<_>>();
+        for (line_index, line) in lines.iter().enumerate() {
+            let baseline = ascent + (line_index as f32 * line_height);
+            let mut pen = 0.;
+            let mut prev = None;
+            for c in line.chars() {
+                if let Some(prev) = prev {
+                    pen += font.horizontal_kern(prev, c, px).unwrap_or(0.);
+                }
+                let (metrics, bitmap) = font.rasterize(c, px);
+                // The glyph's offsets are from the pen on the baseline, with `y` going up
+                let left = pen + metrics.xmin as f32;
+                let top = baseline - (metrics.ymin as f32 + metrics.height as f32);
+                width = width.max(left + metrics.width as f32).max(pen + metrics.advance_width);
+                glyphs.push((left, top, metrics.width, bitmap));
+                pen += metrics.advance_width;
+                prev = Some(c);
+            }
+        }
+
+        let pad = Self::TEXT_PADDING;
+        let image_width = width.ceil() as usize + (2 * pad);
+        let image_height = (lines.len() as f32 * line_height).ceil() as usize + (2 * pad);
+        let mut coverage = Image::<Number>::new_blank(image_width, image_height);
+        for (left, top, glyph_width, bitmap) in glyphs {
+            let (x0, y0) = (left.round() as i64 + pad as i64, top.round() as i64 + pad as i64);
+            for (i, &value) in bitmap.iter().enumerate() {
+                let (x, y) = (x0 + (i % glyph_width) as i64, y0 + (i / glyph_width) as i64);
+                if (0..image_width as i64).contains(&x) && (0..image_height as i64).contains(&y) {
+                    let pixel = &mut coverage[(x as usize, y as usize)];
+                    // Glyphs can overlap a little, so keep the most covered
+                    *pixel = pixel.max(value as Number / 255.);
+                }
+            }
+        }
+        Self::from_coverage(&coverage)
+    }
+
+    pub fn width(&self) -> usize { self.field.width() }
+
+    pub fn height(&self) -> usize { self.field.height() }
+
+    /// The signed distance at a point in the field, in pixels, interpolating between the pixels
+    pub fn distance(&self, px: Number, py: Number) -> Number { self.field.get_bilinear(px, py) }
+}
+
+/// The distance from each pixel to the nearest pixel that is `true` in the `mask`, using the exact euclidean distance
+/// transform
+fn distance_transform(mask: &[bool], width: usize, height: usize) -> Vec<Number> {
+    /*
+    CREDITS:
+
+    Title: "Distance Transforms of Sampled Functions"
+    Authors: Pedro F. Felzenszwalb, Daniel P. Huttenlocher
+    URL: <https://doi.org/10.4086/toc.2012.v008a019>
+    */
+    // Far enough to be treated as infinite, while still keeping the maths finite
+    const FAR: Number = 1e20;
+    if width == 0 || height == 0 {
+        return vec![];
+    }
+    let mut dist = mask.iter().map(|&m| if m { 0. } else { FAR }).collect::<Vec<_>>();
+
+    // The 2D transform is the 1D transform down the columns, and then along the rows
+    let mut column = vec![0.; height];
+    for x in 0..width {
+        for (y, d) in column.iter_mut().enumerate() {
+            *d = dist[x + (y * width)];
+        }
+        for (y, d) in squared_distance_1d(&column).into_iter().enumerate() {
+            dist[x + (y * width)] = d;
+        }
+    }
+    for row in dist.chunks_mut(width) {
+        let squared = squared_distance_1d(row);
+        row.copy_from_slice(&squared);
+    }
+    dist.into_iter().map(Number::sqrt).collect()
+}
+
+/// The 1D squared distance transform of `f`, from the lower envelope of the parabolas rooted at each point
+fn squared_distance_1d(f: &[Number]) -> Vec<Number> {
+    let n = f.len();
+    // The points whose parabolas are in the envelope, and where each one starts being the lowest
+    let mut parabolas = vec![0_usize; n];
+    let mut starts = vec![0.; n + 1];
+    let mut k = 0;
+    starts[0] = Number::NEG_INFINITY;
+    starts[1] = Number::INFINITY;
+    let intersection =
+        |q: usize, p: usize| ((f[q] + (q * q) as Number) - (f[p] + (p * p) as Number)) / (2 * (q - p)) as Number;
+    for q in 1..n {
+        let mut s = intersection(q, parabolas[k]);
+        while s <= starts[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+        k += 1;
+        parabolas[k] = q;
+        starts[k] = s;
+        starts[k + 1] = Number::INFINITY;
+    }
+
+    let mut k = 0;
+    (0..n)
+        .map(|q| {
+            while starts[k + 1] < q as Number {
+                k += 1;
+            }
+            let offset = q as Number - parabolas[k] as Number;
+            (offset * offset) + f[parabolas[k]]
+        })
+        .collect()
+}
+
+/// A texture that stamps a shape (such as text from [SdfMask::from_text()], or a logo) onto a surface, using the
+/// `foreground` texture inside the shape and the `background` texture outside of it.
+///
+/// Like an [ImageTexture](crate::texture::image::ImageTexture), the mask covers the UV coordinates `0.0..=1.0` (after
+/// the scale and offset), and is [background](Self::background) everywhere else
+#[derive(Clone, Debug)]
+pub struct SdfTexture<Fg: Texture = DynamicTexture, Bg: Texture = DynamicTexture> {
+    pub mask: SdfMask,
+    pub foreground: Fg,
+    pub background: Bg,
+    pub scale: Size2,
+    pub offset: Vector2,
+    /// How wide the blend between the textures at the edge of the shape is, in pixels of the mask. A width of one
+    /// pixel gives smooth but sharp edges, and zero gives hard (aliased) edges
+    pub edge_width: Number,
+}
+
+impl<Fg: Texture, Bg: Texture> SdfTexture<Fg, Bg> {
+    pub fn new(mask: SdfMask, foreground: Fg, background: Bg) -> Self {
+        Self {
+            mask,
+            foreground,
+            background,
+            scale: Size2::splat(1.),
+            offset: Vector2::ZERO,
+            edge_width: 1.,
+        }
+    }
+
+    /// How much of the foreground there is at the UV coordinates, from `0.0` to `1.0`
+    pub fn coverage(&self, intersection: &Intersection) -> Number {
+        let translated = self.offset + (intersection.uv.to_vector() * self.scale.to_vector());
+        // Flip y-axis to image coords
+        let (u, v) = (translated.x, 1. - translated.y);
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return 0.;
+        }
+
+        let x = (u * self.mask.width() as Number) - 0.5;
+        let y = (v * self.mask.height() as Number) - 0.5;
+        let distance = self.mask.distance(x, y);
+        if self.edge_width > 0. {
+            ((distance / self.edge_width) + 0.5).clamp(0., 1.)
+        } else if distance > 0. {
+            1.
+        } else {
+            0.
+        }
+    }
+}
+
+impl<Fg: Texture, Bg: Texture> Texture for SdfTexture<Fg, Bg> {
+    fn value(&self, intersection: &Intersection, rng: &mut dyn RngCore) -> Colour {
+        // Only evaluate both of the textures at the edges
+        let coverage = self.coverage(intersection);
+        if coverage <= 0. {
+            self.background.value(intersection, rng)
+        } else if coverage >= 1. {
+            self.foreground.value(intersection, rng)
+        } else {
+            Colour::lerp(
+                self.background.value(intersection, rng),
+                self.foreground.value(intersection, rng),
+                coverage as Channel,
+            )
+        }
+    }
+}
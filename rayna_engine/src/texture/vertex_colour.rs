@@ -0,0 +1,27 @@
+use rand_core::RngCore;
+
+use crate::core::types::Colour;
+use crate::shared::intersect::Intersection;
+use crate::texture::Texture;
+
+/// A texture that uses the interpolated vertex colours of the mesh (see [Intersection::colour])
+///
+/// Meshes that don't have vertex colours use the [fallback](Self::fallback) colour instead
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VertexColourTexture {
+    pub fallback: Colour,
+}
+
+impl Default for VertexColourTexture {
+    fn default() -> Self {
+        Self {
+            fallback: Colour::WHITE,
+        }
+    }
+}
+
+impl Texture for VertexColourTexture {
+    fn value(&self, intersection: &Intersection, _rng: &mut dyn RngCore) -> Colour {
+        intersection.colour.unwrap_or(self.fallback)
+    }
+}
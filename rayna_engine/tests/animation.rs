@@ -0,0 +1,126 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::animated::{AnimatedObject, Interpolation, KeyframeTrack};
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::{Object, ObjectInstance};
+use rayna_engine::scene::camera::Shutter;
+use rayna_engine::shared::aabb::HasAabb;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::texture::TextureInstance;
+
+type Obj = ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>;
+
+/// Tracks should interpolate between keyframes, and hold at the ends
+#[test]
+pub fn keyframe_track() {
+    let at = |x: Number| Transform3::from_translation(Vector3::new(x, 0., 0.));
+    let x = |track: &KeyframeTrack, time: Number| track.transform_at(time).map_point(Point3::ZERO).x;
+
+    // Out of order on purpose
+    let linear = KeyframeTrack::new([(2., at(4.)), (0., at(0.)), (1., at(1.))], Interpolation::Linear);
+    assert_eq!(linear.time_range(), (0., 2.));
+    assert_relative_eq!(x(&linear, -1.), 0., epsilon = 1e-9);
+    assert_relative_eq!(x(&linear, 0.5), 0.5, epsilon = 1e-9);
+    assert_relative_eq!(x(&linear, 1.5), 2.5, epsilon = 1e-9);
+    assert_relative_eq!(x(&linear, 3.), 4., epsilon = 1e-9);
+
+    let step = KeyframeTrack::new([(0., at(0.)), (1., at(1.))], Interpolation::Step);
+    assert_relative_eq!(x(&step, 0.9), 0., epsilon = 1e-9);
+    assert_relative_eq!(x(&step, 1.), 1., epsilon = 1e-9);
+
+    let smooth = KeyframeTrack::new([(0., at(0.)), (1., at(1.))], Interpolation::Smooth);
+    assert_relative_eq!(x(&smooth, 0.5), 0.5, epsilon = 1e-9);
+    assert!(x(&smooth, 0.1) < 0.1);
+}
+
+/// An animated object should be hit wherever it is at the time of the scene, and be bounded over the whole animation
+#[test]
+pub fn animated_object() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let sphere: Obj = SimpleObject::new(SphereMesh::new(Point3::ZERO, 1.), LambertianMaterial::default(), None).into();
+    let track = KeyframeTrack::new(
+        [
+            (0., Transform3::IDENTITY),
+            (10., Transform3::from_translation(Vector3::new(10., 0., 0.))),
+        ],
+        Interpolation::Linear,
+    );
+    let mut object = AnimatedObject::<Obj>::new(sphere, track);
+
+    let aabb = object.aabb().expect("sphere is bounded");
+    assert_relative_eq!(aabb.min().x, -1., epsilon = 1e-9);
+    assert_relative_eq!(aabb.max().x, 11., epsilon = 1e-9);
+
+    let mut hit = |object: &AnimatedObject<Obj>, x: Number| {
+        let ray = Ray::new([x, 0., -5.], Vector3::Z);
+        object
+            .full_intersect(&ray, &interval, rng)
+            .map(|hit| hit.intersection.dist)
+    };
+    assert_relative_eq!(hit(&object, 0.).expect("should hit at start"), 4., epsilon = 1e-9);
+    assert_eq!(hit(&object, 5.), None);
+
+    object.set_time(5.);
+    assert_eq!(object.time(), 5.);
+    assert_eq!(hit(&object, 0.), None);
+    assert_relative_eq!(hit(&object, 5.).expect("should hit halfway"), 4., epsilon = 1e-9);
+}
+
+/// While the shutter is open, an animated object should move along its animation with the time of each ray
+#[test]
+pub fn animated_object_shutter() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let sphere: Obj = SimpleObject::new(SphereMesh::new(Point3::ZERO, 1.), LambertianMaterial::default(), None).into();
+    let track = KeyframeTrack::new(
+        [
+            (0., Transform3::IDENTITY),
+            (10., Transform3::from_translation(Vector3::new(10., 0., 0.))),
+        ],
+        Interpolation::Linear,
+    );
+    let mut object = AnimatedObject::<Obj>::new(sphere, track);
+    object.set_time(5.);
+
+    let mut hit = |object: &AnimatedObject<Obj>, x: Number, time: Number| {
+        let ray = Ray::new([x, 0., -5.], Vector3::Z).with_time(time);
+        object
+            .full_intersect(&ray, &interval, rng)
+            .map(|hit| hit.intersection.dist)
+    };
+
+    // Half of a frame that is 4 long
+    let shutter = Shutter::from_angle(4., Angle::from_degrees(180.));
+    assert_eq!(shutter, Shutter { open: 0., close: 2. });
+    object.set_shutter(shutter);
+    assert_eq!(object.shutter(), shutter);
+    assert_relative_eq!(
+        hit(&object, 5., 0.).expect("should hit when opened"),
+        4.,
+        epsilon = 1e-9
+    );
+    assert_relative_eq!(hit(&object, 6., 0.5).expect("should hit halfway"), 4., epsilon = 1e-9);
+    assert_relative_eq!(
+        hit(&object, 7., 1.).expect("should hit when closed"),
+        4.,
+        epsilon = 1e-9
+    );
+    assert_eq!(hit(&object, 7., 0.), None);
+
+    // Changing the time keeps the shutter
+    object.set_time(0.);
+    assert_relative_eq!(
+        hit(&object, 2., 1.).expect("should hit when closed"),
+        4.,
+        epsilon = 1e-9
+    );
+
+    object.set_shutter(Shutter::INSTANT);
+    assert_eq!(hit(&object, 2., 1.), None);
+}
@@ -0,0 +1,158 @@
+use approx::assert_relative_eq;
+use exr::prelude::{read_all_flat_layers_from_file, FlatSamples, Text};
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::render::aov::{save_aovs_exr, Aov};
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::preset;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+
+mod common;
+
+/// Renders the depth and normal AOVs of a sphere directly in front of the camera, and checks the values at the centre,
+/// then exports all the AOVs to an EXR file
+#[test]
+pub fn sphere_aovs() {
+    let scene = StandardScene {
+        objects: SimpleObject::new_uncorrected(
+            SphereMesh::new(Point3::new(0., 0., 5.), 1.0),
+            LambertianMaterial {
+                albedo: [0.5; 3].into(),
+            },
+            None,
+        )
+        .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera::default();
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+        scene,
+        camera,
+        common::SIMPLE_RENDER_OPTIONS,
+        common::RENDERER_THREAD_COUNT,
+    )
+    .expect("failed creating renderer");
+
+    let [w, h] = common::SIMPLE_RENDER_OPTIONS.dims();
+    let centre = (w / 2, h / 2);
+
+    let depth = renderer.render_aov(Aov::Depth);
+    assert_relative_eq!(depth[centre][0], 4., epsilon = 0.05);
+    // Corners point away from the sphere, into the sky
+    assert_eq!(depth[(0, 0)], Colour::BLACK);
+
+    let normal = renderer.render_aov(Aov::Normal);
+    assert_relative_eq!(normal[centre][2], -1., epsilon = 0.05);
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("aovs.exr");
+    save_aovs_exr(&mut renderer, &[Aov::Beauty, Aov::Normal, Aov::Depth, Aov::Id], &path).expect("failed saving AOVs");
+    assert!(std::fs::metadata(&path).expect("EXR file should exist").len() > 0);
+}
+
+/// Renders all the auxiliary AOVs alongside the beauty image, and checks they match the separately rendered AOVs
+#[test]
+pub fn aovs_in_single_pass() {
+    let scene = StandardScene {
+        objects: SimpleObject::new_uncorrected(
+            SphereMesh::new(Point3::new(0., 0., 5.), 1.0),
+            LambertianMaterial {
+                albedo: [0.25, 0.5, 0.75].into(),
+            },
+            None,
+        )
+        .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera::default();
+    let opts = RenderOpts {
+        aovs: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer");
+
+    let [w, h] = opts.dims();
+    let centre = (w / 2, h / 2);
+
+    let render = renderer.render();
+    assert_eq!(
+        render.aovs.iter().map(|(aov, _)| *aov).collect::<Vec<_>>(),
+        Aov::AUXILIARY
+    );
+    let aov = |wanted: Aov| &render.aovs.iter().find(|(aov, _)| *aov == wanted).unwrap().1;
+
+    assert_relative_eq!(aov(Aov::Depth)[centre][0], 4., epsilon = 0.05);
+    assert_relative_eq!(aov(Aov::Normal)[centre][2], -1., epsilon = 0.05);
+    assert_eq!(aov(Aov::Albedo)[centre], Colour::from([0.25, 0.5, 0.75]));
+    assert_ne!(aov(Aov::MaterialId)[centre], Colour::BLACK);
+    // Corners point away from the sphere, into the sky
+    assert!(render.aovs.iter().all(|(_, img)| img[(0, 0)] == Colour::BLACK));
+
+    // Rendering the AOVs separately should give the same results
+    for (aov, img) in &render.aovs {
+        assert_eq!(img[centre], renderer.render_aov(*aov)[centre], "{aov} differs");
+    }
+
+    // Turning them off again shouldn't render any
+    renderer.set_options(common::SIMPLE_RENDER_OPTIONS);
+    assert!(renderer.render().aovs.is_empty());
+}
+
+/// Saves a render and its AOVs to an EXR file, and reads it back to check the values are kept exactly
+#[test]
+pub fn render_exr_roundtrip() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        aovs: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, 1)
+        .expect("failed creating renderer");
+    let render = renderer.render();
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("render.exr");
+    render.save_exr(&path).expect("failed saving EXR");
+
+    let exr = read_all_flat_layers_from_file(&path).expect("failed reading EXR");
+    let names = exr
+        .layer_data
+        .iter()
+        .map(|layer| layer.attributes.layer_name.as_ref().map(Text::to_string))
+        .collect::<Vec<_>>();
+    assert!(names.contains(&None), "image should be in the main layer");
+    for (aov, _) in &render.aovs {
+        assert!(
+            names.contains(&Some(aov.layer_name().to_string())),
+            "missing layer for {aov}"
+        );
+    }
+
+    // The main layer should have the exact (unclamped) values of the image
+    let main = exr
+        .layer_data
+        .iter()
+        .find(|layer| layer.attributes.layer_name.is_none())
+        .unwrap();
+    let [w, _] = opts.dims();
+    for (c, channel) in ["R", "G", "B"].into_iter().enumerate() {
+        let channel = main
+            .channel_data
+            .list
+            .iter()
+            .find(|ch| ch.name.to_string() == channel)
+            .expect("missing channel");
+        let FlatSamples::F32(samples) = &channel.sample_data else {
+            panic!("samples should be 32-bit floats");
+        };
+        for (i, &sample) in samples.iter().enumerate() {
+            assert_eq!(sample, render.img[(i % w, i / w)][c]);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::aperture::{Aperture, ApertureMask};
+use rayna_engine::scene::camera::Camera;
+use std::f64::consts::PI;
+
+const SAMPLES: usize = 4096;
+/// How far outside of a shape a point in a mask can be, since the mask is made of pixels
+const CELL: Number = 2. / ApertureMask::SIZE as Number;
+
+fn samples(aperture: Aperture) -> Vec<Vector2> {
+    let rng = &mut rand::thread_rng();
+    (0..SAMPLES).map(|_| aperture.sample(rng)).collect()
+}
+
+/// Samples should be inside of the polygon, and reach out into its corners
+#[test]
+pub fn polygon_aperture() {
+    let blades = 6;
+    let rotation = Angle::from_degrees(10.);
+    let samples = samples(Aperture::Polygon { blades, rotation });
+
+    // The distance from the centre to the middle of each side
+    let apothem = (PI / blades as Number).cos();
+    for i in 0..blades {
+        let angle = rotation.radians + (PI / blades as Number) * ((2 * i) + 1) as Number;
+        let normal = Vector2::new(-angle.sin(), angle.cos());
+        assert!(samples.iter().all(|p| p.dot(normal) <= apothem + 1e-9));
+    }
+    let furthest = samples.iter().map(|p| p.length()).fold(0., Number::max);
+    assert!(furthest > apothem, "samples should reach into the corners: {furthest}");
+
+    // Too few blades for a polygon is just a circle
+    let circle = self::samples(Aperture::Polygon { blades: 2, rotation });
+    assert!(circle.iter().all(|p| p.length() <= 1.));
+}
+
+/// Samples should only be where the mask is open
+#[test]
+pub fn mask_aperture() {
+    // A tall oval, like an anamorphic lens
+    let oval = ApertureMask::from_fn(|p| ((p.x * p.x) / 0.25) + (p.y * p.y) <= 1.);
+    let samples = samples(Aperture::Mask(oval));
+    assert!(samples.iter().all(|&p| oval.contains(p)));
+    assert!(samples.iter().all(|p| p.x.abs() <= 0.5 + CELL));
+    assert!(samples.iter().any(|p| p.y.abs() >= 0.9));
+
+    // Only the left half of the image is bright
+    let img = Image::from_fn(16, 16, |x, _| if x < 8 { Colour::WHITE } else { Colour::BLACK });
+    let samples = self::samples(Aperture::Mask(ApertureMask::from_image(&img)));
+    assert!(samples.iter().all(|p| p.x <= CELL));
+
+    // Nothing is open, so all the rays go through the middle
+    let closed = self::samples(Aperture::Mask(ApertureMask::from_fn(|_| false)));
+    assert!(closed.iter().all(|&p| p == Vector2::ZERO));
+}
+
+/// The camera's rays should start from inside of its aperture
+#[test]
+pub fn camera_aperture() {
+    let rng = &mut rand::thread_rng();
+    let camera = Camera {
+        focus_dist: 2.,
+        defocus_angle: Angle::from_degrees(10.),
+        aperture: Aperture::Polygon {
+            blades: 3,
+            rotation: Angle::from_degrees(0.),
+        },
+        ..Default::default()
+    };
+    let viewport = camera.calculate_viewport().expect("camera should be valid");
+    let radius = viewport.defocus_disk_u.length();
+    // A triangle with a corner at the top is never more than half way down
+    for _ in 0..SAMPLES {
+        let ray = viewport.calc_ray(0., 0., 16., 16., rng);
+        let offset = ray.pos() - viewport.pos;
+        assert!(offset.length() <= radius + 1e-9);
+        assert!(
+            offset.y >= -(radius / 2.) - 1e-9,
+            "{offset:?} is outside of the triangle"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use rayna_engine::core::colour::ColourSpace;
+use rayna_engine::core::types::*;
+use rayna_engine::scene::asset::{placeholder_image, AssetError, AssetResolver};
+use rayna_engine::scene::validation::{SceneValidation, SceneWarning};
+use std::path::Path;
+
+/// Saves a small, single-colour PNG at `path` (creating the directories it's in)
+fn save_png(path: impl AsRef<Path>, value: u8) {
+    let path = path.as_ref();
+    std::fs::create_dir_all(path.parent().unwrap()).expect("failed creating directories");
+    image::RgbImage::from_pixel(2, 2, image::Rgb([value; 3]))
+        .save(path)
+        .expect("failed saving PNG");
+}
+
+/// Relative paths should be looked for in the scene's directory first, and then each of the search paths in order,
+/// while absolute paths are used as-is
+#[test]
+pub fn asset_relative_paths() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let (scene, search_a, search_b) = (dir.path().join("scene"), dir.path().join("a"), dir.path().join("b"));
+    save_png(scene.join("textures/both.png"), 0);
+    save_png(search_a.join("textures/both.png"), 0);
+    save_png(search_a.join("only_a.png"), 0);
+    save_png(search_b.join("only_a.png"), 0);
+    save_png(search_b.join("only_b.png"), 0);
+
+    let resolver = AssetResolver::for_scene_file(scene.join("scene.json"))
+        .with_search_path(&search_a)
+        .with_search_path(&search_b);
+    assert_eq!(resolver.base_dir().as_deref(), Some(scene.as_path()));
+
+    let resolve = |path: &str| resolver.resolve(path).expect("failed resolving asset");
+    assert_eq!(resolve("textures/both.png"), scene.join("textures/both.png"));
+    assert_eq!(resolve("only_a.png"), search_a.join("only_a.png"));
+    assert_eq!(resolve("only_b.png"), search_b.join("only_b.png"));
+
+    let absolute = search_b.join("only_b.png");
+    assert_eq!(resolver.resolve(&absolute).expect("failed resolving asset"), absolute);
+    // Moving the whole scene (along with its assets) shouldn't break anything
+    let moved = dir.path().join("moved");
+    std::fs::rename(&scene, &moved).expect("failed moving scene");
+    let resolver = AssetResolver::for_scene_file(moved.join("scene.json"));
+    assert_eq!(
+        resolver.resolve("textures/both.png").expect("failed resolving asset"),
+        moved.join("textures/both.png")
+    );
+}
+
+/// Missing assets should be an error that says where they were looked for, or a placeholder (and a warning) when a
+/// placeholder is asked for
+#[test]
+pub fn asset_missing_files() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let search = dir.path().join("search");
+    let resolver = AssetResolver::new().with_base_dir(dir.path()).with_search_path(&search);
+
+    match resolver.resolve("missing.png") {
+        Err(AssetError::NotFound { path, searched }) => {
+            assert_eq!(path, Path::new("missing.png"));
+            assert!(
+                searched.starts_with(&[dir.path().to_path_buf(), search]),
+                "{searched:?}"
+            );
+        }
+        other => panic!("expected NotFound, got {other:?}"),
+    }
+    let absolute = dir.path().join("missing.png");
+    assert!(matches!(
+        resolver.resolve(&absolute),
+        Err(AssetError::NotFound { searched, .. }) if searched.is_empty()
+    ));
+    // Directories aren't files either
+    std::fs::create_dir(dir.path().join("folder")).expect("failed creating directory");
+    assert!(matches!(resolver.resolve("folder"), Err(AssetError::NotFound { .. })));
+
+    // Files that exist, but aren't images
+    std::fs::write(dir.path().join("broken.png"), "not a png").expect("failed writing file");
+    assert!(matches!(
+        resolver.load_image("broken.png"),
+        Err(AssetError::ImageError { .. })
+    ));
+
+    let mut validation = SceneValidation::new();
+    let img = resolver.load_image_or_placeholder("missing.png", &mut validation);
+    let placeholder = placeholder_image();
+    assert_eq!([img.width(), img.height()], [placeholder.width(), placeholder.height()]);
+    assert!(img.iter().eq(placeholder.iter()));
+    match validation.warnings() {
+        [SceneWarning::MissingAsset { path, .. }] => assert_eq!(path, Path::new("missing.png")),
+        other => panic!("expected a single MissingAsset warning, got {other:?}"),
+    }
+}
+
+/// Loading the same image again should use the cached image instead of loading the file again, which is shared between
+/// clones of the resolver. Each colour space is cached separately
+#[test]
+pub fn asset_caching() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    save_png(dir.path().join("grey.png"), 128);
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+
+    let first = resolver.load_image("grey.png").expect("failed loading image");
+    let cloned = resolver.clone();
+    // Gone from the disk, but still in the cache
+    std::fs::remove_file(dir.path().join("grey.png")).expect("failed removing file");
+    let second = cloned.load_image("grey.png").expect("failed loading cached image");
+    assert_eq!(
+        first.data().as_ptr(),
+        second.data().as_ptr(),
+        "the image should be shared"
+    );
+
+    // Not loaded yet in this colour space, so it has to be loaded from the (now missing) file
+    assert!(matches!(
+        resolver.load_image_as("grey.png", ColourSpace::Linear),
+        Err(AssetError::NotFound { .. })
+    ));
+    save_png(dir.path().join("grey.png"), 128);
+    let linear = resolver
+        .load_image_as("grey.png", ColourSpace::Linear)
+        .expect("failed loading image");
+    assert_ne!(
+        linear[(0, 0)],
+        first[(0, 0)],
+        "the colour spaces should be cached separately"
+    );
+}
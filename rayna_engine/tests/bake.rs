@@ -0,0 +1,126 @@
+use approx::assert_relative_eq;
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::advanced::indexed_triangle::{IndexedTriangleMesh, VertexAttributes, VertexNormals};
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::transform::ObjectTransform;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::bake::{BakeChannel, BakeError, BakeOpts};
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::skybox::SkyboxInstance;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+/// A unit quad on the `XZ` plane facing up, with UVs matching the `x` and `z` coordinates
+fn quad(uvs: bool) -> IndexedTriangleMesh {
+    let vertices = [[0., 0., 0.], [1., 0., 0.], [1., 0., 1.], [0., 0., 1.]].map(Point3::from);
+    let attributes = VertexAttributes {
+        uvs: uvs.then(|| vertices.map(|v| Point2::new(v.x, v.z)).to_vec()),
+        colours: None,
+    };
+    IndexedTriangleMesh::new_with_attributes(vertices, [[0, 3, 1], [1, 3, 2]], VertexNormals::Flat, attributes)
+}
+
+type Obj = ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>;
+
+/// A renderer for a quad with a small sphere floating above its centre (both placed by the `transform`), in a white sky
+fn new_renderer(transform: ObjectTransform) -> Renderer<Obj, SkyboxInstance, common::Rng> {
+    let material: MaterialInstance<TextureInstance> = LambertianMaterial::default().into();
+    let objects: [MeshInstance; 2] = [quad(true).into(), SphereMesh::new((0.5, 0.25, 0.5), 0.15).into()];
+    let scene = StandardScene {
+        objects: objects
+            .map(|mesh| Obj::from(SimpleObject::new_uncorrected(mesh, material.clone(), transform)))
+            .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    Renderer::new_from(
+        scene,
+        Camera::default(),
+        common::SIMPLE_RENDER_OPTIONS,
+        common::RENDERER_THREAD_COUNT,
+    )
+    .expect("failed creating renderer")
+}
+
+const BAKE_OPTS: BakeOpts = BakeOpts {
+    width: nonzero!(8_usize),
+    height: nonzero!(8_usize),
+    samples: nonzero!(256_usize),
+    ao_distance: 0.3,
+};
+
+/// Bakes the quad, checking it's in the right place and occluded by the sphere
+#[test]
+pub fn bake_quad() {
+    let mut renderer = new_renderer(ObjectTransform::IDENTITY);
+    let opts = BAKE_OPTS;
+
+    // Bottom-left texel is near the origin (`v` is flipped)
+    let position = renderer
+        .bake(&quad(true), None, BakeChannel::Position, &opts)
+        .expect("quad has UVs");
+    assert_relative_eq!(position[(0, 7)][0], 0.0625, epsilon = 1e-6);
+    assert_relative_eq!(position[(0, 7)][2], 0.0625, epsilon = 1e-6);
+
+    let normal = renderer
+        .bake(&quad(true), None, BakeChannel::Normal, &opts)
+        .expect("quad has UVs");
+    assert_relative_eq!(normal[(3, 3)][1], 1.);
+
+    // Only the texels under the sphere should be occluded (the corners are too far away)
+    let ao = renderer
+        .bake(&quad(true), None, BakeChannel::AmbientOcclusion, &opts)
+        .expect("quad has UVs");
+    assert_eq!(ao[(0, 0)], Colour::WHITE);
+    assert!(ao[(4, 4)][0] < 0.9, "centre should be occluded: {:?}", ao[(4, 4)]);
+
+    // Light from a white sky is white, as long as nothing is in the way
+    let lighting = renderer
+        .bake(&quad(true), None, BakeChannel::Lighting, &opts)
+        .expect("quad has UVs");
+    assert_relative_eq!(lighting[(0, 0)][0], 1., epsilon = 0.05);
+
+    assert!(matches!(
+        renderer.bake(&quad(false), None, BakeChannel::Position, &opts),
+        Err(BakeError::MissingUvs)
+    ));
+}
+
+/// Transformed meshes should be baked where the transform puts them, with their normals rotated to match
+#[test]
+pub fn bake_transformed_quad() {
+    // Stood up to face along `+Z` instead of `+Y`, and moved along `X`
+    let transform = ObjectTransform::new(
+        Transform3::from_axis_angle(Vector3::X, Angle::from_degrees(90.)).then_translate(Vector3::new(10., 0., 0.)),
+    );
+    let mut renderer = new_renderer(transform);
+    let opts = BAKE_OPTS;
+
+    let position = renderer
+        .bake(&quad(true), transform, BakeChannel::Position, &opts)
+        .expect("quad has UVs");
+    assert_relative_eq!(position[(0, 7)][0], 10.0625, epsilon = 1e-6);
+    assert_relative_eq!(position[(0, 7)][1], -0.0625, epsilon = 1e-6);
+    assert_relative_eq!(position[(0, 7)][2], 0., epsilon = 1e-6);
+
+    let normal = renderer
+        .bake(&quad(true), transform, BakeChannel::Normal, &opts)
+        .expect("quad has UVs");
+    assert_relative_eq!(normal[(3, 3)][1], 0., epsilon = 1e-6);
+    assert_relative_eq!(normal[(3, 3)][2], 1., epsilon = 1e-6);
+
+    // The sphere moved along with the quad, so it should still be over the centre
+    let ao = renderer
+        .bake(&quad(true), transform, BakeChannel::AmbientOcclusion, &opts)
+        .expect("quad has UVs");
+    assert_eq!(ao[(0, 0)], Colour::WHITE);
+    assert!(ao[(4, 4)][0] < 0.9, "centre should be occluded: {:?}", ao[(4, 4)]);
+}
@@ -0,0 +1,102 @@
+use rand::rngs::mock::StepRng;
+use rayna_engine::core::types::*;
+use rayna_engine::texture::brick::{BrickPattern, UvBrickTexture, WorldBrickTexture};
+use rayna_engine::texture::solid::SolidTexture;
+use rayna_engine::texture::Texture;
+
+mod common;
+
+/// Bricks that are 2 by 1 (2.2 by 1.2 with the mortar)
+fn pattern() -> BrickPattern {
+    BrickPattern {
+        brick_size: Vector2::new(2., 1.),
+        mortar_width: 0.2,
+        row_offset: 0.5,
+        colour_jitter: 0.,
+        seed: 0,
+    }
+}
+
+/// Points should be found in the right bricks, with every other row shifted along
+#[test]
+pub fn brick_layout() {
+    let pattern = pattern();
+    assert_eq!(pattern.locate([1.1, 0.6]), Some([0, 0]));
+    assert_eq!(pattern.locate([3.3, 0.6]), Some([1, 0]));
+    assert_eq!(pattern.locate([-1.5, -0.5]), Some([-1, -1]));
+    // In the mortar between the bricks, and between the rows
+    assert_eq!(pattern.locate([0.05, 0.6]), None);
+    assert_eq!(pattern.locate([1.1, 1.15]), None);
+    // The second row is shifted along by half a brick
+    assert_eq!(pattern.locate([1.1, 1.8]), None);
+    assert_eq!(pattern.locate([2.2, 1.8]), Some([0, 1]));
+
+    // Tiles line up
+    let tiles = BrickPattern {
+        row_offset: 0.,
+        ..pattern
+    };
+    assert_eq!(tiles.locate([1.1, 1.8]), Some([0, 1]));
+
+    // Blocks in 3D are as deep as they are wide, and shift along both horizontal axes
+    assert_eq!(pattern.locate([1.1, 0.6, 1.1]), Some([0, 0, 0]));
+    assert_eq!(pattern.locate([2.2, 1.8, 2.2]), Some([0, 1, 0]));
+    assert_eq!(pattern.locate([1.1, 0.6, 2.25]), None);
+}
+
+/// Each brick should have its own brightness, within the jitter
+#[test]
+pub fn brick_jitter() {
+    let jittered = BrickPattern {
+        colour_jitter: 0.3,
+        seed: 5,
+        ..pattern()
+    };
+    let brightness = (0..20).map(|i| jittered.brick_brightness([i, 0])).collect::<Vec<_>>();
+    assert!(brightness.iter().all(|b| (0.7..=1.3).contains(b)));
+    assert!(brightness.windows(2).any(|w| w[0] != w[1]));
+    assert_eq!(jittered.brick_brightness([3, 0]), brightness[3]);
+    assert_eq!(pattern().brick_brightness([3, 0]), 1.);
+}
+
+/// The textures should show the brick texture on the bricks and the mortar texture between them
+#[test]
+pub fn brick_textures() {
+    let brick = SolidTexture::from(Colour::WHITE);
+    let mortar = SolidTexture::from(Colour::BLACK);
+    let rng = &mut StepRng::new(0, 1);
+
+    let uv = UvBrickTexture {
+        pattern: pattern(),
+        brick,
+        mortar,
+    };
+    assert_eq!(
+        uv.value(
+            &common::intersection(Point3::ZERO, Vector3::Z, Point2::new(1.1, 0.6)),
+            rng
+        ),
+        Colour::WHITE
+    );
+    assert_eq!(
+        uv.value(
+            &common::intersection(Point3::ZERO, Vector3::Z, Point2::new(0.05, 0.6)),
+            rng
+        ),
+        Colour::BLACK
+    );
+
+    let world = WorldBrickTexture {
+        pattern: pattern(),
+        brick,
+        mortar,
+    };
+    let at = |pos: Point3| {
+        world.value(
+            &common::intersection(pos, Vector3::Z, Point2::ZERO),
+            &mut StepRng::new(0, 1),
+        )
+    };
+    assert_eq!(at(Point3::new(1.1, 0.6, 1.1)), Colour::WHITE);
+    assert_eq!(at(Point3::new(1.1, 1.15, 1.1)), Colour::BLACK);
+}
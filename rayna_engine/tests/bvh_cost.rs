@@ -0,0 +1,68 @@
+use rand::{Rng, SeedableRng};
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::bvh::BvhObject;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::{Object, ObjectInstance};
+use rayna_engine::shared::bvh_cost;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::texture::TextureInstance;
+
+type Mat = MaterialInstance<TextureInstance>;
+type Obj = ObjectInstance<MeshInstance, Mat>;
+
+/// Tracing a ray through a BVH should count the nodes it visits, and only test a few of the objects in it
+#[test]
+pub fn bvh_cost_counts_traversal() {
+    let rng = &mut rand::rngs::SmallRng::seed_from_u64(0);
+    let material: Mat = LambertianMaterial::default().into();
+    let objects = (0..200)
+        .map(|_| {
+            let centre = Point3::new(
+                rng.gen_range(-10.0..10.),
+                rng.gen_range(-10.0..10.),
+                rng.gen_range(5.0..30.),
+            );
+            SimpleObject::new(SphereMesh::new(centre, 0.5), material.clone(), None).into()
+        })
+        .collect::<Vec<Obj>>();
+    let bvh = BvhObject::<Obj>::new_uncorrected(objects, None);
+    let interval = Interval::from(1e-3..);
+    bvh_cost::set_enabled(true);
+
+    // Pointing away from everything, so only the root is visited
+    bvh_cost::take();
+    assert!(bvh
+        .full_intersect(&Ray::new(Point3::ZERO, Vector3::new(0., 0., -1.)), &interval, rng)
+        .is_none());
+    assert_eq!(
+        bvh_cost::take(),
+        bvh_cost::BvhCost {
+            nodes: 1,
+            primitives: 0
+        }
+    );
+
+    let mut hits = 0;
+    for _ in 0..100 {
+        let ray = Ray::new(
+            Point3::ZERO,
+            Vector3::new(rng.gen_range(-0.3..0.3), rng.gen_range(-0.3..0.3), 1.),
+        );
+        bvh_cost::take();
+        let hit = bvh.full_intersect(&ray, &interval, rng);
+        let cost = bvh_cost::take();
+
+        assert!(cost.nodes > 1, "{ray:?} only visited {cost:?}");
+        assert!(cost.primitives < 200, "{ray:?} tested every object");
+        if hit.is_some() {
+            hits += 1;
+            assert!(cost.primitives >= 1, "{ray:?} hit without testing any objects");
+        }
+    }
+    assert!(hits > 0, "some of the rays should hit the spheres");
+}
@@ -0,0 +1,116 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::camera::{Camera, PhysicalCamera};
+
+const EPSILON: Number = 1e-9;
+
+fn assert_near(a: Vector3, b: Vector3) {
+    assert!((a - b).length() < EPSILON, "{a:?} != {b:?}");
+}
+
+/// A camera looking at a point should see it in the middle of the image, in focus
+#[test]
+pub fn look_at_target() {
+    let [w, h] = [40., 30.];
+    let (pos, target) = (Point3::new(1., 2., 3.), Point3::new(-4., 0.5, 7.));
+    let camera = Camera::look_at(pos, target, Vector3::Y).expect("camera should be valid");
+    assert_near(camera.fwd, (target - pos).normalize());
+    assert!((camera.focus_dist - (target - pos).length()).abs() < EPSILON);
+
+    let viewport = camera.calculate_viewport().expect("camera should be valid");
+    let (x, y) = viewport.project(target, w, h).expect("target should be in view");
+    assert!((x - w / 2.).abs() < EPSILON && (y - h / 2.).abs() < EPSILON);
+
+    assert!(Camera::look_at(pos, pos, Vector3::Y).is_err(), "can't look at itself");
+    assert!(
+        Camera::look_at(pos, pos + Vector3::Y, Vector3::Y).is_err(),
+        "up can't be parallel"
+    );
+    assert!(Camera::look_at(pos, target, Vector3::ZERO).is_err());
+}
+
+/// The `up` of the camera should be the top of the image
+#[test]
+pub fn look_at_up() {
+    let [w, h] = [40., 30.];
+    let camera = Camera::look_at(Point3::ZERO, Point3::new(0., 0., 5.), Vector3::X).expect("camera should be valid");
+    let viewport = camera.calculate_viewport().expect("camera should be valid");
+    let (_, y) = viewport
+        .project(Point3::new(1., 0., 5.), w, h)
+        .expect("point should be in view");
+    assert!(y < h / 2., "the up direction should be towards the top of the image");
+}
+
+/// Orbiting should keep the camera at the radius, looking at the target
+#[test]
+pub fn orbit() {
+    let target = Point3::new(0., 1., -2.);
+    let at = |yaw: Number, pitch: Number| {
+        Camera::orbit(target, 3., Angle::from_degrees(yaw), Angle::from_degrees(pitch)).expect("camera should be valid")
+    };
+    for (yaw, pitch) in [(0., 0.), (90., 0.), (200., 30.), (-45., -60.)] {
+        let camera = at(yaw, pitch);
+        assert!(((camera.pos - target).length() - 3.).abs() < EPSILON);
+        assert_near(
+            (camera.pos + (camera.fwd * camera.focus_dist)).to_vector(),
+            target.to_vector(),
+        );
+    }
+    assert_near(
+        at(0., 0.).pos.to_vector(),
+        (target + Vector3::new(0., 0., 3.)).to_vector(),
+    );
+    assert_near(
+        at(90., 0.).pos.to_vector(),
+        (target + Vector3::new(3., 0., 0.)).to_vector(),
+    );
+    assert!(at(0., 45.).pos.y > target.y, "pitching up should move the camera up");
+}
+
+/// Dollying moves the camera but keeps the same point in focus
+#[test]
+pub fn dolly() {
+    let target = Point3::new(0., 0., 10.);
+    let mut camera = Camera::look_at(Point3::ZERO, target, Vector3::Y).expect("camera should be valid");
+    camera.dolly(4.).expect("dolly should be valid");
+    assert_near(camera.pos.to_vector(), Vector3::new(0., 0., 4.));
+    assert_near(
+        (camera.pos + (camera.fwd * camera.focus_dist)).to_vector(),
+        target.to_vector(),
+    );
+
+    camera.dolly(-2.).expect("dolly should be valid");
+    assert!((camera.focus_dist - 8.).abs() < EPSILON);
+    assert!(camera.dolly(8.).is_err(), "can't dolly past the focus");
+    assert!(
+        (camera.focus_dist - 8.).abs() < EPSILON,
+        "failing shouldn't change the camera"
+    );
+}
+
+/// Zooming in by a factor should make things that factor larger in the image
+#[test]
+pub fn zoom() {
+    let [w, h] = [40., 30.];
+    let point = Point3::new(0.2, 0.1, 5.);
+    let offset = |camera: &Camera| {
+        let viewport = camera.calculate_viewport().expect("camera should be valid");
+        let (x, y) = viewport.project(point, w, h).expect("point should be in view");
+        Vector2::new(x - w / 2., y - h / 2.)
+    };
+
+    for physical in [None, Some(PhysicalCamera::default())] {
+        let mut camera = Camera {
+            physical,
+            ..Default::default()
+        };
+        let before = offset(&camera);
+        camera.zoom(2.5).expect("zoom should be valid");
+        let after = offset(&camera);
+        assert!(
+            (after - (before * 2.5)).length() < 1e-6,
+            "{before:?} * 2.5 != {after:?}"
+        );
+        assert!(camera.zoom(0.).is_err());
+        assert!(camera.zoom(-1.).is_err());
+    }
+}
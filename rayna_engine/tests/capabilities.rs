@@ -0,0 +1,16 @@
+use rayna_engine::core::capabilities::Backend;
+use rayna_engine::render::render_opts::RenderMode;
+
+/// The reported capabilities should match the engine's actual types and enums
+#[test]
+pub fn capabilities_match_build() {
+    let caps = rayna_engine::capabilities();
+
+    assert_eq!(caps.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(caps.number_bits, 64);
+    assert_eq!(caps.channel_bits, 32);
+    assert_eq!(caps.simd_lanes * caps.number_bits, caps.simd_width);
+    assert!(caps.backends.contains(&Backend::Cpu));
+    assert!(caps.render_modes.contains(&RenderMode::PBR));
+    assert_eq!(caps.validation, cfg!(debug_assertions));
+}
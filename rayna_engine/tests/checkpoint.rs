@@ -0,0 +1,202 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::advanced::indexed_triangle::{IndexedTriangleMesh, VertexAttributes, VertexNormals};
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::checkpoint::CheckpointError;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::preset;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::skybox::SkyboxInstance;
+use rayna_engine::texture::image::ImageTexture;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+/// Resuming from a checkpoint should give exactly the same render as if the renderer had never stopped
+#[test]
+pub fn resume_matches_uninterrupted() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(64_usize),
+        height: nonzero!(64_usize),
+        deterministic: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let new_renderer = |opts: RenderOpts| {
+        Renderer::<_, _, common::Rng>::new_from(
+            preset.scene.clone(),
+            preset.camera,
+            opts,
+            common::RENDERER_THREAD_COUNT,
+        )
+        .expect("failed creating renderer")
+    };
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("render.checkpoint");
+
+    let mut uninterrupted = new_renderer(opts);
+    for _ in 0..3 {
+        uninterrupted.render();
+    }
+    let expected = uninterrupted.render();
+
+    let mut first = new_renderer(opts);
+    for _ in 0..3 {
+        first.render();
+    }
+    first.save_checkpoint(&path).expect("failed saving checkpoint");
+
+    let mut resumed = new_renderer(opts);
+    resumed.resume_from(&path).expect("failed resuming from checkpoint");
+    let render = resumed.render();
+    assert_eq!(render.stats.accum_frames, expected.stats.accum_frames);
+    assert_eq!(render.stats.accum_samples, expected.stats.accum_samples);
+    assert!(
+        render.img.iter().eq(expected.img.iter()),
+        "resumed render should be identical"
+    );
+
+    // The post-processing can be changed, but the samples can't be used with a different camera
+    let mut exposed = new_renderer(RenderOpts { exposure: 1., ..opts });
+    assert!(exposed.resume_from(&path).is_ok());
+    let mut moved = new_renderer(opts);
+    let mut camera = preset.camera;
+    camera.focus_dist *= 2.;
+    moved.set_camera(camera);
+    assert!(matches!(
+        moved.resume_from(&path),
+        Err(CheckpointError::SettingsMismatch { .. })
+    ));
+
+    // Nor with an edited scene, or the same scene at a different time
+    let mut edited = new_renderer(opts);
+    let mut scene = preset.scene.clone();
+    scene.skybox = SkyboxInstance::from(None);
+    edited.set_scene(scene);
+    assert!(matches!(
+        edited.resume_from(&path),
+        Err(CheckpointError::SceneMismatch { .. })
+    ));
+    let mut later = new_renderer(opts);
+    later.set_time(1.);
+    assert!(matches!(
+        later.resume_from(&path),
+        Err(CheckpointError::SceneMismatch { .. })
+    ));
+}
+
+type Obj = ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>;
+
+/// A textured triangle, with the given pixels and UVs
+fn textured_scene(pixels: [Colour; 4], uvs: [[Number; 2]; 3]) -> StandardScene {
+    let vertices = [[-1., -1., 0.], [1., -1., 0.], [0., 1., 0.]].map(Point3::from);
+    let attributes = VertexAttributes {
+        uvs: Some(uvs.map(Point2::from).to_vec()),
+        colours: None,
+    };
+    let mesh = IndexedTriangleMesh::new_with_attributes(vertices, [[0, 1, 2]], VertexNormals::Flat, attributes);
+    let texture = ImageTexture::from(Image::from_fn(2, 2, |x, y| pixels[x + (y * 2)]));
+    let material: MaterialInstance<TextureInstance> = LambertianMaterial {
+        albedo: TextureInstance::from(texture),
+    }
+    .into();
+    let objects: Vec<Obj> = vec![SimpleObject::new_uncorrected(mesh, material, None).into()];
+    StandardScene {
+        objects: objects.into(),
+        skybox: WhiteSkybox.into(),
+    }
+}
+
+/// The scene's assets should be part of its hash, so that changing only the pixels of a texture, or the UVs of a mesh,
+/// stops a checkpoint from being resumed
+#[test]
+pub fn scene_assets_are_hashed() {
+    let (pixels, uvs) = (
+        [Colour::WHITE, Colour::BLACK, Colour::BLACK, Colour::WHITE],
+        [[0., 0.], [1., 0.], [0.5, 1.]],
+    );
+    let camera = Camera {
+        pos: Point3::new(0., 0., -3.),
+        fwd: Vector3::Z,
+        focus_dist: 3.,
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(16_usize),
+        height: nonzero!(16_usize),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let new_renderer = |scene| {
+        Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer")
+    };
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("render.checkpoint");
+
+    let mut renderer = new_renderer(textured_scene(pixels, uvs));
+    renderer.render();
+    renderer.save_checkpoint(&path).expect("failed saving checkpoint");
+
+    // An identical copy of the scene is fine
+    assert!(new_renderer(textured_scene(pixels, uvs)).resume_from(&path).is_ok());
+
+    let mut repainted = pixels;
+    repainted[1] = Colour::WHITE;
+    assert!(matches!(
+        new_renderer(textured_scene(repainted, uvs)).resume_from(&path),
+        Err(CheckpointError::SceneMismatch { .. })
+    ));
+    let mut remapped = uvs;
+    remapped[2] = [0.5, 0.5];
+    assert!(matches!(
+        new_renderer(textured_scene(pixels, remapped)).resume_from(&path),
+        Err(CheckpointError::SceneMismatch { .. })
+    ));
+}
+
+/// Checkpoints with a corrupted buffer size, or that are cut short, should be rejected without allocating the buffer
+#[test]
+pub fn corrupt_checkpoint_is_invalid() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(16_usize),
+        height: nonzero!(16_usize),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("render.checkpoint");
+    renderer.render();
+    renderer.save_checkpoint(&path).expect("failed saving checkpoint");
+    let saved = std::fs::read(&path).expect("failed reading checkpoint");
+
+    // The header (magic, version and hashes), then the frame and sample counters, then the width and height
+    let size_offset = 8 + 4 + 8 + 8 + 8 + 8;
+    for (w, h) in [(u64::MAX, u64::MAX), (1 << 40, 1 << 40), (16, 17), (0, 16)] {
+        let mut corrupt = saved.clone();
+        corrupt[size_offset..size_offset + 8].copy_from_slice(&w.to_le_bytes());
+        corrupt[size_offset + 8..size_offset + 16].copy_from_slice(&h.to_le_bytes());
+        std::fs::write(&path, corrupt).expect("failed writing checkpoint");
+        assert!(
+            matches!(renderer.resume_from(&path), Err(CheckpointError::InvalidFile { .. })),
+            "size {w}x{h} should be invalid"
+        );
+    }
+
+    std::fs::write(&path, &saved[..saved.len() - 1]).expect("failed writing checkpoint");
+    assert!(matches!(
+        renderer.resume_from(&path),
+        Err(CheckpointError::InvalidFile { .. })
+    ));
+    std::fs::write(&path, &saved).expect("failed writing checkpoint");
+    assert!(renderer.resume_from(&path).is_ok());
+}
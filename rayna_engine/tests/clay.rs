@@ -0,0 +1,90 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::clay::Shading;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::light::LightMaterial;
+use rayna_engine::material::shadow_catcher::ShadowCatcherMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+type Mat = MaterialInstance<TextureInstance>;
+
+/// Renders a single sphere with the given material, filling the middle of the image, and returns the colour of the
+/// middle pixel
+fn render_sphere(material: Mat, clay: bool) -> Colour {
+    let scene = StandardScene {
+        objects: [SimpleObject::new_uncorrected(
+            SphereMesh::new(Point3::ZERO, 1.),
+            material,
+            None,
+        )]
+        .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera {
+        pos: Point3::new(0., 0., -5.),
+        focus_dist: 5.,
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(32_usize),
+        height: nonzero!(32_usize),
+        samples: nonzero!(4_usize),
+        clay,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer");
+    renderer.render().img[(16, 16)]
+}
+
+/// Clay renders should replace the colour of ordinary materials with plain grey, but leave lights alone
+#[test]
+pub fn clay_replaces_materials() {
+    let red: Mat = LambertianMaterial {
+        albedo: [0.8, 0.1, 0.1].into(),
+    }
+    .into();
+    let [r, g, b] = render_sphere(red.clone(), false).0;
+    assert!(r > g && r > b, "the sphere should be red normally: {:?}", [r, g, b]);
+    // Under a white sky, a grey sphere can only come out grey
+    let [r, g, b] = render_sphere(red, true).0;
+    assert!(r > 0., "the clay sphere should still be lit");
+    assert!(r == g && g == b, "the clay sphere should be grey: {:?}", [r, g, b]);
+
+    let light: Mat = LightMaterial {
+        emissive: [2., 0., 0.].into(),
+        group: None,
+    }
+    .into();
+    assert_eq!(render_sphere(light.clone(), true), render_sphere(light, false));
+}
+
+/// Lights and shadow catchers should always keep their own materials
+#[test]
+pub fn clay_keeps_lights_and_catchers() {
+    let diffuse: Mat = LambertianMaterial {
+        albedo: [0.8; 3].into(),
+    }
+    .into();
+    let light: Mat = LightMaterial {
+        emissive: [1.; 3].into(),
+        group: None,
+    }
+    .into();
+    let catcher: Mat = ShadowCatcherMaterial::default().into();
+
+    assert!(matches!(Shading::new(&diffuse, false), Shading::Original(_)));
+    assert!(matches!(Shading::new(&diffuse, true), Shading::Clay(_)));
+    assert!(matches!(Shading::new(&light, true), Shading::Original(_)));
+    assert!(matches!(Shading::new(&catcher, true), Shading::Original(_)));
+}
@@ -1,11 +1,19 @@
+// Not every test uses every helper
+#![allow(dead_code)]
+
 use nonzero::nonzero;
 use rayna_engine::core::types::*;
 use rayna_engine::object::Object;
 use rayna_engine::render::{
+    filter::PixelFilter,
+    postprocess::{tone_mapping::ToneMapping, white_balance::WhiteBalance},
     render_opts::{RenderMode, RenderOpts},
     renderer::Renderer,
 };
 use rayna_engine::scene::{camera::Camera, Scene};
+use rayna_engine::shared::intersect::Intersection;
+use rayna_engine::shared::intersect_arena;
+use rayna_engine::shared::rng::SamplerKind;
 use rayna_engine::skybox::Skybox;
 
 pub type Rng = rand::rngs::SmallRng;
@@ -14,9 +22,28 @@ pub const SIMPLE_RENDER_OPTIONS: RenderOpts = RenderOpts {
     width: nonzero!(320_usize),
     height: nonzero!(320_usize),
     samples: nonzero!(10_usize),
+    target_samples: None,
+    max_time: None,
+    progressive_preview: false,
+    temporal_reprojection: false,
+    sampler: SamplerKind::Random,
+    filter: PixelFilter::Box,
     mode: RenderMode::PBR,
+    clay: false,
     ray_depth: 5,
     ray_branching: nonzero!(1_usize),
+    robust_intersections: false,
+    bvh_stats: false,
+    intersection_capacity: intersect_arena::DEFAULT_CAPACITY,
+    deterministic: false,
+    aovs: false,
+    variance: false,
+    transparent_sky: false,
+    auto_exposure: None,
+    exposure: 0.,
+    white_balance: WhiteBalance::NEUTRAL,
+    denoise: None,
+    tone_mapping: ToneMapping::Linear,
 };
 
 pub const RENDERER_THREAD_COUNT: usize = 4;
@@ -27,3 +54,21 @@ pub fn render_simple<Obj: Object, Sky: Skybox>(scene: Scene<Obj, Sky>, camera: C
         .expect("failed creating renderer");
     rend.render().img
 }
+
+/// A hit at `pos` (the same in world and local space) on a surface facing along `normal`, for testing textures and
+/// materials without having to intersect a mesh. Anything else (e.g. the `tangent`) can be set with struct update
+/// syntax
+pub fn intersection(pos: Point3, normal: Vector3, uv: Point2) -> Intersection {
+    Intersection {
+        pos_w: pos,
+        pos_l: pos,
+        normal,
+        ray_normal: normal,
+        front_face: true,
+        dist: 1.,
+        uv,
+        side: 0,
+        colour: None,
+        tangent: None,
+    }
+}
@@ -0,0 +1,79 @@
+use approx::assert_relative_eq;
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::render::compare::{compare_images, compare_renders, flip_colour_error, CompareError};
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+
+mod common;
+
+/// Comparing an image with itself should give no error at all
+#[test]
+pub fn compare_identical_images() {
+    let img = Image::from_fn(16, 16, |x, y| {
+        Colour::from([x as Channel / 16., y as Channel / 16., 0.5])
+    });
+    let cmp = compare_images(&img, &img).expect("images have the same dimensions");
+
+    assert_relative_eq!(cmp.metrics.rmse, 0.);
+    assert_relative_eq!(cmp.metrics.mean_flip, 0.);
+    assert_relative_eq!(cmp.metrics.max_flip, 0.);
+    assert!(cmp.difference.iter().all(|&c| c == Colour::BLACK));
+}
+
+/// Black and white are as different as it gets (for RMSE), and very noticeable (for FLIP)
+#[test]
+pub fn compare_black_white_images() {
+    let black = Image::new_filled(8, 8, Colour::BLACK);
+    let white = Image::new_filled(8, 8, Colour::WHITE);
+    let cmp = compare_images(&black, &white).expect("images have the same dimensions");
+
+    assert_relative_eq!(cmp.metrics.rmse, 1.);
+    assert_relative_eq!(cmp.metrics.mean_flip, cmp.metrics.max_flip);
+    assert!(cmp.metrics.max_flip > 0.9, "max flip: {}", cmp.metrics.max_flip);
+}
+
+/// FLIP should increase as the colours get further apart, and be largest for green vs blue
+#[test]
+pub fn flip_error_ordering() {
+    let grey = |v: Channel| Colour::from([v; 3]);
+    let small = flip_colour_error(grey(0.5), grey(0.52));
+    let large = flip_colour_error(grey(0.5), grey(0.8));
+    let max = flip_colour_error(Colour::GREEN, Colour::BLUE);
+
+    assert!(small < large, "small: {small}, large: {large}");
+    assert!(large < max, "large: {large}, max: {max}");
+    assert_relative_eq!(max, 1.);
+}
+
+#[test]
+pub fn compare_mismatched_dimensions() {
+    let a = Image::new_blank(8, 8);
+    let b = Image::new_blank(8, 9);
+    assert!(matches!(
+        compare_images(&a, &b),
+        Err(CompareError::DimensionMismatch { .. })
+    ));
+}
+
+/// The renders being compared should be full passes, even with a single frame and previews enabled
+#[test]
+pub fn compare_renders_skips_previews() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(32_usize),
+        height: nonzero!(32_usize),
+        progressive_preview: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+
+    let cmp = compare_renders(&renderer, opts, opts, nonzero!(1_usize)).expect("options have the same dimensions");
+    for render in [&cmp.a, &cmp.b] {
+        assert_eq!(render.stats.preview_scale, None);
+        assert_eq!(render.stats.accum_frames, 1);
+    }
+}
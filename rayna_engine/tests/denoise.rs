@@ -0,0 +1,76 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::render::postprocess::denoise::{denoise, Denoise, DenoiseGuides};
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use rayna_engine::shared::rng;
+
+mod common;
+
+/// The mean and variance of the first channel of the pixels in the columns `xs`
+fn stats(img: &Image, xs: std::ops::Range<usize>) -> (Channel, Channel) {
+    let values = xs
+        .flat_map(|x| (0..img.height()).map(move |y| (x, y)))
+        .map(|px| img[px][0])
+        .collect::<Vec<_>>();
+    let mean = values.iter().sum::<Channel>() / values.len() as Channel;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<Channel>() / values.len() as Channel;
+    (mean, variance)
+}
+
+/// Denoises an image with two noisy, flat halves that face different ways. The noise should be smoothed away,
+/// without blurring the two halves into each other
+#[test]
+pub fn denoise_keeps_edges() {
+    let (w, h) = (64, 32);
+    let left = |x: usize| x < w / 2;
+    let noise = |x: usize, y: usize| (rng::hash_seed([x as u64, y as u64]) >> 40) as Channel / (1 << 24) as Channel;
+
+    let img = Image::from_fn(w, h, |x, y| {
+        let base = if left(x) { 0.2 } else { 0.8 };
+        Colour::from([base + (noise(x, y) - 0.5) * 0.2; 3])
+    });
+    let normal = Image::from_fn(w, h, |x, _| {
+        Colour::from(if left(x) { [1., 0., 0.] } else { [0., 1., 0.] })
+    });
+    let depth = Image::from_fn(w, h, |_, _| Colour::from([1.; 3]));
+    let albedo = Image::from_fn(w, h, |_, _| Colour::from([0.5; 3]));
+    let guides = DenoiseGuides {
+        normal: &normal,
+        depth: &depth,
+        albedo: &albedo,
+    };
+
+    let denoised = denoise(&img, &guides, &Denoise::default());
+
+    for (xs, expected) in [(0..w / 2, 0.2), (w / 2..w, 0.8)] {
+        let (noisy_mean, noisy_variance) = stats(&img, xs.clone());
+        let (mean, variance) = stats(&denoised, xs);
+        assert_relative_eq!(mean, noisy_mean, epsilon = 0.01);
+        assert_relative_eq!(mean, expected, epsilon = 0.02);
+        assert!(
+            variance < noisy_variance / 10.,
+            "noise wasn't removed: {variance} vs {noisy_variance}"
+        );
+    }
+    // The pixels right next to the edge shouldn't have been blended across it
+    assert_relative_eq!(denoised[(w / 2 - 1, h / 2)][0], 0.2, epsilon = 0.05);
+    assert_relative_eq!(denoised[(w / 2, h / 2)][0], 0.8, epsilon = 0.05);
+}
+
+/// Denoising in the renderer should render the guides, but not return them unless the AOVs are enabled
+#[test]
+pub fn denoise_render() {
+    let opts = RenderOpts {
+        denoise: Some(Denoise::default()),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    let render = renderer.render();
+    assert!(render.aovs.is_empty());
+    assert!(render.img.iter().all(|px| px.iter().all(|c| c.is_finite())));
+}
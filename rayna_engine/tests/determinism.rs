@@ -0,0 +1,29 @@
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+
+mod common;
+
+/// Deterministic renders should be bit-identical, no matter how many threads are used or how many times the scene
+/// is rendered (with the same frame number)
+#[test]
+pub fn deterministic_across_thread_counts() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        deterministic: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let render = |threads: usize| {
+        let mut renderer = Renderer::<_, _, common::Rng>::new_from(preset.scene.clone(), preset.camera, opts, threads)
+            .expect("failed creating renderer");
+        // Two frames, so accumulation is included
+        renderer.render();
+        renderer.render().img
+    };
+
+    let single = render(1);
+    let multi = render(common::RENDERER_THREAD_COUNT);
+    assert!(single.iter().eq(multi.iter()), "renders should be identical");
+    let again = render(1);
+    assert!(single.iter().eq(again.iter()), "renders should be repeatable");
+}
@@ -0,0 +1,272 @@
+use nonzero::nonzero;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::checkpoint::settings_hash;
+use rayna_engine::render::distributed::DistributedError;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use rayna_engine::skybox::SkyboxInstance;
+use rayna_engine::texture::TextureInstance;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+mod common;
+
+type Obj = ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>;
+
+fn new_renderer(opts: RenderOpts) -> Renderer<Obj, SkyboxInstance, common::Rng> {
+    let preset = preset::RTIAW_DEMO();
+    Renderer::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer")
+}
+
+const OPTS: RenderOpts = RenderOpts {
+    width: nonzero!(64_usize),
+    height: nonzero!(40_usize),
+    samples: nonzero!(2_usize),
+    deterministic: true,
+    ..common::SIMPLE_RENDER_OPTIONS
+};
+
+/// When rendering deterministically, the tiles rendered by a worker should be identical to the ones rendered locally
+#[test]
+pub fn distributed_matches_local() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed binding listener");
+    let addr = listener.local_addr().expect("listener has no address");
+
+    let mut local = new_renderer(OPTS);
+    let expected = [local.render().img, local.render().img];
+
+    thread::scope(|scope| {
+        let worker = scope.spawn(move || {
+            let (stream, _) = listener.accept().expect("failed accepting coordinator");
+            new_renderer(OPTS).serve(stream)
+        });
+
+        let mut coordinator = new_renderer(OPTS);
+        coordinator.connect_worker(addr).expect("failed connecting to worker");
+        for expected in &expected {
+            let img = coordinator.render().img;
+            assert!(img.iter().eq(expected.iter()), "worker's tiles differ from local ones");
+        }
+        assert_eq!(coordinator.worker_count(), 1);
+
+        coordinator.disconnect_workers();
+        worker.join().expect("worker panicked").expect("worker failed");
+    });
+}
+
+/// Workers with different settings should be refused, and dropped if the coordinator's settings change, with their
+/// tiles rendered locally instead
+#[test]
+pub fn distributed_settings_mismatch() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed binding listener");
+    let addr = listener.local_addr().expect("listener has no address");
+
+    thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            let (stream, _) = listener.accept().expect("failed accepting coordinator");
+            let opts = RenderOpts {
+                width: nonzero!(32_usize),
+                ..OPTS
+            };
+            new_renderer(opts).serve(stream)
+        });
+
+        let mut coordinator = new_renderer(OPTS);
+        assert!(matches!(
+            coordinator.connect_worker(addr),
+            Err(DistributedError::SettingsMismatch)
+        ));
+        assert!(matches!(
+            worker.join().expect("worker panicked"),
+            Err(DistributedError::SettingsMismatch)
+        ));
+        assert_eq!(coordinator.worker_count(), 0);
+    });
+
+    thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            let (stream, _) = listener.accept().expect("failed accepting coordinator");
+            new_renderer(OPTS).serve(stream)
+        });
+
+        let mut coordinator = new_renderer(OPTS);
+        coordinator.connect_worker(addr).expect("failed connecting to worker");
+        let opts = RenderOpts {
+            height: nonzero!(48_usize),
+            ..OPTS
+        };
+        coordinator.set_options(opts);
+        // The local threads could take all the tiles of a pass before the worker is asked for one
+        let mut local = new_renderer(opts);
+        for _ in 0..16 {
+            let img = coordinator.render().img;
+            assert!(img.iter().eq(local.render().img.iter()));
+            if coordinator.worker_count() == 0 {
+                break;
+            }
+        }
+        assert_eq!(coordinator.worker_count(), 0, "the worker should have been dropped");
+        assert!(matches!(
+            worker.join().expect("worker panicked"),
+            Err(DistributedError::SettingsMismatch)
+        ));
+    });
+}
+
+/// Workers with a different scene should be refused, and dropped if the coordinator's scene changes, with their tiles
+/// rendered locally instead
+#[test]
+pub fn distributed_scene_mismatch() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed binding listener");
+    let addr = listener.local_addr().expect("listener has no address");
+    let edited_scene = || {
+        let mut scene = preset::RTIAW_DEMO().scene;
+        scene.skybox = SkyboxInstance::from(None);
+        scene
+    };
+
+    thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            let (stream, _) = listener.accept().expect("failed accepting coordinator");
+            let mut renderer = new_renderer(OPTS);
+            renderer.set_scene(edited_scene());
+            renderer.serve(stream)
+        });
+
+        let mut coordinator = new_renderer(OPTS);
+        assert!(matches!(
+            coordinator.connect_worker(addr),
+            Err(DistributedError::SceneMismatch)
+        ));
+        assert!(matches!(
+            worker.join().expect("worker panicked"),
+            Err(DistributedError::SceneMismatch)
+        ));
+        assert_eq!(coordinator.worker_count(), 0);
+    });
+
+    thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            let (stream, _) = listener.accept().expect("failed accepting coordinator");
+            new_renderer(OPTS).serve(stream)
+        });
+
+        let mut coordinator = new_renderer(OPTS);
+        coordinator.connect_worker(addr).expect("failed connecting to worker");
+        coordinator.set_scene(edited_scene());
+        let mut local = new_renderer(OPTS);
+        local.set_scene(edited_scene());
+        // The local threads could take all the tiles of a pass before the worker is asked for one
+        for _ in 0..16 {
+            let img = coordinator.render().img;
+            assert!(img.iter().eq(local.render().img.iter()));
+            if coordinator.worker_count() == 0 {
+                break;
+            }
+        }
+        assert_eq!(coordinator.worker_count(), 0, "the worker should have been dropped");
+        assert!(matches!(
+            worker.join().expect("worker panicked"),
+            Err(DistributedError::SceneMismatch)
+        ));
+    });
+}
+
+/// Workers should refuse requests for tiles that are too big, outside the image, or have too many samples, without
+/// allocating anything for them
+#[test]
+pub fn distributed_invalid_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed binding listener");
+    let addr = listener.local_addr().expect("listener has no address");
+    let renderer = new_renderer(OPTS);
+    let (hash, scene_hash) = (settings_hash(&OPTS, renderer.camera()), renderer.scene_hash());
+
+    // The samples, and the tile's `x`, `y`, `w` and `h`. The tile size is checked as soon as the request is read
+    let requests: [([u64; 5], bool); 4] = [
+        ([2, 0, 0, 1 << 40, 1 << 40], true),
+        ([2, 0, 0, 0, 16], true),
+        ([2, 56, 0, 16, 16], false),
+        ([3, 0, 0, 16, 16], false),
+    ];
+    for (request, invalid_size) in requests {
+        thread::scope(|scope| {
+            let worker = scope.spawn(|| {
+                let (stream, _) = listener.accept().expect("failed accepting coordinator");
+                new_renderer(OPTS).serve(stream)
+            });
+
+            // Written by hand, since the coordinator never sends requests like these
+            let mut stream = TcpStream::connect(addr).expect("failed connecting to worker");
+            let mut handshake = b"RAYNADST".to_vec();
+            handshake.extend(2_u32.to_le_bytes());
+            handshake.extend(hash.to_le_bytes());
+            handshake.extend(scene_hash.to_le_bytes());
+            stream.write_all(&handshake).expect("failed sending handshake");
+            let mut status = [0];
+            stream.read_exact(&mut status).expect("failed reading status");
+            assert_eq!(status, [0], "handshake should be accepted");
+
+            let [samples, x, y, w, h] = request;
+            for n in [hash, scene_hash, 0, 0, samples, x, y, w, h] {
+                stream.write_all(&n.to_le_bytes()).expect("failed sending request");
+            }
+            let result = worker.join().expect("worker panicked");
+            let refused = match invalid_size {
+                true => matches!(result, Err(DistributedError::IoError { .. })),
+                false => matches!(result, Err(DistributedError::InvalidRequest)),
+            };
+            assert!(refused, "request {request:?} should be refused: {result:?}");
+            // Nothing was rendered, so the connection is just closed
+            assert_eq!(stream.read(&mut status).expect("failed reading reply"), 0);
+        });
+    }
+}
+
+/// Workers that return invalid colours should be dropped, with their tiles rendered locally instead
+#[test]
+pub fn distributed_invalid_colours() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("failed binding listener");
+    let addr = listener.local_addr().expect("listener has no address");
+
+    thread::scope(|scope| {
+        // Accepts the handshake, and then replies to every request with NaNs
+        scope.spawn(|| {
+            let (mut stream, _) = listener.accept().expect("failed accepting coordinator");
+            let mut handshake = [0; 8 + 4 + 8 + 8];
+            stream.read_exact(&mut handshake).expect("failed reading handshake");
+            stream.write_all(&[0]).expect("failed accepting handshake");
+            let mut request = [0; 9 * 8];
+            while stream.read_exact(&mut request).is_ok() {
+                let number = |i: usize| u64::from_le_bytes(request[i * 8..(i + 1) * 8].try_into().unwrap());
+                let area = number(7) * number(8);
+                let mut reply = vec![0];
+                for _ in 0..area * 3 {
+                    reply.extend(f32::NAN.to_le_bytes());
+                }
+                if stream.write_all(&reply).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut coordinator = new_renderer(OPTS);
+        coordinator.connect_worker(addr).expect("failed connecting to worker");
+        let mut local = new_renderer(OPTS);
+        // The local threads could take all the tiles of a pass before the worker is asked for one
+        for _ in 0..16 {
+            let img = coordinator.render().img;
+            assert!(img.iter().eq(local.render().img.iter()));
+            if coordinator.worker_count() == 0 {
+                break;
+            }
+        }
+        assert_eq!(coordinator.worker_count(), 0, "the worker should have been dropped");
+        // Closes the connection, so the fake worker stops
+        coordinator.disconnect_workers();
+    });
+}
@@ -0,0 +1,101 @@
+use approx::assert_relative_eq;
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::render::exposure::{log_average_luminance, AutoExposure, ExposureState};
+use rayna_engine::render::postprocess::white_balance::{apply_white_balance, WhiteBalance};
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use std::time::Duration;
+
+mod common;
+
+/// A uniformly dark image should be brought up to the key on the first frame,
+/// and then slowly adapt once the image gets brighter
+#[test]
+pub fn auto_exposure_adapts() {
+    let settings = AutoExposure::default();
+    let dark = Image::new_filled(16, 16, Colour::from([0.01; 3]));
+    let bright = Image::new_filled(16, 16, Colour::from([10.; 3]));
+    assert_relative_eq!(log_average_luminance(&dark), 0.01, epsilon = 1e-3);
+
+    let mut state = ExposureState::default();
+    let ev = state.adapt(&settings, &dark, Duration::from_millis(100));
+    assert_relative_eq!(0.01 * ev.exp2(), settings.key, epsilon = 1e-2);
+
+    // Shouldn't reach the (much lower) target in a single short frame
+    let target = (settings.key / 10.).log2();
+    let next = state.adapt(&settings, &bright, Duration::from_millis(100));
+    assert!(
+        target < next && next < ev,
+        "exposure should adapt gradually ({ev} -> {next}, target {target})"
+    );
+
+    // Must stay within the limits, no matter how dark the image is
+    let clamped = AutoExposure { max_ev: 2., ..settings };
+    let ev = ExposureState::default().adapt(&clamped, &dark, Duration::ZERO);
+    assert_eq!(ev, 2.);
+}
+
+/// Lowering the temperature should correct for warm light by making the image bluer, and raising it should do the
+/// opposite. The neutral settings shouldn't change anything
+#[test]
+pub fn white_balance() {
+    let grey = Image::new_filled(4, 4, Colour::from([0.5; 3]));
+    let balanced = |settings: WhiteBalance| {
+        let mut img = grey.clone();
+        apply_white_balance(&mut img, &settings);
+        img[(0, 0)]
+    };
+
+    assert_eq!(balanced(WhiteBalance::NEUTRAL), grey[(0, 0)]);
+    let matrix = WhiteBalance::NEUTRAL.matrix();
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, &value) in row.iter().enumerate() {
+            assert_relative_eq!(value, if i == j { 1. } else { 0. }, epsilon = 1e-4);
+        }
+    }
+
+    let [r, _, b] = balanced(WhiteBalance {
+        temperature: 3200.,
+        ..WhiteBalance::NEUTRAL
+    })
+    .0;
+    assert!(b > r, "tungsten balance should make the image bluer");
+    let [r, _, b] = balanced(WhiteBalance {
+        temperature: 10000.,
+        ..WhiteBalance::NEUTRAL
+    })
+    .0;
+    assert!(r > b, "shade balance should make the image warmer");
+    let [r, g, b] = balanced(WhiteBalance {
+        tint: 0.5,
+        ..WhiteBalance::NEUTRAL
+    })
+    .0;
+    assert!(g < r && g < b, "a positive tint should remove the green");
+}
+
+/// The manual exposure should scale the image, on top of the render
+#[test]
+pub fn manual_exposure() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(32_usize),
+        height: nonzero!(32_usize),
+        deterministic: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let render = |exposure: Number| {
+        let opts = RenderOpts { exposure, ..opts };
+        let mut renderer = Renderer::<_, _, common::Rng>::new_from(preset.scene.clone(), preset.camera, opts, 1)
+            .expect("failed creating renderer");
+        renderer.render().img
+    };
+
+    let normal = render(0.);
+    let brighter = render(1.);
+    for (a, b) in normal.iter().flat_map(|c| c.0).zip(brighter.iter().flat_map(|c| c.0)) {
+        assert_relative_eq!(a * 2., b, max_relative = 1e-5);
+    }
+}
@@ -0,0 +1,66 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::render::filter::PixelFilter;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use strum::IntoEnumIterator;
+
+mod common;
+
+/// The samples should stay inside the filter, be centred on the pixel, and have weights that average to one (so
+/// that the filter doesn't change the brightness of the image)
+#[test]
+pub fn filter_sampling() {
+    const N: usize = 128;
+    for filter in PixelFilter::iter() {
+        let (mut weight_sum, mut offset_sum) = (0., Vector2::ZERO);
+        let mut negative = false;
+        for i in 0..N {
+            for j in 0..N {
+                let u = Vector2::new((i as Number + 0.5) / N as Number, (j as Number + 0.5) / N as Number);
+                let (offset, weight) = filter.sample(u);
+                assert!(
+                    offset.x.abs() <= filter.radius() && offset.y.abs() <= filter.radius(),
+                    "{filter} sample {offset:?} is outside the filter"
+                );
+                weight_sum += weight;
+                offset_sum += offset * weight;
+                negative |= weight < 0.;
+            }
+        }
+        let count = (N * N) as Number;
+        assert_relative_eq!(weight_sum / count, 1., epsilon = 0.01);
+        assert_relative_eq!(offset_sum.x / count, 0., epsilon = 0.01);
+        assert_relative_eq!(offset_sum.y / count, 0., epsilon = 0.01);
+        // Only Mitchell has negative lobes
+        assert_eq!(negative, filter == PixelFilter::Mitchell, "{filter} negative weights");
+    }
+}
+
+/// Filtering shouldn't change the overall brightness of a render
+#[test]
+pub fn filtered_render_brightness() {
+    let preset = preset::RTIAW_DEMO();
+    let mean = |filter: PixelFilter| {
+        let opts = RenderOpts {
+            filter,
+            deterministic: true,
+            ..common::SIMPLE_RENDER_OPTIONS
+        };
+        let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+            preset.scene.clone(),
+            preset.camera,
+            opts,
+            common::RENDERER_THREAD_COUNT,
+        )
+        .expect("failed creating renderer");
+        let img = renderer.render().img;
+        img.iter().flat_map(|c| c.0).sum::<Channel>() / (img.len() * Colour::CHANNEL_COUNT) as Channel
+    };
+
+    let reference = mean(PixelFilter::Box);
+    for filter in PixelFilter::iter() {
+        assert_relative_eq!(mean(filter), reference, max_relative = 0.05);
+    }
+}
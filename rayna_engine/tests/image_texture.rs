@@ -0,0 +1,128 @@
+use approx::assert_relative_eq;
+use rand::rngs::mock::StepRng;
+use rayna_engine::core::colour::{srgb_to_linear, ColourSpace};
+use rayna_engine::core::types::*;
+use rayna_engine::render::output::{save, OutputFormat};
+use rayna_engine::scene::asset::AssetResolver;
+use rayna_engine::texture::image::{ImageTexture, TextureFilter};
+use rayna_engine::texture::Texture;
+
+mod common;
+
+/// A checkerboard of single black and white pixels
+fn checkerboard(width: usize, height: usize) -> Image {
+    Image::from_fn(width, height, |x, y| {
+        if (x + y) % 2 == 0 {
+            Colour::WHITE
+        } else {
+            Colour::BLACK
+        }
+    })
+}
+
+fn value_at(texture: &ImageTexture, u: Number, v: Number) -> Colour {
+    let intersection = common::intersection(Point3::ZERO, Vector3::Y, Point2::new(u, v));
+    texture.value(&intersection, &mut StepRng::new(0, 1))
+}
+
+fn assert_grey(colour: Colour, value: Channel) {
+    for c in 0..3 {
+        assert_relative_eq!(colour[c], value, epsilon = 1e-6);
+    }
+}
+
+/// The mip-map should halve the image down to a single pixel, averaging the pixels
+#[test]
+pub fn mip_levels() {
+    let texture = ImageTexture::from(checkerboard(8, 5));
+    let sizes = texture
+        .mip_levels()
+        .iter()
+        .map(|level| (level.width(), level.height()))
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [(8, 5), (4, 2), (2, 1), (1, 1)]);
+    assert_eq!(texture.image().width(), 8);
+
+    // Each 2x2 block of the checkerboard averages to grey
+    for level in &texture.mip_levels()[1..] {
+        assert!(level.iter().all(|&px| (px[0] - 0.5).abs() < 1e-6));
+    }
+}
+
+/// Sampling the full-size image should give the pixels, and the smaller levels should average them out
+#[test]
+pub fn mip_filtering() {
+    let mut texture = ImageTexture::from(checkerboard(16, 16));
+    // The centres of the top-left pixels (the image is flipped vertically)
+    let (u, v) = (0.5 / 16., 1. - (0.5 / 16.));
+    for filter in [
+        TextureFilter::Nearest,
+        TextureFilter::Bilinear,
+        TextureFilter::Trilinear,
+    ] {
+        texture.filter = filter;
+        texture.mip_bias = 0.;
+        assert_grey(value_at(&texture, u, v), 1.);
+        assert_grey(value_at(&texture, u + (1. / 16.), v), 0.);
+
+        // The checkerboard is lost in the smaller levels
+        for bias in [1., 2.5, 4., 100.] {
+            texture.mip_bias = bias;
+            assert_grey(value_at(&texture, u, v), 0.5);
+        }
+    }
+
+    // Trilinear blends between the full-size image and the next level
+    texture.filter = TextureFilter::Trilinear;
+    texture.mip_bias = 0.25;
+    assert_grey(value_at(&texture, u, v), 0.875);
+    // The others snap to the nearest level
+    texture.filter = TextureFilter::Bilinear;
+    assert_grey(value_at(&texture, u, v), 1.);
+}
+
+/// HDR images should keep their values above `1.0` when loaded as textures, instead of being clamped
+#[test]
+pub fn hdr_texture() {
+    let img = Image::from_fn(4, 4, |x, _| Colour::from([x as Channel * 10., 0.5, 100.]));
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    save(
+        &img,
+        dir.path().join("bright.exr"),
+        OutputFormat::Exr,
+        std::convert::identity,
+    )
+    .expect("failed saving EXR");
+
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+    let texture = ImageTexture::load(&resolver, "bright.exr").expect("failed loading texture");
+    // The centre of the last column
+    let value = value_at(&texture, 3.5 / 4., 0.5);
+    assert_relative_eq!(value[0], 30., epsilon = 1e-3);
+    assert_relative_eq!(value[1], 0.5, epsilon = 1e-3);
+    assert_relative_eq!(value[2], 100., epsilon = 1e-3);
+}
+
+/// 8-bit images should be decoded from sRGB, unless they hold data (like normal maps), which are loaded linearly
+#[test]
+pub fn linear_data_texture() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    image::RgbImage::from_pixel(2, 2, image::Rgb([255, 128, 0]))
+        .save(dir.path().join("data.png"))
+        .expect("failed saving PNG");
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+
+    let encoded = 128. / 255.;
+    let srgb = resolver.load_image("data.png").expect("failed loading image");
+    assert_relative_eq!(srgb[(0, 0)][1], srgb_to_linear(encoded), epsilon = 1e-6);
+    let linear = resolver
+        .load_image_as("data.png", ColourSpace::Linear)
+        .expect("failed loading image");
+    assert_relative_eq!(linear[(0, 0)][1], encoded, epsilon = 1e-6);
+
+    let texture = ImageTexture::load_linear(&resolver, "data.png").expect("failed loading texture");
+    let value = texture.image()[(1, 1)];
+    assert_relative_eq!(value[0], 1., epsilon = 1e-6);
+    assert_relative_eq!(value[1], encoded, epsilon = 1e-6);
+    assert_relative_eq!(value[2], 0., epsilon = 1e-6);
+}
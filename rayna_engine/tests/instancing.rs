@@ -0,0 +1,54 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::instanced::{Instance, InstancedObject};
+use rayna_engine::object::Object;
+use rayna_engine::shared::aabb::HasAabb;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::texture::TextureInstance;
+use std::sync::Arc;
+
+type Mat = MaterialInstance<TextureInstance>;
+
+/// Each instance should be hit where its transform puts it, with its own material if it has one
+#[test]
+pub fn instanced_spheres() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let material = |albedo: [Channel; 3]| -> Mat { LambertianMaterial { albedo: albedo.into() }.into() };
+
+    let mesh = Arc::new(SphereMesh::new(Point3::ZERO, 1.));
+    let instances = (0..4).map(|i| {
+        let transform =
+            Transform3::from_scale(Vector3::splat(0.5)).then_translate(Vector3::new(i as Number * 3., 0., 0.));
+        let instance = Instance::new(transform);
+        if i == 3 {
+            instance.with_material(material([1., 0., 0.]))
+        } else {
+            instance
+        }
+    });
+    let object = InstancedObject::<SphereMesh, Mat>::new(mesh.clone(), material([0.5; 3]), instances);
+    assert_eq!(object.count(), 4);
+    // The mesh is shared, not copied
+    assert!(Arc::ptr_eq(object.mesh(), &mesh));
+
+    let aabb = object.aabb().expect("instances should be bounded");
+    assert_relative_eq!(aabb.min().x, -0.5, epsilon = 1e-9);
+    assert_relative_eq!(aabb.max().x, 9.5, epsilon = 1e-9);
+
+    let hit = |x: Number, rng: &mut _| object.full_intersect(&Ray::new([x, 0., -5.], Vector3::Z), &interval, rng);
+    let first = hit(0., rng).expect("should hit first instance");
+    assert_relative_eq!(first.intersection.dist, 4.5, epsilon = 1e-9);
+    assert!(std::ptr::eq(first.material, object.material()));
+
+    let last = hit(9., rng).expect("should hit last instance");
+    assert_relative_eq!(last.intersection.dist, 4.5, epsilon = 1e-9);
+    assert!(!std::ptr::eq(last.material, object.material()));
+
+    // Between the instances
+    assert!(hit(1.5, rng).is_none());
+}
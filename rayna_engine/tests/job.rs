@@ -0,0 +1,122 @@
+use rayna_engine::core::job::{JobError, JobHandle, JobQueue, JobStatus};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Waits for the job to finish, returning its final status
+fn wait(job: &JobHandle) -> JobStatus {
+    let start = Instant::now();
+    while !job.status().is_finished() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "job {} never finished",
+            job.id()
+        );
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    job.status()
+}
+
+/// With a single worker, jobs should run one at a time, in the order they were submitted
+#[test]
+pub fn jobs_run_in_order() {
+    let queue = JobQueue::new(1).expect("failed creating job queue");
+    let order = Arc::new(Mutex::new(vec![]));
+
+    let jobs = (0..8)
+        .map(|i| {
+            let order = Arc::clone(&order);
+            queue.submit(format!("job {i}"), move |_| {
+                order.lock().unwrap().push(i);
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for job in &jobs {
+        assert_eq!(wait(job), JobStatus::Completed);
+    }
+
+    assert_eq!(*order.lock().unwrap(), (0..8).collect::<Vec<_>>());
+    // The IDs are unique, and increase in the same order
+    assert!(jobs.windows(2).all(|pair| pair[0].id() < pair[1].id()));
+    assert_eq!(
+        queue.get(jobs[3].id()).map(|job| job.name().to_owned()),
+        Some("job 3".into())
+    );
+}
+
+/// Cancelled jobs should be skipped if they haven't started yet, and stop the next time they check if they have
+#[test]
+pub fn jobs_can_be_cancelled() {
+    let queue = JobQueue::new(1).expect("failed creating job queue");
+
+    // Keeps the only worker busy, until it's cancelled
+    let (started_tx, started_rx) = mpsc::channel();
+    let running = queue.submit("running", move |ctx| {
+        started_tx.send(()).unwrap();
+        loop {
+            ctx.check_cancelled()?;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    });
+    let ran = Arc::new(Mutex::new(false));
+    let queued = queue.submit("queued", {
+        let ran = Arc::clone(&ran);
+        move |_| {
+            *ran.lock().unwrap() = true;
+            Ok(())
+        }
+    });
+
+    started_rx.recv().expect("job should have started");
+    assert_eq!(running.status(), JobStatus::Running);
+    assert_eq!(queued.status(), JobStatus::Queued);
+
+    queued.cancel();
+    running.cancel();
+    assert_eq!(wait(&running), JobStatus::Cancelled);
+    assert_eq!(wait(&queued), JobStatus::Cancelled);
+    assert!(!*ran.lock().unwrap(), "cancelled job shouldn't have run");
+
+    // Cancelling everything also cancels jobs submitted before it
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let blocking = queue.submit("blocking", move |_| {
+        started_tx.send(()).unwrap();
+        let _ = release_rx.recv();
+        Ok(())
+    });
+    let skipped = queue.submit("skipped", |_| Ok(()));
+    started_rx.recv().expect("job should have started");
+    queue.cancel_all();
+    drop(release_tx);
+    // The blocking job doesn't check, so it completes anyway
+    assert_eq!(wait(&blocking), JobStatus::Completed);
+    assert_eq!(wait(&skipped), JobStatus::Cancelled);
+}
+
+/// Jobs should end up completed with full progress, or failed with the reason why
+#[test]
+pub fn jobs_complete_or_fail() {
+    let queue = JobQueue::new(2).expect("failed creating job queue");
+
+    let completed = queue.submit("completed", |ctx| {
+        ctx.set_progress(0.5);
+        Ok(())
+    });
+    let failed = queue.submit("failed", |_| Err(JobError::Failed("out of cheese".into())));
+    let panicked = queue.submit("panicked", |_| panic!("job panic (expected by the test)"));
+
+    assert_eq!(wait(&completed), JobStatus::Completed);
+    assert_eq!(completed.progress(), 1.);
+    assert_eq!(wait(&failed), JobStatus::Failed("out of cheese".into()));
+    assert_eq!(wait(&panicked), JobStatus::Failed("job panicked".into()));
+
+    // The workers should survive the panic, and carry on running jobs
+    let after = queue.submit("after", |_| Ok(()));
+    assert_eq!(wait(&after), JobStatus::Completed);
+
+    assert_eq!(queue.jobs().len(), 4);
+    queue.clear_finished();
+    assert!(queue.jobs().is_empty());
+    assert!(queue.get(completed.id()).is_none());
+}
@@ -0,0 +1,176 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::camera::{Camera, Viewport};
+use rayna_engine::scene::lens::{isolate_channel, LensDistortion, TiltShift};
+
+const EPSILON: Number = 1e-9;
+
+const LENS: LensDistortion = LensDistortion {
+    radial: [-0.12, 0.03, -0.004],
+    tangential: [0.001, -0.0005],
+    chromatic: [0.; 3],
+};
+
+fn viewport(distortion: Option<LensDistortion>) -> Viewport {
+    Camera {
+        v_fov: Angle::from_degrees(60.),
+        distortion,
+        ..Default::default()
+    }
+    .calculate_viewport()
+    .expect("camera should be valid")
+}
+
+fn angle_from_fwd(viewport: &Viewport, px: Number, py: Number) -> Number {
+    let fwd = (viewport.pixel_center - viewport.pos).normalize();
+    viewport.centre_ray(px, py, 64., 48.).dir().dot(fwd).acos()
+}
+
+/// Undistorting should find the point that distorts back to where it started
+#[test]
+pub fn undistort_inverts_distort() {
+    for (x, y) in [(0., 0.), (0.3, -0.2), (-0.5, 0.4), (0.6, 0.45)] {
+        let (ux, uy) = LENS.undistort(x, y, None);
+        let (dx, dy) = LENS.distort(ux, uy);
+        assert!(
+            (dx - x).abs() < 1e-6 && (dy - y).abs() < 1e-6,
+            "{:?} != {:?}",
+            (dx, dy),
+            (x, y)
+        );
+    }
+}
+
+/// Barrel distortion squeezes more of the scene into the edges of the image, and projecting should still find the
+/// pixel that a point was seen through
+#[test]
+pub fn distorted_rays() {
+    let (plain, distorted) = (viewport(None), viewport(Some(LENS)));
+    assert!((angle_from_fwd(&plain, 32., 24.) - angle_from_fwd(&distorted, 32., 24.)).abs() < EPSILON);
+    assert!(angle_from_fwd(&distorted, 0., 0.) > angle_from_fwd(&plain, 0., 0.));
+
+    for (px, py) in [(32., 24.), (3., 5.), (60.5, 40.), (20., 47.)] {
+        let point = distorted.centre_ray(px, py, 64., 48.).at(5.);
+        let (x, y) = distorted
+            .project(point, 64., 48.)
+            .expect("point should be in front of the camera");
+        assert!(
+            (x - px).abs() < 1e-4 && (y - py).abs() < 1e-4,
+            "{:?} != {:?}",
+            (x, y),
+            (px, py)
+        );
+    }
+}
+
+/// Each channel should see through a lens with a different focal length, and the isolated channels should average out
+/// to the original colour
+#[test]
+pub fn chromatic_aberration() {
+    let rng = &mut rand::thread_rng();
+    assert_eq!(viewport(Some(LENS)).pick_channel(rng), None);
+
+    let viewport = viewport(Some(LensDistortion {
+        chromatic: [0.01, 0., -0.01],
+        ..LENS
+    }));
+    for _ in 0..32 {
+        assert!(matches!(viewport.pick_channel(rng), Some(0..=2)));
+    }
+    let angle = |channel| {
+        let fwd = (viewport.pixel_center - viewport.pos).normalize();
+        let ray = viewport.calc_ray_channel(0., 0., 64., 48., Some(channel), rng);
+        ray.dir().dot(fwd).acos()
+    };
+    // The red channel has a longer focal length, so sees less of the scene
+    assert!(angle(0) < angle(1) && angle(1) < angle(2));
+
+    let colour = Colour::new([0.2, 0.5, 0.9]);
+    assert_eq!(isolate_channel(colour, None), colour);
+    let mean = (0..3).map(|c| isolate_channel(colour, Some(c))).sum::<Colour>() / 3. as Channel;
+    assert!((mean - colour).into_iter().all(|c| c.abs() < 1e-6));
+}
+
+/// Shifting the lens up should move the image down without turning the camera, so that projecting still works
+#[test]
+pub fn lens_shift() {
+    let [w, h] = [64., 48.];
+    let shifted = Camera {
+        v_fov: Angle::from_degrees(60.),
+        tilt_shift: Some(TiltShift {
+            shift: [0.1, 0.3],
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .calculate_viewport()
+    .expect("camera should be valid");
+
+    let (x, y) = shifted
+        .project(Point3::new(0., 0., 5.), w, h)
+        .expect("point should be in front of the camera");
+    assert!((x - ((w / 2.) - (0.1 * h))).abs() < EPSILON && (y - ((h / 2.) + (0.3 * h))).abs() < EPSILON);
+
+    // Vertical lines stay vertical
+    let top = shifted
+        .project(Point3::new(1., 2., 5.), w, h)
+        .expect("point should be in view");
+    let bottom = shifted
+        .project(Point3::new(1., -1., 5.), w, h)
+        .expect("point should be in view");
+    assert!((top.0 - bottom.0).abs() < EPSILON);
+
+    for (px, py) in [(32., 24.), (3., 5.), (60.5, 40.)] {
+        let point = shifted.centre_ray(px, py, w, h).at(5.);
+        let (x, y) = shifted.project(point, w, h).expect("point should be in view");
+        assert!((x - px).abs() < 1e-6 && (y - py).abs() < 1e-6);
+    }
+}
+
+/// Tilting the lens should focus the rays for each pixel onto the tilted plane of focus
+#[test]
+pub fn lens_tilt() {
+    let rng = &mut rand::thread_rng();
+    let [w, h] = [64., 48.];
+    let tilt = Angle::from_degrees(30.);
+    let viewport = Camera {
+        v_fov: Angle::from_degrees(60.),
+        focus_dist: 4.,
+        defocus_angle: Angle::from_degrees(10.),
+        tilt_shift: Some(TiltShift {
+            tilt: [Angle::from_degrees(0.), tilt],
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+    .calculate_viewport()
+    .expect("camera should be valid");
+    let fwd = (viewport.pixel_center - viewport.pos).normalize();
+    let normal = fwd + (viewport.viewport_v.normalize() * tilt.radians.tan());
+
+    for (px, py) in [(32., 24.), (10., 40.), (50., 30.)] {
+        let centre = viewport.centre_ray(px, py, w, h);
+        let t = (viewport.pixel_center - centre.pos()).dot(normal) / centre.dir().dot(normal);
+        let focus = centre.at(t);
+        for _ in 0..16 {
+            let ray = viewport.calc_ray(px, py, w, h, rng);
+            let offset = focus - ray.pos();
+            let miss = (offset - (ray.dir() * offset.dot(ray.dir()))).length();
+            assert!(miss < 1e-6, "ray should pass through the focus at {focus:?}");
+        }
+    }
+    // The bottom of the image is focused nearer
+    let depth = |py| {
+        let ray = viewport.centre_ray(32., py, w, h);
+        ray.dir().dot(fwd) * (viewport.pixel_center - ray.pos()).dot(normal) / ray.dir().dot(normal)
+    };
+    assert!(depth(40.) < 4. && depth(8.) > 4.);
+
+    let too_tilted = Camera {
+        tilt_shift: Some(TiltShift {
+            tilt: [Angle::from_degrees(90.), Angle::from_degrees(0.)],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert!(too_tilted.calculate_viewport().is_err());
+}
@@ -0,0 +1,66 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::material::light::{LightGroup, LightMaterial};
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::render::light_group::save_light_groups_exr;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+/// Looking directly at a light in the "key" group should only show it in the key group's render,
+/// and the skybox should only be in the ungrouped render
+#[test]
+pub fn light_group_contributions() {
+    let key = LightGroup::new("key");
+    let fill = LightGroup::new("fill");
+    let light = |pos: Point3, radius: Number, group: &LightGroup| {
+        let material: MaterialInstance<TextureInstance> = LightMaterial {
+            emissive: [4.; 3].into(),
+            group: Some(*group),
+        }
+        .into();
+        SimpleObject::new_uncorrected(SphereMesh::new(pos, radius), material, None)
+    };
+
+    let scene = StandardScene {
+        objects: [
+            light(Point3::new(0., 0., 5.), 1., &key),
+            light(Point3::new(3., 0., 5.), 0.5, &fill),
+        ]
+        .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera::default();
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+        scene,
+        camera,
+        common::SIMPLE_RENDER_OPTIONS,
+        common::RENDERER_THREAD_COUNT,
+    )
+    .expect("failed creating renderer");
+
+    let [w, h] = common::SIMPLE_RENDER_OPTIONS.dims();
+    let centre = (w / 2, h / 2);
+
+    let key_img = renderer.render_light_group(Some(&key));
+    assert_relative_eq!(key_img[centre][0], 4.);
+    assert_eq!(key_img[(0, 0)], Colour::BLACK);
+
+    let fill_img = renderer.render_light_group(Some(&fill));
+    assert_eq!(fill_img[centre], Colour::BLACK);
+
+    let ungrouped = renderer.render_light_group(None);
+    assert_eq!(ungrouped[centre], Colour::BLACK);
+    assert_relative_eq!(ungrouped[(0, 0)][0], 1.);
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("light_groups.exr");
+    save_light_groups_exr(&mut renderer, &[Some(key), Some(fill), None], &path).expect("failed saving light groups");
+    assert!(std::fs::metadata(&path).expect("EXR file should exist").len() > 0);
+}
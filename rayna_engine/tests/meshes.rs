@@ -0,0 +1,744 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::mesh::advanced::bvh::BvhMesh;
+use rayna_engine::mesh::advanced::csg::CsgMesh;
+use rayna_engine::mesh::advanced::curve::{CurveBasis, CurveMesh, CurveShape, CurveStrand};
+use rayna_engine::mesh::advanced::indexed_triangle::{
+    angle_weighted_normals, IndexedTriangleMesh, VertexAttributes, VertexNormals,
+};
+use rayna_engine::mesh::advanced::point_cloud::{PointCloudMesh, Splat, SplatShape};
+use rayna_engine::mesh::advanced::polygon::{triangulate, PolygonMesh};
+use rayna_engine::mesh::advanced::subdivision::SubdivisionMesh;
+use rayna_engine::mesh::advanced::voxel_grid::VoxelGridMesh;
+use rayna_engine::mesh::isosurface::polygonised::PolygonisedIsosurfaceMesh;
+use rayna_engine::mesh::isosurface::raymarched::RaymarchedIsosurfaceMesh;
+use rayna_engine::mesh::isosurface::sdf::SdfNode;
+use rayna_engine::mesh::ops::{fix_winding, recompute_normals, simplify, weld};
+use rayna_engine::mesh::planar::disk::DiskMesh;
+use rayna_engine::mesh::primitive::capsule::CapsuleMesh;
+use rayna_engine::mesh::primitive::cone::ConeMesh;
+use rayna_engine::mesh::primitive::ellipsoid::EllipsoidMesh;
+use rayna_engine::mesh::primitive::rounded_box::RoundedBoxMesh;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::{Mesh, MeshInstance};
+use rayna_engine::shared::aabb::HasAabb;
+use rayna_engine::shared::intersect_arena::{self, IntersectionList};
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+
+/// A flat quad, split into two triangles, should have all its smooth normals pointing straight out of the quad
+#[test]
+pub fn angle_weighted_normals_flat_quad() {
+    let vertices = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]].map(Point3::from);
+    let indices = [[0, 1, 2], [0, 2, 3]];
+
+    for n in angle_weighted_normals(&vertices, &indices) {
+        assert_relative_eq!(n.x, 0.);
+        assert_relative_eq!(n.y, 0.);
+        assert_relative_eq!(n.z, 1.);
+    }
+}
+
+/// The smooth normal at the corner of a cube should point diagonally outwards, equally along all three axes
+#[test]
+pub fn angle_weighted_normals_cube_corner() {
+    let vertices = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]].map(Point3::from);
+    // Three faces meeting at the origin, wound so that they face outwards (towards negative axes)
+    let indices = [[0, 2, 1], [0, 3, 2], [0, 1, 3]];
+
+    let normals = angle_weighted_normals(&vertices, &indices);
+    let expected = -1. / Number::sqrt(3.);
+    assert_relative_eq!(normals[0].x, expected);
+    assert_relative_eq!(normals[0].y, expected);
+    assert_relative_eq!(normals[0].z, expected);
+}
+
+/// Intersecting a smooth-shaded mesh should give interpolated normals
+#[test]
+pub fn indexed_triangle_mesh_intersect() {
+    let vertices = [[-1., -1., 0.], [1., -1., 0.], [0., 1., 0.]].map(Point3::from);
+    let normals = [[-1., 0., 1.], [1., 0., 1.], [0., 1., 1.]].map(Vector3::from).to_vec();
+    let mesh = IndexedTriangleMesh::new(vertices, [[0, 1, 2]], VertexNormals::Provided(normals));
+
+    let ray = Ray::new(Point3::new(0., -0.5, -1.), Vector3::new(0., 0., 1.));
+    let intersection = mesh
+        .intersect(&ray, &Interval::from(0.0..), &mut rand::thread_rng())
+        .expect("ray should hit mesh");
+
+    assert_relative_eq!(intersection.dist, 1.);
+    assert!(intersection.normal.is_normalized());
+    // Should be (roughly) halfway between the normals of the bottom two vertices
+    assert!(intersection.normal.x.abs() < 0.1, "normal: {:?}", intersection.normal);
+}
+
+/// UVs and vertex colours should be interpolated across the faces of the mesh
+#[test]
+pub fn indexed_triangle_mesh_attributes() {
+    let vertices = [[-1., -1., 0.], [1., -1., 0.], [0., 1., 0.]].map(Point3::from);
+    let attributes = VertexAttributes {
+        uvs: Some([[0., 0.], [1., 0.], [0.5, 1.]].map(Point2::from).to_vec()),
+        colours: Some([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]].map(Colour::from).to_vec()),
+    };
+    let mesh = IndexedTriangleMesh::new_with_attributes(vertices, [[0, 1, 2]], VertexNormals::Flat, attributes);
+
+    // Aim for the centroid of the triangle, where all the vertices have equal weight
+    let ray = Ray::new(Point3::new(0., -1. / 3., -1.), Vector3::new(0., 0., 1.));
+    let intersection = mesh
+        .intersect(&ray, &Interval::from(0.0..), &mut rand::thread_rng())
+        .expect("ray should hit mesh");
+
+    assert_relative_eq!(intersection.uv.x, 0.5, epsilon = 1e-9);
+    assert_relative_eq!(intersection.uv.y, 1. / 3., epsilon = 1e-9);
+    let colour = intersection.colour.expect("mesh should have vertex colours");
+    for c in colour {
+        assert_relative_eq!(c, 1. / 3., epsilon = 1e-5);
+    }
+}
+
+/// Rays should hit the body and caps of cones and frustums where expected
+#[test]
+pub fn cone_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let cone = ConeMesh::new((0., 0., 0.), (0., 2., 0.), 1.);
+
+    // From below, straight up into the base cap
+    let hit = cone
+        .intersect(&Ray::new(Point3::new(0., -1., 0.), Vector3::Y), &interval, rng)
+        .expect("should hit base");
+    assert_eq!(hit.side, 1);
+    assert_relative_eq!(hit.dist, 1.);
+    assert_relative_eq!(hit.normal.y, -1.);
+
+    // From the side, at half height the radius is `0.5`
+    let hit = cone
+        .intersect(&Ray::new(Point3::new(-2., 1., 0.), Vector3::X), &interval, rng)
+        .expect("should hit body");
+    assert_eq!(hit.side, 0);
+    assert_relative_eq!(hit.dist, 1.5);
+    assert!(hit.normal.x < 0. && hit.normal.y > 0., "normal: {:?}", hit.normal);
+
+    // Above the apex should miss
+    assert!(cone
+        .intersect(&Ray::new(Point3::new(-2., 2.5, 0.), Vector3::X), &interval, rng)
+        .is_none());
+
+    // Cutting off the top half gives a top cap with radius `0.5`
+    let frustum = ConeMesh::new_frustum((0., 0., 0.), (0., 2., 0.), 1., 0.5);
+    let hit = frustum
+        .intersect(&Ray::new(Point3::new(0.25, 3., 0.), -Vector3::Y), &interval, rng)
+        .expect("should hit top cap");
+    assert_eq!(hit.side, 2);
+    assert_relative_eq!(hit.dist, 2.);
+    assert_relative_eq!(hit.normal.y, 1.);
+}
+
+/// Annuli should only be hit between the inner and outer radii
+#[test]
+pub fn annulus_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let annulus = DiskMesh::new_annulus((0., 0., 0.), Vector3::Z, 1., 0.5);
+    let mut hits = |x: Number| annulus.intersect(&Ray::new(Point3::new(x, 0., 1.), -Vector3::Z), &interval, rng);
+
+    assert!(hits(0.).is_none(), "should miss the hole");
+    assert!(hits(0.25).is_none(), "should miss the hole");
+    assert!(hits(1.5).is_none(), "should miss outside");
+
+    let hit = hits(0.75).expect("should hit the ring");
+    assert_relative_eq!(hit.dist, 1.);
+    assert_relative_eq!(hit.normal.z, 1.);
+    assert!(hit.front_face);
+    assert!(
+        (0. ..=1.).contains(&hit.uv.x) && (0. ..=1.).contains(&hit.uv.y),
+        "uv: {:?}",
+        hit.uv
+    );
+}
+
+/// Capsules should be hit on the body and the rounded ends, with normals pointing away from the segment
+#[test]
+pub fn capsule_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let capsule = CapsuleMesh::new((0., 0., 0.), (0., 2., 0.), 0.5);
+
+    // From the side, onto the body
+    let hit = capsule
+        .intersect(&Ray::new(Point3::new(-2., 1., 0.), Vector3::X), &interval, rng)
+        .expect("should hit body");
+    assert_eq!(hit.side, 0);
+    assert_relative_eq!(hit.dist, 1.5);
+    assert_relative_eq!(hit.normal.x, -1.);
+
+    // Straight down onto the top of the end hemisphere
+    let hit = capsule
+        .intersect(&Ray::new(Point3::new(0., 4., 0.), -Vector3::Y), &interval, rng)
+        .expect("should hit end");
+    assert_eq!(hit.side, 2);
+    assert_relative_eq!(hit.dist, 1.5);
+    assert_relative_eq!(hit.normal.y, 1.);
+
+    // From inside, should hit the far side (the start hemisphere)
+    let hit = capsule
+        .intersect(&Ray::new(Point3::new(0., 1., 0.), -Vector3::Y), &interval, rng)
+        .expect("should hit start from inside");
+    assert_eq!(hit.side, 1);
+    assert_relative_eq!(hit.dist, 1.5);
+    assert!(!hit.front_face);
+
+    // Just past the rounded corner should miss, even though it's inside the bounding box
+    assert!(capsule
+        .intersect(&Ray::new(Point3::new(-2., 2.45, 0.45), Vector3::X), &interval, rng)
+        .is_none());
+}
+
+/// Ellipsoids should be hit at their radius along each axis, and have exact (not skewed) normals
+#[test]
+pub fn ellipsoid_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let ellipsoid = EllipsoidMesh::new((0., 0., 0.), (2., 1., 0.5));
+
+    let hit = ellipsoid
+        .intersect(&Ray::new(Point3::new(-5., 0., 0.), Vector3::X), &interval, rng)
+        .expect("should hit along x");
+    assert_relative_eq!(hit.dist, 3.);
+    assert_relative_eq!(hit.normal.x, -1.);
+
+    let hit = ellipsoid
+        .intersect(&Ray::new(Point3::new(0., 0., 5.), -Vector3::Z), &interval, rng)
+        .expect("should hit along z");
+    assert_relative_eq!(hit.dist, 4.5);
+    assert_relative_eq!(hit.normal.z, 1.);
+
+    // At `(1, 0, z)` on the surface, the normal is the gradient `(x / rx^2, 0, z / rz^2)`, normalised
+    let z = 0.5 * Number::sqrt(0.75);
+    let hit = ellipsoid
+        .intersect(&Ray::new(Point3::new(1., 0., 5.), -Vector3::Z), &interval, rng)
+        .expect("should hit off-centre");
+    assert_relative_eq!(hit.dist, 5. - z, epsilon = 1e-9);
+    let expected = Vector3::new(1. / 4., 0., z / 0.25).normalize();
+    assert_relative_eq!(hit.normal.x, expected.x, epsilon = 1e-9);
+    assert_relative_eq!(hit.normal.z, expected.z, epsilon = 1e-9);
+}
+
+/// Rounded boxes should be hit exactly on the flat faces, and on the rounded edges where the SDF says
+#[test]
+pub fn rounded_box_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let rounded = RoundedBoxMesh::new((-1., -1., -1.), (1., 1., 1.), 0.5);
+    let mut hit_x =
+        |y: Number, z: Number| rounded.intersect(&Ray::new(Point3::new(-3., y, z), Vector3::X), &interval, rng);
+
+    let hit = hit_x(0., 0.).expect("should hit flat face");
+    assert_eq!(hit.side, 0);
+    assert_relative_eq!(hit.dist, 2.);
+    assert_relative_eq!(hit.normal.x, -1.);
+
+    // The edge between the `-x` and `+y` faces is a quarter-circle, centred on `(-0.5, 0.5)`
+    let hit = hit_x(0.8, 0.).expect("should hit rounded edge");
+    assert_relative_eq!(hit.dist, 2.1, epsilon = 1e-6);
+    assert_relative_eq!(hit.normal.x, -0.8, epsilon = 1e-6);
+    assert_relative_eq!(hit.normal.y, 0.6, epsilon = 1e-6);
+
+    // Would hit a sharp box, but passes by the rounded edge
+    assert!(hit_x(0.9, 0.9).is_none());
+
+    // From the inside, out of the `+z` face
+    let hit = rounded
+        .intersect(&Ray::new(Point3::ZERO, Vector3::Z), &interval, rng)
+        .expect("should hit from inside");
+    assert_eq!(hit.side, 5);
+    assert_relative_eq!(hit.dist, 1., epsilon = 1e-6);
+    assert!(!hit.front_face);
+}
+
+/// Booleans of two overlapping spheres, with the ray passing through the centres of both
+#[test]
+pub fn csg_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let ray = Ray::new(Point3::new(0., 0., -5.), Vector3::Z);
+    // Spans `z = -1..1` and `z = -1.5..-0.5`
+    let big = SphereMesh::new(Point3::ZERO, 1.);
+    let small = SphereMesh::new(Point3::new(0., 0., -1.), 0.5);
+
+    let union = CsgMesh::<MeshInstance>::union(big, small);
+    let dists = union
+        .intersect_all(&ray, &interval, rng)
+        .iter()
+        .map(|hit| hit.dist)
+        .collect::<Vec<_>>();
+    assert_eq!(dists.len(), 2);
+    assert_relative_eq!(dists[0], 3.5, epsilon = 1e-6);
+    assert_relative_eq!(dists[1], 6., epsilon = 1e-6);
+
+    let intersection = CsgMesh::<MeshInstance>::intersection(big, small);
+    let dists = intersection
+        .intersect_all(&ray, &interval, rng)
+        .iter()
+        .map(|hit| hit.dist)
+        .collect::<Vec<_>>();
+    assert_eq!(dists.len(), 2);
+    assert_relative_eq!(dists[0], 4., epsilon = 1e-6);
+    assert_relative_eq!(dists[1], 4.5, epsilon = 1e-6);
+
+    // The first surface is the inside of the carved-out sphere, so the normal faces back towards the ray
+    let difference = CsgMesh::<MeshInstance>::difference(big, small);
+    let hit = difference
+        .intersect(&ray, &interval, rng)
+        .expect("should hit carved surface");
+    assert_relative_eq!(hit.dist, 4.5, epsilon = 1e-6);
+    assert_relative_eq!(hit.normal.z, -1., epsilon = 1e-6);
+    assert!(hit.front_face);
+
+    // Starting inside the carved-out part
+    let hit = difference
+        .intersect(&Ray::new(Point3::new(0., 0., -0.9), Vector3::Z), &interval, rng)
+        .expect("should hit from inside the hole");
+    assert_relative_eq!(hit.dist, 0.4, epsilon = 1e-6);
+    assert!(hit.front_face);
+}
+
+/// All-intersection queries should reuse the buffers in the per-thread arena, instead of allocating new ones each time
+#[test]
+pub fn intersect_arena_reuses_buffers() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let ray = Ray::new(Point3::new(0., 0., -5.), Vector3::Z);
+    let inner = CsgMesh::<MeshInstance>::union(
+        SphereMesh::new(Point3::ZERO, 1.),
+        SphereMesh::new(Point3::new(0., 0., -1.), 0.5),
+    );
+    let csg = CsgMesh::<MeshInstance>::difference(inner, SphereMesh::new(Point3::ZERO, 0.25));
+
+    intersect_arena::set_capacity(16);
+    assert_eq!(intersect_arena::free_buffers(), 0);
+    assert!(IntersectionList::new().capacity() >= 16);
+
+    // Each level of the CSG takes its own buffer, and gives it back once it's done
+    assert_eq!(csg.intersect_all(&ray, &interval, rng).len(), 4);
+    let warmed_up = intersect_arena::free_buffers();
+    assert!(warmed_up > 0);
+    for _ in 0..100 {
+        assert_eq!(csg.intersect_all(&ray, &interval, rng).len(), 4);
+    }
+    assert_eq!(intersect_arena::free_buffers(), warmed_up, "buffers should be reused");
+
+    // Changing the capacity frees the old buffers
+    intersect_arena::set_capacity(4);
+    assert_eq!(intersect_arena::free_buffers(), 0);
+}
+
+/// SDF nodes should combine like their closure equivalents, and be ray-marchable
+#[test]
+pub fn sdf_node_intersect() {
+    let sphere = SdfNode::sphere(Point3::ZERO, 1.);
+    let cube = SdfNode::cuboid(Point3::new(1., 0., 0.), Vector3::splat(1.));
+    assert_relative_eq!(sphere.eval(Point3::new(0., 3., 0.)), 2.);
+    assert_relative_eq!(cube.eval(Point3::new(1., 0., 0.)), -0.5);
+
+    // Sharp booleans are the same as `min`/`max`
+    let p = Point3::new(0.8, 0.1, 0.);
+    let (s, c) = (sphere.eval(p), cube.eval(p));
+    assert_relative_eq!(sphere.clone().union(cube.clone(), 0.).eval(p), s.min(c));
+    assert_relative_eq!(sphere.clone().intersect(cube.clone(), 0.).eval(p), s.max(c));
+    assert_relative_eq!(sphere.clone().subtract(cube.clone(), 0.).eval(p), s.max(-c));
+    // Smoothing only ever adds material to a union
+    assert!(sphere.clone().union(cube.clone(), 0.5).eval(p) <= s.min(c));
+
+    // The cube cuts the `+x` side off the sphere, leaving a flat face at `x = 0.5`
+    let mesh = RaymarchedIsosurfaceMesh::from_node(sphere.subtract(cube, 0.));
+    assert!(mesh.node().is_some());
+    let hit = mesh
+        .intersect(
+            &Ray::new(Point3::new(3., 0., 0.), -Vector3::X),
+            &Interval::from(0.0..),
+            &mut rand::thread_rng(),
+        )
+        .expect("should hit the cut face");
+    assert_relative_eq!(hit.dist, 2.5, epsilon = 1e-5);
+    assert_relative_eq!(hit.normal.x, 1., epsilon = 1e-3);
+}
+
+/// Rays should step through the empty voxels, and stop at the first solid one
+#[test]
+pub fn voxel_grid_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let red = Colour::from([1., 0., 0.]);
+    // A 4x4x4 grid of unit voxels, with two solid voxels along the `x` axis
+    let voxels = [([2, 1, 1], 1), ([3, 1, 1], 2)];
+    let sparse = VoxelGridMesh::new_sparse(Point3::ZERO, 1., [4, 4, 4], voxels).with_palette([Colour::BLACK, red]);
+    let mut dense = vec![0; 4 * 4 * 4];
+    for ([x, y, z], voxel) in voxels {
+        dense[x + (4 * (y + (4 * z)))] = voxel;
+    }
+    let dense = VoxelGridMesh::new_dense(Point3::ZERO, 1., [4, 4, 4], dense).with_palette([Colour::BLACK, red]);
+
+    for grid in [sparse, dense] {
+        // From outside the grid, passing through two empty voxels first
+        let ray = Ray::new(Point3::new(-1., 1.5, 1.5), Vector3::X);
+        let hit = grid.intersect(&ray, &interval, rng).expect("should hit solid voxel");
+        assert_relative_eq!(hit.dist, 3.);
+        assert_eq!(hit.side, 0);
+        assert_eq!(hit.colour, Some(red));
+        assert!(hit.front_face);
+
+        // Misses the solid voxels entirely
+        assert!(grid
+            .intersect(&Ray::new(Point3::new(-1., 2.5, 1.5), Vector3::X), &interval, rng)
+            .is_none());
+
+        // From inside a solid voxel, leaving through the far side of the solid region
+        let hit = grid
+            .intersect(&Ray::new(Point3::new(2.5, 1.5, 1.5), Vector3::X), &interval, rng)
+            .expect("should hit from inside");
+        assert_relative_eq!(hit.dist, 1.5);
+        assert_eq!(hit.side, 1);
+        assert!(!hit.front_face);
+
+        // Entering straight into a solid voxel, from above
+        let hit = grid
+            .intersect(&Ray::new(Point3::new(2.5, 6., 1.5), -Vector3::Y), &interval, rng)
+            .expect("should hit from above");
+        assert_relative_eq!(hit.dist, 4.);
+        assert_eq!(hit.side, 3);
+    }
+}
+
+/// Subdividing a cube should round it off, while keeping it symmetric and inside the cage
+#[test]
+pub fn subdivision_cube() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let vertices = [
+        [-1., -1., -1.],
+        [1., -1., -1.],
+        [1., 1., -1.],
+        [-1., 1., -1.],
+        [-1., -1., 1.],
+        [1., -1., 1.],
+        [1., 1., 1.],
+        [-1., 1., 1.],
+    ]
+    .map(Point3::from);
+    let faces = [
+        [0, 3, 2, 1],
+        [4, 5, 6, 7],
+        [0, 1, 5, 4],
+        [2, 3, 7, 6],
+        [0, 4, 7, 3],
+        [1, 2, 6, 5],
+    ];
+    // Slightly off-centre, so we don't hit exactly on a vertex
+    let dist_along = |mesh: &SubdivisionMesh, dir: Vector3| {
+        let offset = Vector3::splat(1e-4) - (dir * Vector3::dot(dir, Vector3::splat(1e-4)));
+        let hit = mesh
+            .intersect(&Ray::new((dir * -5.).to_point() + offset, dir), &interval, rng)
+            .expect("should hit surface");
+        assert_relative_eq!(Vector3::dot(hit.normal, dir), -1., epsilon = 1e-3);
+        hit.dist
+    };
+
+    // No subdivision is just the cage
+    let cage = SubdivisionMesh::new(vertices, faces, 0);
+    assert_relative_eq!(dist_along(&cage, Vector3::X), 4., epsilon = 1e-9);
+
+    let smooth = SubdivisionMesh::new(vertices, faces, 3);
+    assert_eq!(smooth.mesh().count(), 6 * 4_usize.pow(3) * 2);
+    let dist = dist_along(&smooth, Vector3::X);
+    assert!(dist > 4. && dist < 5., "should shrink inside the cage, dist: {dist}");
+    assert_relative_eq!(dist_along(&smooth, Vector3::Y), dist, epsilon = 1e-4);
+    assert_relative_eq!(dist_along(&smooth, -Vector3::Z), dist, epsilon = 1e-4);
+}
+
+#[test]
+pub fn curve_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    // Two straight strands along the X axis, the second one above the first
+    let strands =
+        [0., 1.].map(|y| CurveStrand::tapered([[-1., y, 0.], [-0.5, y, 0.], [0.5, y, 0.], [1., y, 0.]], 0.1, 0.1));
+
+    let ribbon = CurveMesh::new(strands.clone(), CurveBasis::Bezier, CurveShape::Ribbon, 4);
+    assert_eq!(ribbon.strand_count(), 2);
+    assert_eq!(ribbon.segment_count(), 8);
+    // Ribbons are flat, so are hit at the centre of the strand, but the normal curves around like a cylinder
+    let hit = ribbon
+        .intersect(&Ray::new([0., 1.05, -5.], Vector3::Z), &interval, rng)
+        .expect("should hit ribbon");
+    assert_relative_eq!(hit.dist, 5., epsilon = 1e-9);
+    assert_eq!(hit.side, 1);
+    assert_relative_eq!(hit.uv.x, 0.5, epsilon = 1e-9);
+    assert!(
+        hit.normal.y > 0. && hit.normal.z < 0.,
+        "normal should curve upwards: {:?}",
+        hit.normal
+    );
+    assert!(ribbon
+        .intersect(&Ray::new([0., 0.5, -5.], Vector3::Z), &interval, rng)
+        .is_none());
+
+    let cylinder = CurveMesh::new(strands, CurveBasis::Bezier, CurveShape::Cylinder, 4);
+    let hit = cylinder
+        .intersect(&Ray::new([0.01, 0., -5.], Vector3::Z), &interval, rng)
+        .expect("should hit cylinder");
+    assert_relative_eq!(hit.dist, 4.9, epsilon = 1e-9);
+    assert_eq!(hit.side, 0);
+    assert_relative_eq!(hit.normal.z, -1., epsilon = 1e-9);
+}
+
+#[test]
+pub fn point_cloud_intersect() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let points = [
+        // Facing the camera
+        Splat::new([0., 0., 0.], 0.5)
+            .with_normal(-Vector3::Z)
+            .with_colour([1., 0., 0.]),
+        // Billboard, behind the first one
+        Splat::new([2., 0., 1.], 0.5),
+    ];
+
+    let disc = PointCloudMesh::new(points, SplatShape::Disc);
+    assert_eq!(disc.count(), 2);
+    let hit = disc
+        .intersect(&Ray::new([0.2, 0.2, -5.], Vector3::Z), &interval, rng)
+        .expect("should hit first point");
+    assert_relative_eq!(hit.dist, 5., epsilon = 1e-9);
+    assert_eq!(hit.side, 0);
+    assert_eq!(hit.colour.map(|c| c[0]), Some(1.));
+    assert!(hit.front_face);
+    // Billboards always face the ray
+    let hit = disc
+        .intersect(&Ray::new([2., -4., -2.], Vector3::new(0., 4., 3.)), &interval, rng)
+        .expect("should hit billboard");
+    assert_eq!(hit.side, 1);
+    assert_relative_eq!(
+        Vector3::dot(hit.normal, Vector3::new(0., 4., 3.).normalize()),
+        -1.,
+        epsilon = 1e-9
+    );
+    assert!(disc
+        .intersect(&Ray::new([0.4, 0.4, -5.], Vector3::Z), &interval, rng)
+        .is_none());
+
+    // Gaussians are always hit in the centre, but rarely near the edge
+    let gaussian = PointCloudMesh::new(points, SplatShape::Gaussian);
+    let mut hits = |pos: [Number; 3]| {
+        (0..1000)
+            .filter(|_| gaussian.intersect(&Ray::new(pos, Vector3::Z), &interval, rng).is_some())
+            .count()
+    };
+    assert_eq!(hits([0., 0., -5.]), 1000);
+    let edge = hits([0.45, 0., -5.]);
+    assert!(edge < 100, "edge should mostly be transparent, hit {edge} times");
+}
+
+#[test]
+pub fn bvh_refit() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let mut bvh = BvhMesh::new(
+        (0..3)
+            .map(|i| SphereMesh::new([i as Number * 3., 0., 0.], 1.))
+            .collect(),
+    );
+    let ray = Ray::new([3., 10., -5.], Vector3::Z);
+    assert!(bvh.intersect(&ray, &interval, rng).is_none());
+
+    // Move the middle sphere up, outside the bounds of the tree
+    for mesh in bvh.meshes_mut() {
+        if mesh.pos().x == 3. {
+            *mesh = SphereMesh::new([3., 10., 0.], 1.);
+        }
+    }
+    assert!(
+        bvh.intersect(&ray, &interval, rng).is_none(),
+        "bounds are out of date until refitted"
+    );
+
+    bvh.refit();
+    let hit = bvh.intersect(&ray, &interval, rng).expect("should hit moved sphere");
+    assert_relative_eq!(hit.dist, 4., epsilon = 1e-9);
+    assert_relative_eq!(bvh.aabb().expect("bvh is bounded").max().y, 11., epsilon = 1e-9);
+}
+
+#[test]
+pub fn polygon_triangulation() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    // A concave L-shape, with the notch in the top right
+    let vertices = [[0., 0.], [2., 0.], [2., 1.], [1., 1.], [1., 2.], [0., 2.]].map(|[x, y]| Point3::new(x, y, 0.));
+    let polygon = [0, 1, 2, 3, 4, 5];
+
+    let triangles = triangulate(&vertices, &polygon);
+    assert_eq!(triangles.len(), 4);
+    let area = triangles
+        .iter()
+        .map(|tri| {
+            let [a, b, c] = tri.map(|i| vertices[i]);
+            Vector3::cross(b - a, c - a).z / 2.
+        })
+        .inspect(|&area| assert!(area > 0., "triangles should keep the winding"))
+        .sum::<Number>();
+    assert_relative_eq!(area, 3., epsilon = 1e-9);
+
+    let mesh = PolygonMesh::new(vertices, [polygon], VertexNormals::Flat);
+    let mut hit = |x: Number, y: Number| mesh.intersect(&Ray::new([x, y, 1.], -Vector3::Z), &interval, rng);
+    assert!(hit(0.5, 1.5).is_some());
+    assert!(hit(1.5, 0.5).is_some());
+    assert!(hit(1.5, 1.5).is_none(), "notch should be empty");
+}
+
+/// Dual contouring should keep the sharp corners of a box, and only sample near the surface
+#[test]
+pub fn polygonised_isosurface() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+
+    let cube = PolygonisedIsosurfaceMesh::new(16, |p: Point3| {
+        let q = (p - Point3::splat(0.5)).abs() - Vector3::splat(0.3);
+        q.max(Vector3::ZERO).length() + q.max_element().min(0.)
+    });
+    // Right next to the corner, which marching cubes would have cut off
+    let hit = cube
+        .intersect(&Ray::new([1.5, 0.795, 0.795], -Vector3::X), &interval, rng)
+        .expect("should hit next to the corner");
+    assert_relative_eq!(hit.dist, 0.7, epsilon = 1e-3);
+    assert_relative_eq!(hit.normal.x, 1., epsilon = 1e-3);
+
+    let sphere = PolygonisedIsosurfaceMesh::new(50, |p: Point3| (p - Point3::splat(0.5)).length() - 0.3);
+    assert_eq!(sphere.resolution(), 64);
+    assert!(
+        sphere.sampled_cells() < 64usize.pow(3) / 10,
+        "empty space shouldn't be sampled ({} cells)",
+        sphere.sampled_cells()
+    );
+    let hit = sphere
+        .intersect(&Ray::new([0.5, 0.5, -1.], Vector3::Z), &interval, rng)
+        .expect("should hit sphere");
+    assert_relative_eq!(hit.dist, 1.2, epsilon = 1e-2);
+}
+
+/// SDFs that aren't true distances should still be hit, if given a Lipschitz bound
+#[test]
+pub fn raymarched_lipschitz() {
+    let rng = &mut rand::thread_rng();
+    let ray = Ray::new([0., 0., -5.], Vector3::Z);
+
+    // A sphere whose distance is three times too large, so unscaled steps would overshoot straight through it
+    let mesh = RaymarchedIsosurfaceMesh::new(|p: Point3| 3. * (p.to_vector().length() - 1.))
+        .with_lipschitz(3.)
+        .with_max_iterations(500);
+    assert_eq!(mesh.lipschitz(), 3.);
+    let hit = mesh
+        .intersect(&ray, &Interval::from(0.0..), rng)
+        .expect("should hit scaled sphere");
+    assert_relative_eq!(hit.dist, 4., epsilon = 1e-5);
+    assert_relative_eq!(hit.normal.z, -1., epsilon = 1e-6);
+    assert!(hit.front_face);
+
+    // Stops once it's past the end of the interval
+    assert!(mesh.intersect(&ray, &Interval::from(0.0..3.), rng).is_none());
+}
+
+/// A flat grid should simplify down to the target, without changing its outline
+#[test]
+pub fn simplify_flat_grid() {
+    let rng = &mut rand::thread_rng();
+    const N: usize = 20;
+    let vertices = (0..=N)
+        .flat_map(|y| (0..=N).map(move |x| Point3::new(x as Number, y as Number, 0.)))
+        .collect::<Vec<_>>();
+    let index = |x: usize, y: usize| (y * (N + 1)) + x;
+    let indices = (0..N)
+        .flat_map(|y| (0..N).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            [
+                [index(x, y), index(x + 1, y), index(x + 1, y + 1)],
+                [index(x, y), index(x + 1, y + 1), index(x, y + 1)],
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mesh = IndexedTriangleMesh::new(vertices, indices, VertexNormals::Flat);
+    assert_eq!(mesh.count(), 2 * N * N);
+
+    let simple = simplify(&mesh, 50);
+    assert!(simple.count() <= 50, "should reach the target (has {})", simple.count());
+    assert!(simple.vertices().len() < mesh.vertices().len());
+
+    let (before, after) = (mesh.aabb().unwrap(), simple.aabb().unwrap());
+    assert_relative_eq!(after.min().x, before.min().x, epsilon = 1e-9);
+    assert_relative_eq!(after.max().y, before.max().y, epsilon = 1e-9);
+    assert_relative_eq!(after.max().z, 0., epsilon = 1e-9);
+    // Still covers the whole grid, including the corners
+    for [x, y] in [[0.1, 0.1], [19.9, 0.1], [10., 10.], [0.1, 19.9], [19.9, 19.9]] {
+        let hit = simple.intersect(&Ray::new([x, y, 1.], -Vector3::Z), &Interval::from(0.0..), rng);
+        assert!(hit.is_some(), "should hit simplified grid at {x}, {y}");
+    }
+}
+
+/// A cube made of separate, inconsistently wound triangles should be cleaned up into a smooth, closed mesh
+#[test]
+pub fn weld_and_fix_winding() {
+    let corners = [
+        [0., 0., 0.],
+        [1., 0., 0.],
+        [1., 1., 0.],
+        [0., 1., 0.],
+        [0., 0., 1.],
+        [1., 0., 1.],
+        [1., 1., 1.],
+        [0., 1., 1.],
+    ]
+    .map(Point3::from);
+    let quads = [
+        [0, 3, 2, 1],
+        [4, 5, 6, 7],
+        [0, 1, 5, 4],
+        [2, 3, 7, 6],
+        [1, 2, 6, 5],
+        [0, 4, 7, 3],
+    ];
+    // Triangle soup: every triangle has its own (slightly noisy) vertices, and every other one is backwards
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    for (i, [a, b, c, d]) in quads.into_iter().enumerate() {
+        for (j, mut tri) in [[a, b, c], [a, c, d]].into_iter().enumerate() {
+            if (i + j) % 2 == 1 {
+                tri.swap(1, 2);
+            }
+            let start = vertices.len();
+            vertices.extend(tri.map(|v| corners[v] + Vector3::splat(1e-9 * start as Number)));
+            indices.push([start, start + 1, start + 2]);
+        }
+    }
+    let soup = IndexedTriangleMesh::new(vertices, indices, VertexNormals::Flat);
+    assert_eq!(soup.vertices().len(), 36);
+
+    let welded = weld(&soup, 1e-6);
+    assert_eq!(welded.vertices().len(), 8);
+    assert_eq!(welded.count(), 12);
+
+    let fixed = fix_winding(&welded);
+    let centre = Point3::splat(0.5);
+    for tri in fixed.indices() {
+        let [a, b, c] = tri.map(|v| fixed.vertices()[v]);
+        let outwards = a - centre;
+        assert!(
+            Vector3::dot(Vector3::cross(b - a, c - a), outwards) > 0.,
+            "triangle {tri:?} should face outwards"
+        );
+    }
+
+    let smooth = recompute_normals(&fixed);
+    for (&v, &n) in smooth.vertices().iter().zip(smooth.normals()) {
+        let expected = (v - centre).normalize();
+        assert_relative_eq!(Vector3::dot(n, expected), 1., epsilon = 1e-6);
+    }
+}
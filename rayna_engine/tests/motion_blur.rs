@@ -0,0 +1,59 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::types::*;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::Mesh;
+use rayna_engine::object::transform::ObjectTransform;
+use rayna_engine::scene::camera::{Camera, CameraMotion};
+use rayna_engine::shared::aabb::HasAabb;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+
+/// A moving transform should be intersected wherever it is at the time of the ray
+#[test]
+pub fn moving_transform() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let sphere = SphereMesh::new(Point3::ZERO, 1.);
+    let transform = ObjectTransform::new_moving(
+        Transform3::IDENTITY,
+        Transform3::from_translation(Vector3::new(4., 0., 0.)),
+    );
+    assert!(transform.is_moving());
+
+    let mut hit_dist = |x: Number, time: Number| {
+        let ray = Ray::new([x, 0., -5.], Vector3::Z).with_time(time);
+        let inner = sphere.intersect(&transform.incoming_ray(&ray), &interval, rng)?;
+        Some(transform.outgoing_intersection(&ray, inner).dist)
+    };
+
+    assert_relative_eq!(hit_dist(0., 0.).expect("should hit at start"), 4., epsilon = 1e-9);
+    assert_eq!(hit_dist(0., 1.), None);
+    assert_relative_eq!(hit_dist(2., 0.5).expect("should hit halfway"), 4., epsilon = 1e-9);
+    assert_relative_eq!(hit_dist(4., 1.).expect("should hit at end"), 4., epsilon = 1e-9);
+
+    // The bounds should cover the whole path
+    let aabb = transform.calculate_aabb(sphere.aabb()).expect("sphere is bounded");
+    assert_relative_eq!(aabb.min().x, -1., epsilon = 1e-9);
+    assert_relative_eq!(aabb.max().x, 5., epsilon = 1e-9);
+}
+
+/// Camera rays should be fired at random times, from wherever the camera is at that time
+#[test]
+pub fn moving_camera() {
+    let rng = &mut rand::thread_rng();
+    let camera = Camera {
+        motion: Some(CameraMotion {
+            pos: Point3::new(0., 2., 0.),
+            fwd: Vector3::Z,
+        }),
+        ..Default::default()
+    };
+    let viewport = camera.calculate_viewport().expect("camera should be valid");
+
+    for _ in 0..100 {
+        let ray = viewport.calc_ray(50., 50., 100., 100., rng);
+        assert!((0.0..=1.).contains(&ray.time()));
+        assert_relative_eq!(ray.pos().y, ray.time() * 2., epsilon = 1e-9);
+        assert_relative_eq!(ray.dir().z, 1., epsilon = 1e-9);
+    }
+}
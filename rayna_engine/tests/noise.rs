@@ -0,0 +1,118 @@
+use approx::assert_relative_eq;
+use noise::NoiseFn;
+use rayna_engine::core::types::*;
+use rayna_engine::shared::noise::{BlueNoise, DistanceMetric, WorleyMode, WorleyNoise};
+
+const POINTS: [[Number; 3]; 5] = [
+    [0.1, 0.2, 0.3],
+    [5.5, -3.25, 0.],
+    [-10.7, 2.9, 4.4],
+    [100.01, 0.5, -0.5],
+    [0.99, 0.99, 0.99],
+];
+
+/// The modes of the noise should be consistent with each other, and repeatable
+#[test]
+pub fn worley_modes() {
+    let worley = |mode| WorleyNoise {
+        mode,
+        ..WorleyNoise::new(7)
+    };
+    let (f1, f2, edges) = (
+        worley(WorleyMode::F1),
+        worley(WorleyMode::F2),
+        worley(WorleyMode::F2MinusF1),
+    );
+    for point in POINTS {
+        let (a, b) = (f1.get(point), f2.get(point));
+        assert!(a >= -1.);
+        assert!(b >= a);
+        assert_relative_eq!(edges.get(point), b - a - 1., epsilon = 1e-9);
+        assert_eq!(a, f1.get(point));
+    }
+
+    let other_seed = WorleyNoise::new(8);
+    assert!(POINTS.iter().any(|&p| other_seed.get(p) != f1.get(p)));
+}
+
+/// The frequency should scale the points, in any number of dimensions
+#[test]
+pub fn worley_frequency() {
+    let plain = WorleyNoise::new(3);
+    let scaled = WorleyNoise { frequency: 4., ..plain };
+    for [x, y, z] in POINTS {
+        assert_relative_eq!(
+            scaled.get([x, y, z]),
+            plain.get([x * 4., y * 4., z * 4.]),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(scaled.get([x, y]), plain.get([x * 4., y * 4.]), epsilon = 1e-9);
+    }
+}
+
+/// Each point is closer by the Chebyshev distance than the Euclidean distance, which is closer than the Manhattan
+/// distance, so the nearest points are too
+#[test]
+pub fn worley_metrics() {
+    let worley = |metric| WorleyNoise {
+        metric,
+        ..WorleyNoise::new(11)
+    };
+    let (chebyshev, euclidean, manhattan) = (
+        worley(DistanceMetric::Chebyshev),
+        worley(DistanceMetric::Euclidean),
+        worley(DistanceMetric::Manhattan),
+    );
+    for point in POINTS {
+        assert!(chebyshev.get(point) <= euclidean.get(point));
+        assert!(euclidean.get(point) <= manhattan.get(point));
+    }
+    assert_eq!(DistanceMetric::Manhattan.distance([0., 0.], [1., -2.]), 3.);
+    assert_eq!(DistanceMetric::Chebyshev.distance([0., 0.], [1., -2.]), 2.);
+}
+
+/// Blue noise should have every value once, and repeat in both directions
+#[test]
+pub fn blue_noise_values() {
+    let size = 16;
+    let blue = BlueNoise::new(size, 3);
+    assert_eq!(blue.size(), size);
+
+    let mut values = (0..size * size)
+        .map(|i| blue.get_pixel(i % size, i / size))
+        .collect::<Vec<_>>();
+    values.sort_by(Number::total_cmp);
+    for (i, value) in values.into_iter().enumerate() {
+        assert_relative_eq!(value, (i as Number + 0.5) / (size * size) as Number, epsilon = 1e-12);
+    }
+
+    for (x, y) in [(0, 0), (5, 11), (15, 15)] {
+        let value = blue.get_pixel(x, y);
+        assert_eq!(blue.get_pixel(x + size, y), value);
+        assert_eq!(blue.get_pixel(x, y + (3 * size)), value);
+        assert_relative_eq!(
+            blue.get([x as Number - size as Number + 0.5, y as Number + 0.5]),
+            (value * 2.) - 1.
+        );
+    }
+
+    let again = BlueNoise::new(size, 3);
+    assert!((0..size).all(|x| blue.get_pixel(x, 7) == again.get_pixel(x, 7)));
+}
+
+/// Neighbouring pixels of blue noise should have very different values, unlike white noise where they are unrelated
+#[test]
+pub fn blue_noise_spread() {
+    let blue = BlueNoise::new(32, 0);
+    let size = blue.size();
+    let mut diff = 0.;
+    for y in 0..size {
+        for x in 0..size {
+            let value = blue.get_pixel(x, y);
+            diff += (value - blue.get_pixel(x + 1, y)).abs() + (value - blue.get_pixel(x, y + 1)).abs();
+        }
+    }
+    let mean_diff = diff / (2 * size * size) as Number;
+    // For uniform white noise, the mean difference is 1/3
+    assert!(mean_diff > 0.38, "mean difference of neighbours was {mean_diff}");
+}
@@ -0,0 +1,123 @@
+use approx::assert_relative_eq;
+use rand::rngs::mock::StepRng;
+use rayna_engine::core::types::*;
+use rayna_engine::material::metal::MetalMaterial;
+use rayna_engine::material::normal_mapped::NormalMappedMaterial;
+use rayna_engine::material::Material;
+use rayna_engine::scene::asset::AssetResolver;
+use rayna_engine::shared::intersect::Intersection;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::texture::normal_map::NormalMapTexture;
+use rayna_engine::texture::solid::SolidTexture;
+
+mod common;
+
+const EPSILON: Number = 1e-6;
+
+fn intersection(normal: Vector3, tangent: Option<Vector3>) -> Intersection {
+    Intersection {
+        tangent,
+        ..common::intersection(Point3::ZERO, normal, Point2::new(0.5, 0.5))
+    }
+}
+
+fn normal_of(map: &NormalMapTexture<SolidTexture>) -> Vector3 {
+    map.normal(&intersection(Vector3::Y, None), &mut StepRng::new(0, 1))
+}
+
+fn map(colour: [Channel; 3]) -> NormalMapTexture<SolidTexture> {
+    NormalMapTexture::from(SolidTexture {
+        albedo: Colour::new(colour),
+    })
+}
+
+fn assert_vec_eq(a: Vector3, b: Vector3) {
+    assert_relative_eq!(a.x, b.x, epsilon = EPSILON);
+    assert_relative_eq!(a.y, b.y, epsilon = EPSILON);
+    assert_relative_eq!(a.z, b.z, epsilon = EPSILON);
+}
+
+/// The colours should be decoded into normalised tangent-space vectors
+#[test]
+pub fn normal_map_decoding() {
+    // Flat
+    assert_vec_eq(normal_of(&map([0.5, 0.5, 1.])), Vector3::Z);
+    // Tilted towards the tangent and bitangent
+    assert_vec_eq(normal_of(&map([0.75, 0.5, 0.75])), Vector3::new(1., 0., 1.).normalize());
+    let tilted = map([0.5, 0.75, 0.75]);
+    assert_vec_eq(normal_of(&tilted), Vector3::new(0., 1., 1.).normalize());
+    assert_vec_eq(
+        normal_of(&NormalMapTexture {
+            flip_green: true,
+            ..tilted.clone()
+        }),
+        Vector3::new(0., -1., 1.).normalize(),
+    );
+
+    // The strength scales the tilt
+    assert_vec_eq(
+        normal_of(&NormalMapTexture {
+            strength: 0.,
+            ..tilted.clone()
+        }),
+        Vector3::Z,
+    );
+    assert_vec_eq(
+        normal_of(&NormalMapTexture { strength: 2., ..tilted }),
+        Vector3::new(0., 2., 1.).normalize(),
+    );
+
+    // Normals pointing into the surface are invalid
+    assert_vec_eq(normal_of(&map([0., 0., 0.])), Vector3::Z);
+}
+
+/// Tangent-space normals should be transformed onto the surface
+#[test]
+pub fn normal_map_to_world() {
+    let surface = Vector3::Y;
+    let tangent = Vector3::new(2., 0.3, 0.);
+    let to_world = NormalMapTexture::<SolidTexture>::to_world;
+
+    assert_vec_eq(to_world(Vector3::Z, surface, tangent), surface);
+    assert_vec_eq(to_world(Vector3::X, surface, tangent), Vector3::X);
+    // The bitangent is `normal x tangent`
+    assert_vec_eq(to_world(Vector3::Y, surface, tangent), -Vector3::Z);
+    // Without a valid tangent, the surface normal is kept
+    assert_vec_eq(to_world(Vector3::X, surface, Vector3::Y * 3.), surface);
+}
+
+/// Normal maps hold data, so they should be loaded without decoding sRGB, so that flat areas stay flat
+#[test]
+pub fn normal_map_loads_linearly() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    image::RgbImage::from_pixel(2, 2, image::Rgb([128, 128, 255]))
+        .save(dir.path().join("flat.png"))
+        .expect("failed saving PNG");
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+
+    let map = NormalMapTexture::load(&resolver, "flat.png").expect("failed loading normal map");
+    let normal = map.normal(&intersection(Vector3::Y, None), &mut StepRng::new(0, 1));
+    assert_relative_eq!(normal.z, 1., epsilon = 1e-4);
+}
+
+/// Normal-mapped materials should shade with the bent normals, oriented along the mesh's tangent
+#[test]
+pub fn normal_mapped_material() {
+    let mirror = MetalMaterial {
+        albedo: SolidTexture::from(Colour::WHITE),
+        fuzz: 0.,
+    };
+    let ray = Ray::new(Point3::new(0., 1., 0.), -Vector3::Y);
+    let rng = &mut StepRng::new(0, 1);
+    let hit = intersection(Vector3::Y, Some(Vector3::X));
+
+    // Flat maps change nothing
+    let flat = NormalMappedMaterial::new(mirror, map([0.5, 0.5, 1.]));
+    assert_vec_eq(flat.scatter(&ray, &hit, rng).expect("should scatter"), Vector3::Y);
+
+    // Tilting the normal 45° towards the tangent reflects the ray along the tangent
+    let tilted = NormalMappedMaterial::new(mirror, map([0.75, 0.5, 0.75]));
+    assert_vec_eq(tilted.scatter(&ray, &hit, rng).expect("should scatter"), Vector3::X);
+    let rotated = intersection(Vector3::Y, Some(Vector3::Z));
+    assert_vec_eq(tilted.scatter(&ray, &rotated, rng).expect("should scatter"), Vector3::Z);
+}
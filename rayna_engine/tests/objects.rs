@@ -0,0 +1,376 @@
+use approx::assert_relative_eq;
+use glamour::AngleConsts;
+use rayna_engine::core::grid::DensityGrid;
+use rayna_engine::core::types::*;
+use rayna_engine::material::isotropic::IsotropicMaterial;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::light::LightMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::advanced::csg::CsgOperation;
+use rayna_engine::mesh::planar::disk::DiskMesh;
+use rayna_engine::mesh::planar::parallelogram::ParallelogramMesh;
+use rayna_engine::mesh::planar::Planar;
+use rayna_engine::mesh::primitive::axis_box::AxisBoxMesh;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::clipped::{ClipPlane, ClippedObject};
+use rayna_engine::object::csg::CsgObject;
+use rayna_engine::object::group::GroupObject;
+use rayna_engine::object::instanced::{Instance, InstancedObject};
+use rayna_engine::object::light::{LightObject, LightShape};
+use rayna_engine::object::list::ObjectList;
+use rayna_engine::object::lod::LodObject;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::volumetric::VolumetricObject;
+use rayna_engine::object::{Object, ObjectInstance};
+use rayna_engine::scene::StandardScene;
+use rayna_engine::shared::aabb::HasAabb;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::texture::grid::GridTexture;
+use rayna_engine::texture::TextureInstance;
+
+type Mat = MaterialInstance<TextureInstance>;
+type Obj = ObjectInstance<MeshInstance, Mat>;
+
+/// Booleans of two overlapping spheres with different materials, where each surface should keep the material of the
+/// object it came from
+#[test]
+pub fn csg_object_materials() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let ray = Ray::new([0., 0., -5.], Vector3::Z);
+    let sphere = |centre: [Number; 3], radius: Number, albedo: [Channel; 3]| -> Obj {
+        let material: Mat = LambertianMaterial { albedo: albedo.into() }.into();
+        SimpleObject::new(SphereMesh::new(centre, radius), material, None).into()
+    };
+    let material = |object: &Obj| match object {
+        ObjectInstance::SimpleObject(object) => object.material() as *const Mat,
+        _ => unreachable!("only simple objects are used"),
+    };
+
+    // Spans `z = -1..1` and `z = -1.5..-0.5`
+    let big = sphere([0., 0., 0.], 1., [1., 0., 0.]);
+    let small = sphere([0., 0., -1.], 0.5, [0., 0., 1.]);
+
+    // The carved-out surface is the small sphere's
+    let difference = CsgObject::<Obj>::new(big.clone(), small.clone(), CsgOperation::Difference);
+    let hit = difference
+        .full_intersect(&ray, &interval, rng)
+        .expect("should hit carved surface");
+    assert_relative_eq!(hit.intersection.dist, 4.5, epsilon = 1e-6);
+    assert_relative_eq!(hit.intersection.normal.z, -1., epsilon = 1e-6);
+    assert!(std::ptr::eq(hit.material, material(difference.b())));
+
+    // Enters the big sphere while already inside the small one
+    let intersection = CsgObject::<Obj>::intersection(big.clone(), small.clone());
+    let hits = intersection.full_intersect_all(&ray, &interval, rng);
+    assert_eq!(hits.len(), 2);
+    assert_relative_eq!(hits[0].intersection.dist, 4., epsilon = 1e-6);
+    assert!(std::ptr::eq(hits[0].material, material(intersection.a())));
+    assert!(std::ptr::eq(hits[1].material, material(intersection.b())));
+
+    // Booleans can be nested, through [ObjectInstance]
+    let nested = CsgObject::<Obj>::union(Obj::from(intersection), sphere([0., 0., 5.], 1., [0., 1., 0.]));
+    let hits = nested.full_intersect_all(&ray, &interval, rng);
+    assert_eq!(hits.len(), 4);
+    assert_relative_eq!(hits[2].intersection.dist, 9., epsilon = 1e-6);
+}
+
+/// The level of detail should be picked based on how far away the ray starts, scaled by the quality
+#[test]
+pub fn lod_object_levels() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let material: Mat = LambertianMaterial::default().into();
+    // A smaller sphere stands in for the low-detail version, so it's obvious which level was hit
+    let levels = [
+        (10., SphereMesh::new(Point3::ZERO, 0.5)),
+        (0., SphereMesh::new(Point3::ZERO, 1.)),
+    ];
+    let mut object = LodObject::<MeshInstance, Mat>::new(levels, material, None);
+    assert_eq!(object.levels()[0].0, 0.);
+
+    let hit = |object: &LodObject<MeshInstance, Mat>, z: Number, rng: &mut _| {
+        let ray = Ray::new([0., 0., z], Vector3::Z);
+        object
+            .full_intersect(&ray, &interval, rng)
+            .expect("should hit")
+            .intersection
+            .dist
+    };
+    assert_eq!(object.level_for(Point3::new(0., 0., -5.)), 0);
+    assert_relative_eq!(hit(&object, -5., rng), 4., epsilon = 1e-9);
+    assert_eq!(object.level_for(Point3::new(0., 0., -20.)), 1);
+    assert_relative_eq!(hit(&object, -20., rng), 19.5, epsilon = 1e-9);
+
+    // Higher quality keeps the detailed level out to further away
+    object.set_lod_quality(4.);
+    assert_relative_eq!(hit(&object, -20., rng), 19., epsilon = 1e-9);
+}
+
+/// Samples should be spread over the surface of each light, and the lights should be found in world-space
+#[test]
+pub fn light_object_sampling() {
+    let rng = &mut rand::thread_rng();
+    let material: Mat = LightMaterial {
+        emissive: [1.; 3].into(),
+        group: None,
+    }
+    .into();
+
+    let quad = LightObject::<Mat>::new(
+        ParallelogramMesh::new(Planar::new(Point3::ZERO, [2., 0., 0.], [0., 3., 0.])),
+        material.clone(),
+    );
+    assert_relative_eq!(quad.pdf_area(), 1. / 6., epsilon = 1e-9);
+    for _ in 0..100 {
+        let sample = quad.sample_surface(rng);
+        assert!((0.0..=2.).contains(&sample.pos.x) && (0.0..=3.).contains(&sample.pos.y));
+        assert_relative_eq!(sample.pos.z, 0.);
+        assert_relative_eq!(sample.normal.z.abs(), 1.);
+    }
+
+    let ring = LightObject::<Mat>::new(
+        DiskMesh::new_annulus(Point3::ZERO, Vector3::Z, 2., 1.),
+        material.clone(),
+    );
+    assert_relative_eq!(ring.pdf_area(), 1. / (3. * Number::PI), epsilon = 1e-9);
+    for _ in 0..100 {
+        let dist = ring.sample_surface(rng).pos.to_vector().length();
+        assert!(
+            (1. - 1e-9..=2. + 1e-9).contains(&dist),
+            "sample should be on the ring (was {dist})"
+        );
+    }
+
+    // Lights inside a scaled group should be scaled too
+    let sphere = LightObject::<Mat>::new(SphereMesh::new([1., 0., 0.], 1.), material);
+    let group = ObjectList::<Obj>::new_uncorrected([sphere.into()], Transform3::from_scale(Vector3::splat(2.)));
+    let scene = StandardScene {
+        objects: group.into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let lights = scene.lights();
+    assert_eq!(lights.len(), 1);
+    let LightShape::Sphere(mesh) = lights[0].shape else {
+        panic!("light should still be a sphere");
+    };
+    assert_relative_eq!((mesh.pos() - Point3::new(2., 0., 0.)).length(), 0., epsilon = 1e-9);
+    assert_relative_eq!(mesh.radius(), 2., epsilon = 1e-9);
+    assert_relative_eq!(lights[0].shape.pdf_area(), 1. / (16. * Number::PI), epsilon = 1e-9);
+    let sample = lights[0].shape.sample_surface(rng);
+    assert_relative_eq!((sample.pos - mesh.pos()).length(), 2., epsilon = 1e-9);
+}
+
+/// Objects made of meshes (and objects that hold other objects) should all add their lights to the scene, moved into
+/// world-space
+#[test]
+pub fn nested_lights() {
+    let light: Mat = LightMaterial {
+        emissive: [1.; 3].into(),
+        group: None,
+    }
+    .into();
+    let diffuse: Mat = LambertianMaterial::default().into();
+    let sphere = SphereMesh::new(Point3::ZERO, 1.);
+    let lights = |object: Obj| {
+        let scene = StandardScene {
+            objects: object,
+            skybox: WhiteSkybox.into(),
+        };
+        scene
+            .lights()
+            .iter()
+            .map(|light| match light.shape {
+                LightShape::Sphere(mesh) => mesh.pos(),
+                shape => panic!("light should still be a sphere (was {shape:?})"),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Only the instances with light materials are lights
+    let instanced = InstancedObject::<MeshInstance, Mat>::new_uncorrected(
+        MeshInstance::from(sphere),
+        light.clone(),
+        [
+            Instance::new(Transform3::from_translation(Vector3::new(5., 0., 0.))),
+            Instance::new(Transform3::from_translation(Vector3::new(0., 5., 0.))).with_material(diffuse.clone()),
+        ],
+    );
+    assert_eq!(lights(instanced.into()), [Point3::new(5., 0., 0.)]);
+
+    let lod = LodObject::<MeshInstance, Mat>::new_uncorrected(
+        [(0., sphere)],
+        light.clone(),
+        Transform3::from_translation(Vector3::new(0., 0., 3.)),
+    );
+    assert_eq!(lights(lod.into()), [Point3::new(0., 0., 3.)]);
+    let dark_lod = LodObject::<MeshInstance, Mat>::new_uncorrected([(0., sphere)], diffuse, None);
+    assert!(lights(dark_lod.into()).is_empty());
+
+    let volume = VolumetricObject::<MeshInstance, Mat>::new_uncorrected(
+        sphere,
+        light.clone(),
+        1.,
+        Transform3::from_translation(Vector3::new(-2., 0., 0.)),
+    );
+    assert_eq!(lights(volume.into()), [Point3::new(-2., 0., 0.)]);
+
+    // The second object of a difference is never seen from outside, so isn't a light
+    let light_at =
+        |x: Number| -> Obj { LightObject::<Mat>::new(SphereMesh::new([x, 0., 0.], 1.), light.clone()).into() };
+    let union = CsgObject::<Obj>::new(light_at(0.), light_at(1.), CsgOperation::Union);
+    assert_eq!(lights(union.into()), [Point3::ZERO, Point3::new(1., 0., 0.)]);
+    let difference = CsgObject::<Obj>::new(light_at(0.), light_at(1.), CsgOperation::Difference);
+    assert_eq!(lights(difference.into()), [Point3::ZERO]);
+}
+
+/// An empty density texture should stop the volume from being hit at all, and a full one should be the same as a
+/// constant density
+#[test]
+pub fn volumetric_density_texture() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let ray = Ray::new([0., 0., -5.], Vector3::Z);
+    let volume = |density: Channel| {
+        let material: Mat = IsotropicMaterial::default().into();
+        VolumetricObject::<MeshInstance, Mat>::new(SphereMesh::new(Point3::ZERO, 1.), material, 1000., None)
+            .with_density_texture([density; 3])
+    };
+
+    let empty = volume(0.);
+    assert!((0..100).all(|_| empty.full_intersect(&ray, &interval, rng).is_none()));
+
+    let full = volume(1.);
+    for _ in 0..100 {
+        let hit = full
+            .full_intersect(&ray, &interval, rng)
+            .expect("dense volume should be hit");
+        assert!((4.0..4.1).contains(&hit.intersection.dist));
+    }
+
+    // With half the density, hits should be twice as far in on average
+    let half = volume(0.5);
+    let mean = (0..1000)
+        .map(|_| {
+            half.full_intersect(&ray, &interval, rng)
+                .expect("volume should be hit")
+                .intersection
+                .dist
+                - 4.
+        })
+        .sum::<Number>()
+        / 1000.;
+    assert_relative_eq!(mean, 2e-3, max_relative = 0.2);
+}
+
+/// Density grids should interpolate between voxels, and can be used as the density of a volume
+#[test]
+pub fn density_grid_volume() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+
+    // A single dense voxel at `x = 2`, with an empty voxel either side
+    let grid = DensityGrid::from_voxels([([1, 0, 0], 0.), ([2, 0, 0], 4.), ([3, 0, 0], 0.)]);
+    assert_eq!(grid.dims(), [3, 1, 1]);
+    assert_eq!(grid.max_value(), 4.);
+    let bounds = grid.bounds().expect("grid isn't empty");
+    assert_eq!(bounds.min(), Point3::new(0.5, -0.5, -0.5));
+    assert_eq!(bounds.max(), Point3::new(3.5, 0.5, 0.5));
+    assert_relative_eq!(grid.sample(Point3::new(2., 0., 0.)), 4.);
+    assert_relative_eq!(grid.sample(Point3::new(1.5, 0., 0.)), 2.);
+    assert_relative_eq!(grid.sample(Point3::new(10., 0., 0.)), 0.);
+
+    // Rays through the dense voxel should be scattered, but not those through the empty ones
+    let material: Mat = IsotropicMaterial::default().into();
+    let volume = VolumetricObject::<MeshInstance, Mat>::new_uncorrected(
+        AxisBoxMesh::new(bounds.min(), bounds.max()),
+        material,
+        1000.,
+        None,
+    )
+    .with_density_texture(GridTexture::from(grid));
+    let ray = |x: Number| Ray::new([x, 0., -5.], Vector3::Z);
+    for _ in 0..100 {
+        let hit = volume
+            .full_intersect(&ray(2.), &interval, rng)
+            .expect("dense voxel should be hit");
+        assert!((4.5..4.6).contains(&hit.intersection.dist));
+        assert!(volume.full_intersect(&ray(1.), &interval, rng).is_none());
+    }
+}
+
+/// The transform of a group should move all its children together, and nested groups should combine their transforms
+#[test]
+pub fn group_object_transform() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let sphere = |x: Number| -> Obj {
+        let material: Mat = LambertianMaterial::default().into();
+        SimpleObject::new(SphereMesh::new([x, 0., 0.], 0.5), material, None).into()
+    };
+    let dist = |object: &GroupObject<Obj>, x: Number, y: Number, rng: &mut _| {
+        let ray = Ray::new([x, y, -5.], Vector3::Z);
+        object
+            .full_intersect(&ray, &interval, rng)
+            .map(|hit| hit.intersection.dist)
+    };
+
+    let mut group = GroupObject::new(
+        [sphere(-1.), sphere(1.)],
+        Transform3::from_translation(Vector3::new(0., 2., 0.)),
+    );
+    assert_relative_eq!(dist(&group, 1., 2., rng).expect("should hit"), 4.5, epsilon = 1e-9);
+    assert_eq!(dist(&group, 1., 0., rng), None);
+
+    // Rotating a quarter turn around the centre of the group swaps the spheres onto the y-axis
+    group.set_transform(Transform3::from_axis_angle(Vector3::Z, Angle::from_degrees(90.)));
+    assert_relative_eq!(dist(&group, 0., 1., rng).expect("should hit"), 4.5, epsilon = 1e-9);
+    assert_relative_eq!(dist(&group, 0., -1., rng).expect("should hit"), 4.5, epsilon = 1e-9);
+    assert_eq!(dist(&group, 1., 0., rng), None);
+    let aabb = group.aabb().expect("group is bounded");
+    assert!(aabb.min().y < -1.4 && aabb.max().y > 1.4);
+
+    let outer = GroupObject::new_uncorrected([group.into()], Transform3::from_translation(Vector3::new(3., 0., 0.)));
+    assert_relative_eq!(dist(&outer, 3., 1., rng).expect("should hit"), 4.5, epsilon = 1e-9);
+    assert_eq!(dist(&outer, 0., 1., rng), None);
+}
+
+/// Clipping planes should cut away part of an object, and the cut should be filled in if there is a cap material
+#[test]
+pub fn clipped_object_caps() {
+    let rng = &mut rand::thread_rng();
+    let interval = Interval::from(0.0..);
+    let material: Mat = LambertianMaterial::default().into();
+    let sphere: Obj = SimpleObject::new(SphereMesh::new(Point3::ZERO, 1.), material.clone(), None).into();
+    // Removes the front half of the sphere (facing the ray)
+    let plane = ClipPlane::new(Point3::ZERO, -Vector3::Z);
+    let ray = Ray::new([0., 0., -5.], Vector3::Z);
+
+    let open = ClippedObject::<Obj>::new(sphere.clone(), [plane]);
+    let hit = open.full_intersect(&ray, &interval, rng).expect("should see inside");
+    assert_relative_eq!(hit.intersection.dist, 6., epsilon = 1e-9);
+    assert!(!hit.intersection.front_face);
+
+    let capped = ClippedObject::<Obj>::new(sphere, [plane]).with_cap_material(material);
+    let hit = capped.full_intersect(&ray, &interval, rng).expect("should hit the cap");
+    assert_relative_eq!(hit.intersection.dist, 5., epsilon = 1e-9);
+    assert!(hit.intersection.front_face);
+    assert_eq!(hit.intersection.normal, -Vector3::Z);
+
+    // Rays from the other side should see the uncut half, and rays missing the sphere shouldn't hit the cap
+    let back = Ray::new([0., 0., 5.], -Vector3::Z);
+    assert_relative_eq!(
+        capped
+            .full_intersect(&back, &interval, rng)
+            .expect("should hit")
+            .intersection
+            .dist,
+        4.,
+        epsilon = 1e-9
+    );
+    let miss = Ray::new([2., 0., -5.], Vector3::Z);
+    assert!(capped.full_intersect(&miss, &interval, rng).is_none());
+}
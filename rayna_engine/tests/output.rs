@@ -0,0 +1,87 @@
+use approx::assert_relative_eq;
+use rayna_engine::core::colour::{linear_to_srgb, srgb_to_linear};
+use rayna_engine::core::types::*;
+use rayna_engine::render::output::{save, OutputError, OutputFormat};
+use rayna_engine::scene::asset::AssetResolver;
+use std::convert::identity;
+
+/// Saves an image with some HDR pixels in each of the formats, and reads them back to check they were converted
+/// from linear to sRGB (and clamped) properly
+#[test]
+pub fn save_formats() {
+    let img = Image::from_fn(4, 2, |x, y| Colour::from([x as Channel * 0.5, y as Channel, 0.]));
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+
+    let png = dir.path().join("img.png");
+    save(&img, &png, OutputFormat::from_path(&png).unwrap(), identity).expect("failed saving PNG");
+    let loaded = image::open(&png).expect("failed loading PNG").into_rgb8();
+    assert_eq!(loaded.dimensions(), (4, 2));
+    // Linear 0.5 is about 0.735 in sRGB
+    assert_eq!(loaded[(1, 0)].0, [188, 0, 0]);
+    // HDR values are clamped
+    assert_eq!(loaded[(3, 1)].0, [255, 255, 0]);
+
+    let png16 = dir.path().join("img16.png");
+    save(&img, &png16, OutputFormat::Png16, identity).expect("failed saving 16-bit PNG");
+    let loaded = image::open(&png16).expect("failed loading PNG").into_rgb16();
+    let [r, g, b] = loaded[(1, 0)].0;
+    assert!(
+        r.abs_diff(48_192) <= 1 && g == 0 && b == 0,
+        "16-bit value should be more precise"
+    );
+
+    // The tone-mapping is applied before the conversion to sRGB
+    let halved = dir.path().join("halved.png");
+    save(&img, &halved, OutputFormat::Png, |c| c * 0.5).expect("failed saving PNG");
+    let loaded = image::open(&halved).expect("failed loading PNG").into_rgb8();
+    assert_eq!(loaded[(2, 0)].0, [188, 0, 0]);
+
+    let jpeg = dir.path().join("img.jpg");
+    assert_eq!(
+        OutputFormat::from_path(&jpeg).unwrap(),
+        OutputFormat::Jpeg { quality: 90 }
+    );
+    save(&img, &jpeg, OutputFormat::Jpeg { quality: 100 }, identity).expect("failed saving JPEG");
+    assert_eq!(
+        image::open(&jpeg)
+            .expect("failed loading JPEG")
+            .into_rgb8()
+            .dimensions(),
+        (4, 2)
+    );
+
+    let exr = dir.path().join("img.exr");
+    save(&img, &exr, OutputFormat::Exr, identity).expect("failed saving EXR");
+    let loaded = image::open(&exr).expect("failed loading EXR").into_rgb32f();
+    // Not clamped or converted
+    assert_eq!(loaded[(3, 1)].0, [1.5, 1., 0.]);
+
+    assert!(matches!(
+        OutputFormat::from_path("img.unknown"),
+        Err(OutputError::UnknownFormat { .. })
+    ));
+}
+
+/// Saving an image (which sRGB-encodes it) and loading it back (which decodes it) should give back the original
+/// linear colours, so that nothing is gamma-corrected twice
+#[test]
+pub fn srgb_roundtrip() {
+    assert_relative_eq!(srgb_to_linear(0.5), 0.214, epsilon = 1e-3);
+    for i in 0..=100 {
+        let c = i as Channel / 100.;
+        assert_relative_eq!(srgb_to_linear(linear_to_srgb(c)), c, epsilon = 1e-5);
+    }
+
+    let img = Image::from_fn(8, 1, |x, _| Colour::from([x as Channel / 7., 0.5, 0.01]));
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    save(&img, dir.path().join("img16.png"), OutputFormat::Png16, identity).expect("failed saving PNG");
+    save(&img, dir.path().join("img.exr"), OutputFormat::Exr, identity).expect("failed saving EXR");
+
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+    for file in ["img16.png", "img.exr"] {
+        let loaded = resolver.load_image(file).expect("failed loading image");
+        for (a, b) in img.iter().flat_map(|c| c.0).zip(loaded.iter().flat_map(|c| c.0)) {
+            assert_relative_eq!(a, b, epsilon = 1e-3);
+        }
+    }
+}
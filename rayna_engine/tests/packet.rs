@@ -0,0 +1,136 @@
+#![feature(portable_simd)]
+
+use rand::{Rng, SeedableRng};
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::planar::infinite_plane::InfinitePlaneMesh;
+use rayna_engine::mesh::planar::Planar;
+use rayna_engine::mesh::primitive::axis_box::AxisBoxMesh;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::bvh::BvhObject;
+use rayna_engine::object::list::ObjectList;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::{Object, ObjectInstance};
+use rayna_engine::shared::aabb::HasAabb;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::shared::ray_packet::{PacketMask, RayPacket};
+use rayna_engine::texture::TextureInstance;
+use std::simd::{LaneCount, SupportedLaneCount};
+
+type Mat = MaterialInstance<TextureInstance>;
+type Obj = ObjectInstance<MeshInstance, Mat>;
+
+fn random_objects(rng: &mut impl Rng) -> Vec<Obj> {
+    let material: Mat = LambertianMaterial::default().into();
+    (0..200)
+        .map(|i| {
+            let centre = Point3::new(
+                rng.gen_range(-10.0..10.),
+                rng.gen_range(-10.0..10.),
+                rng.gen_range(5.0..30.),
+            );
+            let mesh: MeshInstance = match i % 2 {
+                0 => SphereMesh::new(centre, rng.gen_range(0.2..1.5)).into(),
+                _ => {
+                    let size = rng.gen_range(0.2..2.);
+                    AxisBoxMesh::new_centred(centre, Size3::new(size, size, size)).into()
+                }
+            };
+            SimpleObject::new(mesh, material.clone(), None).into()
+        })
+        .collect()
+}
+
+/// Checks that tracing `L` rays at a time as a packet gives exactly the same intersections as tracing them one at a
+/// time, whether or not the packet is coherent
+fn assert_packets_match<const L: usize>(object: &impl Object, rng: &mut impl Rng)
+where
+    LaneCount<L>: SupportedLaneCount,
+{
+    let interval = Interval::from(1e-3..);
+    for spread in [0.01, 2.] {
+        for _ in 0..500 {
+            let rays: [Ray; L] = std::array::from_fn(|_| {
+                let dir = Vector3::new(rng.gen_range(-spread..spread), rng.gen_range(-spread..spread), 1.);
+                Ray::new(Point3::new(rng.gen_range(-0.1..0.1), 0., 0.), dir)
+            });
+            let packet = RayPacket::new(rays);
+            let active = PacketMask::from_array(std::array::from_fn(|i| i != 2));
+            let hits = object.full_intersect_packet(&packet, &[interval; L], active, rng);
+
+            for (i, (ray, hit)) in std::iter::zip(rays, hits).enumerate() {
+                let expected = match active.test(i) {
+                    true => object.full_intersect(&ray, &interval, rng),
+                    false => None,
+                };
+                assert_eq!(
+                    hit.map(|h| h.intersection),
+                    expected.map(|h| h.intersection),
+                    "ray {ray:?} (spread {spread})"
+                );
+            }
+        }
+    }
+}
+
+/// Tracing rays as a packet should give exactly the same intersections as tracing them one at a time, whether or not
+/// the packet is coherent
+#[test]
+pub fn packets_match_single_rays() {
+    let rng = &mut rand::rngs::SmallRng::seed_from_u64(0);
+    let bvh = BvhObject::<Obj>::new_uncorrected(random_objects(rng), None);
+
+    assert_packets_match::<4>(&bvh, rng);
+    assert_packets_match::<8>(&bvh, rng);
+
+    let interval = Interval::from(1e-3..);
+    for _ in 0..500 {
+        let rays: [Ray; 4] = std::array::from_fn(|_| {
+            let dir = Vector3::new(rng.gen_range(-2.0..2.), rng.gen_range(-2.0..2.), 1.);
+            Ray::new(Point3::new(rng.gen_range(-0.1..0.1), 0., 0.), dir)
+        });
+        let packet = RayPacket::new(rays);
+        for aabb in bvh.objects().filter_map(|o| o.aabb().copied()).take(20) {
+            let mask = aabb.hit_packet(&packet, &[interval; 4]);
+            let expected = PacketMask::from_array(rays.map(|ray| aabb.hit(&ray, &interval)));
+            assert_eq!(mask, expected, "{aabb:?}");
+        }
+    }
+}
+
+/// Packets traced through an [ObjectList] should find the same hits as single rays, including on its unbounded
+/// objects, and with the list's transform applied
+#[test]
+pub fn object_list_packets() {
+    let rng = &mut rand::rngs::SmallRng::seed_from_u64(1);
+    let material: Mat = LambertianMaterial::default().into();
+    let mut objects = random_objects(rng);
+    // A floor and a ceiling, facing into the scene
+    for (y, u, v) in [(-6., Vector3::Z, Vector3::X), (6., Vector3::X, Vector3::Z)] {
+        let plane = Planar::new(Point3::new(0., y, 0.), u, v);
+        objects.push(SimpleObject::new(InfinitePlaneMesh::from(plane), material.clone(), None).into());
+    }
+
+    let list = ObjectList::<Obj>::new_uncorrected(objects.clone(), None);
+    assert_eq!(list.unbounded().len(), 2);
+    assert_packets_match::<4>(&list, rng);
+    assert_packets_match::<8>(&list, rng);
+
+    let transformed: Obj =
+        ObjectList::new_uncorrected(objects, Transform3::from_translation(Vector3::new(1., -2., 3.))).into();
+    assert_packets_match::<4>(&transformed, rng);
+    assert_packets_match::<8>(&transformed, rng);
+}
+
+/// All the rays in a coherent packet point into the same octant
+#[test]
+pub fn packet_coherence() {
+    let packet = |dirs: [[Number; 3]; 4]| RayPacket::new(dirs.map(|dir| Ray::new(Point3::ZERO, dir)));
+
+    assert!(packet([[0.1, 0.2, 1.], [0.3, 0.1, 1.], [0.2, 0.2, 1.], [0.1, 0.1, 1.]]).is_coherent());
+    assert!(!packet([[0.1, 0.2, 1.], [-0.3, 0.1, 1.], [0.2, 0.2, 1.], [0.1, 0.1, 1.]]).is_coherent());
+    assert!(!packet([[0.1, 0.2, 1.], [0.3, 0.1, 1.], [0.2, 0.2, -1.], [0.1, 0.1, 1.]]).is_coherent());
+}
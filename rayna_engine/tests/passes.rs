@@ -0,0 +1,251 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::Image;
+use rayna_engine::render::cancel::CancelToken;
+use rayna_engine::render::progress::RenderProgress;
+use rayna_engine::render::render::RenderStats;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod common;
+
+/// The passes should accumulate until the target is reached exactly, and then stop rendering new samples
+#[test]
+pub fn passes_stop_at_target() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(64_usize),
+        height: nonzero!(64_usize),
+        samples: nonzero!(4_usize),
+        target_samples: Some(nonzero!(10_usize)),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+
+    // 4 + 4 + 2 samples
+    for (passes, samples) in [(1, 4), (2, 8), (3, 10)] {
+        let stats = renderer.render().stats;
+        assert_eq!([stats.accum_frames, stats.accum_samples], [passes, samples]);
+        assert_eq!(stats.finished, samples == 10);
+    }
+    let last = renderer.render();
+    let again = renderer.render();
+    assert_eq!([again.stats.accum_frames, again.stats.accum_samples], [3, 10]);
+    assert!(last.img.iter().eq(again.img.iter()), "no more samples should be taken");
+
+    renderer.clear_accumulation();
+    let stats = renderer.render().stats;
+    assert_eq!([stats.accum_frames, stats.accum_samples], [1, 4]);
+}
+
+/// Once the time limit is used up, no more passes should be rendered
+#[test]
+pub fn passes_stop_at_time_limit() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(64_usize),
+        height: nonzero!(64_usize),
+        max_time: Some(Duration::ZERO),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+
+    // The first pass is always rendered, since there is nothing to return before it
+    for _ in 0..3 {
+        let stats = renderer.render().stats;
+        assert_eq!(stats.accum_frames, 1);
+        assert!(stats.finished);
+    }
+}
+
+/// Rendering into an existing image should give the same image as a normal render, resizing the image to fit, and the
+/// accumulation should be readable without rendering anything
+#[test]
+pub fn render_into_existing_image() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(64_usize),
+        height: nonzero!(48_usize),
+        samples: nonzero!(4_usize),
+        target_samples: Some(nonzero!(8_usize)),
+        deterministic: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let new_renderer = || {
+        Renderer::<_, _, common::Rng>::new_from(
+            preset.scene.clone(),
+            preset.camera,
+            opts,
+            common::RENDERER_THREAD_COUNT,
+        )
+        .expect("failed creating renderer")
+    };
+    let (mut rendered, mut into) = (new_renderer(), new_renderer());
+
+    let mut target = Image::new_blank(1, 1);
+    assert!(!into.read_accumulation(&mut target), "nothing has been accumulated yet");
+    assert_eq!([target.width(), target.height()], [1, 1]);
+
+    // Including once the render is finished, and the last pass is reused
+    for _ in 0..3 {
+        let render = rendered.render();
+        let stats = into.render_into(&mut target, ());
+        assert_eq!([target.width(), target.height()], [64, 48]);
+        assert_eq!(stats.accum_samples, render.stats.accum_samples);
+        assert!(render.img.iter().eq(target.iter()), "images should be identical");
+    }
+
+    // Nothing is post-processed with these options, so the accumulation is the same as the image
+    let mut accum = Image::new_blank(64, 48);
+    assert!(into.read_accumulation(&mut accum));
+    assert!(accum.iter().eq(target.iter()));
+}
+
+/// Keeps cancelling until `render` returns, since it can't be known exactly when the pass starts
+fn cancel_while(cancel: &CancelToken, render: impl FnOnce() -> RenderStats) -> RenderStats {
+    let done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                cancel.cancel();
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        });
+        let stats = render();
+        done.store(true, Ordering::Relaxed);
+        stats
+    })
+}
+
+/// Cancelling a pass while it's being rendered should throw it away (keeping the passes before it), but cancelling in
+/// between passes shouldn't affect the next one
+#[test]
+pub fn cancel_pass_in_progress() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        samples: nonzero!(64_usize),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    renderer.prepare_scene(|_| ());
+    let cancel = renderer.cancel_token();
+
+    cancel.cancel();
+    let stats = renderer.render().stats;
+    assert!(
+        !stats.cancelled,
+        "cancelling before the pass starts shouldn't do anything"
+    );
+    assert_eq!(stats.accum_frames, 1);
+
+    let stats = cancel_while(&cancel, || renderer.render().stats);
+    assert!(stats.cancelled);
+    assert_eq!([stats.accum_frames, stats.accum_samples], [1, 64]);
+    let mut before = Image::new_blank(1, 1);
+    assert!(renderer.read_accumulation(&mut before));
+
+    let stats = renderer.render().stats;
+    assert!(!stats.cancelled);
+    assert_eq!(stats.accum_frames, 2);
+
+    // Cancelling the next pass should leave the accumulation exactly as it was after the last one
+    let mut after = Image::new_blank(1, 1);
+    assert!(renderer.read_accumulation(&mut after));
+    assert!(cancel_while(&cancel, || renderer.render().stats).cancelled);
+    let mut cancelled = Image::new_blank(1, 1);
+    assert!(renderer.read_accumulation(&mut cancelled));
+    assert!(cancelled.iter().eq(after.iter()), "the earlier passes should be kept");
+    assert!(!cancelled.iter().eq(before.iter()));
+}
+
+/// The progress should be reported once for every tile of the pass
+#[test]
+pub fn progress_reported_per_tile() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(64_usize),
+        height: nonzero!(40_usize),
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+
+    let reports = Mutex::new(vec![]);
+    let mut target = Image::new_blank(0, 0);
+    renderer.render_into(&mut target, |progress: RenderProgress| {
+        reports.lock().unwrap().push(progress)
+    });
+    let mut reports = reports.into_inner().unwrap();
+    // 4 tiles across, and 3 down (the last row being smaller)
+    assert!(reports.iter().all(|p| p.tiles_total == 12));
+    reports.sort_by_key(|p| p.tiles_done);
+    assert!(reports.iter().map(|p| p.tiles_done).eq(1..=12));
+    let last = reports.last().unwrap();
+    assert!(last.is_done());
+    assert_eq!(last.fraction(), 1.);
+    assert_eq!(last.remaining(), Some(Duration::ZERO));
+}
+
+/// The time left should be estimated from how long the tiles so far took
+#[test]
+pub fn progress_estimates_remaining() {
+    let progress = RenderProgress {
+        tiles_done: 0,
+        tiles_total: 10,
+        elapsed: Duration::from_secs(1),
+    };
+    assert_eq!(progress.remaining(), None);
+    assert_eq!(progress.fraction(), 0.);
+
+    let progress = RenderProgress {
+        tiles_done: 4,
+        ..progress
+    };
+    assert_eq!(progress.remaining(), Some(Duration::from_millis(1500)));
+    assert_eq!(progress.fraction(), 0.4);
+    assert!(!progress.is_done());
+}
+
+/// With progressive previews, the first passes should be blocky previews at increasing resolutions, before the
+/// passes start accumulating. They should start again whenever the accumulation is cleared
+#[test]
+pub fn progressive_preview_before_accumulating() {
+    let preset = preset::RTIAW_DEMO();
+    let opts = RenderOpts {
+        width: nonzero!(64_usize),
+        height: nonzero!(48_usize),
+        progressive_preview: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+
+    for _ in 0..2 {
+        for scale in [8, 4, 2] {
+            let render = renderer.render();
+            assert_eq!(render.stats.preview_scale, Some(scale));
+            assert_eq!([render.stats.accum_frames, render.stats.accum_samples], [0, 0]);
+            assert_eq!([render.img.width(), render.img.height()], [64, 48]);
+            // Every pixel in a block is the same
+            for (x, y) in [(1, 0), (0, scale - 1), (scale - 1, scale - 1)] {
+                assert_eq!(render.img[(x, y)], render.img[(0, 0)]);
+            }
+        }
+        let stats = renderer.render().stats;
+        assert_eq!(stats.preview_scale, None);
+        assert_eq!(stats.accum_frames, 1);
+
+        renderer.clear_accumulation();
+    }
+}
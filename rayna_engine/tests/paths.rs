@@ -0,0 +1,92 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::metal::MetalMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::render::render_opts::{RenderMode, RenderOpts};
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+/// Paths that bounce far more times than would fit on the call stack should still render
+#[test]
+pub fn very_deep_paths() {
+    // The camera is inside a perfect mirror, so every path bounces until it runs out of depth
+    let material: MaterialInstance<TextureInstance> = MetalMaterial {
+        albedo: [1.; 3].into(),
+        fuzz: 0.,
+    }
+    .into();
+    let scene = StandardScene {
+        objects: [SimpleObject::new_uncorrected(
+            SphereMesh::new(Point3::ZERO, 5.),
+            material,
+            None,
+        )]
+        .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera::default();
+    let opts = RenderOpts {
+        width: nonzero!(8_usize),
+        height: nonzero!(8_usize),
+        samples: nonzero!(1_usize),
+        ray_depth: 50_000,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer");
+
+    // No light ever gets in
+    let img = renderer.render().img;
+    assert!(img.iter().all(|&c| c == Colour::BLACK));
+}
+
+/// Paths that never end should be shown as cut off in [RenderMode::PathDepth], and paths that escape straight away as
+/// not bouncing at all
+#[test]
+pub fn path_depth_mode() {
+    let mirror = |radius| {
+        let material: MaterialInstance<TextureInstance> = MetalMaterial {
+            albedo: [1.; 3].into(),
+            fuzz: 0.,
+        }
+        .into();
+        StandardScene {
+            objects: [SimpleObject::new_uncorrected(
+                SphereMesh::new(Point3::ZERO, radius),
+                material,
+                None,
+            )]
+            .into(),
+            skybox: WhiteSkybox.into(),
+        }
+    };
+    let camera = |z| Camera {
+        pos: Point3::new(0., 0., z),
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(8_usize),
+        height: nonzero!(8_usize),
+        samples: nonzero!(1_usize),
+        mode: RenderMode::PathDepth,
+        ray_depth: 4,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+
+    // Inside a mirror, every path bounces until it runs out of depth
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(mirror(5.), camera(0.), opts, 1).expect("failed creating renderer");
+    assert!(renderer.render().img.iter().all(|&c| c == Colour::WHITE));
+
+    // Looking away from the mirror, nothing is hit
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(mirror(1.), camera(5.), opts, 1).expect("failed creating renderer");
+    assert!(renderer.render().img.iter().all(|&c| c == Colour::BLACK));
+}
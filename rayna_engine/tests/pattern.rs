@@ -0,0 +1,114 @@
+use rand::rngs::mock::StepRng;
+use rayna_engine::core::types::*;
+use rayna_engine::texture::pattern::{Pattern, UvPatternTexture, WorldPatternTexture};
+use rayna_engine::texture::solid::SolidTexture;
+use rayna_engine::texture::Texture;
+
+mod common;
+
+/// Stripes should alternate along the direction they are turned to
+#[test]
+pub fn stripes() {
+    let stripes = |degrees| Pattern::Stripes {
+        angle: Angle::from_degrees(degrees),
+        width: 1.,
+    };
+    assert!(stripes(0.).is_on([0.5, 7.]));
+    assert!(!stripes(0.).is_on([1.5, 7.]));
+    assert!(!stripes(0.).is_on([-0.5, 7.]));
+
+    assert!(stripes(90.).is_on([5.5, 0.5]));
+    assert!(!stripes(90.).is_on([0.5, 1.5]));
+    // Diagonal
+    assert!(stripes(45.).is_on([0.3, 0.3]));
+    assert!(!stripes(45.).is_on([0.8, 0.8]));
+
+    // In world space, the stripes are vertical
+    assert!(stripes(0.).is_on([0.5, 100., 3.]));
+    assert!(!stripes(0.).is_on([1.5, -3., 0.]));
+    assert!(!stripes(90.).is_on([0.5, 0.5, 1.5]));
+}
+
+/// Dots should be in the middle of each cell of the grid
+#[test]
+pub fn dots() {
+    let dots = Pattern::Dots {
+        spacing: 1.,
+        radius: 0.25,
+    };
+    assert!(dots.is_on([0.5, 0.5]));
+    assert!(dots.is_on([-0.5, 2.6]));
+    assert!(!dots.is_on([0.1, 0.1]));
+    assert!(!dots.is_on([0.5, 0.9]));
+    assert!(dots.is_on([0.5, 0.5, 0.5]));
+    assert!(!dots.is_on([0.5, 0.5, 0.9]));
+}
+
+/// Grid lines should be centred on the multiples of the spacing, along every axis
+#[test]
+pub fn grid_lines() {
+    let grid = Pattern::Grid {
+        spacing: 2.,
+        line_width: 0.2,
+    };
+    assert!(grid.is_on([0.05, 1.]));
+    assert!(grid.is_on([1., 1.95]));
+    assert!(grid.is_on([-2.05, 1.]));
+    assert!(!grid.is_on([1., 1.]));
+    assert!(!grid.is_on([0.2, 1.]));
+    assert!(grid.is_on([1., 1., 4.02]));
+}
+
+/// The textures should use the `on` texture where the pattern is on, offset by their offsets
+#[test]
+pub fn pattern_textures() {
+    let on = SolidTexture::from(Colour::WHITE);
+    let off = SolidTexture::from(Colour::BLACK);
+    let rng = &mut StepRng::new(0, 1);
+    let stripes = Pattern::Stripes {
+        angle: Angle::from_degrees(0.),
+        width: 1.,
+    };
+
+    let uv = UvPatternTexture {
+        pattern: stripes,
+        offset: Vector2::new(1., 0.),
+        on,
+        off,
+    };
+    assert_eq!(
+        uv.value(
+            &common::intersection(Point3::ZERO, Vector3::Y, Point2::new(0.5, 0.)),
+            rng
+        ),
+        Colour::BLACK
+    );
+    assert_eq!(
+        uv.value(
+            &common::intersection(Point3::ZERO, Vector3::Y, Point2::new(1.5, 0.)),
+            rng
+        ),
+        Colour::WHITE
+    );
+
+    let world = WorldPatternTexture {
+        pattern: stripes,
+        offset: Vector3::ZERO,
+        on,
+        off,
+    };
+    assert_eq!(
+        world.value(
+            &common::intersection(Point3::new(0.5, 2., 0.), Vector3::Y, Point2::ZERO),
+            rng
+        ),
+        Colour::WHITE
+    );
+    assert_eq!(
+        world.value(
+            &common::intersection(Point3::new(1.5, 2., 0.), Vector3::Y, Point2::ZERO),
+            rng
+        ),
+        Colour::BLACK
+    );
+}
@@ -0,0 +1,192 @@
+use nonzero::nonzero;
+use rand::{Rng, SeedableRng};
+use rayna_engine::core::types::*;
+use rayna_engine::material::dielectric::DielectricMaterial;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::light::LightMaterial;
+use rayna_engine::material::metal::MetalMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::axis_box::AxisBoxMesh;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::light::LightObject;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::transform::ObjectTransform;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::photon::{pass_radius, Photon, PhotonMap};
+use rayna_engine::render::render_opts::{RenderMode, RenderOpts};
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::none::NoSkybox;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+type Mat = MaterialInstance<TextureInstance>;
+type Obj = ObjectInstance<MeshInstance, Mat>;
+
+/// The photon map should find exactly the photons within the radius, and the radius should shrink every pass
+#[test]
+pub fn photon_map_finds_nearby_photons() {
+    let rng = &mut rand::rngs::SmallRng::seed_from_u64(0);
+    let photons = (0..2000)
+        .map(|_| Photon {
+            pos: Point3::new(
+                rng.gen_range(-5.0..5.),
+                rng.gen_range(-5.0..5.),
+                rng.gen_range(-5.0..5.),
+            ),
+            dir: Vector3::new(0., -1., 0.),
+            power: Colour::WHITE,
+        })
+        .collect::<Vec<_>>();
+    let map = PhotonMap::new(photons.iter().copied(), 0.7);
+    assert_eq!(map.len(), photons.len());
+
+    for _ in 0..100 {
+        let pos = Point3::new(
+            rng.gen_range(-6.0..6.),
+            rng.gen_range(-6.0..6.),
+            rng.gen_range(-6.0..6.),
+        );
+        let found = map.photons_near(pos).count();
+        let expected = photons.iter().filter(|p| (p.pos - pos).length() <= 0.7).count();
+        assert_eq!(found, expected, "{pos:?}");
+    }
+
+    assert_eq!(pass_radius(1., 0), 1.);
+    for pass in 0..100 {
+        assert!(pass_radius(1., pass + 1) < pass_radius(1., pass), "pass {pass}");
+    }
+}
+
+/// A floor lit by a spherical light, with the average brightness of the pixels in the middle of the image. If there is
+/// a glass sphere in between, it focuses the light from above into a caustic
+fn floor_brightness(glass: bool) -> Channel {
+    let floor: Mat = LambertianMaterial {
+        albedo: [0.8; 3].into(),
+    }
+    .into();
+    let light: Mat = LightMaterial {
+        emissive: [50.; 3].into(),
+        group: None,
+    }
+    .into();
+    let mut objects: Vec<Obj> = vec![
+        SimpleObject::new_uncorrected(AxisBoxMesh::new([-50., -1., -50.], [50., 0., 50.]), floor, None).into(),
+        LightObject::new(SphereMesh::new(Point3::new(0., 10., 0.), 1.), light).into(),
+    ];
+    if glass {
+        let glass: Mat = DielectricMaterial {
+            albedo: [1.; 3].into(),
+            refractive_index: 1.5,
+            density: 0.,
+        }
+        .into();
+        objects.push(SimpleObject::new_uncorrected(SphereMesh::new(Point3::new(0., 2., 0.), 1.), glass, None).into());
+    }
+    let scene = StandardScene {
+        objects: objects.into(),
+        skybox: NoSkybox.into(),
+    };
+    // Looking down at the floor underneath the light, past the glass sphere
+    let camera = Camera {
+        pos: Point3::new(0., 6., -12.),
+        fwd: Vector3::new(0., -6., 12.).normalize(),
+        focus_dist: Vector3::new(0., -6., 12.).length(),
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(128_usize),
+        height: nonzero!(128_usize),
+        samples: nonzero!(4_usize),
+        mode: RenderMode::PhotonMapping,
+        deterministic: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer");
+
+    let mut img = renderer.render().img;
+    for _ in 1..16 {
+        img = renderer.render().img;
+    }
+    let block = itertools::iproduct!(60..69, 60..69)
+        .map(|px| img[px].into_iter().sum::<Channel>() / 3.)
+        .collect::<Vec<_>>();
+    block.iter().sum::<Channel>() / block.len() as Channel
+}
+
+/// Photon mapping should light diffuse surfaces as brightly as they really are, and render the caustic from a glass
+/// sphere
+#[test]
+pub fn photon_mapping_renders_caustics() {
+    // A sphere of radiance `L` lights the floor directly beneath it (distance `d`) with an irradiance of
+    // `PI * L * (r / d)^2`, which the floor reflects as a radiance of `albedo * L * (r / d)^2`
+    let plain = floor_brightness(false);
+    let expected = 0.8 * 50. * (1. / 10.) * (1. / 10.);
+    assert!(
+        (plain - expected).abs() < expected * 0.2,
+        "{plain} should be close to {expected}"
+    );
+
+    let caustic = floor_brightness(true);
+    assert!(
+        caustic > plain * 1.5,
+        "caustic ({caustic}) should be brighter than the plain floor ({plain})"
+    );
+}
+
+/// Motion blur should still be seen after bouncing off a mirror in photon mapping, so the reflected rays have to keep
+/// their time
+#[test]
+pub fn photon_mapping_keeps_ray_time() {
+    let mirror: Mat = MetalMaterial {
+        albedo: [1.; 3].into(),
+        fuzz: 0.,
+    }
+    .into();
+    let light: Mat = LightMaterial {
+        emissive: [1.; 3].into(),
+        group: None,
+    }
+    .into();
+    // The light is only above the middle of the mirror for the first quarter of the shutter interval
+    let moving = ObjectTransform::new_moving(
+        Transform3::IDENTITY,
+        Transform3::from_translation(Vector3::new(4., 0., 0.)),
+    );
+    let objects: Vec<Obj> = vec![
+        SimpleObject::new_uncorrected(AxisBoxMesh::new([-50., -1., -50.], [50., 0., 50.]), mirror, None).into(),
+        SimpleObject::new(SphereMesh::new(Point3::new(0., 10., 0.), 1.), light, moving).into(),
+    ];
+    let scene = StandardScene {
+        objects: objects.into(),
+        skybox: NoSkybox.into(),
+    };
+    // Looking straight down at the mirror, with a narrow view so every pixel sees the light's reflection
+    let camera = Camera {
+        pos: Point3::new(0., 5., 0.),
+        v_fov: Angle::from_degrees(2.),
+        fwd: -Vector3::Y,
+        up: Vector3::Z,
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(16_usize),
+        height: nonzero!(16_usize),
+        samples: nonzero!(64_usize),
+        mode: RenderMode::PhotonMapping,
+        deterministic: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer");
+    let img = renderer.render().img;
+    let mean = img.iter().flat_map(|c| c.0).sum::<Channel>() / (img.len() * Colour::CHANNEL_COUNT) as Channel;
+    assert!(
+        (mean - 0.25).abs() < 0.05,
+        "light should be blurred to a quarter (was {mean})"
+    );
+}
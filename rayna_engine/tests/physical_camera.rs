@@ -0,0 +1,66 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::camera::{Camera, PhysicalCamera, Projection};
+
+const EPSILON: Number = 1e-9;
+
+fn physical(physical: PhysicalCamera) -> Camera {
+    Camera {
+        focus_dist: 4.,
+        physical: Some(physical),
+        ..Default::default()
+    }
+}
+
+/// The FOV and defocus should come from the lens and sensor, instead of the camera's own settings
+#[test]
+pub fn physical_lens() {
+    let camera = physical(PhysicalCamera::default());
+    let (v_fov, defocus_angle) = camera.lens();
+    assert!((v_fov.to_degrees() - (2. * (12. as Number / 50.).atan().to_degrees())).abs() < EPSILON);
+    assert_ne!(v_fov, camera.v_fov);
+
+    // A 50mm lens at f/2 has a 25mm aperture
+    let viewport = camera.calculate_viewport().expect("camera should be valid");
+    assert!((viewport.defocus_disk_u.length() - 0.0125).abs() < EPSILON);
+    assert!((defocus_angle.radians - (2. * (0.0125 as Number / 4.).atan())).abs() < EPSILON);
+
+    // An equidistant fisheye maps the angle straight onto the sensor
+    let fisheye = Camera {
+        projection: Projection::FisheyeEquidistant,
+        ..physical(PhysicalCamera {
+            focal_length: 8.,
+            ..Default::default()
+        })
+    };
+    assert!((fisheye.lens().0.radians - 3.).abs() < EPSILON);
+
+    assert_eq!(
+        Camera::default().lens(),
+        (Camera::default().v_fov, Camera::default().defocus_angle)
+    );
+}
+
+/// Each of the exposure settings should change the exposure by the right number of stops
+#[test]
+pub fn physical_exposure() {
+    let base = PhysicalCamera::default();
+    assert!(base.ev100().abs() < EPSILON, "the default should be EV100 0");
+    assert_eq!(Camera::default().exposure(), 0.);
+
+    let stops = |settings: PhysicalCamera| physical(settings).exposure() - base.exposure();
+    let longer = PhysicalCamera {
+        shutter_time: base.shutter_time * 2.,
+        ..base
+    };
+    assert!((stops(longer) - 1.).abs() < EPSILON);
+    let narrower = PhysicalCamera {
+        f_number: base.f_number * 2.,
+        ..base
+    };
+    assert!((stops(narrower) + 2.).abs() < EPSILON);
+    let sensitive = PhysicalCamera {
+        iso: base.iso * 4.,
+        ..base
+    };
+    assert!((stops(sensitive) - 2.).abs() < EPSILON);
+}
@@ -0,0 +1,67 @@
+use rayna_engine::core::types::*;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::{PrepareStage, StandardScene};
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::skybox::{Skybox, SkyboxInstance};
+use rayna_engine::texture::TextureInstance;
+use std::sync::Mutex;
+
+mod common;
+
+/// A skybox that is black until it has been prepared
+#[derive(Clone, Debug, Default)]
+struct LazySkybox {
+    ready: bool,
+}
+
+impl Skybox for LazySkybox {
+    fn sky_colour(&self, _ray: &Ray) -> Colour {
+        if self.ready {
+            Colour::WHITE
+        } else {
+            Colour::BLACK
+        }
+    }
+
+    fn prepare(&mut self) { self.ready = true; }
+}
+
+fn scene() -> StandardScene {
+    let material: MaterialInstance<TextureInstance> = Default::default();
+    StandardScene {
+        objects: [SimpleObject::new_uncorrected(
+            SphereMesh::new((0., 0., 10.), 1.),
+            material,
+            None,
+        )]
+        .into(),
+        skybox: SkyboxInstance::from_dyn(LazySkybox::default()),
+    }
+}
+
+/// The scene should be prepared before the first render, without having to ask for it
+#[test]
+pub fn prepared_before_render() {
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+        scene(),
+        Camera::default(),
+        common::SIMPLE_RENDER_OPTIONS,
+        common::RENDERER_THREAD_COUNT,
+    )
+    .expect("failed creating renderer");
+    assert_eq!(renderer.render().img[(0, 0)], Colour::WHITE);
+
+    // Every stage should report progress
+    let stages = Mutex::new(vec![]);
+    renderer.prepare_scene(|stage| stages.lock().unwrap().push(stage));
+    let mut stages = stages.into_inner().unwrap();
+    stages.sort_by_key(|stage| *stage as usize);
+    assert_eq!(
+        stages,
+        [PrepareStage::Objects, PrepareStage::Skybox, PrepareStage::BlueNoise]
+    );
+}
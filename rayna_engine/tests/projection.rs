@@ -0,0 +1,89 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::camera::{Camera, Projection};
+use strum::IntoEnumIterator;
+
+const EPSILON: Number = 1e-6;
+
+fn viewport(projection: Projection, fov: Number) -> rayna_engine::scene::camera::Viewport {
+    Camera {
+        pos: Point3::new(1., 2., 3.),
+        fwd: Vector3::new(0.3, -0.2, 1.).normalize(),
+        v_fov: Angle::from_degrees(fov),
+        projection,
+        ..Default::default()
+    }
+    .calculate_viewport()
+    .expect("camera should be valid")
+}
+
+fn angle_between(a: Vector3, b: Vector3) -> Number {
+    a.normalize().dot(b.normalize()).clamp(-1., 1.).acos().to_degrees()
+}
+
+/// Projecting a point should give the pixel that it was seen through, for all the projections
+#[test]
+pub fn projection_inverts_centre_ray() {
+    let [w, h] = [64., 32.];
+    for projection in Projection::iter() {
+        let viewport = viewport(projection, 150.);
+        // Inside of the image circles, where the fisheyes can be inverted
+        for (px, py) in [(32., 16.), (40., 10.), (25.5, 22.), (35., 2.)] {
+            let point = viewport.centre_ray(px, py, w, h).at(5.);
+            let (x, y) = viewport
+                .project(point, w, h)
+                .expect("point should be in view of the camera");
+            assert!(
+                (x - px).abs() < EPSILON && (y - py).abs() < EPSILON,
+                "{projection:?}: {:?} != {:?}",
+                (x, y),
+                (px, py)
+            );
+        }
+    }
+}
+
+/// The equirectangular projection should see all the way around, regardless of the FOV
+#[test]
+pub fn equirectangular_sees_everything() {
+    let [w, h] = [64., 32.];
+    let viewport = viewport(Projection::Equirectangular, 45.);
+    let fwd = viewport.pixel_center - viewport.pos;
+    let dir = |px, py| viewport.centre_ray(px, py, w, h).dir();
+
+    assert!(
+        angle_between(dir(32., 16.), fwd) < EPSILON,
+        "the middle should look forwards"
+    );
+    assert!(
+        angle_between(dir(0., 16.), -fwd) < EPSILON,
+        "the edges should look backwards"
+    );
+    assert!((angle_between(dir(16., 16.), fwd) - 90.).abs() < EPSILON);
+    assert!(angle_between(dir(10., 0.), Vector3::Y) < 1e-3, "the top should look up");
+}
+
+/// The edge of the image circle of a fisheye should be at half the FOV from the middle
+#[test]
+pub fn fisheye_fov() {
+    let [w, h] = [48., 32.];
+    for projection in [Projection::FisheyeEquidistant, Projection::FisheyeEquisolid] {
+        for fov in [90., 180., 220.] {
+            let viewport = viewport(projection, fov);
+            let fwd = viewport.pixel_center - viewport.pos;
+            for (px, py) in [(24., 0.), (24., 32.), (8., 16.)] {
+                let angle = angle_between(viewport.centre_ray(px, py, w, h).dir(), fwd);
+                assert!(
+                    (angle - (fov / 2.)).abs() < EPSILON,
+                    "{projection:?} at {fov}°: {angle}°"
+                );
+            }
+        }
+    }
+
+    let too_wide = Camera {
+        v_fov: Angle::from_degrees(360.),
+        projection: Projection::FisheyeEquisolid,
+        ..Default::default()
+    };
+    assert!(too_wide.calculate_viewport().is_err());
+}
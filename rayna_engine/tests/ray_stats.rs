@@ -0,0 +1,107 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::shadow_catcher::ShadowCatcherMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::axis_box::AxisBoxMesh;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::skybox::SkyboxInstance;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+type Mat = MaterialInstance<TextureInstance>;
+type Obj = ObjectInstance<MeshInstance, Mat>;
+
+/// A sphere on top of a shadow catcher, rendered at `16x16` with two samples
+fn new_renderer(bvh_stats: bool) -> Renderer<Obj, SkyboxInstance, common::Rng> {
+    let sphere: Mat = LambertianMaterial {
+        albedo: [0.5; 3].into(),
+    }
+    .into();
+    let catcher: Mat = ShadowCatcherMaterial::default().into();
+    let objects: Vec<Obj> = vec![
+        SimpleObject::new_uncorrected(SphereMesh::new(Point3::new(0., 1., 0.), 1.), sphere, None).into(),
+        SimpleObject::new_uncorrected(AxisBoxMesh::new([-5., -1., -5.], [5., 0., 5.]), catcher, None).into(),
+    ];
+    let scene = StandardScene {
+        objects: objects.into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera {
+        pos: Point3::new(0., 2., -6.),
+        fwd: Vector3::new(0., -1., 6.).normalize(),
+        focus_dist: 6.,
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(16_usize),
+        height: nonzero!(16_usize),
+        samples: nonzero!(2_usize),
+        target_samples: Some(nonzero!(2_usize)),
+        bvh_stats,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    Renderer::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT).expect("failed creating renderer")
+}
+
+/// Every camera ray should be counted, along with the rays bouncing off the sphere and the shadow rays from the
+/// shadow catcher underneath it
+#[test]
+pub fn ray_stats_count_each_kind() {
+    let mut renderer = new_renderer(true);
+
+    let stats = renderer.render().stats;
+    let rays = stats.rays;
+    // The rays that carry on through the shadow catcher are primary as well
+    assert!(rays.primary >= 16 * 16 * 2, "{rays:?}");
+    assert!(rays.bounce > 0, "{rays:?}");
+    assert!(rays.shadow > 0, "{rays:?}");
+    assert!(
+        rays.bvh_nodes >= rays.total(),
+        "every ray should visit the root of the BVH: {rays:?}"
+    );
+    assert!(stats.rays_per_sec() > 0.);
+
+    // Nothing is traced once the render is finished
+    let stats = renderer.render().stats;
+    assert!(stats.finished);
+    assert_eq!(stats.rays.total(), 0);
+    assert_eq!(stats.rays.bvh_nodes, 0);
+
+    // The nodes aren't counted unless they're asked for
+    renderer.set_options(RenderOpts {
+        bvh_stats: false,
+        ..*renderer.options()
+    });
+    let stats = renderer.render().stats;
+    assert!(stats.rays.total() > 0);
+    assert_eq!(stats.rays.bvh_nodes, 0);
+}
+
+/// Renderers with and without the BVH stats shouldn't change each other's settings, even when they're rendering at
+/// the same time
+#[test]
+pub fn bvh_stats_per_renderer() {
+    std::thread::scope(|scope| {
+        for bvh_stats in [true, false] {
+            scope.spawn(move || {
+                let mut renderer = new_renderer(bvh_stats);
+                for _ in 0..20 {
+                    renderer.clear_accumulation();
+                    let rays = renderer.render().stats.rays;
+                    assert!(rays.total() > 0, "{rays:?}");
+                    assert_eq!(rays.bvh_nodes > 0, bvh_stats, "{rays:?}");
+                }
+            });
+        }
+    });
+}
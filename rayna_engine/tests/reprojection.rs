@@ -0,0 +1,112 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::render::compare::compare_images;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::preset;
+
+mod common;
+
+const OPTS: RenderOpts = RenderOpts {
+    width: nonzero!(48_usize),
+    height: nonzero!(32_usize),
+    samples: nonzero!(1_usize),
+    deterministic: true,
+    temporal_reprojection: true,
+    ..common::SIMPLE_RENDER_OPTIONS
+};
+
+fn render_with(camera: Camera, opts: RenderOpts, passes: usize) -> Image {
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, camera, opts, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    (0..passes).map(|_| renderer.render().img).last().expect("no passes")
+}
+
+/// Projecting a point onto the viewport should give the pixel that it was seen through
+#[test]
+pub fn viewport_projection_inverts_centre_ray() {
+    let viewport = preset::RTIAW_DEMO()
+        .camera
+        .calculate_viewport()
+        .expect("failed calculating viewport");
+    let [w, h] = [48., 32.];
+    for (px, py) in [(0., 0.), (24., 16.), (47., 3.), (10.5, 31.)] {
+        let point = viewport.centre_ray(px, py, w, h).at(7.);
+        let (x, y) = viewport
+            .project(point, w, h)
+            .expect("point should be in front of the camera");
+        assert!(
+            (x - px).abs() < 1e-6 && (y - py).abs() < 1e-6,
+            "{:?} != {:?}",
+            (x, y),
+            (px, py)
+        );
+    }
+    let behind = viewport.pos - (viewport.pixel_center - viewport.pos);
+    assert_eq!(viewport.project(behind, w, h), None);
+}
+
+/// After the camera moves a little, the reprojected samples should leave the image much less noisy than starting again
+#[test]
+pub fn reprojection_keeps_samples() {
+    let camera = preset::RTIAW_DEMO().camera;
+    let moved = Camera {
+        pos: camera.pos + Vector3::new(0.05, 0., 0.),
+        ..camera
+    };
+    let reference = render_with(
+        moved,
+        RenderOpts {
+            samples: nonzero!(64_usize),
+            ..OPTS
+        },
+        1,
+    );
+
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, camera, OPTS, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    for _ in 0..16 {
+        renderer.render();
+    }
+    renderer.set_camera(moved);
+    let render = renderer.render();
+    // The counters start again, even though most of the pixels have their samples
+    assert_eq!([render.stats.accum_frames, render.stats.accum_samples], [1, 1]);
+
+    let reprojected = compare_images(&render.img, &reference)
+        .expect("failed comparing")
+        .metrics;
+    let restarted = compare_images(&render_with(moved, OPTS, 1), &reference)
+        .expect("failed comparing")
+        .metrics;
+    assert!(
+        reprojected.rmse < restarted.rmse * 0.75,
+        "{reprojected:?} should be better than {restarted:?}"
+    );
+}
+
+/// When the camera turns around, nothing it sees was seen before, so the render should be the same as starting again
+#[test]
+pub fn reprojection_discards_unseen() {
+    let camera = preset::RTIAW_DEMO().camera;
+    let turned = Camera {
+        fwd: -camera.fwd,
+        ..camera
+    };
+
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, camera, OPTS, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    for _ in 0..4 {
+        renderer.render();
+    }
+    renderer.set_camera(turned);
+    let img = renderer.render().img;
+    assert!(img.iter().eq(render_with(turned, OPTS, 1).iter()));
+}
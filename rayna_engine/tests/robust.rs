@@ -0,0 +1,87 @@
+use nonzero::nonzero;
+use rand::SeedableRng;
+use rayna_engine::core::types::*;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::Mesh;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use rayna_engine::shared::interval::Interval;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::shared::robust;
+
+mod common;
+
+/// The compensated maths should be exact where the plain version cancels out
+#[test]
+pub fn difference_of_products_is_exact() {
+    let (a, b, c) = (1e8 + 1., 1e8 - 1., 1e8);
+    // `a * b` is `1e16 - 1`, which can't be stored exactly
+    assert_ne!((a * b) - (c * c), -1.);
+    assert_eq!(robust::difference_of_products(a, b, c, c), -1.);
+}
+
+/// A small sphere that's very far away should only be hit in the right place with robust intersections enabled
+#[test]
+pub fn robust_sphere_intersection() {
+    let rng = &mut common::Rng::seed_from_u64(0);
+    let sphere = SphereMesh::new(Point3::new(0., 0., 1e9), 1.);
+    // Offset from the centre, so the ray is half a radius away from it
+    let ray = Ray::new(Point3::new(0.5, 0., 0.), Vector3::Z);
+    let interval = Interval::from(1e-3..);
+    let expected = 1e9 - Number::sqrt(0.75);
+
+    robust::set_enabled(true);
+    let hit = sphere
+        .intersect(&ray, &interval, rng)
+        .expect("robust ray should hit the sphere");
+    assert!((hit.dist - expected).abs() < 1e-3, "{hit:?}");
+
+    robust::set_enabled(false);
+    let hit = sphere.intersect(&ray, &interval, rng);
+    assert!(
+        hit.map_or(true, |hit| (hit.dist - expected).abs() > 0.1),
+        "the plain maths should have lost the precision: {hit:?}"
+    );
+}
+
+/// Renderers with and without robust intersections shouldn't change each other's settings, even when they're rendering
+/// at the same time, or the setting of the thread they were created on
+#[test]
+pub fn robust_setting_per_renderer() {
+    let preset = preset::RTIAW_DEMO();
+    let render = |robust_intersections: bool| {
+        let opts = RenderOpts {
+            width: nonzero!(64_usize),
+            height: nonzero!(64_usize),
+            deterministic: true,
+            robust_intersections,
+            ..common::SIMPLE_RENDER_OPTIONS
+        };
+        let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+            preset.scene.clone(),
+            preset.camera,
+            opts,
+            common::RENDERER_THREAD_COUNT,
+        )
+        .expect("failed creating renderer");
+        renderer.render().img
+    };
+
+    robust::set_enabled(false);
+    let alone = [true, false].map(&render);
+    assert!(
+        !robust::enabled(),
+        "rendering shouldn't change the setting of this thread"
+    );
+
+    let render = &render;
+    let together = std::thread::scope(|scope| {
+        [true, false]
+            .map(|robust| scope.spawn(move || render(robust)))
+            .map(|handle| handle.join().expect("render panicked"))
+    });
+    for (alone, together) in alone.iter().zip(&together) {
+        assert!(alone.iter().eq(together.iter()), "renders should be identical");
+    }
+}
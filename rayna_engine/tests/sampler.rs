@@ -0,0 +1,74 @@
+use rand::rngs::mock::StepRng;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::preset;
+use rayna_engine::shared::rng::{Sampler, SamplerKind};
+use strum::IntoEnumIterator;
+
+mod common;
+
+/// Draws the first two dimensions of the first `count` samples
+fn points(kind: SamplerKind, seed: u64, count: u64) -> Vec<[f64; 2]> {
+    let mut sampler = Sampler::new(kind, seed, StepRng::new(0, 0x9E37_79B9_7F4A_7C15));
+    (0..count)
+        .map(|i| {
+            sampler.start_sample(i);
+            [sampler.next_1d(), sampler.next_1d()]
+        })
+        .collect()
+}
+
+/// The low-discrepancy samples should have exactly one point in each cell of a grid, no matter how they are
+/// scrambled
+#[test]
+pub fn samples_are_stratified() {
+    // Sobol is stratified in powers of two, and Halton in powers of the prime for each dimension
+    for (kind, [cols, rows]) in [(SamplerKind::Sobol, [4, 4]), (SamplerKind::Halton, [4, 9])] {
+        for seed in [0, 1, 12345] {
+            let mut cells = vec![0; cols * rows];
+            for [u, v] in points(kind, seed, (cols * rows) as u64) {
+                cells[((u * cols as f64) as usize) + (cols * (v * rows as f64) as usize)] += 1;
+            }
+            assert!(
+                cells.iter().all(|&n| n == 1),
+                "{kind} (seed {seed}) isn't stratified: {cells:?}"
+            );
+        }
+    }
+}
+
+/// Every dimension should be in the range `0.0..1.0`, including the ones past the low-discrepancy dimensions
+#[test]
+pub fn samples_in_range() {
+    for kind in SamplerKind::iter() {
+        let mut sampler = Sampler::new(kind, 42, StepRng::new(0, 0x9E37_79B9_7F4A_7C15));
+        for i in 0..64 {
+            sampler.start_sample(i);
+            for _ in 0..100 {
+                let n = sampler.next_1d();
+                assert!((0.0..1.0).contains(&n), "{kind} gave {n}");
+            }
+        }
+    }
+}
+
+/// The renderer should work with all the samplers
+#[test]
+pub fn render_with_samplers() {
+    let preset = preset::RTIAW_DEMO();
+    for sampler in SamplerKind::iter() {
+        let opts = RenderOpts {
+            sampler,
+            ..common::SIMPLE_RENDER_OPTIONS
+        };
+        let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+            preset.scene.clone(),
+            preset.camera,
+            opts,
+            common::RENDERER_THREAD_COUNT,
+        )
+        .expect("failed creating renderer");
+        let render = renderer.render();
+        assert!(render.img.iter().all(|px| px.iter().all(|c| c.is_finite())));
+    }
+}
@@ -0,0 +1,69 @@
+use approx::assert_relative_eq;
+use rand::rngs::mock::StepRng;
+use rayna_engine::core::types::*;
+use rayna_engine::shared::intersect::Intersection;
+use rayna_engine::texture::sdf::{SdfMask, SdfTexture};
+use rayna_engine::texture::solid::SolidTexture;
+use rayna_engine::texture::Texture;
+
+mod common;
+
+/// A disk with a radius of 10 pixels, in the middle of a 40x40 image
+fn disk() -> SdfMask {
+    let coverage = Image::<Number>::from_fn(40, 40, |x, y| {
+        let (dx, dy) = (x as Number - 19.5, y as Number - 19.5);
+        if (dx * dx) + (dy * dy) < 100. {
+            1.
+        } else {
+            0.
+        }
+    });
+    SdfMask::from_coverage(&coverage)
+}
+
+fn at_uv(u: Number, v: Number) -> Intersection { common::intersection(Point3::ZERO, Vector3::Z, Point2::new(u, v)) }
+
+/// The field should be the distance to the edge of the shape, positive inside
+#[test]
+pub fn sdf_distances() {
+    let mask = disk();
+    assert_eq!((mask.width(), mask.height()), (40, 40));
+    // The centre of the disk (the pixels either side of it are a little closer to the edge)
+    assert_relative_eq!(mask.distance(19.5, 19.5), 9., epsilon = 1.);
+    // Outside the disk, along an axis and diagonally
+    assert_relative_eq!(mask.distance(34.5, 19.5), -5., epsilon = 1.);
+    assert_relative_eq!(mask.distance(0., 0.), -(27.6 - 10.), epsilon = 1.);
+    // The sign changes at the edge
+    assert!(mask.distance(27., 19.5) > 0.);
+    assert!(mask.distance(31., 19.5) < 0.);
+
+    // Shapes without any inside are outside everywhere
+    let empty = SdfMask::from_coverage(&Image::<Number>::new_blank(8, 4));
+    assert!(empty.distance(4., 2.) < -8.);
+}
+
+/// The texture should show the foreground inside the shape, and the background outside of it (and outside the UVs)
+#[test]
+pub fn sdf_texture() {
+    let mut texture = SdfTexture::new(
+        disk(),
+        SolidTexture::from(Colour::WHITE),
+        SolidTexture::from(Colour::BLACK),
+    );
+    let rng = &mut StepRng::new(0, 1);
+    assert_eq!(texture.value(&at_uv(0.5, 0.5), rng), Colour::WHITE);
+    assert_eq!(texture.value(&at_uv(0.05, 0.9), rng), Colour::BLACK);
+    assert_eq!(texture.value(&at_uv(1.5, 0.5), rng), Colour::BLACK);
+
+    // The edge is blended over the edge width
+    let edge = texture.coverage(&at_uv(0.5 + (10. / 40.), 0.5));
+    assert!(edge > 0. && edge < 1., "edge coverage was {edge}");
+    texture.edge_width = 0.;
+    let hard = texture.coverage(&at_uv(0.5 + (10. / 40.), 0.5));
+    assert!(hard == 0. || hard == 1.);
+
+    // Scaling the UVs shrinks the shape into the corner
+    texture.scale = Size2::splat(2.);
+    assert_eq!(texture.value(&at_uv(0.25, 0.25), rng), Colour::WHITE);
+    assert_eq!(texture.value(&at_uv(0.75, 0.75), rng), Colour::BLACK);
+}
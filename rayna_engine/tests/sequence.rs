@@ -0,0 +1,142 @@
+use approx::assert_relative_eq;
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::object::animated::Interpolation;
+use rayna_engine::render::animation::{self, frame_path, CameraPath};
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::{Camera, Shutter};
+use rayna_engine::scene::preset;
+use std::path::{Path, PathBuf};
+
+mod common;
+
+const OPTS: RenderOpts = RenderOpts {
+    width: nonzero!(16_usize),
+    height: nonzero!(12_usize),
+    samples: nonzero!(1_usize),
+    target_samples: Some(nonzero!(2_usize)),
+    ..common::SIMPLE_RENDER_OPTIONS
+};
+
+fn camera_at(x: Number) -> Camera {
+    Camera {
+        pos: Point3::new(x, 0., 0.),
+        focus_dist: x + 1.,
+        ..Default::default()
+    }
+}
+
+/// Keyframed paths should interpolate the cameras, with frames at both ends of the path
+#[test]
+pub fn keyframe_path() {
+    // Out of order on purpose
+    let path = CameraPath::keyframes([(4., camera_at(8.)), (0., camera_at(0.))], Interpolation::Linear);
+    assert_eq!(path.time_range(), (0., 4.));
+    assert!(!path.is_loop());
+
+    let camera = path.camera_at(1.).expect("camera should be valid");
+    assert_relative_eq!(camera.pos.x, 2., epsilon = 1e-9);
+    assert_relative_eq!(camera.focus_dist, 3., epsilon = 1e-9);
+    assert_relative_eq!(
+        path.camera_at(10.).expect("camera should be valid").pos.x,
+        8.,
+        epsilon = 1e-9
+    );
+
+    let times = (0..5).map(|frame| path.frame_time(frame, 5)).collect::<Vec<_>>();
+    assert_eq!(times, [0., 1., 2., 3., 4.]);
+    assert_eq!(path.frame_time(0, 1), 0.);
+}
+
+/// A turntable should orbit around the target once, without repeating the first frame at the end
+#[test]
+pub fn turntable_path() {
+    let target = Point3::new(0., 1., 0.);
+    let path = CameraPath::turntable(Camera::default(), target, 5., Angle::from_degrees(20.), 2.);
+    assert!(path.is_loop());
+    assert_eq!(path.frame_time(3, 4), 1.5);
+
+    let first = path.camera_at(path.frame_time(0, 4)).expect("camera should be valid");
+    let half = path.camera_at(path.frame_time(2, 4)).expect("camera should be valid");
+    for camera in [first, half] {
+        assert_relative_eq!((camera.pos - target).length(), 5., epsilon = 1e-9);
+    }
+    // Half way round, the camera is on the other side of the target
+    assert_relative_eq!(first.fwd.x, -half.fwd.x, epsilon = 1e-9);
+    assert_relative_eq!(first.fwd.z, -half.fwd.z, epsilon = 1e-9);
+}
+
+/// Each frame of a sequence should be rendered from its own camera, leaving the renderer at the last one
+#[test]
+pub fn render_frames() {
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, OPTS, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    let path = CameraPath::keyframes(
+        [
+            (
+                0.,
+                Camera {
+                    shutter: Shutter::from_angle(1., Angle::from_degrees(180.)),
+                    ..preset.camera
+                },
+            ),
+            (
+                1.,
+                Camera {
+                    pos: preset.camera.pos + Vector3::new(0., 1., 0.),
+                    ..preset.camera
+                },
+            ),
+        ],
+        Interpolation::Linear,
+    );
+
+    let images = animation::render_sequence(&mut renderer, &path, 3).expect("failed rendering sequence");
+    assert_eq!(images.len(), 3);
+    assert!(images.iter().all(|img| (img.width(), img.height()) == (16, 12)));
+    assert!(images[0].iter().ne(images[2].iter()), "the camera should have moved");
+    assert_eq!(renderer.camera().pos, preset.camera.pos + Vector3::new(0., 1., 0.));
+}
+
+/// Saved sequences should be numbered in order
+#[test]
+pub fn save_frames() {
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, OPTS, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    let path = CameraPath::turntable(preset.camera, Point3::ZERO, 10., Angle::from_degrees(10.), 1.);
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+
+    let paths = animation::save_sequence(&mut renderer, &path, 3, dir.path().join("frame_###.png"))
+        .expect("failed saving sequence");
+    assert_eq!(
+        paths,
+        (0..3)
+            .map(|i| dir.path().join(format!("frame_00{i}.png")))
+            .collect::<Vec<_>>()
+    );
+    for path in &paths {
+        let img = image::open(path).expect("failed loading frame");
+        assert_eq!((img.width(), img.height()), (16, 12));
+    }
+}
+
+/// The frame number should replace the `#`s, or be added to the end of the name
+#[test]
+pub fn frame_paths() {
+    assert_eq!(
+        frame_path(Path::new("out/frame_####.exr"), 12),
+        PathBuf::from("out/frame_0012.exr")
+    );
+    assert_eq!(frame_path(Path::new("#_#.png"), 3), PathBuf::from("#_3.png"));
+    assert_eq!(frame_path(Path::new("##.png"), 123), PathBuf::from("123.png"));
+    assert_eq!(
+        frame_path(Path::new("out/render.png"), 7),
+        PathBuf::from("out/render_0007.png")
+    );
+    assert_eq!(frame_path(Path::new("render"), 7), PathBuf::from("render_0007"));
+}
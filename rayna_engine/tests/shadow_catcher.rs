@@ -0,0 +1,161 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::shadow_catcher::ShadowCatcherMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::planar::parallelogram::ParallelogramMesh;
+use rayna_engine::mesh::planar::Planar;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::render::Render;
+use rayna_engine::render::render_opts::{RenderMode, RenderOpts};
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::texture::TextureInstance;
+
+mod common;
+
+type Mat = MaterialInstance<TextureInstance>;
+type Obj = ObjectInstance<MeshInstance, Mat>;
+
+/// The pixel in the middle of the image, which sees the top of the sphere
+const CENTRE: (usize, usize) = (16, 16);
+/// A pixel just past the edge of the sphere, where the ground is in its shadow
+const NEAR: (usize, usize) = (21, 16);
+/// A pixel in the corner, where the ground is far enough from the sphere to barely be shadowed
+const FAR: (usize, usize) = (0, 0);
+
+/// Renders a large shadow catcher on the ground (optionally with a sphere sitting on it), looking straight down from
+/// above so that the ground fills the whole `32x32` image
+fn render(sphere: bool, opts: RenderOpts) -> Render<Image> {
+    let catcher: Mat = ShadowCatcherMaterial::default().into();
+    let mut objects: Vec<Obj> = vec![SimpleObject::new_uncorrected(
+        ParallelogramMesh::new(Planar::new_centred(Point3::ZERO, [0., 0., 20.], [20., 0., 0.])),
+        catcher,
+        None,
+    )
+    .into()];
+    if sphere {
+        let material: Mat = LambertianMaterial {
+            albedo: [0.5; 3].into(),
+        }
+        .into();
+        objects
+            .push(SimpleObject::new_uncorrected(SphereMesh::new(Point3::new(0., 1., 0.), 1.), material, None).into());
+    }
+    let scene = StandardScene {
+        objects: objects.into(),
+        skybox: WhiteSkybox.into(),
+    };
+    let camera = Camera {
+        pos: Point3::new(0., 10., 0.),
+        fwd: -Vector3::Y,
+        up: Vector3::Z,
+        focus_dist: 10.,
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(32_usize),
+        height: nonzero!(32_usize),
+        samples: nonzero!(256_usize),
+        ..opts
+    };
+    Renderer::<Obj, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer")
+        .render()
+}
+
+/// The mean of the channels of a colour
+fn mean(col: Colour) -> Channel { col.into_iter().sum::<Channel>() / Colour::CHANNEL_COUNT as Channel }
+
+/// With nothing to cast shadows onto it, a shadow catcher should be invisible, and completely transparent
+#[test]
+pub fn shadow_catcher_without_shadows_is_invisible() {
+    let beauty = render(false, common::SIMPLE_RENDER_OPTIONS);
+    assert!(
+        beauty
+            .img
+            .iter()
+            .all(|&px| px.into_iter().all(|c| (c - 1.).abs() < 1e-4)),
+        "the sky should show through the catcher everywhere"
+    );
+
+    let alpha = render(
+        false,
+        RenderOpts {
+            mode: RenderMode::Alpha,
+            ..common::SIMPLE_RENDER_OPTIONS
+        },
+    );
+    assert!(alpha.img.iter().all(|&px| mean(px) < 1e-4), "nothing should be covered");
+}
+
+/// In [RenderMode::Alpha], objects should be opaque, and shadow catchers should be as opaque as the shadows on them are
+/// dense
+#[test]
+pub fn shadow_catcher_alpha_follows_shadows() {
+    let render = render(
+        true,
+        RenderOpts {
+            mode: RenderMode::Alpha,
+            ..common::SIMPLE_RENDER_OPTIONS
+        },
+    );
+    let img = &render.img;
+    assert_eq!(img[CENTRE], Colour::WHITE, "the sphere should be opaque");
+
+    let (near, far) = (mean(img[NEAR]), mean(img[FAR]));
+    assert!(near > 0.05, "the ground next to the sphere should be shadowed: {near}");
+    assert!(
+        far < 0.05,
+        "the ground far from the sphere should barely be shadowed: {far}"
+    );
+    assert!(
+        near > far,
+        "the shadow should fade away from the sphere: {near} <= {far}"
+    );
+}
+
+/// The beauty image should show the sky through the catcher, darkened where it's in shadow, while with a
+/// [transparent sky](RenderOpts::transparent_sky) the catcher should be black, and only show up in the alpha (so that
+/// compositing the render over a background darkens it where the shadows are)
+#[test]
+pub fn shadow_catcher_composites_shadows() {
+    let beauty = render(true, common::SIMPLE_RENDER_OPTIONS);
+    let (near, far) = (mean(beauty.img[NEAR]), mean(beauty.img[FAR]));
+    assert!(near < 0.95, "the sky should be darkened by the shadow: {near}");
+    assert!(
+        far > 0.95,
+        "the sky should barely be darkened far from the sphere: {far}"
+    );
+
+    let transparent = render(
+        true,
+        RenderOpts {
+            transparent_sky: true,
+            ..common::SIMPLE_RENDER_OPTIONS
+        },
+    );
+    let alpha = transparent
+        .alpha
+        .as_ref()
+        .expect("transparent renders should have an alpha");
+    assert_eq!(transparent.img[NEAR], Colour::BLACK);
+    assert_eq!(transparent.img[FAR], Colour::BLACK);
+    assert!(
+        transparent.img[CENTRE].into_iter().all(|c| c > 0.),
+        "the sphere should still be lit by the sky"
+    );
+
+    assert_eq!(alpha[CENTRE], Colour::WHITE);
+    let (near, far) = (mean(alpha[NEAR]), mean(alpha[FAR]));
+    assert!(near > 0.05, "the shadow should be in the alpha: {near}");
+    assert!(
+        far < 0.05,
+        "the alpha should barely be covered far from the sphere: {far}"
+    );
+}
@@ -0,0 +1,123 @@
+use approx::assert_relative_eq;
+use rand::SeedableRng;
+use rayna_engine::core::types::*;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::MeshInstance;
+use rayna_engine::object::animated::Interpolation;
+use rayna_engine::object::ObjectInstance;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::shared::math::Lerp;
+use rayna_engine::shared::ray::Ray;
+use rayna_engine::shared::rng;
+use rayna_engine::skybox::gradient::GradientSkybox;
+use rayna_engine::skybox::time_of_day::TimeOfDaySkybox;
+use rayna_engine::skybox::{Skybox, SkyboxInstance};
+use rayna_engine::texture::TextureInstance;
+use std::f64::consts::PI;
+
+mod common;
+
+type Obj = ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>;
+
+/// The gradient should hit each colour exactly at the poles and horizon, with the sun on top
+#[test]
+pub fn three_point_gradient() {
+    let [sky, horizon, ground] = [[0., 0., 1.], [0., 1., 0.], [1., 0., 0.]].map(Colour::from);
+    let sun_dir = Vector3::new(1., 1., 0.).normalize();
+    let skybox = SkyboxInstance::three_point(sky, horizon, ground, sun_dir, 10.);
+    let colour = |dir: Vector3| skybox.sky_colour(&Ray::new(Point3::ZERO, dir));
+
+    assert_eq!(colour(Vector3::Y), sky);
+    assert_eq!(colour(-Vector3::Y), ground);
+    assert_eq!(colour(Vector3::X), horizon);
+
+    // The sun is added on top of the gradient
+    let sun = colour(sun_dir);
+    let gradient = Colour::lerp(horizon, sky, sun_dir.y.sqrt() as Channel);
+    for c in 0..3 {
+        assert_relative_eq!(sun[c], gradient[c] + 10., epsilon = 1e-5);
+    }
+}
+
+/// The sun should follow a daily arc, so a time-lapse can be rendered by stepping the time
+#[test]
+pub fn gradient_time_of_day() {
+    let skybox = GradientSkybox::default();
+    let max = Angle::from_degrees(60.);
+
+    let sunrise = skybox.with_time_of_day(6., max).sun_dir;
+    assert_relative_eq!(sunrise.x, 1., epsilon = 1e-9);
+    assert_relative_eq!(sunrise.y, 0., epsilon = 1e-9);
+
+    let noon = skybox.with_time_of_day(12., max).sun_dir;
+    assert_relative_eq!(noon.y, max.radians.sin(), epsilon = 1e-9);
+    assert_relative_eq!(noon.z, max.radians.cos(), epsilon = 1e-9);
+
+    let sunset = skybox.with_time_of_day(18., max).sun_dir;
+    assert_relative_eq!(sunset.x, -1., epsilon = 1e-9);
+
+    // Wraps around, and is below the horizon at night
+    assert!(skybox.with_time_of_day(24., max).sun_dir.y < 0.);
+    assert_relative_eq!(skybox.with_time_of_day(30., max).sun_dir.x, 1., epsilon = 1e-9);
+}
+
+/// Setting the time of the scene should move the sun along its keyframes, so a day-night time-lapse can be rendered
+#[test]
+pub fn time_of_day_keyframes() {
+    let max = Angle::from_degrees(60.);
+    let skybox = TimeOfDaySkybox::new(
+        GradientSkybox::default(),
+        [(10., 18.), (0., 6.)],
+        Interpolation::Linear,
+        max,
+    );
+    assert_relative_eq!(skybox.hours_at(-1.), 6.);
+    assert_relative_eq!(skybox.hours_at(5.), 12.);
+    assert_relative_eq!(skybox.hours_at(20.), 18.);
+    // Starts at time zero
+    assert_relative_eq!(skybox.sky().sun_dir.x, 1., epsilon = 1e-9);
+
+    let mut scene = StandardScene {
+        objects: Vec::<Obj>::new().into(),
+        skybox: skybox.into(),
+    };
+    let noon = Vector3::new(0., max.radians.sin(), max.radians.cos());
+    let brightness = |scene: &StandardScene| scene.skybox.sky_colour(&Ray::new(Point3::ZERO, noon))[0];
+
+    let before = brightness(&scene);
+    scene.set_time(5.);
+    let after = brightness(&scene);
+    assert!(
+        after > before + 10.,
+        "the sun should be overhead at noon ({before} -> {after})"
+    );
+}
+
+/// Directions sampled towards the sun should all be on the disk, with a pdf that integrates to one over the sphere
+#[test]
+pub fn sun_sampling_pdf() {
+    let rng = &mut common::Rng::seed_from_u64(0);
+    let mut skybox = GradientSkybox::default();
+    skybox.sun_size = Angle::from_degrees(30.);
+    let cos_edge = skybox.sun_size.radians.cos();
+
+    for _ in 0..1000 {
+        let dir = skybox.sample_direction(rng).expect("should sample the sun");
+        assert!(Vector3::dot(dir, skybox.sun_dir) >= cos_edge - 1e-9, "{dir:?}");
+        assert!(skybox.direction_probability(dir) > 0.);
+    }
+    assert_eq!(skybox.direction_probability(-skybox.sun_dir), 0.);
+
+    // Monte Carlo estimate of the integral, using uniform directions
+    const SAMPLES: usize = 200_000;
+    let integral = (0..SAMPLES)
+        .map(|_| skybox.direction_probability(rng::normal_on_unit_sphere(rng)) * 4. * PI)
+        .sum::<Number>()
+        / SAMPLES as Number;
+    assert_relative_eq!(integral, 1., epsilon = 0.02);
+
+    // Nothing to sample without a sun
+    skybox.sun_intensity = 0.;
+    assert!(skybox.sample_direction(rng).is_none());
+    assert_eq!(skybox.direction_probability(skybox.sun_dir), 0.);
+}
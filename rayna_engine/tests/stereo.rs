@@ -0,0 +1,98 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::camera::{Camera, Projection, Viewport};
+use rayna_engine::scene::stereo::{StereoCamera, StereoLayout};
+use rayna_engine::shared::ray::Ray;
+
+const EPSILON: Number = 1e-9;
+
+fn viewport(projection: Projection, layout: StereoLayout) -> Viewport {
+    Camera {
+        pos: Point3::new(1., 2., 3.),
+        fwd: Vector3::new(0.3, -0.2, 1.).normalize(),
+        projection,
+        stereo: Some(StereoCamera {
+            ipd: 0.1,
+            convergence: 4.,
+            layout,
+        }),
+        ..Default::default()
+    }
+    .calculate_viewport()
+    .expect("camera should be valid")
+}
+
+/// How far a point is from the line of a ray
+fn dist_to_ray(ray: &Ray, point: Point3) -> Number {
+    let offset = point - ray.pos();
+    let dir = ray.dir().normalize();
+    (offset - (dir * offset.dot(dir))).length()
+}
+
+/// The same pixel of each eye's image should see the same point at the convergence distance, from eyes that are the
+/// IPD apart
+#[test]
+pub fn stereo_converges() {
+    let [w, h] = [64., 32.];
+    for layout in [StereoLayout::SideBySide, StereoLayout::TopBottom] {
+        let viewport = viewport(Projection::Perspective, layout);
+        let fwd = (viewport.pixel_center - viewport.pos).normalize();
+        // The offset from a pixel of the left eye to the same pixel of the right eye
+        let (dx, dy) = match layout {
+            StereoLayout::SideBySide => (w / 2., 0.),
+            StereoLayout::TopBottom => (0., h / 2.),
+        };
+        for (px, py) in [(8., 4.), (16.5, 10.), (3., 13.)] {
+            let left = viewport.centre_ray(px, py, w, h);
+            let right = viewport.centre_ray(px + dx, py + dy, w, h);
+            assert!(((right.pos() - left.pos()).length() - 0.1).abs() < EPSILON);
+
+            // Where the left eye sees the convergence plane
+            let t = 4. / left.dir().normalize().dot(fwd);
+            let point = left.pos() + (left.dir().normalize() * t);
+            assert!(
+                dist_to_ray(&right, point) < 1e-6,
+                "{layout:?}: eyes should converge at {point:?}"
+            );
+        }
+    }
+}
+
+/// The eyes of a 360° stereo panorama should be on a circle around the camera, to the sides of the direction they see
+#[test]
+pub fn stereo_panorama() {
+    let [w, h] = [64., 64.];
+    let viewport = viewport(Projection::Equirectangular, StereoLayout::TopBottom);
+    for (px, py) in [(0., 16.), (16., 10.), (40.5, 20.), (63., 5.)] {
+        let left = viewport.centre_ray(px, py, w, h);
+        let right = viewport.centre_ray(px, py + (h / 2.), w, h);
+        for ray in [&left, &right] {
+            let offset = ray.pos() - viewport.pos;
+            assert!(offset.dot(viewport.viewport_v).abs() < EPSILON, "eye should be level");
+        }
+        // The eyes are either side of the camera, so they converge straight out from it
+        let point = viewport.pos + ((left.dir() + right.dir()).normalize() * 4.);
+        assert!(dist_to_ray(&left, point) < 1e-6 && dist_to_ray(&right, point) < 1e-6);
+    }
+
+    // On the horizon, the eyes are the IPD apart
+    let left = viewport.centre_ray(10., 16., w, h);
+    assert!(((left.pos() - viewport.pos).length() - 0.05).abs() < EPSILON);
+    // Straight up, there isn't a stereo effect
+    let up = viewport.centre_ray(10., 32., w, h);
+    assert!((up.pos() - viewport.pos).length() < EPSILON);
+}
+
+/// A stereo camera has to converge in front of it
+#[test]
+pub fn stereo_invalid() {
+    for convergence in [0., -1., Number::NAN] {
+        let camera = Camera {
+            stereo: Some(StereoCamera {
+                convergence,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(camera.calculate_viewport().is_err());
+    }
+}
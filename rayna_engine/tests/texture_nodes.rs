@@ -0,0 +1,68 @@
+use approx::assert_relative_eq;
+use rand::rngs::mock::StepRng;
+use rayna_engine::core::types::*;
+use rayna_engine::texture::math::{MathOp, MathTexture};
+use rayna_engine::texture::mix::MixTexture;
+use rayna_engine::texture::solid::SolidTexture;
+use rayna_engine::texture::Texture;
+
+mod common;
+
+fn value_of(texture: &impl Texture) -> Colour {
+    let intersection = common::intersection(Point3::ZERO, Vector3::Y, Point2::ZERO);
+    texture.value(&intersection, &mut StepRng::new(0, 1))
+}
+
+fn solid(colour: [Channel; 3]) -> SolidTexture { SolidTexture::from(Colour::new(colour)) }
+
+fn assert_colour_eq(a: Colour, b: [Channel; 3]) {
+    for c in 0..3 {
+        assert_relative_eq!(a[c], b[c], epsilon = 1e-6);
+    }
+}
+
+/// Mixing should blend each channel by the factor
+#[test]
+pub fn mix_texture() {
+    let mix = |factor| MixTexture {
+        a: solid([0., 0.2, 1.]),
+        b: solid([1., 0.6, 0.]),
+        factor: solid(factor),
+    };
+    assert_colour_eq(value_of(&mix([0.; 3])), [0., 0.2, 1.]);
+    assert_colour_eq(value_of(&mix([1.; 3])), [1., 0.6, 0.]);
+    assert_colour_eq(value_of(&mix([0.25; 3])), [0.25, 0.3, 0.75]);
+    assert_colour_eq(value_of(&mix([1., 0., 0.5])), [1., 0.2, 0.5]);
+}
+
+/// Each of the maths operations should apply to every channel
+#[test]
+pub fn math_texture() {
+    let math = |op| MathTexture {
+        texture: solid([0., 0.25, 2.]),
+        op,
+    };
+    assert_colour_eq(value_of(&math(MathOp::Invert)), [1., 0.75, -1.]);
+    assert_colour_eq(value_of(&math(MathOp::Clamp { min: 0.1, max: 1. })), [0.1, 0.25, 1.]);
+    assert_colour_eq(value_of(&math(MathOp::Gamma(2.))), [0., 0.0625, 4.]);
+    assert_colour_eq(value_of(&math(MathOp::Contrast(2.))), [-0.5, 0., 3.5]);
+    assert_colour_eq(value_of(&math(MathOp::Multiply(3.))), [0., 0.75, 6.]);
+    // The limits being the wrong way round shouldn't panic
+    let _ = value_of(&math(MathOp::Clamp { min: 1., max: 0. }));
+
+    // Operations can be chained, and used as the factor of a mix
+    let chained = MathTexture {
+        texture: math(MathOp::Multiply(2.)),
+        op: MathOp::Invert,
+    };
+    assert_colour_eq(value_of(&chained), [1., 0.5, -3.]);
+    let mixed = MixTexture {
+        a: solid([0.; 3]),
+        b: solid([1.; 3]),
+        factor: MathTexture {
+            texture: solid([0.2; 3]),
+            op: MathOp::Invert,
+        },
+    };
+    assert_colour_eq(value_of(&mixed), [0.8; 3]);
+}
@@ -0,0 +1,28 @@
+use ndarray::Array2;
+use rayna_engine::render::tile::{split_tiles, tiles};
+
+/// The tiles should cover every pixel exactly once, including the partial tiles at the edges
+#[test]
+pub fn tiles_cover_image() {
+    let (w, h) = (35, 20);
+    let tiles = tiles([w, h], 16);
+    assert_eq!(tiles.len(), 3 * 2);
+    assert_eq!(tiles.iter().map(|tile| tile.area()).sum::<usize>(), w * h);
+
+    let mut covered = Array2::<usize>::zeros((w, h));
+    tiles
+        .iter()
+        .flat_map(|tile| tile.pixels())
+        .for_each(|px| covered[px] += 1);
+    assert!(covered.iter().all(|&count| count == 1));
+
+    // Writing through the split views should write to the matching pixels
+    let mut img = Array2::<(usize, usize)>::default((w, h));
+    let split = split_tiles(img.view_mut(), 16);
+    assert_eq!(split.iter().map(|(tile, _)| *tile).collect::<Vec<_>>(), tiles);
+    for (tile, mut view) in split {
+        view.indexed_iter_mut()
+            .for_each(|((x, y), px)| *px = (tile.x + x, tile.y + y));
+    }
+    assert!(img.indexed_iter().all(|(px, &value)| px == value));
+}
@@ -0,0 +1,36 @@
+use rayna_engine::core::types::*;
+use rayna_engine::render::postprocess::tone_mapping::{apply_tone_mapping, ToneMapping};
+use strum::IntoEnumIterator;
+
+/// Every operator should keep black as black, get brighter as the input does, and never go above white
+#[test]
+pub fn tone_mapping_operators() {
+    let grey = |v: Channel| Colour::from([v; 3]);
+    for mapping in ToneMapping::iter() {
+        let black = mapping.apply(Colour::BLACK);
+        assert!(
+            black.iter().all(|c| c.abs() < 1e-3),
+            "{mapping} should keep black: {black:?}"
+        );
+
+        let mut prev = black;
+        for v in [0.01, 0.1, 0.5, 1., 2., 10., 100.] {
+            let mapped = mapping.apply(grey(v));
+            assert!(mapped[0] >= prev[0], "{mapping} should be increasing at {v}");
+            if mapping != ToneMapping::Linear {
+                assert!(
+                    mapped.iter().all(|&c| c <= 1.),
+                    "{mapping} should compress {v} into range"
+                );
+            }
+            prev = mapped;
+        }
+    }
+
+    // Linear doesn't change anything, and the others do
+    let mut img = Image::new_filled(4, 4, grey(4.));
+    apply_tone_mapping(&mut img, ToneMapping::Linear);
+    assert!(img.iter().all(|&c| c == grey(4.)));
+    apply_tone_mapping(&mut img, ToneMapping::Reinhard);
+    assert!(img.iter().all(|&c| c[0] < 1.));
+}
@@ -0,0 +1,83 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::material::lambertian::LambertianMaterial;
+use rayna_engine::material::MaterialInstance;
+use rayna_engine::mesh::primitive::sphere::SphereMesh;
+use rayna_engine::object::simple::SimpleObject;
+use rayna_engine::render::output::{save_with_alpha, OutputError, OutputFormat};
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::StandardScene;
+use rayna_engine::skybox::simple::WhiteSkybox;
+use rayna_engine::texture::TextureInstance;
+use std::convert::identity;
+
+mod common;
+
+/// With a transparent sky, the sky should be black and transparent, while the objects stay opaque (and are still lit
+/// by the sky). The alpha should be saved to PNGs, but not JPEGs
+#[test]
+pub fn transparent_sky() {
+    let material: MaterialInstance<TextureInstance> = LambertianMaterial {
+        albedo: [0.8; 3].into(),
+    }
+    .into();
+    let scene = StandardScene {
+        objects: [SimpleObject::new_uncorrected(
+            SphereMesh::new(Point3::ZERO, 1.),
+            material,
+            None,
+        )]
+        .into(),
+        skybox: WhiteSkybox.into(),
+    };
+    // The sphere fills the middle of the image, and the corners only see the sky
+    let camera = Camera {
+        pos: Point3::new(0., 0., -5.),
+        focus_dist: 5.,
+        ..Default::default()
+    };
+    let opts = RenderOpts {
+        width: nonzero!(32_usize),
+        height: nonzero!(32_usize),
+        samples: nonzero!(4_usize),
+        transparent_sky: true,
+        ..common::SIMPLE_RENDER_OPTIONS
+    };
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(scene, camera, opts, common::RENDERER_THREAD_COUNT)
+        .expect("failed creating renderer");
+
+    let render = renderer.render();
+    let alpha = render.alpha.as_ref().expect("transparent renders should have an alpha");
+    let (corner, centre) = ((0, 0), (16, 16));
+    assert_eq!(render.img[corner], Colour::BLACK);
+    assert_eq!(alpha[corner], Colour::BLACK);
+    assert_eq!(alpha[centre], Colour::WHITE);
+    assert!(
+        render.img[centre].into_iter().all(|c| c > 0.),
+        "the sphere should still be lit by the sky"
+    );
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let png = dir.path().join("img.png");
+    save_with_alpha(&render.img, alpha, &png, OutputFormat::Png, identity).expect("failed saving PNG");
+    let loaded = image::open(&png).expect("failed loading PNG").into_rgba8();
+    assert_eq!(loaded[(0, 0)].0[3], 0);
+    assert_eq!(loaded[(16, 16)].0[3], 255);
+
+    assert!(matches!(
+        save_with_alpha(
+            &render.img,
+            alpha,
+            dir.path().join("img.jpg"),
+            OutputFormat::Jpeg { quality: 90 },
+            identity
+        ),
+        Err(OutputError::NoAlpha { .. })
+    ));
+
+    // Opaque renders don't have one
+    renderer.set_options(common::SIMPLE_RENDER_OPTIONS);
+    assert!(renderer.render().alpha.is_none());
+}
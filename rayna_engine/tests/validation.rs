@@ -0,0 +1,92 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::asset::{placeholder_image, AssetError, AssetResolver};
+use rayna_engine::scene::validation::{SceneValidation, SceneWarning};
+use rayna_engine::skybox::hdri::HdrImageSkybox;
+use rayna_engine::texture::image::ImageTexture;
+use std::path::Path;
+
+/// A resolver for a temporary directory that only has a valid `texture.png`, and a `broken.hdr` that isn't an image
+fn new_resolver() -> (tempfile::TempDir, AssetResolver) {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    image::RgbImage::from_pixel(2, 2, image::Rgb([255; 3]))
+        .save(dir.path().join("texture.png"))
+        .expect("failed saving PNG");
+    std::fs::write(dir.path().join("broken.hdr"), "not an image").expect("failed writing file");
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+    (dir, resolver)
+}
+
+/// Whether the image is the placeholder for missing assets
+fn is_placeholder(img: &Image) -> bool {
+    let placeholder = placeholder_image();
+    [img.width(), img.height()] == [placeholder.width(), placeholder.height()] && img.iter().eq(placeholder.iter())
+}
+
+/// Loading assets strictly should fail with the reason they couldn't be loaded
+#[test]
+pub fn invalid_assets_are_rejected() {
+    let (_dir, resolver) = new_resolver();
+
+    assert!(ImageTexture::load(&resolver, "texture.png").is_ok());
+    assert!(matches!(
+        ImageTexture::load(&resolver, "missing.png"),
+        Err(AssetError::NotFound { path, .. }) if path == Path::new("missing.png")
+    ));
+    assert!(matches!(
+        ImageTexture::load_linear(&resolver, "missing.png"),
+        Err(AssetError::NotFound { .. })
+    ));
+    assert!(matches!(
+        HdrImageSkybox::load(&resolver, "broken.hdr"),
+        Err(AssetError::ImageError { path, .. }) if path.ends_with("broken.hdr")
+    ));
+}
+
+/// Loading a scene with missing or broken assets should replace them with placeholders, and record a warning for each
+/// one (in order) with the same reason that loading it strictly would have failed with
+#[test]
+pub fn invalid_assets_are_reported() {
+    let (_dir, resolver) = new_resolver();
+    let mut validation = SceneValidation::new();
+
+    let valid = ImageTexture::load_or_placeholder(&resolver, "texture.png", &mut validation);
+    assert!(
+        validation.is_ok(),
+        "valid assets shouldn't be reported: {:?}",
+        validation.warnings()
+    );
+    assert!(!is_placeholder(valid.image()));
+
+    let missing = ImageTexture::load_or_placeholder(&resolver, "missing.png", &mut validation);
+    let missing_linear = ImageTexture::load_linear_or_placeholder(&resolver, "normals.png", &mut validation);
+    let broken = HdrImageSkybox::load_or_placeholder(&resolver, "broken.hdr", &mut validation);
+    assert!(is_placeholder(missing.image()));
+    assert!(is_placeholder(missing_linear.image()));
+    assert!(is_placeholder(&broken.image));
+
+    let reason = |err: AssetError| err.to_string();
+    let expected = [
+        (
+            "missing.png",
+            reason(ImageTexture::load(&resolver, "missing.png").unwrap_err()),
+        ),
+        (
+            "normals.png",
+            reason(ImageTexture::load_linear(&resolver, "normals.png").unwrap_err()),
+        ),
+        (
+            "broken.hdr",
+            reason(HdrImageSkybox::load(&resolver, "broken.hdr").unwrap_err()),
+        ),
+    ]
+    .map(|(path, reason)| SceneWarning::MissingAsset {
+        path: path.into(),
+        reason,
+    });
+    assert!(!validation.is_ok());
+    assert_eq!(validation.warnings(), expected);
+
+    let message = validation.warnings()[0].to_string();
+    assert!(message.contains("missing.png"), "{message}");
+    assert!(message.contains("placeholder"), "{message}");
+}
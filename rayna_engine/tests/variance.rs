@@ -0,0 +1,82 @@
+use nonzero::nonzero;
+use rayna_engine::core::types::*;
+use rayna_engine::render::render_opts::RenderOpts;
+use rayna_engine::render::renderer::Renderer;
+use rayna_engine::render::variance;
+use rayna_engine::scene::preset;
+
+mod common;
+
+const OPTS: RenderOpts = RenderOpts {
+    width: nonzero!(32_usize),
+    height: nonzero!(24_usize),
+    samples: nonzero!(1_usize),
+    deterministic: true,
+    variance: true,
+    ..common::SIMPLE_RENDER_OPTIONS
+};
+
+fn mean(img: &Image) -> Number { img.iter().map(|c| c.0[0] as Number).sum::<Number>() / img.len() as Number }
+
+/// The variance is only there when it's enabled, and once there have been passes to compare
+#[test]
+pub fn variance_needs_passes() {
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer = Renderer::<_, _, common::Rng>::new_from(
+        preset.scene.clone(),
+        preset.camera.clone(),
+        OPTS,
+        common::RENDERER_THREAD_COUNT,
+    )
+    .expect("failed creating renderer");
+    let mut img = Image::new_blank(0, 0);
+    assert!(
+        !renderer.read_variance(&mut img),
+        "there shouldn't be a variance before any passes"
+    );
+
+    renderer.render();
+    assert!(renderer.read_variance(&mut img));
+    assert!(
+        img.iter().all(|v| v.0[0].is_infinite()),
+        "one pass can't give a variance"
+    );
+    assert_eq!(variance::heatmap(&img).dim(), img.dim());
+
+    let mut disabled = Renderer::<_, _, common::Rng>::new_from(
+        preset.scene,
+        preset.camera,
+        RenderOpts {
+            variance: false,
+            ..OPTS
+        },
+        common::RENDERER_THREAD_COUNT,
+    )
+    .expect("failed creating renderer");
+    disabled.render();
+    disabled.render();
+    assert!(
+        !disabled.read_variance(&mut img),
+        "the variance shouldn't be tracked when it's disabled"
+    );
+}
+
+/// As more passes are accumulated, the render should converge, so the variance should go down
+#[test]
+pub fn variance_decreases() {
+    let preset = preset::RTIAW_DEMO();
+    let mut renderer =
+        Renderer::<_, _, common::Rng>::new_from(preset.scene, preset.camera, OPTS, common::RENDERER_THREAD_COUNT)
+            .expect("failed creating renderer");
+    let mut img = Image::new_blank(0, 0);
+    let mut means = vec![];
+    for passes in 1..=32 {
+        renderer.render();
+        if [4, 32].contains(&passes) {
+            assert!(renderer.read_variance(&mut img));
+            assert!(img.iter().all(|v| v.0[0].is_finite() && v.0[0] >= 0.));
+            means.push(mean(&img));
+        }
+    }
+    assert!(means[1] < means[0] * 0.5, "variance should decrease: {means:?}");
+}
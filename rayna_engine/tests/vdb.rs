@@ -0,0 +1,144 @@
+use rayna_engine::core::types::*;
+use rayna_engine::scene::asset::{AssetError, AssetResolver};
+use std::path::Path;
+
+/// A tile or voxel in a VDB fixture: its index inside the node it's in, and its value
+type Value = ([usize; 3], f32);
+
+/// Writes a tiny, uncompressed VDB file with a single `"density"` grid of floats.
+///
+/// The grid has one top-level node (of `32^3` children, each `128^3` voxels) at the origin, with the `top_tiles`.
+/// If there are any `node_tiles` (each `8^3` voxels) or `voxels`, the first child of the top node is an internal node
+/// holding them, and the `voxels` are in its first leaf (also at the origin).
+fn write_vdb(path: &Path, top_tiles: &[Value], node_tiles: &[Value], voxels: &[Value]) {
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend((s.len() as u32).to_le_bytes());
+        out.extend(s.as_bytes());
+    }
+    /// The bit mask of which values are set, followed by the values themselves ("compressed" with the mask)
+    fn values(out: &mut Vec<u8>, log2_dim: usize, values: &[Value]) {
+        let index = |[x, y, z]: [usize; 3]| (x << (2 * log2_dim)) + (y << log2_dim) + z;
+        let mut sorted = values.to_vec();
+        sorted.sort_by_key(|&(pos, _)| index(pos));
+        let mut mask = vec![0_u64; (1 << (3 * log2_dim)) / 64];
+        sorted
+            .iter()
+            .for_each(|&(pos, _)| mask[index(pos) / 64] |= 1 << (index(pos) % 64));
+        mask.iter().for_each(|word| out.extend(word.to_le_bytes()));
+        // No inactive values, and only the active values are stored
+        out.push(0);
+        sorted.iter().for_each(|(_, value)| out.extend(value.to_le_bytes()));
+    }
+    fn child_mask(out: &mut Vec<u8>, log2_dim: usize, first_child: bool) {
+        let mut mask = vec![0_u64; (1 << (3 * log2_dim)) / 64];
+        mask[0] = first_child as u64;
+        mask.iter().for_each(|word| out.extend(word.to_le_bytes()));
+    }
+    let has_node = !node_tiles.is_empty() || !voxels.is_empty();
+    let has_leaf = !voxels.is_empty();
+
+    // Header
+    let mut out = vec![];
+    out.extend(0x56444220_u64.to_le_bytes());
+    out.extend(224_u32.to_le_bytes());
+    out.extend([10_u32, 0].map(u32::to_le_bytes).concat());
+    // Has grid offsets
+    out.push(1);
+    out.extend(b"00000000-0000-0000-0000-000000000000");
+    // No file metadata, and one grid
+    out.extend([0_u32, 1].map(u32::to_le_bytes).concat());
+
+    // Grid descriptor, which points to the positions of the grid and its leaf data
+    string(&mut out, "density");
+    string(&mut out, "Tree_float_5_4_3");
+    string(&mut out, "");
+    let offsets_pos = out.len();
+    out.extend([0_u8; 24]);
+
+    // Grid, with `ACTIVE_MASK` compression, no metadata, and a unit transform
+    let grid_pos = out.len();
+    out.extend([2_u32, 0].map(u32::to_le_bytes).concat());
+    string(&mut out, "UniformScaleMap");
+    for vector in [[1.; 3], [1.; 3], [1.; 3], [1.; 3], [0.5; 3]] {
+        vector.iter().for_each(|x: &f64| out.extend(x.to_le_bytes()));
+    }
+
+    // Tree topology: one buffer, the background, no root tiles, one child
+    out.extend(1_u32.to_le_bytes());
+    out.extend(0_f32.to_le_bytes());
+    out.extend([0_u32, 1].map(u32::to_le_bytes).concat());
+    out.extend([0_i32; 3].map(i32::to_le_bytes).concat());
+    child_mask(&mut out, 5, has_node);
+    values(&mut out, 5, top_tiles);
+    if has_node {
+        child_mask(&mut out, 4, has_leaf);
+        values(&mut out, 4, node_tiles);
+    }
+    let mut leaf_mask = vec![];
+    if has_leaf {
+        // The leaf's topology is just its mask, which is the same as the one with its values
+        values(&mut leaf_mask, 3, voxels);
+        out.extend(&leaf_mask[..64]);
+    }
+
+    // Leaf data
+    let block_pos = out.len();
+    out.extend(leaf_mask);
+    let end_pos = out.len();
+
+    let offsets = [grid_pos, block_pos, end_pos]
+        .map(|pos| (pos as i64).to_le_bytes())
+        .concat();
+    out[offsets_pos..offsets_pos + 24].copy_from_slice(&offsets);
+    std::fs::write(path, out).expect("failed writing VDB");
+}
+
+/// Voxels and tiles should be loaded into a dense grid that just covers them
+#[test]
+pub fn load_vdb() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    write_vdb(
+        &dir.path().join("smoke.vdb"),
+        &[],
+        &[([1, 0, 0], 0.5)],
+        &[([1, 2, 3], 1.), ([4, 4, 4], 0.25)],
+    );
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+
+    let grid = resolver.load_vdb("smoke.vdb", None).expect("failed loading VDB");
+    // The voxels start at `x = 1`, and the tile covers `8..16` on `x`, and `0..8` on `y` and `z`
+    assert_eq!(grid.origin(), Point3::new(1., 0., 0.));
+    assert_eq!(grid.dims(), [15, 8, 8]);
+    assert_eq!(grid.max_value(), 1.);
+    let data = grid.data();
+    assert_eq!(data[[0, 2, 3]], 1.);
+    assert_eq!(data[[3, 4, 4]], 0.25);
+    assert_eq!(data[[0, 0, 0]], 0.);
+    assert_eq!(data[[7, 0, 0]], 0.5);
+    assert_eq!(data[[14, 7, 7]], 0.5);
+
+    assert!(matches!(
+        resolver.load_vdb("smoke.vdb", Some("temperature")),
+        Err(AssetError::VdbGridNotFound { .. })
+    ));
+}
+
+/// Huge tiles should be rejected, instead of being expanded into billions of voxels
+#[test]
+pub fn vdb_too_large() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    // Two tiles at opposite corners of the top node, which covers `4096^3` voxels
+    write_vdb(
+        &dir.path().join("huge.vdb"),
+        &[([0, 0, 0], 1.), ([31, 31, 31], 1.)],
+        &[],
+        &[],
+    );
+    let resolver = AssetResolver::new().with_base_dir(dir.path());
+
+    let result = resolver.load_vdb("huge.vdb", None);
+    match result {
+        Err(AssetError::VdbTooLarge { source, .. }) => assert!(source.dims.iter().all(|&d| d >= 4096), "{source}"),
+        other => panic!("expected the grid to be too large, got {other:?}"),
+    }
+}
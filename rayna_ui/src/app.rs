@@ -1,5 +1,5 @@
 use crate::ext::ui_ext::UiExt as _;
-use crate::integration::message::MessageToWorker;
+use crate::integration::message::{DisplayChannel, MessageToUi, MessageToWorker};
 use crate::integration::{Integration, IntegrationError};
 use crate::targets::*;
 use crate::ui_val::*;
@@ -7,15 +7,29 @@ use eframe::epaint::textures::TextureFilter;
 use egui::load::SizedTexture;
 use egui::{ColorImage, Context, CursorIcon, Key, Sense, TextureHandle, TextureOptions, TextureWrapMode, Vec2, Widget};
 use puffin::{profile_function, profile_scope};
+use rayna_engine::core::capabilities::Capabilities;
+use rayna_engine::core::job::{JobInfo, JobStatus};
 use rayna_engine::core::types::*;
+use rayna_engine::render::compare::ComparisonMetrics;
+use rayna_engine::render::exposure::AutoExposure;
+use rayna_engine::render::filter::PixelFilter;
+use rayna_engine::render::postprocess::denoise::Denoise;
+use rayna_engine::render::postprocess::tone_mapping::ToneMapping;
+use rayna_engine::render::postprocess::white_balance::WhiteBalance;
+use rayna_engine::render::progress::RenderProgress;
 use rayna_engine::render::render::RenderStats;
 use rayna_engine::render::render_opts::{RenderMode, RenderOpts};
-use rayna_engine::scene::camera::Camera;
+use rayna_engine::scene::aperture::Aperture;
+use rayna_engine::scene::camera::{Camera, PhysicalCamera, Projection, Shutter};
+use rayna_engine::scene::lens::{LensDistortion, TiltShift};
 use rayna_engine::scene::preset::PresetScene;
+use rayna_engine::scene::stereo::{StereoCamera, StereoLayout};
 use rayna_engine::scene::{self, StandardScene};
+use rayna_engine::shared::rng::SamplerKind;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 use strum::IntoEnumIterator;
 use throttle::Throttle;
 use tracing::{error, info, trace, warn};
@@ -26,6 +40,8 @@ pub struct RaynaApp {
     scene: StandardScene,
     camera: Camera,
     all_presets: Vec<PresetScene>,
+    /// What the engine supports, shown in the stats
+    capabilities: Capabilities,
 
     // Display things
     /// A handle to the texture that holds the current render buffer
@@ -37,6 +53,26 @@ pub struct RaynaApp {
     /// Used by the "fit canvas to screen" button
     render_display_size: Vec2,
     render_stats: RenderStats,
+    /// How far through the frame the worker is, so that slow frames don't look stuck
+    render_progress: Option<RenderProgress>,
+    /// Which image the worker sends to be displayed
+    display: DisplayChannel,
+
+    // Snapshots
+    /// Resolution multiplier used when taking a snapshot, relative to the current render size
+    snapshot_scale: NonZeroUsize,
+    /// Number of frames accumulated when taking a snapshot
+    snapshot_frames: NonZeroUsize,
+    // Comparisons
+    /// The render options that the current options are compared against
+    compare_baseline: Option<RenderOpts>,
+    /// Number of frames accumulated for each render in a comparison
+    compare_frames: NonZeroUsize,
+    /// The results of the most recent comparison
+    comparison: Option<ComparisonMetrics>,
+
+    /// The most recent state of the worker's background jobs
+    jobs: Vec<JobInfo>,
 
     // Integration with the engine and worker
     integration: Integration,
@@ -48,9 +84,17 @@ impl crate::backend::UiApp for RaynaApp {
     fn new(ctx: &Context) -> Self {
         info!(target: MAIN, "ui app init");
 
+        let capabilities = rayna_engine::capabilities();
+        info!(target: MAIN, ?capabilities, "engine capabilities");
+
         trace!(target: MAIN, "loading preset scene and render opts");
         let PresetScene { scene, camera, name: _ } = scene::preset::RTTNW_DEMO();
-        let render_opts = Default::default();
+        // The camera is moved around interactively, so the previews and reprojection are worth it
+        let render_opts = RenderOpts {
+            progressive_preview: true,
+            temporal_reprojection: true,
+            ..Default::default()
+        };
         let all_presets = scene::preset::ALL().into();
 
         trace!(target: MAIN, "creating render buffer texture");
@@ -80,11 +124,21 @@ impl crate::backend::UiApp for RaynaApp {
             camera,
             render_opts,
             all_presets,
+            capabilities,
 
             render_buf_tex_options,
             render_buf_tex,
             render_display_size: egui::vec2(1.0, 1.0),
             render_stats: Default::default(),
+            render_progress: None,
+            display: DisplayChannel::Beauty,
+
+            snapshot_scale: NonZeroUsize::new(2).unwrap(),
+            snapshot_frames: NonZeroUsize::new(64).unwrap(),
+            compare_baseline: None,
+            compare_frames: NonZeroUsize::new(16).unwrap(),
+            comparison: None,
+            jobs: vec![],
         }
     }
 
@@ -155,11 +209,85 @@ impl crate::backend::UiApp for RaynaApp {
                 dirty_render_opts |= egui::DragValue::new(&mut msaa).ui(ui).changed();
                 self.render_opts.samples = NonZeroUsize::new(msaa).unwrap_or(NonZeroUsize::MIN);
 
+                // TARGET SAMPLES
+
+                let mut target_samples = self.render_opts.target_samples.is_some();
+                if ui.checkbox(&mut target_samples, "Target Samples").changed() {
+                    dirty_render_opts = true;
+                    self.render_opts.target_samples = NonZeroUsize::new(1024).filter(|_| target_samples);
+                }
+                if let Some(target) = &mut self.render_opts.target_samples {
+                    let mut total = target.get();
+                    dirty_render_opts |= egui::DragValue::new(&mut total).ui(ui).changed();
+                    *target = NonZeroUsize::new(total).unwrap_or(NonZeroUsize::MIN);
+                }
+
+                // TIME LIMIT
+
+                let mut time_limit = self.render_opts.max_time.is_some();
+                if ui.checkbox(&mut time_limit, "Time Limit").changed() {
+                    dirty_render_opts = true;
+                    self.render_opts.max_time = time_limit.then_some(Duration::from_secs(60));
+                }
+                if let Some(max_time) = &mut self.render_opts.max_time {
+                    let mut secs = max_time.as_secs_f64();
+                    dirty_render_opts |= egui::DragValue::new(&mut secs)
+                        .suffix("s")
+                        .clamp_range(0.0..=Number::INFINITY)
+                        .ui(ui)
+                        .changed();
+                    *max_time = Duration::from_secs_f64(secs);
+                }
+
+                // PROGRESSIVE PREVIEW
+
+                dirty_render_opts |= ui
+                    .checkbox(&mut self.render_opts.progressive_preview, "Progressive Preview")
+                    .changed();
+
+                // TEMPORAL REPROJECTION
+
+                dirty_render_opts |= ui
+                    .checkbox(&mut self.render_opts.temporal_reprojection, "Temporal Reprojection")
+                    .changed();
+
                 // RAY BOUNCE DEPTH
 
                 ui.label("Ray Depth");
                 dirty_render_opts |= egui::DragValue::new(&mut self.render_opts.ray_depth).ui(ui).changed();
 
+                // SAMPLER
+
+                ui.label("Sampler");
+                egui::ComboBox::from_id_source("sampler")
+                    .selected_text(<&'static str>::from(self.render_opts.sampler))
+                    .show_ui(ui, |ui| {
+                        for variant in SamplerKind::iter() {
+                            let resp = ui.selectable_value::<SamplerKind>(
+                                &mut self.render_opts.sampler,
+                                variant,
+                                <&'static str>::from(variant),
+                            );
+                            dirty_render_opts |= resp.changed();
+                        }
+                    });
+
+                // FILTER
+
+                ui.label("Filter");
+                egui::ComboBox::from_id_source("filter")
+                    .selected_text(<&'static str>::from(self.render_opts.filter))
+                    .show_ui(ui, |ui| {
+                        for variant in PixelFilter::iter() {
+                            let resp = ui.selectable_value::<PixelFilter>(
+                                &mut self.render_opts.filter,
+                                variant,
+                                <&'static str>::from(variant),
+                            );
+                            dirty_render_opts |= resp.changed();
+                        }
+                    });
+
                 // RAY BRANCHING
 
                 ui.label("Ray Branching");
@@ -167,6 +295,153 @@ impl crate::backend::UiApp for RaynaApp {
                 dirty_render_opts |= egui::DragValue::new(&mut ray_branching).ui(ui).changed();
                 self.render_opts.ray_branching = NonZeroUsize::new(ray_branching).unwrap_or(NonZeroUsize::MIN);
 
+                // ROBUST INTERSECTIONS
+
+                dirty_render_opts |= ui
+                    .checkbox(&mut self.render_opts.robust_intersections, "Robust Intersections")
+                    .changed();
+
+                // BVH STATS
+
+                dirty_render_opts |= ui.checkbox(&mut self.render_opts.bvh_stats, "BVH Stats").changed();
+
+                // INTERSECTION CAPACITY
+
+                ui.label("Intersection Capacity");
+                dirty_render_opts |= egui::DragValue::new(&mut self.render_opts.intersection_capacity)
+                    .ui(ui)
+                    .changed();
+
+                // DETERMINISTIC
+
+                dirty_render_opts |= ui
+                    .checkbox(&mut self.render_opts.deterministic, "Deterministic")
+                    .changed();
+
+                // AOVS
+
+                dirty_render_opts |= ui.checkbox(&mut self.render_opts.aovs, "AOVs").changed();
+
+                // VARIANCE
+
+                dirty_render_opts |= ui.checkbox(&mut self.render_opts.variance, "Variance").changed();
+
+                // DISPLAY CHANNEL
+
+                ui.label("Display");
+                let mut display_changed = false;
+                egui::ComboBox::from_id_source("display")
+                    .selected_text(self.display.name())
+                    .show_ui(ui, |ui| {
+                        for variant in DisplayChannel::ALL {
+                            display_changed |= ui
+                                .selectable_value(&mut self.display, variant, variant.name())
+                                .changed();
+                        }
+                    });
+                if display_changed {
+                    // The heatmap can't be shown without the variance
+                    if self.display == DisplayChannel::Variance && !self.render_opts.variance {
+                        self.render_opts.variance = true;
+                        dirty_render_opts = true;
+                    }
+                    if let Err(err) = self.integration.send_message(MessageToWorker::SetDisplay(self.display)) {
+                        warn!(target: UI, ?err)
+                    }
+                }
+
+                // TRANSPARENT SKY
+
+                dirty_render_opts |= ui
+                    .checkbox(&mut self.render_opts.transparent_sky, "Transparent Sky")
+                    .changed();
+
+                // AUTO EXPOSURE
+
+                let mut auto_exposure = self.render_opts.auto_exposure.is_some();
+                if ui.checkbox(&mut auto_exposure, "Auto Exposure").changed() {
+                    dirty_render_opts = true;
+                    self.render_opts.auto_exposure = auto_exposure.then(AutoExposure::default);
+                }
+                if let Some(exposure) = &mut self.render_opts.auto_exposure {
+                    ui.label("Min/Max Exposure");
+                    ui.horizontal(|ui| {
+                        dirty_render_opts |= egui::DragValue::new(&mut exposure.min_ev)
+                            .speed(DRAG_SLOW)
+                            .suffix(UNIT_EV)
+                            .clamp_range(Number::NEG_INFINITY..=exposure.max_ev)
+                            .ui(ui)
+                            .changed();
+                        dirty_render_opts |= egui::DragValue::new(&mut exposure.max_ev)
+                            .speed(DRAG_SLOW)
+                            .suffix(UNIT_EV)
+                            .clamp_range(exposure.min_ev..=Number::INFINITY)
+                            .ui(ui)
+                            .changed();
+                    });
+                    ui.label("Adaptation Speed");
+                    dirty_render_opts |= egui::DragValue::new(&mut exposure.speed)
+                        .speed(DRAG_SLOW)
+                        .clamp_range(0.0..=Number::INFINITY)
+                        .ui(ui)
+                        .changed();
+                }
+
+                // EXPOSURE & WHITE BALANCE
+
+                ui.label("Exposure");
+                dirty_render_opts |= egui::DragValue::new(&mut self.render_opts.exposure)
+                    .speed(DRAG_SLOW)
+                    .suffix(UNIT_EV)
+                    .ui(ui)
+                    .changed();
+                ui.label("White Balance (Temperature/Tint)");
+                ui.horizontal(|ui| {
+                    let white_balance = &mut self.render_opts.white_balance;
+                    dirty_render_opts |= egui::DragValue::new(&mut white_balance.temperature)
+                        .speed(10.)
+                        .suffix("K")
+                        .clamp_range(WhiteBalance::TEMPERATURE_RANGE)
+                        .ui(ui)
+                        .changed();
+                    dirty_render_opts |= egui::DragValue::new(&mut white_balance.tint)
+                        .speed(DRAG_SLOW)
+                        .clamp_range(-1.0..=1.0)
+                        .ui(ui)
+                        .changed();
+                });
+
+                // DENOISE
+
+                let mut denoise = self.render_opts.denoise.is_some();
+                if ui.checkbox(&mut denoise, "Denoise").changed() {
+                    dirty_render_opts = true;
+                    self.render_opts.denoise = denoise.then(Denoise::default);
+                }
+                if let Some(denoise) = &mut self.render_opts.denoise {
+                    ui.label("Denoise Iterations");
+                    dirty_render_opts |= egui::DragValue::new(&mut denoise.iterations)
+                        .clamp_range(1..=10)
+                        .ui(ui)
+                        .changed();
+                }
+
+                // TONE MAPPING
+
+                ui.label("Tone Mapping");
+                egui::ComboBox::from_id_source("tone_mapping")
+                    .selected_text(<&'static str>::from(self.render_opts.tone_mapping))
+                    .show_ui(ui, |ui| {
+                        for variant in ToneMapping::iter() {
+                            let resp = ui.selectable_value::<ToneMapping>(
+                                &mut self.render_opts.tone_mapping,
+                                variant,
+                                <&'static str>::from(variant),
+                            );
+                            dirty_render_opts |= resp.changed();
+                        }
+                    });
+
                 // RENDER MODE
 
                 ui.label("Mode");
@@ -182,6 +457,10 @@ impl crate::backend::UiApp for RaynaApp {
                             dirty_render_opts |= resp.changed();
                         }
                     });
+
+                // CLAY
+
+                dirty_render_opts |= ui.checkbox(&mut self.render_opts.clay, "Clay").changed();
             });
 
             ui.group(|ui| {
@@ -194,42 +473,236 @@ impl crate::backend::UiApp for RaynaApp {
                 dirty_camera |= ui.vec3_edit(cam.pos.as_array_mut(), UNIT_LEN).changed();
                 ui.label("fwd");
                 dirty_camera |= ui.vec3_edit(cam.fwd.as_array_mut(), UNIT_LEN).changed();
-                ui.label("fov");
-                dirty_camera |= ui
-                    .add(
-                        egui::DragValue::from_get_set(|o| {
-                            if let Some(val) = o {
-                                cam.v_fov = Angle::from_degrees(val);
-                            }
-                            cam.v_fov.to_degrees()
-                        })
-                        .suffix(UNIT_DEG)
-                        .clamp_range(0.0..=180.0)
-                        .min_decimals(1)
-                        .speed(DRAG_SLOW),
-                    )
-                    .changed();
+                ui.label("projection");
+                egui::ComboBox::from_id_source("projection")
+                    .selected_text(<&'static str>::from(cam.projection))
+                    .show_ui(ui, |ui| {
+                        for variant in Projection::iter() {
+                            let resp = ui.selectable_value::<Projection>(
+                                &mut cam.projection,
+                                variant,
+                                <&'static str>::from(variant),
+                            );
+                            dirty_camera |= resp.changed();
+                        }
+                    });
+                let mut physical = cam.physical.is_some();
+                if ui.checkbox(&mut physical, "physical").changed() {
+                    cam.physical = physical.then(PhysicalCamera::default);
+                    dirty_camera = true;
+                }
+                if let Some(physical) = &mut cam.physical {
+                    ui.label("focal length");
+                    dirty_camera |= egui::DragValue::new(&mut physical.focal_length)
+                        .suffix(UNIT_MM)
+                        .clamp_range(1.0..=2000.0)
+                        .speed(DRAG_NORM)
+                        .ui(ui)
+                        .changed();
+                    ui.label("sensor height");
+                    dirty_camera |= egui::DragValue::new(&mut physical.sensor_height)
+                        .suffix(UNIT_MM)
+                        .clamp_range(1.0..=100.0)
+                        .speed(DRAG_SLOW)
+                        .ui(ui)
+                        .changed();
+                    ui.label("f-number");
+                    dirty_camera |= egui::DragValue::new(&mut physical.f_number)
+                        .prefix("f/")
+                        .clamp_range(0.5..=64.0)
+                        .speed(DRAG_SLOW)
+                        .ui(ui)
+                        .changed();
+                    ui.label("shutter time");
+                    dirty_camera |= egui::DragValue::new(&mut physical.shutter_time)
+                        .suffix(UNIT_SEC)
+                        .clamp_range(1e-4..=60.0)
+                        .speed(1e-3)
+                        .ui(ui)
+                        .changed();
+                    ui.label("iso");
+                    dirty_camera |= egui::DragValue::new(&mut physical.iso)
+                        .clamp_range(25.0..=102400.0)
+                        .speed(10.0)
+                        .ui(ui)
+                        .changed();
+                    ui.label(format!("fov: {:.1}{UNIT_DEG}", cam.lens().0.to_degrees()));
+                    ui.label(format!("exposure: {:.2}{UNIT_EV}", cam.exposure()));
+                } else {
+                    ui.label("fov");
+                    let max_fov = cam.projection.max_fov().to_degrees();
+                    dirty_camera |= ui
+                        .add(
+                            egui::DragValue::from_get_set(|o| {
+                                if let Some(val) = o {
+                                    cam.v_fov = Angle::from_degrees(val);
+                                }
+                                cam.v_fov.to_degrees()
+                            })
+                            .suffix(UNIT_DEG)
+                            .clamp_range(0.0..=max_fov)
+                            .min_decimals(1)
+                            .speed(DRAG_SLOW),
+                        )
+                        .changed();
+                }
                 ui.label("focus dist");
                 dirty_camera |= egui::DragValue::new(&mut cam.focus_dist)
                     .suffix(UNIT_LEN)
                     .speed(DRAG_SLOW)
                     .ui(ui)
                     .changed();
-                ui.label("defocus angle");
+                if cam.physical.is_none() {
+                    ui.label("defocus angle");
+                    dirty_camera |= ui
+                        .add(
+                            egui::DragValue::from_get_set(|o| {
+                                if let Some(val) = o {
+                                    cam.defocus_angle = Angle::from_degrees(val);
+                                }
+                                cam.defocus_angle.to_degrees()
+                            })
+                            .suffix(UNIT_DEG)
+                            .clamp_range(0.0..=180.0)
+                            .min_decimals(1)
+                            .speed(DRAG_SLOW),
+                        )
+                        .changed();
+                }
+                ui.label("aperture blades");
+                let (mut blades, mut rotation) = match cam.aperture {
+                    Aperture::Polygon { blades, rotation } => (blades, rotation),
+                    _ => (0, Angle::from_degrees(0.)),
+                };
+                let mut aperture_changed = egui::DragValue::new(&mut blades)
+                    .clamp_range(0..=16)
+                    .custom_formatter(|n, _| if n < 3. { "circle".into() } else { format!("{n}") })
+                    .ui(ui)
+                    .changed();
+                if blades >= 3 {
+                    ui.label("aperture rotation");
+                    aperture_changed |= ui
+                        .add(
+                            egui::DragValue::from_get_set(|o| {
+                                if let Some(val) = o {
+                                    rotation = Angle::from_degrees(val);
+                                }
+                                rotation.to_degrees()
+                            })
+                            .suffix(UNIT_DEG)
+                            .clamp_range(0.0..=360.0)
+                            .min_decimals(1)
+                            .speed(DRAG_NORM),
+                        )
+                        .changed();
+                }
+                if aperture_changed {
+                    cam.aperture = if blades >= 3 {
+                        Aperture::Polygon { blades, rotation }
+                    } else {
+                        Aperture::Circle
+                    };
+                    dirty_camera = true;
+                }
+                ui.label("shutter (open, close)");
                 dirty_camera |= ui
-                    .add(
-                        egui::DragValue::from_get_set(|o| {
-                            if let Some(val) = o {
-                                cam.defocus_angle = Angle::from_degrees(val);
+                    .horizontal(|ui| {
+                        let Shutter { open, close } = &mut cam.shutter;
+                        egui::DragValue::new(open).speed(DRAG_SLOW).ui(ui)
+                            | egui::DragValue::new(close).speed(DRAG_SLOW).ui(ui)
+                    })
+                    .inner
+                    .changed();
+                let mut distorted = cam.distortion.is_some();
+                if ui.checkbox(&mut distorted, "lens distortion").changed() {
+                    cam.distortion = distorted.then(LensDistortion::default);
+                    dirty_camera = true;
+                }
+                if let Some(lens) = &mut cam.distortion {
+                    ui.label("radial (k1, k2, k3)");
+                    dirty_camera |= ui.vec3_edit(&mut lens.radial, "").changed();
+                    ui.label("tangential (p1, p2)");
+                    dirty_camera |= ui
+                        .horizontal(|ui| {
+                            let [p1, p2] = &mut lens.tangential;
+                            egui::DragValue::new(p1).speed(1e-3).ui(ui) | egui::DragValue::new(p2).speed(1e-3).ui(ui)
+                        })
+                        .inner
+                        .changed();
+                    ui.label("chromatic (r, g, b)");
+                    dirty_camera |= ui.vec3_edit(&mut lens.chromatic, "").changed();
+                }
+                let mut tilt_shift = cam.tilt_shift.is_some();
+                if ui.checkbox(&mut tilt_shift, "tilt-shift").changed() {
+                    cam.tilt_shift = tilt_shift.then(TiltShift::default);
+                    dirty_camera = true;
+                }
+                if let Some(tilt_shift) = &mut cam.tilt_shift {
+                    ui.label("shift (right, up)");
+                    dirty_camera |= ui
+                        .horizontal(|ui| {
+                            let [x, y] = &mut tilt_shift.shift;
+                            egui::DragValue::new(x).speed(1e-3).ui(ui) | egui::DragValue::new(y).speed(1e-3).ui(ui)
+                        })
+                        .inner
+                        .changed();
+                    ui.label("tilt (horizontal, vertical)");
+                    dirty_camera |= ui
+                        .horizontal(|ui| {
+                            let mut changed = false;
+                            for tilt in &mut tilt_shift.tilt {
+                                changed |= ui
+                                    .add(
+                                        egui::DragValue::from_get_set(|o| {
+                                            if let Some(val) = o {
+                                                *tilt = Angle::from_degrees(val);
+                                            }
+                                            tilt.to_degrees()
+                                        })
+                                        .suffix(UNIT_DEG)
+                                        .clamp_range(-89.0..=89.0)
+                                        .min_decimals(1)
+                                        .speed(DRAG_SLOW),
+                                    )
+                                    .changed();
                             }
-                            cam.defocus_angle.to_degrees()
+                            changed
                         })
-                        .suffix(UNIT_DEG)
-                        .clamp_range(0.0..=180.0)
-                        .min_decimals(1)
-                        .speed(DRAG_SLOW),
-                    )
-                    .changed();
+                        .inner;
+                }
+                let mut stereo = cam.stereo.is_some();
+                if ui.checkbox(&mut stereo, "stereo").changed() {
+                    cam.stereo = stereo.then(StereoCamera::default);
+                    dirty_camera = true;
+                }
+                if let Some(stereo) = &mut cam.stereo {
+                    ui.label("eye distance (ipd)");
+                    dirty_camera |= egui::DragValue::new(&mut stereo.ipd)
+                        .suffix(UNIT_LEN)
+                        .clamp_range(0.0..=1.0)
+                        .speed(1e-3)
+                        .ui(ui)
+                        .changed();
+                    ui.label("convergence");
+                    dirty_camera |= egui::DragValue::new(&mut stereo.convergence)
+                        .suffix(UNIT_LEN)
+                        .clamp_range(0.01..=1000.0)
+                        .speed(DRAG_SLOW)
+                        .ui(ui)
+                        .changed();
+                    egui::ComboBox::from_id_source("stereo_layout")
+                        .selected_text(<&'static str>::from(stereo.layout))
+                        .show_ui(ui, |ui| {
+                            for variant in StereoLayout::iter() {
+                                let resp = ui.selectable_value::<StereoLayout>(
+                                    &mut stereo.layout,
+                                    variant,
+                                    <&'static str>::from(variant),
+                                );
+                                dirty_camera |= resp.changed();
+                            }
+                        });
+                }
             });
 
             ui.group(|ui| {
@@ -256,6 +729,110 @@ impl crate::backend::UiApp for RaynaApp {
                 }
             });
 
+            ui.group(|ui| {
+                profile_scope!("sec/snapshot");
+
+                ui.heading("Snapshot");
+
+                ui.label("Resolution Scale");
+                let mut scale = self.snapshot_scale.get();
+                egui::DragValue::new(&mut scale).suffix("x").clamp_range(1..=16).ui(ui);
+                self.snapshot_scale = NonZeroUsize::new(scale).unwrap_or(NonZeroUsize::MIN);
+
+                ui.label("Frames");
+                let mut frames = self.snapshot_frames.get();
+                egui::DragValue::new(&mut frames).ui(ui);
+                self.snapshot_frames = NonZeroUsize::new(frames).unwrap_or(NonZeroUsize::MIN);
+
+                if ui.button("Save Snapshot").clicked() {
+                    let timestamp = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    let path = PathBuf::from(format!("rayna_snapshot_{timestamp}.png"));
+                    info!(target: UI, ?path, "requesting snapshot");
+
+                    if let Err(err) = self.integration.send_message(MessageToWorker::Snapshot {
+                        scale: self.snapshot_scale,
+                        frames: self.snapshot_frames,
+                        path,
+                    }) {
+                        warn!(target: UI, ?err)
+                    }
+                }
+            });
+
+            ui.group(|ui| {
+                profile_scope!("sec/compare");
+
+                ui.heading("Compare Settings");
+
+                if ui.button("Set Baseline").clicked() {
+                    self.compare_baseline = Some(self.render_opts);
+                }
+                ui.label(match self.compare_baseline {
+                    Some(_) => "baseline: set",
+                    None => "baseline: none",
+                });
+
+                ui.label("Frames");
+                let mut frames = self.compare_frames.get();
+                egui::DragValue::new(&mut frames).ui(ui);
+                self.compare_frames = NonZeroUsize::new(frames).unwrap_or(NonZeroUsize::MIN);
+
+                if let Some(baseline) = self.compare_baseline {
+                    if ui.button("Compare With Baseline").clicked() {
+                        info!(target: UI, ?baseline, "requesting comparison");
+
+                        if let Err(err) = self.integration.send_message(MessageToWorker::Compare {
+                            baseline,
+                            frames: self.compare_frames,
+                        }) {
+                            warn!(target: UI, ?err)
+                        }
+                    }
+                }
+
+                if let Some(metrics) = &self.comparison {
+                    ui.label(format!("RMSE: {:.5}", metrics.rmse));
+                    ui.label(format!("FLIP (mean): {:.5}", metrics.mean_flip));
+                    ui.label(format!("FLIP (max): {:.5}", metrics.max_flip));
+                }
+            });
+
+            ui.group(|ui| {
+                profile_scope!("sec/jobs");
+
+                ui.heading("Jobs");
+
+                let mut messages = vec![];
+                for job in &self.jobs {
+                    ui.horizontal(|ui| {
+                        ui.label(job.name.as_str());
+                        if !job.status.is_finished() && ui.small_button("Cancel").clicked() {
+                            messages.push(MessageToWorker::CancelJob(job.id));
+                        }
+                    });
+                    match &job.status {
+                        JobStatus::Failed(err) => ui.label(format!("failed: {err}")),
+                        status => egui::ProgressBar::new(job.progress as f32)
+                            .text(format!("{status:?}"))
+                            .ui(ui),
+                    };
+                }
+
+                if ui.button("Clear Finished").clicked() {
+                    self.jobs.retain(|j| !j.status.is_finished());
+                    messages.push(MessageToWorker::ClearFinishedJobs);
+                }
+
+                for msg in messages {
+                    if let Err(err) = self.integration.send_message(msg) {
+                        warn!(target: UI, ?err)
+                    }
+                }
+            });
+
             ui.group(|ui| {
                 profile_scope!("sec/options");
 
@@ -275,12 +852,52 @@ impl crate::backend::UiApp for RaynaApp {
                 ui.label(format!("width:\t\t\t {}", stats.opts.width.get()));
                 ui.label(format!("height:\t\t\t {}", stats.opts.height.get()));
                 ui.label(format!("samples:\t\t {}", stats.opts.samples));
+                ui.label(format!("sampler:\t\t {}", stats.opts.sampler));
+                ui.label(format!("filter:\t\t\t {}", stats.opts.filter));
                 ui.label(format!("depth:\t\t\t {}", stats.opts.ray_depth));
                 ui.label(format!("branching:\t\t\t {}", stats.opts.ray_branching));
                 ui.label(format!("mode:\t\t\t {}", stats.opts.mode));
+                ui.label(format!("clay:\t\t\t {}", stats.opts.clay));
+                ui.label(format!("robust:\t\t\t {}", stats.opts.robust_intersections));
+                ui.label(format!("bvh stats:\t\t {}", stats.opts.bvh_stats));
+                ui.label(format!("deterministic:\t {}", stats.opts.deterministic));
+                ui.label(format!("reprojection:\t {}", stats.opts.temporal_reprojection));
+                ui.label(format!("aovs:\t\t\t {}", stats.opts.aovs));
+                ui.label(format!("variance:\t\t {}", stats.opts.variance));
+                ui.label(format!("transparent:\t {}", stats.opts.transparent_sky));
+                ui.label(format!("denoise:\t\t {}", stats.opts.denoise.is_some()));
+                ui.label(format!("tone mapping:\t {}", stats.opts.tone_mapping));
+                ui.label(format!("white balance:\t {}K", stats.opts.white_balance.temperature));
                 ui.label(format!("num threads: {}", stats.num_threads));
-                ui.label(format!("accumulated: {}", stats.accum_frames));
+                ui.label(format!(
+                    "accumulated: {} passes, {} samples",
+                    stats.accum_frames, stats.accum_samples
+                ));
+                ui.label(format!("finished:\t\t {}", stats.finished));
+                if let Some(scale) = stats.preview_scale {
+                    ui.label(format!("preview:\t\t 1/{scale}"));
+                }
+                if let Some(progress) = self.render_progress {
+                    let text = match progress.remaining() {
+                        Some(remaining) if !progress.is_done() => format!("{:.1}s left", remaining.as_secs_f64()),
+                        _ => format!("{}/{} tiles", progress.tiles_done, progress.tiles_total),
+                    };
+                    egui::ProgressBar::new(progress.fraction() as f32).text(text).ui(ui);
+                }
+                if let Some(ev) = stats.exposure {
+                    ui.label(format!("exposure:\t\t {ev:+.2}{UNIT_EV}"));
+                }
                 ui.label(format!("duration:\t\t {}", humantime::format_duration(stats.duration)));
+                ui.label(format!("primary rays:\t {}", stats.rays.primary));
+                ui.label(format!("bounce rays:\t {}", stats.rays.bounce));
+                ui.label(format!("shadow rays:\t {}", stats.rays.shadow));
+                ui.label(format!("bvh nodes:\t\t {}", stats.rays.bvh_nodes));
+                ui.label(format!("rays/sec:\t\t {:.3e}", stats.rays_per_sec()));
+
+                let caps = &self.capabilities;
+                ui.label(format!("engine:\t\t\t v{}", caps.version));
+                ui.label(format!("precision:\t\t f{}", caps.number_bits));
+                ui.label(format!("simd:\t\t\t {} bits", caps.simd_width));
             });
         });
 
@@ -402,6 +1019,16 @@ impl crate::backend::UiApp for RaynaApp {
             }
         }
 
+        // The frame the worker is rendering is already out of date, so don't wait for it to finish. Unless only the
+        // camera moved and the frame can be reprojected, since the pass is still worth finishing then
+        if dirty_render_opts || dirty_scene || (dirty_camera && !self.render_opts.temporal_reprojection) {
+            trace!(target: UI, "cancelling stale render");
+
+            if let Err(err) = self.integration.send_message(MessageToWorker::CancelRender) {
+                warn!(target: UI, ?err)
+            }
+        }
+
         // Continuously update UI
         ctx.request_repaint();
     }
@@ -456,9 +1083,30 @@ impl RaynaApp {
                     warn!(target: UI, ?err)
                 }
 
-                Ok(msg) => {
-                    // Don't have any messages implemented currently
-                    error!(target: UI, ?msg, "TODO: Implement message handling")
+                Ok(MessageToUi::SnapshotSaved(path)) => {
+                    info!(target: UI, ?path, "snapshot saved")
+                }
+
+                Ok(MessageToUi::SnapshotFailed { path, error }) => {
+                    error!(target: UI, ?path, %error, "snapshot failed")
+                }
+
+                Ok(MessageToUi::ComparisonFinished(metrics)) => {
+                    info!(target: UI, ?metrics, "comparison finished");
+                    self.comparison = Some(metrics);
+                }
+
+                Ok(MessageToUi::JobsUpdated(jobs)) => self.jobs = jobs,
+
+                Ok(MessageToUi::Progress(progress)) => self.render_progress = Some(progress),
+
+                Ok(MessageToUi::RenderFinished(stats)) => {
+                    info!(
+                        target: UI,
+                        passes = stats.accum_frames,
+                        samples = stats.accum_samples,
+                        "render finished"
+                    );
                 }
             }
         }
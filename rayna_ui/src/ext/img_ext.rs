@@ -1,31 +1,20 @@
-use std::ops::DerefMut as _;
-
 use egui::{Color32, ColorImage};
 use puffin::{profile_function, profile_scope};
 use rayna_engine::core::types::*;
-use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
+use rayon::iter::{IndexedParallelIterator as _, IntoParallelRefMutIterator as _, ParallelIterator as _};
 
 pub trait ImageExt {
     /// Converts the image outputted by the renderer into an egui-appropriate one.
-    /// Also converts from linear space to SRGB space
-    fn to_egui(self) -> ColorImage;
+    /// Also converts from linear space to SRGB space.
+    ///
+    /// The image is only borrowed, so that the renderer can keep drawing into the same one
+    fn to_egui(&self) -> ColorImage;
 }
 
 impl ImageExt for Image {
-    fn to_egui(mut self) -> ColorImage {
+    fn to_egui(&self) -> ColorImage {
         profile_function!();
 
-        // TODO: I may be doing something wrong here,
-        //  maybe should be using `ecolor::rgba::Rgba` not `ecolor::ecolor32::Color32`.
-        //  Apparently it's an
-        {
-            profile_scope!("correct_gamma");
-            const GAMMA: Channel = 2.2;
-            const INV_GAMMA: Channel = 1.0 / GAMMA;
-
-            // Gamma correction is per-channel, not per-pixel
-            self.deref_mut().into_par_iter().for_each(|c| *c = c.powf(INV_GAMMA));
-        }
         // TODO: Pool the images?
         let mut output = {
             profile_scope!("alloc_output");
@@ -36,12 +25,20 @@ impl ImageExt for Image {
             }
         };
 
-        // Convert each pixel into array of u8 channels and write to output
+        // Convert each pixel from linear to sRGB, into an array of u8 channels, and write to output
+        // TODO: I may be doing something wrong here,
+        //  maybe should be using `ecolor::rgba::Rgba` not `ecolor::ecolor32::Color32`.
+        //  Apparently it's an
         {
             profile_scope!("convert_channels_u8");
-            self.indexed_iter().for_each(|((x, y), col)| {
-                let [r, g, b] = col.0.map(|c| (c * 255.0) as u8);
-                output[(x, y)] = Color32::from_rgb(r, g, b)
+            let width = self.width();
+            output.pixels.par_iter_mut().enumerate().for_each(|(i, px)| {
+                // The egui image is stored row by row
+                let [r, g, b] = self[(i % width, i / width)]
+                    .linear_to_srgb()
+                    .0
+                    .map(|c| (c * 255.0).round() as u8);
+                *px = Color32::from_rgb(r, g, b)
             });
         };
 
@@ -1,6 +1,12 @@
+use rayna_engine::core::job::{JobId, JobInfo};
+use rayna_engine::render::compare::ComparisonMetrics;
+use rayna_engine::render::progress::RenderProgress;
+use rayna_engine::render::render::RenderStats;
 use rayna_engine::render::render_opts::RenderOpts;
 use rayna_engine::scene::camera::Camera;
 use rayna_engine::scene::StandardScene;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 
 /// A message sent by the UI to the worker
 #[derive(Debug, Clone)]
@@ -8,8 +14,79 @@ pub(crate) enum MessageToWorker {
     SetRenderOpts(RenderOpts),
     SetScene(StandardScene),
     SetCamera(Camera),
+    /// Changes which image the worker sends to be displayed
+    SetDisplay(DisplayChannel),
+    /// Requests a one-shot, high-resolution render of the current scene and camera, which is saved to disk.
+    ///
+    /// This is rendered separately to the interactive session, so doesn't disturb the accumulation buffer.
+    Snapshot {
+        /// The multiplier for the render resolution, relative to the current render options
+        scale: NonZeroUsize,
+        /// How many frames to accumulate before saving
+        frames: NonZeroUsize,
+        /// Where to save the snapshot to
+        path: PathBuf,
+    },
+    /// Requests a comparison between renders made with the `baseline` options and the current options,
+    /// of the current scene and camera.
+    ///
+    /// The baseline is rendered at the same resolution as the current options, so that the renders can be compared.
+    Compare {
+        baseline: RenderOpts,
+        /// How many frames to accumulate for each of the renders
+        frames: NonZeroUsize,
+    },
+    /// Cancels the frame that the worker is in the middle of rendering, so that it can react to the messages sent before
+    /// this one straight away, instead of finishing a frame that is already out of date.
+    ///
+    /// The worker can't receive messages while it is rendering, so this is acted on as soon as it is
+    /// [sent](crate::integration::Integration::send_message), through a
+    /// [CancelToken](rayna_engine::render::cancel::CancelToken)
+    CancelRender,
+    /// Requests cancellation of a background job
+    CancelJob(JobId),
+    /// Removes all the finished background jobs from the job list
+    ClearFinishedJobs,
+}
+
+/// Which image the worker sends to the UI to be displayed
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum DisplayChannel {
+    /// The rendered image itself
+    #[default]
+    Beauty,
+    /// A [heatmap](rayna_engine::render::variance::heatmap) of how converged each pixel is. This needs
+    /// [RenderOpts::variance] to be enabled, otherwise the rendered image is shown instead
+    Variance,
+}
+
+impl DisplayChannel {
+    pub const ALL: [Self; 2] = [Self::Beauty, Self::Variance];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Beauty => "Beauty",
+            Self::Variance => "Variance",
+        }
+    }
 }
 
 /// A message sent from the worker, to the UI
 #[derive(Clone, Debug)]
-pub(crate) enum MessageToUi {}
+pub(crate) enum MessageToUi {
+    /// A snapshot requested by [MessageToWorker::Snapshot] was saved successfully
+    SnapshotSaved(PathBuf),
+    /// A snapshot requested by [MessageToWorker::Snapshot] couldn't be saved
+    SnapshotFailed { path: PathBuf, error: String },
+    /// A comparison requested by [MessageToWorker::Compare] finished
+    ComparisonFinished(ComparisonMetrics),
+    /// The current state of all the worker's background jobs
+    JobsUpdated(Vec<JobInfo>),
+    /// How far the worker is through rendering the current frame. This is only sent every few percent, rather than for
+    /// every tile
+    Progress(RenderProgress),
+    /// The interactive render reached its [target samples](rayna_engine::render::render_opts::RenderOpts::target_samples)
+    /// or [time limit](rayna_engine::render::render_opts::RenderOpts::max_time), so the worker has stopped rendering
+    /// until something changes. Contains the stats of the final render
+    RenderFinished(RenderStats),
+}
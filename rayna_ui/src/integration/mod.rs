@@ -7,6 +7,8 @@ use crate::integration::message::{MessageToUi, MessageToWorker};
 use crate::integration::worker::BgWorker;
 use crate::targets::INTEGRATION;
 use egui::ColorImage;
+use rayna_engine::core::job::JobQueue;
+use rayna_engine::render::cancel::CancelToken;
 use rayna_engine::render::render::Render;
 use rayna_engine::render::render_opts::RenderOpts;
 use rayna_engine::render::renderer::Renderer;
@@ -39,6 +41,8 @@ pub(crate) struct Integration {
     msg_tx: flume::Sender<MessageToWorker>,
     msg_rx: flume::Receiver<MessageToUi>,
     render_rx: flume::Receiver<Render<ColorImage>>,
+    /// Cancels the worker's current frame, for [MessageToWorker::CancelRender]
+    cancel_render: CancelToken,
     worker_handle: WorkerHandle,
 }
 
@@ -73,17 +77,20 @@ impl Integration {
         let (rend_tx, rend_rx) = flume::bounded::<Render<ColorImage>>(1);
 
         trace!(target: INTEGRATION, "creating worker");
+        let renderer = Renderer::new_from(
+            initial_scene.clone(),
+            initial_camera.clone(),
+            initial_render_opts.clone(),
+            6,
+        )
+        .expect("failed to create renderer");
+        let cancel_render = renderer.cancel_token();
         let worker = BgWorker {
             msg_rx: work_rx,
             msg_tx: work_tx,
             render_tx: rend_tx,
-            renderer: Renderer::new_from(
-                initial_scene.clone(),
-                initial_camera.clone(),
-                initial_render_opts.clone(),
-                6,
-            )
-            .expect("failed to create renderer"),
+            renderer,
+            jobs: JobQueue::new(1).map_err(IntegrationError::from)?,
         };
         let thread = worker.start_bg_thread().map_err(IntegrationError::from)?;
 
@@ -91,6 +98,7 @@ impl Integration {
             msg_tx: main_tx,
             msg_rx: main_rx,
             render_rx: rend_rx,
+            cancel_render,
             worker_handle: WorkerHandle::Running(thread),
         })
     }
@@ -130,9 +138,15 @@ impl Integration {
 
         self.ensure_worker_alive()?;
 
+        // Sent after the messages it's reacting to, so they are waiting for the worker once the frame is cancelled
+        let cancel = matches!(message, MessageToWorker::CancelRender);
         self.msg_tx
             .send(message)
-            .map_err(|_| IntegrationError::TxChannelDisconnected)
+            .map_err(|_| IntegrationError::TxChannelDisconnected)?;
+        if cancel {
+            self.cancel_render.cancel();
+        }
+        Ok(())
     }
 
     // endregion
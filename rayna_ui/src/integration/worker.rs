@@ -1,31 +1,46 @@
 use crate::ext::img_ext::ImageExt;
-use crate::integration::message::{MessageToUi, MessageToWorker};
+use crate::integration::message::{DisplayChannel, MessageToUi, MessageToWorker};
 use crate::targets::BG_WORKER;
 use egui::ColorImage;
 use puffin::{profile_function, profile_scope};
+use rayna_engine::core::job::{JobError, JobHandle, JobQueue};
 use rayna_engine::core::profiler;
+use rayna_engine::core::types::{Image, Number};
 use rayna_engine::material::MaterialInstance;
 use rayna_engine::mesh::MeshInstance;
 use rayna_engine::object::ObjectInstance;
+use rayna_engine::render::compare::compare_renders;
+use rayna_engine::render::output::{self, OutputFormat};
+use rayna_engine::render::progress::RenderProgress;
 use rayna_engine::render::render::Render;
+use rayna_engine::render::render_opts::RenderOpts;
 use rayna_engine::render::renderer::Renderer;
+use rayna_engine::render::variance;
 use rayna_engine::skybox::SkyboxInstance;
 use rayna_engine::texture::TextureInstance;
+use std::convert::identity;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::thread::JoinHandle;
 use std::time::Duration;
 use tracing::{info, trace, warn};
 
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub(super) struct BgWorker {
     /// Sender for messages from the worker, back to the UI
     pub msg_tx: flume::Sender<MessageToUi>,
     /// Receiver for messages from the UI, to the worker
     pub msg_rx: flume::Receiver<MessageToWorker>,
     pub render_tx: flume::Sender<Render<ColorImage>>,
-    pub renderer:
-        Renderer<ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>, SkyboxInstance, rand::rngs::SmallRng>,
+    pub renderer: WorkerRenderer,
+    /// Queue for long-running jobs, so they don't block the render loop
+    pub jobs: JobQueue,
 }
 
+/// The type of renderer used by the [BgWorker]
+pub(super) type WorkerRenderer =
+    Renderer<ObjectInstance<MeshInstance, MaterialInstance<TextureInstance>>, SkyboxInstance, rand::rngs::SmallRng>;
+
 impl BgWorker {
     /// Starts the worker in a background thread, returning the thread handle
     pub fn start_bg_thread(self) -> std::io::Result<JoinHandle<()>> {
@@ -45,7 +60,16 @@ impl BgWorker {
             msg_rx,
             render_tx,
             mut renderer,
+            jobs,
         } = self;
+        let mut last_job_infos = vec![];
+        // Whether the last render sent to the UI was already finished, so there's nothing new to render
+        let mut finished = false;
+        // Reused for every frame, so that the renderer doesn't have to allocate a new image each time
+        let mut frame = Image::new_blank(0, 0);
+        // Which image to send to the UI, and where the variance is read into when it's shown
+        let mut display = DisplayChannel::Beauty;
+        let mut variance = Image::new_blank(0, 0);
 
         loop {
             profiler::renderer::lock().new_frame();
@@ -66,19 +90,80 @@ impl BgWorker {
                         MessageToWorker::SetRenderOpts(o) => {
                             trace!(target: BG_WORKER, ?o, "got render opts from ui");
                             renderer.set_options(o);
+                            finished = false;
                         }
                         MessageToWorker::SetScene(s) => {
                             trace!(target: BG_WORKER, ?s, "got scene from ui");
                             renderer.set_scene(s);
+                            finished = false;
                         }
                         MessageToWorker::SetCamera(c) => {
                             trace!(target: BG_WORKER, ?c, "got scene from ui");
                             renderer.set_camera(c);
+                            finished = false;
+                        }
+                        MessageToWorker::SetDisplay(d) => {
+                            trace!(target: BG_WORKER, ?d, "got display channel from ui");
+                            display = d;
+                            // The last frame was of the other channel
+                            finished = false;
+                        }
+                        MessageToWorker::Snapshot { scale, frames, path } => {
+                            trace!(
+                                target: BG_WORKER,
+                                scale = scale.get(),
+                                frames = frames.get(),
+                                ?path,
+                                "got snapshot request from ui"
+                            );
+                            Self::submit_snapshot(&jobs, &renderer, scale, frames, path, msg_tx.clone());
+                        }
+                        MessageToWorker::Compare { baseline, frames } => {
+                            trace!(
+                                target: BG_WORKER,
+                                ?baseline,
+                                frames = frames.get(),
+                                "got comparison request from ui"
+                            );
+                            Self::submit_comparison(&jobs, &renderer, baseline, frames, msg_tx.clone());
+                        }
+                        MessageToWorker::CancelRender => {
+                            // Already cancelled when it was sent, and the frame has been thrown away since
+                            trace!(target: BG_WORKER, "got render cancel request from ui");
                         }
+                        MessageToWorker::CancelJob(id) => {
+                            trace!(target: BG_WORKER, id, "got job cancel request from ui");
+                            match jobs.get(id) {
+                                Some(job) => job.cancel(),
+                                None => warn!(target: BG_WORKER, id, "tried to cancel job that doesn't exist"),
+                            }
+                        }
+                        MessageToWorker::ClearFinishedJobs => {
+                            trace!(target: BG_WORKER, "got clear finished jobs request from ui");
+                            jobs.clear_finished();
+                        }
+                    }
+                }
+            }
+
+            {
+                profile_scope!("send_job_status");
+                // Only send when something changed, so we don't flood the channel
+                let job_infos = jobs.jobs().iter().map(JobHandle::info).collect::<Vec<_>>();
+                if job_infos != last_job_infos {
+                    if let Err(_) = msg_tx.send(MessageToUi::JobsUpdated(job_infos.clone())) {
+                        warn!(target: BG_WORKER, "failed to send job status to UI")
                     }
+                    last_job_infos = job_infos;
                 }
             }
 
+            if finished {
+                // Don't burn CPU re-sending the same image; just keep checking for messages
+                std::thread::sleep(Duration::from_millis(50));
+                continue;
+            }
+
             {
                 profile_scope!("waiting_channel_empty");
                 // UI hasn't received the last message we sent
@@ -93,14 +178,49 @@ impl BgWorker {
 
             let render_result = {
                 profile_scope!("make_render");
-                let render = renderer.render();
+                let stats = renderer.render_into(&mut frame, |progress: RenderProgress| {
+                    // Every tile would flood the channel, so only every few percent
+                    let step = (progress.tiles_total / 20).max(1);
+                    if progress.tiles_done % step == 0 || progress.is_done() {
+                        if let Err(_) = msg_tx.send(MessageToUi::Progress(progress)) {
+                            warn!(target: BG_WORKER, "failed to send render progress to UI")
+                        }
+                    }
+                });
+                if stats.cancelled {
+                    // Out of date, so go straight back to the messages that it was cancelled for
+                    trace!(target: BG_WORKER, "render cancelled, not sending frame");
+                    continue;
+                }
 
+                // The UI only shows the one image. The variance isn't there until the renderer has been told to track it
+                let img = match display {
+                    DisplayChannel::Variance if renderer.read_variance(&mut variance) => {
+                        variance::heatmap(&variance).to_egui()
+                    }
+                    _ => frame.to_egui(),
+                };
                 Render {
-                    img: render.img.to_egui(),
-                    stats: render.stats,
+                    img,
+                    stats,
+                    aovs: vec![],
+                    alpha: None,
                 }
             };
 
+            if render_result.stats.finished {
+                info!(
+                    target: BG_WORKER,
+                    passes = render_result.stats.accum_frames,
+                    samples = render_result.stats.accum_samples,
+                    "render finished, pausing until something changes"
+                );
+                finished = true;
+                if let Err(_) = msg_tx.send(MessageToUi::RenderFinished(render_result.stats)) {
+                    warn!(target: BG_WORKER, "failed to send render finished message to UI")
+                }
+            }
+
             {
                 profile_scope!("send_frame");
 
@@ -112,4 +232,112 @@ impl BgWorker {
 
         info!(target: BG_WORKER, "BgWorker thread exit");
     }
+
+    /// Submits a job that renders a high-resolution snapshot of the renderer's current scene and camera.
+    ///
+    /// The renderer is cloned, so the interactive session (and its accumulation) is left untouched,
+    /// and the snapshot is rendered as a background job so the UI keeps updating in the meantime.
+    /// Once complete, the result is reported back to the UI with a [MessageToUi].
+    fn submit_snapshot(
+        jobs: &JobQueue,
+        renderer: &WorkerRenderer,
+        scale: NonZeroUsize,
+        frames: NonZeroUsize,
+        path: PathBuf,
+        msg_tx: flume::Sender<MessageToUi>,
+    ) -> JobHandle {
+        let mut renderer = renderer.clone();
+        let mut opts = *renderer.options();
+        opts.width = opts.width.saturating_mul(scale);
+        opts.height = opts.height.saturating_mul(scale);
+        // Otherwise the first few frames would be low-resolution previews, instead of passes
+        opts.progressive_preview = false;
+        renderer.set_options(opts);
+
+        jobs.submit(format!("snapshot {}", path.display()), move |ctx| {
+            profile_function!();
+            info!(
+                target: BG_WORKER,
+                width = opts.width.get(),
+                height = opts.height.get(),
+                frames = frames.get(),
+                ?path,
+                "rendering snapshot"
+            );
+
+            let mut render = None;
+            for frame in 0..frames.get() {
+                ctx.check_cancelled()?;
+                render = Some(renderer.render());
+                ctx.set_progress((frame + 1) as Number / frames.get() as Number);
+            }
+            let render = render.expect("`frames` is non-zero");
+
+            // Keep the transparency, unless the format can't store it
+            let saved = OutputFormat::from_path(&path).and_then(|format| match &render.alpha {
+                Some(alpha) if format.supports_alpha() => {
+                    output::save_with_alpha(&render.img, alpha, &path, format, identity)
+                }
+                _ => output::save(&render.img, &path, format, identity),
+            });
+            let (msg, result) = match saved {
+                Ok(()) => {
+                    info!(target: BG_WORKER, ?path, "saved snapshot");
+                    (MessageToUi::SnapshotSaved(path), Ok(()))
+                }
+                Err(err) => {
+                    warn!(target: BG_WORKER, ?err, ?path, "failed to save snapshot");
+                    let error = err.to_string();
+                    (
+                        MessageToUi::SnapshotFailed {
+                            path,
+                            error: error.clone(),
+                        },
+                        Err(JobError::Failed(error)),
+                    )
+                }
+            };
+
+            if let Err(_) = msg_tx.send(msg) {
+                warn!(target: BG_WORKER, "failed to send snapshot result to UI")
+            }
+            result
+        })
+    }
+
+    /// Submits a job that compares renders of the current scene and camera, made with the `baseline` options
+    /// and the renderer's current options.
+    ///
+    /// Like [Self::submit_snapshot()], the renderer is cloned so the interactive session is left untouched.
+    /// Once complete, the metrics are reported back to the UI with [MessageToUi::ComparisonFinished].
+    fn submit_comparison(
+        jobs: &JobQueue,
+        renderer: &WorkerRenderer,
+        mut baseline: RenderOpts,
+        frames: NonZeroUsize,
+        msg_tx: flume::Sender<MessageToUi>,
+    ) -> JobHandle {
+        let renderer = renderer.clone();
+        let current = *renderer.options();
+        // Can only compare images of the same size
+        baseline.width = current.width;
+        baseline.height = current.height;
+
+        jobs.submit("compare settings", move |ctx| {
+            profile_function!();
+            info!(target: BG_WORKER, ?baseline, ?current, frames = frames.get(), "comparing settings");
+
+            // The renders can't be interrupted once they've started, so this is the last chance to cancel
+            ctx.check_cancelled()?;
+            let comparison = compare_renders(&renderer, baseline, current, frames)
+                .map_err(|err| JobError::Failed(err.to_string()))?
+                .comparison;
+            info!(target: BG_WORKER, metrics = ?comparison.metrics, "comparison finished");
+
+            if let Err(_) = msg_tx.send(MessageToUi::ComparisonFinished(comparison.metrics)) {
+                warn!(target: BG_WORKER, "failed to send comparison result to UI")
+            }
+            Ok(())
+        })
+    }
 }
@@ -8,6 +8,9 @@ pub const APP_NAME: &'static str = "rayna";
 pub const UNIT_PX: &'static str = " px";
 pub const UNIT_DEG: &'static str = " °";
 pub const UNIT_LEN: &'static str = " m";
+pub const UNIT_EV: &'static str = " EV";
+pub const UNIT_MM: &'static str = " mm";
+pub const UNIT_SEC: &'static str = " s";
 
 pub const DRAG_SLOW: Number = 0.1;
 pub const DRAG_NORM: Number = 1.0;